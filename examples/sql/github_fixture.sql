@@ -0,0 +1,9 @@
+{{ sink(name="postgres_sink") }}
+{{ schedule("0 */3 * * * *")  }}
+
+
+SELECT
+    id,
+    full_name,
+    stargazers_count
+FROM {{ use_source("github_repos_fixture") }} AS t;
@@ -0,0 +1,193 @@
+//! Liveness and readiness probes for the long-running scheduler.
+//!
+//! Enabled with `--health-addr`. A small HTTP/1.1 listener answers:
+//!
+//! - `GET /healthz` — `200` while the process is alive.
+//! - `GET /readyz` — `200` once the config is loaded and the scheduler has
+//!   started, `503` before that (or while a reload is rebuilding it). The body
+//!   lists every module with its last success, last failure, and last error.
+
+use std::collections::BTreeMap;
+use std::net::SocketAddr;
+use std::sync::{Arc, RwLock};
+
+use chrono::{DateTime, Utc};
+use serde::Serialize;
+use serde_json::json;
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::net::{TcpListener, TcpStream};
+use tracing::{debug, info};
+
+use crate::errors::Result;
+
+/// Outcome history of one scheduled module.
+#[derive(Debug, Clone, Default, Serialize, PartialEq)]
+pub struct ModuleStatus {
+    pub last_success: Option<DateTime<Utc>>,
+    pub last_failure: Option<DateTime<Utc>>,
+    pub last_error: Option<String>,
+}
+
+impl ModuleStatus {
+    /// True when the module has succeeded and not failed since.
+    pub fn last_run_succeeded(&self) -> bool {
+        match (self.last_success, self.last_failure) {
+            (Some(success), Some(failure)) => success >= failure,
+            (Some(_), None) => true,
+            (None, _) => false,
+        }
+    }
+}
+
+#[derive(Debug, Default)]
+struct Inner {
+    config_loaded: bool,
+    scheduler_started: bool,
+    modules: BTreeMap<String, ModuleStatus>,
+}
+
+/// Shared state behind the probes, updated by the scheduler and its jobs.
+///
+/// Cloning is cheap; all clones see the same state.
+#[derive(Debug, Clone, Default)]
+pub struct HealthState {
+    inner: Arc<RwLock<Inner>>,
+}
+
+impl HealthState {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    fn write(&self) -> std::sync::RwLockWriteGuard<'_, Inner> {
+        self.inner.write().unwrap_or_else(|e| e.into_inner())
+    }
+
+    fn read(&self) -> std::sync::RwLockReadGuard<'_, Inner> {
+        self.inner.read().unwrap_or_else(|e| e.into_inner())
+    }
+
+    /// Marks the configuration as loaded (or not, e.g. mid-reload).
+    pub fn set_config_loaded(&self, loaded: bool) {
+        self.write().config_loaded = loaded;
+    }
+
+    /// Marks the scheduler as running.
+    pub fn set_scheduler_started(&self, started: bool) {
+        self.write().scheduler_started = started;
+    }
+
+    /// Registers a module so it is listed before its first run.
+    pub fn register_module(&self, module: &str) {
+        self.write().modules.entry(module.to_string()).or_default();
+    }
+
+    pub fn record_success(&self, module: &str) {
+        let mut inner = self.write();
+        let status = inner.modules.entry(module.to_string()).or_default();
+        status.last_success = Some(Utc::now());
+    }
+
+    pub fn record_failure(&self, module: &str, error: impl ToString) {
+        let mut inner = self.write();
+        let status = inner.modules.entry(module.to_string()).or_default();
+        status.last_failure = Some(Utc::now());
+        status.last_error = Some(error.to_string());
+    }
+
+    /// True once the config is loaded and the scheduler is running.
+    pub fn is_ready(&self) -> bool {
+        let inner = self.read();
+        inner.config_loaded && inner.scheduler_started
+    }
+
+    /// Status of `module`, if it has been registered or has run.
+    pub fn module(&self, module: &str) -> Option<ModuleStatus> {
+        self.read().modules.get(module).cloned()
+    }
+
+    /// JSON body served by `/readyz`.
+    pub fn readiness_report(&self) -> serde_json::Value {
+        let inner = self.read();
+        json!({
+            "ready": inner.config_loaded && inner.scheduler_started,
+            "config_loaded": inner.config_loaded,
+            "scheduler_started": inner.scheduler_started,
+            "modules": inner.modules,
+        })
+    }
+}
+
+/// Binds `addr` and serves the probes in a background task.
+///
+/// Returns the bound address, which differs from `addr` when it asks for
+/// port 0.
+///
+/// # Errors
+///
+/// Returns an error if the address cannot be bound.
+pub async fn spawn_health_server(addr: SocketAddr, state: HealthState) -> Result<SocketAddr> {
+    let listener = TcpListener::bind(addr).await?;
+    let bound = listener.local_addr()?;
+    info!("🩺 Health endpoints listening on http://{bound} (/healthz, /readyz)");
+
+    tokio::spawn(async move {
+        loop {
+            match listener.accept().await {
+                Ok((socket, _)) => {
+                    let state = state.clone();
+                    tokio::spawn(async move {
+                        if let Err(e) = handle(socket, &state).await {
+                            debug!("health probe connection failed: {}", e);
+                        }
+                    });
+                }
+                Err(e) => debug!("health listener accept failed: {}", e),
+            }
+        }
+    });
+
+    Ok(bound)
+}
+
+async fn handle(mut socket: TcpStream, state: &HealthState) -> std::io::Result<()> {
+    let mut buf = [0u8; 1024];
+    let n = socket.read(&mut buf).await?;
+    let request = String::from_utf8_lossy(&buf[..n]);
+    let mut parts = request
+        .lines()
+        .next()
+        .unwrap_or_default()
+        .split_whitespace();
+    let method = parts.next().unwrap_or_default();
+    let path = parts.next().unwrap_or_default();
+    let path = path.split('?').next().unwrap_or_default();
+
+    let (status, body) = match (method, path) {
+        ("GET" | "HEAD", "/healthz") => ("200 OK", json!({"status": "ok"})),
+        ("GET" | "HEAD", "/readyz") => {
+            let status = if state.is_ready() {
+                "200 OK"
+            } else {
+                "503 Service Unavailable"
+            };
+            (status, state.readiness_report())
+        }
+        ("GET" | "HEAD", _) => ("404 Not Found", json!({"error": "not found"})),
+        _ => (
+            "405 Method Not Allowed",
+            json!({"error": "method not allowed"}),
+        ),
+    };
+
+    let body = body.to_string();
+    let head = format!(
+        "HTTP/1.1 {status}\r\ncontent-type: application/json\r\ncontent-length: {}\r\nconnection: close\r\n\r\n",
+        body.len()
+    );
+    socket.write_all(head.as_bytes()).await?;
+    if method != "HEAD" {
+        socket.write_all(body.as_bytes()).await?;
+    }
+    socket.shutdown().await
+}
@@ -4,25 +4,67 @@
 //! for extracting data from REST APIs, transforming it with SQL, and loading it
 //! into data warehouses.
 
+pub mod health;
+pub mod summary;
+
+use std::collections::{HashMap, HashSet};
+use std::net::SocketAddr;
+use std::path::PathBuf;
 use std::sync::{Arc, Mutex};
-use std::time::Instant;
+use std::time::{Duration, Instant};
 
+use chrono::NaiveDate;
 use clap::Parser;
+use datafusion::arrow::datatypes::SchemaRef;
 use tokio_cron_scheduler::{Job, JobScheduler};
-use tracing::{debug, info, instrument, warn};
+use tracing::{debug, info, instrument, warn, Instrument};
 
+use crate::config::dag::ModuleGraph;
+use crate::config::effective::effective_config;
 use crate::config::load_config_from_path;
+use crate::config::remote::{is_remote, RemoteCache};
+use crate::config::schedule::{resolve_schedules, OverlapGuard};
 use crate::config::templating::{
-    build_env_with_captures, list_sql_templates, render_one, RenderCapture,
+    build_env_with_captures, list_sql_templates, render_one, substitute_source, RenderCapture,
+    RenderedSql, SinkCapture,
 };
 use crate::errors::{self, Result};
-use crate::http::Http;
-use crate::pipeline::run::{run_fetch, FetchOpts, FetchRequest, QueryConfig, WriteConfig};
-use crate::pipeline::sink::{MakeWriter, WriterOpts};
-use crate::pipeline::Config;
+use crate::http::capture::{Capture, DEFAULT_CAPTURE_MAX_BYTES};
+use crate::http::fetcher::{FetchStats, RequestTemplate, ResponseFormat};
+use crate::http::oauth2::ClientCredentials;
+use crate::http::preflight::Preflight;
+use crate::http::signing::{HmacSigner, QueryParamAuth, RequestSigner, SignerChain};
+use crate::http::{Http, HttpPool, DEFAULT_USER_AGENT};
+use crate::pipeline::backfill::{parse_window, Backfill, BackfillMode};
+use crate::pipeline::checkpoint::Resume;
+use crate::pipeline::conditional::Conditional;
+use crate::pipeline::empty::{EmptyGuardWriter, OnEmpty};
+use crate::pipeline::grpc::GrpcRequest;
+use crate::pipeline::limits::{Limits, LimitsConfig};
+use crate::pipeline::run::{
+    preview_schema, resolve_path_params, run_fetch_all, FetchOpts, FetchRequest, QueryConfig,
+    WriteConfig,
+};
+use crate::pipeline::sink::{Hook, MakeWriter, WriterOpts};
+use crate::pipeline::split::{SourceSplit, SplitWriter};
 use crate::pipeline::SinkConn;
-use crate::pipeline::Source;
-use crate::writer::WriteMode;
+use crate::pipeline::{Config, TablePolicy};
+use crate::pipeline::{Header, SigningConfig, Source, SourceAuth, SourceKind};
+use crate::utils::datafusion_ext::get_shared_context;
+use crate::utils::execution::ExecutionOpts;
+use crate::utils::flatten::FlattenConfig;
+use crate::utils::lenient_json::LenientJsonConfig;
+use crate::utils::progress::{ProgressOpts, DEFAULT_PROGRESS_INTERVAL_SECS};
+use crate::utils::secrets::resolve_config_secrets;
+use crate::utils::table_provider::register_lookups;
+use crate::utils::template;
+use crate::utils::transform::RecordTransform;
+use crate::writer::columns::{ColumnFilterWriter, ColumnSelection};
+use crate::writer::fanout::{FanOutWriter, SinkErrorPolicy, SinkWriter};
+use crate::writer::post_check::PostCheck;
+use crate::writer::{DataWriter, WriteMode};
+use health::{spawn_health_server, HealthState};
+use summary::{render_table, RunSummary, SummaryRow};
 
 /// Default number of concurrent requests for fetching data.
 const CONCURRENCY: usize = 5;
@@ -33,6 +75,9 @@ const DEFAULT_PAGE_SIZE: usize = 50;
 /// Batch size for fetching records.
 const FETCH_BATCH_SIZE: usize = 256;
 
+/// Rows a writer buffers and writes per batch.
+const WRITE_BATCH_SIZE: usize = 50;
+
 /// Command-line interface structure for the Apitap ETL tool.
 #[derive(Parser, Debug)]
 #[command(
@@ -44,7 +89,8 @@ HTTP-to-warehouse ETL powered by DataFusion.\n\n\
 Resources:\n  • Modules: Jinja-like SQL templates that declare {{ sink(...) }} and {{ use_source(...) }}\n  • YAML config: defines sources (HTTP + pagination) and targets (warehouses)\n  • Execution: fetch JSON → DataFusion SQL → write via sink-specific writers"
 )]
 pub struct Cli {
-    /// Directory containing SQL module templates.
+    /// Directory containing SQL module templates, or an object store URL
+    /// (`s3://...`) to download them from.
     #[arg(
         long = "modules",
         short = 'm',
@@ -53,7 +99,8 @@ pub struct Cli {
     )]
     pub modules: String,
 
-    /// Path to the YAML configuration file.
+    /// Path to the YAML configuration file, or an `https://` or object
+    /// store URL to download it from.
     #[arg(
         long = "yaml-config",
         short = 'y',
@@ -71,6 +118,311 @@ pub struct Cli {
     /// Example: info,warn,debug
     #[arg(long = "log-level")]
     pub log_level: Option<String>,
+
+    /// Number of concurrent page requests per source.
+    ///
+    /// Sources can override this with `concurrency` in the YAML.
+    #[arg(
+        long = "concurrency",
+        value_name = "N",
+        default_value_t = CONCURRENCY,
+        value_parser = parse_positive
+    )]
+    pub concurrency: usize,
+
+    /// Page size for paginated API requests.
+    ///
+    /// Sources can override this with `page_size` in the YAML.
+    #[arg(
+        long = "page-size",
+        value_name = "N",
+        default_value_t = DEFAULT_PAGE_SIZE,
+        value_parser = parse_positive
+    )]
+    pub page_size: usize,
+
+    /// Number of records buffered per fetch batch.
+    ///
+    /// Sources can override this with `fetch_batch_size` in the YAML.
+    #[arg(
+        long = "fetch-batch-size",
+        value_name = "N",
+        default_value_t = FETCH_BATCH_SIZE,
+        value_parser = parse_positive
+    )]
+    pub fetch_batch_size: usize,
+
+    /// Run every module once, then exit instead of starting the scheduler.
+    ///
+    /// Exits with status 1 if any module failed.
+    #[arg(long = "once")]
+    pub once: bool,
+
+    /// Fetch at most N records per source, across all of its pages, run
+    /// every module once on them, and exit. Use it to try a new source or
+    /// module end to end in seconds; the destinations get only the sample.
+    #[arg(
+        long = "sample",
+        value_name = "N",
+        value_parser = parse_positive,
+        conflicts_with = "backfill"
+    )]
+    pub sample: Option<usize>,
+
+    /// Fetch the first page for MODULE's source, print the inferred Arrow
+    /// schema, and exit without writing anything.
+    ///
+    /// MODULE is the template path relative to `--modules` (e.g. `orders.sql`).
+    #[arg(long = "infer-schema", value_name = "MODULE")]
+    pub infer_schema: Option<String>,
+
+    /// Serve `/healthz` and `/readyz` probes on ADDR while the scheduler runs.
+    ///
+    /// Example: 0.0.0.0:8080
+    #[arg(long = "health-addr", value_name = "ADDR")]
+    pub health_addr: Option<SocketAddr>,
+
+    /// Print the effective configuration (defaults applied, `${ENV}`
+    /// resolved, secrets masked) and exit. Nothing is fetched or written.
+    #[arg(
+        long = "print-config",
+        value_name = "FORMAT",
+        num_args = 0..=1,
+        default_missing_value = "yaml"
+    )]
+    pub print_config: Option<ConfigFormat>,
+
+    /// Print the SQL each module runs (templates rendered, the source name
+    /// replaced by its table) and exit. Nothing is fetched or written.
+    ///
+    /// With MODULE (relative to `--modules`), print only that module.
+    #[arg(
+        long = "print-sql",
+        value_name = "MODULE",
+        num_args = 0..=1,
+        default_missing_value = ""
+    )]
+    pub print_sql: Option<String>,
+
+    /// Seconds between write progress logs during a run; 0 turns them off.
+    #[arg(
+        long = "progress-interval",
+        value_name = "SECS",
+        default_value_t = DEFAULT_PROGRESS_INTERVAL_SECS
+    )]
+    pub progress_interval: u64,
+
+    /// Also log write progress every N rows.
+    #[arg(long = "progress-every-rows", value_name = "N", value_parser = parse_positive)]
+    pub progress_every_rows: Option<usize>,
+
+    /// Delete saved pagination checkpoints at startup so sources with
+    /// `resume` fetch from their first page.
+    #[arg(long = "full-restart")]
+    pub full_restart: bool,
+
+    /// Write every page request (method, URL, masked headers) and response
+    /// body to numbered files in DIR, to debug a source's data shape.
+    #[arg(long = "capture-dir", value_name = "DIR")]
+    pub capture_dir: Option<PathBuf>,
+
+    /// Stop capturing once the files in `--capture-dir` reach N megabytes.
+    #[arg(
+        long = "capture-max-mb",
+        value_name = "N",
+        default_value_t = DEFAULT_CAPTURE_MAX_BYTES / (1024 * 1024),
+        requires = "capture_dir"
+    )]
+    pub capture_max_mb: u64,
+
+    /// Run at most N modules at once; later ones wait for a slot.
+    ///
+    /// Overrides `limits.max_concurrent_modules` in the YAML.
+    #[arg(long = "max-concurrent-modules", value_name = "N", value_parser = parse_positive)]
+    pub max_concurrent_modules: Option<usize>,
+
+    /// Keep at most N HTTP requests in flight across all modules.
+    ///
+    /// Overrides `limits.max_concurrent_requests` in the YAML.
+    #[arg(long = "max-concurrent-requests", value_name = "N", value_parser = parse_positive)]
+    pub max_concurrent_requests: Option<usize>,
+
+    /// Keep going when a module fails: the scheduler skips modules that
+    /// cannot be scheduled instead of exiting. This is already the default
+    /// for `--once` and `--backfill`.
+    #[arg(long = "continue-on-error", conflicts_with = "fail_fast")]
+    pub continue_on_error: bool,
+
+    /// Stop at the first failing module: `--once` and `--backfill` skip the
+    /// modules after it. This is already the default for the scheduler.
+    #[arg(long = "fail-fast")]
+    pub fail_fast: bool,
+
+    /// Run every module once per date window from `--from` through `--to`,
+    /// then exit. Sources read the window with `{{ window_start() }}` and
+    /// `{{ window_end() }}`.
+    ///
+    /// Exits with status 1 if any module failed.
+    #[arg(long = "backfill", requires_all = ["from", "to"])]
+    pub backfill: bool,
+
+    /// First day of the backfill.
+    #[arg(long = "from", value_name = "YYYY-MM-DD", requires = "backfill")]
+    pub from: Option<NaiveDate>,
+
+    /// Last day of the backfill, inclusive.
+    #[arg(long = "to", value_name = "YYYY-MM-DD", requires = "backfill")]
+    pub to: Option<NaiveDate>,
+
+    /// Length of each backfill window: days (`1d`, `30`) or weeks (`2w`).
+    #[arg(
+        long = "window",
+        value_name = "LEN",
+        default_value = "1d",
+        value_parser = parse_backfill_window,
+        requires = "backfill"
+    )]
+    pub window: u32,
+
+    /// `append` adds every window to the destination; `replace` truncates
+    /// it before the first window.
+    #[arg(
+        long = "backfill-mode",
+        value_name = "MODE",
+        default_value = "append",
+        value_parser = parse_backfill_mode,
+        requires = "backfill"
+    )]
+    pub backfill_mode: BackfillMode,
+}
+
+impl Cli {
+    /// The `--backfill` options, or `None` without `--backfill`.
+    pub fn backfill(&self) -> Option<Backfill> {
+        if !self.backfill {
+            return None;
+        }
+        Some(Backfill {
+            from: self.from?,
+            to: self.to?,
+            window_days: self.window,
+            mode: self.backfill_mode,
+        })
+    }
+}
+
+/// Output format for `--print-config`.
+#[derive(clap::ValueEnum, Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ConfigFormat {
+    Yaml,
+    Json,
+}
+
+/// What a run does with the remaining modules after one fails.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum OnModuleError {
+    /// Run the other modules and report every failure at the end.
+    Continue,
+    /// Stop at the first failure.
+    FailFast,
+}
+
+/// Parses a CLI value that must be at least 1.
+fn parse_positive(s: &str) -> std::result::Result<usize, String> {
+    match s.parse::<usize>() {
+        Ok(0) => Err("must be greater than 0".to_string()),
+        Ok(n) => Ok(n),
+        Err(e) => Err(e.to_string()),
+    }
+}
+
+fn parse_backfill_window(s: &str) -> std::result::Result<u32, String> {
+    parse_window(s).map_err(|e| e.to_string())
+}
+
+fn parse_backfill_mode(s: &str) -> std::result::Result<BackfillMode, String> {
+    s.parse().map_err(|e: errors::ApitapError| e.to_string())
+}
+
+/// Process-wide options for a pipeline run.
+///
+/// Built from [`Cli`] by the binary; library callers can start from
+/// [`RunOptions::default`] and adjust individual fields.
+#[derive(Debug, Clone)]
+pub struct RunOptions {
+    /// Fetch tuning applied to every source unless the source overrides it.
+    pub fetch_opts: FetchOpts,
+    /// Address for the health probe server; disabled when `None`.
+    pub health_addr: Option<SocketAddr>,
+    /// Drop pagination checkpoints before the first run.
+    pub full_restart: bool,
+    /// Process-wide concurrency caps; each one set here overrides the
+    /// config's `limits`.
+    pub limits: LimitsConfig,
+    /// Failure policy; `None` keeps each mode's default: the scheduler fails
+    /// fast while scheduling, one-off runs continue.
+    pub on_module_error: Option<OnModuleError>,
+}
+
+impl Default for RunOptions {
+    fn default() -> Self {
+        Self {
+            fetch_opts: create_fetch_options(),
+            health_addr: None,
+            full_restart: false,
+            limits: LimitsConfig::default(),
+            on_module_error: None,
+        }
+    }
+}
+
+impl RunOptions {
+    /// `fetch_opts` with the process-wide permits for `config`'s `limits`,
+    /// overridden by [`RunOptions::limits`].
+    fn fetch_opts_for(&self, config: &Config) -> FetchOpts {
+        FetchOpts {
+            limits: Limits::new(&self.limits.or(&config.limits)),
+            ..self.fetch_opts.clone()
+        }
+    }
+}
+
+impl From<&Cli> for RunOptions {
+    fn from(cli: &Cli) -> Self {
+        Self {
+            fetch_opts: FetchOpts {
+                concurrency: cli.concurrency,
+                preserve_order: false,
+                default_page_size: cli.page_size,
+                fetch_batch_size: cli.fetch_batch_size,
+                timeout: None,
+                progress: ProgressOpts {
+                    interval: Some(Duration::from_secs(cli.progress_interval)),
+                    every_rows: cli.progress_every_rows.map(|n| n as u64),
+                },
+                execution: ExecutionOpts::default(),
+                capture: cli
+                    .capture_dir
+                    .as_ref()
+                    .map(|dir| Arc::new(Capture::new(dir, cli.capture_max_mb * 1024 * 1024))),
+                limits: Limits::default(),
+                max_records: cli.sample.map(|n| n as u64),
+            },
+            health_addr: cli.health_addr,
+            full_restart: cli.full_restart,
+            limits: LimitsConfig {
+                max_concurrent_modules: cli.max_concurrent_modules,
+                max_concurrent_requests: cli.max_concurrent_requests,
+            },
+            on_module_error: if cli.fail_fast {
+                Some(OnModuleError::FailFast)
+            } else if cli.continue_on_error {
+                Some(OnModuleError::Continue)
+            } else {
+                None
+            },
+        }
+    }
 }
 
 /// Main pipeline execution function.
@@ -80,6 +432,9 @@ pub struct Cli {
 /// 2. Loads configuration from YAML
 /// 3. Processes each template to extract, transform, and load data
 ///
+/// The scheduler runs until Ctrl+C or SIGTERM. On Unix, SIGHUP reloads the
+/// modules and configuration without restarting the process.
+///
 /// # Arguments
 ///
 /// * `root` - Root directory containing SQL templates
@@ -93,16 +448,91 @@ pub struct Cli {
 /// - Source or target resolution fails
 /// - HTTP client creation fails
 /// - Data fetching or writing fails
+pub async fn run_pipeline(root: &str, cfg_path: &str) -> Result<()> {
+    run_pipeline_with(root, cfg_path, &RunOptions::default()).await
+}
+
+/// Runs the pipeline like [`run_pipeline`], using explicit [`RunOptions`].
+///
+/// # Errors
+///
+/// Same as [`run_pipeline`].
 #[instrument(
     name = "run_pipeline",
     err,
     skip_all, // Don't record large args by default
 )]
-pub async fn run_pipeline(root: &str, cfg_path: &str) -> Result<()> {
+pub async fn run_pipeline_with(root: &str, cfg_path: &str, opts: &RunOptions) -> Result<()> {
     log_pipeline_start();
 
     let start_time = Instant::now();
 
+    if opts.full_restart {
+        load_config_from_path(cfg_path)?
+            .checkpoint_store()
+            .clear_all()?;
+        info!("🧹 Pagination checkpoints cleared (--full-restart)");
+    }
+
+    let health = HealthState::new();
+    if let Some(addr) = opts.health_addr {
+        spawn_health_server(addr, health.clone()).await?;
+    }
+    let summary = RunSummary::new();
+
+    let mut scheduler = build_scheduler(root, cfg_path, opts, &health, &summary).await?;
+    scheduler.start().await?;
+    health.set_scheduler_started(true);
+
+    info!("⏰ Scheduler started. Press Ctrl+C (or send SIGTERM) to stop.");
+    info!("═══════════════════════════════════════════════════════════");
+
+    loop {
+        match wait_for_signal().await {
+            Ok(Signal::Shutdown) => {
+                info!("🛑 Shutdown signal received. Stopping scheduler...");
+                health.set_scheduler_started(false);
+                scheduler.shutdown().await?;
+                log_run_summary(&summary.rows());
+                log_pipeline_complete(start_time.elapsed().as_millis());
+                break;
+            }
+            Ok(Signal::Reload) => {
+                info!("🔁 SIGHUP received. Reloading modules and configuration...");
+                // Build the replacement first so a broken config keeps the
+                // current jobs running.
+                match build_scheduler(root, cfg_path, opts, &health, &summary).await {
+                    Ok(next) => {
+                        scheduler.shutdown().await?;
+                        next.start().await?;
+                        scheduler = next;
+                        info!("✅ Reload complete. Scheduler restarted.");
+                    }
+                    Err(err) => {
+                        warn!("Reload failed, keeping previous schedule: {}", err);
+                    }
+                }
+            }
+            Err(err) => {
+                warn!("Unable to listen for shutdown signal: {}", err);
+                break;
+            }
+        }
+    }
+
+    Ok(())
+}
+
+/// Discovers templates, loads configuration, and schedules one job per module.
+///
+/// The returned scheduler has not been started yet.
+async fn build_scheduler(
+    root: &str,
+    cfg_path: &str,
+    opts: &RunOptions,
+    health: &HealthState,
+    summary: &RunSummary,
+) -> Result<JobScheduler> {
     let mut scheduler = JobScheduler::new().await?;
 
     // Discover SQL templates and load configuration
@@ -111,61 +541,592 @@ pub async fn run_pipeline(root: &str, cfg_path: &str) -> Result<()> {
 
     let config = load_config_from_path(cfg_path)?;
     info!("⚙️  Configuration loaded successfully");
+    health.set_config_loaded(true);
 
     // Initialize templating environment
     let capture = Arc::new(Mutex::new(RenderCapture::default()));
     let env = build_env_with_captures(root, &capture);
 
     // Configure fetch options
-    let fetch_opts = create_fetch_options();
+    let fetch_opts = opts.fetch_opts_for(&config);
     debug!(?fetch_opts, "Fetch options configured");
 
+    // Reject unknown dependencies and cycles before any job is scheduled;
+    // templates that fail to render are reported by process_template
+    let declared_deps: Vec<(String, Vec<String>)> = template_names
+        .iter()
+        .map(|name| {
+            let deps = render_one(&env, &capture, name)
+                .map(|r| r.capture.depends_on)
+                .unwrap_or_default();
+            (name.clone(), deps)
+        })
+        .collect();
+    let graph = ModuleGraph::new(&declared_deps, &template_names)?;
+
     // Process each template
+    let total = template_names.len();
+    let mut skipped = Vec::new();
     for (index, name) in template_names.into_iter().enumerate() {
-        process_template(
+        let result = process_template(
             ProcessTemplateConfig {
                 index: index + 1,
-                name,
+                name: name.clone(),
                 env: &env,
                 capture: &capture,
                 config: &config,
                 fetch_opts: &fetch_opts,
+                dependencies: graph
+                    .dependencies(&name)
+                    .into_iter()
+                    .map(String::from)
+                    .collect(),
+                health,
+                summary,
             },
             &mut scheduler,
         )
-        .await?;
+        .await;
+        match result {
+            Ok(()) => {}
+            Err(e) if opts.on_module_error == Some(OnModuleError::Continue) => {
+                warn!("❌ Module '{name}' could not be scheduled: {}", e);
+                summary.record(SummaryRow::new(
+                    &name,
+                    "",
+                    Vec::new(),
+                    Duration::ZERO,
+                    &Err(e),
+                ));
+                skipped.push(name);
+            }
+            Err(e) => return Err(e),
+        }
+    }
+    if !skipped.is_empty() {
+        warn!(
+            "⚠️  {} of {total} module(s) not scheduled (--continue-on-error): {}",
+            skipped.len(),
+            skipped.join(", ")
+        );
     }
 
-    // Start the scheduler
-    scheduler.start().await?;
-    
-    info!("⏰ Scheduler started. Press Ctrl+C to stop.");
-    info!("═══════════════════════════════════════════════════════════");
-    
-    // Wait for shutdown signal (Ctrl+C)
-    match tokio::signal::ctrl_c().await {
-        Ok(()) => {
-            info!("🛑 Shutdown signal received. Stopping scheduler...");
-            scheduler.shutdown().await?;
-            log_pipeline_complete(start_time.elapsed().as_millis());
+    Ok(scheduler)
+}
+
+/// Outcome of one module run by [`run_modules_once`].
+#[derive(Debug)]
+pub struct ModuleRunResult {
+    /// Template path relative to the modules directory.
+    pub module: String,
+    /// Source the module reads; empty if the template failed to render.
+    pub source: String,
+    /// Sinks the module declares, in template order.
+    pub sinks: Vec<String>,
+    pub duration: Duration,
+    /// Fetch totals (records, pages, requests, bytes), or why the module failed.
+    pub result: Result<FetchStats>,
+}
+
+impl ModuleRunResult {
+    pub fn is_success(&self) -> bool {
+        self.result.is_ok()
+    }
+
+    /// This run as a line of the end-of-run summary table.
+    pub fn summary_row(&self) -> SummaryRow {
+        SummaryRow::new(
+            &self.module,
+            &self.source,
+            self.sinks.clone(),
+            self.duration,
+            &self.result,
+        )
+    }
+}
+
+/// Runs each module once, dependencies first (see [`crate::config::dag`]),
+/// and returns one result per module run.
+///
+/// For embedding apitap in another program: no scheduler, health server, or
+/// signal handler is installed, and a failing module doesn't stop the ones
+/// after it unless [`RunOptions::on_module_error`] is
+/// [`OnModuleError::FailFast`]; modules depending on it fail as skipped.
+/// `modules` are template paths relative to `root` (or prefixed with it); an
+/// empty list runs every module under `root`.
+///
+/// # Errors
+///
+/// Returns an error only if the modules cannot be listed, a named module
+/// doesn't exist, the modules' `depends_on` name an unknown module or form a
+/// cycle, or checkpoints cannot be cleared for [`RunOptions::full_restart`].
+/// Module failures are reported in the results.
+///
+/// # Example
+///
+/// ```no_run
+/// use apitap::cmd::{run_modules_once, RunOptions};
+/// use apitap::config::load_config_from_path;
+///
+/// # async fn demo() -> apitap::errors::Result<()> {
+/// let config = load_config_from_path("pipelines.yaml")?;
+/// let results = run_modules_once(
+///     "pipelines",
+///     &["orders.sql".to_string()],
+///     &config,
+///     &RunOptions::default(),
+/// )
+/// .await?;
+/// for run in &results {
+///     match &run.result {
+///         Ok(stats) => println!("{}: {} records", run.module, stats.total_items),
+///         Err(e) => eprintln!("{}: {e}", run.module),
+///     }
+/// }
+/// # Ok(())
+/// # }
+/// ```
+pub async fn run_modules_once(
+    root: &str,
+    modules: &[String],
+    config: &Config,
+    opts: &RunOptions,
+) -> Result<Vec<ModuleRunResult>> {
+    run_modules_once_with(root, modules, config, opts, &|_| {}).await
+}
+
+/// [`run_modules_once`], passing each rendered module's captures through
+/// `adjust` before it runs.
+async fn run_modules_once_with(
+    root: &str,
+    modules: &[String],
+    config: &Config,
+    opts: &RunOptions,
+    adjust: &(dyn Fn(&mut RenderCapture) + Sync),
+) -> Result<Vec<ModuleRunResult>> {
+    let available = list_sql_templates(root)?;
+    let names = if modules.is_empty() {
+        available.clone()
+    } else {
+        modules
+            .iter()
+            .map(|m| resolve_module_name(root, m, &available))
+            .collect::<Result<Vec<_>>>()?
+    };
+
+    if opts.full_restart {
+        config.checkpoint_store().clear_all()?;
+        info!("🧹 Pagination checkpoints cleared (--full-restart)");
+    }
+
+    let capture = Arc::new(Mutex::new(RenderCapture::default()));
+    let env = build_env_with_captures(root, &capture);
+    let fetch_opts = opts.fetch_opts_for(config);
+    if let Some(n) = fetch_opts.max_records {
+        warn!("🧪 Sampled run: each source stops after {n} records; destinations get only the sample");
+    }
+
+    // Every module is rendered first: the run order follows their depends_on
+    let mut rendered: HashMap<String, Result<RenderedSql>> = names
+        .iter()
+        .map(|name| {
+            let result = render_one(&env, &capture, name).map(|mut r| {
+                adjust(&mut r.capture);
+                r
+            });
+            (name.clone(), result)
+        })
+        .collect();
+    let declared_deps: Vec<(String, Vec<String>)> = names
+        .iter()
+        .map(|name| {
+            let deps = match &rendered[name] {
+                Ok(r) => r.capture.depends_on.clone(),
+                Err(_) => Vec::new(),
+            };
+            (name.clone(), deps)
+        })
+        .collect();
+    let graph = ModuleGraph::new(&declared_deps, &available)?;
+    let order: Vec<String> = graph.order().into_iter().map(String::from).collect();
+
+    let total = order.len();
+    let mut results = Vec::with_capacity(total);
+    let mut failed_modules = HashSet::new();
+    for (index, name) in order.into_iter().enumerate() {
+        let span = module_span(index + 1, &name);
+        let started = Instant::now();
+        let mut declared = RenderCapture::default();
+        let failed_dep = graph
+            .dependencies(&name)
+            .into_iter()
+            .find(|dep| failed_modules.contains(*dep));
+        let module = rendered
+            .remove(&name)
+            .expect("every module in the graph was rendered");
+        let result = async {
+            let module = module?;
+            record_module(&tracing::Span::current(), &name, &module.capture, config);
+            declared = module.capture.clone();
+            if let Some(dep) = failed_dep {
+                return Err(errors::ApitapError::PipelineError(format!(
+                    "skipped: dependency '{dep}' did not succeed"
+                )));
+            }
+            execute_pipeline_job(&name, &module.capture, &module.sql, config, &fetch_opts).await
+        }
+        .instrument(span)
+        .await;
+        let failed = result.is_err();
+        if let Err(e) = &result {
+            warn!("❌ Module '{name}' failed: {}", e);
+            failed_modules.insert(name.clone());
         }
-        Err(err) => {
-            warn!("Unable to listen for shutdown signal: {}", err);
+        results.push(ModuleRunResult {
+            module: name,
+            source: declared.source,
+            sinks: declared.sinks.into_iter().map(|s| s.name).collect(),
+            duration: started.elapsed(),
+            result,
+        });
+        if failed && opts.on_module_error == Some(OnModuleError::FailFast) {
+            let remaining = total - results.len();
+            if remaining > 0 {
+                warn!("⏭️  Skipping {remaining} remaining module(s) (--fail-fast)");
+            }
+            break;
         }
     }
+    Ok(results)
+}
 
-    Ok(())
+/// Loads the config at `cfg_path` and runs every module under `root` once,
+/// as `apitap-run --once` does.
+///
+/// # Errors
+///
+/// Same as [`run_modules_once`], plus configuration loading errors.
+pub async fn run_pipeline_once(
+    root: &str,
+    cfg_path: &str,
+    opts: &RunOptions,
+) -> Result<Vec<ModuleRunResult>> {
+    log_pipeline_start();
+    let start_time = Instant::now();
+
+    let config = load_config_from_path(cfg_path)?;
+    info!("⚙️  Configuration loaded successfully");
+    let results = run_modules_once(root, &[], &config, opts).await?;
+    let rows: Vec<SummaryRow> = results.iter().map(ModuleRunResult::summary_row).collect();
+    log_run_summary(&rows);
+
+    let failed = results.iter().filter(|r| !r.is_success()).count();
+    if failed == 0 {
+        log_pipeline_complete(start_time.elapsed().as_millis());
+    } else {
+        warn!(
+            "⚠️  {failed} of {} module(s) failed in {}ms",
+            results.len(),
+            start_time.elapsed().as_millis()
+        );
+    }
+    Ok(results)
+}
+
+/// Loads the config at `cfg_path` and runs every module under `root` once per
+/// window of `backfill`, as `apitap-run --backfill` does.
+///
+/// Windows run in order with `window_start()` and `window_end()` set to their
+/// bounds. Every sink appends; with [`BackfillMode::Replace`] the first
+/// window truncates the destination first. The backfill stops after the
+/// first window in which a module fails, and the results of every window run
+/// so far are returned.
+///
+/// # Errors
+///
+/// Same as [`run_pipeline_once`], plus an invalid date range.
+pub async fn run_backfill(
+    root: &str,
+    cfg_path: &str,
+    backfill: &Backfill,
+    opts: &RunOptions,
+) -> Result<Vec<ModuleRunResult>> {
+    let windows = backfill.windows()?;
+    log_pipeline_start();
+    let start_time = Instant::now();
+
+    let config = load_config_from_path(cfg_path)?;
+    info!("⚙️  Configuration loaded successfully");
+
+    let mut results = Vec::new();
+    let mut opts = opts.clone();
+    for (index, window) in windows.iter().enumerate() {
+        info!(
+            "📆 Backfill window {}/{}: {} → {}",
+            index + 1,
+            windows.len(),
+            window.start,
+            window.end
+        );
+        let truncate = backfill.mode == BackfillMode::Replace && index == 0;
+        let adjust = |capture: &mut RenderCapture| {
+            for sink in &mut capture.sinks {
+                sink.mode = Some(WriteMode::Append);
+                sink.tables.auto_truncate = Some(truncate);
+                // Each window adds to the table rather than replacing it
+                sink.tables.swap = Some(false);
+            }
+        };
+
+        template::set_window(Some((window.start, window.end)));
+        let window_results = run_modules_once_with(root, &[], &config, &opts, &adjust).await;
+        template::set_window(None);
+        let window_results = window_results?;
+        // Checkpoints are cleared once, not before every window.
+        opts.full_restart = false;
+
+        let rows: Vec<SummaryRow> = window_results
+            .iter()
+            .map(ModuleRunResult::summary_row)
+            .collect();
+        log_run_summary(&rows);
+        let failed = window_results.iter().any(|r| !r.is_success());
+        results.extend(window_results);
+        if failed {
+            warn!(
+                "⚠️  Backfill stopped at window {} → {}; rerun with --from {} to resume",
+                window.start, window.end, window.start
+            );
+            return Ok(results);
+        }
+    }
+
+    log_pipeline_complete(start_time.elapsed().as_millis());
+    Ok(results)
+}
+
+/// Normalizes `module` to a template path relative to `root` and checks it exists.
+fn resolve_module_name(root: &str, module: &str, available: &[String]) -> Result<String> {
+    let name = std::path::Path::new(module)
+        .strip_prefix(root)
+        .unwrap_or(std::path::Path::new(module))
+        .to_string_lossy()
+        .replace('\\', "/");
+    if !available.contains(&name) {
+        return Err(errors::ApitapError::ConfigError(format!(
+            "module '{module}' not found under '{root}'"
+        )));
+    }
+    Ok(name)
+}
+
+/// Fetches the first page of `module`'s source and returns the schema the
+/// pipeline would infer from it.
+///
+/// Uses the source's headers, auth, `data_path`, and pagination parameters;
+/// the target is never contacted. `module` may be given relative to `root` or
+/// with `root` as a prefix.
+///
+/// # Errors
+///
+/// Returns an error if the module or its source cannot be found, the request
+/// fails, or the first page has no records to infer from.
+pub async fn infer_module_schema(
+    root: &str,
+    cfg_path: &str,
+    module: &str,
+    opts: &RunOptions,
+) -> Result<SchemaRef> {
+    let name = resolve_module_name(root, module, &list_sql_templates(root)?)?;
+
+    let config = load_config_from_path(cfg_path)?;
+    resolve_config_secrets(&config).await?;
+    let capture = Arc::new(Mutex::new(RenderCapture::default()));
+    let env = build_env_with_captures(root, &capture);
+    let rendered = render_one(&env, &capture, &name)?;
+
+    let source_name = &rendered.capture.source;
+    if source_name.is_empty() {
+        return Err(errors::ApitapError::ConfigError(format!(
+            "module '{name}' has no use_source(...); there is no response to infer a schema from"
+        )));
+    }
+    let source = config
+        .source(source_name)
+        .ok_or_else(|| create_config_error("source", source_name))?;
+
+    info!("🔍 Inferring schema: {name} | {source_name}");
+    let fetch_opts = opts.fetch_opts.for_source(source);
+    let ctx = get_shared_context().await;
+    register_lookups(&ctx, &config.lookups).await?;
+    let request = build_fetch_requests(&name, source, &config)
+        .await?
+        .into_iter()
+        .next()
+        .ok_or_else(|| {
+            errors::ApitapError::ConfigError(format!(
+                "source '{source_name}' has no path_params values to preview"
+            ))
+        })?;
+    preview_schema(request, &fetch_opts).await
+}
+
+/// Downloads `--modules` and `--yaml-config` when they are URLs and points
+/// `cli` at the local copies.
+///
+/// Returns the cache holding the copies, which must be kept alive for the
+/// run, or `None` when both are local paths.
+///
+/// # Errors
+///
+/// Returns an error if a download fails or a module URL cannot be listed.
+pub async fn fetch_remote_locations(cli: &mut Cli) -> Result<Option<RemoteCache>> {
+    if !is_remote(&cli.modules) && !is_remote(&cli.yaml_config) {
+        return Ok(None);
+    }
+    let cache = RemoteCache::new()?;
+    cli.yaml_config = cache.config(&cli.yaml_config).await?;
+    cli.modules = cache.modules(&cli.modules).await?;
+    Ok(Some(cache))
+}
+
+/// Loads `cfg_path` and renders its effective configuration for `--print-config`.
+///
+/// Only reads the file and environment; no source or target is contacted.
+///
+/// # Errors
+///
+/// Returns an error if the config fails to load or validate.
+pub fn render_effective_config(cfg_path: &str, format: ConfigFormat) -> Result<String> {
+    let config = load_config_from_path(cfg_path)?;
+    let effective = effective_config(&config)?;
+    Ok(match format {
+        ConfigFormat::Yaml => serde_yaml::to_string(&effective)?,
+        ConfigFormat::Json => serde_json::to_string_pretty(&effective)? + "\n",
+    })
+}
+
+/// Renders `module`, or every module under `root`, to the SQL it runs, each
+/// under a `--` header naming its source and table.
+///
+/// Only reads the files; no source or target is contacted.
+///
+/// # Errors
+///
+/// Returns an error if the config fails to load, a module is not found or
+/// fails to render, or its source is not configured.
+pub fn render_modules_sql(root: &str, cfg_path: &str, module: Option<&str>) -> Result<String> {
+    let available = list_sql_templates(root)?;
+    let names = match module {
+        Some(module) => vec![resolve_module_name(root, module, &available)?],
+        None => available,
+    };
+    let config = load_config_from_path(cfg_path)?;
+    let capture = Arc::new(Mutex::new(RenderCapture::default()));
+    let env = build_env_with_captures(root, &capture);
+
+    let mut out = String::new();
+    for name in names {
+        let rendered = render_one(&env, &capture, &name)?;
+        let source_name = rendered.capture.source.as_str();
+        let sql = if source_name.is_empty() {
+            let sinks: Vec<&str> = rendered
+                .capture
+                .sinks
+                .iter()
+                .map(|s| s.name.as_str())
+                .collect();
+            out.push_str(&format!("-- {name}: runs on {}\n", sinks.join(", ")));
+            rendered.sql
+        } else {
+            let source = config
+                .source(source_name)
+                .ok_or_else(|| create_config_error("source", source_name))?;
+            let dest_table = extract_destination_table(&config, source, &name)?;
+            out.push_str(&format!(
+                "-- {name}: source '{source_name}' → table {dest_table}\n"
+            ));
+            substitute_source(&rendered.sql, source_name, query_table(&dest_table))
+        };
+        out.push_str(sql.trim_end());
+        out.push_str("\n\n");
+    }
+    Ok(out)
+}
+
+/// Process signals the scheduler loop reacts to.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Signal {
+    /// Ctrl+C or SIGTERM: stop the scheduler and exit.
+    Shutdown,
+    /// SIGHUP: reload modules and configuration.
+    Reload,
+}
+
+/// Waits for the next shutdown or reload signal.
+///
+/// Ctrl+C is honored on every platform. On Unix, SIGTERM also triggers a
+/// shutdown (as sent by container runtimes) and SIGHUP requests a reload.
+#[cfg(unix)]
+async fn wait_for_signal() -> std::io::Result<Signal> {
+    use tokio::signal::unix::{signal, SignalKind};
+
+    let mut sigterm = signal(SignalKind::terminate())?;
+    let mut sighup = signal(SignalKind::hangup())?;
+
+    tokio::select! {
+        res = tokio::signal::ctrl_c() => res.map(|_| Signal::Shutdown),
+        _ = sigterm.recv() => Ok(Signal::Shutdown),
+        _ = sighup.recv() => Ok(Signal::Reload),
+    }
+}
+
+/// Waits for the next shutdown signal (Ctrl+C only on this platform).
+#[cfg(not(unix))]
+async fn wait_for_signal() -> std::io::Result<Signal> {
+    tokio::signal::ctrl_c().await.map(|_| Signal::Shutdown)
 }
 
 /// Creates fetch options with default values.
 fn create_fetch_options() -> FetchOpts {
     FetchOpts {
         concurrency: CONCURRENCY,
+        preserve_order: false,
         default_page_size: DEFAULT_PAGE_SIZE,
         fetch_batch_size: FETCH_BATCH_SIZE,
+        timeout: None,
+        progress: ProgressOpts::default(),
+        execution: ExecutionOpts::default(),
+        capture: None,
+        limits: Limits::default(),
+        max_records: None,
     }
 }
 
+/// The span every event of a module is logged under; [`record_module`] fills
+/// in `source` and `sink` once the template is rendered.
+fn module_span(idx: usize, module: &str) -> tracing::Span {
+    tracing::info_span!(
+        "module",
+        idx,
+        module,
+        source = tracing::field::Empty,
+        sink = tracing::field::Empty
+    )
+}
+
+/// Records the module's source and sinks on `span` and applies its log level:
+/// `{{ log_level(...) }}`, else its source's `log_level`.
+fn record_module(span: &tracing::Span, module: &str, capture: &RenderCapture, cfg: &Config) {
+    span.record("source", capture.source.as_str());
+    let sinks: Vec<&str> = capture.sinks.iter().map(|s| s.name.as_str()).collect();
+    span.record("sink", sinks.join(",").as_str());
+    let level = capture
+        .log_level
+        .or_else(|| cfg.source(&capture.source).and_then(|s| s.log_level));
+    crate::log::set_module_level(module, level);
+}
+
 /// Configuration for processing a single SQL template.
 struct ProcessTemplateConfig<'a> {
     index: usize,
@@ -174,6 +1135,10 @@ struct ProcessTemplateConfig<'a> {
     capture: &'a Arc<Mutex<RenderCapture>>,
     config: &'a Config,
     fetch_opts: &'a FetchOpts,
+    /// Modules whose latest run must have succeeded before a tick runs.
+    dependencies: Vec<String>,
+    health: &'a HealthState,
+    summary: &'a RunSummary,
 }
 
 /// Processes a single SQL template through the ETL pipeline.
@@ -181,49 +1146,96 @@ async fn process_template(
     config: ProcessTemplateConfig<'_>,
     scheduler: &mut JobScheduler,
 ) -> Result<()> {
-    let span = tracing::info_span!("module", idx = config.index, name = %config.name);
+    let span = module_span(config.index, &config.name);
     let _guard = span.enter();
 
     // Render template and extract metadata
     let rendered = render_one(config.env, config.capture, &config.name)?;
+    record_module(&span, &config.name, &rendered.capture, config.config);
     let source_name = rendered.capture.source.clone();
-    let sink_name = rendered.capture.sink.clone();
-    let schedule = rendered.capture.schedule.clone();
+    // Expand aliases and fail with a readable config error before the scheduler sees it
+    let schedules = resolve_schedules(&config.name, &rendered.capture.schedules)?;
+
+    // One guard per module, shared by its schedules, so a slow run never
+    // overlaps its next tick
+    let overlap = OverlapGuard::new(
+        config
+            .config
+            .source(&source_name)
+            .map(|s| s.overlap)
+            .unwrap_or_default(),
+    );
 
     // Clone data needed for the scheduled job
+    let capture = rendered.capture.clone();
     let module_name = config.name.clone();
     let sql_template = rendered.sql.clone();
     let cfg = config.config.clone();
     let fetch_opts = config.fetch_opts.clone();
+    let health = config.health.clone();
+    health.register_module(&module_name);
+    let summary = config.summary.clone();
+    let index = config.index;
+    let dependencies = config.dependencies.clone();
 
-    // Clone module_name for use after the closure
-    let module_name_for_log = module_name.clone();
-
-    // Add async job
-    scheduler
-        .add(Job::new_async(&schedule, move |uuid, mut l| {
+    // One job per schedule
+    for schedule in schedules {
+        let capture = capture.clone();
+        let module_name = module_name.clone();
+        let sql_template = sql_template.clone();
+        let cfg = cfg.clone();
+        let fetch_opts = fetch_opts.clone();
+        let overlap = overlap.clone();
+        let health = health.clone();
+        let summary = summary.clone();
+        let dependencies = dependencies.clone();
+        let log_name = module_name.clone();
+        let job = Job::new_async_tz(&schedule.cron, schedule.tz, move |uuid, mut l| {
             // Clone for the async block
-            let source_name = source_name.clone();
-            let sink_name = sink_name.clone();
+            let capture = capture.clone();
             let module_name = module_name.clone();
             let sql_template = sql_template.clone();
             let cfg = cfg.clone();
             let fetch_opts = fetch_opts.clone();
+            let overlap = overlap.clone();
+            let health = health.clone();
+            let summary = summary.clone();
+            let dependencies = dependencies.clone();
 
             Box::pin(async move {
+                let pending = dependencies.iter().find(|dep| {
+                    !health
+                        .module(dep)
+                        .is_some_and(|status| status.last_run_succeeded())
+                });
+                if let Some(dep) = pending {
+                    warn!("⏭️  Skipping tick for '{module_name}': dependency '{dep}' has not succeeded yet");
+                    return;
+                }
+                let Some(_permit) = overlap.acquire().await else {
+                    warn!("⏭️  Skipping tick for '{module_name}': previous run still in progress");
+                    return;
+                };
+
                 // Execute the scheduled job
-                match execute_pipeline_job(
+                let started = Instant::now();
+                let span = module_span(index, &module_name);
+                record_module(&span, &module_name, &capture, &cfg);
+                let result =
+                    execute_pipeline_job(&module_name, &capture, &sql_template, &cfg, &fetch_opts)
+                        .instrument(span)
+                        .await;
+                summary.record(SummaryRow::new(
                     &module_name,
-                    &source_name,
-                    &sink_name,
-                    &sql_template,
-                    &cfg,
-                    &fetch_opts,
-                )
-                .await
-                {
+                    &capture.source,
+                    capture.sinks.iter().map(|s| s.name.clone()).collect(),
+                    started.elapsed(),
+                    &result,
+                ));
+                match result {
                     Ok(_) => {
                         info!("✅ Scheduled job '{module_name}' completed successfully");
+                        health.record_success(&module_name);
 
                         // Log next execution time
                         if let Ok(Some(ts)) = l.next_tick_for_job(uuid).await {
@@ -232,123 +1244,743 @@ async fn process_template(
                     }
                     Err(e) => {
                         warn!("❌ Scheduled job '{module_name}' failed: {}", e);
+                        health.record_failure(&module_name, &e);
                     }
                 }
             })
-        })?)
-        .await?;
-
-    info!("📅 Scheduled job '{module_name_for_log}' with cron: {schedule}");
+        })?;
+        scheduler.add(job).await?;
+        info!("📅 Scheduled job '{log_name}' with cron: {schedule}");
+    }
     Ok(())
 }
 
-/// Executes a single pipeline job (called by scheduler or directly).
+/// Executes a single pipeline job (called by the scheduler or [`run_modules_once`]).
+///
+/// The HTTP client, headers, query auth, signing keys, and target connections
+/// are built here from the config on every run, never kept between ticks, so
+/// `${ENV}`, `${FILE:...}`, and `${SECRET:...}` values rotated since the last
+/// run are picked up.
 async fn execute_pipeline_job(
     module_name: &str,
-    source_name: &str,
-    sink_name: &str,
+    capture: &RenderCapture,
     sql_template: &str,
     cfg: &Config,
     fetch_opts: &FetchOpts,
-) -> Result<()> {
+) -> Result<FetchStats> {
     let module_start = Instant::now();
-    
-    // Resolve source and target configurations
+    // Fetched on every run so rotated secrets are picked up
+    resolve_config_secrets(cfg).await?;
+    let source_name = capture.source.as_str();
+    if capture.sinks.is_empty() {
+        return Err(errors::ApitapError::PipelineError(format!(
+            "module '{module_name}' declares no sink"
+        )));
+    }
+
+    if source_name.is_empty() {
+        return execute_sql_module(module_name, capture, sql_template, cfg, fetch_opts).await;
+    }
+
+    // Resolve source configuration
     let source = cfg
         .source(source_name)
         .ok_or_else(|| create_config_error("source", source_name))?;
 
-    let target = cfg
-        .target(sink_name)
-        .ok_or_else(|| create_config_error("target", sink_name))?;
-
-    // Build HTTP client with configured headers
-    let client = build_http_client(source)?;
-
-    // Substitute environment variables in URL
-    let url_with_env = crate::utils::template::substitute_env_vars(&source.url)?;
-    let url = reqwest::Url::parse(&Http::new(url_with_env).get_url())?;
+    // Held for the whole run when `max_concurrent_modules` is set
+    let _slot = fetch_opts.limits.acquire_module(module_name).await;
 
     // Prepare destination table and SQL
-    let dest_table = extract_destination_table(source, source_name)?;
-    let sql = sql_template.replace(source_name, dest_table);
-
-    // Initialize writer with configuration
-    let writer_opts = create_writer_options(dest_table, source);
+    let dest_table = extract_destination_table(cfg, source, module_name)?;
+    let dest_table = dest_table.as_str();
+    let sql = module_sql(module_name, sql_template, source_name, dest_table);
 
-    let connection = target.create_conn().await?;
-    let (writer, maybe_truncate) = connection.make_writer(&writer_opts)?;
+    // Open every sink before truncating any of them
+    let mut opened = Vec::with_capacity(capture.sinks.len());
+    let mut failures = Vec::new();
+    for sink in &capture.sinks {
+        match open_sink(sink, cfg, source, dest_table, &capture.post_checks).await {
+            Ok(writer) => opened.push(writer),
+            Err(e) if sink.on_error == SinkErrorPolicy::Continue => {
+                warn!("❌ Sink '{}' of '{module_name}' skipped: {}", sink.name, e);
+                failures.push((sink.name.clone(), e.to_string()));
+            }
+            Err(e) => return Err(e),
+        }
+    }
 
-    // Execute truncate hook if provided
-    if let Some(truncate_hook) = maybe_truncate {
-        truncate_hook().await?;
+    // Execute truncate hooks if provided; `on_empty` guards defer them to the
+    // first row, as does a conditional source so a 304 leaves the table alone
+    let defer_truncate = capture.on_empty != OnEmpty::Proceed || source.conditional;
+    let mut sinks = Vec::with_capacity(opened.len());
+    let mut guards = Vec::new();
+    for (mut sink, maybe_truncate) in opened {
+        if defer_truncate {
+            let guard = Arc::new(EmptyGuardWriter::new(sink.writer, maybe_truncate));
+            sink.writer = guard.clone();
+            guards.push(guard);
+            sinks.push(sink);
+            continue;
+        }
+        let Some(truncate_hook) = maybe_truncate else {
+            sinks.push(sink);
+            continue;
+        };
+        match truncate_hook().await {
+            Ok(()) => sinks.push(sink),
+            Err(e) if sink.on_error == SinkErrorPolicy::Continue => {
+                warn!("❌ Sink '{}' of '{module_name}' skipped: {}", sink.name, e);
+                failures.push((sink.name, e.to_string()));
+            }
+            Err(e) => return Err(e),
+        }
     }
+    if sinks.is_empty() {
+        return Err(errors::ApitapError::PipelineError(format!(
+            "every sink of module '{module_name}' failed"
+        )));
+    }
+    let mut splits = Vec::with_capacity(source.splits.len());
+    for split in &source.splits {
+        let writer = open_split(module_name, capture, cfg, source, split, defer_truncate).await?;
+        splits.push(writer);
+    }
+
+    // One sink writes directly; several share the fetch through a fan-out
+    let write_mode = sinks[0].write_mode.clone();
+    let (writer, fanout): (Arc<dyn DataWriter>, _) = if sinks.len() == 1 {
+        (sinks.remove(0).writer, None)
+    } else {
+        let fanout = Arc::new(FanOutWriter::new(sinks));
+        (fanout.clone(), Some(fanout))
+    };
 
     // Execute ETL pipeline
     info!("🔄 Running: {module_name} | {source_name} → {dest_table}");
 
-    let request = FetchRequest {
-        client,
-        url,
-        data_path: source.data_path.clone(),
-        extra_params: source.query_params.clone(),
-        pagination: source.pagination.clone(),
-        retry: source.retry.clone(),
+    // Reload lookups every run so edits to the files take effect
+    let ctx = get_shared_context().await;
+    register_lookups(&ctx, &cfg.lookups).await?;
+
+    let mut requests = build_fetch_requests(module_name, source, cfg).await?;
+    let metadata = cfg
+        .metadata_columns_for(source)
+        .stamp(&source.name, module_name);
+    let source_capture = match &fetch_opts.capture {
+        Some(c) => Some(c.for_source(&source.name, resolve_headers(&cfg.headers_for(source))?)),
+        None => None,
     };
+    for request in &mut requests {
+        request.metadata = Some(metadata.clone());
+        request.request_template.capture = source_capture.clone();
+        request.request_template.request_limit = fetch_opts.limits.requests();
+    }
 
     let query = QueryConfig {
         sql: &sql,
-        dest_table,
+        dest_table: query_table(dest_table),
     };
 
     let write_config = WriteConfig {
         writer,
-        write_mode: writer_opts.write_mode,
+        write_mode,
+        splits,
+    };
+
+    let fetch_opts = FetchOpts {
+        execution: cfg.execution.clone(),
+        ..fetch_opts.for_source(source)
     };
+    let stats = run_fetch_all(requests, query, write_config, &fetch_opts).await?;
 
-    let stats = run_fetch(request, query, write_config, fetch_opts).await?;
+    let empty = !guards.is_empty() && !guards.iter().any(|g| g.wrote_rows());
+    // A 304 is "nothing new", not a broken run
+    if empty && capture.on_empty == OnEmpty::Fail && stats.not_modified == 0 {
+        return Err(errors::ApitapError::PipelineError(format!(
+            "module '{module_name}' produced no rows (on_empty=fail); destination left untouched"
+        )));
+    }
 
     let duration = module_start.elapsed().as_millis();
-    info!("✅ Completed: {module_name} | {} records | {}ms", stats.total_items, duration);
-    Ok(())
+    info!(
+        "✅ Completed: {module_name} | {} records | {} pages | {} requests | {} bytes | {}ms",
+        stats.total_items, stats.page_count, stats.request_count, stats.total_bytes, duration
+    );
+    if stats.not_modified > 0 && stats.total_items == 0 {
+        info!("⏭️  {module_name}: not modified since the last run; destination left untouched");
+    } else if empty && capture.on_empty != OnEmpty::Proceed {
+        info!("⏭️  {module_name}: no rows; destination left untouched (on_empty=skip)");
+    }
+    failures.extend(fanout.map(|f| f.failures()).unwrap_or_default());
+    for (sink, error) in &failures {
+        warn!("⚠️  {module_name}: sink '{sink}' was not written: {error}");
+    }
+    Ok(stats)
 }
 
-/// Builds an HTTP client with configured headers from the source.
-fn build_http_client(source: &Source) -> Result<reqwest::Client> {
-    let mut http = Http::new(source.url.clone());
+/// Runs a module without `use_source(...)`: its SQL goes to the target of each
+/// sink as written, with no fetch and no DataFusion query. Like a fetch, it
+/// fails with [`errors::ApitapError::Timeout`] once `fetch_opts.timeout` elapses.
+/// The module is not wrapped in a transaction: the sink running at the deadline
+/// is cancelled, and sinks that already ran are not undone.
+async fn execute_sql_module(
+    module_name: &str,
+    capture: &RenderCapture,
+    sql: &str,
+    cfg: &Config,
+    fetch_opts: &FetchOpts,
+) -> Result<FetchStats> {
+    let module_start = Instant::now();
+    let _slot = fetch_opts.limits.acquire_module(module_name).await;
+
+    let run = async {
+        let mut ran = 0;
+        for sink in &capture.sinks {
+            info!("🔄 Running: {module_name} | SQL → {}", sink.name);
+            let result = async {
+                let target = cfg
+                    .target(&sink.name)
+                    .ok_or_else(|| create_config_error("target", &sink.name))?;
+                target.create_conn().await?.execute_sql(sql).await
+            }
+            .await;
+            match result {
+                Ok(()) => ran += 1,
+                Err(e) if sink.on_error == SinkErrorPolicy::Continue => {
+                    warn!(
+                        "⚠️  {module_name}: sink '{}' was not written: {e}",
+                        sink.name
+                    );
+                }
+                Err(e) => return Err(e),
+            }
+        }
+        if ran == 0 {
+            return Err(errors::ApitapError::PipelineError(format!(
+                "every sink of module '{module_name}' failed"
+            )));
+        }
+        Ok(())
+    };
+    match fetch_opts.timeout {
+        Some(limit) => tokio::time::timeout(limit, run).await.unwrap_or_else(|_| {
+            Err(errors::ApitapError::Timeout(format!(
+                "run for '{module_name}' exceeded {}s and was cancelled; \
+                 sinks that already finished keep their changes",
+                limit.as_secs()
+            )))
+        })?,
+        None => run.await?,
+    }
+
+    info!(
+        "✅ Completed: {module_name} | SQL-only | {}ms",
+        module_start.elapsed().as_millis()
+    );
+    Ok(FetchStats::new())
+}
 
-    if let Some(headers) = &source.headers {
-        for header in headers {
-            // Substitute environment variables in header values
-            let value = crate::utils::template::substitute_env_vars(&header.value)?;
-            http = http.header(&header.key, &value);
+/// Opens the module's sinks for one of the source's `splits`, truncating them
+/// like the main table.
+///
+/// Split tables take the split's key and no `column_types`, `raw_json`, or
+/// column selection; without a key, merges become appends.
+async fn open_split(
+    module_name: &str,
+    capture: &RenderCapture,
+    cfg: &Config,
+    source: &Source,
+    split: &SourceSplit,
+    defer_truncate: bool,
+) -> Result<SplitWriter> {
+    let split_source = Source {
+        primary_key_in_dest: split.primary_key.clone(),
+        column_types: Default::default(),
+        raw_json: false,
+        ..source.clone()
+    };
+    let mut sinks = Vec::with_capacity(capture.sinks.len());
+    for sink in &capture.sinks {
+        let mode = sink.mode.clone().unwrap_or(WriteMode::Merge);
+        let sink = SinkCapture {
+            mode: Some(match mode {
+                WriteMode::Merge if split.primary_key.is_none() => WriteMode::Append,
+                mode => mode,
+            }),
+            columns: ColumnSelection::default(),
+            ..sink.clone()
+        };
+        let opened = async {
+            let (mut writer, maybe_truncate) =
+                open_sink(&sink, cfg, &split_source, &split.dest_table, &[]).await?;
+            match maybe_truncate {
+                Some(truncate) if defer_truncate => {
+                    writer.writer = Arc::new(EmptyGuardWriter::new(writer.writer, Some(truncate)));
+                }
+                Some(truncate) => truncate().await?,
+                None => {}
+            }
+            Ok::<_, errors::ApitapError>(writer)
+        }
+        .await;
+        match opened {
+            Ok(writer) => sinks.push(writer),
+            Err(e) if sink.on_error == SinkErrorPolicy::Continue => warn!(
+                "❌ Sink '{}' of '{module_name}' skipped for '{}': {}",
+                sink.name, split.dest_table, e
+            ),
+            Err(e) => return Err(e),
         }
     }
+    if sinks.is_empty() {
+        return Err(errors::ApitapError::PipelineError(format!(
+            "every sink of module '{module_name}' failed for split '{}'",
+            split.dest_table
+        )));
+    }
+
+    let write_mode = sinks[0].write_mode.clone();
+    let writer: Arc<dyn DataWriter> = if sinks.len() == 1 {
+        sinks.remove(0).writer
+    } else {
+        Arc::new(FanOutWriter::new(sinks))
+    };
+    Ok(SplitWriter {
+        data_path: split.data_path.clone(),
+        dest_table: split.dest_table.clone(),
+        writer,
+        write_mode,
+    })
+}
 
+/// Connects to the target of one `sink(...)` call and builds its writer,
+/// which runs the target's post checks and then `post_checks`.
+async fn open_sink(
+    sink: &SinkCapture,
+    cfg: &Config,
+    source: &Source,
+    dest_table: &str,
+    post_checks: &[PostCheck],
+) -> Result<(SinkWriter, Option<Hook>)> {
+    let target = cfg
+        .target(&sink.name)
+        .ok_or_else(|| create_config_error("target", &sink.name))?;
+
+    let write_mode = sink.mode.clone().unwrap_or(WriteMode::Merge);
+    let tables = sink.tables.or(target.tables());
+    if tables.swap() && tables.auto_truncate() {
+        return Err(errors::ApitapError::ConfigError(format!(
+            "sink '{}': swap and auto_truncate cannot both be set",
+            sink.name
+        )));
+    }
+    // A resumed run appends to the pages of the failed one
+    if source.resume.is_some() && (tables.swap() || tables.auto_truncate()) {
+        return Err(errors::ApitapError::ConfigError(format!(
+            "sink '{}': source '{}' has resume, which cannot be combined with swap or auto_truncate",
+            sink.name, source.name
+        )));
+    }
+    let dest_table = sink.table(dest_table);
+    let mut writer_opts = create_writer_options(&dest_table, source, write_mode.clone(), tables);
+    writer_opts.columns = sink.columns.clone();
+    writer_opts.post_checks = target
+        .post_checks()
+        .iter()
+        .chain(post_checks)
+        .cloned()
+        .collect();
+
+    let connection = target.create_conn().await?;
+    let (writer, maybe_truncate) = connection.make_writer(&writer_opts)?;
+    let writer = ColumnFilterWriter::wrap(writer, writer_opts.columns.clone());
+    Ok((
+        SinkWriter {
+            name: sink.name.clone(),
+            writer,
+            write_mode,
+            on_error: sink.on_error,
+        },
+        maybe_truncate,
+    ))
+}
+
+/// Builds the requests for one run of a source: a single request, or one per
+/// `path_params` value (lookups must already be registered).
+async fn build_fetch_requests(
+    module_name: &str,
+    source: &Source,
+    cfg: &Config,
+) -> Result<Vec<FetchRequest>> {
+    let request = build_fetch_request(module_name, source, cfg)?;
+    let Some(params) = &source.path_params else {
+        return Ok(vec![request]);
+    };
+
+    let values = resolve_path_params(params).await?;
+    if values.is_empty() {
+        warn!(
+            "source '{}': path_params '{}' resolved to no values; nothing to fetch",
+            source.name, params.name
+        );
+    } else {
+        info!(
+            "🔀 source '{}': fanning out over {} '{}' values",
+            source.name,
+            values.len(),
+            params.name
+        );
+    }
+    request.expand_path_param(&params.name, &values)
+}
+
+/// Builds the HTTP request description for a source: client, URL, pagination, and body.
+fn build_fetch_request(module_name: &str, source: &Source, cfg: &Config) -> Result<FetchRequest> {
+    // Build HTTP client with configured headers
+    if source.danger_accept_invalid_certs {
+        warn!(
+            source = %source.name,
+            "⚠️ TLS certificate verification is DISABLED (danger_accept_invalid_certs); use only against dev/staging endpoints"
+        );
+    }
+    let headers = cfg.headers_for(source);
+    let accept = match &source.accept {
+        Some(accept) => Some(accept.as_str()),
+        // Keep an `Accept` set under `headers`
+        None if headers.iter().any(|h| h.key.eq_ignore_ascii_case("accept")) => None,
+        None => Some(response_format(source).unwrap_or_default().media_type()),
+    };
+    let client = build_http_client(
+        &headers,
+        accept,
+        source.danger_accept_invalid_certs,
+        source.pool,
+    )?;
+
+    let file = resolve_file_path(source)?;
+    let url = match &file {
+        // Names the files in logs and state keys
+        Some(path) => file_url(path)?,
+        None => {
+            // Substitute environment variables in URL
+            let url_with_env = crate::utils::template::substitute_env_vars(&source.url)?;
+            reqwest::Url::parse(&Http::new(url_with_env).get_url())?
+        }
+    };
+
+    Ok(FetchRequest {
+        client,
+        url,
+        data_path: source.data_path.clone(),
+        extra_params: source.query_params.clone(),
+        pagination: source.pagination.clone(),
+        retry: source.retry.clone(),
+        request_template: build_request_template(source)?,
+        graphql: resolve_graphql(source)?,
+        grpc: resolve_grpc(source, cfg)?,
+        file,
+        raw_json: source.raw_json,
+        ejson: source.ejson,
+        fields: source.fields.clone(),
+        numbers: source.numbers.clone(),
+        nulls: source.nulls.clone(),
+        transform: RecordTransform::parse(&source.transform)?,
+        flatten: source.flatten.as_ref().and_then(FlattenConfig::resolve),
+        dictionary: source.dictionary.clone(),
+        resume: source.resume.as_ref().map(|r| Resume {
+            store: cfg.checkpoint_store(),
+            key: state_key(module_name, &source.name),
+            window: Duration::from_secs(r.window_secs),
+        }),
+        metadata: None,
+        constant_columns: source
+            .constant_columns
+            .as_ref()
+            .map(|c| c.resolve())
+            .transpose()?,
+        // Read on every run so edits to a contract file take effect
+        schema_contract: source
+            .schema_contract
+            .as_ref()
+            .map(|c| c.load().map(Arc::new))
+            .transpose()?,
+        conditional: source.conditional.then(|| Conditional {
+            store: cfg.validator_store(),
+            key: state_key(module_name, &source.name),
+        }),
+        preflight: source.preflight.then_some(Preflight {
+            detect_format: source.format.is_none(),
+        }),
+    })
+}
+
+/// Builds an HTTP client sending `headers`, plus a default `User-Agent` if none
+/// is set; `accept` replaces any `Accept` header, and `accept_invalid_certs`
+/// turns off TLS verification. The client, and so its connection pool, is
+/// shared by every request of the run.
+fn build_http_client(
+    headers: &[Header],
+    accept: Option<&str>,
+    accept_invalid_certs: bool,
+    pool: HttpPool,
+) -> Result<reqwest::Client> {
+    let mut http = Http::new("")
+        .danger_accept_invalid_certs(accept_invalid_certs)
+        .pool(pool);
+    for (key, value) in resolve_headers(headers)? {
+        if accept.is_some() && key.eq_ignore_ascii_case("accept") {
+            continue;
+        }
+        http = http.header(key, value);
+    }
+    if let Some(accept) = accept {
+        http = http.header("Accept", accept);
+    }
     Ok(http.build_client())
 }
 
-/// Extracts the destination table name from the source configuration.
-fn extract_destination_table<'a>(source: &'a Source, source_name: &str) -> Result<&'a str> {
-    source.table_destination_name.as_deref().ok_or_else(|| {
-        warn!(%source_name, "Missing table_destination_name");
+/// Header names and values as sent: `${ENV}` substituted, plus a default
+/// `User-Agent` if none is set.
+fn resolve_headers(headers: &[Header]) -> Result<Vec<(String, String)>> {
+    let mut resolved = Vec::with_capacity(headers.len() + 1);
+    for header in headers {
+        let value = crate::utils::template::substitute_env_vars(&header.value)?;
+        resolved.push((header.key.clone(), value));
+    }
+    if !headers
+        .iter()
+        .any(|h| h.key.eq_ignore_ascii_case("user-agent"))
+    {
+        resolved.push(("User-Agent".to_string(), DEFAULT_USER_AGENT.to_string()));
+    }
+    Ok(resolved)
+}
+
+/// How `source`'s responses are read: its `format`, else the one its
+/// `accept` names.
+fn response_format(source: &Source) -> Option<ResponseFormat> {
+    source.format.or_else(|| {
+        let accept = source.accept.as_deref()?;
+        ResponseFormat::from_content_type(accept.split(',').next()?)
+    })
+}
+
+/// Name of the files that keep a source's state between runs of one module,
+/// so modules sharing the source keep their own.
+fn state_key(module_name: &str, source_name: &str) -> String {
+    format!("{source_name}.{}", module_name.trim_end_matches(".sql"))
+}
+
+/// Builds the per-request method/body template, substituting env vars and templates in the body.
+fn build_request_template(source: &Source) -> Result<RequestTemplate> {
+    let body = source
+        .body
+        .as_ref()
+        .map(crate::utils::template::substitute_json)
+        .transpose()?;
+
+    Ok(RequestTemplate {
+        method: source.method,
+        body,
+        body_encoding: source.body_encoding,
+        pagination_in: source.pagination_in,
+        body_path: source.pagination_body_path.clone(),
+        format: response_format(source).unwrap_or_default(),
+        csv: source.csv.clone().unwrap_or_default(),
+        records_as: source.records_as,
+        record_key_column: source.record_key_column.clone(),
+        lenient_json: source
+            .lenient_json
+            .as_ref()
+            .and_then(LenientJsonConfig::resolve),
+        duplicate_keys: source.duplicate_keys,
+        signer: build_request_signer(source)?,
+        // Set per run from the saved validators
+        conditional: None,
+        // Set per run from `--capture-dir` and `limits`
+        capture: None,
+        request_limit: None,
+        page_headers: Vec::new(),
+        page_vars: Vec::new(),
+        // Set per run for sources with `splits`
+        splits: None,
+        stop_when: source.stop_when.clone(),
+        page_stop: None,
+        // Set per run from `--sample`
+        record_budget: None,
+    })
+}
+
+/// Combines the source's auth and signing, auth first so a signature
+/// covers the key or token.
+fn build_request_signer(source: &Source) -> Result<Option<Arc<dyn RequestSigner>>> {
+    let mut signers: Vec<Arc<dyn RequestSigner>> = Vec::new();
+    match &source.auth {
+        Some(SourceAuth::Query { param, value }) => {
+            let value = crate::utils::template::substitute_env_vars(value)?;
+            signers.push(Arc::new(QueryParamAuth::new(param.clone(), value)));
+        }
+        Some(SourceAuth::Oauth2 {
+            token_url,
+            client_id,
+            client_secret,
+            scope,
+        }) => {
+            let env = crate::utils::template::substitute_env_vars;
+            signers.push(Arc::new(
+                ClientCredentials::new(env(token_url)?, env(client_id)?, env(client_secret)?)
+                    .with_scope(scope.as_deref().map(env).transpose()?)
+                    // The source's TLS and pool settings, but not its headers
+                    .with_client(
+                        Http::new("")
+                            .danger_accept_invalid_certs(source.danger_accept_invalid_certs)
+                            .pool(source.pool)
+                            .build_client(),
+                    ),
+            ));
+        }
+        None => {}
+    }
+    if let Some(signing) = &source.signing {
+        signers.push(build_signer(signing)?);
+    }
+    Ok(match signers.len() {
+        0 => None,
+        1 => signers.pop(),
+        _ => Some(Arc::new(SignerChain(signers))),
+    })
+}
+
+/// Builds the request signer configured on a source.
+fn build_signer(cfg: &SigningConfig) -> Result<Arc<dyn RequestSigner>> {
+    match cfg {
+        SigningConfig::Hmac {
+            secret,
+            header,
+            timestamp_header,
+            prefix,
+        } => {
+            let secret = crate::utils::template::substitute_env_vars(secret)?;
+            Ok(Arc::new(
+                HmacSigner::new(secret)
+                    .with_header(header.clone())
+                    .with_timestamp_header(timestamp_header.clone())
+                    .with_prefix(prefix.clone()),
+            ))
+        }
+    }
+}
+
+/// Returns the GraphQL settings for `kind: graphql` sources.
+fn resolve_graphql(source: &Source) -> Result<Option<crate::pipeline::GraphqlConfig>> {
+    match source.kind {
+        SourceKind::Http | SourceKind::File | SourceKind::Grpc => Ok(None),
+        SourceKind::Graphql => source.graphql.clone().map(Some).ok_or_else(|| {
+            errors::ApitapError::ConfigError(format!(
+                "source '{}' has kind graphql but no graphql block",
+                source.name
+            ))
+        }),
+    }
+}
+
+/// Returns the method settings and metadata of `kind: grpc` sources, with
+/// `${ENV}` substituted in the descriptor path.
+fn resolve_grpc(source: &Source, cfg: &Config) -> Result<Option<GrpcRequest>> {
+    if source.kind != SourceKind::Grpc {
+        return Ok(None);
+    }
+    let mut config = source.grpc.clone().ok_or_else(|| {
+        errors::ApitapError::ConfigError(format!(
+            "source '{}' has kind grpc but no grpc block",
+            source.name
+        ))
+    })?;
+    config.descriptor_set = config
+        .descriptor_set
+        .as_deref()
+        .map(crate::utils::template::substitute_env_vars)
+        .transpose()?;
+    Ok(Some(GrpcRequest {
+        config,
+        metadata: resolve_headers(&cfg.headers_for(source))?,
+    }))
+}
+
+/// Returns the path or glob of `kind: file` sources, `${ENV}` substituted.
+fn resolve_file_path(source: &Source) -> Result<Option<String>> {
+    if source.kind != SourceKind::File {
+        return Ok(None);
+    }
+    let path = source.path.as_deref().ok_or_else(|| {
+        errors::ApitapError::ConfigError(format!(
+            "source '{}' has kind file but no path",
+            source.name
+        ))
+    })?;
+    crate::utils::template::substitute_env_vars(path).map(Some)
+}
+
+/// `file://` URL of `path`, resolved against the working directory.
+fn file_url(path: &str) -> Result<reqwest::Url> {
+    let absolute = std::env::current_dir()?.join(path);
+    reqwest::Url::from_file_path(&absolute)
+        .map_err(|_| errors::ApitapError::ConfigError(format!("invalid file source path '{path}'")))
+}
+
+/// The module SQL reads the fetched rows under the bare table name.
+fn query_table(dest_table: &str) -> &str {
+    dest_table
+        .rsplit_once('.')
+        .map_or(dest_table, |(_, table)| table)
+}
+
+/// The SQL `module_name` runs over its fetched rows.
+fn module_sql(
+    module_name: &str,
+    sql_template: &str,
+    source_name: &str,
+    dest_table: &str,
+) -> String {
+    let sql = substitute_source(sql_template, source_name, query_table(dest_table));
+    debug!(module = module_name, sql = %sql, "module SQL");
+    sql
+}
+
+/// Destination table of a module: `table_destination_name`, or the module
+/// file name.
+fn extract_destination_table(cfg: &Config, source: &Source, module_name: &str) -> Result<String> {
+    cfg.destination_table(source, module_name).ok_or_else(|| {
         errors::ApitapError::PipelineError(format!(
-            "table_destination_name is required for source: {source_name}"
+            "cannot derive a table name from module '{module_name}'; set table_destination_name on source '{}'",
+            source.name
         ))
     })
 }
 
 /// Creates writer options with sensible defaults.
-fn create_writer_options<'a>(dest_table: &'a str, source: &Source) -> WriterOpts<'a> {
+fn create_writer_options<'a>(
+    dest_table: &'a str,
+    source: &Source,
+    write_mode: WriteMode,
+    tables: TablePolicy,
+) -> WriterOpts<'a> {
     WriterOpts {
         dest_table,
         primary_key: source.primary_key_in_dest.clone(),
-        batch_size: 50,
+        batch_size: source.write_batch_size.unwrap_or(WRITE_BATCH_SIZE),
         sample_size: 10,
-        auto_create: true,
-        auto_truncate: false,
-        truncate_first: false,
-        write_mode: WriteMode::Merge,
+        auto_create: tables.auto_create(),
+        auto_truncate: tables.auto_truncate(),
+        truncate_first: tables.auto_truncate(),
+        truncate_mode: tables.truncate_mode(),
+        swap: tables.swap(),
+        write_mode,
+        commit_every: source.commit_every,
+        raw_json: source.raw_json,
+        column_types: source.column_types.clone(),
+        columns: ColumnSelection::default(),
+        post_checks: Vec::new(),
     }
 }
 
@@ -366,10 +1998,18 @@ fn log_pipeline_start() {
     info!("═══════════════════════════════════════════════════════════");
 }
 
+/// Logs the end-of-run summary table, if any module ran.
+fn log_run_summary(rows: &[SummaryRow]) {
+    if rows.is_empty() {
+        return;
+    }
+    info!("📋 Run summary:\n{}", render_table(rows));
+}
+
 /// Logs the completion of the pipeline execution.
 fn log_pipeline_complete(duration_ms: u128) {
     info!("═══════════════════════════════════════════════════════════");
     info!("🎉 All Pipelines Completed Successfully!");
     info!("⏱️  Total Execution Time: {duration_ms}ms");
     info!("═══════════════════════════════════════════════════════════");
-}
\ No newline at end of file
+}
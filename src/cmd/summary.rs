@@ -0,0 +1,179 @@
+//! End-of-run summary table.
+//!
+//! Logged after a `--once` run, and on scheduler shutdown with the last run
+//! of each module:
+//!
+//! ```text
+//! module      source  sink       rows  duration  status
+//! orders.sql  orders  warehouse  1200  3.4s      ✅ ok
+//! users.sql   users   warehouse  -     120ms     ❌ failed: HTTP request failed: ...
+//! ```
+
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+use crate::errors::Result;
+use crate::http::fetcher::FetchStats;
+
+/// Errors longer than this are cut in the status column.
+const MAX_ERROR_CHARS: usize = 80;
+
+/// One line of the summary: the outcome of one module run.
+#[derive(Debug, Clone, PartialEq)]
+pub struct SummaryRow {
+    pub module: String,
+    pub source: String,
+    /// Sink names, in template order.
+    pub sinks: Vec<String>,
+    /// Records fetched; `None` if the run failed.
+    pub rows: Option<usize>,
+    pub duration: Duration,
+    /// Why the run failed, if it did.
+    pub error: Option<String>,
+}
+
+impl SummaryRow {
+    pub fn new(
+        module: &str,
+        source: &str,
+        sinks: Vec<String>,
+        duration: Duration,
+        result: &Result<FetchStats>,
+    ) -> Self {
+        let (rows, error) = match result {
+            Ok(stats) => (Some(stats.total_items), None),
+            Err(e) => (None, Some(e.to_string())),
+        };
+        Self {
+            module: module.to_string(),
+            source: source.to_string(),
+            sinks,
+            rows,
+            duration,
+            error,
+        }
+    }
+}
+
+/// Latest run of each module, shared by the scheduler's jobs.
+///
+/// Cloning is cheap; all clones see the same rows.
+#[derive(Debug, Clone, Default)]
+pub struct RunSummary {
+    rows: Arc<Mutex<Vec<SummaryRow>>>,
+}
+
+impl RunSummary {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Records a run, replacing the previous run of the same module.
+    pub fn record(&self, row: SummaryRow) {
+        let mut rows = self.rows.lock().unwrap_or_else(|e| e.into_inner());
+        match rows.iter_mut().find(|r| r.module == row.module) {
+            Some(existing) => *existing = row,
+            None => rows.push(row),
+        }
+    }
+
+    pub fn rows(&self) -> Vec<SummaryRow> {
+        self.rows.lock().unwrap_or_else(|e| e.into_inner()).clone()
+    }
+}
+
+/// Renders `rows` as a plain text table, one module per line.
+///
+/// # Example
+///
+/// ```
+/// use std::time::Duration;
+/// use apitap::cmd::summary::{render_table, SummaryRow};
+///
+/// let table = render_table(&[SummaryRow {
+///     module: "orders.sql".into(),
+///     source: "orders".into(),
+///     sinks: vec!["warehouse".into()],
+///     rows: Some(1200),
+///     duration: Duration::from_millis(3400),
+///     error: None,
+/// }]);
+/// assert_eq!(
+///     table,
+///     "module      source  sink       rows  duration  status\n\
+///      orders.sql  orders  warehouse  1200  3.4s      ✅ ok\n"
+/// );
+/// ```
+pub fn render_table(rows: &[SummaryRow]) -> String {
+    let header = ["module", "source", "sink", "rows", "duration", "status"].map(String::from);
+    let lines: Vec<[String; 6]> = std::iter::once(header)
+        .chain(rows.iter().map(|r| {
+            [
+                r.module.clone(),
+                or_dash(r.source.clone()),
+                or_dash(r.sinks.join(",")),
+                r.rows.map_or_else(|| "-".to_string(), |n| n.to_string()),
+                format_duration(r.duration),
+                status(r.error.as_deref()),
+            ]
+        }))
+        .collect();
+
+    let mut widths = [0usize; 6];
+    for line in &lines {
+        for (width, cell) in widths.iter_mut().zip(line) {
+            *width = (*width).max(cell.chars().count());
+        }
+    }
+
+    let mut out = String::new();
+    for line in &lines {
+        let mut text = String::new();
+        for (i, cell) in line.iter().enumerate() {
+            if i + 1 == line.len() {
+                text.push_str(cell);
+            } else {
+                let pad = widths[i] - cell.chars().count() + 2;
+                text.push_str(cell);
+                text.extend(std::iter::repeat(' ').take(pad));
+            }
+        }
+        out.push_str(text.trim_end());
+        out.push('\n');
+    }
+    out
+}
+
+fn or_dash(s: String) -> String {
+    if s.is_empty() {
+        "-".to_string()
+    } else {
+        s
+    }
+}
+
+fn status(error: Option<&str>) -> String {
+    match error {
+        None => "✅ ok".to_string(),
+        Some(e) => {
+            let first_line = e.lines().next().unwrap_or_default();
+            let mut short: String = first_line.chars().take(MAX_ERROR_CHARS).collect();
+            if short.len() < e.len() {
+                short.push_str("...");
+            }
+            format!("❌ failed: {short}")
+        }
+    }
+}
+
+/// `850ms` under a second, `3.4s` under a minute, `2m05s` beyond.
+fn format_duration(d: Duration) -> String {
+    let ms = d.as_millis();
+    if ms < 1000 {
+        format!("{ms}ms")
+    } else if ms < 60_000 {
+        format!("{:.1}s", d.as_secs_f64())
+    } else {
+        format!("{}m{:02}s", d.as_secs() / 60, d.as_secs() % 60)
+    }
+}
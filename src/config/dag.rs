@@ -0,0 +1,172 @@
+//! Ordering of modules declared with `{{ depends_on("...") }}`.
+//!
+//! A module may name other modules it reads from, by template path with or
+//! without the `.sql` extension:
+//!
+//! ```sql
+//! {{ depends_on("raw/orders.sql", "raw/customers") }}
+//! SELECT ...
+//! ```
+//!
+//! `--once` and `--backfill` run modules in dependency order and skip a
+//! module whose dependency failed. The scheduler keeps each module on its own
+//! cron, but a tick only runs once the latest run of every dependency has
+//! succeeded. Unknown dependencies and cycles are rejected when the modules
+//! are loaded.
+
+use std::collections::BTreeSet;
+
+use crate::errors::{ApitapError, Result};
+
+/// Dependencies between the modules of one run, checked for cycles.
+///
+/// # Example
+///
+/// ```
+/// use apitap::config::dag::ModuleGraph;
+///
+/// let available = vec!["daily.sql".to_string(), "raw.sql".to_string()];
+/// let graph = ModuleGraph::new(
+///     &[
+///         ("daily.sql".to_string(), vec!["raw".to_string()]),
+///         ("raw.sql".to_string(), vec![]),
+///     ],
+///     &available,
+/// )
+/// .unwrap();
+///
+/// assert_eq!(graph.order(), ["raw.sql", "daily.sql"]);
+/// assert_eq!(graph.dependencies("daily.sql"), ["raw.sql"]);
+/// ```
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ModuleGraph {
+    modules: Vec<String>,
+    /// Indexes into `modules` each module depends on.
+    deps: Vec<Vec<usize>>,
+    order: Vec<usize>,
+}
+
+impl ModuleGraph {
+    /// Builds the graph of `modules`, given as `(name, depends_on)` in
+    /// listing order.
+    ///
+    /// Dependencies are resolved against `available`, every module under the
+    /// modules directory; one that exists but is not in `modules` (as when
+    /// only some modules are run) is left out of the graph.
+    ///
+    /// # Errors
+    ///
+    /// Returns a `ConfigError` when a dependency names no module, a module
+    /// depends on itself, or the dependencies form a cycle.
+    pub fn new(modules: &[(String, Vec<String>)], available: &[String]) -> Result<Self> {
+        let names: Vec<String> = modules.iter().map(|(name, _)| name.clone()).collect();
+        let mut deps = Vec::with_capacity(modules.len());
+        for (name, depends_on) in modules {
+            let mut resolved = BTreeSet::new();
+            for dep in depends_on {
+                let dep = resolve_dependency(name, dep, available)?;
+                if let Some(idx) = names.iter().position(|n| *n == dep) {
+                    resolved.insert(idx);
+                }
+            }
+            deps.push(resolved.into_iter().collect());
+        }
+        let order = topological_order(&names, &deps)?;
+        Ok(Self {
+            modules: names,
+            deps,
+            order,
+        })
+    }
+
+    /// Module names, every dependency before its dependents. Modules keep
+    /// their listing order otherwise.
+    pub fn order(&self) -> Vec<&str> {
+        self.order
+            .iter()
+            .map(|&idx| self.modules[idx].as_str())
+            .collect()
+    }
+
+    /// Modules `module` depends on; empty for unknown modules.
+    pub fn dependencies(&self, module: &str) -> Vec<&str> {
+        self.modules
+            .iter()
+            .position(|m| m == module)
+            .map(|idx| {
+                self.deps[idx]
+                    .iter()
+                    .map(|&dep| self.modules[dep].as_str())
+                    .collect()
+            })
+            .unwrap_or_default()
+    }
+}
+
+/// The template path `dep` refers to: itself, or with `.sql` appended.
+fn resolve_dependency(module: &str, dep: &str, available: &[String]) -> Result<String> {
+    let dep = dep.trim();
+    let found = [dep.to_string(), format!("{dep}.sql")]
+        .into_iter()
+        .find(|candidate| available.contains(candidate))
+        .ok_or_else(|| {
+            ApitapError::ConfigError(format!(
+                "module '{module}' depends on unknown module '{dep}'"
+            ))
+        })?;
+    if found == module {
+        return Err(ApitapError::ConfigError(format!(
+            "module '{module}' depends on itself"
+        )));
+    }
+    Ok(found)
+}
+
+/// Kahn's algorithm, always taking the earliest listed module that is ready.
+fn topological_order(names: &[String], deps: &[Vec<usize>]) -> Result<Vec<usize>> {
+    let mut done = vec![false; names.len()];
+    let mut order = Vec::with_capacity(names.len());
+    while order.len() < names.len() {
+        let next = (0..names.len()).find(|&idx| !done[idx] && deps[idx].iter().all(|&d| done[d]));
+        match next {
+            Some(idx) => {
+                done[idx] = true;
+                order.push(idx);
+            }
+            None => {
+                return Err(ApitapError::ConfigError(format!(
+                    "module dependency cycle: {}",
+                    find_cycle(names, deps, &done)
+                )))
+            }
+        }
+    }
+    Ok(order)
+}
+
+/// Follows dependencies among the unfinished modules until one repeats and
+/// renders that loop as `a.sql -> b.sql -> a.sql`.
+fn find_cycle(names: &[String], deps: &[Vec<usize>], done: &[bool]) -> String {
+    let Some(mut current) = (0..names.len()).find(|&idx| !done[idx]) else {
+        return String::new();
+    };
+    let mut path = vec![current];
+    loop {
+        // Every unfinished module waits on at least one unfinished module
+        current = deps[current]
+            .iter()
+            .copied()
+            .find(|&d| !done[d])
+            .unwrap_or(current);
+        if let Some(start) = path.iter().position(|&idx| idx == current) {
+            path.drain(..start);
+            path.push(current);
+            return path
+                .iter()
+                .map(|&idx| names[idx].as_str())
+                .collect::<Vec<_>>()
+                .join(" -> ");
+        }
+        path.push(current);
+    }
+}
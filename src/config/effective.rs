@@ -0,0 +1,88 @@
+//! The effective configuration shown by `--print-config`.
+//!
+//! Starts from the parsed config, so serde defaults are filled in, then
+//! resolves `${ENV}` and `${FILE:/path}` references and masks secrets.
+//! `{{ fn() }}` templates are left as written since they change per run.
+
+use serde_json::Value;
+
+use crate::errors::Result;
+use crate::pipeline::Config;
+use crate::utils::redact::{Redactor, MASK};
+use crate::utils::template::substitute_env_vars;
+
+/// Returns `cfg` as JSON with env references resolved and secrets masked.
+///
+/// Masked values are:
+/// - fields whose name is a sensitive query parameter (`password`, `token`,
+///   `secret`, ...), such as target passwords and signing secrets;
+/// - the `value` of a header or query param whose `key` is sensitive, and
+///   of query auth;
+/// - sensitive query parameters inside any string (e.g. a URL).
+///
+/// A reference that cannot be resolved is kept as written.
+///
+/// # Example
+///
+/// ```
+/// use apitap::config::effective::effective_config;
+/// use apitap::pipeline::Config;
+///
+/// std::env::set_var("APITAP_DOC_HOST", "db.internal");
+/// let cfg: Config = serde_yaml::from_str(
+///     r#"
+/// sources: []
+/// targets:
+///   - type: postgres
+///     name: pg
+///     host: ${APITAP_DOC_HOST}
+///     database: app
+///     auth: { username: etl, password: hunter2 }
+/// "#,
+/// )
+/// .unwrap();
+///
+/// let effective = effective_config(&cfg).unwrap();
+/// assert_eq!(effective["targets"][0]["host"], "db.internal");
+/// assert_eq!(effective["targets"][0]["port"], 5432);
+/// assert_eq!(effective["targets"][0]["auth"]["password"], "***");
+/// ```
+pub fn effective_config(cfg: &Config) -> Result<Value> {
+    let redactor = Redactor::from_config(&cfg.redact_config());
+    let mut value = serde_json::to_value(cfg)?;
+    resolve(&mut value, &redactor);
+    Ok(value)
+}
+
+fn resolve(value: &mut Value, redactor: &Redactor) {
+    match value {
+        Value::String(s) => {
+            let resolved = substitute_env_vars(s).unwrap_or_else(|_| s.clone());
+            *s = redactor.redact_url(&resolved);
+        }
+        Value::Array(items) => items.iter_mut().for_each(|v| resolve(v, redactor)),
+        Value::Object(obj) => {
+            // `{key, value}` pairs are headers and query params; `{param, value}` is query auth
+            let pair_is_secret = obj
+                .get("key")
+                .or_else(|| obj.get("param"))
+                .and_then(Value::as_str)
+                .is_some_and(|k| redactor.is_sensitive_header(k) || redactor.is_sensitive_param(k))
+                && obj.contains_key("value");
+
+            for (name, field) in obj.iter_mut() {
+                let secret = if name == "value" {
+                    pair_is_secret
+                } else {
+                    name != "key" && name != "param" && redactor.is_sensitive_param(name)
+                };
+                if secret && !field.is_null() {
+                    *field = Value::String(MASK.to_string());
+                } else {
+                    resolve(field, redactor);
+                }
+            }
+        }
+        _ => {}
+    }
+}
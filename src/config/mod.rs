@@ -1,11 +1,14 @@
 use crate::errors::Result;
 use crate::pipeline::Config as PipelineConfig;
+use crate::utils::redact::{self, Redactor};
 use std::env;
 use std::{fs::File, path::Path};
 
 // Validate credentials for targets that require authentication.
 fn validate_credentials(cfg: &PipelineConfig) -> Result<()> {
     for tgt in &cfg.targets {
+        // Reject targets whose backend feature was compiled out before anything else.
+        tgt.ensure_backend_enabled()?;
         match tgt {
             crate::pipeline::Target::Postgres(pg) => {
                 let auth = &pg.auth;
@@ -53,11 +56,315 @@ fn validate_credentials(cfg: &PipelineConfig) -> Result<()> {
                 }
                 return Err(crate::errors::ApitapError::ConfigError(format!("postgres target '{}' missing credentials; provide username/password or username_env/password_env", pg.name)));
             }
+            // Object store credentials are resolved by the cloud SDK chain at connect time
+            crate::pipeline::Target::ObjectStore(_) => {}
+            // Custom factories read and check their own options
+            crate::pipeline::Target::Custom(_) => {}
+            // Provider secrets are fetched per run, not at load time
+            crate::pipeline::Target::Snowflake(sf) if sf.auth.token.contains("${SECRET:") => {}
+            crate::pipeline::Target::Snowflake(sf) => {
+                // Resolve `${ENV}` references now so a missing secret fails at load time
+                let token =
+                    crate::utils::template::substitute_env_vars(&sf.auth.token).map_err(|e| {
+                        crate::errors::ApitapError::ConfigError(format!(
+                            "snowflake target '{}' token: {}",
+                            sf.name, e
+                        ))
+                    })?;
+                if token.trim().is_empty() {
+                    return Err(crate::errors::ApitapError::ConfigError(format!(
+                        "snowflake target '{}' has an empty token",
+                        sf.name
+                    )));
+                }
+            }
+        }
+    }
+    Ok(())
+}
+
+// Reject zero-valued tuning overrides, which would stall or never page.
+fn validate_sources(cfg: &PipelineConfig) -> Result<()> {
+    for src in &cfg.sources {
+        let overrides = [
+            ("concurrency", src.concurrency),
+            ("page_size", src.page_size),
+            (
+                "pagination.page_size",
+                src.pagination
+                    .as_ref()
+                    .and_then(crate::http::fetcher::Pagination::page_size),
+            ),
+            ("max_page_size", src.max_page_size),
+            ("fetch_batch_size", src.fetch_batch_size),
+            ("commit_every", src.commit_every),
+            ("write_batch_size", src.write_batch_size),
+        ];
+        for (field, value) in overrides {
+            if value == Some(0) {
+                return Err(crate::errors::ApitapError::ConfigError(format!(
+                    "source '{}': {} must be greater than 0",
+                    src.name, field
+                )));
+            }
+        }
+        if src.resume.is_some()
+            && !matches!(
+                src.pagination,
+                Some(crate::http::fetcher::Pagination::PageNumber { .. })
+            )
+        {
+            return Err(crate::errors::ApitapError::ConfigError(format!(
+                "source '{}': resume requires page_number pagination",
+                src.name
+            )));
+        }
+        if let Some(crate::pipeline::SourceAuth::Query { param, .. }) = &src.auth {
+            if param.trim().is_empty() {
+                return Err(crate::errors::ApitapError::ConfigError(format!(
+                    "source '{}': auth param must not be empty",
+                    src.name
+                )));
+            }
+        }
+        if let Some(crate::pipeline::SourceAuth::Oauth2 {
+            token_url,
+            client_id,
+            ..
+        }) = &src.auth
+        {
+            if token_url.trim().is_empty() || client_id.trim().is_empty() {
+                return Err(crate::errors::ApitapError::ConfigError(format!(
+                    "source '{}': oauth2 auth needs token_url and client_id",
+                    src.name
+                )));
+            }
+        }
+        if src.conditional && src.kind == crate::pipeline::SourceKind::Graphql {
+            return Err(crate::errors::ApitapError::ConfigError(format!(
+                "source '{}': conditional is not supported for graphql sources",
+                src.name
+            )));
+        }
+        if !src.splits.is_empty() && src.kind == crate::pipeline::SourceKind::Graphql {
+            return Err(crate::errors::ApitapError::ConfigError(format!(
+                "source '{}': splits are not supported for graphql sources",
+                src.name
+            )));
+        }
+        if src.stop_when.is_some()
+            && (src.kind != crate::pipeline::SourceKind::Http
+                || matches!(
+                    src.pagination,
+                    None | Some(crate::http::fetcher::Pagination::Default)
+                ))
+        {
+            return Err(crate::errors::ApitapError::ConfigError(format!(
+                "source '{}': stop_when needs an http source with pagination",
+                src.name
+            )));
+        }
+        if src.preflight && src.kind != crate::pipeline::SourceKind::Http {
+            return Err(crate::errors::ApitapError::ConfigError(format!(
+                "source '{}': preflight needs an http source",
+                src.name
+            )));
+        }
+        if src.accept.as_deref().is_some_and(|accept| {
+            accept.trim().is_empty() || reqwest::header::HeaderValue::from_str(accept).is_err()
+        }) {
+            return Err(crate::errors::ApitapError::ConfigError(format!(
+                "source '{}': accept is not a valid header value",
+                src.name
+            )));
+        }
+        for split in &src.splits {
+            if !split.data_path.starts_with('/') || split.dest_table.trim().is_empty() {
+                return Err(crate::errors::ApitapError::ConfigError(format!(
+                    "source '{}': each split needs a data_path starting with '/' and a dest_table",
+                    src.name
+                )));
+            }
+        }
+        if let Some(contract) = &src.schema_contract {
+            if src.raw_json {
+                return Err(crate::errors::ApitapError::ConfigError(format!(
+                    "source '{}': schema_contract cannot be combined with raw_json",
+                    src.name
+                )));
+            }
+            contract.load().map_err(|e| {
+                crate::errors::ApitapError::ConfigError(format!("source '{}': {e}", src.name))
+            })?;
+        }
+        validate_source_location(src)?;
+        if src.record_key_column.is_some()
+            && src.records_as != crate::http::fetcher::RecordsAs::ObjectValues
+        {
+            return Err(crate::errors::ApitapError::ConfigError(format!(
+                "source '{}': record_key_column requires records_as: object_values",
+                src.name
+            )));
+        }
+        crate::utils::transform::RecordTransform::parse(&src.transform).map_err(|e| {
+            crate::errors::ApitapError::ConfigError(format!("source '{}': {e}", src.name))
+        })?;
+    }
+    Ok(())
+}
+
+/// `kind: file` sources need a `path` and read nothing over HTTP; every
+/// other kind needs a `url`.
+fn validate_source_location(src: &crate::pipeline::Source) -> Result<()> {
+    let invalid = |msg: &str| {
+        Err(crate::errors::ApitapError::ConfigError(format!(
+            "source '{}': {msg}",
+            src.name
+        )))
+    };
+    if src.kind == crate::pipeline::SourceKind::Grpc {
+        return validate_grpc_source(src);
+    }
+    if src.kind != crate::pipeline::SourceKind::File {
+        if src.url.trim().is_empty() {
+            return invalid("url is required");
+        }
+        return Ok(());
+    }
+    if src.path.as_deref().map_or(true, |p| p.trim().is_empty()) {
+        return invalid("kind file requires a path");
+    }
+    if src.conditional {
+        return invalid("conditional is not supported for file sources");
+    }
+    if src.path_params.is_some() {
+        return invalid("path_params is not supported for file sources");
+    }
+    if src.resume.is_some() {
+        return invalid("resume is not supported for file sources");
+    }
+    Ok(())
+}
+
+/// `kind: grpc` sources need the feature, a `url`, and a `grpc` block whose
+/// page token fields come in pairs; HTTP-only settings are rejected.
+fn validate_grpc_source(src: &crate::pipeline::Source) -> Result<()> {
+    let invalid = |msg: &str| {
+        Err(crate::errors::ApitapError::ConfigError(format!(
+            "source '{}': {msg}",
+            src.name
+        )))
+    };
+    if !cfg!(feature = "grpc") {
+        return invalid(
+            "kind grpc is not compiled into this build; rebuild with `--features grpc`",
+        );
+    }
+    if src.url.trim().is_empty() {
+        return invalid("url is required");
+    }
+    let Some(grpc) = &src.grpc else {
+        return invalid("kind grpc requires a grpc block");
+    };
+    crate::pipeline::grpc::split_method(&grpc.method).map_err(|e| {
+        crate::errors::ApitapError::ConfigError(format!("source '{}': {e}", src.name))
+    })?;
+    if grpc.page_token_field.is_some() != grpc.next_page_token_field.is_some() {
+        return invalid("grpc page_token_field and next_page_token_field must be set together");
+    }
+    for (set, option) in [
+        (src.pagination.is_some(), "pagination"),
+        (src.auth.is_some(), "auth (send credentials in headers)"),
+        (src.conditional, "conditional"),
+        (src.path_params.is_some(), "path_params"),
+        (src.resume.is_some(), "resume"),
+        (!src.splits.is_empty(), "splits"),
+    ] {
+        if set {
+            return invalid(&format!("{option} is not supported for grpc sources"));
+        }
+    }
+    Ok(())
+}
+
+/// Rejects zero batch, buffer, partition, or memory sizes in `execution`, and
+/// a `spill_dir` with spilling turned off.
+fn validate_execution(cfg: &PipelineConfig) -> Result<()> {
+    let exec = &cfg.execution;
+    let values = [
+        ("batch_size", exec.batch_size),
+        ("max_buffered_items", exec.max_buffered_items),
+        ("target_partitions", exec.target_partitions),
+        ("memory_limit", exec.memory_limit.0 as usize),
+    ];
+    for (field, value) in values {
+        if value == 0 {
+            return Err(crate::errors::ApitapError::ConfigError(format!(
+                "execution: {field} must be greater than 0"
+            )));
+        }
+    }
+    if exec.spill_dir.is_some() && !exec.spill {
+        return Err(crate::errors::ApitapError::ConfigError(
+            "execution: spill_dir is set but spill is false".to_string(),
+        ));
+    }
+    Ok(())
+}
+
+/// Rejects zero caps in `limits`, which would stall every run.
+fn validate_limits(cfg: &PipelineConfig) -> Result<()> {
+    let limits = &cfg.limits;
+    let values = [
+        ("max_concurrent_modules", limits.max_concurrent_modules),
+        ("max_concurrent_requests", limits.max_concurrent_requests),
+    ];
+    for (field, value) in values {
+        if value == Some(0) {
+            return Err(crate::errors::ApitapError::ConfigError(format!(
+                "limits: {field} must be greater than 0"
+            )));
+        }
+    }
+    Ok(())
+}
+
+// Reject pool limits sqlx would refuse or that can never be satisfied.
+fn validate_targets(cfg: &PipelineConfig) -> Result<()> {
+    for tgt in &cfg.targets {
+        if let crate::pipeline::Target::Postgres(pg) = tgt {
+            if pg.max_connections == Some(0) {
+                return Err(crate::errors::ApitapError::ConfigError(format!(
+                    "postgres target '{}': max_connections must be greater than 0",
+                    pg.name
+                )));
+            }
+            if let (Some(min), Some(max)) = (pg.min_connections, pg.max_connections) {
+                if min > max {
+                    return Err(crate::errors::ApitapError::ConfigError(format!(
+                        "postgres target '{}': min_connections ({}) exceeds max_connections ({})",
+                        pg.name, min, max
+                    )));
+                }
+            }
+        }
+        #[cfg(feature = "object_store")]
+        if let crate::pipeline::Target::ObjectStore(os) = tgt {
+            crate::writer::object_store::writer_properties(&os.parquet).map_err(|e| {
+                crate::errors::ApitapError::ConfigError(format!(
+                    "object_store target '{}': {e}",
+                    os.name
+                ))
+            })?;
         }
     }
     Ok(())
 }
 
+pub mod dag;
+pub mod effective;
+pub mod remote;
+pub mod schedule;
 pub mod templating;
 
 /// Loads and validates a pipeline configuration from a YAML file.
@@ -81,6 +388,13 @@ pub mod templating;
 /// - The YAML syntax is invalid
 /// - Required environment variables for credentials are not set or are empty
 /// - Credential configuration is incomplete (missing username/password pairs)
+/// - A target references a backend whose cargo feature is disabled in this build
+/// - A source sets `concurrency`, `page_size`, `max_page_size`, `fetch_batch_size`,
+///   `commit_every`, or `write_batch_size` (or its pagination sets `page_size`) to 0
+/// - A source sets `resume` without `page_number` pagination
+/// - A source has a `transform` statement that doesn't parse
+/// - A Postgres target sets `max_connections` to 0 or below `min_connections`
+/// - An object store target sets an invalid `compression_level`
 ///
 /// # Example
 ///
@@ -104,5 +418,11 @@ pub fn load_config_from_path<P: AsRef<Path>>(path: P) -> Result<PipelineConfig>
     let cfg: PipelineConfig = serde_yaml::from_reader(f)?;
     // Validate credentials (ensures env vars referenced exist)
     validate_credentials(&cfg)?;
+    validate_sources(&cfg)?;
+    validate_targets(&cfg)?;
+    validate_execution(&cfg)?;
+    validate_limits(&cfg)?;
+    // Mask this config's secrets in everything logged from here on
+    redact::install(Redactor::from_config(&cfg.redact_config()));
     Ok(cfg)
 }
@@ -0,0 +1,198 @@
+//! Config files and module directories stored outside the local filesystem.
+//!
+//! `--yaml-config` and `--modules` accept `http://`/`https://` URLs and, with
+//! the `object_store` feature, object store URLs (`s3://`, `gs://`, `az://`,
+//! ...). They are downloaded once at startup into a temporary directory that
+//! lives as long as the [`RemoteCache`]; every command then reads the local
+//! copies. A module directory has to be listable, so it must be a local path
+//! or an object store prefix.
+
+use std::path::{Path, PathBuf};
+
+use tracing::info;
+use url::Url;
+
+use crate::errors::{ApitapError, Result};
+
+/// Schemes served by the `object_store` backends.
+const OBJECT_STORE_SCHEMES: &[&str] = &["s3", "s3a", "gs", "az", "abfs", "abfss", "azure"];
+
+/// Whether `location` is a URL to download rather than a local path.
+///
+/// ```
+/// use apitap::config::remote::is_remote;
+///
+/// assert!(is_remote("https://config.example.com/pipelines.yaml"));
+/// assert!(is_remote("s3://pipelines/prod/modules"));
+/// assert!(!is_remote("pipelines.yaml"));
+/// assert!(!is_remote("/etc/apitap/modules"));
+/// assert!(!is_remote(r"C:\apitap\pipelines.yaml"));
+/// ```
+pub fn is_remote(location: &str) -> bool {
+    Url::parse(location).is_ok_and(|url| {
+        matches!(url.scheme(), "http" | "https") || OBJECT_STORE_SCHEMES.contains(&url.scheme())
+    })
+}
+
+/// Temporary directory holding the downloaded config and modules of one run.
+///
+/// The files are removed when the cache is dropped.
+#[derive(Debug)]
+pub struct RemoteCache {
+    dir: tempfile::TempDir,
+}
+
+impl RemoteCache {
+    /// Creates an empty cache under the system temp directory.
+    ///
+    /// # Errors
+    ///
+    /// Returns an I/O error if the directory cannot be created.
+    pub fn new() -> Result<Self> {
+        let dir = tempfile::Builder::new()
+            .prefix("apitap-remote-")
+            .tempdir()?;
+        Ok(Self { dir })
+    }
+
+    /// The cache directory.
+    pub fn path(&self) -> &Path {
+        self.dir.path()
+    }
+
+    /// Local path of the config file at `location`: `location` itself when it
+    /// is local, otherwise the downloaded copy.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the download fails or the server answers with an
+    /// error status.
+    pub async fn config(&self, location: &str) -> Result<String> {
+        if !is_remote(location) {
+            return Ok(location.to_string());
+        }
+        let url = Url::parse(location)?;
+        let name = url
+            .path_segments()
+            .and_then(|mut segments| segments.next_back())
+            .filter(|name| !name.is_empty())
+            .unwrap_or("pipelines.yaml");
+        let dest = self.path().join("config").join(name);
+        std::fs::create_dir_all(self.path().join("config"))?;
+
+        let bytes = if matches!(url.scheme(), "http" | "https") {
+            reqwest::get(url.clone())
+                .await?
+                .error_for_status()?
+                .bytes()
+                .await?
+                .to_vec()
+        } else {
+            get_object(location).await?
+        };
+        std::fs::write(&dest, bytes)?;
+        info!(location = %display_url(&url), "📥 Downloaded configuration");
+        Ok(path_string(dest))
+    }
+
+    /// Local directory of the modules at `location`: `location` itself when
+    /// it is local, otherwise a copy of every object under the prefix.
+    ///
+    /// # Errors
+    ///
+    /// Returns a `ConfigError` for an `http(s)://` location, which cannot be
+    /// listed, and an error if listing or downloading fails.
+    pub async fn modules(&self, location: &str) -> Result<String> {
+        if !is_remote(location) {
+            return Ok(location.to_string());
+        }
+        let url = Url::parse(location)?;
+        if matches!(url.scheme(), "http" | "https") {
+            return Err(ApitapError::ConfigError(format!(
+                "modules location '{}' cannot be listed over HTTP; use a local directory or an object store URL",
+                display_url(&url)
+            )));
+        }
+        let dest = self.path().join("modules");
+        std::fs::create_dir_all(&dest)?;
+        let count = list_objects(location, &dest).await?;
+        info!(location = %display_url(&url), files = count, "📥 Downloaded modules");
+        Ok(path_string(dest))
+    }
+}
+
+/// Copies every object under `prefix` in `store` into `dest`, keeping the
+/// key layout below the prefix. Returns the number of files written.
+///
+/// # Errors
+///
+/// Returns an error if listing or reading an object, or writing a file, fails.
+#[cfg(feature = "object_store")]
+pub async fn download_prefix(
+    store: &dyn object_store::ObjectStore,
+    prefix: &object_store::path::Path,
+    dest: &Path,
+) -> Result<usize> {
+    use futures::TryStreamExt;
+
+    let mut listing = store.list(Some(prefix));
+    let mut count = 0;
+    while let Some(meta) = listing.try_next().await? {
+        let key = meta.location.as_ref();
+        let relative = key
+            .strip_prefix(prefix.as_ref())
+            .unwrap_or(key)
+            .trim_start_matches('/');
+        if relative.is_empty() {
+            continue;
+        }
+        let bytes = store.get(&meta.location).await?.bytes().await?;
+        let path = dest.join(relative);
+        if let Some(parent) = path.parent() {
+            std::fs::create_dir_all(parent)?;
+        }
+        std::fs::write(path, bytes)?;
+        count += 1;
+    }
+    Ok(count)
+}
+
+#[cfg(feature = "object_store")]
+async fn get_object(location: &str) -> Result<Vec<u8>> {
+    let (store, path) = crate::writer::object_store::store_from_url(location)?;
+    Ok(store.get(&path).await?.bytes().await?.to_vec())
+}
+
+#[cfg(feature = "object_store")]
+async fn list_objects(location: &str, dest: &Path) -> Result<usize> {
+    let (store, prefix) = crate::writer::object_store::store_from_url(location)?;
+    download_prefix(store.as_ref(), &prefix, dest).await
+}
+
+#[cfg(not(feature = "object_store"))]
+async fn get_object(location: &str) -> Result<Vec<u8>> {
+    Err(object_store_disabled(location))
+}
+
+#[cfg(not(feature = "object_store"))]
+async fn list_objects(location: &str, _dest: &Path) -> Result<usize> {
+    Err(object_store_disabled(location))
+}
+
+#[cfg(not(feature = "object_store"))]
+fn object_store_disabled(location: &str) -> ApitapError {
+    ApitapError::ConfigError(format!(
+        "'{location}' is an object store URL, which is not compiled into this build; rebuild with `--features object_store`"
+    ))
+}
+
+/// `url` without its query string, which may carry a signature.
+fn display_url(url: &Url) -> String {
+    let mut shown = url.clone();
+    shown.set_query(None);
+    shown.to_string()
+}
+
+fn path_string(path: PathBuf) -> String {
+    path.to_string_lossy().into_owned()
+}
@@ -0,0 +1,255 @@
+//! Resolution and validation for schedules captured by `{{ schedule("...") }}`.
+//!
+//! Schedules use the six-field cron syntax expected by the job scheduler
+//! (`sec min hour day-of-month month day-of-week`), for example
+//! `"0 */3 * * * *"` to run every three minutes.
+//!
+//! The following aliases are also accepted and expanded before validation:
+//!
+//! | Alias                               | Cron              |
+//! |-------------------------------------|-------------------|
+//! | `@yearly`, `@annually`, `yearly`    | `0 0 0 1 1 *`     |
+//! | `@monthly`, `monthly`               | `0 0 0 1 * *`     |
+//! | `@weekly`, `weekly`                 | `0 0 0 * * SUN`   |
+//! | `@daily`, `@midnight`, `daily`      | `0 0 0 * * *`     |
+//! | `@hourly`, `hourly`, `every hour`   | `0 0 * * * *`     |
+//! | `every minute`                      | `0 * * * * *`     |
+//! | `every N seconds`                   | `*/N * * * * *`   |
+//! | `every N minutes`                   | `0 */N * * * *`   |
+//! | `every N hours`                     | `0 0 */N * * *`   |
+//!
+//! Expressions are read in UTC unless `tz` names an IANA timezone, and a
+//! module may call `schedule(...)` several times to run on each schedule:
+//!
+//! ```sql
+//! {{ schedule("0 0 9 * * MON-FRI", tz="America/New_York") }}
+//! {{ schedule("0 0 17 * * MON-FRI", tz="America/New_York") }}
+//! ```
+
+use std::fmt;
+use std::sync::Arc;
+
+use chrono_tz::Tz;
+use croner::parser::{CronParser, Seconds};
+use serde::{Deserialize, Serialize};
+use tokio::sync::{Mutex, OwnedMutexGuard};
+
+use crate::config::templating::ScheduleCapture;
+use crate::errors::{ApitapError, Result};
+
+/// A validated schedule: a six-field cron expression and the timezone it is
+/// read in.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Schedule {
+    pub cron: String,
+    pub tz: Tz,
+}
+
+impl fmt::Display for Schedule {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} ({})", self.cron, self.tz)
+    }
+}
+
+/// Resolves every `schedule(...)` of `module`, expanding aliases and parsing
+/// timezones.
+///
+/// # Errors
+///
+/// Returns [`ApitapError::ConfigError`] when `schedules` is empty, for an
+/// expression [`resolve_schedule`] rejects, or for an unknown timezone.
+///
+/// # Example
+///
+/// ```
+/// use apitap::config::schedule::resolve_schedules;
+/// use apitap::config::templating::ScheduleCapture;
+///
+/// let schedules = resolve_schedules(
+///     "m.sql",
+///     &[
+///         ScheduleCapture { expr: "@daily".into(), tz: None },
+///         ScheduleCapture { expr: "0 0 2 * * *".into(), tz: Some("Europe/Berlin".into()) },
+///     ],
+/// )
+/// .unwrap();
+/// assert_eq!(schedules[0].to_string(), "0 0 0 * * * (UTC)");
+/// assert_eq!(schedules[1].to_string(), "0 0 2 * * * (Europe/Berlin)");
+/// ```
+pub fn resolve_schedules(module: &str, schedules: &[ScheduleCapture]) -> Result<Vec<Schedule>> {
+    if schedules.is_empty() {
+        validate_cron(module, "")?;
+    }
+    schedules
+        .iter()
+        .map(|schedule| {
+            let tz = match &schedule.tz {
+                Some(tz) => tz.trim().parse::<Tz>().map_err(|_| {
+                    ApitapError::ConfigError(format!(
+                        "module '{module}' has unknown schedule timezone '{tz}' \
+                         (expected an IANA name like Europe/Berlin)"
+                    ))
+                })?,
+                None => Tz::UTC,
+            };
+            Ok(Schedule {
+                cron: resolve_schedule(module, &schedule.expr)?,
+                tz,
+            })
+        })
+        .collect()
+}
+
+/// Expands a schedule alias and validates the result.
+///
+/// Strings that are not aliases are returned unchanged if they are valid cron.
+///
+/// # Errors
+///
+/// Same as [`validate_cron`].
+///
+/// # Example
+///
+/// ```
+/// use apitap::config::schedule::resolve_schedule;
+///
+/// assert_eq!(resolve_schedule("m.sql", "@daily").unwrap(), "0 0 0 * * *");
+/// assert_eq!(resolve_schedule("m.sql", "every 5 minutes").unwrap(), "0 */5 * * * *");
+/// assert_eq!(resolve_schedule("m.sql", "0 */3 * * * *").unwrap(), "0 */3 * * * *");
+/// ```
+pub fn resolve_schedule(module: &str, expr: &str) -> Result<String> {
+    let cron = expand_alias(expr).unwrap_or_else(|| expr.trim().to_string());
+    validate_cron(module, &cron)?;
+    Ok(cron)
+}
+
+/// Returns the cron expression for a known alias, or `None`.
+pub fn expand_alias(expr: &str) -> Option<String> {
+    let normalized = expr.trim().to_ascii_lowercase();
+    let fixed = match normalized.as_str() {
+        "@yearly" | "@annually" | "yearly" | "annually" => Some("0 0 0 1 1 *"),
+        "@monthly" | "monthly" => Some("0 0 0 1 * *"),
+        "@weekly" | "weekly" => Some("0 0 0 * * SUN"),
+        "@daily" | "@midnight" | "daily" | "every day" => Some("0 0 0 * * *"),
+        "@hourly" | "hourly" | "every hour" => Some("0 0 * * * *"),
+        "every minute" => Some("0 * * * * *"),
+        _ => None,
+    };
+    if let Some(cron) = fixed {
+        return Some(cron.to_string());
+    }
+
+    // "every N <unit>"
+    let mut parts = normalized.split_whitespace();
+    let (Some("every"), Some(n), Some(unit), None) =
+        (parts.next(), parts.next(), parts.next(), parts.next())
+    else {
+        return None;
+    };
+    let n: u32 = n.parse().ok().filter(|n| *n > 0)?;
+    match unit {
+        "second" | "seconds" => Some(format!("*/{n} * * * * *")),
+        "minute" | "minutes" => Some(format!("0 */{n} * * * *")),
+        "hour" | "hours" => Some(format!("0 0 */{n} * * *")),
+        _ => None,
+    }
+}
+
+/// Checks that `expr` is a cron expression the scheduler will accept.
+///
+/// Parses with the same settings the scheduler uses, so anything that passes
+/// here can be added as a job.
+///
+/// # Errors
+///
+/// Returns [`ApitapError::ConfigError`] naming `module` and the offending
+/// expression when the schedule is missing or cannot be parsed.
+///
+/// # Example
+///
+/// ```
+/// use apitap::config::schedule::validate_cron;
+///
+/// assert!(validate_cron("users.sql", "0 */3 * * * *").is_ok());
+/// assert!(validate_cron("users.sql", "every tuesday-ish").is_err());
+/// ```
+pub fn validate_cron(module: &str, expr: &str) -> Result<()> {
+    if expr.trim().is_empty() {
+        return Err(ApitapError::ConfigError(format!(
+            "module '{module}' has no schedule; add {{{{ schedule(\"<cron>\") }}}}"
+        )));
+    }
+
+    CronParser::builder()
+        .seconds(Seconds::Required)
+        .dom_and_dow(true)
+        .build()
+        .parse(expr)
+        .map(|_| ())
+        .map_err(|e| {
+            ApitapError::ConfigError(format!(
+                "module '{module}' has invalid schedule '{expr}': {e} \
+                 (expected 6 fields: sec min hour day month weekday, or an alias like @daily)"
+            ))
+        })
+}
+
+/// What to do when a module's next tick fires while its previous run is still going.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum OverlapPolicy {
+    /// Drop the new tick and log a warning.
+    #[default]
+    Skip,
+    /// Wait for the previous run to finish, then run.
+    Queue,
+}
+
+/// Serializes the runs of one scheduled module.
+///
+/// Clones share the same lock, so one guard is created per module and moved
+/// into its job closure.
+///
+/// # Example
+///
+/// ```
+/// use apitap::config::schedule::{OverlapGuard, OverlapPolicy};
+///
+/// # #[tokio::main(flavor = "current_thread")]
+/// # async fn main() {
+/// let guard = OverlapGuard::new(OverlapPolicy::Skip);
+/// let first = guard.acquire().await;
+/// assert!(first.is_some());
+/// assert!(guard.acquire().await.is_none()); // still running: skipped
+/// drop(first);
+/// assert!(guard.acquire().await.is_some());
+/// # }
+/// ```
+#[derive(Debug, Clone)]
+pub struct OverlapGuard {
+    lock: Arc<Mutex<()>>,
+    policy: OverlapPolicy,
+}
+
+impl OverlapGuard {
+    pub fn new(policy: OverlapPolicy) -> Self {
+        Self {
+            lock: Arc::new(Mutex::new(())),
+            policy,
+        }
+    }
+
+    pub fn policy(&self) -> OverlapPolicy {
+        self.policy
+    }
+
+    /// Returns a permit to run, or `None` if the tick should be skipped.
+    ///
+    /// Hold the permit for the whole run.
+    pub async fn acquire(&self) -> Option<OwnedMutexGuard<()>> {
+        match self.policy {
+            OverlapPolicy::Skip => self.lock.clone().try_lock_owned().ok(),
+            OverlapPolicy::Queue => Some(self.lock.clone().lock_owned().await),
+        }
+    }
+}
@@ -2,16 +2,89 @@ use std::path::Path;
 use std::sync::{Arc, Mutex};
 
 use crate::errors::Result;
+use crate::log::LogLevel;
+use crate::pipeline::empty::OnEmpty;
+use crate::pipeline::TablePolicy;
+use crate::writer::columns::ColumnSelection;
+use crate::writer::fanout::SinkErrorPolicy;
+use crate::writer::post_check::{Expectation, PostCheck};
+use crate::writer::{TruncateMode, WriteMode};
 use minijinja::path_loader;
-use minijinja::value::{Kwargs, Value};
+use minijinja::value::{Kwargs, Rest, Value};
 use minijinja::{Environment, Error as MjError};
 use walkdir::WalkDir;
 
 #[derive(Debug, Default, Clone)]
 pub struct RenderCapture {
-    pub sink: String,
+    /// Every `sink(...)` call, in template order.
+    pub sinks: Vec<SinkCapture>,
     pub source: String,
-    pub schedule: String,
+    /// Every `schedule(...)` call, in template order; the module runs on
+    /// each of them.
+    pub schedules: Vec<ScheduleCapture>,
+    /// `on_empty("skip|proceed|fail")`.
+    pub on_empty: OnEmpty,
+    /// `log_level("debug")`: level of this module's own logs.
+    pub log_level: Option<LogLevel>,
+    /// `depends_on("...", ...)`: modules that must succeed before this one
+    /// runs (see [`crate::config::dag`]).
+    pub depends_on: Vec<String>,
+    /// `post_check("...", expect="...")`: assertion queries run on each
+    /// sink's table after the load (see [`crate::writer::post_check`]).
+    pub post_checks: Vec<PostCheck>,
+}
+
+/// One `schedule(...)` call of a module.
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct ScheduleCapture {
+    /// Cron expression or alias (see [`crate::config::schedule`]).
+    pub expr: String,
+    /// `schedule(tz="...")`: IANA timezone the expression is read in; UTC
+    /// when `None`.
+    pub tz: Option<String>,
+}
+
+/// One `sink(...)` call of a module.
+#[derive(Debug, Default, Clone, PartialEq)]
+pub struct SinkCapture {
+    pub name: String,
+    /// Write mode from `sink(mode="...")`; `None` keeps the default (merge).
+    pub mode: Option<WriteMode>,
+    /// `sink(auto_create=..., auto_truncate=...)`; overrides the target's.
+    pub tables: TablePolicy,
+    /// `sink(on_error="fail|continue")`.
+    pub on_error: SinkErrorPolicy,
+    /// `sink(write_columns=[...], ignore_columns=[...])`.
+    pub columns: ColumnSelection,
+    /// `sink(schema="...")`: schema the table is written to in this target.
+    pub schema: Option<String>,
+}
+
+impl SinkCapture {
+    /// The table this sink writes: `dest_table`, moved into `schema` when set.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use apitap::config::templating::SinkCapture;
+    ///
+    /// let sink = SinkCapture {
+    ///     schema: Some("analytics".into()),
+    ///     ..SinkCapture::default()
+    /// };
+    /// assert_eq!(sink.table("users"), "analytics.users");
+    /// assert_eq!(sink.table("staging.users"), "analytics.users");
+    /// assert_eq!(SinkCapture::default().table("staging.users"), "staging.users");
+    /// ```
+    pub fn table(&self, dest_table: &str) -> String {
+        match &self.schema {
+            Some(schema) => {
+                let name = dest_table.rsplit_once('.').map_or(dest_table, |(_, t)| t);
+                format!("{schema}.{name}")
+            }
+            None => dest_table.to_string(),
+        }
+    }
 }
 
 #[derive(Debug, Clone)]
@@ -24,8 +97,16 @@ pub struct RenderedSql {
 /// Builds a Minijinja template environment with custom functions for SQL templating.
 ///
 /// Creates a templating environment that supports:
-/// - `{{ sink(name="...") }}` - Declares the target sink/destination
-/// - `{{ use_source("...") }}` - References a data source by name
+/// - `{{ sink(name="...") }}` - Declares a target sink/destination; call it
+///   again to write the same rows to more targets
+/// - `{{ use_source("...") }}` - References a data source by name; modules
+///   without one run their SQL on the sink's target
+/// - `{{ schedule("...", tz="...") }}` - Cron expression or alias, optionally in a
+///   timezone; call it again to add schedules (see [`crate::config::schedule`])
+/// - `{{ on_empty("skip|proceed|fail") }}` - What an empty run does (see [`crate::pipeline::empty`])
+/// - `{{ log_level("debug") }}` - Level of the module's logs (see [`crate::log::set_module_level`])
+/// - `{{ depends_on("raw.sql", ...) }}` - Modules that run first (see [`crate::config::dag`])
+/// - `{{ post_check("SELECT ...", expect="> 0") }}` - Query checked after the load (see [`crate::writer::post_check`])
 ///
 /// The environment captures sink and source names during template rendering
 /// for pipeline configuration.
@@ -58,15 +139,60 @@ pub fn build_env_with_captures(
     let mut env = Environment::new();
     env.set_loader(path_loader(root));
 
-    // {{ sink(name="...") }}
+    // {{ sink(name="...", mode="merge|append|insert", auto_create=true, auto_truncate=false,
+    //         swap=false, on_error="fail|continue", write_columns=[...], ignore_columns=[...],
+    //         schema="...") }}
     {
         let cap = Arc::clone(shared_cap);
         env.add_function(
             "sink",
             move |kwargs: Kwargs| -> std::result::Result<Value, MjError> {
                 let name: String = kwargs.get("name")?;
+                let invalid =
+                    |e: crate::errors::ApitapError| MjError::new(minijinja::ErrorKind::InvalidOperation, e.to_string());
+                let mode = kwargs
+                    .get::<Option<String>>("mode")?
+                    .map(|m| m.parse::<WriteMode>())
+                    .transpose()
+                    .map_err(invalid)?;
+                let on_error = kwargs
+                    .get::<Option<String>>("on_error")?
+                    .map(|p| p.parse::<SinkErrorPolicy>())
+                    .transpose()
+                    .map_err(invalid)?
+                    .unwrap_or_default();
+                let tables = TablePolicy {
+                    auto_create: kwargs.get("auto_create")?,
+                    auto_truncate: kwargs.get("auto_truncate")?,
+                    truncate_mode: kwargs
+                        .get::<Option<String>>("truncate_mode")?
+                        .map(|m| m.parse::<TruncateMode>())
+                        .transpose()
+                        .map_err(invalid)?,
+                    swap: kwargs.get("swap")?,
+                };
+                let columns = ColumnSelection {
+                    write_columns: kwargs.get("write_columns")?,
+                    ignore_columns: kwargs
+                        .get::<Option<Vec<String>>>("ignore_columns")?
+                        .unwrap_or_default(),
+                };
+                let schema: Option<String> = kwargs.get("schema")?;
                 let mut c = cap.lock().expect("RenderCapture mutex poisoned - this indicates a panic occurred while holding the lock");
-                c.sink = name;
+                if c.sinks.iter().any(|s| s.name == name) {
+                    return Err(MjError::new(
+                        minijinja::ErrorKind::InvalidOperation,
+                        format!("sink '{name}' is declared more than once"),
+                    ));
+                }
+                c.sinks.push(SinkCapture {
+                    name,
+                    mode,
+                    tables,
+                    on_error,
+                    columns,
+                    schema,
+                });
                 Ok(Value::from(""))
             },
         );
@@ -85,14 +211,84 @@ pub fn build_env_with_captures(
         );
     }
 
-    // {{ schedule("...") }}
+    // {{ schedule("...", tz="Europe/Berlin") }}
     {
         let cap = Arc::clone(shared_cap);
         env.add_function(
             "schedule",
-            move |name: String| -> std::result::Result<Value, MjError> {
+            move |expr: String, kwargs: Kwargs| -> std::result::Result<Value, MjError> {
+                let tz = kwargs.get::<Option<String>>("tz")?;
+                let mut c = cap.lock().expect("RenderCapture mutex poisoned - this indicates a panic occurred while holding the lock");
+                c.schedules.push(ScheduleCapture { expr, tz });
+                Ok(Value::from(""))
+            },
+        );
+    }
+
+    // {{ on_empty("skip|proceed|fail") }}
+    {
+        let cap = Arc::clone(shared_cap);
+        env.add_function(
+            "on_empty",
+            move |policy: String| -> std::result::Result<Value, MjError> {
+                let policy = policy.parse::<OnEmpty>().map_err(|e| {
+                    MjError::new(minijinja::ErrorKind::InvalidOperation, e.to_string())
+                })?;
+                let mut c = cap.lock().expect("RenderCapture mutex poisoned - this indicates a panic occurred while holding the lock");
+                c.on_empty = policy;
+                Ok(Value::from(""))
+            },
+        );
+    }
+
+    // {{ log_level("trace|debug|info|warn|error|off") }}
+    {
+        let cap = Arc::clone(shared_cap);
+        env.add_function(
+            "log_level",
+            move |level: String| -> std::result::Result<Value, MjError> {
+                let level = level.parse::<LogLevel>().map_err(|e| {
+                    MjError::new(minijinja::ErrorKind::InvalidOperation, e.to_string())
+                })?;
+                let mut c = cap.lock().expect("RenderCapture mutex poisoned - this indicates a panic occurred while holding the lock");
+                c.log_level = Some(level);
+                Ok(Value::from(""))
+            },
+        );
+    }
+
+    // {{ depends_on("module.sql", ...) }}
+    {
+        let cap = Arc::clone(shared_cap);
+        env.add_function(
+            "depends_on",
+            move |modules: Rest<String>| -> std::result::Result<Value, MjError> {
+                let mut c = cap.lock().expect("RenderCapture mutex poisoned - this indicates a panic occurred while holding the lock");
+                for module in modules.iter() {
+                    if !c.depends_on.contains(module) {
+                        c.depends_on.push(module.clone());
+                    }
+                }
+                Ok(Value::from(""))
+            },
+        );
+    }
+
+    // {{ post_check("SELECT count(*) FROM {table}", expect="> 0") }}
+    {
+        let cap = Arc::clone(shared_cap);
+        env.add_function(
+            "post_check",
+            move |sql: String, kwargs: Kwargs| -> std::result::Result<Value, MjError> {
+                let expect = kwargs
+                    .get::<Option<String>>("expect")?
+                    .map(|e| e.parse::<Expectation>())
+                    .transpose()
+                    .map_err(|e| {
+                        MjError::new(minijinja::ErrorKind::InvalidOperation, e.to_string())
+                    })?;
                 let mut c = cap.lock().expect("RenderCapture mutex poisoned - this indicates a panic occurred while holding the lock");
-                c.schedule = name.clone();
+                c.post_checks.push(PostCheck { sql, expect });
                 Ok(Value::from(""))
             },
         );
@@ -131,7 +327,9 @@ pub fn build_env_with_captures(
 ///     .expect("Failed to render template");
 ///
 /// println!("SQL: {}", rendered.sql);
-/// println!("Sink: {}", rendered.capture.sink);
+/// for sink in &rendered.capture.sinks {
+///     println!("Sink: {}", sink.name);
+/// }
 /// println!("Source: {}", rendered.capture.source);
 /// ```
 pub fn render_one(
@@ -143,9 +341,13 @@ pub fn render_one(
         let mut c = shared_cap.lock().expect(
             "RenderCapture mutex poisoned - this indicates a panic occurred while holding the lock",
         );
-        c.sink.clear();
+        c.sinks.clear();
         c.source.clear();
-        c.schedule.clear();
+        c.schedules.clear();
+        c.on_empty = OnEmpty::default();
+        c.log_level = None;
+        c.depends_on.clear();
+        c.post_checks.clear();
     }
 
     let tmpl = env.get_template(name)?;
@@ -164,6 +366,73 @@ pub fn render_one(
     })
 }
 
+/// `sql` with each reference to `source_name` replaced by `table`, the name
+/// the fetched rows are registered under.
+///
+/// Only whole identifiers are replaced, so a source named `user` leaves
+/// `username` alone. Names inside string literals, `--` and `/* */` comments,
+/// and the last part of a qualified name (`raw.user`) are kept too, while a
+/// qualifier (`user.id`) is replaced.
+///
+/// ```
+/// use apitap::config::templating::substitute_source;
+///
+/// assert_eq!(
+///     substitute_source("SELECT user.id, username, 'user' FROM user", "user", "dim_user"),
+///     "SELECT dim_user.id, username, 'user' FROM dim_user"
+/// );
+/// ```
+pub fn substitute_source(sql: &str, source_name: &str, table: &str) -> String {
+    if source_name.is_empty() {
+        return sql.to_string();
+    }
+    let is_ident = |c: char| c.is_alphanumeric() || c == '_';
+    let skipped = literal_and_comment_spans(sql);
+    let mut out = String::with_capacity(sql.len());
+    let mut copied = 0;
+    for (at, _) in sql.match_indices(source_name) {
+        let before = &sql[..at];
+        let after = &sql[at + source_name.len()..];
+        let in_literal = skipped.iter().any(|&(start, end)| start <= at && at < end);
+        let glued_before = before
+            .chars()
+            .next_back()
+            .is_some_and(|c| is_ident(c) || c == '.');
+        let glued_after = after.chars().next().is_some_and(is_ident);
+        if in_literal || glued_before || glued_after {
+            continue;
+        }
+        out.push_str(&sql[copied..at]);
+        out.push_str(table);
+        copied = at + source_name.len();
+    }
+    out.push_str(&sql[copied..]);
+    out
+}
+
+/// Byte ranges of `sql` covered by string literals and `--` / `/* */`
+/// comments. An escaped quote (`''`) ends one literal and starts the next, so
+/// the ranges still cover it. An unterminated literal or comment runs to the end.
+fn literal_and_comment_spans(sql: &str) -> Vec<(usize, usize)> {
+    let bytes = sql.as_bytes();
+    let mut spans = Vec::new();
+    let mut i = 0;
+    while i < bytes.len() {
+        let end = match (bytes[i], bytes.get(i + 1)) {
+            (b'\'', _) => sql[i + 1..].find('\'').map_or(sql.len(), |n| i + n + 2),
+            (b'-', Some(b'-')) => sql[i..].find('\n').map_or(sql.len(), |n| i + n),
+            (b'/', Some(b'*')) => sql[i + 2..].find("*/").map_or(sql.len(), |n| i + n + 4),
+            _ => {
+                i += 1;
+                continue;
+            }
+        };
+        spans.push((i, end));
+        i = end;
+    }
+    spans
+}
+
 /// Lists all SQL template files in a directory recursively.
 ///
 /// Walks through the directory tree finding all `.sql` files (case-insensitive)
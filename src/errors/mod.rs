@@ -3,6 +3,8 @@ use tokio_cron_scheduler::JobSchedulerError;
 use tokio_util::codec::LinesCodecError;
 use tracing_subscriber::filter::FromEnvError;
 
+use crate::utils::redact::redact_text;
+
 /// Main error type for apitap operations
 #[derive(Error, Debug)]
 pub enum ApitapError {
@@ -14,12 +16,16 @@ pub enum ApitapError {
     RegexError(#[from] regex::Error),
 
     #[error("DataFusion error: {0}")]
-    Datafusion(#[from] datafusion::error::DataFusionError),
+    Datafusion(datafusion::error::DataFusionError),
+
+    /// Module SQL needed more memory than `execution.memory_limit` allows.
+    #[error("module SQL exceeded execution.memory_limit: {0} (raise memory_limit, or enable spill so sorts, joins, and aggregations can use disk)")]
+    MemoryLimit(String),
 
     #[error("I/O error: {0}")]
     Io(#[from] std::io::Error),
 
-    #[error("HTTP request failed: {0}")]
+    #[error("HTTP request failed: {}", redact_text(&.0.to_string()))]
     Reqwest(#[from] reqwest::Error),
 
     #[error("Invalid header name: {0}")]
@@ -31,6 +37,7 @@ pub enum ApitapError {
     #[error("JSON serialization error: {0}")]
     SerdeJson(#[from] serde_json::Error),
 
+    #[cfg(feature = "postgres")]
     #[error("Database error: {0}")]
     Sqlx(#[from] sqlx::Error),
 
@@ -40,15 +47,39 @@ pub enum ApitapError {
     #[error("Arrow error: {0}")]
     Arrow(#[from] datafusion::arrow::error::ArrowError),
 
+    #[cfg(feature = "parquet")]
     #[error("Parquet error: {0}")]
     Parquet(#[from] datafusion::parquet::errors::ParquetError),
 
+    #[cfg(feature = "object_store")]
+    #[error("Object store error: {0}")]
+    ObjectStore(#[from] object_store::Error),
+
+    #[cfg(feature = "object_store")]
+    #[error("Object store path error: {0}")]
+    ObjectStorePath(#[from] object_store::path::Error),
+
+    /// Boxed: `tonic::Status` would make every `Result` in the crate larger.
+    #[cfg(feature = "grpc")]
+    #[error("gRPC error: {0}")]
+    Grpc(Box<tonic::Status>),
+
+    #[cfg(feature = "grpc")]
+    #[error("gRPC transport error: {0}")]
+    GrpcTransport(#[from] tonic::transport::Error),
+
     #[error("Serde Arrow error: {0}")]
     SerdeArrow(#[from] serde_arrow::Error),
 
     #[error("YAML error: {0}")]
     SerdeYaml(#[from] serde_yaml::Error),
 
+    #[error("XML error: {0}")]
+    Xml(#[from] quick_xml::Error),
+
+    #[error("CSV error: {0}")]
+    Csv(#[from] csv::Error),
+
     #[error("Directory walk error: {0}")]
     WalkDir(#[from] walkdir::Error),
 
@@ -76,6 +107,9 @@ pub enum ApitapError {
     #[error("Pipeline error: {0}")]
     PipelineError(String),
 
+    #[error("Timeout: {0}")]
+    Timeout(String),
+
     #[error("Unsupported sink: {0}")]
     UnsupportedSink(String),
 
@@ -85,13 +119,40 @@ pub enum ApitapError {
     #[error("Data Type Error: {0}")]
     DataTypeError(String),
 
+    #[error("schema contract violated for {0}")]
+    SchemaContract(String),
+
+    #[error("post_check failed on {0}")]
+    PostCheck(String),
+
+    #[error("OAuth2 token request failed: {}", redact_text(.0))]
+    TokenError(String),
+
     #[error("Tracing From Env Error: {0}")]
     FromEnvError(#[from] FromEnvError),
 
-    #[error("Reqwest Middleware Error: {0}")]
+    #[error("Reqwest Middleware Error: {}", redact_text(&.0.to_string()))]
     ReqwestMiddlewareError(#[from] reqwest_middleware::Error),
 }
 
+impl From<datafusion::error::DataFusionError> for ApitapError {
+    fn from(error: datafusion::error::DataFusionError) -> Self {
+        match error.find_root() {
+            datafusion::error::DataFusionError::ResourcesExhausted(message) => {
+                ApitapError::MemoryLimit(message.clone())
+            }
+            _ => ApitapError::Datafusion(error),
+        }
+    }
+}
+
+#[cfg(feature = "grpc")]
+impl From<tonic::Status> for ApitapError {
+    fn from(status: tonic::Status) -> Self {
+        ApitapError::Grpc(Box::new(status))
+    }
+}
+
 /// Convenience Result type that uses ApitapError
 pub type Result<T> = std::result::Result<T, ApitapError>;
 
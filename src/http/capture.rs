@@ -0,0 +1,240 @@
+//! Request/response capture ("tap") for debugging a source.
+//!
+//! With `--capture-dir DIR`, every page request is recorded as two numbered
+//! files, whatever the pagination mode:
+//!
+//! ```text
+//! DIR/000001-orders.request.json    method, URL, headers, body, response status
+//! DIR/000001-orders.response.json   the response body as received
+//! ```
+//!
+//! Sensitive headers and query parameters are masked as in the logs. Capture
+//! stops, with one warning, once the files reach `--capture-max-mb`; the body
+//! being written at that point is cut short.
+
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::sync::Arc;
+
+use reqwest::header::HeaderMap;
+use serde_json::{json, Map, Value};
+use tracing::{debug, warn};
+
+use crate::http::fetcher::ResponseFormat;
+use crate::utils::redact::{self, Redactor};
+
+/// Default cap on the bytes written under a capture directory.
+pub const DEFAULT_CAPTURE_MAX_BYTES: u64 = 100 * 1024 * 1024;
+
+/// A capture directory shared by every source of the process.
+#[derive(Debug)]
+pub struct Capture {
+    dir: PathBuf,
+    max_bytes: u64,
+    next: AtomicU64,
+    written: AtomicU64,
+    full: AtomicBool,
+}
+
+impl Capture {
+    /// Captures into `dir` (created on first use) until `max_bytes` are written.
+    pub fn new(dir: impl Into<PathBuf>, max_bytes: u64) -> Self {
+        Self {
+            dir: dir.into(),
+            max_bytes,
+            next: AtomicU64::new(1),
+            written: AtomicU64::new(0),
+            full: AtomicBool::new(false),
+        }
+    }
+
+    pub fn dir(&self) -> &Path {
+        &self.dir
+    }
+
+    /// Bytes written so far.
+    pub fn written(&self) -> u64 {
+        self.written.load(Ordering::Relaxed)
+    }
+
+    /// Capture of one source's requests; `headers` are the source's
+    /// configured headers, which the client adds to every request.
+    pub fn for_source(
+        self: &Arc<Self>,
+        source: &str,
+        headers: Vec<(String, String)>,
+    ) -> Arc<SourceCapture> {
+        Arc::new(SourceCapture {
+            capture: Arc::clone(self),
+            source: file_safe(source),
+            headers,
+        })
+    }
+
+    /// Takes up to `len` bytes of the budget and returns how many were
+    /// granted. Warns once when the budget runs out.
+    fn reserve(&self, len: u64) -> u64 {
+        let mut granted = 0;
+        let _ = self
+            .written
+            .fetch_update(Ordering::Relaxed, Ordering::Relaxed, |written| {
+                granted = len.min(self.max_bytes.saturating_sub(written));
+                Some(written + granted)
+            });
+        if granted < len && !self.full.swap(true, Ordering::Relaxed) {
+            warn!(
+                dir = %self.dir.display(),
+                max_bytes = self.max_bytes,
+                "capture limit reached; later requests are not captured"
+            );
+        }
+        granted
+    }
+
+    fn is_full(&self) -> bool {
+        self.full.load(Ordering::Relaxed)
+    }
+
+    /// Appends as much of `bytes` to `path` as the budget allows.
+    fn append(&self, path: &Path, bytes: &[u8]) {
+        let granted = self.reserve(bytes.len() as u64) as usize;
+        if granted == 0 && !bytes.is_empty() {
+            return;
+        }
+        let result = std::fs::create_dir_all(&self.dir).and_then(|()| {
+            std::fs::OpenOptions::new()
+                .create(true)
+                .append(true)
+                .open(path)?
+                .write_all(&bytes[..granted])
+        });
+        if let Err(e) = result {
+            warn!(path = %path.display(), error = %e, "could not write capture file");
+        }
+    }
+}
+
+/// [`Capture`] of one source, carried by its request template.
+#[derive(Debug)]
+pub struct SourceCapture {
+    capture: Arc<Capture>,
+    source: String,
+    headers: Vec<(String, String)>,
+}
+
+impl SourceCapture {
+    /// Writes the request file of one exchange and returns where its
+    /// response body goes, or `None` once the capture is full.
+    ///
+    /// `headers` are the headers set on this request on top of the source's.
+    pub fn record(
+        &self,
+        method: &reqwest::Method,
+        headers: &HeaderMap,
+        body: Option<&Value>,
+        response: &reqwest::Response,
+        format: ResponseFormat,
+    ) -> Option<Arc<BodyCapture>> {
+        if self.capture.is_full() {
+            return None;
+        }
+        let seq = self.capture.next.fetch_add(1, Ordering::Relaxed);
+        let stem = format!("{seq:06}-{}", self.source);
+        let redactor = redact::current();
+
+        let mut request_headers: Vec<(String, String)> = self.headers.clone();
+        request_headers.extend(header_pairs(headers));
+        let mut record = json!({
+            "method": method.as_str(),
+            "url": redactor.redact_url(response.url().as_str()),
+            "headers": masked(&redactor, request_headers),
+            "response": {
+                "status": response.status().as_u16(),
+                "headers": masked(&redactor, header_pairs(response.headers())),
+            },
+        });
+        if let Some(body) = body {
+            record["body"] = body.clone();
+        }
+        let text = serde_json::to_vec_pretty(&record).unwrap_or_default();
+        let path = self.capture.dir.join(format!("{stem}.request.json"));
+        self.capture.append(&path, &text);
+        if self.capture.is_full() {
+            return None;
+        }
+
+        let ndjson = response
+            .headers()
+            .get(reqwest::header::CONTENT_TYPE)
+            .and_then(|v| v.to_str().ok())
+            .is_some_and(|ct| ct.contains("ndjson"));
+        let extension = match format {
+            ResponseFormat::Json if ndjson => "ndjson",
+            ResponseFormat::NdjsonStream => "ndjson",
+            ResponseFormat::Json => "json",
+            ResponseFormat::Xml => "xml",
+            ResponseFormat::Csv => "csv",
+            ResponseFormat::Tsv => "tsv",
+        };
+        debug!(request = %path.display(), "captured request");
+        Some(Arc::new(BodyCapture {
+            capture: Arc::clone(&self.capture),
+            path: self
+                .capture
+                .dir
+                .join(format!("{stem}.response.{extension}")),
+        }))
+    }
+}
+
+/// The response body file of one captured exchange.
+#[derive(Debug)]
+pub struct BodyCapture {
+    capture: Arc<Capture>,
+    path: PathBuf,
+}
+
+impl BodyCapture {
+    /// Appends a chunk of the body; call once for a buffered body.
+    pub fn write(&self, bytes: &[u8]) {
+        self.capture.append(&self.path, bytes);
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+}
+
+fn header_pairs(headers: &HeaderMap) -> impl Iterator<Item = (String, String)> + '_ {
+    headers.iter().map(|(name, value)| {
+        (
+            name.to_string(),
+            String::from_utf8_lossy(value.as_bytes()).into_owned(),
+        )
+    })
+}
+
+fn masked(redactor: &Redactor, headers: impl IntoIterator<Item = (String, String)>) -> Value {
+    let map: Map<String, Value> = headers
+        .into_iter()
+        .map(|(name, value)| {
+            let value = redactor.redact_header(&name, &value).to_string();
+            (name, Value::String(value))
+        })
+        .collect();
+    Value::Object(map)
+}
+
+/// `name` with everything but ASCII letters, digits, `-` and `_` replaced.
+fn file_safe(name: &str) -> String {
+    name.chars()
+        .map(|c| {
+            if c.is_ascii_alphanumeric() || c == '-' || c == '_' {
+                c
+            } else {
+                '_'
+            }
+        })
+        .collect()
+}
@@ -1,31 +1,664 @@
+use crate::config::templating::substitute_source;
 use crate::errors::{ApitapError, Result};
+use crate::http::capture::{BodyCapture, SourceCapture};
+use crate::http::signing::RequestSigner;
+use crate::http::stop::StopWhen;
+use crate::pipeline::conditional::ConditionalRequest;
+use crate::pipeline::split::SplitTee;
+use crate::utils::constant_columns::ConstantColumns;
 use crate::utils::datafusion_ext::{
-    get_shared_context, DataFrameExt, JsonStreamType, JsonValueExt, QueryResultStream,
+    sample_fields, session_context, DataFrameExt, JsonStreamType, JsonValueExt, QueryResultStream,
 };
-use crate::utils::schema::infer_schema_from_values;
+use crate::utils::dictionary::{decode_batch, decoded_schema, DictionaryEncoding};
+use crate::utils::duplicate_keys::DuplicateKeys;
+use crate::utils::ejson::unwrap_extended;
+use crate::utils::execution::ExecutionOpts;
+use crate::utils::fields::FieldMapping;
+use crate::utils::flatten::Flatten;
+use crate::utils::http_retry::AttemptTally;
+use crate::utils::lenient_json::LenientJson;
+use crate::utils::metadata::MetadataStamp;
+use crate::utils::nulls::NullHandling;
+use crate::utils::numbers::NumberHandling;
+use crate::utils::progress::WriteProgress;
+use crate::utils::redact::redact_url;
+use crate::utils::schema::{
+    infer_schema_from_values, raw_json_schema, wrap_raw_json, STREAM_SAMPLE_SIZE,
+};
+use crate::utils::schema_contract::SchemaContract;
 use crate::utils::table_provider::JsonStreamTableProvider;
-use crate::utils::{http_retry, schema};
+use crate::utils::transform::RecordTransform;
+use crate::utils::{http_retry, json_path, schema};
 use crate::writer::{DataWriter, WriteMode};
 use async_trait::async_trait;
 use datafusion::arrow;
-use datafusion::arrow::datatypes::SchemaRef;
+use datafusion::arrow::datatypes::{Schema, SchemaRef};
 use futures::stream::{self, BoxStream, StreamExt, TryStreamExt};
 use futures::Stream;
-use reqwest::header::CONTENT_TYPE;
+use reqwest::header::{HeaderMap, HeaderName, HeaderValue, CONTENT_ENCODING, CONTENT_TYPE};
 use reqwest::Client;
+use reqwest::StatusCode;
 use serde::{Deserialize, Serialize};
 use serde_json::Value;
 use std::collections::VecDeque;
+use std::io::Write;
 use std::pin::Pin;
-use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::atomic::{AtomicBool, AtomicU64, AtomicUsize, Ordering};
 use std::sync::Arc;
-use tokio::sync::Mutex;
+use tokio::sync::{Mutex, OwnedSemaphorePermit, Semaphore};
 use tokio_util::{
     codec::{FramedRead, LinesCodec},
     io::StreamReader,
 };
 use tracing::{debug, debug_span, error, info, info_span, trace, warn};
 
+// =========================== Request shape ===================================
+
+/// HTTP method used for page requests.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "UPPERCASE")]
+pub enum HttpMethod {
+    #[default]
+    Get,
+    Post,
+}
+
+impl From<HttpMethod> for reqwest::Method {
+    fn from(m: HttpMethod) -> Self {
+        match m {
+            HttpMethod::Get => reqwest::Method::GET,
+            HttpMethod::Post => reqwest::Method::POST,
+        }
+    }
+}
+
+/// Where pagination parameters (`offset`, `page`, `cursor`, ...) are sent.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum PaginationIn {
+    /// Appended to the query string (default).
+    #[default]
+    Query,
+    /// Deep-merged into the JSON request body.
+    Body,
+    /// Only where the body references them, as `{{ cursor }}`, `{{ page }}`,
+    /// ... (see [`render_page_vars`]); nothing is added to the request.
+    Template,
+}
+
+/// How a cursor read from a response header is sent back.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum CursorIn {
+    /// As the `cursor_param` pagination parameter, placed per `pagination_in` (default).
+    #[default]
+    Param,
+    /// As a request header named `cursor_param`.
+    Header,
+}
+
+/// Encoding of response bodies.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ResponseFormat {
+    /// JSON, or NDJSON when the content type says so (default).
+    #[default]
+    Json,
+    /// XML, converted with [`crate::utils::xml::xml_to_json`] before `data_path` applies.
+    Xml,
+    /// Comma-separated rows, one record per line.
+    Csv,
+    /// Tab-separated rows, one record per line.
+    Tsv,
+    /// NDJSON whatever the content type: one JSON value per line, read as
+    /// the body arrives, for unbounded export streams.
+    NdjsonStream,
+}
+
+impl ResponseFormat {
+    /// The format a `Content-Type` header value names, if any. JSON and
+    /// NDJSON types both map to [`ResponseFormat::Json`], which reads NDJSON
+    /// by its content type.
+    ///
+    /// ```
+    /// use apitap::http::fetcher::ResponseFormat;
+    ///
+    /// assert_eq!(ResponseFormat::from_content_type("text/csv; charset=utf-8"), Some(ResponseFormat::Csv));
+    /// assert_eq!(ResponseFormat::from_content_type("application/atom+xml"), Some(ResponseFormat::Xml));
+    /// assert_eq!(ResponseFormat::from_content_type("application/x-ndjson"), Some(ResponseFormat::Json));
+    /// assert_eq!(ResponseFormat::from_content_type("application/octet-stream"), None);
+    /// ```
+    pub fn from_content_type(content_type: &str) -> Option<Self> {
+        let mime = content_type
+            .split(';')
+            .next()
+            .unwrap_or_default()
+            .trim()
+            .to_ascii_lowercase();
+        match mime.as_str() {
+            "text/csv" | "application/csv" => Some(ResponseFormat::Csv),
+            "text/tab-separated-values" => Some(ResponseFormat::Tsv),
+            "text/xml" | "application/xml" => Some(ResponseFormat::Xml),
+            m if m.ends_with("+xml") => Some(ResponseFormat::Xml),
+            m if m.ends_with("json") || m.contains("ndjson") || m.contains("jsonl") => {
+                Some(ResponseFormat::Json)
+            }
+            _ => None,
+        }
+    }
+
+    /// The media type requested for this format when a source sets no `accept`.
+    ///
+    /// ```
+    /// use apitap::http::fetcher::ResponseFormat;
+    ///
+    /// assert_eq!(ResponseFormat::Json.media_type(), "application/json");
+    /// assert_eq!(ResponseFormat::Csv.media_type(), "text/csv");
+    /// ```
+    pub fn media_type(self) -> &'static str {
+        match self {
+            ResponseFormat::Json => "application/json",
+            ResponseFormat::Xml => "application/xml",
+            ResponseFormat::Csv => "text/csv",
+            ResponseFormat::Tsv => "text/tab-separated-values",
+            ResponseFormat::NdjsonStream => "application/x-ndjson",
+        }
+    }
+}
+
+/// Content encoding of request bodies.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum BodyEncoding {
+    /// Plain JSON (default).
+    #[default]
+    Identity,
+    /// Gzip-compressed JSON sent with `Content-Encoding: gzip`.
+    Gzip,
+}
+
+impl BodyEncoding {
+    /// Sets `body` as the JSON body of `builder`, compressed per `self`.
+    ///
+    /// The body is kept as bytes so the retry middleware can resend it.
+    pub fn apply(
+        self,
+        builder: reqwest_middleware::RequestBuilder,
+        body: &Value,
+    ) -> Result<reqwest_middleware::RequestBuilder> {
+        match self {
+            BodyEncoding::Identity => Ok(builder.json(body)),
+            BodyEncoding::Gzip => {
+                let json = serde_json::to_vec(body)?;
+                let mut encoder =
+                    flate2::write::GzEncoder::new(Vec::new(), flate2::Compression::default());
+                encoder.write_all(&json)?;
+                Ok(builder
+                    .header(CONTENT_TYPE, "application/json")
+                    .header(CONTENT_ENCODING, "gzip")
+                    .body(encoder.finish()?))
+            }
+        }
+    }
+}
+
+/// How the value at `data_path` is turned into records.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum RecordsAs {
+    /// An array yields one record per element; any other value is one record (default).
+    #[default]
+    Array,
+    /// An object keyed by ID, e.g. `{"123": {...}, "456": {...}}`, yields one
+    /// record per value.
+    ObjectValues,
+}
+
+/// Parsing options for `csv`/`tsv` bodies.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CsvOptions {
+    /// Field separator; defaults to `,` for `csv` and tab for `tsv`.
+    #[serde(default)]
+    pub delimiter: Option<char>,
+    /// Whether the first row names the columns; otherwise `column_1`, `column_2`, ...
+    #[serde(default = "default_csv_has_header")]
+    pub has_header: bool,
+}
+
+fn default_csv_has_header() -> bool {
+    true
+}
+
+impl Default for CsvOptions {
+    fn default() -> Self {
+        Self {
+            delimiter: None,
+            has_header: true,
+        }
+    }
+}
+
+/// Final query string pairs and optional JSON body for one request.
+pub type RequestParts = (Vec<(String, String)>, Option<Value>);
+
+/// Method, body template, pagination placement, and response format shared by
+/// every page request.
+#[derive(Debug, Clone, Default)]
+pub struct RequestTemplate {
+    pub method: HttpMethod,
+    /// JSON body sent with each request (already env/template substituted);
+    /// pagination placeholders are rendered per request from `page_vars`.
+    pub body: Option<Value>,
+    /// Compression applied to `body` on the wire.
+    pub body_encoding: BodyEncoding,
+    pub pagination_in: PaginationIn,
+    /// Dotted path inside the body where pagination params are merged; root when `None`.
+    pub body_path: Option<String>,
+    /// How response bodies are decoded.
+    pub format: ResponseFormat,
+    /// Delimiter and header settings when `format` is `csv` or `tsv`.
+    pub csv: CsvOptions,
+    /// How the value at `data_path` becomes records.
+    pub records_as: RecordsAs,
+    /// With [`RecordsAs::ObjectValues`], column that receives each record's key.
+    pub record_key_column: Option<String>,
+    /// Rewrites `NaN`/`Infinity` and oversized numbers before JSON bodies are
+    /// parsed; strict when `None`.
+    pub lenient_json: Option<LenientJson>,
+    /// How keys repeated within a JSON object are handled.
+    pub duplicate_keys: DuplicateKeys,
+    /// Runs on every outgoing request, e.g. to add an HMAC signature header.
+    pub signer: Option<Arc<dyn RequestSigner>>,
+    /// Validators for the first request, for sources with `conditional: true`.
+    pub conditional: Option<Arc<ConditionalRequest>>,
+    /// Records each request and response body, with `--capture-dir`.
+    pub capture: Option<Arc<SourceCapture>>,
+    /// Process-wide cap on requests in flight; a permit is held from sending
+    /// until the body is read.
+    pub request_limit: Option<Arc<Semaphore>>,
+    /// Headers of one page request, such as a cursor sent with
+    /// [`CursorIn::Header`]; set by the fetcher.
+    pub page_headers: Vec<(String, String)>,
+    /// Pagination state of one page request (`page`, `offset`, `cursor`, ...)
+    /// rendered into the body; set by the fetcher.
+    pub page_vars: Vec<(String, Value)>,
+    /// Receives every parsed response, for sources with `splits`; set per run.
+    pub splits: Option<Arc<SplitTee>>,
+    /// Ends pagination after a page that matches, besides the usual signals.
+    pub stop_when: Option<StopWhen>,
+    /// Whether the body of one page request matched `stop_when`; set by the
+    /// fetcher.
+    pub page_stop: Option<Arc<AtomicBool>>,
+    /// Records left to fetch, shared by every request of a source; set per
+    /// run with `--sample`.
+    pub record_budget: Option<Arc<RecordBudget>>,
+}
+
+/// A cap on the records fetched for one source, across all of its requests
+/// and pages.
+///
+/// Clones of an `Arc<RecordBudget>` share the count, so one budget can span
+/// every `path_params` request of a source.
+///
+/// # Example
+///
+/// ```
+/// use apitap::http::fetcher::RecordBudget;
+///
+/// let budget = RecordBudget::new(3);
+/// assert_eq!(budget.claim(2), 2);
+/// assert_eq!(budget.claim(2), 1);
+/// assert!(budget.exhausted());
+/// assert_eq!(budget.claim(1), 0);
+/// ```
+#[derive(Debug)]
+pub struct RecordBudget {
+    max: u64,
+    taken: AtomicU64,
+}
+
+impl RecordBudget {
+    pub fn new(max: u64) -> Self {
+        Self {
+            max,
+            taken: AtomicU64::new(0),
+        }
+    }
+
+    /// Claims up to `n` records and returns how many may be kept.
+    pub fn claim(&self, n: usize) -> usize {
+        let claim = |taken: u64| Some(self.max.min(taken + n as u64));
+        let taken = self
+            .taken
+            .fetch_update(Ordering::Relaxed, Ordering::Relaxed, claim)
+            .unwrap_or_else(|taken| taken);
+        (self.max.min(taken + n as u64) - taken) as usize
+    }
+
+    /// Whether every record has been claimed.
+    pub fn exhausted(&self) -> bool {
+        self.taken.load(Ordering::Relaxed) >= self.max
+    }
+}
+
+impl RequestTemplate {
+    /// Parses a whole response body into a JSON value according to `format`.
+    ///
+    /// CSV and TSV bodies become an array of row objects.
+    pub fn parse_body(&self, body: &[u8]) -> Result<Value> {
+        match self.format {
+            ResponseFormat::Json | ResponseFormat::NdjsonStream => self.parse_json(body),
+            ResponseFormat::Xml => crate::utils::xml::xml_to_json(body),
+            ResponseFormat::Csv | ResponseFormat::Tsv => {
+                let default = if self.format == ResponseFormat::Tsv {
+                    '\t'
+                } else {
+                    ','
+                };
+                let delimiter = self.csv.delimiter.unwrap_or(default);
+                let delimiter = u8::try_from(delimiter).map_err(|_| {
+                    ApitapError::ConfigError(format!(
+                        "csv delimiter must be a single-byte character, got {delimiter:?}"
+                    ))
+                })?;
+                crate::utils::csv::csv_to_json(body, delimiter, self.csv.has_header)
+            }
+        }
+    }
+
+    /// Parses a JSON document, leniently when `lenient_json` is set, with
+    /// repeated keys handled per `duplicate_keys`.
+    pub fn parse_json(&self, body: &[u8]) -> Result<Value> {
+        match &self.lenient_json {
+            Some(lenient) => self.duplicate_keys.parse(&lenient.rewrite(body)),
+            None => self.duplicate_keys.parse(body),
+        }
+    }
+
+    /// Splits the value found at `data_path` into records according to `records_as`.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use apitap::http::fetcher::{RecordsAs, RequestTemplate};
+    /// use serde_json::json;
+    ///
+    /// let template = RequestTemplate {
+    ///     records_as: RecordsAs::ObjectValues,
+    ///     record_key_column: Some("_id".into()),
+    ///     ..RequestTemplate::default()
+    /// };
+    /// assert_eq!(
+    ///     template.records(json!({"123": {"name": "Ada"}})),
+    ///     vec![json!({"_id": "123", "name": "Ada"})]
+    /// );
+    /// ```
+    pub fn records(&self, target: Value) -> Vec<Value> {
+        match target {
+            Value::Null => Vec::new(),
+            Value::Array(items) => items,
+            Value::Object(map) if self.records_as == RecordsAs::ObjectValues => map
+                .into_iter()
+                .map(|(key, value)| match (value, &self.record_key_column) {
+                    (Value::Object(mut record), Some(column)) => {
+                        record.insert(column.clone(), Value::String(key));
+                        Value::Object(record)
+                    }
+                    (value, _) => value,
+                })
+                .collect(),
+            other => vec![other],
+        }
+    }
+
+    /// Hands a parsed response body to the source's `splits`, if any.
+    ///
+    /// Called once per body, before `data_path` is applied.
+    pub fn tee_splits(&self, body: &Value) {
+        if let Some(splits) = &self.splits {
+            splits.send(body, self);
+        }
+    }
+
+    /// Whether `target` is a record collection rather than a single record.
+    fn is_collection(&self, target: &Value) -> bool {
+        target.is_array() || (self.records_as == RecordsAs::ObjectValues && target.is_object())
+    }
+
+    /// Claims up to `n` records of `record_budget` and returns how many may
+    /// be kept.
+    fn claim_records(&self, n: usize) -> usize {
+        match &self.record_budget {
+            Some(budget) => budget.claim(n),
+            None => n,
+        }
+    }
+
+    /// Whether `record_budget` is spent, so no further request is needed.
+    pub fn records_exhausted(&self) -> bool {
+        self.record_budget.as_ref().is_some_and(|b| b.exhausted())
+    }
+
+    /// Ends `records` once `record_budget` is spent.
+    pub fn capped(
+        &self,
+        records: BoxStream<'static, Result<Value>>,
+    ) -> BoxStream<'static, Result<Value>> {
+        let Some(budget) = self.record_budget.clone() else {
+            return records;
+        };
+        records
+            .take_while(move |item| futures::future::ready(item.is_err() || budget.claim(1) == 1))
+            .boxed()
+    }
+
+    /// A copy for one page request whose body renders `vars`.
+    pub fn for_page(&self, vars: &[(&str, Value)]) -> RequestTemplate {
+        RequestTemplate {
+            page_vars: vars
+                .iter()
+                .map(|(name, value)| (name.to_string(), value.clone()))
+                .collect(),
+            page_stop: self.stop_when.as_ref().map(|_| Arc::default()),
+            ..self.clone()
+        }
+    }
+
+    /// Notes whether a parsed response body matches `stop_when`.
+    fn check_stop(&self, body: &Value) {
+        if let (Some(stop_when), Some(page_stop)) = (&self.stop_when, &self.page_stop) {
+            if stop_when.matches_body(body) {
+                page_stop.store(true, Ordering::Relaxed);
+            }
+        }
+    }
+
+    /// Whether the page this request fetched, holding `records` records of
+    /// `page_size` requested, is the last one per `stop_when`.
+    pub fn is_last_page(&self, records: usize, page_size: u64) -> bool {
+        let Some(stop_when) = &self.stop_when else {
+            return false;
+        };
+        stop_when.matches_count(records, page_size)
+            || self
+                .page_stop
+                .as_ref()
+                .is_some_and(|hit| hit.load(Ordering::Relaxed))
+    }
+
+    /// Splits pagination params between the query string and the body for one
+    /// request, after rendering the page's variables into the body.
+    pub fn build(
+        &self,
+        query: &[(String, String)],
+        page_params: &[(String, Value)],
+    ) -> Result<RequestParts> {
+        let body = match &self.body {
+            Some(body) if !self.page_vars.is_empty() => {
+                Some(render_page_vars(body, &self.page_vars))
+            }
+            other => other.clone(),
+        };
+        match self.pagination_in {
+            PaginationIn::Query => {
+                let mut q = query.to_vec();
+                q.extend(page_params.iter().map(|(k, v)| (k.clone(), param_text(v))));
+                Ok((q, body))
+            }
+            PaginationIn::Body => {
+                let body = merge_into_body(body.as_ref(), self.body_path.as_deref(), page_params)?;
+                Ok((query.to_vec(), Some(body)))
+            }
+            PaginationIn::Template => Ok((query.to_vec(), body)),
+        }
+    }
+}
+
+/// Replaces `{{ name }}` placeholders in the string values of `body` with the
+/// page's pagination state.
+///
+/// A string that is exactly one placeholder becomes the variable's JSON value,
+/// so numbers stay numbers and the first page's cursor is `null`; a
+/// placeholder inside longer text is replaced by the value's text. Unknown
+/// names are left as written. The variables per pagination kind are:
+///
+/// - `limit_offset`: `offset`, `limit`, `page`
+/// - `page_number`: `page`, `per_page`
+/// - `cursor`: `cursor`, `page_size`, `page`
+///
+/// `page` counts requests from 1, except with `page_number` where it is the
+/// page number sent.
+///
+/// # Example
+///
+/// ```
+/// use apitap::http::fetcher::render_page_vars;
+/// use serde_json::{json, Value};
+///
+/// let body = json!({"after": "{{ cursor }}", "first": "{{ page_size }}", "note": "page {{ page }}"});
+/// let vars = vec![
+///     ("cursor".to_string(), Value::Null),
+///     ("page_size".to_string(), json!(50)),
+///     ("page".to_string(), json!(1)),
+/// ];
+/// assert_eq!(
+///     render_page_vars(&body, &vars),
+///     json!({"after": null, "first": 50, "note": "page 1"})
+/// );
+/// ```
+pub fn render_page_vars(body: &Value, vars: &[(String, Value)]) -> Value {
+    match body {
+        Value::String(text) => {
+            let lookup = |name: &str| vars.iter().find(|(n, _)| n == name).map(|(_, v)| v);
+            if let Some(value) = page_var_name(text.trim()).and_then(lookup) {
+                return value.clone();
+            }
+            let mut out = String::with_capacity(text.len());
+            let mut rest = text.as_str();
+            while let Some(start) = rest.find("{{") {
+                let Some(len) = rest[start..].find("}}").map(|end| end + 2) else {
+                    break;
+                };
+                let placeholder = &rest[start..start + len];
+                out.push_str(&rest[..start]);
+                match page_var_name(placeholder).and_then(lookup) {
+                    Some(Value::String(s)) => out.push_str(s),
+                    Some(Value::Null) => {}
+                    Some(other) => out.push_str(&other.to_string()),
+                    None => out.push_str(placeholder),
+                }
+                rest = &rest[start + len..];
+            }
+            out.push_str(rest);
+            Value::String(out)
+        }
+        Value::Array(items) => {
+            Value::Array(items.iter().map(|v| render_page_vars(v, vars)).collect())
+        }
+        Value::Object(map) => Value::Object(
+            map.iter()
+                .map(|(k, v)| (k.clone(), render_page_vars(v, vars)))
+                .collect(),
+        ),
+        other => other.clone(),
+    }
+}
+
+/// The variable name of a `{{ name }}` placeholder, if `text` is exactly one.
+fn page_var_name(text: &str) -> Option<&str> {
+    let name = text.strip_prefix("{{")?.strip_suffix("}}")?.trim();
+    (!name.is_empty() && name.chars().all(|c| c.is_ascii_alphanumeric() || c == '_'))
+        .then_some(name)
+}
+
+/// Query-string text of a pagination param: strings as written, numbers as digits.
+fn param_text(value: &Value) -> String {
+    match value {
+        Value::String(s) => s.clone(),
+        other => other.to_string(),
+    }
+}
+
+/// Deep-merges `params` into a copy of `body` at the dotted `path`, creating
+/// intermediate objects as needed. Values keep their JSON type, so offsets and
+/// limits go out as numbers and cursor tokens as strings, even numeric-looking
+/// ones such as `"000123"`.
+///
+/// # Example
+///
+/// ```
+/// use apitap::http::fetcher::merge_into_body;
+/// use serde_json::json;
+///
+/// let body = json!({"filter": {"status": "open"}});
+/// let merged = merge_into_body(
+///     Some(&body),
+///     Some("paging"),
+///     &[
+///         ("offset".into(), json!(100)),
+///         ("limit".into(), json!(50)),
+///         ("cursor".into(), json!("000123")),
+///     ],
+/// )
+/// .unwrap();
+/// assert_eq!(
+///     merged,
+///     json!({"filter": {"status": "open"}, "paging": {"offset": 100, "limit": 50, "cursor": "000123"}})
+/// );
+/// ```
+pub fn merge_into_body(
+    body: Option<&Value>,
+    path: Option<&str>,
+    params: &[(String, Value)],
+) -> Result<Value> {
+    let mut root = body
+        .cloned()
+        .unwrap_or_else(|| Value::Object(serde_json::Map::new()));
+
+    let mut target = &mut root;
+    for segment in path.unwrap_or("").split('.').filter(|s| !s.is_empty()) {
+        let Value::Object(map) = target else {
+            return Err(ApitapError::ConfigError(format!(
+                "cannot merge pagination into body: '{segment}' parent is not an object"
+            )));
+        };
+        target = map
+            .entry(segment.to_string())
+            .or_insert_with(|| Value::Object(serde_json::Map::new()));
+    }
+
+    let Value::Object(map) = target else {
+        return Err(ApitapError::ConfigError(
+            "cannot merge pagination into body: target is not an object".to_string(),
+        ));
+    };
+    for (k, v) in params {
+        map.insert(k.clone(), v.clone());
+    }
+
+    Ok(root)
+}
+
 // =========================== NDJSON helper ===================================
 
 /// Stream an HTTP response as NDJSON and flatten an optional JSON pointer (`/data`, etc.).
@@ -36,38 +669,274 @@ pub async fn ndjson_stream_qs(
     query: &[(String, String)],
     data_path: Option<&str>,
     config_retry: &crate::pipeline::Retry,
+) -> Result<BoxStream<'static, Result<Value>>> {
+    ndjson_stream_request(
+        client,
+        url,
+        query,
+        &[],
+        &RequestTemplate::default(),
+        data_path,
+        config_retry,
+    )
+    .await
+}
+
+/// Like [`ndjson_stream_qs`], but shapes the request with a [`RequestTemplate`]
+/// so pagination params can travel in a POST body instead of the query string.
+pub async fn ndjson_stream_request(
+    client: &reqwest::Client,
+    url: &str,
+    query: &[(String, String)],
+    page_params: &[(String, Value)],
+    request: &RequestTemplate,
+    data_path: Option<&str>,
+    config_retry: &crate::pipeline::Retry,
+) -> Result<BoxStream<'static, Result<Value>>> {
+    counted_stream_request(
+        client,
+        url,
+        query,
+        page_params,
+        request,
+        data_path,
+        config_retry,
+        Arc::new(TransferCounters::default()),
+    )
+    .await
+}
+
+/// [`ndjson_stream_request`] that reports requests, pages, and bytes into `counters`.
+#[allow(clippy::too_many_arguments)]
+async fn counted_stream_request(
+    client: &reqwest::Client,
+    url: &str,
+    query: &[(String, String)],
+    page_params: &[(String, Value)],
+    request: &RequestTemplate,
+    data_path: Option<&str>,
+    config_retry: &crate::pipeline::Retry,
+    counters: Arc<TransferCounters>,
 ) -> Result<BoxStream<'static, Result<Value>>> {
     // Instrument HTTP/NDJSON parsing for tracing with source and optional data_path
     let span = debug_span!("http.ndjson_stream", source = %url, query_len = query.len());
     let _g = span.enter();
-    let client_with_retry = http_retry::build_client_with_retry(client.clone(), config_retry);
+    let client_with_retry =
+        http_retry::build_client_with_signer(client.clone(), config_retry, request.signer.clone());
+
+    // Nothing is sent once the record budget is spent, so pagination sees an empty page
+    if request.records_exhausted() {
+        return Ok(stream::empty().boxed());
+    }
+    let resp = send_request(
+        &client_with_retry,
+        url,
+        query,
+        page_params,
+        request,
+        &counters,
+    )
+    .await?;
+    let records = response_to_stream(resp, data_path, request, counters).await?;
+    Ok(request.capped(records))
+}
+
+/// Running request/byte/page totals shared by every page stream of a fetcher.
+#[derive(Debug, Default)]
+struct TransferCounters {
+    bytes: AtomicU64,
+    /// Attempts sent, retries included; handed to each request as an
+    /// [`AttemptTally`] so the retry layer can count them.
+    requests: Arc<AtomicUsize>,
+    pages: AtomicUsize,
+    progress: Option<Arc<WriteProgress>>,
+}
+
+impl TransferCounters {
+    fn page_fetched(&self) {
+        self.pages.fetch_add(1, Ordering::Relaxed);
+        if let Some(progress) = &self.progress {
+            progress.add_page();
+        }
+    }
+
+    fn snapshot(&self) -> (u64, usize, usize) {
+        (
+            self.bytes.load(Ordering::Relaxed),
+            self.requests.load(Ordering::Relaxed),
+            self.pages.load(Ordering::Relaxed),
+        )
+    }
+
+    /// Adds everything counted since `start` (a previous [`Self::snapshot`]) to `stats`.
+    fn record_since(&self, start: (u64, usize, usize), stats: &mut FetchStats) {
+        let (bytes, requests, pages) = self.snapshot();
+        stats.total_bytes += bytes - start.0;
+        stats.request_count += requests - start.1;
+        stats.page_count += pages - start.2;
+    }
+}
+
+/// Sends one page request and fails on non-success status codes.
+async fn send_request(
+    client: &reqwest_middleware::ClientWithMiddleware,
+    url: &str,
+    query: &[(String, String)],
+    page_params: &[(String, Value)],
+    request: &RequestTemplate,
+    counters: &TransferCounters,
+) -> Result<reqwest::Response> {
+    let (query, body) = request.build(query, page_params)?;
 
     // Instrument the HTTP request/response at debug level with timing and status
-    let req_span =
-        debug_span!("http.request", method = "GET", source = %url, query_len = query.len());
+    let req_span = debug_span!(
+        "http.request",
+        method = ?request.method,
+        source = %redact_url(url),
+        query_len = query.len()
+    );
     let _req_g = req_span.enter();
     let started = std::time::Instant::now();
 
-    let resp = client_with_retry.get(url).query(query).send().await?;
+    let mut builder = client.request(request.method.into(), url).query(&query);
+    if let Some(body) = &body {
+        builder = request.body_encoding.apply(builder, body)?;
+    }
+    let mut sent = HeaderMap::new();
+    for (name, value) in &request.page_headers {
+        let name = HeaderName::from_bytes(name.as_bytes())
+            .map_err(|e| ApitapError::PaginationError(format!("invalid header '{name}': {e}")))?;
+        let value = HeaderValue::from_str(value).map_err(|e| {
+            ApitapError::PaginationError(format!("invalid value for header '{name}': {e}"))
+        })?;
+        sent.insert(name, value);
+    }
+    let conditional = request
+        .conditional
+        .as_ref()
+        .and_then(|c| Some((c, c.first_request_headers()?)));
+    if let Some((_, headers)) = &conditional {
+        sent.extend(headers.clone());
+    }
+    if !sent.is_empty() {
+        builder = builder.headers(sent.clone());
+    }
+    let permit = acquire_request_permit(request.request_limit.as_ref()).await;
+    let mut resp = builder
+        .with_extension(AttemptTally(Arc::clone(&counters.requests)))
+        .send()
+        .await?;
+    if let Some(permit) = permit {
+        // Released once the body has been read
+        resp.extensions_mut().insert(Arc::new(permit));
+    }
+    let captured = request.capture.as_ref().and_then(|c| {
+        c.record(
+            &request.method.into(),
+            &sent,
+            body.as_ref(),
+            &resp,
+            request.format,
+        )
+    });
+    if let Some((c, _)) = conditional {
+        c.record(&resp);
+    }
 
     let status = resp.status();
     let elapsed = started.elapsed();
     debug!(status = %status, elapsed_ms = elapsed.as_millis(), "http response received");
 
-    let resp = resp.error_for_status()?;
+    let resp = match captured {
+        Some(captured) => {
+            resp.extensions_mut().insert(Arc::clone(&captured));
+            error_for_status_captured(resp, &captured).await?
+        }
+        None => resp.error_for_status()?,
+    };
+    counters.page_fetched();
+    Ok(resp)
+}
+
+/// `next` as an absolute URL, resolved against `current` when relative.
+fn resolve_next_url(current: &str, next: &str) -> Result<String> {
+    let base = url::Url::parse(current)
+        .map_err(|e| ApitapError::PaginationError(format!("invalid URL '{current}': {e}")))?;
+    base.join(next)
+        .map(String::from)
+        .map_err(|e| ApitapError::PaginationError(format!("invalid next page URL '{next}': {e}")))
+}
+
+/// Waits for a request permit when requests are limited process-wide.
+async fn acquire_request_permit(limit: Option<&Arc<Semaphore>>) -> Option<OwnedSemaphorePermit> {
+    Arc::clone(limit?).acquire_owned().await.ok()
+}
+
+/// Like [`reqwest::Response::error_for_status`], but saves the body of an
+/// error response to `captured` first.
+async fn error_for_status_captured(
+    resp: reqwest::Response,
+    captured: &BodyCapture,
+) -> Result<reqwest::Response> {
+    match resp.error_for_status_ref() {
+        Ok(_) => Ok(resp),
+        Err(e) => {
+            captured.write(&resp.bytes().await.unwrap_or_default());
+            Err(e.into())
+        }
+    }
+}
+
+/// Reads a whole response body and parses it according to `format`.
+async fn read_body(
+    resp: reqwest::Response,
+    request: &RequestTemplate,
+    counters: &TransferCounters,
+) -> Result<Value> {
+    let captured = resp.extensions().get::<Arc<BodyCapture>>().cloned();
+    let permit = resp
+        .extensions()
+        .get::<Arc<OwnedSemaphorePermit>>()
+        .cloned();
+    let bytes = resp.bytes().await?;
+    counters
+        .bytes
+        .fetch_add(bytes.len() as u64, Ordering::Relaxed);
+    if let Some(captured) = &captured {
+        captured.write(&bytes);
+    }
+    drop(permit);
+    let v = request.parse_body(&bytes)?;
+    request.tee_splits(&v);
+    request.check_stop(&v);
+    Ok(v)
+}
+
+/// Turns a response into a stream of records, honouring NDJSON and `data_path`.
+async fn response_to_stream(
+    resp: reqwest::Response,
+    data_path: Option<&str>,
+    request: &RequestTemplate,
+    counters: Arc<TransferCounters>,
+) -> Result<BoxStream<'static, Result<Value>>> {
+    if resp.status() == StatusCode::NOT_MODIFIED {
+        debug!("304 Not Modified; no records");
+        return Ok(stream::empty().boxed());
+    }
 
-    // Heuristic: treat as NDJSON only if content-type says so
-    let is_ndjson = resp
-        .headers()
-        .get(CONTENT_TYPE)
-        .and_then(|h| h.to_str().ok())
-        .map(|ct| ct.contains("ndjson") || ct.contains("x-ndjson"))
-        .unwrap_or(false);
+    // `ndjson_stream`, or JSON whose content type says it is NDJSON
+    let is_ndjson = request.format == ResponseFormat::NdjsonStream
+        || request.format == ResponseFormat::Json
+            && resp
+                .headers()
+                .get(CONTENT_TYPE)
+                .and_then(|h| h.to_str().ok())
+                .map(|ct| ct.contains("ndjson") || ct.contains("x-ndjson"))
+                .unwrap_or(false);
 
     if !is_ndjson {
         // -------- Regular JSON (object or array) path --------
-        let bytes = resp.bytes().await?;
-        let v: Value = serde_json::from_slice(&bytes)?;
+        let v = read_body(resp, request, &counters).await?;
 
         // If data_path is provided, drill into it; else use the whole value.
         let target = if let Some(p) = data_path {
@@ -76,13 +945,7 @@ pub async fn ndjson_stream_qs(
             v
         };
 
-        let items: Vec<Value> = if let Some(arr) = target.as_array() {
-            arr.clone()
-        } else if target.is_null() {
-            Vec::new()
-        } else {
-            vec![target]
-        };
+        let items = request.records(target);
 
         debug!(items = items.len(), "parsed JSON response items");
 
@@ -92,15 +955,31 @@ pub async fn ndjson_stream_qs(
     }
 
     // -------- NDJSON path (one JSON per line) --------
+    let captured = resp.extensions().get::<Arc<BodyCapture>>().cloned();
+    let permit = resp
+        .extensions()
+        .get::<Arc<OwnedSemaphorePermit>>()
+        .cloned();
     let byte_stream = resp
         .bytes_stream()
+        .inspect_ok(move |chunk| {
+            counters
+                .bytes
+                .fetch_add(chunk.len() as u64, Ordering::Relaxed);
+            if let Some(captured) = &captured {
+                captured.write(chunk);
+            }
+        })
         .map_err(|e| std::io::Error::new(std::io::ErrorKind::Other, e));
 
     let reader = StreamReader::new(byte_stream);
     let lines = FramedRead::new(reader, LinesCodec::new());
     let data_path_owned = data_path.map(|s| s.to_owned());
+    let request = request.clone();
 
     let s = async_stream::try_stream! {
+        // Held until the whole body has been read
+        let _permit = permit;
         let mut lines = lines;
         while let Some(line_res) = lines.next().await {
             let line = line_res?;
@@ -109,24 +988,15 @@ pub async fn ndjson_stream_qs(
 
             trace!(len = trimmed.len(), "ndjson line");
 
-            let v: Value = serde_json::from_str(trimmed)?;
-
-            if let Some(ref p) = data_path_owned {
-                if let Some(inner) = v.pointer(p) {
-                    if let Some(arr) = inner.as_array() {
-                        for item in arr { yield item.clone(); }
-                    } else if !inner.is_null() {
-                        yield inner.clone();
-                    }
-                    continue;
-                }
-            }
+            let v = request.parse_json(trimmed.as_bytes())?;
+            request.tee_splits(&v);
 
-            if let Some(arr) = v.as_array() {
-                for item in arr { yield item.clone(); }
-            } else {
-                yield v;
-            }
+            // A line without `data_path` is taken whole
+            let target = match data_path_owned.as_deref().and_then(|p| v.pointer(p)) {
+                Some(inner) => inner.clone(),
+                None => v,
+            };
+            for item in request.records(target) { yield item; }
         }
     };
     Ok(s.boxed())
@@ -154,11 +1024,27 @@ pub trait PageWriter: Send + Sync {
         Ok(())
     }
 
+    /// Like [`Self::write_page_stream`] for a stream holding only page `page_number`.
+    async fn write_numbered_page_stream(
+        &self,
+        _page_number: u64,
+        stream_data: Pin<Box<dyn Stream<Item = Result<Value>> + Send>>,
+        write_mode: WriteMode,
+    ) -> Result<()> {
+        self.write_page_stream(stream_data, write_mode).await
+    }
+
     async fn on_page_error(&self, page_number: u64, error: String) -> Result<()> {
         error!(page = page_number, %error, "error fetching page");
         Ok(())
     }
 
+    /// Called by page-number pagination once every row of page `page_number`
+    /// has been written, after however many writes the page took.
+    async fn page_done(&self, _page_number: u64) -> Result<()> {
+        Ok(())
+    }
+
     async fn begin(&self) -> Result<()> {
         Ok(())
     }
@@ -175,21 +1061,89 @@ pub enum Pagination {
     LimitOffset {
         limit_param: String,
         offset_param: String,
+        /// Offset of the first request; defaults to 0.
+        #[serde(default)]
+        start_offset: u64,
+        /// Page size for this source; overrides the source's `page_size`.
+        #[serde(default)]
+        page_size: Option<usize>,
     },
     PageNumber {
         page_param: String,
         per_page_param: String,
+        /// Page number of the first request; defaults to 1. Set 0 for
+        /// zero-based APIs, or a later page to resume a backfill.
+        #[serde(default = "default_start_page")]
+        start_page: u64,
+        /// Page size for this source; overrides the source's `page_size`.
+        #[serde(default)]
+        page_size: Option<usize>,
     },
     PageOnly {
         page_param: String,
     },
+    /// Sends back the token the previous response returned in the
+    /// `next_cursor_header` header until a response comes without one.
     Cursor {
         cursor_param: String,
         page_size_param: Option<String>,
+        /// Response header carrying the next page's token, e.g. `X-Next-Page-Token`.
+        #[serde(default)]
+        next_cursor_header: Option<String>,
+        /// Whether the token goes back as a parameter or a header.
+        #[serde(default)]
+        cursor_in: CursorIn,
+        /// Page size for this source; overrides the source's `page_size`.
+        #[serde(default)]
+        page_size: Option<usize>,
+    },
+    /// Requests the URL the previous response gave at `next_path` until a
+    /// response has none, e.g. `{"next": "https://api.example.com/items?page=2"}`.
+    NextUrl {
+        /// Dotted path or JSON pointer to the next page's URL; relative URLs
+        /// are resolved against the current one.
+        next_path: String,
+        /// Boolean that must be true for another page, e.g. `has_more`.
+        #[serde(default)]
+        has_more_path: Option<String>,
+        /// Page size parameter sent with the first request.
+        #[serde(default)]
+        page_size_param: Option<String>,
+        /// Page size for this source; overrides the source's `page_size`.
+        #[serde(default)]
+        page_size: Option<usize>,
     },
     Default,
 }
 
+impl Pagination {
+    /// The `page_size` set on the pagination block, if any.
+    ///
+    /// ```
+    /// use apitap::http::fetcher::Pagination;
+    ///
+    /// let p: Pagination = serde_yaml::from_str(
+    ///     "{kind: limit_offset, limit_param: limit, offset_param: offset, page_size: 1000}",
+    /// )
+    /// .unwrap();
+    /// assert_eq!(p.page_size(), Some(1000));
+    /// assert_eq!(Pagination::Default.page_size(), None);
+    /// ```
+    pub fn page_size(&self) -> Option<usize> {
+        match self {
+            Pagination::LimitOffset { page_size, .. }
+            | Pagination::PageNumber { page_size, .. }
+            | Pagination::Cursor { page_size, .. }
+            | Pagination::NextUrl { page_size, .. } => *page_size,
+            Pagination::PageOnly { .. } | Pagination::Default => None,
+        }
+    }
+}
+
+fn default_start_page() -> u64 {
+    1
+}
+
 /// Hint to compute total pages.
 /// - Items: pointer points to total items; pages = ceil(items/limit)
 /// - Pages:  pointer points directly to total pages
@@ -208,6 +1162,7 @@ pub enum TotalHint {
 /// - Page Number (e.g., `?page=2&per_page=50`)
 /// - Page Only (e.g., `?page=2`)
 /// - Cursor-based (e.g., `?cursor=xxx`)
+/// - GraphQL cursor (`pageInfo.endCursor` fed back as `$after`)
 ///
 /// # Features
 ///
@@ -242,6 +1197,10 @@ pub struct PaginatedFetcher {
     concurrency: usize,
     pagination_config: Pagination,
     batch_size: usize,
+    /// Write concurrently fetched pages in page order.
+    preserve_order: bool,
+    request: RequestTemplate,
+    counters: Arc<TransferCounters>,
 }
 
 impl PaginatedFetcher {
@@ -273,6 +1232,9 @@ impl PaginatedFetcher {
             concurrency,
             pagination_config: Pagination::Default,
             batch_size: 256,
+            preserve_order: false,
+            request: RequestTemplate::default(),
+            counters: Arc::new(TransferCounters::default()),
         }
     }
 
@@ -300,10 +1262,22 @@ impl PaginatedFetcher {
         self.pagination_config = Pagination::LimitOffset {
             limit_param: limit_param.into(),
             offset_param: offset_param.into(),
+            start_offset: 0,
+            page_size: None,
         };
         self
     }
 
+    /// Starts limit/offset pagination at `offset` instead of 0.
+    ///
+    /// Has no effect unless [`with_limit_offset`](Self::with_limit_offset) was called first.
+    pub fn with_start_offset(mut self, offset: u64) -> Self {
+        if let Pagination::LimitOffset { start_offset, .. } = &mut self.pagination_config {
+            *start_offset = offset;
+        }
+        self
+    }
+
     /// Configures page number pagination strategy.
     ///
     /// # Arguments
@@ -328,14 +1302,123 @@ impl PaginatedFetcher {
         self.pagination_config = Pagination::PageNumber {
             page_param: page_param.into(),
             per_page_param: per_page_param.into(),
+            start_page: default_start_page(),
+            page_size: None,
+        };
+        self
+    }
+
+    /// Starts page-number pagination at `page` instead of 1.
+    ///
+    /// Has no effect unless [`with_page_number`](Self::with_page_number) was called first.
+    pub fn with_start_page(mut self, page: u64) -> Self {
+        if let Pagination::PageNumber { start_page, .. } = &mut self.pagination_config {
+            *start_page = page;
+        }
+        self
+    }
+
+    /// Configures cursor pagination that reads the next token from the
+    /// `next_cursor_header` response header.
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// # use reqwest::Client;
+    /// # use apitap::http::fetcher::{CursorIn, PaginatedFetcher};
+    /// let fetcher = PaginatedFetcher::new(Client::new(), "https://api.example.com", 1)
+    ///     .with_cursor("page_token", Some("page_size"), "X-Next-Page-Token", CursorIn::Param);
+    /// // Fetches: ?page_size=50, then ?page_size=50&page_token=<X-Next-Page-Token>, etc.
+    /// ```
+    pub fn with_cursor(
+        mut self,
+        cursor_param: impl Into<String>,
+        page_size_param: Option<&str>,
+        next_cursor_header: impl Into<String>,
+        cursor_in: CursorIn,
+    ) -> Self {
+        self.pagination_config = Pagination::Cursor {
+            cursor_param: cursor_param.into(),
+            page_size_param: page_size_param.map(str::to_string),
+            next_cursor_header: Some(next_cursor_header.into()),
+            cursor_in,
+            page_size: None,
+        };
+        self
+    }
+
+    /// Configures pagination that follows the next page's URL read from the
+    /// response body at `next_path`.
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// # use reqwest::Client;
+    /// # use apitap::http::fetcher::PaginatedFetcher;
+    /// let fetcher = PaginatedFetcher::new(Client::new(), "https://api.example.com/items", 1)
+    ///     .with_next_url("links.next", None, Some("limit"));
+    /// // Fetches: ?limit=50, then whatever `links.next` says, until it is null
+    /// ```
+    pub fn with_next_url(
+        mut self,
+        next_path: impl Into<String>,
+        has_more_path: Option<&str>,
+        page_size_param: Option<&str>,
+    ) -> Self {
+        self.pagination_config = Pagination::NextUrl {
+            next_path: next_path.into(),
+            has_more_path: has_more_path.map(str::to_string),
+            page_size_param: page_size_param.map(str::to_string),
+            page_size: None,
         };
         self
     }
 
+    /// Reports every fetched page to `progress`.
+    pub fn with_progress(mut self, progress: Arc<WriteProgress>) -> Self {
+        self.counters = Arc::new(TransferCounters {
+            progress: Some(progress),
+            ..TransferCounters::default()
+        });
+        self
+    }
+
     pub fn with_batch_size(mut self, n: usize) -> Self {
         self.batch_size = n.max(1);
         self
     }
+
+    /// Writes pages fetched concurrently in page order instead of as they
+    /// arrive.
+    ///
+    /// Each page is then read whole before it is written, and up to
+    /// `concurrency` pages wait in memory behind a slower earlier one.
+    pub fn with_preserve_order(mut self, enabled: bool) -> Self {
+        self.preserve_order = enabled;
+        self
+    }
+
+    /// Sets the method, body template, and pagination placement for page requests.
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// # use reqwest::Client;
+    /// # use apitap::http::fetcher::{HttpMethod, PaginatedFetcher, PaginationIn, RequestTemplate};
+    /// let fetcher = PaginatedFetcher::new(Client::new(), "https://api.example.com/search", 5)
+    ///     .with_limit_offset("limit", "offset")
+    ///     .with_request(RequestTemplate {
+    ///         method: HttpMethod::Post,
+    ///         body: Some(serde_json::json!({"query": {"match_all": {}}})),
+    ///         pagination_in: PaginationIn::Body,
+    ///         ..RequestTemplate::default()
+    ///     });
+    /// // POSTs {"query": {...}, "limit": 50, "offset": 0}, then offset 50, ...
+    /// ```
+    pub fn with_request(mut self, request: RequestTemplate) -> Self {
+        self.request = request;
+        self
+    }
 }
 
 /// Configuration for limit/offset fetch operations.
@@ -349,6 +1432,39 @@ pub struct LimitOffsetConfig<'a> {
     pub retry: &'a crate::pipeline::Retry,
 }
 
+/// Configuration for cursor and next-URL fetch operations.
+pub struct CursorConfig<'a> {
+    pub page_size: u64,
+    pub data_path: Option<String>,
+    pub extra_params: Option<&'a [(String, String)]>,
+    pub writer: Arc<dyn PageWriter>,
+    pub write_mode: WriteMode,
+    pub retry: &'a crate::pipeline::Retry,
+}
+
+/// Configuration for a single unpaginated request, e.g. an NDJSON export.
+pub struct StreamConfig<'a> {
+    pub data_path: Option<String>,
+    pub extra_params: Option<&'a [(String, String)]>,
+    pub writer: Arc<dyn PageWriter>,
+    pub write_mode: WriteMode,
+    pub retry: &'a crate::pipeline::Retry,
+}
+
+/// Configuration for GraphQL fetch operations.
+pub struct GraphqlFetchConfig<'a> {
+    pub query: &'a str,
+    /// Already-substituted variables object (or `Value::Null` for none).
+    pub variables: Value,
+    pub records_path: &'a str,
+    pub end_cursor_path: &'a str,
+    pub has_next_page_path: &'a str,
+    pub cursor_variable: &'a str,
+    pub writer: Arc<dyn PageWriter>,
+    pub write_mode: WriteMode,
+    pub retry: &'a crate::pipeline::Retry,
+}
+
 impl PaginatedFetcher {
     pub async fn limit_offset_stream(
         &self,
@@ -357,11 +1473,13 @@ impl PaginatedFetcher {
         extra_params: Option<&[(String, String)]>,
         config_retry: &crate::pipeline::Retry,
     ) -> crate::errors::Result<JsonStreamType> {
-        let (limit_param, offset_param) = match &self.pagination_config {
+        let (limit_param, offset_param, start_offset) = match &self.pagination_config {
             Pagination::LimitOffset {
                 limit_param,
                 offset_param,
-            } => (limit_param.clone(), offset_param.clone()),
+                start_offset,
+                ..
+            } => (limit_param.clone(), offset_param.clone(), *start_offset),
             other => {
                 return Err(crate::errors::ApitapError::PaginationError(format!(
                     "Pagination::LimitOffset not configured {other:?}"
@@ -374,24 +1492,35 @@ impl PaginatedFetcher {
         let data_path_owned = data_path.map(|s| s.to_string());
         let retry_cfg = config_retry.clone();
         let extra_params_owned = extra_params.map(|p| p.to_vec()).unwrap_or_default();
+        let request = self.request.clone();
+        let counters = Arc::clone(&self.counters);
 
         // Build the stream
         let s = async_stream::try_stream! {
-            let mut offset: u64 = 0;
+            let mut offset: u64 = start_offset;
+            let mut page: u64 = 1;
 
             loop {
-                // Merge pagination params with extra params
-                let mut query_params = extra_params_owned.clone();
-                query_params.push((limit_param.clone(), limit.to_string()));
-                query_params.push((offset_param.clone(), offset.to_string()));
+                let page_params = vec![
+                    (limit_param.clone(), Value::from(limit)),
+                    (offset_param.clone(), Value::from(offset)),
+                ];
+                let page_request = request.for_page(&[
+                    ("offset", offset.into()),
+                    ("limit", limit.into()),
+                    ("page", page.into()),
+                ]);
 
                 let mut page_stream: BoxStream<'static, crate::errors::Result<Value>> =
-                    ndjson_stream_qs(
+                    counted_stream_request(
                         &client,
                         &base_url,
-                        &query_params,
+                        &extra_params_owned,
+                        &page_params,
+                        &page_request,
                         data_path_owned.as_deref(),
                         &retry_cfg,
+                        Arc::clone(&counters),
                     ).await?;
 
                 let mut page_count = 0usize;
@@ -402,11 +1531,12 @@ impl PaginatedFetcher {
                     yield v;
                 }
 
-                if page_count == 0 {
+                if page_count == 0 || page_request.is_last_page(page_count, limit) {
                     break;
                 }
 
                 offset += limit;
+                page += 1;
             }
         };
 
@@ -420,6 +1550,7 @@ impl PaginatedFetcher {
         let _g = span.enter();
 
         let mut stats = FetchStats::new();
+        let transfer_start = self.counters.snapshot();
 
         // Build a single JsonStreamType over all pages
         let json_stream = self
@@ -434,7 +1565,7 @@ impl PaginatedFetcher {
         // Now you can wrap it into your QueryResultStream abstraction
 
         self.write_streamed_page(
-            1,
+            None,
             json_stream,
             &*config.writer,
             &mut stats,
@@ -444,6 +1575,7 @@ impl PaginatedFetcher {
 
         // You don't have per-page stats here easily, but you could compute total_items
         // inside write_stream, or wrap the stream to count rows.
+        self.counters.record_since(transfer_start, &mut stats);
         Ok(stats)
     }
 
@@ -457,192 +1589,784 @@ impl PaginatedFetcher {
         write_mode: WriteMode,
         config_retry: &crate::pipeline::Retry,
     ) -> Result<FetchStats> {
-        let (page_param, per_page_param) = match &self.pagination_config {
+        let (page_param, per_page_param, first_page) = match &self.pagination_config {
             Pagination::PageNumber {
                 page_param,
                 per_page_param,
-            } => (page_param.clone(), per_page_param.clone()),
+                start_page,
+                ..
+            } => (page_param.clone(), per_page_param.clone(), *start_page),
+            other => {
+                return Err(ApitapError::PaginationError(format!(
+                    "expected Pagination::PageNumber, got {other:?}"
+                )));
+            }
+        };
+
+        let span = debug_span!("fetch.page_number", source = %self.base_url, per_page = per_page);
+        let _g = span.enter();
+
+        writer.begin().await?;
+        let transfer_start = self.counters.snapshot();
+
+        let page_params = |page: u64| {
+            vec![
+                (page_param.clone(), Value::from(page)),
+                (per_page_param.clone(), Value::from(per_page)),
+            ]
+        };
+        let page_request = |page: u64| {
+            self.request
+                .for_page(&[("page", page.into()), ("per_page", per_page.into())])
+        };
+
+        // First request as JSON (page=start_page)
+        let client_with_retry = http_retry::build_client_with_signer(
+            self.client.clone(),
+            config_retry,
+            self.request.signer.clone(),
+        );
+        let first_resp = send_request(
+            &client_with_retry,
+            &self.base_url,
+            &[],
+            &page_params(first_page),
+            &page_request(first_page),
+            &self.counters,
+        )
+        .await?;
+        let mut stats = FetchStats::new();
+        if first_resp.status() == StatusCode::NOT_MODIFIED {
+            info!(source = %redact_url(&self.base_url), "304 Not Modified; nothing to fetch");
+            self.counters.record_since(transfer_start, &mut stats);
+            return Ok(stats);
+        }
+        let first_body = first_resp.bytes().await?;
+        self.counters
+            .bytes
+            .fetch_add(first_body.len() as u64, Ordering::Relaxed);
+        let first_json = self.request.parse_body(&first_body)?;
+
+        // Write the first page
+        let mut wrote_first = false;
+        let mut first_count = 0;
+        if let Some(p) = data_path {
+            if let Some(target) = first_json
+                .pointer(p)
+                .filter(|v| self.request.is_collection(v))
+            {
+                // Otherwise the page is fetched again below and teed then
+                self.request.tee_splits(&first_json);
+                let mut arr = self.request.records(target.clone());
+                arr.truncate(self.request.claim_records(arr.len()));
+                let n = arr.len();
+                writer
+                    .write_page(first_page, arr, write_mode.clone())
+                    .await?;
+                stats.add_page(first_page, n);
+                wrote_first = true;
+                first_count = n;
+            }
+        }
+        if !wrote_first {
+            let s = counted_stream_request(
+                &self.client,
+                &self.base_url,
+                &[],
+                &page_params(first_page),
+                &page_request(first_page),
+                data_path,
+                config_retry,
+                Arc::clone(&self.counters),
+            )
+            .await?;
+            first_count = self
+                .write_streamed_page(
+                    Some(first_page),
+                    s,
+                    &*writer,
+                    &mut stats,
+                    write_mode.clone(),
+                )
+                .await?;
+        }
+        writer.page_done(first_page).await?;
+
+        // `stop_when` says nothing follows the first page, or the record budget is spent
+        if self.request.records_exhausted()
+            || self.request.stop_when.as_ref().is_some_and(|stop| {
+                stop.matches_body(&first_json) || stop.matches_count(first_count, per_page)
+            })
+        {
+            writer.commit().await?;
+            self.counters.record_since(transfer_start, &mut stats);
+            return Ok(stats);
+        }
+
+        // Determine total pages
+        let pages_opt = match total_hint {
+            Some(TotalHint::Items { ref pointer }) => first_json
+                .pointer(pointer)
+                .and_then(|v| v.as_u64())
+                .map(|total_items| (total_items + per_page - 1) / per_page),
+            Some(TotalHint::Pages { ref pointer }) => {
+                first_json.pointer(pointer).and_then(|v| v.as_u64())
+            }
+            None => None,
+        };
+
+        // Remaining pages up to the last one; a start_page of 0 means zero-based numbering
+        let last_page = pages_opt.map(|total_pages| {
+            if first_page == 0 {
+                total_pages.saturating_sub(1)
+            } else {
+                total_pages
+            }
+        });
+        if let (Some(last_page), true) = (last_page, self.preserve_order) {
+            let mut pages = stream::iter(first_page + 1..=last_page)
+                .map(|page| {
+                    let params = page_params(page);
+                    let request = page_request(page);
+                    let counters = Arc::clone(&self.counters);
+                    async move {
+                        let fetched = async {
+                            let s = counted_stream_request(
+                                &self.client,
+                                &self.base_url,
+                                &[],
+                                &params,
+                                &request,
+                                data_path,
+                                config_retry,
+                                counters,
+                            )
+                            .await?;
+                            Ok::<_, ApitapError>(s.collect::<Vec<_>>().await)
+                        }
+                        .await;
+                        (page, fetched)
+                    }
+                })
+                .buffered(self.concurrency);
+
+            while let Some((page, fetched)) = pages.next().await {
+                let items = match fetched {
+                    Ok(items) => items,
+                    Err(e) => {
+                        let _ = writer.on_page_error(page, e.to_string()).await;
+                        continue;
+                    }
+                };
+                let mut failed = false;
+                let mut rows = Vec::with_capacity(items.len());
+                for item in items {
+                    match item {
+                        Ok(v) => rows.push(v),
+                        Err(e) => {
+                            failed = true;
+                            let _ = writer.on_page_error(page, e.to_string()).await;
+                        }
+                    }
+                }
+                stats.add_page(page, rows.len());
+                while !rows.is_empty() {
+                    let rest = rows.split_off(rows.len().min(self.batch_size));
+                    let out = std::mem::replace(&mut rows, rest);
+                    if let Err(e) = writer.write_page(page, out, write_mode.clone()).await {
+                        failed = true;
+                        let _ = writer.on_page_error(page, e.to_string()).await;
+                    }
+                }
+                if !failed {
+                    writer.page_done(page).await?;
+                }
+            }
+        } else if let Some(last_page) = last_page {
+            let client = self.client.clone();
+            let url = self.base_url.clone();
+            let page_param_c = page_param.clone();
+            let per_page_param_c = per_page_param.clone();
+            let data_path_c = data_path.map(|s| s.to_string());
+            let writer_ref = Arc::clone(&writer);
+            let batch_size = self.batch_size;
+            let write_mode_clone = write_mode.clone();
+            let request_c = self.request.clone();
+            let counters_c = Arc::clone(&self.counters);
+
+            stream::iter(first_page + 1..=last_page)
+                .map(move |page| {
+                    let client = client.clone();
+                    let url = url.clone();
+                    let page_param = page_param_c.clone();
+                    let per_page_param = per_page_param_c.clone();
+                    let data_path = data_path_c.clone();
+                    let writer = Arc::clone(&writer_ref);
+                    let write_mode_c = write_mode_clone.clone();
+                    let request = request_c.clone();
+                    let counters = Arc::clone(&counters_c);
+
+                    async move {
+                        let mut s = match counted_stream_request(
+                            &client,
+                            &url,
+                            &[],
+                            &[
+                                (page_param, page.to_string()),
+                                (per_page_param, per_page.to_string()),
+                            ],
+                            &request.for_page(&[("page", page.into()), ("per_page", per_page.into())]),
+                            data_path.as_deref(),
+                            config_retry,
+                            counters,
+                        )
+                        .await
+                        {
+                            Ok(s) => s,
+                            Err(e) => {
+                                let _ = writer.on_page_error(page, e.to_string()).await;
+                                return;
+                            }
+                        };
+                        let mut failed = false;
+                        let mut buf = Vec::with_capacity(batch_size);
+                        while let Some(item) = s.next().await {
+                            match item {
+                                Ok(v) => {
+                                    buf.push(v);
+                                    if buf.len() == batch_size {
+                                        let out = std::mem::take(&mut buf);
+                                        if let Err(e) =
+                                            writer.write_page(page, out, write_mode_c.clone()).await
+                                        {
+                                            failed = true;
+                                            let _ = writer.on_page_error(page, e.to_string()).await;
+                                        }
+                                        trace!(page = page, batch = true, "wrote batch for page");
+                                    }
+                                }
+                                Err(e) => {
+                                    failed = true;
+                                    let _ = writer.on_page_error(page, e.to_string()).await;
+                                }
+                            }
+                        }
+                        if !buf.is_empty() {
+                            let out = std::mem::take(&mut buf);
+                            let cnt = out.len();
+                            if let Err(e) = writer.write_page(page, out, write_mode_c.clone()).await
+                            {
+                                failed = true;
+                                let _ = writer.on_page_error(page, e.to_string()).await;
+                            } else {
+                                info!(page = page, items = cnt, source = %redact_url(&url), "wrote page remainder");
+                            }
+                        }
+                        if !failed {
+                            if let Err(e) = writer.page_done(page).await {
+                                let _ = writer.on_page_error(page, e.to_string()).await;
+                            }
+                        }
+                    }
+                })
+                .buffer_unordered(self.concurrency)
+                .collect::<Vec<_>>()
+                .await;
+        } else {
+            // Unknown total pages: fetch following pages until one is empty
+            let mut page = first_page + 1;
+            loop {
+                let request = page_request(page);
+                let s = match counted_stream_request(
+                    &self.client,
+                    &self.base_url,
+                    &[],
+                    &page_params(page),
+                    &request,
+                    data_path,
+                    config_retry,
+                    Arc::clone(&self.counters),
+                )
+                .await
+                {
+                    Ok(s) => s,
+                    Err(e) => {
+                        let _ = writer.on_page_error(page, e.to_string()).await;
+                        break;
+                    }
+                };
+
+                let wrote = self
+                    .write_streamed_page(Some(page), s, &*writer, &mut stats, write_mode.clone())
+                    .await?;
+                writer.page_done(page).await?;
+                if wrote == 0 || request.is_last_page(wrote, per_page) {
+                    break;
+                } // stop on empty page, or as `stop_when` says
+                page += 1;
+            }
+        }
+
+        writer.commit().await?;
+        self.counters.record_since(transfer_start, &mut stats);
+        Ok(stats)
+    }
+
+    /// Cursor stream: sends each page's `next_cursor_header` token back as
+    /// `cursor_param` until a response has no token, or repeats the last one.
+    pub async fn cursor_stream(
+        &self,
+        page_size: u64,
+        data_path: Option<&str>,
+        extra_params: Option<&[(String, String)]>,
+        config_retry: &crate::pipeline::Retry,
+    ) -> Result<JsonStreamType> {
+        let (cursor_param, page_size_param, next_cursor_header, cursor_in) =
+            match &self.pagination_config {
+                Pagination::Cursor {
+                    cursor_param,
+                    page_size_param,
+                    next_cursor_header: Some(header),
+                    cursor_in,
+                    ..
+                } => (
+                    cursor_param.clone(),
+                    page_size_param.clone(),
+                    header.clone(),
+                    *cursor_in,
+                ),
+                other => {
+                    return Err(ApitapError::PaginationError(format!(
+                        "Pagination::Cursor with next_cursor_header not configured {other:?}"
+                    )));
+                }
+            };
+
+        let client = http_retry::build_client_with_signer(
+            self.client.clone(),
+            config_retry,
+            self.request.signer.clone(),
+        );
+        let base_url = self.base_url.clone();
+        let data_path_owned = data_path.map(|s| s.to_string());
+        let extra_params_owned = extra_params.map(|p| p.to_vec()).unwrap_or_default();
+        let request = self.request.clone();
+        let counters = Arc::clone(&self.counters);
+
+        let s = async_stream::try_stream! {
+            let mut cursor: Option<String> = None;
+            let mut page: u64 = 1;
+
+            loop {
+                let mut page_params = Vec::new();
+                if let Some(param) = &page_size_param {
+                    page_params.push((param.clone(), Value::from(page_size)));
+                }
+                let mut page_request = request.for_page(&[
+                    ("cursor", cursor.clone().into()),
+                    ("page_size", page_size.into()),
+                    ("page", page.into()),
+                ]);
+                if let Some(token) = &cursor {
+                    match cursor_in {
+                        CursorIn::Param => {
+                            page_params.push((cursor_param.clone(), Value::from(token.clone())))
+                        }
+                        CursorIn::Header => page_request
+                            .page_headers
+                            .push((cursor_param.clone(), token.clone())),
+                    }
+                }
+
+                if page_request.records_exhausted() {
+                    break;
+                }
+                let resp = send_request(
+                    &client,
+                    &base_url,
+                    &extra_params_owned,
+                    &page_params,
+                    &page_request,
+                    &counters,
+                )
+                .await?;
+                let next = resp
+                    .headers()
+                    .get(&next_cursor_header)
+                    .and_then(|v| v.to_str().ok())
+                    .map(str::trim)
+                    .filter(|v| !v.is_empty())
+                    .map(str::to_string);
+
+                let mut page_stream = page_request.capped(
+                    response_to_stream(
+                        resp,
+                        data_path_owned.as_deref(),
+                        &page_request,
+                        Arc::clone(&counters),
+                    )
+                    .await?,
+                );
+                let mut page_count = 0usize;
+                while let Some(item) = page_stream.next().await {
+                    page_count += 1;
+                    yield item?;
+                }
+                if page_request.is_last_page(page_count, page_size) {
+                    break;
+                }
+
+                match next {
+                    Some(next) if Some(&next) != cursor.as_ref() => cursor = Some(next),
+                    _ => break,
+                }
+                page += 1;
+            }
+        };
+
+        Ok(s.boxed())
+    }
+
+    /// CURSOR mode: streams every page into `writer` via a single streamed write.
+    pub async fn fetch_cursor(&self, config: CursorConfig<'_>) -> Result<FetchStats> {
+        let span = debug_span!("fetch.cursor.stream", source = %self.base_url);
+        let _g = span.enter();
+
+        let mut stats = FetchStats::new();
+        let transfer_start = self.counters.snapshot();
+        let json_stream = self
+            .cursor_stream(
+                config.page_size,
+                config.data_path.as_deref(),
+                config.extra_params,
+                config.retry,
+            )
+            .await?;
+
+        self.write_streamed_page(
+            None,
+            json_stream,
+            &*config.writer,
+            &mut stats,
+            config.write_mode.clone(),
+        )
+        .await?;
+
+        self.counters.record_since(transfer_start, &mut stats);
+        Ok(stats)
+    }
+
+    /// Next-URL stream: requests the base URL, then each URL the previous
+    /// response gave at `next_path`, until a response has none (or
+    /// `has_more_path` is false) or repeats the current URL.
+    ///
+    /// `extra_params` and the page size go with the first request only; the
+    /// next URLs are expected to carry everything the API needs.
+    pub async fn next_url_stream(
+        &self,
+        page_size: u64,
+        data_path: Option<&str>,
+        extra_params: Option<&[(String, String)]>,
+        config_retry: &crate::pipeline::Retry,
+    ) -> Result<JsonStreamType> {
+        let (next_path, has_more_path, page_size_param) = match &self.pagination_config {
+            Pagination::NextUrl {
+                next_path,
+                has_more_path,
+                page_size_param,
+                ..
+            } => (
+                next_path.clone(),
+                has_more_path.clone(),
+                page_size_param.clone(),
+            ),
+            other => {
+                return Err(ApitapError::PaginationError(format!(
+                    "Pagination::NextUrl not configured {other:?}"
+                )));
+            }
+        };
+
+        let client = http_retry::build_client_with_signer(
+            self.client.clone(),
+            config_retry,
+            self.request.signer.clone(),
+        );
+        let base_url = self.base_url.clone();
+        let data_path_owned = data_path.map(|s| s.to_string());
+        let extra_params_owned = extra_params.map(|p| p.to_vec()).unwrap_or_default();
+        let request = self.request.clone();
+        let counters = Arc::clone(&self.counters);
+
+        let s = async_stream::try_stream! {
+            let mut url = base_url;
+            let mut page: u64 = 1;
+
+            loop {
+                let (query, page_params) = if page == 1 {
+                    let page_params: Vec<(String, Value)> = page_size_param
+                        .iter()
+                        .map(|param| (param.clone(), Value::from(page_size)))
+                        .collect();
+                    (extra_params_owned.clone(), page_params)
+                } else {
+                    (Vec::new(), Vec::new())
+                };
+                let page_request = request.for_page(&[
+                    ("page_size", page_size.into()),
+                    ("page", page.into()),
+                ]);
+
+                if page_request.records_exhausted() {
+                    break;
+                }
+                let resp = send_request(
+                    &client,
+                    &url,
+                    &query,
+                    &page_params,
+                    &page_request,
+                    &counters,
+                )
+                .await?;
+                let v = read_body(resp, &page_request, &counters).await?;
+
+                let has_more = match &has_more_path {
+                    Some(path) => json_path::select_first(&v, path)
+                        .and_then(Value::as_bool)
+                        .unwrap_or(false),
+                    None => true,
+                };
+                let next = json_path::select_first(&v, &next_path)
+                    .and_then(Value::as_str)
+                    .map(str::trim)
+                    .filter(|next| !next.is_empty())
+                    .map(|next| resolve_next_url(&url, next))
+                    .transpose()?;
+
+                let target = match data_path_owned.as_deref() {
+                    Some(p) => v.pointer(p).cloned().unwrap_or(Value::Null),
+                    None => v,
+                };
+                let mut records = page_request.records(target);
+                let last = page_request.is_last_page(records.len(), page_size);
+                records.truncate(page_request.claim_records(records.len()));
+                for item in records {
+                    yield item;
+                }
+
+                match next {
+                    Some(next) if has_more && !last && next != url => url = next,
+                    _ => break,
+                }
+                page += 1;
+            }
+        };
+
+        Ok(s.boxed())
+    }
+
+    /// NEXT-URL mode: streams every page into `writer` via a single streamed write.
+    pub async fn fetch_next_url(&self, config: CursorConfig<'_>) -> Result<FetchStats> {
+        let span = debug_span!("fetch.next_url.stream", source = %self.base_url);
+        let _g = span.enter();
+
+        let mut stats = FetchStats::new();
+        let transfer_start = self.counters.snapshot();
+        let json_stream = self
+            .next_url_stream(
+                config.page_size,
+                config.data_path.as_deref(),
+                config.extra_params,
+                config.retry,
+            )
+            .await?;
+
+        self.write_streamed_page(
+            None,
+            json_stream,
+            &*config.writer,
+            &mut stats,
+            config.write_mode.clone(),
+        )
+        .await?;
+
+        self.counters.record_since(transfer_start, &mut stats);
+        Ok(stats)
+    }
+
+    /// Sends one request and writes its records as they are read, until the
+    /// body ends. With `format: ndjson_stream` nothing is buffered beyond the
+    /// current line.
+    pub async fn fetch_stream(&self, config: StreamConfig<'_>) -> Result<FetchStats> {
+        let span = debug_span!("fetch.stream", source = %self.base_url);
+        let _g = span.enter();
+
+        let mut stats = FetchStats::new();
+        let transfer_start = self.counters.snapshot();
+        let json_stream = counted_stream_request(
+            &self.client,
+            &self.base_url,
+            config.extra_params.unwrap_or_default(),
+            &[],
+            &self.request,
+            config.data_path.as_deref(),
+            config.retry,
+            Arc::clone(&self.counters),
+        )
+        .await?;
+
+        self.write_streamed_page(
+            None,
+            json_stream,
+            &*config.writer,
+            &mut stats,
+            config.write_mode.clone(),
+        )
+        .await?;
+
+        self.counters.record_since(transfer_start, &mut stats);
+        Ok(stats)
+    }
+
+    /// GraphQL cursor stream: POSTs `{query, variables}` and feeds the previous
+    /// page's end cursor into `cursor_variable` until `hasNextPage` is false.
+    pub async fn graphql_stream(&self, config: &GraphqlFetchConfig<'_>) -> Result<JsonStreamType> {
+        let client = http_retry::build_client_with_signer(
+            self.client.clone(),
+            config.retry,
+            self.request.signer.clone(),
+        );
+        let base_url = self.base_url.clone();
+        let query = config.query.to_string();
+        let base_variables = match &config.variables {
+            Value::Null => Value::Object(serde_json::Map::new()),
+            Value::Object(_) => config.variables.clone(),
             other => {
-                return Err(ApitapError::PaginationError(format!(
-                    "expected Pagination::PageNumber, got {other:?}"
-                )));
+                return Err(ApitapError::ConfigError(format!(
+                    "graphql variables must be an object, got {other}"
+                )))
             }
         };
+        let records_path = config.records_path.to_string();
+        let end_cursor_path = config.end_cursor_path.to_string();
+        let has_next_page_path = config.has_next_page_path.to_string();
+        let cursor_variable = config.cursor_variable.to_string();
+        let counters = Arc::clone(&self.counters);
+        let capture = self.request.capture.clone();
+        let request_limit = self.request.request_limit.clone();
+        let template = self.request.clone();
 
-        let span = debug_span!("fetch.page_number", source = %self.base_url, per_page = per_page);
-        let _g = span.enter();
+        let s = async_stream::try_stream! {
+            let mut cursor: Option<Value> = None;
+            let mut page = 1u64;
 
-        writer.begin().await?;
+            loop {
+                if template.records_exhausted() {
+                    break;
+                }
+                let mut variables = base_variables.clone();
+                if let (Some(c), Value::Object(map)) = (&cursor, &mut variables) {
+                    map.insert(cursor_variable.clone(), c.clone());
+                }
+                let body = serde_json::json!({ "query": query, "variables": variables });
+
+                let span = debug_span!("http.request", method = "POST", source = %base_url, page = page);
+                let started = std::time::Instant::now();
+                let permit = acquire_request_permit(request_limit.as_ref()).await;
+                let resp = template
+                    .body_encoding
+                    .apply(client.post(&base_url), &body)?
+                    .with_extension(AttemptTally(Arc::clone(&counters.requests)))
+                    .send()
+                    .await?;
+                let captured = capture.as_ref().and_then(|c| {
+                    c.record(
+                        &reqwest::Method::POST,
+                        &Default::default(),
+                        Some(&body),
+                        &resp,
+                        ResponseFormat::Json,
+                    )
+                });
+                let resp = match &captured {
+                    Some(captured) => error_for_status_captured(resp, captured).await?,
+                    None => resp.error_for_status()?,
+                };
+                counters.page_fetched();
+                let raw = resp.bytes().await?;
+                counters.bytes.fetch_add(raw.len() as u64, Ordering::Relaxed);
+                drop(permit);
+                if let Some(captured) = &captured {
+                    captured.write(&raw);
+                }
+                let v = template.parse_json(&raw)?;
+                span.in_scope(|| debug!(elapsed_ms = started.elapsed().as_millis(), "graphql response received"));
 
-        // First request as JSON (page=1)
-        let first_json: Value = self
-            .client
-            .get(&self.base_url)
-            .query(&[(page_param.as_str(), "1".to_string())])
-            .query(&[(per_page_param.as_str(), per_page.to_string())])
-            .send()
-            .await?
-            .error_for_status()?
-            .json()
-            .await?;
+                if let Some(errors) = v.get("errors").filter(|e| !e.is_null()) {
+                    Err(ApitapError::PaginationError(format!(
+                        "graphql errors on page {page}: {errors}"
+                    )))?;
+                }
 
-        let mut stats = FetchStats::new();
+                for selected in json_path::select(&v, &records_path) {
+                    match selected {
+                        Value::Array(items) => {
+                            for item in items {
+                                yield item.clone();
+                            }
+                        }
+                        Value::Null => {}
+                        other => yield other.clone(),
+                    }
+                }
 
-        // Write page 1
-        let mut wrote_first = false;
-        if let Some(p) = data_path {
-            if let Some(arr) = first_json.pointer(p).and_then(|v| v.as_array()).cloned() {
-                let n = arr.len();
-                writer.write_page(1, arr, write_mode.clone()).await?;
-                stats.add_page(1, n);
-                wrote_first = true;
-            }
-        }
-        if !wrote_first {
-            let s = ndjson_stream_qs(
-                &self.client,
-                &self.base_url,
-                &[
-                    (page_param.clone(), "1".into()),
-                    (per_page_param.clone(), per_page.to_string()),
-                ],
-                data_path,
-                config_retry,
-            )
-            .await?;
-            self.write_streamed_page(1, s, &*writer, &mut stats, write_mode.clone())
-                .await?;
-        }
+                let has_next = json_path::select_first(&v, &has_next_page_path)
+                    .and_then(Value::as_bool)
+                    .unwrap_or(false);
+                let next_cursor = json_path::select_first(&v, &end_cursor_path).cloned();
 
-        // Determine total pages
-        let pages_opt = match total_hint {
-            Some(TotalHint::Items { ref pointer }) => first_json
-                .pointer(pointer)
-                .and_then(|v| v.as_u64())
-                .map(|total_items| (total_items + per_page - 1) / per_page),
-            Some(TotalHint::Pages { ref pointer }) => {
-                first_json.pointer(pointer).and_then(|v| v.as_u64())
+                match next_cursor {
+                    Some(next) if has_next && Some(&next) != cursor.as_ref() => {
+                        cursor = Some(next);
+                        page += 1;
+                    }
+                    _ => break,
+                }
             }
-            None => None,
         };
 
-        if let Some(total_pages) = pages_opt {
-            // pages 2..=total_pages
-            let client = self.client.clone();
-            let url = self.base_url.clone();
-            let page_param_c = page_param.clone();
-            let per_page_param_c = per_page_param.clone();
-            let data_path_c = data_path.map(|s| s.to_string());
-            let writer_ref = Arc::clone(&writer);
-            let batch_size = self.batch_size;
-            let write_mode_clone = write_mode.clone();
+        Ok(self.request.capped(s.boxed()))
+    }
 
-            stream::iter(2..=total_pages)
-                .map(move |page| {
-                    let client = client.clone();
-                    let url = url.clone();
-                    let page_param = page_param_c.clone();
-                    let per_page_param = per_page_param_c.clone();
-                    let data_path = data_path_c.clone();
-                    let writer = Arc::clone(&writer_ref);
-                    let write_mode_c = write_mode_clone.clone();
+    /// GraphQL mode: streams every page into `writer` via a single streamed write.
+    pub async fn fetch_graphql(&self, config: GraphqlFetchConfig<'_>) -> Result<FetchStats> {
+        let span = debug_span!("fetch.graphql.stream", source = %self.base_url);
+        let _g = span.enter();
 
-                    async move {
-                        let mut s = match ndjson_stream_qs(
-                            &client,
-                            &url,
-                            &[
-                                (page_param, page.to_string()),
-                                (per_page_param, per_page.to_string()),
-                            ],
-                            data_path.as_deref(),
-                            config_retry,
-                        )
-                        .await
-                        {
-                            Ok(s) => s,
-                            Err(e) => {
-                                let _ = writer.on_page_error(page, e.to_string()).await;
-                                return;
-                            }
-                        };
-                        let mut buf = Vec::with_capacity(batch_size);
-                        while let Some(item) = s.next().await {
-                            match item {
-                                Ok(v) => {
-                                    buf.push(v);
-                                    if buf.len() == batch_size {
-                                        let out = std::mem::take(&mut buf);
-                                        if let Err(e) =
-                                            writer.write_page(page, out, write_mode_c.clone()).await
-                                        {
-                                            let _ = writer.on_page_error(page, e.to_string()).await;
-                                        }
-                                        trace!(page = page, batch = true, "wrote batch for page");
-                                    }
-                                }
-                                Err(e) => {
-                                    let _ = writer.on_page_error(page, e.to_string()).await;
-                                }
-                            }
-                        }
-                        if !buf.is_empty() {
-                            let out = std::mem::take(&mut buf);
-                            let cnt = out.len();
-                            if let Err(e) = writer.write_page(page, out, write_mode_c.clone()).await
-                            {
-                                let _ = writer.on_page_error(page, e.to_string()).await;
-                            } else {
-                                info!(page = page, items = cnt, source = %url, "wrote page remainder");
-                            }
-                        }
-                    }
-                })
-                .buffer_unordered(self.concurrency)
-                .collect::<Vec<_>>()
-                .await;
-        } else {
-            // Unknown total pages: fetch page=2,3,... until empty
-            let mut page = 2u64;
-            loop {
-                let s = match ndjson_stream_qs(
-                    &self.client,
-                    &self.base_url,
-                    &[
-                        (page_param.clone(), page.to_string()),
-                        (per_page_param.clone(), per_page.to_string()),
-                    ],
-                    data_path,
-                    config_retry,
-                )
-                .await
-                {
-                    Ok(s) => s,
-                    Err(e) => {
-                        let _ = writer.on_page_error(page, e.to_string()).await;
-                        break;
-                    }
-                };
+        let mut stats = FetchStats::new();
+        let transfer_start = self.counters.snapshot();
+        let json_stream = self.graphql_stream(&config).await?;
 
-                let wrote = self
-                    .write_streamed_page(page, s, &*writer, &mut stats, write_mode.clone())
-                    .await?;
-                if wrote == 0 {
-                    break;
-                } // stop on empty page
-                page += 1;
-            }
-        }
+        self.write_streamed_page(
+            None,
+            json_stream,
+            &*config.writer,
+            &mut stats,
+            config.write_mode.clone(),
+        )
+        .await?;
 
-        writer.commit().await?;
+        self.counters.record_since(transfer_start, &mut stats);
         Ok(stats)
     }
 
     // -------------------- Private helpers ------------------------------------
 
+    /// Writes one stream of records; `page` is `None` when it spans several pages.
     async fn write_streamed_page(
         &self,
-        _page: u64,
+        page: Option<u64>,
         s: BoxStream<'static, Result<Value>>,
         writer: &dyn PageWriter,
         stats: &mut FetchStats,
@@ -661,11 +2385,18 @@ impl PaginatedFetcher {
             })
             .boxed();
 
-        writer.write_page_stream(counted_stream, write_mode).await?;
+        match page {
+            Some(page) => {
+                writer
+                    .write_numbered_page_stream(page, counted_stream, write_mode)
+                    .await?
+            }
+            None => writer.write_page_stream(counted_stream, write_mode).await?,
+        }
 
         // Get final count
         let final_count = count.load(Ordering::Relaxed);
-        stats.add_page(_page, final_count);
+        stats.add_page(page.unwrap_or(1), final_count);
         Ok(final_count)
     }
 }
@@ -674,7 +2405,9 @@ impl PaginatedFetcher {
 
 /// Statistics for a fetch operation.
 ///
-/// Tracks the number of successful pages, errors, and total items fetched.
+/// Tracks the number of successful pages, errors, and total items fetched,
+/// plus the HTTP traffic it took: requests sent, pages received, and response
+/// body bytes (as received, i.e. after transport decompression).
 ///
 /// # Example
 ///
@@ -684,12 +2417,22 @@ impl PaginatedFetcher {
 /// let stats = FetchStats::new();
 /// assert_eq!(stats.success_count, 0);
 /// assert_eq!(stats.total_items, 0);
+/// assert_eq!(stats.total_bytes, 0);
 /// ```
 #[derive(Debug, Clone)]
+#[non_exhaustive]
 pub struct FetchStats {
     pub success_count: usize,
     pub error_count: usize,
     pub total_items: usize,
+    /// Response body bytes read across all pages.
+    pub total_bytes: u64,
+    /// HTTP requests sent, including ones that failed and each retry attempt.
+    pub request_count: usize,
+    /// Pages that came back with a success status.
+    pub page_count: usize,
+    /// Fetches skipped because the server answered `304 Not Modified`.
+    pub not_modified: usize,
 }
 
 impl Default for FetchStats {
@@ -705,8 +2448,22 @@ impl FetchStats {
             success_count: 0,
             error_count: 0,
             total_items: 0,
+            total_bytes: 0,
+            request_count: 0,
+            page_count: 0,
+            not_modified: 0,
         }
     }
+    /// Adds `other`'s counts to these.
+    pub fn merge(&mut self, other: &FetchStats) {
+        self.success_count += other.success_count;
+        self.error_count += other.error_count;
+        self.total_items += other.total_items;
+        self.total_bytes += other.total_bytes;
+        self.request_count += other.request_count;
+        self.page_count += other.page_count;
+        self.not_modified += other.not_modified;
+    }
     fn add_page(&mut self, _page: u64, items: usize) {
         self.success_count += 1;
         self.total_items += items;
@@ -723,6 +2480,19 @@ pub struct DataFusionPageWriter {
     table_name: String,
     sql: String,
     final_writer: Arc<dyn DataWriter>,
+    raw_json: bool,
+    ejson: bool,
+    fields: FieldMapping,
+    transform: RecordTransform,
+    flatten: Option<Flatten>,
+    numbers: NumberHandling,
+    nulls: NullHandling,
+    dictionary: Option<DictionaryEncoding>,
+    metadata: Option<MetadataStamp>,
+    constants: Option<ConstantColumns>,
+    contract: Option<Arc<SchemaContract>>,
+    progress: Option<Arc<WriteProgress>>,
+    execution: ExecutionOpts,
 }
 impl DataFusionPageWriter {
     pub fn new(
@@ -734,8 +2504,176 @@ impl DataFusionPageWriter {
             table_name: table_name.into(),
             sql: sql.into(),
             final_writer,
+            raw_json: false,
+            ejson: false,
+            fields: FieldMapping::default(),
+            transform: RecordTransform::default(),
+            flatten: None,
+            numbers: NumberHandling::default(),
+            nulls: NullHandling::default(),
+            dictionary: None,
+            metadata: None,
+            constants: None,
+            contract: None,
+            progress: None,
+            execution: ExecutionOpts::default(),
+        }
+    }
+
+    /// Batch size, buffering, and parallelism of the module SQL.
+    pub fn with_execution(mut self, execution: ExecutionOpts) -> Self {
+        self.execution = execution;
+        self
+    }
+
+    /// Exposes each record as a single `data` text column instead of inferring a schema.
+    pub fn with_raw_json(mut self, enabled: bool) -> Self {
+        self.raw_json = enabled;
+        self
+    }
+
+    /// Unwraps MongoDB extended JSON before anything else sees a record.
+    pub fn with_ejson(mut self, enabled: bool) -> Self {
+        self.ejson = enabled;
+        self
+    }
+
+    /// Renames and drops fields on every record before anything else sees it.
+    pub fn with_field_mapping(mut self, fields: FieldMapping) -> Self {
+        self.fields = fields;
+        self
+    }
+
+    /// Adds the run's metadata columns to every row after the SQL runs.
+    pub fn with_metadata(mut self, metadata: Option<MetadataStamp>) -> Self {
+        self.metadata = metadata.filter(|m| !m.is_empty());
+        self
+    }
+
+    /// Adds the run's constant columns to every row after the SQL runs.
+    pub fn with_constant_columns(mut self, constants: Option<ConstantColumns>) -> Self {
+        self.constants = constants.filter(|c| !c.is_empty());
+        self
+    }
+
+    /// Checks the schema inferred from each page, or each stream's samples,
+    /// against `contract`.
+    pub fn with_schema_contract(mut self, contract: Option<Arc<SchemaContract>>) -> Self {
+        self.contract = contract;
+        self
+    }
+
+    /// Appends the metadata and constant columns to the query's output, when
+    /// configured.
+    fn stamped(
+        &self,
+        page: Option<u64>,
+        mut stream: JsonStreamType,
+        mut schema: SchemaRef,
+    ) -> Result<(JsonStreamType, SchemaRef)> {
+        if let Some(metadata) = self.metadata.clone() {
+            schema = metadata.schema(&schema);
+            stream = stream
+                .map(move |row| row.map(|v| metadata.apply(v, page)))
+                .boxed();
+        }
+        if let Some(constants) = self.constants.clone() {
+            schema = constants.schema(&schema)?;
+            stream = stream
+                .map(move |row| row.map(|v| constants.apply(v)))
+                .boxed();
+        }
+        Ok((stream, schema))
+    }
+
+    /// Counts rows handed to the final writer into `progress`.
+    pub fn with_progress(mut self, progress: Arc<WriteProgress>) -> Self {
+        self.progress = Some(progress);
+        self
+    }
+
+    /// Wraps `stream` so its rows are counted, when progress is tracked.
+    fn counted(&self, stream: JsonStreamType) -> JsonStreamType {
+        match &self.progress {
+            Some(progress) => progress.count_rows(stream),
+            None => stream,
         }
     }
+
+    /// Runs `transform` statements on every record, after field mapping.
+    pub fn with_transform(mut self, transform: RecordTransform) -> Self {
+        self.transform = transform;
+        self
+    }
+
+    /// Flattens nested objects into columns, after `transform`.
+    pub fn with_flatten(mut self, flatten: Option<Flatten>) -> Self {
+        self.flatten = flatten;
+        self
+    }
+
+    /// Lands out-of-range integers and `string_columns` as text (or errors),
+    /// after field mapping.
+    pub fn with_number_handling(mut self, numbers: NumberHandling) -> Self {
+        self.numbers = numbers;
+        self
+    }
+
+    /// Reads null-like strings (`""`, `"N/A"`, ...) as null, after field mapping.
+    pub fn with_null_handling(mut self, nulls: NullHandling) -> Self {
+        self.nulls = nulls;
+        self
+    }
+
+    /// Builds the configured (or detected) low-cardinality string columns as
+    /// dictionary arrays for the module SQL; the writer gets plain text.
+    pub fn with_dictionary(mut self, dictionary: Option<DictionaryEncoding>) -> Self {
+        self.dictionary = dictionary;
+        self
+    }
+
+    /// True when records reach the SQL as fetched.
+    fn passes_through(&self) -> bool {
+        !self.ejson
+            && self.fields.is_empty()
+            && self.nulls.is_empty()
+            && self.transform.is_empty()
+            && self.flatten.is_none()
+    }
+
+    /// Extended JSON unwrapping, field mapping, null coercion, `transform`,
+    /// then flattening, on one record.
+    fn prepare(&self, record: Value) -> Value {
+        prepare_record(
+            record,
+            self.ejson,
+            &self.fields,
+            &self.nulls,
+            &self.transform,
+            self.flatten.as_ref(),
+        )
+    }
+}
+
+/// The record steps of [`DataFusionPageWriter`], in order.
+fn prepare_record(
+    record: Value,
+    ejson: bool,
+    fields: &FieldMapping,
+    nulls: &NullHandling,
+    transform: &RecordTransform,
+    flatten: Option<&Flatten>,
+) -> Value {
+    let record = if ejson {
+        unwrap_extended(record)
+    } else {
+        record
+    };
+    let record = transform.apply(nulls.apply(fields.apply(record)));
+    match flatten {
+        Some(flatten) => flatten.apply(record),
+        None => record,
+    }
 }
 
 #[async_trait]
@@ -751,16 +2689,54 @@ impl PageWriter for DataFusionPageWriter {
         let span = info_span!("transform.load", table = %self.table_name, page = page_number, items = items);
         let _g = span.enter();
 
+        let data = if self.passes_through() {
+            data
+        } else {
+            data.into_iter().map(|v| self.prepare(v)).collect()
+        };
+        let data = if self.raw_json {
+            data.iter().map(wrap_raw_json).collect::<Result<Vec<_>>>()?
+        } else {
+            self.numbers.apply_page(data)?
+        };
+        if let (Some(contract), false) = (&self.contract, self.raw_json) {
+            if !data.is_empty() {
+                let schema = infer_schema_from_values(&data)?;
+                contract.check(&self.table_name, &schema)?;
+            }
+        }
+        let fields = match &self.dictionary {
+            Some(dictionary) if !self.raw_json && !data.is_empty() => {
+                Some(dictionary.encode(&sample_fields(&data)?, &data))
+            }
+            _ => None,
+        };
         let json_array = Value::Array(data);
-        let sdf = json_array.to_sql(&self.table_name, &self.sql).await?;
+        let ctx = session_context(&self.execution).await?;
+        let sdf = match fields {
+            Some(fields) => {
+                json_array
+                    .to_sql_with_fields(ctx, &self.table_name, &self.sql, &fields)
+                    .await?
+            }
+            None => {
+                json_array
+                    .to_sql_in(ctx, &self.table_name, &self.sql)
+                    .await?
+            }
+        };
         let result_stream = sdf.inner().to_stream().await?;
+        let result_schema = decoded_schema(sdf.inner().schema().as_arrow());
+        let (result_stream, result_schema) =
+            self.stamped(Some(page_number), result_stream, result_schema)?;
         // Use structured fields for the downstream writer call
         let table_page = format!("{}_page_{}", self.table_name, page_number);
         self.final_writer
             .write_stream(
                 QueryResultStream {
                     table_name: table_page,
-                    data: result_stream,
+                    data: self.counted(result_stream),
+                    schema: Some(result_schema),
                 },
                 write_mode,
             )
@@ -771,13 +2747,67 @@ impl PageWriter for DataFusionPageWriter {
     async fn write_page_stream(
         &self,
         json_stream: Pin<Box<dyn Stream<Item = Result<serde_json::Value>> + Send>>,
+        write_mode: WriteMode,
+    ) -> Result<()> {
+        self.write_stream_through_sql(None, json_stream, write_mode)
+            .await
+    }
+
+    async fn write_numbered_page_stream(
+        &self,
+        page_number: u64,
+        json_stream: Pin<Box<dyn Stream<Item = Result<serde_json::Value>> + Send>>,
+        write_mode: WriteMode,
+    ) -> Result<()> {
+        self.write_stream_through_sql(Some(page_number), json_stream, write_mode)
+            .await
+    }
+
+    async fn commit(&self) -> Result<()> {
+        self.final_writer.commit().await
+    }
+}
+
+impl DataFusionPageWriter {
+    /// Streams records through the module SQL into the final writer;
+    /// `page` is `None` when the stream spans several pages.
+    async fn write_stream_through_sql(
+        &self,
+        page: Option<u64>,
+        json_stream: Pin<Box<dyn Stream<Item = Result<serde_json::Value>> + Send>>,
         _write_mode: WriteMode,
     ) -> Result<()> {
         debug!("starting streaming pipeline");
-        let ctx = get_shared_context().await;
+        let ctx = session_context(&self.execution).await?;
+
+        let json_stream = if self.passes_through() {
+            json_stream
+        } else {
+            let ejson = self.ejson;
+            let fields = self.fields.clone();
+            let nulls = self.nulls.clone();
+            let transform = self.transform.clone();
+            let flatten = self.flatten.clone();
+            json_stream
+                .map(move |item| {
+                    item.map(|v| {
+                        prepare_record(v, ejson, &fields, &nulls, &transform, flatten.as_ref())
+                    })
+                })
+                .boxed()
+        };
+        let json_stream = if self.raw_json {
+            json_stream
+                .map(|item| item.and_then(|v| wrap_raw_json(&v)))
+                .boxed()
+        } else {
+            json_stream
+        };
 
         // Single-producer, single-consumer channel with increased buffer for better throughput
-        let (tx, mut rx) = tokio::sync::mpsc::channel::<Result<serde_json::Value>>(8192);
+        let (tx, mut rx) = tokio::sync::mpsc::channel::<Result<serde_json::Value>>(
+            self.execution.max_buffered_items.max(1),
+        );
 
         // Move the ONLY sender into the task so the channel closes when done.
         let _stream_task = tokio::spawn(async move {
@@ -794,7 +2824,7 @@ impl PageWriter for DataFusionPageWriter {
         // --------- Sample for schema (and keep the samples) ---------
         let mut samples: Vec<serde_json::Value> = Vec::new();
 
-        while samples.len() < 100 {
+        while samples.len() < STREAM_SAMPLE_SIZE {
             match rx.recv().await {
                 Some(Ok(v)) => samples.push(v),
                 Some(Err(e)) => {
@@ -810,7 +2840,32 @@ impl PageWriter for DataFusionPageWriter {
             return Ok(());
         }
 
-        let arrow_schema = infer_schema_from_values(&samples)?;
+        // Text columns are fixed by the samples, like the schema
+        let numbers = Arc::new(if self.raw_json {
+            NumberHandling::default()
+        } else {
+            self.numbers.clone()
+        });
+        let text_columns = Arc::new(numbers.text_columns(&samples)?);
+        let samples = samples
+            .into_iter()
+            .map(|v| numbers.apply_streamed(v, &text_columns))
+            .collect::<Result<Vec<_>>>()?;
+
+        let arrow_schema = if self.raw_json {
+            raw_json_schema()
+        } else {
+            infer_schema_from_values(&samples)?
+        };
+        if let (Some(contract), false) = (&self.contract, self.raw_json) {
+            contract.check(&self.table_name, &arrow_schema)?;
+        }
+        let arrow_schema = match (&self.dictionary, self.raw_json) {
+            (Some(dictionary), false) => Arc::new(Schema::new(
+                dictionary.encode(arrow_schema.fields(), &samples),
+            )),
+            _ => arrow_schema,
+        };
         debug!(
             fields = arrow_schema.fields().len(),
             field_names = ?arrow_schema.fields().iter().map(|f| f.name().as_str()).collect::<Vec<_>>(),
@@ -830,6 +2885,8 @@ impl PageWriter for DataFusionPageWriter {
             move || {
                 let prefix = Arc::clone(&prefix);
                 let rx_arc = Arc::clone(&rx_arc);
+                let numbers = Arc::clone(&numbers);
+                let text_columns = Arc::clone(&text_columns);
                 async_stream::stream! {
                     loop {
                         // 1) yield buffered samples first
@@ -843,7 +2900,7 @@ impl PageWriter for DataFusionPageWriter {
                         match r.recv().await {
                             Some(item) => {
                                 drop(r); // release lock before yield
-                                yield item;
+                                yield item.and_then(|v| numbers.apply_streamed(v, &text_columns));
                             }
                             None => break, // channel closed: end of stream
                         }
@@ -854,7 +2911,8 @@ impl PageWriter for DataFusionPageWriter {
         };
 
         // Create table provider with schema
-        let table_provider = JsonStreamTableProvider::new(Arc::new(stream_factory), arrow_schema);
+        let table_provider = JsonStreamTableProvider::new(Arc::new(stream_factory), arrow_schema)
+            .with_stream_config(self.execution.stream_config());
 
         // Use a unique table name to avoid conflicts in shared context
         // Use only alphanumeric characters to avoid SQL parsing issues
@@ -872,22 +2930,27 @@ impl PageWriter for DataFusionPageWriter {
         ctx.register_table(unique_table_name.clone(), Arc::new(table_provider))?;
 
         // Replace the original table name in SQL with the unique table name
-        let sql_with_unique_table = self.sql.replace(&self.table_name, &unique_table_name);
+        let sql_with_unique_table =
+            substitute_source(&self.sql, &self.table_name, &unique_table_name);
 
         let df = ctx.sql(&sql_with_unique_table).await?;
 
         // Execute query and get streaming results
         let record_batch_stream = df.execute_stream().await?;
+        let result_schema = decoded_schema(&record_batch_stream.schema());
 
         // Convert RecordBatch stream to JSON stream for the writer
         let json_value_stream = convert_record_batch_to_json(record_batch_stream);
+        let (json_value_stream, result_schema) =
+            self.stamped(page, json_value_stream, result_schema)?;
 
         // Write the streaming results to the final destination
         self.final_writer
             .write_stream(
                 QueryResultStream {
                     table_name: self.table_name.clone(),
-                    data: json_value_stream,
+                    data: self.counted(json_value_stream),
+                    schema: Some(result_schema),
                 },
                 _write_mode,
             )
@@ -898,9 +2961,6 @@ impl PageWriter for DataFusionPageWriter {
 
         Ok(())
     }
-    async fn commit(&self) -> Result<()> {
-        self.final_writer.commit().await
-    }
 }
 
 pub async fn infer_schema_and_create_factory(
@@ -945,8 +3005,7 @@ fn convert_record_batch_to_json(
 ) -> BoxStream<'static, Result<serde_json::Value>> {
     let json_stream = async_stream::try_stream! {
         while let Some(batch_result) = stream.next().await {
-            let batch = batch_result
-                .map_err(|e| ApitapError::PipelineError(format!("RecordBatch error: {}", e)))?;
+            let batch = decode_batch(batch_result?)?;
 
             for row_index in 0..batch.num_rows() {
                 let mut row_json = serde_json::Map::new();
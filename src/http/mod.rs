@@ -1,6 +1,72 @@
+pub mod capture;
 pub mod fetcher;
+pub mod oauth2;
+pub mod preflight;
+pub mod signing;
+pub mod stop;
+use std::time::Duration;
+
 use datafusion::common::HashMap;
 use reqwest::Client;
+use serde::{Deserialize, Serialize};
+
+/// `User-Agent` sent when neither the source nor `defaults.headers` sets one.
+pub const DEFAULT_USER_AGENT: &str = concat!("apitap/", env!("CARGO_PKG_VERSION"));
+
+/// Idle connections kept per host unless a source sets `pool_max_idle_per_host`.
+pub const DEFAULT_POOL_MAX_IDLE_PER_HOST: usize = 10;
+/// Seconds an idle connection is kept unless a source sets `pool_idle_timeout_secs`.
+pub const DEFAULT_POOL_IDLE_TIMEOUT_SECS: u64 = 90;
+/// Seconds between TCP keep-alive probes unless a source sets `tcp_keepalive_secs`.
+pub const DEFAULT_TCP_KEEPALIVE_SECS: u64 = 60;
+
+/// Connection pool settings of a source's HTTP client.
+///
+/// One client serves every page, retry, and `path_params` request of a run,
+/// so pages after the first reuse its pooled connections instead of paying
+/// for a new TCP and TLS handshake. Unset values keep the defaults above.
+///
+/// ```yaml
+/// sources:
+///   - name: events
+///     url: https://api.example.com/events
+///     concurrency: 16
+///     pool_max_idle_per_host: 16   # one warm connection per concurrent page
+///     pool_idle_timeout_secs: 30   # under the server's keep-alive timeout
+///     tcp_keepalive_secs: 0        # no TCP keep-alive probes
+/// ```
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct HttpPool {
+    /// Idle connections kept open per host; `0` closes each after use.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub pool_max_idle_per_host: Option<usize>,
+    /// Seconds an idle connection is kept before being closed.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub pool_idle_timeout_secs: Option<u64>,
+    /// Seconds between TCP keep-alive probes on open connections; `0` turns
+    /// them off.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub tcp_keepalive_secs: Option<u64>,
+}
+
+impl HttpPool {
+    /// `builder` with these settings, or their defaults, applied.
+    pub fn apply(&self, builder: reqwest::ClientBuilder) -> reqwest::ClientBuilder {
+        let keepalive = self
+            .tcp_keepalive_secs
+            .unwrap_or(DEFAULT_TCP_KEEPALIVE_SECS);
+        builder
+            .pool_max_idle_per_host(
+                self.pool_max_idle_per_host
+                    .unwrap_or(DEFAULT_POOL_MAX_IDLE_PER_HOST),
+            )
+            .pool_idle_timeout(Duration::from_secs(
+                self.pool_idle_timeout_secs
+                    .unwrap_or(DEFAULT_POOL_IDLE_TIMEOUT_SECS),
+            ))
+            .tcp_keepalive((keepalive > 0).then(|| Duration::from_secs(keepalive)))
+    }
+}
 
 #[derive(Clone)]
 pub struct Http {
@@ -8,6 +74,8 @@ pub struct Http {
     params: Option<HashMap<String, String>>,
     headers: Option<HashMap<String, String>>,
     bearer_auth: Option<String>,
+    accept_invalid_certs: bool,
+    pool: HttpPool,
 }
 
 impl Http {
@@ -17,6 +85,8 @@ impl Http {
             params: None,
             headers: None,
             bearer_auth: None,
+            accept_invalid_certs: false,
+            pool: HttpPool::default(),
         }
     }
     pub fn param(mut self, key: impl Into<String>, value: impl Into<String>) -> Self {
@@ -33,6 +103,16 @@ impl Http {
         self.bearer_auth = Some(token.into());
         self
     }
+    /// Accepts any TLS certificate, including self-signed and expired ones.
+    pub fn danger_accept_invalid_certs(mut self, accept: bool) -> Self {
+        self.accept_invalid_certs = accept;
+        self
+    }
+    /// Connection pool and keep-alive settings of the built client.
+    pub fn pool(mut self, pool: HttpPool) -> Self {
+        self.pool = pool;
+        self
+    }
     pub fn build_client(&self) -> Client {
         let mut headers = reqwest::header::HeaderMap::new();
 
@@ -59,16 +139,15 @@ impl Http {
             }
         }
 
-        Client::builder()
-            .default_headers(headers)
-            // ===== HTTP Connection Pooling & Keep-Alive Optimizations =====
-            // Based on flamegraph analysis: reduce TLS handshake overhead (6.48% CPU time)
-            // Enable HTTP connection reuse and configure pool settings
-            .pool_max_idle_per_host(10) // Keep up to 10 idle connections per host
-            .pool_idle_timeout(Some(std::time::Duration::from_secs(90))) // Keep connections alive for 90s
-            .timeout(std::time::Duration::from_secs(30)) // Request timeout
-            .connect_timeout(std::time::Duration::from_secs(10)) // Connection timeout
-            .tcp_keepalive(Some(std::time::Duration::from_secs(60))) // TCP keepalive
+        let builder = Client::builder().default_headers(headers);
+        // ===== HTTP Connection Pooling & Keep-Alive Optimizations =====
+        // Based on flamegraph analysis: reduce TLS handshake overhead (6.48% CPU time)
+        // Enable HTTP connection reuse and configure pool settings
+        self.pool
+            .apply(builder)
+            .timeout(Duration::from_secs(30)) // Request timeout
+            .connect_timeout(Duration::from_secs(10)) // Connection timeout
+            .danger_accept_invalid_certs(self.accept_invalid_certs)
             // TLS session resumption is enabled by default in reqwest
             .build()
             .unwrap_or_else(|_| Client::new())
@@ -0,0 +1,266 @@
+//! OAuth2 client-credentials tokens for `auth: { type: oauth2 }` sources.
+//!
+//! Tokens are cached process-wide by token URL, client id, and scope, so
+//! every module and every run against the same credentials shares one token
+//! until shortly before it expires, instead of calling the token endpoint
+//! once per module. Concurrent requests for a missing or expired token wait
+//! for a single refresh.
+
+use std::collections::HashMap;
+use std::fmt::Debug;
+use std::future::Future;
+use std::sync::{Arc, Mutex, OnceLock};
+use std::time::{Duration, Instant};
+
+use async_trait::async_trait;
+use reqwest::header::{HeaderValue, AUTHORIZATION};
+use reqwest::{Client, Request, Response, StatusCode};
+use serde::Deserialize;
+
+use crate::errors::{ApitapError, Result};
+use crate::http::signing::RequestSigner;
+use crate::http::Http;
+
+/// Seconds before `expires_in` runs out at which a cached token is refreshed.
+/// Tokens living less than twice this long are refreshed halfway instead.
+pub const TOKEN_EXPIRY_MARGIN_SECS: u64 = 30;
+/// Lifetime assumed for tokens whose response has no `expires_in`.
+pub const DEFAULT_TOKEN_LIFETIME_SECS: u64 = 3600;
+
+/// What a cached token is shared by.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct TokenKey {
+    pub token_url: String,
+    pub client_id: String,
+    pub scope: Option<String>,
+}
+
+struct CachedToken {
+    access_token: String,
+    refresh_at: Instant,
+}
+
+type Slot = Arc<tokio::sync::Mutex<Option<CachedToken>>>;
+
+/// Access tokens by [`TokenKey`].
+///
+/// # Example
+///
+/// ```
+/// use std::time::Duration;
+/// use apitap::http::oauth2::{TokenCache, TokenKey};
+///
+/// # #[tokio::main(flavor = "current_thread")]
+/// # async fn main() {
+/// let cache = TokenCache::default();
+/// let key = TokenKey {
+///     token_url: "https://idp.example.com/token".into(),
+///     client_id: "etl".into(),
+///     scope: None,
+/// };
+/// let fetch = || async { Ok(("t-1".to_string(), Duration::from_secs(3600))) };
+/// assert_eq!(cache.get_or_fetch(&key, fetch).await.unwrap(), "t-1");
+///
+/// // Still valid, so the second fetch is never called
+/// let fetch = || async { Ok(("t-2".to_string(), Duration::from_secs(3600))) };
+/// assert_eq!(cache.get_or_fetch(&key, fetch).await.unwrap(), "t-1");
+/// # }
+/// ```
+#[derive(Default)]
+pub struct TokenCache {
+    slots: Mutex<HashMap<TokenKey, Slot>>,
+}
+
+impl Debug for TokenCache {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let keys = self.slots.lock().map(|s| s.len()).unwrap_or_default();
+        f.debug_struct("TokenCache").field("keys", &keys).finish()
+    }
+}
+
+impl TokenCache {
+    /// The process-wide cache used by `oauth2` sources.
+    pub fn global() -> Arc<TokenCache> {
+        static GLOBAL: OnceLock<Arc<TokenCache>> = OnceLock::new();
+        GLOBAL.get_or_init(Arc::default).clone()
+    }
+
+    fn slot(&self, key: &TokenKey) -> Slot {
+        self.slots
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .entry(key.clone())
+            .or_default()
+            .clone()
+    }
+
+    /// Returns the cached token for `key`, or calls `fetch` for a new one
+    /// when there is none or it is within [`TOKEN_EXPIRY_MARGIN_SECS`] (or,
+    /// for short-lived tokens, half its lifetime) of expiring. `fetch` returns
+    /// the token and its lifetime.
+    ///
+    /// # Errors
+    ///
+    /// Returns the error of `fetch`; nothing is cached then.
+    pub async fn get_or_fetch<F, Fut>(&self, key: &TokenKey, fetch: F) -> Result<String>
+    where
+        F: FnOnce() -> Fut,
+        Fut: Future<Output = Result<(String, Duration)>>,
+    {
+        let slot = self.slot(key);
+        let mut cached = slot.lock().await;
+        if let Some(token) = cached.as_ref() {
+            if Instant::now() < token.refresh_at {
+                return Ok(token.access_token.clone());
+            }
+        }
+        let (access_token, lifetime) = fetch().await?;
+        let margin = Duration::from_secs(TOKEN_EXPIRY_MARGIN_SECS).min(lifetime / 2);
+        *cached = Some(CachedToken {
+            access_token: access_token.clone(),
+            refresh_at: Instant::now() + lifetime.saturating_sub(margin),
+        });
+        Ok(access_token)
+    }
+
+    /// Drops the token cached for `key` so the next request fetches a new
+    /// one. Skipped while a refresh for `key` is in flight.
+    pub fn invalidate(&self, key: &TokenKey) {
+        if let Ok(mut cached) = self.slot(key).try_lock() {
+            *cached = None;
+        }
+    }
+}
+
+#[derive(Deserialize)]
+struct TokenResponse {
+    access_token: String,
+    #[serde(default)]
+    expires_in: Option<u64>,
+}
+
+/// Sends a client-credentials access token as `Authorization: Bearer` on
+/// every request.
+///
+/// A `401` response drops the cached token, so the next request, including
+/// one in another module, fetches a new one.
+///
+/// # Example
+///
+/// ```
+/// use apitap::http::oauth2::ClientCredentials;
+///
+/// let auth = ClientCredentials::new("https://idp.example.com/token", "etl", "s3cr3t")
+///     .with_scope("orders:read".to_string());
+/// assert_eq!(auth.key().scope.as_deref(), Some("orders:read"));
+/// ```
+#[derive(Clone)]
+pub struct ClientCredentials {
+    key: TokenKey,
+    client_secret: String,
+    client: Client,
+    cache: Arc<TokenCache>,
+}
+
+impl Debug for ClientCredentials {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("ClientCredentials")
+            .field("key", &self.key)
+            .field("client_secret", &"***")
+            .finish()
+    }
+}
+
+impl ClientCredentials {
+    pub fn new(
+        token_url: impl Into<String>,
+        client_id: impl Into<String>,
+        client_secret: impl Into<String>,
+    ) -> Self {
+        Self {
+            key: TokenKey {
+                token_url: token_url.into(),
+                client_id: client_id.into(),
+                scope: None,
+            },
+            client_secret: client_secret.into(),
+            client: Http::new("").build_client(),
+            cache: TokenCache::global(),
+        }
+    }
+
+    pub fn with_scope(mut self, scope: impl Into<Option<String>>) -> Self {
+        self.key.scope = scope.into();
+        self
+    }
+
+    /// Requests tokens with `client`, e.g. one with the source's TLS and pool
+    /// settings. The default client has the usual request timeout and no
+    /// default headers.
+    pub fn with_client(mut self, client: Client) -> Self {
+        self.client = client;
+        self
+    }
+
+    /// Uses `cache` instead of the process-wide one.
+    pub fn with_cache(mut self, cache: Arc<TokenCache>) -> Self {
+        self.cache = cache;
+        self
+    }
+
+    pub fn key(&self) -> &TokenKey {
+        &self.key
+    }
+
+    /// A valid access token, from the cache when possible.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`ApitapError::TokenError`] when the token endpoint fails or
+    /// answers without an `access_token`.
+    pub async fn token(&self) -> Result<String> {
+        self.cache
+            .get_or_fetch(&self.key, || self.request_token())
+            .await
+    }
+
+    async fn request_token(&self) -> Result<(String, Duration)> {
+        let mut form = vec![
+            ("grant_type", "client_credentials"),
+            ("client_id", self.key.client_id.as_str()),
+            ("client_secret", self.client_secret.as_str()),
+        ];
+        if let Some(scope) = &self.key.scope {
+            form.push(("scope", scope));
+        }
+        let url = &self.key.token_url;
+        let response = self.client.post(url).form(&form).send().await?;
+        let status = response.status();
+        if !status.is_success() {
+            return Err(ApitapError::TokenError(format!("{url} returned {status}")));
+        }
+        let token: TokenResponse = response
+            .json()
+            .await
+            .map_err(|e| ApitapError::TokenError(format!("{url}: invalid response: {e}")))?;
+        let lifetime = token.expires_in.unwrap_or(DEFAULT_TOKEN_LIFETIME_SECS);
+        Ok((token.access_token, Duration::from_secs(lifetime)))
+    }
+}
+
+#[async_trait]
+impl RequestSigner for ClientCredentials {
+    async fn sign(&self, request: &mut Request) -> Result<()> {
+        let token = self.token().await?;
+        let mut value = HeaderValue::from_str(&format!("Bearer {token}"))?;
+        value.set_sensitive(true);
+        request.headers_mut().insert(AUTHORIZATION, value);
+        Ok(())
+    }
+
+    fn on_response(&self, response: &Response) {
+        if response.status() == StatusCode::UNAUTHORIZED {
+            self.cache.invalidate(&self.key);
+        }
+    }
+}
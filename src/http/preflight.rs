@@ -0,0 +1,96 @@
+//! `HEAD` requests sent before fetching a source with `preflight: true`.
+//!
+//! The response's `Content-Type` and `Content-Length` are logged, and when
+//! the source leaves `format` unset the content type picks it (XML, CSV, TSV,
+//! or JSON). Endpoints that reject `HEAD`, or fail it, are fetched as if
+//! preflight were off.
+
+use reqwest::header::{HeaderName, CONTENT_LENGTH, CONTENT_TYPE};
+use reqwest::Client;
+use tracing::info;
+use url::Url;
+
+use crate::http::fetcher::ResponseFormat;
+use crate::utils::redact::redact_url;
+
+/// Preflight settings of one request.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct Preflight {
+    /// The source set no `format`, so the content type may choose one.
+    pub detect_format: bool,
+}
+
+/// What a `HEAD` response reported about the resource.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct HeadInfo {
+    pub content_type: Option<String>,
+    pub content_length: Option<u64>,
+}
+
+impl HeadInfo {
+    /// Sends `HEAD url?query` with `client`'s default headers.
+    ///
+    /// Returns `None`, after logging why, when the request fails or the
+    /// response status is not a success.
+    pub async fn fetch(client: &Client, url: &Url, query: &[(String, String)]) -> Option<Self> {
+        let shown = redact_url(url.as_str());
+        let resp = match client.head(url.clone()).query(query).send().await {
+            Ok(resp) => resp,
+            Err(e) => {
+                info!(url = %shown, error = %e, "preflight HEAD failed; fetching without it");
+                return None;
+            }
+        };
+        if !resp.status().is_success() {
+            info!(url = %shown, status = %resp.status(), "preflight HEAD not supported; fetching without it");
+            return None;
+        }
+        let header = |name: HeaderName| {
+            resp.headers()
+                .get(name)
+                .and_then(|v| v.to_str().ok())
+                .map(str::to_string)
+        };
+        let head = HeadInfo {
+            content_type: header(CONTENT_TYPE),
+            content_length: header(CONTENT_LENGTH).and_then(|len| len.trim().parse().ok()),
+        };
+        info!(
+            url = %shown,
+            content_type = head.content_type.as_deref().unwrap_or("-"),
+            content_length = ?head.content_length,
+            "🔎 preflight"
+        );
+        Some(head)
+    }
+
+    /// The response format the content type names, if any.
+    pub fn format(&self) -> Option<ResponseFormat> {
+        self.content_type
+            .as_deref()
+            .and_then(ResponseFormat::from_content_type)
+    }
+}
+
+impl Preflight {
+    /// Runs the preflight for `url` and returns the format to read it with:
+    /// `format` unless it may be detected and the content type names another.
+    pub async fn resolve_format(
+        &self,
+        client: &Client,
+        url: &Url,
+        query: &[(String, String)],
+        format: ResponseFormat,
+    ) -> ResponseFormat {
+        let Some(head) = HeadInfo::fetch(client, url, query).await else {
+            return format;
+        };
+        match head.format() {
+            Some(detected) if self.detect_format && detected != format => {
+                info!(url = %redact_url(url.as_str()), format = ?detected, "using the response format from the preflight content type");
+                detected
+            }
+            _ => format,
+        }
+    }
+}
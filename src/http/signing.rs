@@ -0,0 +1,270 @@
+// src/http/signing.rs
+
+//! Request signing hooks.
+//!
+//! A [`RequestSigner`] sees every outgoing request right before it is sent
+//! (after retries are scheduled, so each attempt is signed afresh) and every
+//! successful response afterwards. Attach one to a source through
+//! [`RequestTemplate::signer`](crate::http::fetcher::RequestTemplate::signer).
+//!
+//! [`HmacSigner`] covers the common "HMAC-SHA256 over method, path, timestamp,
+//! and body" scheme used by many partner APIs.
+
+use std::fmt::Debug;
+use std::sync::Arc;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use async_trait::async_trait;
+use hmac::{Hmac, Mac};
+use http::Extensions;
+use reqwest::header::{HeaderName, HeaderValue};
+use reqwest::{Request, Response};
+use reqwest_middleware::{Middleware, Next, Result as MwResult};
+use sha2::Sha256;
+
+use crate::errors::{ApitapError, Result};
+
+/// Hook that can inspect and modify requests before dispatch.
+#[async_trait]
+pub trait RequestSigner: Debug + Send + Sync {
+    /// Called for every attempt just before it is sent; may add or rewrite headers.
+    async fn sign(&self, request: &mut Request) -> Result<()>;
+
+    /// Called with each response that reached the server, before status checks.
+    fn on_response(&self, _response: &Response) {}
+}
+
+/// Adapts a [`RequestSigner`] to `reqwest-middleware`.
+pub struct SigningMiddleware {
+    signer: Arc<dyn RequestSigner>,
+}
+
+impl SigningMiddleware {
+    pub fn new(signer: Arc<dyn RequestSigner>) -> Self {
+        Self { signer }
+    }
+}
+
+#[async_trait]
+impl Middleware for SigningMiddleware {
+    async fn handle(
+        &self,
+        mut req: Request,
+        extensions: &mut Extensions,
+        next: Next<'_>,
+    ) -> MwResult<Response> {
+        self.signer
+            .sign(&mut req)
+            .await
+            .map_err(reqwest_middleware::Error::middleware)?;
+
+        let res = next.run(req, extensions).await;
+        if let Ok(resp) = &res {
+            self.signer.on_response(resp);
+        }
+        res
+    }
+}
+
+/// Signs requests with a hex-encoded HMAC-SHA256.
+///
+/// The signed string is
+///
+/// ```text
+/// METHOD \n PATH[?QUERY] \n TIMESTAMP \n BODY
+/// ```
+///
+/// where `TIMESTAMP` is the Unix time in seconds (sent in `timestamp_header`
+/// when set, otherwise left empty) and `BODY` is the raw request body.
+///
+/// # Example
+///
+/// ```
+/// use apitap::http::signing::HmacSigner;
+///
+/// let signer = HmacSigner::new("s3cr3t")
+///     .with_header("X-Signature")
+///     .with_timestamp_header("X-Timestamp".to_string());
+/// let sig = signer.signature("GET", "/v1/orders?page=2", "1700000000", b"");
+/// assert_eq!(sig.len(), 64);
+/// ```
+#[derive(Clone)]
+pub struct HmacSigner {
+    secret: Vec<u8>,
+    pub header: String,
+    pub timestamp_header: Option<String>,
+    /// Prepended to the hex digest, e.g. `sha256=`.
+    pub prefix: String,
+}
+
+impl Debug for HmacSigner {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("HmacSigner")
+            .field("secret", &"***")
+            .field("header", &self.header)
+            .field("timestamp_header", &self.timestamp_header)
+            .field("prefix", &self.prefix)
+            .finish()
+    }
+}
+
+impl HmacSigner {
+    pub fn new(secret: impl AsRef<[u8]>) -> Self {
+        Self {
+            secret: secret.as_ref().to_vec(),
+            header: "X-Signature".to_string(),
+            timestamp_header: None,
+            prefix: String::new(),
+        }
+    }
+
+    pub fn with_header(mut self, header: impl Into<String>) -> Self {
+        self.header = header.into();
+        self
+    }
+
+    pub fn with_timestamp_header(mut self, header: impl Into<Option<String>>) -> Self {
+        self.timestamp_header = header.into();
+        self
+    }
+
+    pub fn with_prefix(mut self, prefix: impl Into<String>) -> Self {
+        self.prefix = prefix.into();
+        self
+    }
+
+    /// Computes the header value for the given request parts.
+    pub fn signature(&self, method: &str, path: &str, timestamp: &str, body: &[u8]) -> String {
+        let mut mac =
+            Hmac::<Sha256>::new_from_slice(&self.secret).expect("HMAC accepts keys of any size");
+        mac.update(method.as_bytes());
+        mac.update(b"\n");
+        mac.update(path.as_bytes());
+        mac.update(b"\n");
+        mac.update(timestamp.as_bytes());
+        mac.update(b"\n");
+        mac.update(body);
+        format!(
+            "{}{}",
+            self.prefix,
+            hex::encode(mac.finalize().into_bytes())
+        )
+    }
+}
+
+#[async_trait]
+impl RequestSigner for HmacSigner {
+    async fn sign(&self, request: &mut Request) -> Result<()> {
+        let timestamp = match &self.timestamp_header {
+            Some(name) => {
+                let now = SystemTime::now()
+                    .duration_since(UNIX_EPOCH)
+                    .map_err(|e| ApitapError::PipelineError(e.to_string()))?
+                    .as_secs()
+                    .to_string();
+                request.headers_mut().insert(
+                    HeaderName::from_bytes(name.as_bytes())?,
+                    HeaderValue::from_str(&now)?,
+                );
+                now
+            }
+            None => String::new(),
+        };
+
+        let url = request.url();
+        let path = match url.query() {
+            Some(q) => format!("{}?{}", url.path(), q),
+            None => url.path().to_string(),
+        };
+        let body = request
+            .body()
+            .and_then(|b| b.as_bytes())
+            .unwrap_or_default();
+
+        let signature = self.signature(request.method().as_str(), &path, &timestamp, body);
+        request.headers_mut().insert(
+            HeaderName::from_bytes(self.header.as_bytes())?,
+            HeaderValue::from_str(&signature)?,
+        );
+        Ok(())
+    }
+}
+
+/// Sends an API key as a query parameter on every request, replacing any
+/// value of the same parameter already in the URL.
+///
+/// # Example
+///
+/// ```
+/// use apitap::http::signing::{QueryParamAuth, RequestSigner};
+///
+/// # #[tokio::main(flavor = "current_thread")]
+/// # async fn main() {
+/// let auth = QueryParamAuth::new("api_key", "k-123");
+/// let url = "https://api.example.com/v1/orders?page=2".parse().unwrap();
+/// let mut request = reqwest::Request::new(reqwest::Method::GET, url);
+/// auth.sign(&mut request).await.unwrap();
+/// assert_eq!(request.url().query(), Some("page=2&api_key=k-123"));
+/// # }
+/// ```
+#[derive(Clone)]
+pub struct QueryParamAuth {
+    pub param: String,
+    value: String,
+}
+
+impl Debug for QueryParamAuth {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("QueryParamAuth")
+            .field("param", &self.param)
+            .field("value", &"***")
+            .finish()
+    }
+}
+
+impl QueryParamAuth {
+    pub fn new(param: impl Into<String>, value: impl Into<String>) -> Self {
+        Self {
+            param: param.into(),
+            value: value.into(),
+        }
+    }
+}
+
+#[async_trait]
+impl RequestSigner for QueryParamAuth {
+    async fn sign(&self, request: &mut Request) -> Result<()> {
+        let url = request.url_mut();
+        let kept: Vec<(String, String)> = url
+            .query_pairs()
+            .filter(|(name, _)| *name != self.param)
+            .map(|(name, value)| (name.into_owned(), value.into_owned()))
+            .collect();
+        url.query_pairs_mut()
+            .clear()
+            .extend_pairs(kept)
+            .append_pair(&self.param, &self.value);
+        Ok(())
+    }
+}
+
+/// Runs several signers in order, e.g. query auth before an HMAC signature
+/// so the signature covers the key.
+#[derive(Debug)]
+pub struct SignerChain(pub Vec<Arc<dyn RequestSigner>>);
+
+#[async_trait]
+impl RequestSigner for SignerChain {
+    async fn sign(&self, request: &mut Request) -> Result<()> {
+        for signer in &self.0 {
+            signer.sign(request).await?;
+        }
+        Ok(())
+    }
+
+    fn on_response(&self, response: &Response) {
+        for signer in &self.0 {
+            signer.on_response(response);
+        }
+    }
+}
@@ -0,0 +1,75 @@
+//! Explicit last-page signals for paginated sources.
+//!
+//! Pagination normally ends on an empty page (or when the cursor or next
+//! link runs out). APIs that return a full last page, or say outright that
+//! nothing follows, can end it earlier with `stop_when`, checked after every
+//! page in each pagination kind:
+//!
+//! ```yaml
+//! stop_when: { path: has_more, equals: false }   # the body says so
+//! stop_when: { path: links.next, equals: null }  # null or missing
+//! stop_when: { short_page: true }                # fewer records than the page size
+//! ```
+//!
+//! `path` is a dotted path or JSON pointer into the whole response body (see
+//! [`crate::utils::json_path`]).
+
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+use crate::utils::json_path;
+
+/// A condition that marks the page just fetched as the last one.
+///
+/// # Example
+///
+/// ```
+/// use apitap::http::stop::StopWhen;
+/// use serde_json::json;
+///
+/// let stop: StopWhen = serde_yaml::from_str("{path: has_more, equals: false}").unwrap();
+/// assert!(stop.matches_body(&json!({"has_more": false, "data": []})));
+/// assert!(!stop.matches_body(&json!({"has_more": true, "data": []})));
+///
+/// let stop: StopWhen = serde_yaml::from_str("{path: next, equals: null}").unwrap();
+/// assert!(stop.matches_body(&json!({"data": []})));
+///
+/// let stop: StopWhen = serde_yaml::from_str("{short_page: true}").unwrap();
+/// assert!(stop.matches_count(7, 10));
+/// assert!(!stop.matches_count(10, 10));
+/// ```
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(untagged, deny_unknown_fields)]
+pub enum StopWhen {
+    /// The value at `path` equals `equals`; `equals: null` also matches a
+    /// missing value.
+    Equals { path: String, equals: Value },
+    /// The page held fewer records than the page size requested.
+    ShortPage { short_page: bool },
+}
+
+impl StopWhen {
+    /// Whether the response `body` marks its page as the last.
+    pub fn matches_body(&self, body: &Value) -> bool {
+        match self {
+            StopWhen::Equals { path, equals } => {
+                let found = json_path::select(body, path).into_iter().next();
+                match (found, equals) {
+                    (None, Value::Null) => true,
+                    (Some(found), equals) => found == equals,
+                    (None, _) => false,
+                }
+            }
+            StopWhen::ShortPage { .. } => false,
+        }
+    }
+
+    /// Whether a page of `records` records, out of `page_size` requested, is
+    /// the last.
+    pub fn matches_count(&self, records: usize, page_size: u64) -> bool {
+        match self {
+            StopWhen::ShortPage { short_page } => *short_page && (records as u64) < page_size,
+            StopWhen::Equals { .. } => false,
+        }
+    }
+}
@@ -1,6 +1,21 @@
 // tracing_setup.rs
+use std::collections::BTreeMap;
+use std::fmt;
+use std::str::FromStr;
+use std::sync::RwLock;
+
+use serde::{Deserialize, Serialize};
+use tracing::field::{Field, Visit};
+use tracing::level_filters::LevelFilter;
+use tracing::span::{Attributes, Id, Record};
+use tracing::subscriber::Interest;
+use tracing::{Event, Metadata, Subscriber};
 use tracing_error::ErrorLayer;
-use tracing_subscriber::{fmt, layer::SubscriberExt, EnvFilter, Registry};
+use tracing_subscriber::layer::{Context, Layer, SubscriberExt};
+use tracing_subscriber::registry::LookupSpan;
+use tracing_subscriber::{EnvFilter, Registry};
+
+use crate::errors::{ApitapError, Result};
 
 /// Initialize tracing subscriber with default environment-based configuration.
 ///
@@ -67,13 +82,15 @@ pub fn init_tracing_with(level: Option<&str>, use_json: bool) {
         Some(lvl) => EnvFilter::new(lvl),
         None => EnvFilter::try_from_default_env().unwrap_or_else(|_| EnvFilter::new("info")),
     };
+    let filter = ModuleLevelFilter::new(filter);
 
     if use_json {
         let subscriber = Registry::default()
             .with(filter)
             .with(
-                fmt::layer()
+                tracing_subscriber::fmt::layer()
                     .json()
+                    .with_current_span(true)
                     .with_target(false)
                     .with_file(false)
                     .with_line_number(false),
@@ -86,7 +103,7 @@ pub fn init_tracing_with(level: Option<&str>, use_json: bool) {
         let subscriber = Registry::default()
             .with(filter)
             .with(
-                fmt::layer()
+                tracing_subscriber::fmt::layer()
                     .with_target(false)
                     .with_file(true)
                     .with_line_number(true),
@@ -97,3 +114,176 @@ pub fn init_tracing_with(level: Option<&str>, use_json: bool) {
             .expect("failed to set global tracing subscriber");
     }
 }
+
+/// Log level of one module, from `{{ log_level("...") }}` or a source's
+/// `log_level`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(try_from = "String", into = "String")]
+pub struct LogLevel(pub LevelFilter);
+
+impl FromStr for LogLevel {
+    type Err = ApitapError;
+
+    fn from_str(s: &str) -> Result<Self> {
+        LevelFilter::from_str(s.trim()).map(LogLevel).map_err(|_| {
+            ApitapError::ConfigError(format!(
+                "unknown log level '{s}' (expected trace, debug, info, warn, error or off)"
+            ))
+        })
+    }
+}
+
+impl TryFrom<String> for LogLevel {
+    type Error = ApitapError;
+
+    fn try_from(s: String) -> Result<Self> {
+        s.parse()
+    }
+}
+
+impl From<LogLevel> for String {
+    fn from(level: LogLevel) -> String {
+        level.to_string()
+    }
+}
+
+impl fmt::Display for LogLevel {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.0.to_string().to_lowercase())
+    }
+}
+
+/// Levels of modules that override the process-wide one, by module name.
+static MODULE_LEVELS: RwLock<BTreeMap<String, LevelFilter>> = RwLock::new(BTreeMap::new());
+
+/// Sets (or with `None`, clears) the level ApiTap's own events use inside
+/// the `module` span of `module`.
+pub fn set_module_level(module: &str, level: Option<LogLevel>) {
+    let mut levels = MODULE_LEVELS.write().unwrap_or_else(|e| e.into_inner());
+    let changed = match level {
+        Some(LogLevel(level)) => levels.insert(module.to_string(), level) != Some(level),
+        None => levels.remove(module).is_some(),
+    };
+    drop(levels);
+    if changed {
+        // Callsites cached as never/always enabled are asked again
+        tracing::callsite::rebuild_interest_cache();
+    }
+}
+
+/// Module name stored on each `module` span.
+struct ModuleName(String);
+
+struct ModuleNameVisitor(Option<String>);
+
+impl Visit for ModuleNameVisitor {
+    fn record_str(&mut self, field: &Field, value: &str) {
+        if field.name() == "module" {
+            self.0 = Some(value.to_string());
+        }
+    }
+
+    fn record_debug(&mut self, field: &Field, value: &dyn fmt::Debug) {
+        if field.name() == "module" {
+            self.0 = Some(format!("{value:?}"));
+        }
+    }
+}
+
+/// An [`EnvFilter`] that lets modules override its level.
+///
+/// Inside a span named `module` whose `module` field names a module given a
+/// level with [`set_module_level`], ApiTap's own spans and events (target
+/// `apitap...`) are filtered by that level, above or below the process-wide
+/// one. Everything else, and events of other crates, go through `base`.
+pub struct ModuleLevelFilter {
+    base: EnvFilter,
+}
+
+impl ModuleLevelFilter {
+    pub fn new(base: EnvFilter) -> Self {
+        Self { base }
+    }
+
+    fn module_level<S>(metadata: &Metadata<'_>, ctx: &Context<'_, S>) -> Option<LevelFilter>
+    where
+        S: Subscriber + for<'a> LookupSpan<'a>,
+    {
+        if !metadata.target().starts_with("apitap") {
+            return None;
+        }
+        let levels = MODULE_LEVELS.read().unwrap_or_else(|e| e.into_inner());
+        if levels.is_empty() {
+            return None;
+        }
+        let span = ctx.lookup_current()?;
+        let module = span
+            .scope()
+            .find_map(|s| s.extensions().get::<ModuleName>().map(|m| m.0.clone()))?;
+        levels.get(&module).copied()
+    }
+}
+
+impl<S> Layer<S> for ModuleLevelFilter
+where
+    S: Subscriber + for<'a> LookupSpan<'a>,
+{
+    fn register_callsite(&self, metadata: &'static Metadata<'static>) -> Interest {
+        let overridden = !MODULE_LEVELS
+            .read()
+            .unwrap_or_else(|e| e.into_inner())
+            .is_empty();
+        if overridden && metadata.target().starts_with("apitap") {
+            return Interest::sometimes();
+        }
+        Layer::<S>::register_callsite(&self.base, metadata)
+    }
+
+    fn enabled(&self, metadata: &Metadata<'_>, ctx: Context<'_, S>) -> bool {
+        match Self::module_level(metadata, &ctx) {
+            Some(level) => metadata.level() <= &level,
+            None => Layer::<S>::enabled(&self.base, metadata, ctx),
+        }
+    }
+
+    fn max_level_hint(&self) -> Option<LevelFilter> {
+        let base = Layer::<S>::max_level_hint(&self.base);
+        let levels = MODULE_LEVELS.read().unwrap_or_else(|e| e.into_inner());
+        match (base, levels.values().max()) {
+            (Some(base), Some(module)) => Some(base.max(*module)),
+            (base, None) => base,
+            (None, Some(_)) => None,
+        }
+    }
+
+    fn on_new_span(&self, attrs: &Attributes<'_>, id: &Id, ctx: Context<'_, S>) {
+        if attrs.metadata().name() == "module" {
+            let mut visitor = ModuleNameVisitor(None);
+            attrs.record(&mut visitor);
+            if let (Some(name), Some(span)) = (visitor.0, ctx.span(id)) {
+                span.extensions_mut().insert(ModuleName(name));
+            }
+        }
+        self.base.on_new_span(attrs, id, ctx);
+    }
+
+    fn on_record(&self, id: &Id, values: &Record<'_>, ctx: Context<'_, S>) {
+        self.base.on_record(id, values, ctx);
+    }
+
+    fn on_event(&self, event: &Event<'_>, ctx: Context<'_, S>) {
+        self.base.on_event(event, ctx);
+    }
+
+    fn on_enter(&self, id: &Id, ctx: Context<'_, S>) {
+        self.base.on_enter(id, ctx);
+    }
+
+    fn on_exit(&self, id: &Id, ctx: Context<'_, S>) {
+        self.base.on_exit(id, ctx);
+    }
+
+    fn on_close(&self, id: Id, ctx: Context<'_, S>) {
+        self.base.on_close(id, ctx);
+    }
+}
@@ -1,6 +1,10 @@
 use apitap::{
-    cmd::{run_pipeline, Cli},
+    cmd::{
+        fetch_remote_locations, infer_module_schema, render_effective_config, render_modules_sql,
+        run_backfill, run_pipeline_once, run_pipeline_with, Cli, RunOptions,
+    },
     log,
+    utils::schema::format_schema,
 };
 use clap::Parser;
 use dotenvy::dotenv;
@@ -9,10 +13,93 @@ use std::process::ExitCode;
 #[tokio::main]
 async fn main() -> ExitCode {
     dotenv().ok();
-    let cli = Cli::parse();
+    let mut cli = Cli::parse();
     log::init_tracing_with(cli.log_level.as_deref(), cli.log_json);
 
-    match run_pipeline(&cli.modules, &cli.yaml_config).await {
+    // Held until exit: remote config and modules are read from its copies
+    let _remote = match fetch_remote_locations(&mut cli).await {
+        Ok(cache) => cache,
+        Err(e) => {
+            eprintln!("error: {e}");
+            return ExitCode::from(1);
+        }
+    };
+
+    if let Some(format) = cli.print_config {
+        return match render_effective_config(&cli.yaml_config, format) {
+            Ok(rendered) => {
+                print!("{rendered}");
+                ExitCode::SUCCESS
+            }
+            Err(e) => {
+                eprintln!("error: {e}");
+                ExitCode::from(1)
+            }
+        };
+    }
+
+    if let Some(module) = &cli.print_sql {
+        let module = Some(module.as_str()).filter(|m| !m.is_empty());
+        return match render_modules_sql(&cli.modules, &cli.yaml_config, module) {
+            Ok(sql) => {
+                print!("{sql}");
+                ExitCode::SUCCESS
+            }
+            Err(e) => {
+                eprintln!("error: {e}");
+                ExitCode::from(1)
+            }
+        };
+    }
+
+    if let Some(module) = &cli.infer_schema {
+        return match infer_module_schema(
+            &cli.modules,
+            &cli.yaml_config,
+            module,
+            &RunOptions::from(&cli),
+        )
+        .await
+        {
+            Ok(schema) => {
+                print!("{}", format_schema(&schema));
+                ExitCode::SUCCESS
+            }
+            Err(e) => {
+                eprintln!("error: {e}");
+                ExitCode::from(1)
+            }
+        };
+    }
+
+    if let Some(backfill) = cli.backfill() {
+        return match run_backfill(
+            &cli.modules,
+            &cli.yaml_config,
+            &backfill,
+            &RunOptions::from(&cli),
+        )
+        .await
+        {
+            Ok(results) if results.iter().all(|r| r.is_success()) => ExitCode::SUCCESS,
+            Err(e) => {
+                eprintln!("error: {e}");
+                ExitCode::from(1)
+            }
+            _ => ExitCode::from(1),
+        };
+    }
+
+    if cli.once || cli.sample.is_some() {
+        return match run_pipeline_once(&cli.modules, &cli.yaml_config, &RunOptions::from(&cli))
+            .await
+        {
+            Ok(results) if results.iter().all(|r| r.is_success()) => ExitCode::SUCCESS,
+            _ => ExitCode::from(1),
+        };
+    }
+
+    match run_pipeline_with(&cli.modules, &cli.yaml_config, &RunOptions::from(&cli)).await {
         Ok(_) => ExitCode::SUCCESS,
         Err(_) => ExitCode::from(1),
     }
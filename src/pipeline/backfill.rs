@@ -0,0 +1,144 @@
+//! Date-window backfills.
+//!
+//! `--backfill --from 2024-01-01 --to 2024-12-31 --window 7d` runs every
+//! module once per window instead of once. Each run sees the window's bounds
+//! through the `window_start()` and `window_end()` templates, so a source
+//! filters by date like this:
+//!
+//! ```yaml
+//! query_params:
+//!   - key: updated_after
+//!     value: "{{ window_start() }}"   # first day of the window
+//!   - key: updated_before
+//!     value: "{{ window_end() }}"     # day after its last day
+//! ```
+//!
+//! Every window is appended. With `--backfill-mode replace` the destination
+//! is truncated before the first window instead of being added to.
+
+use chrono::{Duration, NaiveDate};
+
+use crate::errors::{ApitapError, Result};
+
+/// How the first window of a backfill treats existing rows.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum BackfillMode {
+    /// Keep existing rows (the default).
+    #[default]
+    Append,
+    /// Truncate the destination before the first window.
+    Replace,
+}
+
+impl std::str::FromStr for BackfillMode {
+    type Err = ApitapError;
+
+    fn from_str(s: &str) -> Result<Self> {
+        match s.trim().to_ascii_lowercase().as_str() {
+            "append" => Ok(BackfillMode::Append),
+            "replace" => Ok(BackfillMode::Replace),
+            other => Err(ApitapError::ConfigError(format!(
+                "unknown backfill mode '{other}' (expected append or replace)"
+            ))),
+        }
+    }
+}
+
+/// One window of a backfill: `start` inclusive, `end` exclusive.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Window {
+    pub start: NaiveDate,
+    pub end: NaiveDate,
+}
+
+/// The `--backfill` options.
+///
+/// # Example
+///
+/// ```
+/// use apitap::pipeline::backfill::{Backfill, BackfillMode};
+/// use chrono::NaiveDate;
+///
+/// let day = |d| NaiveDate::from_ymd_opt(2024, 1, d).unwrap();
+/// let backfill = Backfill {
+///     from: day(1),
+///     to: day(10),
+///     window_days: 4,
+///     mode: BackfillMode::Append,
+/// };
+/// let windows = backfill.windows().unwrap();
+/// assert_eq!(windows.len(), 3);
+/// assert_eq!((windows[0].start, windows[0].end), (day(1), day(5)));
+/// // The last window stops after `to`
+/// assert_eq!((windows[2].start, windows[2].end), (day(9), day(11)));
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Backfill {
+    /// First day to fetch.
+    pub from: NaiveDate,
+    /// Last day to fetch, inclusive.
+    pub to: NaiveDate,
+    /// Days per window.
+    pub window_days: u32,
+    pub mode: BackfillMode,
+}
+
+impl Backfill {
+    /// The windows from `from` through `to`, in order.
+    pub fn windows(&self) -> Result<Vec<Window>> {
+        if self.to < self.from {
+            return Err(ApitapError::ConfigError(format!(
+                "backfill --to {} is before --from {}",
+                self.to, self.from
+            )));
+        }
+        if self.window_days == 0 {
+            return Err(ApitapError::ConfigError(
+                "backfill --window must be at least one day".into(),
+            ));
+        }
+        let step = Duration::days(self.window_days.into());
+        let stop = self.to + Duration::days(1);
+        let mut windows = Vec::new();
+        let mut start = self.from;
+        while start < stop {
+            let end = (start + step).min(stop);
+            windows.push(Window { start, end });
+            start = end;
+        }
+        Ok(windows)
+    }
+}
+
+/// Parses a window length: `1d`, `7d`, `2w`, or a bare number of days.
+///
+/// # Example
+///
+/// ```
+/// use apitap::pipeline::backfill::parse_window;
+///
+/// assert_eq!(parse_window("1d").unwrap(), 1);
+/// assert_eq!(parse_window("2w").unwrap(), 14);
+/// assert_eq!(parse_window("30").unwrap(), 30);
+/// assert!(parse_window("0d").is_err());
+/// assert!(parse_window("1h").is_err());
+/// ```
+pub fn parse_window(s: &str) -> Result<u32> {
+    let s = s.trim();
+    let (number, unit) = match s.char_indices().last() {
+        Some((i, c)) if c.is_ascii_alphabetic() => (&s[..i], c.to_ascii_lowercase()),
+        _ => (s, 'd'),
+    };
+    let invalid = || {
+        ApitapError::ConfigError(format!(
+            "invalid backfill window '{s}' (expected e.g. 1d, 7d, or 2w)"
+        ))
+    };
+    let n: u32 = number.parse().map_err(|_| invalid())?;
+    let days = match unit {
+        'd' => Some(n),
+        'w' => n.checked_mul(7),
+        _ => None,
+    };
+    days.filter(|&d| d > 0).ok_or_else(invalid)
+}
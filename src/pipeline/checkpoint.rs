@@ -0,0 +1,294 @@
+//! Resumable pagination for long, flaky fetches.
+//!
+//! A source with `resume` commits the destination after every page and
+//! records the next page to fetch in a small JSON file per source and module
+//! under the state directory. If a run fails, the next run within the resume window
+//! starts from that page instead of page 1; a run that finishes clears the
+//! file. Start `apitap-run` with `--full-restart` to drop all checkpoints.
+//!
+//! ```yaml
+//! state_dir: /var/lib/apitap      # default: .apitap/state
+//! sources:
+//!   - name: events
+//!     pagination: { kind: page_number, page_param: page, per_page_param: per_page }
+//!     resume:
+//!       window_secs: 21600        # default: 86400
+//! ```
+
+use std::collections::{BTreeMap, BTreeSet};
+use std::path::{Path, PathBuf};
+use std::pin::Pin;
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::sync::Arc;
+use std::time::Duration;
+
+use async_trait::async_trait;
+use chrono::{DateTime, Utc};
+use futures::Stream;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use tracing::debug;
+
+use crate::errors::Result;
+use crate::http::fetcher::PageWriter;
+use crate::writer::{DataWriter, WriteMode};
+
+/// Directory for checkpoint files when the config sets no `state_dir`.
+pub const DEFAULT_STATE_DIR: &str = ".apitap/state";
+
+/// A source's `resume` block.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ResumeConfig {
+    /// Checkpoints older than this are ignored and the fetch starts over.
+    #[serde(default = "default_window_secs")]
+    pub window_secs: u64,
+}
+
+fn default_window_secs() -> u64 {
+    24 * 60 * 60
+}
+
+/// Progress of one request URL.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Checkpoint {
+    /// First page not yet written.
+    pub next_page: u64,
+    /// Every page was written; a resumed run skips this URL.
+    #[serde(default)]
+    pub complete: bool,
+    pub saved_at: DateTime<Utc>,
+}
+
+/// Checkpoint files under a state directory: one per source and module,
+/// keyed by URL.
+#[derive(Debug, Clone)]
+pub struct CheckpointStore {
+    dir: PathBuf,
+}
+
+impl CheckpointStore {
+    pub fn new(dir: impl Into<PathBuf>) -> Self {
+        Self { dir: dir.into() }
+    }
+
+    fn path(&self, key: &str) -> PathBuf {
+        let name: String = key
+            .chars()
+            .map(|c| {
+                if c.is_ascii_alphanumeric() || c == '-' || c == '_' {
+                    c
+                } else {
+                    '_'
+                }
+            })
+            .collect();
+        self.dir.join(format!("{name}.json"))
+    }
+
+    fn read(&self, key: &str) -> Result<BTreeMap<String, Checkpoint>> {
+        match std::fs::read_to_string(self.path(key)) {
+            Ok(text) => Ok(serde_json::from_str(&text)?),
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(BTreeMap::new()),
+            Err(e) => Err(e.into()),
+        }
+    }
+
+    /// The checkpoint for `url`, if one was saved within `window`.
+    pub fn load(&self, key: &str, url: &str, window: Duration) -> Result<Option<Checkpoint>> {
+        let Some(checkpoint) = self.read(key)?.remove(url) else {
+            return Ok(None);
+        };
+        let age = Utc::now()
+            .signed_duration_since(checkpoint.saved_at)
+            .to_std()
+            .unwrap_or_default();
+        Ok((age <= window).then_some(checkpoint))
+    }
+
+    /// Records progress for `url`.
+    pub fn save(&self, key: &str, url: &str, next_page: u64, complete: bool) -> Result<()> {
+        let mut all = self.read(key)?;
+        all.insert(
+            url.to_string(),
+            Checkpoint {
+                next_page,
+                complete,
+                saved_at: Utc::now(),
+            },
+        );
+        std::fs::create_dir_all(&self.dir)?;
+        // Write then rename so a crash never leaves a half-written file
+        let path = self.path(key);
+        let tmp = path.with_extension("json.tmp");
+        std::fs::write(&tmp, serde_json::to_vec_pretty(&all)?)?;
+        std::fs::rename(tmp, path)?;
+        Ok(())
+    }
+
+    /// Removes every checkpoint under `key`.
+    pub fn clear(&self, key: &str) -> Result<()> {
+        remove_if_exists(&self.path(key))
+    }
+
+    /// Removes every checkpoint in the state directory.
+    pub fn clear_all(&self) -> Result<()> {
+        let entries = match std::fs::read_dir(&self.dir) {
+            Ok(entries) => entries,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(()),
+            Err(e) => return Err(e.into()),
+        };
+        for entry in entries {
+            let path = entry?.path();
+            if path.extension().is_some_and(|ext| ext == "json") {
+                remove_if_exists(&path)?;
+            }
+        }
+        Ok(())
+    }
+}
+
+fn remove_if_exists(path: &Path) -> Result<()> {
+    match std::fs::remove_file(path) {
+        Err(e) if e.kind() != std::io::ErrorKind::NotFound => Err(e.into()),
+        _ => Ok(()),
+    }
+}
+
+/// Where and how long a request's progress is kept.
+#[derive(Debug, Clone)]
+pub struct Resume {
+    pub store: CheckpointStore,
+    /// File the checkpoints go in; one per source and module.
+    pub key: String,
+    pub window: Duration,
+}
+
+/// Page writer that commits the destination after every page and records
+/// the next page in the checkpoint store.
+///
+/// A page counts once [`PageWriter::page_done`] reports it, however many
+/// writes it took. The checkpoint only moves past pages that are all done, so
+/// a page that fails or is still being written holds it back. Commits happen
+/// between writes, so pages must be written one at a time (the fetcher's
+/// `preserve_order`).
+pub struct CheckpointingPageWriter {
+    inner: Arc<dyn PageWriter>,
+    writer: Arc<dyn DataWriter>,
+    resume: Resume,
+    url: String,
+    next_page: AtomicU64,
+    /// Pages done past `next_page`, waiting for the ones before them.
+    done: tokio::sync::Mutex<BTreeSet<u64>>,
+    page_failed: AtomicBool,
+}
+
+impl CheckpointingPageWriter {
+    pub fn new(
+        inner: Arc<dyn PageWriter>,
+        writer: Arc<dyn DataWriter>,
+        resume: Resume,
+        url: impl Into<String>,
+        start_page: u64,
+    ) -> Self {
+        Self {
+            inner,
+            writer,
+            resume,
+            url: url.into(),
+            next_page: AtomicU64::new(start_page),
+            done: tokio::sync::Mutex::new(BTreeSet::new()),
+            page_failed: AtomicBool::new(false),
+        }
+    }
+
+    /// First page not yet written.
+    pub fn next_page(&self) -> u64 {
+        self.next_page.load(Ordering::SeqCst)
+    }
+
+    /// True if a page failed to fetch, which ends page-number pagination early.
+    pub fn page_failed(&self) -> bool {
+        self.page_failed.load(Ordering::SeqCst)
+    }
+
+    /// Makes the page durable, then records `next_page`.
+    async fn checkpoint(&self, next_page: u64) -> Result<()> {
+        self.writer.commit().await?;
+        self.writer.begin().await?;
+        self.next_page.store(next_page, Ordering::SeqCst);
+        self.resume
+            .store
+            .save(&self.resume.key, &self.url, next_page, false)?;
+        debug!(key = %self.resume.key, next_page, "pagination checkpoint saved");
+        Ok(())
+    }
+
+    /// Marks the URL as fully fetched.
+    pub fn finish(&self) -> Result<()> {
+        self.resume.store.save(
+            &self.resume.key,
+            &self.url,
+            self.next_page.load(Ordering::SeqCst),
+            true,
+        )
+    }
+}
+
+#[async_trait]
+impl PageWriter for CheckpointingPageWriter {
+    async fn write_page(
+        &self,
+        page_number: u64,
+        data: Vec<Value>,
+        write_mode: WriteMode,
+    ) -> Result<()> {
+        self.inner.write_page(page_number, data, write_mode).await
+    }
+
+    async fn write_page_stream(
+        &self,
+        stream_data: Pin<Box<dyn Stream<Item = Result<Value>> + Send>>,
+        write_mode: WriteMode,
+    ) -> Result<()> {
+        self.inner.write_page_stream(stream_data, write_mode).await
+    }
+
+    async fn write_numbered_page_stream(
+        &self,
+        page_number: u64,
+        stream_data: Pin<Box<dyn Stream<Item = Result<Value>> + Send>>,
+        write_mode: WriteMode,
+    ) -> Result<()> {
+        self.inner
+            .write_numbered_page_stream(page_number, stream_data, write_mode)
+            .await
+    }
+
+    async fn on_page_error(&self, page_number: u64, error: String) -> Result<()> {
+        self.page_failed.store(true, Ordering::SeqCst);
+        self.inner.on_page_error(page_number, error).await
+    }
+
+    async fn page_done(&self, page_number: u64) -> Result<()> {
+        self.inner.page_done(page_number).await?;
+        // Held through the checkpoint so checkpoints are saved in page order
+        let mut done = self.done.lock().await;
+        done.insert(page_number);
+        let mut next = self.next_page.load(Ordering::SeqCst);
+        while done.remove(&next) {
+            next += 1;
+        }
+        if next > self.next_page.load(Ordering::SeqCst) {
+            self.checkpoint(next).await?;
+        }
+        Ok(())
+    }
+
+    async fn begin(&self) -> Result<()> {
+        self.inner.begin().await
+    }
+
+    async fn commit(&self) -> Result<()> {
+        self.inner.commit().await
+    }
+}
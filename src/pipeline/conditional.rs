@@ -0,0 +1,212 @@
+//! Conditional fetches with `ETag` / `Last-Modified` validators.
+//!
+//! A source with `conditional: true` saves the validators of the first
+//! response of each run under the state directory. The next run sends them
+//! back as `If-None-Match` / `If-Modified-Since`; a `304 Not Modified`
+//! answer ends the fetch with no rows, and the destination is left untouched
+//! (an `auto_truncate` table is not emptied). Validators are only saved once
+//! the run commits, so a failed run re-fetches next time.
+//!
+//! ```yaml
+//! sources:
+//!   - name: catalog
+//!     url: https://api.example.com/catalog
+//!     conditional: true
+//! ```
+
+use std::collections::BTreeMap;
+use std::path::PathBuf;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Mutex;
+
+use chrono::{DateTime, Utc};
+use reqwest::header::{
+    HeaderMap, HeaderValue, ETAG, IF_MODIFIED_SINCE, IF_NONE_MATCH, LAST_MODIFIED,
+};
+use reqwest::StatusCode;
+use serde::{Deserialize, Serialize};
+
+use crate::errors::Result;
+
+/// Cache validators from a response.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Validators {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub etag: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub last_modified: Option<String>,
+}
+
+impl Validators {
+    /// Reads `ETag` and `Last-Modified` from response headers.
+    pub fn from_headers(headers: &HeaderMap) -> Self {
+        let get = |name| {
+            headers
+                .get(name)
+                .and_then(|v| v.to_str().ok())
+                .map(str::to_string)
+        };
+        Self {
+            etag: get(ETAG),
+            last_modified: get(LAST_MODIFIED),
+        }
+    }
+
+    /// True when the response carried neither validator.
+    pub fn is_empty(&self) -> bool {
+        self.etag.is_none() && self.last_modified.is_none()
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct SavedValidators {
+    #[serde(flatten)]
+    validators: Validators,
+    saved_at: DateTime<Utc>,
+}
+
+/// Validator files under a state directory: one per source and module,
+/// keyed by URL.
+#[derive(Debug, Clone)]
+pub struct ValidatorStore {
+    dir: PathBuf,
+}
+
+impl ValidatorStore {
+    pub fn new(dir: impl Into<PathBuf>) -> Self {
+        Self { dir: dir.into() }
+    }
+
+    fn path(&self, key: &str) -> PathBuf {
+        let name: String = key
+            .chars()
+            .map(|c| {
+                if c.is_ascii_alphanumeric() || c == '-' || c == '_' {
+                    c
+                } else {
+                    '_'
+                }
+            })
+            .collect();
+        self.dir.join(format!("{name}.validators.json"))
+    }
+
+    fn read(&self, key: &str) -> Result<BTreeMap<String, SavedValidators>> {
+        match std::fs::read_to_string(self.path(key)) {
+            Ok(text) => Ok(serde_json::from_str(&text)?),
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(BTreeMap::new()),
+            Err(e) => Err(e.into()),
+        }
+    }
+
+    /// Validators saved for `url` by the last committed run.
+    pub fn load(&self, key: &str, url: &str) -> Result<Option<Validators>> {
+        Ok(self.read(key)?.remove(url).map(|s| s.validators))
+    }
+
+    /// Saves the validators of `url` for the next run.
+    pub fn save(&self, key: &str, url: &str, validators: &Validators) -> Result<()> {
+        let mut all = self.read(key)?;
+        all.insert(
+            url.to_string(),
+            SavedValidators {
+                validators: validators.clone(),
+                saved_at: Utc::now(),
+            },
+        );
+        std::fs::create_dir_all(&self.dir)?;
+        // Write then rename so a crash never leaves a half-written file
+        let path = self.path(key);
+        let tmp = path.with_extension("json.tmp");
+        std::fs::write(&tmp, serde_json::to_vec_pretty(&all)?)?;
+        std::fs::rename(tmp, path)?;
+        Ok(())
+    }
+}
+
+/// Where a request's validators are kept.
+#[derive(Debug, Clone)]
+pub struct Conditional {
+    pub store: ValidatorStore,
+    /// File the validators go in; one per source and module.
+    pub key: String,
+}
+
+/// Validator state of one fetch: applies the previous validators to the
+/// first request and captures the new ones from its response.
+///
+/// # Example
+///
+/// ```
+/// use apitap::pipeline::conditional::{ConditionalRequest, Validators};
+///
+/// let conditional = ConditionalRequest::new(Some(Validators {
+///     etag: Some("\"v1\"".into()),
+///     last_modified: None,
+/// }));
+/// let headers = conditional.first_request_headers().unwrap();
+/// assert_eq!(headers["if-none-match"], "\"v1\"");
+/// // Later pages are sent unconditionally
+/// assert!(conditional.first_request_headers().is_none());
+/// ```
+#[derive(Debug, Default)]
+pub struct ConditionalRequest {
+    previous: Option<Validators>,
+    claimed: AtomicBool,
+    received: Mutex<Option<Validators>>,
+    not_modified: AtomicBool,
+}
+
+impl ConditionalRequest {
+    pub fn new(previous: Option<Validators>) -> Self {
+        Self {
+            previous,
+            ..Self::default()
+        }
+    }
+
+    /// Headers carrying the previous validators, for the fetch's first
+    /// request. Returns `None` for every later request.
+    pub fn first_request_headers(&self) -> Option<HeaderMap> {
+        if self.claimed.swap(true, Ordering::SeqCst) {
+            return None;
+        }
+        let mut headers = HeaderMap::new();
+        let previous = self.previous.clone().unwrap_or_default();
+        let values = [
+            (IF_NONE_MATCH, previous.etag),
+            (IF_MODIFIED_SINCE, previous.last_modified),
+        ];
+        for (name, value) in values {
+            if let Some(value) = value.and_then(|v| HeaderValue::from_str(&v).ok()) {
+                headers.insert(name, value);
+            }
+        }
+        Some(headers)
+    }
+
+    /// Records the first response: its validators, or that it was a 304.
+    pub fn record(&self, response: &reqwest::Response) {
+        if response.status() == StatusCode::NOT_MODIFIED {
+            self.not_modified.store(true, Ordering::SeqCst);
+            return;
+        }
+        let validators = Validators::from_headers(response.headers());
+        if !validators.is_empty() {
+            *self.received.lock().unwrap_or_else(|e| e.into_inner()) = Some(validators);
+        }
+    }
+
+    /// True if the server answered the first request with `304 Not Modified`.
+    pub fn not_modified(&self) -> bool {
+        self.not_modified.load(Ordering::SeqCst)
+    }
+
+    /// Validators to save once the run commits.
+    pub fn received(&self) -> Option<Validators> {
+        self.received
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .clone()
+    }
+}
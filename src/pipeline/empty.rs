@@ -0,0 +1,147 @@
+//! What a module does when its run produces no rows.
+//!
+//! With the default `on_empty("proceed")` a sink's truncate hook runs before
+//! the fetch, so an empty API response leaves an `auto_truncate` table empty.
+//! `skip` and `fail` wrap each sink in an [`EmptyGuardWriter`] instead, which
+//! runs the truncate hook only once the first row reaches the sink. An empty
+//! run then leaves the destination untouched; `fail` also fails the run.
+//!
+//! ```sql
+//! {{ sink(name="warehouse", auto_truncate=true) }}
+//! {{ on_empty("skip") }}
+//! ```
+
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+
+use async_trait::async_trait;
+use futures::StreamExt;
+use tokio::sync::Mutex;
+
+use crate::errors::{ApitapError, Result};
+use crate::pipeline::sink::Hook;
+use crate::utils::datafusion_ext::{QueryError, QueryResult, QueryResultStream};
+use crate::writer::{DataWriter, WriteMode};
+
+/// A module's `on_empty(...)` policy.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum OnEmpty {
+    /// Leave the destination untouched and report success.
+    Skip,
+    /// Truncate up front and write whatever arrives (the default).
+    #[default]
+    Proceed,
+    /// Leave the destination untouched and fail the run.
+    Fail,
+}
+
+impl std::str::FromStr for OnEmpty {
+    type Err = ApitapError;
+
+    fn from_str(s: &str) -> Result<Self> {
+        match s.trim().to_ascii_lowercase().as_str() {
+            "skip" => Ok(OnEmpty::Skip),
+            "proceed" => Ok(OnEmpty::Proceed),
+            "fail" => Ok(OnEmpty::Fail),
+            other => Err(ApitapError::ConfigError(format!(
+                "unknown on_empty policy '{other}' (expected skip, proceed, or fail)"
+            ))),
+        }
+    }
+}
+
+/// Writer that defers the sink's truncate hook until the first row and
+/// never hands an empty result to the sink.
+pub struct EmptyGuardWriter {
+    inner: Arc<dyn DataWriter>,
+    truncate: Mutex<Option<Hook>>,
+    wrote: AtomicBool,
+}
+
+impl EmptyGuardWriter {
+    pub fn new(inner: Arc<dyn DataWriter>, truncate: Option<Hook>) -> Self {
+        Self {
+            inner,
+            truncate: Mutex::new(truncate),
+            wrote: AtomicBool::new(false),
+        }
+    }
+
+    /// True once a row has been handed to the sink.
+    pub fn wrote_rows(&self) -> bool {
+        self.wrote.load(Ordering::SeqCst)
+    }
+
+    /// Runs the truncate hook the first time rows arrive.
+    async fn on_rows(&self) -> Result<()> {
+        self.wrote.store(true, Ordering::SeqCst);
+        if let Some(truncate) = self.truncate.lock().await.take() {
+            truncate().await?;
+        }
+        Ok(())
+    }
+
+    /// Peeks at `result`; `None` if it has no rows.
+    async fn non_empty(&self, result: QueryResultStream) -> Result<Option<QueryResultStream>> {
+        let mut data = result.data.peekable();
+        match std::pin::Pin::new(&mut data).peek().await {
+            None => return Ok(None),
+            Some(Ok(_)) => self.on_rows().await?,
+            // Let the sink report the error without truncating first
+            Some(Err(_)) => {}
+        }
+        Ok(Some(QueryResultStream {
+            data: data.boxed(),
+            ..result
+        }))
+    }
+}
+
+#[async_trait]
+impl DataWriter for EmptyGuardWriter {
+    async fn write(&self, result: QueryResult) -> Result<()> {
+        if result.row_count == 0 {
+            return Ok(());
+        }
+        self.on_rows().await?;
+        self.inner.write(result).await
+    }
+
+    async fn write_stream(&self, result: QueryResultStream, write_mode: WriteMode) -> Result<()> {
+        match self.non_empty(result).await? {
+            Some(result) => self.inner.write_stream(result, write_mode).await,
+            None => Ok(()),
+        }
+    }
+
+    async fn merge(&self, result: QueryResultStream) -> Result<()> {
+        match self.non_empty(result).await? {
+            Some(result) => self.inner.merge(result).await,
+            None => Ok(()),
+        }
+    }
+
+    async fn on_error(&self, error: QueryError) -> Result<()> {
+        self.inner.on_error(error).await
+    }
+
+    async fn begin(&self) -> Result<()> {
+        self.inner.begin().await
+    }
+
+    async fn commit(&self) -> Result<()> {
+        self.inner.commit().await
+    }
+
+    async fn rollback(&self) -> Result<()> {
+        self.inner.rollback().await
+    }
+
+    async fn finish(&self) -> Result<()> {
+        // An empty run leaves the destination as it was
+        if !self.wrote_rows() {
+            return Ok(());
+        }
+        self.inner.finish().await
+    }
+}
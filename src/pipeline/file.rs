@@ -0,0 +1,242 @@
+//! `kind: file` sources: records read from local files instead of an API.
+//!
+//! Meant for trying out module SQL offline, seeding tables, and golden-file
+//! tests of transforms. `path` is a file or a glob (`*`, `?`, and `**` for
+//! any number of directories); relative paths resolve against the working
+//! directory. Matching files are read in name order and their records go
+//! through the same field mapping, transforms, and SQL as fetched ones.
+//! Pagination and retries do not apply.
+//!
+//! Files ending in `.ndjson` or `.jsonl`, or read with `format: ndjson_stream`,
+//! are read one record per line; others are parsed whole according to the
+//! source's `format`, with `data_path` and `records_as` applied as for a
+//! response body.
+//!
+//! ```yaml
+//! sources:
+//!   - name: orders_fixture
+//!     kind: file
+//!     path: ./fixtures/orders/*.json
+//!     data_path: /data
+//!     table_destination_name: orders
+//! ```
+
+use std::path::{Component, Path, PathBuf};
+use std::sync::atomic::{AtomicU64, AtomicUsize, Ordering};
+use std::sync::Arc;
+
+use futures::stream::{self, BoxStream};
+use futures::StreamExt;
+use serde_json::Value;
+use tracing::{debug, info};
+
+use crate::errors::{ApitapError, Result};
+use crate::http::fetcher::{FetchStats, PageWriter, RequestTemplate, ResponseFormat};
+use crate::writer::WriteMode;
+
+/// Files matching `pattern`, sorted by path.
+///
+/// A pattern without wildcards must name an existing file. A glob matching
+/// nothing is an error too, so a typo doesn't pass for an empty source.
+///
+/// # Example
+///
+/// ```
+/// use apitap::pipeline::file::expand_glob;
+///
+/// let dir = tempfile::tempdir().unwrap();
+/// std::fs::create_dir(dir.path().join("2024")).unwrap();
+/// std::fs::write(dir.path().join("2024/b.json"), "[]").unwrap();
+/// std::fs::write(dir.path().join("a.json"), "[]").unwrap();
+/// std::fs::write(dir.path().join("notes.txt"), "").unwrap();
+///
+/// let pattern = format!("{}/**/*.json", dir.path().display());
+/// let names: Vec<_> = expand_glob(&pattern)
+///     .unwrap()
+///     .iter()
+///     .map(|p| p.strip_prefix(dir.path()).unwrap().to_string_lossy().replace('\\', "/"))
+///     .collect();
+/// assert_eq!(names, ["2024/b.json", "a.json"]);
+/// ```
+pub fn expand_glob(pattern: &str) -> Result<Vec<PathBuf>> {
+    let is_wild = |s: &str| s.contains(['*', '?']);
+    if !is_wild(pattern) {
+        let path = PathBuf::from(pattern);
+        if !path.is_file() {
+            return Err(ApitapError::ConfigError(format!(
+                "file source: '{pattern}' is not a file"
+            )));
+        }
+        return Ok(vec![path]);
+    }
+
+    // Walk from the deepest directory without wildcards
+    let mut base = PathBuf::new();
+    let mut rest: Vec<String> = Vec::new();
+    for component in Path::new(pattern).components() {
+        let text = component.as_os_str().to_string_lossy();
+        if rest.is_empty() && !is_wild(&text) {
+            base.push(component);
+        } else if !matches!(component, Component::CurDir) {
+            rest.push(text.into_owned());
+        }
+    }
+    if base.as_os_str().is_empty() {
+        base = PathBuf::from(".");
+    }
+    let matcher = regex::Regex::new(&glob_regex(&rest))?;
+
+    let mut paths = Vec::new();
+    for entry in walkdir::WalkDir::new(&base) {
+        let entry = entry?;
+        if !entry.file_type().is_file() {
+            continue;
+        }
+        let relative = entry
+            .path()
+            .strip_prefix(&base)
+            .unwrap_or(entry.path())
+            .to_string_lossy()
+            .replace('\\', "/");
+        if matcher.is_match(&relative) {
+            paths.push(entry.into_path());
+        }
+    }
+    if paths.is_empty() {
+        return Err(ApitapError::ConfigError(format!(
+            "file source: no files match '{pattern}'"
+        )));
+    }
+    paths.sort();
+    Ok(paths)
+}
+
+/// Anchored regex for glob path components, matched against `/`-separated
+/// relative paths.
+fn glob_regex(components: &[String]) -> String {
+    let mut re = String::from("^");
+    for (i, component) in components.iter().enumerate() {
+        let last = i + 1 == components.len();
+        if component == "**" {
+            re.push_str(if last { ".*" } else { "(?:[^/]+/)*" });
+            continue;
+        }
+        for c in component.chars() {
+            match c {
+                '*' => re.push_str("[^/]*"),
+                '?' => re.push_str("[^/]"),
+                c => re.push_str(&regex::escape(&c.to_string())),
+            }
+        }
+        if !last {
+            re.push('/');
+        }
+    }
+    re.push('$');
+    re
+}
+
+/// True for files read one JSON record per line.
+fn is_ndjson(path: &Path) -> bool {
+    path.extension()
+        .and_then(|e| e.to_str())
+        .is_some_and(|e| e.eq_ignore_ascii_case("ndjson") || e.eq_ignore_ascii_case("jsonl"))
+}
+
+/// Records of one file, after `data_path` and `records_as`.
+fn file_records(
+    path: &Path,
+    body: &[u8],
+    data_path: Option<&str>,
+    template: &RequestTemplate,
+) -> Result<Vec<Value>> {
+    let pick = |v: Value| match data_path {
+        Some(p) => v.pointer(p).cloned().unwrap_or(Value::Null),
+        None => v,
+    };
+    if !is_ndjson(path) && template.format != ResponseFormat::NdjsonStream {
+        let v = template.parse_body(body)?;
+        template.tee_splits(&v);
+        return Ok(template.records(pick(v)));
+    }
+    let mut records = Vec::new();
+    for line in String::from_utf8_lossy(body).lines() {
+        let line = line.trim();
+        if line.is_empty() {
+            continue;
+        }
+        // A line without `data_path` is taken whole, as for NDJSON responses
+        let v = template.parse_json(line.as_bytes())?;
+        template.tee_splits(&v);
+        let target = match data_path.and_then(|p| v.pointer(p)) {
+            Some(inner) => inner.clone(),
+            None => v,
+        };
+        records.extend(template.records(target));
+    }
+    Ok(records)
+}
+
+/// Stream of the records in `paths`, in order. Each file is read when the
+/// stream reaches it; `bytes` counts what was read.
+pub fn records_stream(
+    paths: Vec<PathBuf>,
+    data_path: Option<String>,
+    template: RequestTemplate,
+    bytes: Arc<AtomicU64>,
+) -> BoxStream<'static, Result<Value>> {
+    stream::iter(paths)
+        .then(move |path| {
+            let data_path = data_path.clone();
+            let template = template.clone();
+            let bytes = Arc::clone(&bytes);
+            async move {
+                debug!(path = %path.display(), "reading file source");
+                let body = tokio::fs::read(&path).await.map_err(|e| {
+                    ApitapError::PipelineError(format!("reading '{}': {e}", path.display()))
+                })?;
+                bytes.fetch_add(body.len() as u64, Ordering::Relaxed);
+                file_records(&path, &body, data_path.as_deref(), &template)
+            }
+        })
+        .flat_map(|records| match records {
+            Ok(records) => stream::iter(records.into_iter().map(Ok)).boxed(),
+            Err(e) => stream::once(async { Err(e) }).boxed(),
+        })
+        .boxed()
+}
+
+/// Reads every file matching `pattern` and hands the records to `writer` as
+/// one stream. Each file counts as a page in the returned stats.
+pub async fn fetch_files(
+    pattern: &str,
+    data_path: Option<String>,
+    template: RequestTemplate,
+    writer: Arc<dyn PageWriter>,
+    write_mode: WriteMode,
+) -> Result<FetchStats> {
+    let paths = expand_glob(pattern)?;
+    info!(files = paths.len(), %pattern, "reading file source");
+
+    let files = paths.len();
+    let bytes = Arc::new(AtomicU64::new(0));
+    let items = Arc::new(AtomicUsize::new(0));
+    let counted = Arc::clone(&items);
+    let records = records_stream(paths, data_path, template.clone(), Arc::clone(&bytes));
+    let records = template
+        .capped(records)
+        .inspect(move |r| {
+            if r.is_ok() {
+                counted.fetch_add(1, Ordering::Relaxed);
+            }
+        })
+        .boxed();
+    writer.write_page_stream(records, write_mode).await?;
+
+    let mut stats = FetchStats::new();
+    stats.total_items = items.load(Ordering::Relaxed);
+    stats.total_bytes = bytes.load(Ordering::Relaxed);
+    stats.page_count = files;
+    stats.success_count = files;
+    Ok(stats)
+}
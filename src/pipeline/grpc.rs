@@ -0,0 +1,419 @@
+//! `kind: grpc` sources: records read from a gRPC method instead of a REST API.
+//!
+//! The method is described by a `FileDescriptorSet` (`descriptor_set`) or,
+//! when none is given, by the server's reflection service, so no generated
+//! code is needed. Requests are built from the JSON in `request`, and every
+//! response message is turned into JSON (proto field names, 64-bit integers
+//! as numbers, default values included) before `data_path` and the usual
+//! record steps apply.
+//!
+//! Server-streaming methods are read until the stream ends; unary methods
+//! return one message. With `page_token_field` and `next_page_token_field`
+//! the call is repeated, passing the token from the last message of each
+//! response, until the token comes back empty:
+//!
+//! ```yaml
+//! sources:
+//!   - name: users
+//!     kind: grpc
+//!     url: http://users.internal:50051
+//!     data_path: /users
+//!     headers:
+//!       - key: authorization
+//!         value: Bearer ${USERS_TOKEN}
+//!     grpc:
+//!       method: acme.users.v1.UserService/ListUsers
+//!       descriptor_set: ./protos/users.binpb
+//!       request: { page_size: 500 }
+//!       page_token_field: page_token
+//!       next_page_token_field: next_page_token
+//! ```
+//!
+//! Needs the `grpc` cargo feature. `retry`, `pagination`, and `auth` do not
+//! apply; credentials go in `headers`, which are sent as metadata.
+
+use std::sync::Arc;
+
+use url::Url;
+
+use crate::errors::Result;
+use crate::http::fetcher::{FetchStats, PageWriter, RequestTemplate};
+use crate::pipeline::GrpcConfig;
+use crate::writer::WriteMode;
+
+/// A `kind: grpc` source ready to call: its settings and the metadata sent
+/// with every call.
+#[derive(Debug, Clone)]
+pub struct GrpcRequest {
+    pub config: GrpcConfig,
+    pub metadata: Vec<(String, String)>,
+}
+
+/// Splits `package.Service/Method` into the service and method names.
+///
+/// # Example
+///
+/// ```
+/// use apitap::pipeline::grpc::split_method;
+///
+/// assert_eq!(
+///     split_method("acme.users.v1.UserService/ListUsers").unwrap(),
+///     ("acme.users.v1.UserService", "ListUsers")
+/// );
+/// assert!(split_method("ListUsers").is_err());
+/// ```
+pub fn split_method(method: &str) -> Result<(&str, &str)> {
+    method
+        .trim_start_matches('/')
+        .split_once('/')
+        .filter(|(service, name)| !service.is_empty() && !name.is_empty())
+        .ok_or_else(|| {
+            crate::errors::ApitapError::ConfigError(format!(
+                "grpc method '{method}' must be written as package.Service/Method"
+            ))
+        })
+}
+
+#[cfg(feature = "grpc")]
+pub use imp::{fetch_grpc, DynamicCodec};
+
+#[cfg(not(feature = "grpc"))]
+pub async fn fetch_grpc(
+    _url: &Url,
+    _request: &GrpcRequest,
+    _data_path: Option<String>,
+    _template: RequestTemplate,
+    _writer: Arc<dyn PageWriter>,
+    _write_mode: WriteMode,
+) -> Result<FetchStats> {
+    Err(crate::errors::ApitapError::ConfigError(
+        "kind grpc is not compiled into this build; rebuild with `--features grpc`".into(),
+    ))
+}
+
+#[cfg(feature = "grpc")]
+mod imp {
+    use std::collections::HashSet;
+    use std::sync::atomic::{AtomicU64, AtomicUsize, Ordering};
+
+    use futures::stream::{self, BoxStream};
+    use futures::StreamExt;
+    use prost_reflect::prost::Message;
+    use prost_reflect::prost_types::{FileDescriptorProto, FileDescriptorSet};
+    use prost_reflect::{
+        DescriptorPool, DynamicMessage, MessageDescriptor, MethodDescriptor, SerializeOptions,
+    };
+    use serde_json::Value;
+    use tonic::codec::{Codec, DecodeBuf, Decoder, EncodeBuf, Encoder};
+    use tonic::metadata::{AsciiMetadataKey, AsciiMetadataValue};
+    use tonic::transport::{Channel, ClientTlsConfig, Endpoint};
+    use tonic::Status;
+    use tonic_reflection::pb::v1::server_reflection_client::ServerReflectionClient;
+    use tonic_reflection::pb::v1::server_reflection_request::MessageRequest;
+    use tonic_reflection::pb::v1::server_reflection_response::MessageResponse;
+    use tonic_reflection::pb::v1::ServerReflectionRequest;
+    use tracing::{debug, info};
+
+    use super::*;
+    use crate::errors::ApitapError;
+    use crate::utils::template;
+
+    /// Proto field names and plain numbers, so columns match the `.proto`.
+    const JSON_OPTIONS: SerializeOptions = SerializeOptions::new()
+        .use_proto_field_name(true)
+        .stringify_64_bit_integers(false)
+        .skip_default_fields(false);
+
+    /// Encodes [`DynamicMessage`]s and decodes them with a fixed descriptor,
+    /// letting tonic call methods it has no generated code for.
+    #[derive(Debug, Clone)]
+    pub struct DynamicCodec {
+        decode: MessageDescriptor,
+    }
+
+    impl DynamicCodec {
+        /// A codec that decodes `decode` messages (the method's output on
+        /// the client side).
+        pub fn new(decode: MessageDescriptor) -> Self {
+            Self { decode }
+        }
+    }
+
+    impl Codec for DynamicCodec {
+        type Encode = DynamicMessage;
+        type Decode = DynamicMessage;
+        type Encoder = DynamicCodec;
+        type Decoder = DynamicCodec;
+
+        fn encoder(&mut self) -> Self::Encoder {
+            self.clone()
+        }
+
+        fn decoder(&mut self) -> Self::Decoder {
+            self.clone()
+        }
+    }
+
+    impl Encoder for DynamicCodec {
+        type Item = DynamicMessage;
+        type Error = Status;
+
+        fn encode(
+            &mut self,
+            item: DynamicMessage,
+            dst: &mut EncodeBuf<'_>,
+        ) -> std::result::Result<(), Status> {
+            item.encode(dst)
+                .map_err(|e| Status::internal(format!("encoding request: {e}")))
+        }
+    }
+
+    impl Decoder for DynamicCodec {
+        type Item = DynamicMessage;
+        type Error = Status;
+
+        fn decode(
+            &mut self,
+            src: &mut DecodeBuf<'_>,
+        ) -> std::result::Result<Option<DynamicMessage>, Status> {
+            DynamicMessage::decode(self.decode.clone(), src)
+                .map(Some)
+                .map_err(|e| Status::internal(format!("decoding response: {e}")))
+        }
+    }
+
+    /// Calls the method until the page token runs out and hands every record
+    /// to `writer` as one stream. Each call counts as a page in the stats.
+    pub async fn fetch_grpc(
+        url: &Url,
+        request: &GrpcRequest,
+        data_path: Option<String>,
+        template: RequestTemplate,
+        writer: Arc<dyn PageWriter>,
+        write_mode: WriteMode,
+    ) -> Result<FetchStats> {
+        let config = request.config.clone();
+        let (service, method_name) = split_method(&config.method)?;
+        let channel = connect(url).await?;
+        let pool = match &config.descriptor_set {
+            Some(path) => load_descriptor_set(path)?,
+            None => reflect_descriptors(channel.clone(), service).await?,
+        };
+        let method = find_method(&pool, service, method_name)?;
+        if method.is_client_streaming() {
+            return Err(ApitapError::ConfigError(format!(
+                "grpc method '{}' is client-streaming, which is not supported",
+                config.method
+            )));
+        }
+        let base = match &config.request {
+            Some(v) => template::substitute_json(v)?,
+            None => Value::Object(Default::default()),
+        };
+        if !base.is_object() {
+            return Err(ApitapError::ConfigError(format!(
+                "grpc method '{}': request must be a JSON object",
+                config.method
+            )));
+        }
+        info!(method = %config.method, "calling grpc source");
+
+        let pages = Arc::new(AtomicUsize::new(0));
+        let items = Arc::new(AtomicUsize::new(0));
+        let bytes = Arc::new(AtomicU64::new(0));
+        let (counted_pages, counted_items, counted_bytes) =
+            (Arc::clone(&pages), Arc::clone(&items), Arc::clone(&bytes));
+        let metadata = request.metadata.clone();
+        let budget = template.clone();
+
+        let records = async_stream::try_stream! {
+            let mut token: Option<String> = None;
+            loop {
+                if template.records_exhausted() {
+                    break;
+                }
+                let mut body = base.clone();
+                if let (Some(field), Some(token), Value::Object(fields)) =
+                    (&config.page_token_field, &token, &mut body)
+                {
+                    fields.insert(field.clone(), Value::String(token.clone()));
+                }
+                let message = DynamicMessage::deserialize(method.input(), body)?;
+                let mut responses = call(channel.clone(), &method, message, &metadata).await?;
+                counted_pages.fetch_add(1, Ordering::Relaxed);
+
+                let mut last = None;
+                while let Some(response) = responses.next().await {
+                    let response = response?;
+                    counted_bytes.fetch_add(response.encoded_len() as u64, Ordering::Relaxed);
+                    let value = response.serialize_with_options(serde_json::value::Serializer, &JSON_OPTIONS)?;
+                    let target = match data_path.as_deref() {
+                        Some(p) => value.pointer(p).cloned().unwrap_or(Value::Null),
+                        None => value.clone(),
+                    };
+                    for record in template.records(target) {
+                        yield record;
+                    }
+                    last = Some(value);
+                }
+
+                let next = next_token(&config, last.as_ref());
+                if next.is_some() && next == token {
+                    Err(ApitapError::PaginationError(format!(
+                        "grpc method '{}' returned the page token it was sent",
+                        config.method
+                    )))?;
+                }
+                debug!(method = %config.method, next = ?next, "grpc page done");
+                match next {
+                    Some(next) => token = Some(next),
+                    None => break,
+                }
+            }
+        };
+        // Counted after the record budget has cut the stream short
+        let records = budget
+            .capped(records.boxed())
+            .inspect(move |r| {
+                if r.is_ok() {
+                    counted_items.fetch_add(1, Ordering::Relaxed);
+                }
+            })
+            .boxed();
+        writer.write_page_stream(records, write_mode).await?;
+
+        let mut stats = FetchStats::new();
+        stats.total_items = items.load(Ordering::Relaxed);
+        stats.total_bytes = bytes.load(Ordering::Relaxed);
+        stats.page_count = pages.load(Ordering::Relaxed);
+        stats.request_count = stats.page_count;
+        stats.success_count = stats.page_count;
+        Ok(stats)
+    }
+
+    /// The token in `message` that asks for another page, if any.
+    fn next_token(config: &GrpcConfig, message: Option<&Value>) -> Option<String> {
+        let field = config.next_page_token_field.as_deref()?;
+        message?
+            .get(field)
+            .and_then(Value::as_str)
+            .filter(|token| !token.is_empty())
+            .map(str::to_string)
+    }
+
+    /// Opens a channel to the server at `url`, with TLS for `https`.
+    async fn connect(url: &Url) -> Result<Channel> {
+        let mut endpoint = Endpoint::from_shared(url.as_str().trim_end_matches('/').to_string())?;
+        if url.scheme() == "https" {
+            endpoint = endpoint.tls_config(ClientTlsConfig::new().with_webpki_roots())?;
+        }
+        Ok(endpoint.connect().await?)
+    }
+
+    fn load_descriptor_set(path: &str) -> Result<DescriptorPool> {
+        let bytes = std::fs::read(path).map_err(|e| {
+            ApitapError::ConfigError(format!("reading grpc descriptor_set '{path}': {e}"))
+        })?;
+        DescriptorPool::decode(bytes.as_slice())
+            .map_err(|e| ApitapError::ConfigError(format!("grpc descriptor_set '{path}': {e}")))
+    }
+
+    /// Asks the server's reflection service for the file defining `service`
+    /// and every file it imports.
+    async fn reflect_descriptors(channel: Channel, service: &str) -> Result<DescriptorPool> {
+        let mut client = ServerReflectionClient::new(channel);
+        let mut files = Vec::new();
+        let mut seen = HashSet::new();
+        let mut pending = vec![MessageRequest::FileContainingSymbol(service.to_string())];
+        while let Some(message) = pending.pop() {
+            let request = ServerReflectionRequest {
+                host: String::new(),
+                message_request: Some(message),
+            };
+            let mut responses = client
+                .server_reflection_info(stream::iter([request]))
+                .await?
+                .into_inner();
+            let response = responses.message().await?.and_then(|r| r.message_response);
+            let found = match response {
+                Some(MessageResponse::FileDescriptorResponse(found)) => found,
+                Some(MessageResponse::ErrorResponse(e)) => {
+                    return Err(ApitapError::PipelineError(format!(
+                        "grpc reflection for '{service}': {}",
+                        e.error_message
+                    )))
+                }
+                _ => {
+                    return Err(ApitapError::PipelineError(format!(
+                        "grpc reflection for '{service}' returned no descriptors"
+                    )))
+                }
+            };
+            for encoded in found.file_descriptor_proto {
+                let file = FileDescriptorProto::decode(encoded.as_slice()).map_err(|e| {
+                    ApitapError::PipelineError(format!("grpc reflection descriptor: {e}"))
+                })?;
+                if !seen.insert(file.name().to_string()) {
+                    continue;
+                }
+                pending.extend(
+                    file.dependency
+                        .iter()
+                        .filter(|dep| !seen.contains(*dep))
+                        .map(|dep| MessageRequest::FileByFilename(dep.clone())),
+                );
+                files.push(file);
+            }
+        }
+        DescriptorPool::from_file_descriptor_set(FileDescriptorSet { file: files }).map_err(|e| {
+            ApitapError::PipelineError(format!("grpc reflection for '{service}': {e}"))
+        })
+    }
+
+    fn find_method(pool: &DescriptorPool, service: &str, name: &str) -> Result<MethodDescriptor> {
+        let found = pool
+            .get_service_by_name(service)
+            .and_then(|s| s.methods().find(|m| m.name() == name));
+        found.ok_or_else(|| {
+            ApitapError::ConfigError(format!(
+                "grpc method '{service}/{name}' is not in the service descriptors"
+            ))
+        })
+    }
+
+    /// Sends one request, returning the response messages as a stream.
+    async fn call(
+        channel: Channel,
+        method: &MethodDescriptor,
+        message: DynamicMessage,
+        metadata: &[(String, String)],
+    ) -> Result<BoxStream<'static, Result<DynamicMessage>>> {
+        let mut request = tonic::Request::new(message);
+        for (key, value) in metadata {
+            let invalid = |e: &dyn std::fmt::Display| {
+                ApitapError::ConfigError(format!("grpc metadata '{key}': {e}"))
+            };
+            let key = AsciiMetadataKey::from_bytes(key.to_ascii_lowercase().as_bytes())
+                .map_err(|e| invalid(&e))?;
+            let value = AsciiMetadataValue::try_from(value.as_str()).map_err(|e| invalid(&e))?;
+            request.metadata_mut().insert(key, value);
+        }
+
+        let path = format!("/{}/{}", method.parent_service().full_name(), method.name());
+        let path = http::uri::PathAndQuery::try_from(path)
+            .map_err(|e| ApitapError::ConfigError(format!("grpc method path: {e}")))?;
+        let codec = DynamicCodec::new(method.output());
+        let mut grpc = tonic::client::Grpc::new(channel);
+        grpc.ready().await?;
+
+        if method.is_server_streaming() {
+            let responses = grpc.server_streaming(request, path, codec).await?;
+            return Ok(responses
+                .into_inner()
+                .map(|r| r.map_err(ApitapError::from))
+                .boxed());
+        }
+        let response = grpc.unary(request, path, codec).await?;
+        Ok(stream::once(async move { Ok(response.into_inner()) }).boxed())
+    }
+}
@@ -0,0 +1,107 @@
+//! Process-wide concurrency limits shared by every module.
+//!
+//! A source's `concurrency` bounds the page requests of one module. When
+//! dozens of modules share a schedule they still all start on the same tick;
+//! the top-level `limits` block (or the matching CLI flags, which win) caps
+//! the whole process:
+//!
+//! ```yaml
+//! limits:
+//!   max_concurrent_modules: 4    # runs at once; later ones wait for a slot
+//!   max_concurrent_requests: 16  # HTTP requests in flight across all modules
+//! ```
+//!
+//! A run or request that cannot get a permit waits for one instead of being
+//! skipped. Both limits are unset by default.
+
+use std::sync::Arc;
+
+use serde::{Deserialize, Serialize};
+use tokio::sync::{OwnedSemaphorePermit, Semaphore};
+use tracing::info;
+
+/// The `limits` config block.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct LimitsConfig {
+    /// Module runs allowed at once.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub max_concurrent_modules: Option<usize>,
+    /// HTTP requests in flight at once, over every module.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub max_concurrent_requests: Option<usize>,
+}
+
+impl LimitsConfig {
+    /// Each limit of `self`, or of `fallback` where `self` leaves it unset.
+    pub fn or(&self, fallback: &LimitsConfig) -> LimitsConfig {
+        LimitsConfig {
+            max_concurrent_modules: self
+                .max_concurrent_modules
+                .or(fallback.max_concurrent_modules),
+            max_concurrent_requests: self
+                .max_concurrent_requests
+                .or(fallback.max_concurrent_requests),
+        }
+    }
+}
+
+/// Permits for [`LimitsConfig`]. Cloning is cheap; clones share permits.
+///
+/// # Example
+///
+/// ```
+/// use apitap::pipeline::limits::{Limits, LimitsConfig};
+///
+/// # #[tokio::main(flavor = "current_thread")]
+/// # async fn main() {
+/// let limits = Limits::new(&LimitsConfig {
+///     max_concurrent_modules: Some(1),
+///     max_concurrent_requests: None,
+/// });
+/// let first = limits.acquire_module("orders.sql").await;
+/// assert!(first.is_some());
+/// assert!(limits.try_acquire_module().is_none());
+/// drop(first);
+/// assert!(limits.try_acquire_module().is_some());
+/// assert!(limits.requests().is_none());
+/// # }
+/// ```
+#[derive(Debug, Clone, Default)]
+pub struct Limits {
+    modules: Option<Arc<Semaphore>>,
+    requests: Option<Arc<Semaphore>>,
+}
+
+impl Limits {
+    pub fn new(config: &LimitsConfig) -> Self {
+        let semaphore = |n: Option<usize>| n.map(|n| Arc::new(Semaphore::new(n)));
+        Self {
+            modules: semaphore(config.max_concurrent_modules),
+            requests: semaphore(config.max_concurrent_requests),
+        }
+    }
+
+    /// Waits for a slot to run `module`. Returns `None` when modules are
+    /// unlimited.
+    ///
+    /// Hold the permit for the whole run.
+    pub async fn acquire_module(&self, module: &str) -> Option<OwnedSemaphorePermit> {
+        let modules = self.modules.clone()?;
+        if let Ok(permit) = Arc::clone(&modules).try_acquire_owned() {
+            return Some(permit);
+        }
+        info!("⏳ {module}: waiting for a free slot (max_concurrent_modules)");
+        modules.acquire_owned().await.ok()
+    }
+
+    /// A module slot if one is free right now.
+    pub fn try_acquire_module(&self) -> Option<OwnedSemaphorePermit> {
+        self.modules.clone()?.try_acquire_owned().ok()
+    }
+
+    /// The semaphore every HTTP request takes a permit from, if limited.
+    pub fn requests(&self) -> Option<Arc<Semaphore>> {
+        self.requests.clone()
+    }
+}
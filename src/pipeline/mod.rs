@@ -1,11 +1,34 @@
 use async_trait::async_trait;
 use serde::{de, Deserialize, Deserializer, Serialize};
+#[cfg(feature = "postgres")]
 use sqlx::PgPool;
-use std::collections::HashMap;
+use std::collections::{BTreeMap, HashMap};
+#[cfg(feature = "postgres")]
 use std::env;
 
+use crate::config::schedule::OverlapPolicy;
 use crate::errors::Result as CustomResult;
-use crate::http::fetcher::Pagination;
+use crate::http::fetcher::{
+    BodyEncoding, CsvOptions, HttpMethod, Pagination, PaginationIn, RecordsAs, ResponseFormat,
+};
+use crate::http::stop::StopWhen;
+use crate::pipeline::checkpoint::{CheckpointStore, ResumeConfig, DEFAULT_STATE_DIR};
+use crate::pipeline::conditional::ValidatorStore;
+use crate::utils::constant_columns::ConstantColumnsConfig;
+use crate::utils::dictionary::DictionaryEncoding;
+use crate::utils::duplicate_keys::DuplicateKeys;
+use crate::utils::execution::ExecutionOpts;
+use crate::utils::fields::FieldMapping;
+use crate::utils::flatten::FlattenConfig;
+use crate::utils::lenient_json::LenientJsonConfig;
+use crate::utils::metadata::MetadataColumns;
+use crate::utils::nulls::NullHandling;
+use crate::utils::numbers::NumberHandling;
+use crate::utils::redact::RedactConfig;
+use crate::utils::schema_contract::SchemaContractConfig;
+use crate::utils::table_provider::Lookup;
+use crate::writer::TruncateMode;
+use limits::LimitsConfig;
 
 // ================== Public types ==================
 
@@ -13,6 +36,24 @@ use crate::http::fetcher::Pagination;
 pub struct Config {
     pub sources: Vec<Source>,
     pub targets: Vec<Target>,
+    /// Static tables registered before each module's SQL runs.
+    #[serde(default)]
+    pub lookups: Vec<Lookup>,
+    /// Extra header and query parameter names to mask in logs.
+    #[serde(default)]
+    pub redact: RedactConfig,
+    /// Settings shared by every source.
+    #[serde(default)]
+    pub defaults: Defaults,
+    /// Directory for pagination checkpoints of sources with `resume`.
+    #[serde(default)]
+    pub state_dir: Option<String>,
+    /// How module SQL runs: batch size, buffering, and parallelism.
+    #[serde(default)]
+    pub execution: ExecutionOpts,
+    /// Concurrency caps shared by every module of the process.
+    #[serde(default)]
+    pub limits: LimitsConfig,
 
     // name -> index (built on deserialize)
     #[serde(skip)]
@@ -25,25 +66,443 @@ pub struct Config {
 #[serde(rename_all = "snake_case")]
 pub struct Retry {
     pub max_attempts: u32,
+    /// Ceiling on any single backoff delay. Also accepted as `max_interval_secs`.
+    #[serde(alias = "max_interval_secs")]
     pub max_delay_secs: u64,
     pub min_delay_secs: u64,
+    /// Randomization applied to each backoff delay.
+    #[serde(default)]
+    pub jitter: RetryJitter,
+    /// Stop retrying once this many seconds have passed since the first
+    /// attempt, even if `max_attempts` isn't reached. Also accepted as
+    /// `max_elapsed_time_secs`.
+    #[serde(default, alias = "max_elapsed_time_secs")]
+    pub max_elapsed_secs: Option<u64>,
+}
+
+/// Three attempts with 1-60s delays, for sources without a `retry` block.
+impl Default for Retry {
+    fn default() -> Self {
+        Self {
+            max_attempts: 3,
+            max_delay_secs: 60,
+            min_delay_secs: 1,
+            jitter: RetryJitter::default(),
+            max_elapsed_secs: None,
+        }
+    }
+}
+
+/// How retry delays are randomized so concurrent requests don't retry in lockstep.
+///
+/// With `d` the exponential delay for an attempt:
+/// - `full`: uniform in `[0, d]` (the default)
+/// - `equal`: `d/2` plus uniform in `[0, d/2]`
+/// - `none`: exactly `d`
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum RetryJitter {
+    None,
+    #[default]
+    Full,
+    Equal,
+}
+
+/// How a source is fetched.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum SourceKind {
+    /// Plain REST endpoint driven by `pagination`.
+    #[default]
+    Http,
+    /// GraphQL endpoint driven by the `graphql` block.
+    Graphql,
+    /// Local JSON/NDJSON files at `path`; see [`file`].
+    File,
+    /// gRPC method driven by the `grpc` block; see [`grpc`].
+    Grpc,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Source {
     pub name: String,
+    #[serde(default)]
+    pub kind: SourceKind,
+    /// Endpoint to fetch; required unless `kind: file`.
+    #[serde(default, skip_serializing_if = "String::is_empty")]
     pub url: String,
+    /// File or glob read by `kind: file` sources.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub path: Option<String>,
+    /// HTTP method for page requests (`GET` or `POST`).
+    #[serde(default)]
+    pub method: HttpMethod,
+    /// JSON body sent with each request; string values support `${ENV}` and `{{ fn() }}`.
+    #[serde(default)]
+    pub body: Option<serde_json::Value>,
+    /// `gzip` compresses the `body` and sends `Content-Encoding: gzip`.
+    #[serde(default)]
+    pub body_encoding: BodyEncoding,
+    /// Send pagination params in the `query` string (default), merge them into
+    /// the `body`, or (`template`) only where the body says `{{ page }}` etc.
+    #[serde(default)]
+    pub pagination_in: PaginationIn,
+    /// Dotted path in `body` where pagination params are merged (e.g. `page`); body root when unset.
+    #[serde(default)]
+    pub pagination_body_path: Option<String>,
+    /// Response body encoding (`json`, `xml`, `csv`, or `tsv`). XML is converted
+    /// to JSON first, so `data_path` is a pointer into the converted document
+    /// (e.g. `/feed/entry`); CSV/TSV bodies yield one record per row. Unset
+    /// reads JSON, or with `preflight` the format the content type names.
+    #[serde(default)]
+    pub format: Option<ResponseFormat>,
+    /// Delimiter and header settings for `csv`/`tsv` formats.
+    #[serde(default)]
+    pub csv: Option<CsvOptions>,
+    /// Table the module writes to, and the name its SQL reads the source
+    /// by. Defaults to the module file name (see [`Config::destination_table`]).
     #[serde(default)]
     pub table_destination_name: Option<String>,
     #[serde(default)]
     pub headers: Option<Vec<Header>>,
+    /// `Accept` header sent with every request, replacing one set in
+    /// `headers`. Unset, a configured `Accept` header is kept, or else the
+    /// media type of `format` is sent (`application/json` by default). With no
+    /// `format`, a JSON, XML, CSV or TSV type here also sets how responses are read.
+    #[serde(default)]
+    pub accept: Option<String>,
     #[serde(default)]
     pub query_params: Option<Vec<QueryParam>>,
     #[serde(default)]
     pub pagination: Option<Pagination>,
+    /// Ends pagination after a page that matches, e.g.
+    /// `{ path: has_more, equals: false }` or `{ short_page: true }`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub stop_when: Option<StopWhen>,
     pub data_path: Option<String>,
+    /// More record arrays of the same responses, each written to its own
+    /// table (see [`split`]).
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub splits: Vec<split::SourceSplit>,
+    /// `object_values` reads an object keyed by ID at `data_path` as one
+    /// record per value instead of a single record.
+    #[serde(default)]
+    pub records_as: RecordsAs,
+    /// With `records_as: object_values`, column that receives each record's key (e.g. `_id`).
+    #[serde(default)]
+    pub record_key_column: Option<String>,
+    /// Lineage columns added to every written row; replaces `defaults.metadata_columns`.
+    #[serde(default)]
+    pub metadata_columns: Option<MetadataColumns>,
+    /// Fixed text columns added to every written row, e.g. `tenant_id: acme`.
+    /// See [`crate::utils::constant_columns`].
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub constant_columns: Option<ConstantColumnsConfig>,
+    /// Expected columns and types, checked against every inferred schema.
+    /// See [`crate::utils::schema_contract`].
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub schema_contract: Option<SchemaContractConfig>,
+    /// Send the previous run's `ETag`/`Last-Modified` back and skip the run
+    /// on `304 Not Modified`. See [`conditional`].
+    #[serde(default)]
+    pub conditional: bool,
+    /// Send a `HEAD` request first and log the content type and size; when
+    /// `format` is unset the content type picks it. See [`crate::http::preflight`].
+    #[serde(default)]
+    pub preflight: bool,
+    /// Backoff for failed requests; unused by `kind: file` sources.
+    #[serde(default)]
     pub retry: Retry,
+    /// Skip TLS certificate and hostname verification, for staging endpoints
+    /// with self-signed certificates. Never enable this in production.
+    #[serde(default)]
+    pub danger_accept_invalid_certs: bool,
+    /// `pool_max_idle_per_host`, `pool_idle_timeout_secs`, and
+    /// `tcp_keepalive_secs` of the source's HTTP client.
+    #[serde(flatten)]
+    pub pool: crate::http::HttpPool,
     pub primary_key_in_dest: Option<String>,
+    /// Concurrent page requests for this source; overrides `--concurrency`.
+    #[serde(default)]
+    pub concurrency: Option<usize>,
+    /// Write concurrently fetched pages in page order (buffering up to
+    /// `concurrency` pages) rather than as they arrive.
+    #[serde(default)]
+    pub preserve_order: bool,
+    /// Page size for paginated requests; overrides `--page-size`. A
+    /// `page_size` on the `pagination` block wins over this one.
+    #[serde(default)]
+    pub page_size: Option<usize>,
+    /// Largest page size the API accepts. A larger configured page size is
+    /// clamped to it, with a warning.
+    #[serde(default)]
+    pub max_page_size: Option<usize>,
+    /// Records buffered per fetch batch; overrides `--fetch-batch-size`.
+    #[serde(default)]
+    pub fetch_batch_size: Option<usize>,
+    /// Commit the destination transaction every N rows instead of once per run.
+    ///
+    /// Rows from earlier commits stay written if a later batch fails.
+    #[serde(default)]
+    pub commit_every: Option<usize>,
+    /// Rows the writer buffers and writes (or merges) per batch; defaults to 50.
+    ///
+    /// Bounds the rows held in memory on the write side. Postgres merges a
+    /// batch wider than one statement through a temporary table.
+    #[serde(default)]
+    pub write_batch_size: Option<usize>,
+    /// Land each record as serialized JSON in a single `data` column, skipping
+    /// schema inference. Postgres targets store the column as `JSONB`.
+    #[serde(default)]
+    pub raw_json: bool,
+    /// Unwrap MongoDB extended JSON (`{"$oid": ...}`, `{"$date": ...}`, ...)
+    /// into plain values. See [`crate::utils::ejson`].
+    #[serde(default)]
+    pub ejson: bool,
+    /// Accept `NaN`/`Infinity` tokens and numbers too large for `f64` in
+    /// JSON responses. See [`crate::utils::lenient_json`].
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub lenient_json: Option<LenientJsonConfig>,
+    /// What happens to keys repeated within a JSON object. See
+    /// [`crate::utils::duplicate_keys`].
+    #[serde(default)]
+    pub duplicate_keys: DuplicateKeys,
+    /// `rename` and `drop` applied to each record before schema inference.
+    #[serde(flatten)]
+    pub fields: FieldMapping,
+    /// `large_integers` and `string_columns`: keeps integers beyond `i64` exact.
+    #[serde(flatten)]
+    pub numbers: NumberHandling,
+    /// `null_values` and `column_null_values`: strings such as `""` or `"N/A"`
+    /// read as null. See [`crate::utils::nulls`].
+    #[serde(flatten)]
+    pub nulls: NullHandling,
+    /// jq-like statements run on each record after `rename`/`drop`, e.g.
+    /// `.city = .address.city`. See [`crate::utils::transform`].
+    #[serde(default)]
+    pub transform: Vec<String>,
+    /// Flatten nested objects into columns such as `address_city`, after
+    /// every other record step. See [`crate::utils::flatten`].
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub flatten: Option<FlattenConfig>,
+    /// Low-cardinality string columns built as dictionary arrays for the
+    /// module SQL. See [`crate::utils::dictionary`].
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub dictionary: Option<DictionaryEncoding>,
+    /// Whether a tick that fires during a still-running run is skipped or queued.
+    #[serde(default)]
+    pub overlap: OverlapPolicy,
+    /// Level of the logs of modules reading this source; a module's
+    /// `{{ log_level(...) }}` wins.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub log_level: Option<crate::log::LogLevel>,
+    /// Fail (and roll back) a run that takes longer than this many seconds.
+    #[serde(default)]
+    pub timeout_secs: Option<u64>,
+    /// Postgres column types that replace the inferred ones, e.g. `amount: NUMERIC(12,2)`.
+    #[serde(default)]
+    pub column_types: BTreeMap<String, String>,
+    /// Required when `kind: graphql`.
+    #[serde(default)]
+    pub graphql: Option<GraphqlConfig>,
+    /// Required when `kind: grpc`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub grpc: Option<GrpcConfig>,
+    /// Signs every request, for APIs that require a per-request signature.
+    #[serde(default)]
+    pub signing: Option<SigningConfig>,
+    /// Credentials sent with every request.
+    #[serde(default)]
+    pub auth: Option<SourceAuth>,
+    /// Fans the source out into one request per value of a `{name}` URL path
+    /// segment; every request's records feed the same module run.
+    #[serde(default)]
+    pub path_params: Option<PathParams>,
+    /// Checkpoint page-number pagination so a failed run resumes where it stopped.
+    #[serde(default)]
+    pub resume: Option<ResumeConfig>,
+}
+
+/// Values substituted for a `{name}` segment in a source URL path.
+///
+/// `values` are used as listed; `query` runs against the DataFusion context
+/// (so it can read `lookups`) and contributes its first column. Both may be set.
+///
+/// ```yaml
+/// url: https://api.example.com/users/{user_id}/orders
+/// path_params:
+///   name: user_id
+///   values: [17, 42]
+///   query: SELECT id FROM active_users
+/// ```
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PathParams {
+    pub name: String,
+    #[serde(default)]
+    pub values: Vec<serde_json::Value>,
+    #[serde(default)]
+    pub query: Option<String>,
+}
+
+/// Credentials a source sends with every request.
+///
+/// ```yaml
+/// auth:
+///   type: query
+///   param: api_key
+///   value: ${API_KEY}
+/// ```
+///
+/// ```yaml
+/// auth:
+///   type: oauth2
+///   token_url: https://idp.example.com/oauth/token
+///   client_id: ${ETL_CLIENT_ID}
+///   client_secret: ${ETL_CLIENT_SECRET}
+///   scope: orders:read
+/// ```
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum SourceAuth {
+    /// An API key in the query string of every request, including each
+    /// page. `param` is masked in logs; `value` supports `${ENV}` and is
+    /// resolved again on every run, so rotated keys are picked up.
+    Query { param: String, value: String },
+    /// A bearer token from the OAuth2 client-credentials grant at
+    /// `token_url`. All fields support `${ENV}`. Tokens are shared by every
+    /// source with the same `token_url`, `client_id`, and `scope` until
+    /// shortly before they expire.
+    Oauth2 {
+        token_url: String,
+        client_id: String,
+        client_secret: String,
+        #[serde(default)]
+        scope: Option<String>,
+    },
+}
+
+/// Request signing scheme for a source.
+///
+/// ```yaml
+/// signing:
+///   kind: hmac
+///   secret: ${PARTNER_SIGNING_SECRET}
+///   header: X-Signature
+///   timestamp_header: X-Timestamp
+///   prefix: "sha256="
+/// ```
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum SigningConfig {
+    /// HMAC-SHA256 over method, path and query, timestamp, and body.
+    Hmac {
+        /// Shared secret; supports `${ENV}`.
+        secret: String,
+        #[serde(default = "default_signature_header")]
+        header: String,
+        #[serde(default)]
+        timestamp_header: Option<String>,
+        #[serde(default)]
+        prefix: String,
+    },
+}
+
+fn default_signature_header() -> String {
+    "X-Signature".to_string()
+}
+
+/// GraphQL query and cursor pagination settings for `kind: graphql` sources.
+///
+/// Paths accept either JSON pointers (`/data/repo/pageInfo/endCursor`) or dotted
+/// paths with `*` wildcards (`data.*.pageInfo.endCursor`).
+///
+/// ```yaml
+/// kind: graphql
+/// graphql:
+///   query: |
+///     query($org: String!, $after: String) {
+///       organization(login: $org) {
+///         repositories(first: 100, after: $after) {
+///           edges { node { id name } }
+///           pageInfo { endCursor hasNextPage }
+///         }
+///       }
+///     }
+///   variables:
+///     org: ${GITHUB_ORG}
+///   records_path: data.*.*.edges.node
+///   end_cursor_path: data.*.*.pageInfo.endCursor
+///   has_next_page_path: data.*.*.pageInfo.hasNextPage
+/// ```
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct GraphqlConfig {
+    pub query: String,
+    /// Variables sent with every request; string values support `${ENV}` and `{{ fn() }}`.
+    #[serde(default)]
+    pub variables: Option<serde_json::Value>,
+    #[serde(default = "default_graphql_records_path")]
+    pub records_path: String,
+    #[serde(default = "default_graphql_end_cursor_path")]
+    pub end_cursor_path: String,
+    #[serde(default = "default_graphql_has_next_page_path")]
+    pub has_next_page_path: String,
+    /// Variable that receives the cursor of the previous page.
+    #[serde(default = "default_graphql_cursor_variable")]
+    pub cursor_variable: String,
+}
+
+/// Method and paging settings for `kind: grpc` sources.
+///
+/// The source `url` is the server (`http://host:port`, or `https://` for
+/// TLS) and its `headers` are sent as request metadata. Every response
+/// message is a record, or with `data_path` the records inside it.
+///
+/// ```yaml
+/// kind: grpc
+/// url: http://users.internal:50051
+/// data_path: /users
+/// grpc:
+///   method: acme.users.v1.UserService/ListUsers
+///   descriptor_set: ./protos/users.binpb   # server reflection when unset
+///   request:
+///     page_size: 500
+///   page_token_field: page_token
+///   next_page_token_field: next_page_token
+/// ```
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct GrpcConfig {
+    /// `package.Service/Method`.
+    pub method: String,
+    /// `FileDescriptorSet` file describing the service, as written by
+    /// `protoc --include_imports --descriptor_set_out`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub descriptor_set: Option<String>,
+    /// Request message in its JSON form; string values support `${ENV}` and
+    /// `{{ fn() }}`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub request: Option<serde_json::Value>,
+    /// Request field that receives the previous response's next page token.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub page_token_field: Option<String>,
+    /// Response field holding the next page token; an empty token ends.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub next_page_token_field: Option<String>,
+}
+
+fn default_graphql_records_path() -> String {
+    "data.*.edges.node".to_string()
+}
+
+fn default_graphql_end_cursor_path() -> String {
+    "data.*.pageInfo.endCursor".to_string()
+}
+
+fn default_graphql_has_next_page_path() -> String {
+    "data.*.pageInfo.hasNextPage".to_string()
+}
+
+fn default_graphql_cursor_variable() -> String {
+    "after".to_string()
 }
 
 #[derive(Debug, Clone, Deserialize, Serialize)]
@@ -52,22 +511,255 @@ pub struct Header {
     pub value: String,
 }
 
+/// Top-level `defaults` block applied under every source's own settings.
+#[derive(Debug, Clone, Default, Deserialize, Serialize)]
+#[serde(default)]
+pub struct Defaults {
+    /// Sent with every request; a source header with the same name wins.
+    pub headers: Vec<Header>,
+    /// Lineage columns for sources that don't set their own `metadata_columns`.
+    pub metadata_columns: MetadataColumns,
+    /// Prepended to table names derived from module file names, e.g. `raw_`.
+    /// An explicit `table_destination_name` is used as is.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub table_prefix: Option<String>,
+}
+
 #[derive(Debug, Clone, Deserialize, Serialize)]
 pub struct QueryParam {
     pub key: String,
-    pub value: String,
+    /// A single value, or a list serialized according to `style`.
+    pub value: QueryValue,
+    #[serde(default)]
+    pub style: ArrayStyle,
+    /// Send the value as written, without `{{ ... }}` templates or `${ENV}`
+    /// substitution, for filter syntaxes that use `{`, `}` or `$`.
+    #[serde(default)]
+    pub raw: bool,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
-#[serde(tag = "type", rename_all = "snake_case")]
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(untagged)]
+pub enum QueryValue {
+    One(String),
+    Many(Vec<String>),
+}
+
+/// How a list-valued [`QueryParam`] is written into the query string.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ArrayStyle {
+    /// `?id=1&id=2`
+    #[default]
+    Repeat,
+    /// `?id[]=1&id[]=2`
+    Bracket,
+    /// `?id=1,2`
+    Comma,
+}
+
+impl QueryParam {
+    /// Expands the parameter into query pairs, passing each value through `render` first.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use apitap::pipeline::{ArrayStyle, QueryParam, QueryValue};
+    ///
+    /// let param = QueryParam {
+    ///     key: "id".to_string(),
+    ///     value: QueryValue::Many(vec!["1".to_string(), "2".to_string()]),
+    ///     style: ArrayStyle::Bracket,
+    ///     raw: false,
+    /// };
+    /// let pairs = param.to_pairs(|v| Ok(v.to_string())).unwrap();
+    /// assert_eq!(
+    ///     pairs,
+    ///     vec![("id[]".to_string(), "1".to_string()), ("id[]".to_string(), "2".to_string())]
+    /// );
+    /// ```
+    pub fn to_pairs<F>(&self, mut render: F) -> CustomResult<Vec<(String, String)>>
+    where
+        F: FnMut(&str) -> CustomResult<String>,
+    {
+        let values = match &self.value {
+            QueryValue::One(v) => return Ok(vec![(self.key.clone(), render(v)?)]),
+            QueryValue::Many(values) => values
+                .iter()
+                .map(|v| render(v))
+                .collect::<CustomResult<Vec<_>>>()?,
+        };
+
+        Ok(match self.style {
+            ArrayStyle::Repeat => values.into_iter().map(|v| (self.key.clone(), v)).collect(),
+            ArrayStyle::Bracket => {
+                let key = format!("{}[]", self.key);
+                values.into_iter().map(|v| (key.clone(), v)).collect()
+            }
+            ArrayStyle::Comma => vec![(self.key.clone(), values.join(","))],
+        })
+    }
+}
+
+/// A `targets` entry, picked by its `type`.
+///
+/// Types other than the built-in ones land in [`Target::Custom`] and are
+/// written by the [`WriterFactory`](crate::writer::factory::WriterFactory)
+/// registered for them.
+#[derive(Debug, Clone)]
 pub enum Target {
     Postgres(PostgresSink),
-    // If/when you add BigQuery, add a variant here and extend `create_conn`.
+    Snowflake(SnowflakeSink),
+    ObjectStore(ObjectStoreSink),
+    Custom(CustomSink),
+}
+
+/// The built-in target types, tagged by `type`.
+#[derive(Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+enum BuiltinTarget {
+    Postgres(PostgresSink),
+    Snowflake(SnowflakeSink),
+    ObjectStore(ObjectStoreSink),
+}
+
+#[derive(Serialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+enum BuiltinTargetRef<'a> {
+    Postgres(&'a PostgresSink),
+    Snowflake(&'a SnowflakeSink),
+    ObjectStore(&'a ObjectStoreSink),
+}
+
+impl<'de> Deserialize<'de> for Target {
+    fn deserialize<D>(deserializer: D) -> std::result::Result<Self, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        let value = serde_json::Value::deserialize(deserializer)?;
+        let kind = value
+            .get("type")
+            .and_then(serde_json::Value::as_str)
+            .ok_or_else(|| de::Error::missing_field("type"))?;
+        if !matches!(kind, "postgres" | "snowflake" | "object_store") {
+            return CustomSink::deserialize(value)
+                .map(Target::Custom)
+                .map_err(de::Error::custom);
+        }
+        Ok(
+            match BuiltinTarget::deserialize(value).map_err(de::Error::custom)? {
+                BuiltinTarget::Postgres(pg) => Target::Postgres(pg),
+                BuiltinTarget::Snowflake(sf) => Target::Snowflake(sf),
+                BuiltinTarget::ObjectStore(os) => Target::ObjectStore(os),
+            },
+        )
+    }
+}
+
+impl Serialize for Target {
+    fn serialize<S>(&self, serializer: S) -> std::result::Result<S::Ok, S::Error>
+    where
+        S: serde::Serializer,
+    {
+        match self {
+            Target::Postgres(pg) => BuiltinTargetRef::Postgres(pg).serialize(serializer),
+            Target::Snowflake(sf) => BuiltinTargetRef::Snowflake(sf).serialize(serializer),
+            Target::ObjectStore(os) => BuiltinTargetRef::ObjectStore(os).serialize(serializer),
+            Target::Custom(custom) => custom.serialize(serializer),
+        }
+    }
 }
 
 #[derive(Debug)]
 pub enum TargetConn {
-    Postgres { pool: PgPool, database: String },
+    #[cfg(feature = "postgres")]
+    Postgres {
+        pool: PgPool,
+        database: String,
+        /// Backoff for batches that hit a transient database error.
+        write_retry: Option<Retry>,
+        /// Receives rows that fail on their own data, shared by every module.
+        dead_letter: Option<std::sync::Arc<crate::writer::dead_letter::DeadLetterWriter>>,
+    },
+    #[cfg(feature = "snowflake")]
+    Snowflake {
+        client: std::sync::Arc<crate::writer::snowflake::SnowflakeClient>,
+    },
+    #[cfg(feature = "object_store")]
+    ObjectStore {
+        store: std::sync::Arc<dyn object_store::ObjectStore>,
+        prefix: object_store::path::Path,
+        partition: Option<String>,
+        parquet: ParquetOptions,
+    },
+    Custom {
+        sink: CustomSink,
+        factory: std::sync::Arc<dyn crate::writer::factory::WriterFactory>,
+    },
+}
+
+impl Target {
+    /// Name of the cargo feature that provides this target's backend
+    /// (`custom` for types written by a registered factory).
+    pub fn backend_feature(&self) -> &'static str {
+        match self {
+            Target::Postgres(_) => "postgres",
+            Target::Snowflake(_) => "snowflake",
+            Target::ObjectStore(_) => "object_store",
+            Target::Custom(_) => "custom",
+        }
+    }
+
+    /// The target's `auto_create`/`auto_truncate` settings.
+    pub fn tables(&self) -> TablePolicy {
+        match self {
+            Target::Postgres(pg) => pg.tables,
+            Target::Snowflake(sf) => sf.tables,
+            Target::ObjectStore(os) => os.tables,
+            Target::Custom(custom) => custom.tables,
+        }
+    }
+
+    /// Post checks of the target; only Postgres targets have them.
+    pub fn post_checks(&self) -> &[crate::writer::post_check::PostCheck] {
+        match self {
+            Target::Postgres(pg) => &pg.post_checks,
+            _ => &[],
+        }
+    }
+
+    /// Whether the backend for this target was compiled into this build, or
+    /// for a custom type, whether a factory is registered for it.
+    pub fn backend_enabled(&self) -> bool {
+        match self {
+            Target::Postgres(_) => cfg!(feature = "postgres"),
+            Target::Snowflake(_) => cfg!(feature = "snowflake"),
+            Target::ObjectStore(_) => cfg!(feature = "object_store"),
+            Target::Custom(custom) => {
+                crate::writer::factory::writer_factory(&custom.kind).is_some()
+            }
+        }
+    }
+
+    /// Fails with a clear error when the target references a backend whose
+    /// cargo feature was disabled at build time, or a custom type no factory
+    /// is registered for.
+    pub fn ensure_backend_enabled(&self) -> CustomResult<()> {
+        if self.backend_enabled() {
+            return Ok(());
+        }
+        if let Target::Custom(custom) = self {
+            return Err(crate::errors::ApitapError::UnsupportedSink(format!(
+                "target '{}' has unknown type '{}'; no writer factory is registered for it",
+                custom.name, custom.kind
+            )));
+        }
+        Err(crate::errors::ApitapError::UnsupportedSink(format!(
+            "target '{}' uses the '{feature}' backend, which is not compiled into this build; rebuild with `--features {feature}`",
+            self.name(),
+            feature = self.backend_feature()
+        )))
+    }
 }
 
 #[async_trait]
@@ -78,7 +770,67 @@ pub trait SinkConn {
 #[async_trait]
 impl SinkConn for Target {
     async fn create_conn(&self) -> CustomResult<TargetConn> {
+        self.ensure_backend_enabled()?;
         match self {
+            #[cfg(not(feature = "postgres"))]
+            Target::Postgres(_) => unreachable!("checked by ensure_backend_enabled"),
+            #[cfg(not(feature = "snowflake"))]
+            Target::Snowflake(_) => unreachable!("checked by ensure_backend_enabled"),
+            #[cfg(not(feature = "object_store"))]
+            Target::ObjectStore(_) => unreachable!("checked by ensure_backend_enabled"),
+            Target::Custom(custom) => {
+                let factory =
+                    crate::writer::factory::writer_factory(&custom.kind).ok_or_else(|| {
+                        crate::errors::ApitapError::UnsupportedSink(format!(
+                            "no writer factory is registered for target type '{}'",
+                            custom.kind
+                        ))
+                    })?;
+                Ok(TargetConn::Custom {
+                    sink: custom.clone(),
+                    factory,
+                })
+            }
+            #[cfg(feature = "object_store")]
+            Target::ObjectStore(os) => {
+                let url = crate::utils::template::substitute_env_vars(&os.url)?;
+                let (store, prefix) = crate::writer::object_store::store_from_url(&url)?;
+                Ok(TargetConn::ObjectStore {
+                    store,
+                    prefix,
+                    partition: os.partition.clone(),
+                    parquet: os.parquet.clone(),
+                })
+            }
+            #[cfg(feature = "snowflake")]
+            Target::Snowflake(sf) => {
+                use crate::utils::template::substitute_env_vars;
+                use crate::writer::snowflake::SnowflakeClient;
+
+                let token = substitute_env_vars(&sf.auth.token)?;
+                if token.trim().is_empty() {
+                    return Err(crate::errors::ApitapError::ConfigError(format!(
+                        "snowflake target '{}' has an empty token",
+                        sf.name
+                    )));
+                }
+                let mut client = SnowflakeClient::new(
+                    &substitute_env_vars(&sf.account)?,
+                    token,
+                    sf.auth.token_type.as_header(),
+                    substitute_env_vars(&sf.warehouse)?,
+                    substitute_env_vars(&sf.database)?,
+                    substitute_env_vars(&sf.schema)?,
+                )
+                .with_role(sf.role.as_deref().map(substitute_env_vars).transpose()?);
+                if let Some(endpoint) = &sf.endpoint {
+                    client = client.with_endpoint(substitute_env_vars(endpoint)?);
+                }
+                Ok(TargetConn::Snowflake {
+                    client: std::sync::Arc::new(client),
+                })
+            }
+            #[cfg(feature = "postgres")]
             Target::Postgres(pg) => {
                 // Resolve credentials: prefer env var references if provided, otherwise use inline values.
                 let username = if let Some(env_name) = &pg.auth.username_env {
@@ -96,7 +848,7 @@ impl SinkConn for Target {
                     }
                     val
                 } else if let Some(u) = &pg.auth.username {
-                    u.clone()
+                    crate::utils::template::substitute_env_vars(u)?
                 } else {
                     return Err(crate::errors::ApitapError::ConfigError(
                         "postgres username not provided".into(),
@@ -118,7 +870,7 @@ impl SinkConn for Target {
                     }
                     val
                 } else if let Some(p) = &pg.auth.password {
-                    p.clone()
+                    crate::utils::template::substitute_env_vars(p)?
                 } else {
                     return Err(crate::errors::ApitapError::ConfigError(
                         "postgres password not provided".into(),
@@ -133,10 +885,16 @@ impl SinkConn for Target {
                     port = pg.port,
                     db = pg.database
                 );
-                let pool = PgPool::connect(&url).await?;
+                let pool = pg.pool_options().connect(&url).await?;
                 Ok(TargetConn::Postgres {
-                    pool,
                     database: pg.database.clone(),
+                    write_retry: pg.write_retry.clone(),
+                    dead_letter: pg.dead_letter.as_ref().map(|dl| {
+                        std::sync::Arc::new(
+                            crate::writer::dead_letter::DeadLetterWriter::for_postgres(dl, &pool),
+                        )
+                    }),
+                    pool,
                 })
             }
         }
@@ -151,6 +909,60 @@ pub struct PostgresSink {
     pub port: u16,
     pub database: String,
     pub auth: PostgresAuth,
+    /// Upper bound on open connections (sqlx default: 10).
+    ///
+    /// Every module run opens its own pool, so the warehouse sees up to
+    /// `max_connections` × concurrently running modules. `concurrency` on the
+    /// source only limits HTTP requests; each module writes through a single
+    /// writer and rarely needs more than a couple of connections.
+    #[serde(default)]
+    pub max_connections: Option<u32>,
+    /// Connections kept open even when idle (sqlx default: 0).
+    #[serde(default)]
+    pub min_connections: Option<u32>,
+    /// Seconds to wait for a free connection before failing (sqlx default: 30).
+    #[serde(default)]
+    pub acquire_timeout_secs: Option<u64>,
+    /// Seconds an idle connection is kept before being closed (sqlx default: 600).
+    #[serde(default)]
+    pub idle_timeout_secs: Option<u64>,
+    /// Retries a batch that fails with a deadlock, serialization failure,
+    /// lock timeout, or dropped connection; constraint violations and other
+    /// errors fail at once. Same fields as the source `retry`.
+    #[serde(default)]
+    pub write_retry: Option<Retry>,
+    /// Where rows that fail on their own data (a rejected value, a
+    /// constraint violation) go, so the rest of their batch is still written.
+    #[serde(default)]
+    pub dead_letter: Option<crate::writer::dead_letter::DeadLetter>,
+    /// Assertion queries run on every table loaded here once its run
+    /// commits (see [`crate::writer::post_check`]).
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub post_checks: Vec<crate::writer::post_check::PostCheck>,
+    /// `auto_create` and `auto_truncate` for every module writing here.
+    #[serde(flatten)]
+    pub tables: TablePolicy,
+}
+
+#[cfg(feature = "postgres")]
+impl PostgresSink {
+    /// Pool options with the configured limits applied on top of sqlx defaults.
+    pub fn pool_options(&self) -> sqlx::postgres::PgPoolOptions {
+        let mut opts = sqlx::postgres::PgPoolOptions::new();
+        if let Some(n) = self.max_connections {
+            opts = opts.max_connections(n);
+        }
+        if let Some(n) = self.min_connections {
+            opts = opts.min_connections(n);
+        }
+        if let Some(secs) = self.acquire_timeout_secs {
+            opts = opts.acquire_timeout(std::time::Duration::from_secs(secs));
+        }
+        if let Some(secs) = self.idle_timeout_secs {
+            opts = opts.idle_timeout(std::time::Duration::from_secs(secs));
+        }
+        opts
+    }
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -168,6 +980,256 @@ pub struct PostgresAuth {
     pub password_env: Option<String>,
 }
 
+/// Snowflake target loaded through the SQL REST API.
+///
+/// String fields support `${ENV}` references, resolved when connecting.
+///
+/// ```yaml
+/// - type: snowflake
+///   name: sf_sink
+///   account: ${SNOWFLAKE_ACCOUNT}   # e.g. myorg-myaccount
+///   warehouse: LOAD_WH
+///   database: RAW
+///   schema: API
+///   role: LOADER
+///   auth:
+///     token: ${SNOWFLAKE_TOKEN}
+///     token_type: PROGRAMMATIC_ACCESS_TOKEN
+/// ```
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SnowflakeSink {
+    pub name: String,
+    pub account: String,
+    pub warehouse: String,
+    pub database: String,
+    pub schema: String,
+    #[serde(default)]
+    pub role: Option<String>,
+    /// Base URL override; defaults to `https://<account>.snowflakecomputing.com`.
+    #[serde(default)]
+    pub endpoint: Option<String>,
+    pub auth: SnowflakeAuth,
+    /// `auto_create` and `auto_truncate` for every module writing here.
+    #[serde(flatten)]
+    pub tables: TablePolicy,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SnowflakeAuth {
+    /// Bearer token, usually `${ENV}`.
+    pub token: String,
+    #[serde(default)]
+    pub token_type: SnowflakeTokenType,
+}
+
+/// Kind of token in [`SnowflakeAuth::token`].
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "SCREAMING_SNAKE_CASE")]
+pub enum SnowflakeTokenType {
+    Oauth,
+    KeypairJwt,
+    #[default]
+    ProgrammaticAccessToken,
+}
+
+impl SnowflakeTokenType {
+    /// Value for the `X-Snowflake-Authorization-Token-Type` header.
+    pub fn as_header(&self) -> &'static str {
+        match self {
+            SnowflakeTokenType::Oauth => "OAUTH",
+            SnowflakeTokenType::KeypairJwt => "KEYPAIR_JWT",
+            SnowflakeTokenType::ProgrammaticAccessToken => "PROGRAMMATIC_ACCESS_TOKEN",
+        }
+    }
+}
+
+/// Parquet files written to S3, GCS, Azure Blob, or a local directory.
+///
+/// Credentials come from the environment (`AWS_*`, `GOOGLE_*`, `AZURE_*`) or
+/// instance metadata; `url` supports `${ENV}` references.
+///
+/// ```yaml
+/// - type: object_store
+///   name: lake
+///   url: s3://my-bucket/raw/apitap
+///   partition: dt={{ current_date() }}
+///   compression: zstd        # default
+/// ```
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ObjectStoreSink {
+    pub name: String,
+    /// Bucket and key prefix, e.g. `s3://bucket/prefix` or `file:///data/lake`.
+    pub url: String,
+    /// Optional partition path under each table, rendered per run.
+    #[serde(default)]
+    pub partition: Option<String>,
+    /// Codec and layout of the Parquet files.
+    #[serde(flatten)]
+    pub parquet: ParquetOptions,
+    /// `auto_truncate` deletes the table's objects before each run;
+    /// `auto_create` has no effect.
+    #[serde(flatten)]
+    pub tables: TablePolicy,
+}
+
+/// A target whose `type` is not built in, written through the
+/// [`WriterFactory`](crate::writer::factory::WriterFactory) registered for
+/// that type.
+///
+/// Besides `type`, `name`, and the [`TablePolicy`] fields, every key is kept
+/// in `options` for the factory to read; `${ENV}` references are left as
+/// written.
+///
+/// ```yaml
+/// - type: clickhouse
+///   name: events_db
+///   url: http://clickhouse:8123
+///   database: raw
+/// ```
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct CustomSink {
+    /// The target's `type`, which picks the factory.
+    #[serde(rename = "type")]
+    pub kind: String,
+    pub name: String,
+    #[serde(flatten)]
+    pub tables: TablePolicy,
+    /// Every other key of the target.
+    #[serde(flatten)]
+    pub options: serde_json::Map<String, serde_json::Value>,
+}
+
+impl CustomSink {
+    /// Reads `options` into the factory's own settings type.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`crate::errors::ApitapError::ConfigError`] naming the target
+    /// when the options don't match `T`.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use apitap::pipeline::CustomSink;
+    /// use serde::Deserialize;
+    ///
+    /// #[derive(Deserialize)]
+    /// struct ClickHouse {
+    ///     url: String,
+    ///     #[serde(default)]
+    ///     database: Option<String>,
+    /// }
+    ///
+    /// let sink: CustomSink = serde_yaml::from_str(
+    ///     "{type: clickhouse, name: ch, url: 'http://ch:8123', auto_create: false}",
+    /// )
+    /// .unwrap();
+    /// let settings: ClickHouse = sink.options().unwrap();
+    /// assert_eq!(settings.url, "http://ch:8123");
+    /// assert_eq!(settings.database, None);
+    /// assert_eq!(sink.tables.auto_create, Some(false));
+    /// ```
+    pub fn options<T: serde::de::DeserializeOwned>(&self) -> CustomResult<T> {
+        serde_json::from_value(serde_json::Value::Object(self.options.clone())).map_err(|e| {
+            crate::errors::ApitapError::ConfigError(format!(
+                "{} target '{}': {e}",
+                self.kind, self.name
+            ))
+        })
+    }
+}
+
+/// Whether ApiTap may create and empty destination tables.
+///
+/// Set on a target, or per module with
+/// `{{ sink(name="...", auto_create=false) }}`; the module wins. Unset
+/// values fall back to creating missing tables and never truncating.
+///
+/// ```yaml
+/// - type: postgres
+///   name: warehouse
+///   auto_create: false   # tables are managed by migrations
+/// ```
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct TablePolicy {
+    /// Create a missing destination table from the inferred schema.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub auto_create: Option<bool>,
+    /// Truncate the destination before each run.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub auto_truncate: Option<bool>,
+    /// Statement `auto_truncate` empties the table with (Postgres only).
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub truncate_mode: Option<TruncateMode>,
+    /// Load into `<table>__staging` and swap it in for the table once the run
+    /// succeeds (Postgres only); excludes `auto_truncate`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub swap: Option<bool>,
+}
+
+impl TablePolicy {
+    /// Fills values unset here from `fallback`.
+    pub fn or(self, fallback: TablePolicy) -> TablePolicy {
+        TablePolicy {
+            auto_create: self.auto_create.or(fallback.auto_create),
+            auto_truncate: self.auto_truncate.or(fallback.auto_truncate),
+            truncate_mode: self.truncate_mode.or(fallback.truncate_mode),
+            swap: self.swap.or(fallback.swap),
+        }
+    }
+
+    /// Defaults to `true`.
+    pub fn auto_create(&self) -> bool {
+        self.auto_create.unwrap_or(true)
+    }
+
+    /// Defaults to `false`.
+    pub fn auto_truncate(&self) -> bool {
+        self.auto_truncate.unwrap_or(false)
+    }
+
+    /// Defaults to [`TruncateMode::Truncate`].
+    pub fn truncate_mode(&self) -> TruncateMode {
+        self.truncate_mode.unwrap_or_default()
+    }
+
+    /// Defaults to `false`.
+    pub fn swap(&self) -> bool {
+        self.swap.unwrap_or(false)
+    }
+}
+
+/// Parquet column codec.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ParquetCompression {
+    None,
+    Snappy,
+    Gzip,
+    #[default]
+    Zstd,
+    Lz4,
+}
+
+/// Parquet writer settings of an object store target.
+///
+/// Unset sizes keep the `parquet` crate defaults (1Mi rows per row group,
+/// 1 MiB data pages).
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct ParquetOptions {
+    #[serde(default)]
+    pub compression: ParquetCompression,
+    /// Codec level: 0-9 for `gzip`, 1-22 for `zstd`; other codecs take none.
+    #[serde(default)]
+    pub compression_level: Option<i32>,
+    /// Maximum rows per row group.
+    #[serde(default)]
+    pub row_group_size: Option<usize>,
+    /// Target size of a data page in bytes.
+    #[serde(default)]
+    pub data_page_size: Option<usize>,
+}
+
 // (These are kept if you plan to add BigQuery later; otherwise you can remove.)
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct BigQuerySink {
@@ -190,6 +1252,18 @@ fn default_pg_port() -> u16 {
 struct ConfigWire {
     sources: Vec<Source>,
     targets: Vec<Target>,
+    #[serde(default)]
+    lookups: Vec<Lookup>,
+    #[serde(default)]
+    redact: RedactConfig,
+    #[serde(default)]
+    defaults: Defaults,
+    #[serde(default)]
+    state_dir: Option<String>,
+    #[serde(default)]
+    execution: ExecutionOpts,
+    #[serde(default)]
+    limits: LimitsConfig,
 }
 
 impl<'de> Deserialize<'de> for Config {
@@ -198,6 +1272,12 @@ impl<'de> Deserialize<'de> for Config {
         let mut cfg = Config {
             sources: wire.sources,
             targets: wire.targets,
+            lookups: wire.lookups,
+            redact: wire.redact,
+            defaults: wire.defaults,
+            state_dir: wire.state_dir,
+            execution: wire.execution,
+            limits: wire.limits,
             source_ix: HashMap::new(),
             target_ix: HashMap::new(),
         };
@@ -231,6 +1311,63 @@ impl Config {
         self.build_indexes()
     }
 
+    /// Destination table for `module` reading `source`: the source's
+    /// `table_destination_name`, or else the module file name without its
+    /// directory and extension (`reports/users.sql` -> `users`), after
+    /// `defaults.table_prefix`.
+    ///
+    /// Returns `None` only if `module` has no file name.
+    pub fn destination_table(&self, source: &Source, module: &str) -> Option<String> {
+        if let Some(name) = &source.table_destination_name {
+            return Some(name.clone());
+        }
+        let stem = std::path::Path::new(module).file_stem()?.to_str()?;
+        let prefix = self.defaults.table_prefix.as_deref().unwrap_or_default();
+        Some(format!("{prefix}{stem}"))
+    }
+
+    /// Headers for `source`: `defaults.headers` overlaid by the source's own,
+    /// compared case-insensitively.
+    pub fn headers_for(&self, source: &Source) -> Vec<Header> {
+        let mut merged: Vec<Header> = Vec::new();
+        let own = source.headers.iter().flatten();
+        for header in self.defaults.headers.iter().chain(own) {
+            merged.retain(|h| !h.key.eq_ignore_ascii_case(&header.key));
+            merged.push(header.clone());
+        }
+        merged
+    }
+
+    /// Metadata columns for `source`: its own `metadata_columns` if set,
+    /// otherwise `defaults.metadata_columns`.
+    pub fn metadata_columns_for<'a>(&'a self, source: &'a Source) -> &'a MetadataColumns {
+        source
+            .metadata_columns
+            .as_ref()
+            .unwrap_or(&self.defaults.metadata_columns)
+    }
+
+    /// The `redact` block plus every source's query auth parameter.
+    pub fn redact_config(&self) -> RedactConfig {
+        let mut redact = self.redact.clone();
+        for source in &self.sources {
+            if let Some(SourceAuth::Query { param, .. }) = &source.auth {
+                redact.query_params.push(param.clone());
+            }
+        }
+        redact
+    }
+
+    /// Store for pagination checkpoints under `state_dir`.
+    pub fn checkpoint_store(&self) -> CheckpointStore {
+        CheckpointStore::new(self.state_dir.as_deref().unwrap_or(DEFAULT_STATE_DIR))
+    }
+
+    /// Where `conditional` sources keep their `ETag`/`Last-Modified` validators.
+    pub fn validator_store(&self) -> ValidatorStore {
+        ValidatorStore::new(self.state_dir.as_deref().unwrap_or(DEFAULT_STATE_DIR))
+    }
+
     pub fn source(&self, name: &str) -> Option<&Source> {
         self.source_ix.get(name).and_then(|&i| self.sources.get(i))
     }
@@ -264,6 +1401,9 @@ impl Named for Target {
     fn name(&self) -> &str {
         match self {
             Target::Postgres(x) => &x.name,
+            Target::Snowflake(x) => &x.name,
+            Target::ObjectStore(x) => &x.name,
+            Target::Custom(x) => &x.name,
         }
     }
 }
@@ -272,5 +1412,13 @@ impl Named for Target {
 // Enable your templates to call `{{ source("json_place_holder") }}`
 // and `{{ sink("postgres_sink") }}` to choose a YAML target by name.
 
+pub mod backfill;
+pub mod checkpoint;
+pub mod conditional;
+pub mod empty;
+pub mod file;
+pub mod grpc;
+pub mod limits;
 pub mod run;
 pub mod sink;
+pub mod split;
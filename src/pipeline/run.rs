@@ -1,25 +1,122 @@
+use datafusion::arrow::array::AsArray;
+use datafusion::arrow::compute::cast;
+use datafusion::arrow::datatypes::{DataType, SchemaRef};
+use futures::StreamExt;
 use reqwest::Client;
 use std::sync::Arc;
+use std::time::Duration;
 use url::Url;
 
-use crate::http::fetcher::FetchStats;
-use crate::pipeline::QueryParam;
+use crate::http::capture::Capture;
+use crate::http::fetcher::{
+    ndjson_stream_request, FetchStats, GraphqlFetchConfig, RequestTemplate,
+};
+use crate::http::preflight::Preflight;
+use crate::pipeline::checkpoint::{CheckpointingPageWriter, Resume};
+use crate::pipeline::conditional::{Conditional, ConditionalRequest};
+use crate::pipeline::file::{expand_glob, fetch_files, records_stream};
+use crate::pipeline::grpc::{fetch_grpc, GrpcRequest};
+use crate::pipeline::limits::Limits;
+use crate::pipeline::split::{write_split, SplitTee, SplitWriter};
+use crate::pipeline::{GraphqlConfig, PathParams, QueryParam, Source};
+use crate::utils::constant_columns::ConstantColumns;
+use crate::utils::datafusion_ext::get_shared_context;
+use crate::utils::dictionary::DictionaryEncoding;
+use crate::utils::ejson::unwrap_extended;
+use crate::utils::execution::ExecutionOpts;
+use crate::utils::fields::FieldMapping;
+use crate::utils::flatten::Flatten;
+use crate::utils::metadata::MetadataStamp;
+use crate::utils::nulls::NullHandling;
+use crate::utils::numbers::NumberHandling;
+use crate::utils::progress::{ProgressOpts, WriteProgress};
+use crate::utils::redact::redact_url;
+use crate::utils::schema::{infer_schema_from_values, raw_json_schema};
+use crate::utils::schema_contract::SchemaContract;
 use crate::utils::template;
+use crate::utils::transform::RecordTransform;
 use crate::{
     errors::{ApitapError, Result},
-    http::fetcher::{DataFusionPageWriter, LimitOffsetConfig, PaginatedFetcher, Pagination},
+    http::fetcher::{
+        CursorConfig, DataFusionPageWriter, LimitOffsetConfig, PageWriter, PaginatedFetcher,
+        Pagination, RecordBudget, ResponseFormat, StreamConfig,
+    },
     writer::{DataWriter, WriteMode},
 };
 
 #[derive(Debug, Clone)]
 pub struct FetchOpts {
     pub concurrency: usize,
+    /// Write concurrently fetched pages in page order.
+    pub preserve_order: bool,
     pub default_page_size: usize,
     pub fetch_batch_size: usize, // internal http batch size
+    /// Deadline for fetching and writing one run. An exceeded run is cancelled
+    /// and its writer rolled back, which only undoes writes the writer holds in
+    /// a transaction (Postgres); Snowflake truncates and batches and object
+    /// store files that were already written are kept.
+    pub timeout: Option<Duration>,
+    /// Cadence of the periodic write progress logs.
+    pub progress: ProgressOpts,
+    /// Batch size, buffering, and parallelism of the module SQL.
+    pub execution: ExecutionOpts,
+    /// Where request/response captures go, with `--capture-dir`.
+    pub capture: Option<Arc<Capture>>,
+    /// Module and request permits shared by every module of the process.
+    pub limits: Limits,
+    /// Stop fetching a source after this many records, across all of its
+    /// requests and pages (`--sample`).
+    pub max_records: Option<u64>,
+}
+
+impl FetchOpts {
+    /// Returns a copy with the source's tuning overrides applied.
+    ///
+    /// Values set on the source win over the process-wide defaults.
+    pub fn for_source(&self, source: &Source) -> FetchOpts {
+        FetchOpts {
+            concurrency: source.concurrency.unwrap_or(self.concurrency),
+            preserve_order: source.preserve_order || self.preserve_order,
+            default_page_size: page_size_for(source, self.default_page_size),
+            fetch_batch_size: source.fetch_batch_size.unwrap_or(self.fetch_batch_size),
+            timeout: source
+                .timeout_secs
+                .map(Duration::from_secs)
+                .or(self.timeout),
+            progress: self.progress,
+            execution: self.execution.clone(),
+            capture: self.capture.clone(),
+            limits: self.limits.clone(),
+            max_records: self.max_records,
+        }
+    }
+}
+
+/// Page size for `source`: its `pagination.page_size`, else its `page_size`,
+/// else `default`, clamped to its `max_page_size`.
+fn page_size_for(source: &Source, default: usize) -> usize {
+    let page_size = source
+        .pagination
+        .as_ref()
+        .and_then(Pagination::page_size)
+        .or(source.page_size)
+        .unwrap_or(default);
+    match source.max_page_size {
+        Some(max) if page_size > max => {
+            tracing::warn!(
+                source = %source.name,
+                page_size,
+                max_page_size = max,
+                "⚠️ page size exceeds max_page_size; using the maximum"
+            );
+            max
+        }
+        _ => page_size,
+    }
 }
 
 /// Configuration for the HTTP fetch request
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 pub struct FetchRequest {
     pub client: Client,
     pub url: Url,
@@ -27,6 +124,122 @@ pub struct FetchRequest {
     pub extra_params: Option<Vec<QueryParam>>,
     pub pagination: Option<Pagination>,
     pub retry: crate::pipeline::Retry,
+    /// Method, body, and pagination placement for every page request.
+    pub request_template: RequestTemplate,
+    /// Set for `kind: graphql` sources; takes precedence over `pagination`.
+    pub graphql: Option<GraphqlConfig>,
+    /// Set for `kind: grpc` sources; the method is called instead of `url`.
+    pub grpc: Option<GrpcRequest>,
+    /// File or glob to read instead of fetching, for `kind: file` sources.
+    pub file: Option<String>,
+    /// Skip schema inference and expose each record as one `data` text column.
+    pub raw_json: bool,
+    /// Unwrap MongoDB extended JSON wrappers in every record.
+    pub ejson: bool,
+    /// Field renames and drops applied to every record.
+    pub fields: FieldMapping,
+    /// Handling of integers outside the `i64` range.
+    pub numbers: NumberHandling,
+    /// Strings read as null in every record.
+    pub nulls: NullHandling,
+    /// Per-record `transform` statements, run after field mapping.
+    pub transform: RecordTransform,
+    /// Flattening of nested objects, run after `transform`.
+    pub flatten: Option<Flatten>,
+    /// Dictionary encoding of low-cardinality string columns.
+    pub dictionary: Option<DictionaryEncoding>,
+    /// Checkpointing of page-number pagination, for sources with `resume`.
+    pub resume: Option<Resume>,
+    /// Lineage columns added to every output row of the run.
+    pub metadata: Option<MetadataStamp>,
+    /// Fixed columns added to every output row, after `metadata`.
+    pub constant_columns: Option<ConstantColumns>,
+    /// Checked against the schema inferred from the records.
+    pub schema_contract: Option<Arc<SchemaContract>>,
+    /// Validator storage, for sources with `conditional: true`.
+    pub conditional: Option<Conditional>,
+    /// `HEAD` request sent before fetching, for sources with `preflight: true`.
+    pub preflight: Option<Preflight>,
+}
+
+impl FetchRequest {
+    /// Expands the request into one per value, each with the `{name}` segment
+    /// of its URL path replaced by that (percent-encoded) value.
+    ///
+    /// # Errors
+    ///
+    /// Returns a config error if the URL path has no `{name}` segment.
+    pub fn expand_path_param(self, name: &str, values: &[String]) -> Result<Vec<FetchRequest>> {
+        // `Url` stores the braces percent-encoded
+        let placeholder = format!("%7B{name}%7D");
+        let segments: Vec<&str> = self.url.path().split('/').collect();
+        if !segments.contains(&placeholder.as_str()) {
+            return Err(ApitapError::ConfigError(format!(
+                "path_params '{name}': url '{}' has no {{{name}}} path segment",
+                self.url
+            )));
+        }
+
+        Ok(values
+            .iter()
+            .map(|value| {
+                let value = encode_path_segment(value);
+                let path = segments
+                    .iter()
+                    .map(|s| if *s == placeholder { value.as_str() } else { s })
+                    .collect::<Vec<_>>()
+                    .join("/");
+                let mut request = self.clone();
+                request.url.set_path(&path);
+                request
+            })
+            .collect())
+    }
+}
+
+/// Percent-encodes `value` for use as a single URL path segment.
+fn encode_path_segment(value: &str) -> String {
+    let mut scratch = Url::parse("http://localhost/").expect("static url is valid");
+    scratch
+        .path_segments_mut()
+        .expect("http urls have a path")
+        .clear()
+        .push(value);
+    scratch.path()[1..].to_string()
+}
+
+/// Resolves the values a source fans out over: the listed `values`, followed
+/// by the first column of `query` run against the shared DataFusion context.
+///
+/// Null query results are skipped.
+pub async fn resolve_path_params(params: &PathParams) -> Result<Vec<String>> {
+    let mut values: Vec<String> = params
+        .values
+        .iter()
+        .map(|v| match v {
+            serde_json::Value::String(s) => s.clone(),
+            other => other.to_string(),
+        })
+        .collect();
+
+    if let Some(sql) = &params.query {
+        let ctx = get_shared_context().await;
+        for batch in ctx.sql(sql).await?.collect().await? {
+            if batch.num_columns() == 0 {
+                continue;
+            }
+            let column = cast(batch.column(0), &DataType::Utf8)?;
+            values.extend(
+                column
+                    .as_string::<i32>()
+                    .iter()
+                    .flatten()
+                    .map(str::to_string),
+            );
+        }
+    }
+
+    Ok(values)
 }
 
 /// Configuration for SQL query execution
@@ -40,6 +253,8 @@ pub struct QueryConfig<'a> {
 pub struct WriteConfig {
     pub writer: Arc<dyn DataWriter>,
     pub write_mode: WriteMode,
+    /// Writers for the source's `splits`, run alongside `writer`.
+    pub splits: Vec<SplitWriter>,
 }
 
 fn clean_param(params: Option<Vec<QueryParam>>) -> Result<Vec<(String, String)>> {
@@ -47,39 +262,246 @@ fn clean_param(params: Option<Vec<QueryParam>>) -> Result<Vec<(String, String)>>
         Some(params) => params
             .into_iter()
             .map(|q| {
-                let key = q.key;
+                if q.raw {
+                    return q.to_pairs(|v| Ok(v.to_string()));
+                }
                 // First substitute environment variables, then templates
-                let val = template::substitute_env_vars(&q.value)?;
-                let val = template::substitute_templates(&val)?;
-                Ok((key, val))
+                q.to_pairs(|v| {
+                    let val = template::substitute_env_vars(v)?;
+                    template::substitute_templates(&val)
+                })
             })
-            .collect(),
+            .collect::<Result<Vec<_>>>()
+            .map(|pairs| pairs.into_iter().flatten().collect()),
         None => Ok(Vec::new()),
     }
 }
+/// Fetches every page, runs the module SQL, and writes the result.
+///
+/// The whole run is wrapped in the writer's `begin`/`commit` hooks; on error
+/// the writer is rolled back before the error is returned. When
+/// [`FetchOpts::timeout`] is set and elapses first, the in-flight fetch is
+/// dropped, the writer rolled back, and [`ApitapError::Timeout`] returned; see
+/// [`FetchOpts::timeout`] for which writes that undoes.
 pub async fn run_fetch(
     request: FetchRequest,
     query: QueryConfig<'_>,
     write_config: WriteConfig,
     opts: &FetchOpts,
 ) -> Result<FetchStats> {
-    let page_writer = Arc::new(DataFusionPageWriter::new(
-        query.dest_table,
-        query.sql,
-        write_config.writer.clone(),
-    ));
+    run_fetch_all(vec![request], query, write_config, opts).await
+}
+
+/// Like [`run_fetch`], but fetches several requests (e.g. one per
+/// `path_params` value) into the same run and transaction.
+///
+/// Requests are fetched one after another, each with its own pagination,
+/// `opts.concurrency` page requests, and retries; stats are summed.
+pub async fn run_fetch_all(
+    requests: Vec<FetchRequest>,
+    query: QueryConfig<'_>,
+    write_config: WriteConfig,
+    opts: &FetchOpts,
+) -> Result<FetchStats> {
+    let writer = write_config.writer.clone();
+    writer.begin().await?;
+    for split in &write_config.splits {
+        split.writer.begin().await?;
+    }
+
+    let dest_table = query.dest_table;
+    // A sampled run leaves checkpoints and validators as they were: the next
+    // full run must neither skip pages nor get a 304 for the sampled data
+    let sampled = opts.max_records.is_some();
+    let resumes: Vec<Resume> = requests
+        .iter()
+        .filter(|_| !sampled)
+        .filter_map(|r| r.resume.clone())
+        .collect();
+    let mut requests = requests;
+    // One budget for the whole source, across every `path_params` request
+    let budget = opts.max_records.map(|max| Arc::new(RecordBudget::new(max)));
+    let mut validators = Vec::new();
+    for request in &mut requests {
+        request.request_template.record_budget = budget.clone();
+        if let Some(conditional) = request.conditional.as_ref().filter(|_| !sampled) {
+            let url = redact_url(request.url.as_str());
+            let previous = conditional.store.load(&conditional.key, &url)?;
+            let state = Arc::new(ConditionalRequest::new(previous));
+            request.request_template.conditional = Some(Arc::clone(&state));
+            validators.push((conditional.clone(), url, state));
+        }
+    }
+    let (tee, split_records) =
+        SplitTee::new(write_config.splits.iter().map(|s| s.data_path.as_str()));
+    if !write_config.splits.is_empty() {
+        for request in &mut requests {
+            request.request_template.splits = Some(Arc::clone(&tee));
+        }
+    }
+    let progress = WriteProgress::start(dest_table, &opts.progress);
+    let run = async {
+        let mut total = FetchStats::new();
+        for request in requests {
+            let stats = fetch_and_write(request, &query, &write_config, opts, &progress).await?;
+            total.merge(&stats);
+        }
+        total.not_modified = validators
+            .iter()
+            .filter(|(_, _, s)| s.not_modified())
+            .count();
+        Ok(total)
+    };
+    let run = async {
+        let outcome = match opts.timeout {
+            Some(limit) => tokio::time::timeout(limit, run).await.unwrap_or_else(|_| {
+                Err(ApitapError::Timeout(format!(
+                    "run for '{dest_table}' exceeded {}s and was cancelled; \
+                     writes outside a transaction are not undone",
+                    limit.as_secs()
+                )))
+            }),
+            None => run.await,
+        };
+        // Lets the split writers drain what was teed and finish
+        tee.close();
+        outcome
+    };
+    let splits = futures::future::try_join_all(
+        write_config
+            .splits
+            .iter()
+            .zip(split_records)
+            .map(|(split, records)| write_split(split, records, &opts.execution)),
+    );
+    let (outcome, split_outcome) = tokio::join!(run, splits);
+    let outcome = outcome.and_then(|stats| split_outcome.map(|_| stats));
+
+    match outcome {
+        Ok(stats) => {
+            writer.commit().await?;
+            writer.finish().await?;
+            for split in &write_config.splits {
+                split.writer.commit().await?;
+                split.writer.finish().await?;
+            }
+            // Finished runs start over next time
+            for resume in resumes {
+                resume.store.clear(&resume.key)?;
+            }
+            for (conditional, url, state) in &validators {
+                if let Some(received) = state.received() {
+                    conditional.store.save(&conditional.key, url, &received)?;
+                }
+            }
+            Ok(stats)
+        }
+        Err(e) => {
+            if let Err(rb) = writer.rollback().await {
+                tracing::warn!("rollback after failed write also failed: {}", rb);
+            }
+            for split in &write_config.splits {
+                if let Err(rb) = split.writer.rollback().await {
+                    tracing::warn!(
+                        "rollback of '{}' after failed write also failed: {}",
+                        split.dest_table,
+                        rb
+                    );
+                }
+            }
+            Err(e)
+        }
+    }
+}
+
+async fn fetch_and_write(
+    mut request: FetchRequest,
+    query: &QueryConfig<'_>,
+    write_config: &WriteConfig,
+    opts: &FetchOpts,
+    progress: &Arc<WriteProgress>,
+) -> Result<FetchStats> {
+    let page_writer = Arc::new(
+        DataFusionPageWriter::new(query.dest_table, query.sql, write_config.writer.clone())
+            .with_raw_json(request.raw_json)
+            .with_ejson(request.ejson)
+            .with_field_mapping(request.fields.clone())
+            .with_transform(request.transform.clone())
+            .with_flatten(request.flatten.clone())
+            .with_number_handling(request.numbers.clone())
+            .with_null_handling(request.nulls.clone())
+            .with_dictionary(request.dictionary.clone())
+            .with_metadata(request.metadata.clone())
+            .with_constant_columns(request.constant_columns.clone())
+            .with_schema_contract(request.schema_contract.clone())
+            .with_progress(Arc::clone(progress))
+            .with_execution(opts.execution.clone()),
+    );
+
+    if let Some(pattern) = &request.file {
+        return fetch_files(
+            pattern,
+            request.data_path,
+            request.request_template,
+            page_writer,
+            write_config.write_mode.clone(),
+        )
+        .await;
+    }
+
+    if let Some(grpc) = &request.grpc {
+        return fetch_grpc(
+            &request.url,
+            grpc,
+            request.data_path,
+            request.request_template,
+            page_writer,
+            write_config.write_mode.clone(),
+        )
+        .await;
+    }
+
+    if let Some(gql) = &request.graphql {
+        let variables = match &gql.variables {
+            Some(v) => template::substitute_json(v)?,
+            None => serde_json::Value::Null,
+        };
+        let fetcher = PaginatedFetcher::new(request.client, request.url, opts.concurrency)
+            .with_progress(Arc::clone(progress))
+            .with_batch_size(opts.fetch_batch_size);
+
+        return fetcher
+            .fetch_graphql(GraphqlFetchConfig {
+                query: &gql.query,
+                variables,
+                records_path: &gql.records_path,
+                end_cursor_path: &gql.end_cursor_path,
+                has_next_page_path: &gql.has_next_page_path,
+                cursor_variable: &gql.cursor_variable,
+                writer: page_writer,
+                write_mode: write_config.write_mode.clone(),
+                retry: &request.retry,
+            })
+            .await;
+    }
 
     // Convert QueryParam to (String, String) tuples
-    let extra_params_vec: Vec<(String, String)> = clean_param(request.extra_params)?;
+    let extra_params_vec: Vec<(String, String)> = clean_param(request.extra_params.take())?;
+    apply_preflight(&mut request, &extra_params_vec).await;
 
     match request.pagination {
         Some(Pagination::LimitOffset {
             limit_param,
             offset_param,
+            start_offset,
+            ..
         }) => {
             let fetcher = PaginatedFetcher::new(request.client, request.url, opts.concurrency)
+                .with_progress(Arc::clone(progress))
                 .with_limit_offset(&limit_param, &offset_param)
-                .with_batch_size(opts.fetch_batch_size);
+                .with_start_offset(start_offset)
+                .with_batch_size(opts.fetch_batch_size)
+                .with_request(request.request_template.clone());
 
             let page_size: u64 = opts.default_page_size.try_into().map_err(|_| {
                 ApitapError::ConfigError(format!(
@@ -95,7 +517,7 @@ pub async fn run_fetch(
                     extra_params: Some(&extra_params_vec),
                     total_hint: None,
                     writer: page_writer,
-                    write_mode: write_config.write_mode,
+                    write_mode: write_config.write_mode.clone(),
                     retry: &request.retry,
                 })
                 .await?;
@@ -105,16 +527,48 @@ pub async fn run_fetch(
         Some(Pagination::PageNumber {
             page_param,
             per_page_param,
+            start_page,
+            ..
         }) => {
-            let page_writer = Arc::new(DataFusionPageWriter::new(
-                query.dest_table,
-                query.sql,
-                write_config.writer.clone(),
-            ));
+            let mut start_page = start_page;
+            let mut page_writer: Arc<dyn PageWriter> = page_writer;
+            let mut checkpointer = None;
+            // A sampled run neither resumes nor leaves a checkpoint behind
+            let resume = request
+                .resume
+                .as_ref()
+                .filter(|_| opts.max_records.is_none());
+            if let Some(resume) = resume {
+                let url = redact_url(request.url.as_str());
+                if let Some(saved) = resume.store.load(&resume.key, &url, resume.window)? {
+                    if saved.complete {
+                        tracing::info!(key = %resume.key, %url, "already fetched before the last failure; skipping");
+                        return Ok(FetchStats::new());
+                    }
+                    if saved.next_page > start_page {
+                        tracing::info!(key = %resume.key, page = saved.next_page, "resuming pagination from checkpoint");
+                        start_page = saved.next_page;
+                    }
+                }
+                let writer = Arc::new(CheckpointingPageWriter::new(
+                    page_writer,
+                    write_config.writer.clone(),
+                    resume.clone(),
+                    url,
+                    start_page,
+                ));
+                page_writer = writer.clone();
+                checkpointer = Some(writer);
+            }
 
             let fetcher = PaginatedFetcher::new(request.client, request.url, opts.concurrency)
+                .with_progress(Arc::clone(progress))
                 .with_batch_size(opts.fetch_batch_size)
-                .with_page_number(&page_param, &per_page_param);
+                .with_page_number(&page_param, &per_page_param)
+                .with_start_page(start_page)
+                // Checkpoints commit between writes, so pages go in one at a time
+                .with_preserve_order(opts.preserve_order || checkpointer.is_some())
+                .with_request(request.request_template.clone());
 
             let per_page: u64 = opts.default_page_size.try_into().map_err(|_| {
                 ApitapError::ConfigError(format!(
@@ -129,27 +583,125 @@ pub async fn run_fetch(
                     request.data_path.as_deref(),
                     None,
                     page_writer,
-                    write_config.write_mode,
+                    write_config.write_mode.clone(),
                     &request.retry,
                 )
                 .await?;
 
+            if let Some(checkpointer) = checkpointer {
+                if checkpointer.page_failed() {
+                    return Err(ApitapError::PaginationError(format!(
+                        "page {} failed; pages before it are saved and the next run resumes there",
+                        checkpointer.next_page()
+                    )));
+                }
+                checkpointer.finish()?;
+            }
+
             Ok(stats)
         }
 
         Some(Pagination::PageOnly { page_param: _ }) => {
             let _fetcher = PaginatedFetcher::new(request.client, request.url, opts.concurrency)
+                .with_progress(Arc::clone(progress))
                 .with_batch_size(opts.fetch_batch_size);
             Ok(FetchStats::new())
         }
 
         Some(Pagination::Cursor {
-            cursor_param: _,
-            page_size_param: _,
+            cursor_param,
+            page_size_param,
+            next_cursor_header,
+            cursor_in,
+            ..
         }) => {
-            let _fetcher = PaginatedFetcher::new(request.client, request.url, opts.concurrency)
-                .with_batch_size(opts.fetch_batch_size);
-            Ok(FetchStats::new())
+            let next_cursor_header = next_cursor_header.ok_or_else(|| {
+                ApitapError::PaginationError(
+                    "cursor pagination needs next_cursor_header, the response header carrying the next token".into(),
+                )
+            })?;
+            let fetcher = PaginatedFetcher::new(request.client, request.url, opts.concurrency)
+                .with_progress(Arc::clone(progress))
+                .with_batch_size(opts.fetch_batch_size)
+                .with_cursor(
+                    &cursor_param,
+                    page_size_param.as_deref(),
+                    &next_cursor_header,
+                    cursor_in,
+                )
+                .with_request(request.request_template.clone());
+
+            let page_size: u64 = opts.default_page_size.try_into().map_err(|_| {
+                ApitapError::ConfigError(format!(
+                    "Invalid page size: {} (must fit in u64)",
+                    opts.default_page_size
+                ))
+            })?;
+
+            fetcher
+                .fetch_cursor(CursorConfig {
+                    page_size,
+                    data_path: request.data_path,
+                    extra_params: Some(&extra_params_vec),
+                    writer: page_writer,
+                    write_mode: write_config.write_mode.clone(),
+                    retry: &request.retry,
+                })
+                .await
+        }
+
+        Some(Pagination::NextUrl {
+            next_path,
+            has_more_path,
+            page_size_param,
+            ..
+        }) => {
+            let fetcher = PaginatedFetcher::new(request.client, request.url, opts.concurrency)
+                .with_progress(Arc::clone(progress))
+                .with_batch_size(opts.fetch_batch_size)
+                .with_next_url(
+                    &next_path,
+                    has_more_path.as_deref(),
+                    page_size_param.as_deref(),
+                )
+                .with_request(request.request_template.clone());
+
+            let page_size: u64 = opts.default_page_size.try_into().map_err(|_| {
+                ApitapError::ConfigError(format!(
+                    "Invalid page size: {} (must fit in u64)",
+                    opts.default_page_size
+                ))
+            })?;
+
+            fetcher
+                .fetch_next_url(CursorConfig {
+                    page_size,
+                    data_path: request.data_path,
+                    extra_params: Some(&extra_params_vec),
+                    writer: page_writer,
+                    write_mode: write_config.write_mode.clone(),
+                    retry: &request.retry,
+                })
+                .await
+        }
+
+        // An export stream is one request read to the end of its body
+        Some(Pagination::Default) | None
+            if request.request_template.format == ResponseFormat::NdjsonStream =>
+        {
+            let fetcher = PaginatedFetcher::new(request.client, request.url, opts.concurrency)
+                .with_progress(Arc::clone(progress))
+                .with_batch_size(opts.fetch_batch_size)
+                .with_request(request.request_template.clone());
+            fetcher
+                .fetch_stream(StreamConfig {
+                    data_path: request.data_path,
+                    extra_params: Some(&extra_params_vec),
+                    writer: page_writer,
+                    write_mode: write_config.write_mode.clone(),
+                    retry: &request.retry,
+                })
+                .await
         }
 
         Some(Pagination::Default) | None => Err(ApitapError::PaginationError(
@@ -157,3 +709,129 @@ pub async fn run_fetch(
         )),
     }
 }
+
+/// Records sampled by [`preview_schema`], matching the page writer's sample size.
+const PREVIEW_SAMPLE_SIZE: usize = 100;
+
+/// Fetches the first page of `request` and returns the schema the pipeline
+/// would infer from it. Nothing is written.
+///
+/// GraphQL and gRPC sources are not supported yet.
+pub async fn preview_schema(mut request: FetchRequest, opts: &FetchOpts) -> Result<SchemaRef> {
+    if request.graphql.is_some() {
+        return Err(ApitapError::ConfigError(
+            "schema preview is not supported for graphql sources".into(),
+        ));
+    }
+    if request.grpc.is_some() {
+        return Err(ApitapError::ConfigError(
+            "schema preview is not supported for grpc sources".into(),
+        ));
+    }
+
+    let mut stream = match &request.file {
+        Some(pattern) => records_stream(
+            expand_glob(pattern)?,
+            request.data_path.clone(),
+            request.request_template.clone(),
+            Default::default(),
+        ),
+        None => {
+            let query = clean_param(request.extra_params.clone())?;
+            apply_preflight(&mut request, &query).await;
+            first_page_stream(&request, opts).await?
+        }
+    };
+
+    let mut samples = Vec::new();
+    while samples.len() < PREVIEW_SAMPLE_SIZE {
+        match stream.next().await {
+            Some(item) => {
+                let mut record = item?;
+                if request.ejson {
+                    record = unwrap_extended(record);
+                }
+                let record = request.nulls.apply(request.fields.apply(record));
+                let record = request.transform.apply(record);
+                samples.push(match &request.flatten {
+                    Some(flatten) => flatten.apply(record),
+                    None => record,
+                });
+            }
+            None => break,
+        }
+    }
+
+    if request.raw_json {
+        return Ok(raw_json_schema());
+    }
+    let schema = infer_schema_from_values(&request.numbers.apply_page(samples)?)?;
+    if let Some(contract) = &request.schema_contract {
+        let diff = contract.diff(&schema);
+        if !diff.is_empty() {
+            tracing::warn!("⚠️ inferred schema differs from schema_contract: {diff}");
+        }
+    }
+    Ok(schema)
+}
+
+/// Sends the preflight `HEAD` of `request`, if it has one, and reads the
+/// response with the format it detects.
+async fn apply_preflight(request: &mut FetchRequest, query: &[(String, String)]) {
+    if let Some(preflight) = request.preflight {
+        let template = &mut request.request_template;
+        template.format = preflight
+            .resolve_format(&request.client, &request.url, query, template.format)
+            .await;
+    }
+}
+
+/// Records of the first page of `request`.
+async fn first_page_stream(
+    request: &FetchRequest,
+    opts: &FetchOpts,
+) -> Result<futures::stream::BoxStream<'static, Result<serde_json::Value>>> {
+    let extra_params = clean_param(request.extra_params.clone())?;
+    let page_size = serde_json::Value::from(opts.default_page_size);
+    let page_params: Vec<(String, serde_json::Value)> = match &request.pagination {
+        Some(Pagination::LimitOffset {
+            limit_param,
+            offset_param,
+            start_offset,
+            ..
+        }) => vec![
+            (limit_param.clone(), page_size),
+            (offset_param.clone(), (*start_offset).into()),
+        ],
+        Some(Pagination::PageNumber {
+            page_param,
+            per_page_param,
+            start_page,
+            ..
+        }) => vec![
+            (page_param.clone(), (*start_page).into()),
+            (per_page_param.clone(), page_size),
+        ],
+        Some(Pagination::PageOnly { page_param }) => vec![(page_param.clone(), 1.into())],
+        Some(Pagination::Cursor {
+            page_size_param: Some(param),
+            ..
+        })
+        | Some(Pagination::NextUrl {
+            page_size_param: Some(param),
+            ..
+        }) => vec![(param.clone(), page_size)],
+        _ => Vec::new(),
+    };
+
+    ndjson_stream_request(
+        &request.client,
+        request.url.as_str(),
+        &extra_params,
+        &page_params,
+        &request.request_template,
+        request.data_path.as_deref(),
+        &request.retry,
+    )
+    .await
+}
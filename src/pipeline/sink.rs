@@ -1,13 +1,26 @@
+use std::collections::BTreeMap;
 use std::future::Future;
 use std::pin::Pin;
 use std::sync::Arc;
 
+#[cfg(any(feature = "postgres", feature = "snowflake", feature = "object_store"))]
 use futures::FutureExt;
 
 use crate::errors::Result;
 use crate::pipeline::TargetConn;
+#[cfg(feature = "postgres")]
+use crate::utils::http_retry::JitteredBackoff;
+#[cfg(feature = "postgres")]
+use crate::utils::schema::RAW_JSON_COLUMN;
+use crate::writer::columns::ColumnSelection;
+#[cfg(feature = "object_store")]
+use crate::writer::object_store::ObjectStoreWriter;
+use crate::writer::post_check::PostCheck;
+#[cfg(feature = "postgres")]
 use crate::writer::postgres::PostgresWriter;
-use crate::writer::{DataWriter, WriteMode};
+#[cfg(feature = "snowflake")]
+use crate::writer::snowflake::SnowflakeWriter;
+use crate::writer::{DataWriter, TruncateMode, WriteMode};
 
 pub type HookFuture = Pin<Box<dyn Future<Output = Result<()>> + Send + 'static>>;
 pub type Hook = Box<dyn FnOnce() -> HookFuture + Send>;
@@ -21,7 +34,21 @@ pub struct WriterOpts<'a> {
     pub auto_create: bool,
     pub auto_truncate: bool,
     pub truncate_first: bool,
+    /// How the truncate hook empties the table (Postgres only).
+    pub truncate_mode: TruncateMode,
+    /// Load into a staging table swapped in at the end (Postgres only).
+    pub swap: bool,
     pub write_mode: WriteMode,
+    /// Commit every N rows instead of once per run (Postgres only).
+    pub commit_every: Option<usize>,
+    /// Rows carry serialized JSON in the `data` column; stored as `JSONB` in Postgres.
+    pub raw_json: bool,
+    /// Per-column Postgres type overrides for auto-created tables.
+    pub column_types: BTreeMap<String, String>,
+    /// Columns of the result this sink writes.
+    pub columns: ColumnSelection,
+    /// Assertion queries run once the load is committed (Postgres only).
+    pub post_checks: Vec<PostCheck>,
 }
 
 pub trait MakeWriter {
@@ -31,7 +58,13 @@ pub trait MakeWriter {
 impl MakeWriter for TargetConn {
     fn make_writer(&self, opts: &WriterOpts<'_>) -> Result<(Arc<dyn DataWriter>, Option<Hook>)> {
         match self {
-            TargetConn::Postgres { pool, .. } => {
+            #[cfg(feature = "postgres")]
+            TargetConn::Postgres {
+                pool,
+                write_retry,
+                dead_letter,
+                ..
+            } => {
                 // 1) Build concrete writer
 
                 let pg = Arc::new(
@@ -40,7 +73,23 @@ impl MakeWriter for TargetConn {
                         .with_batch_size(opts.batch_size)
                         .with_sample_size(opts.sample_size)
                         .auto_create(opts.auto_create)
-                        .auto_truncate(opts.auto_truncate),
+                        .auto_truncate(opts.auto_truncate)
+                        .with_truncate_mode(opts.truncate_mode)
+                        .with_swap(opts.swap)
+                        .with_post_checks(opts.post_checks.clone())
+                        .with_write_retry(write_retry.as_ref().map(JitteredBackoff::from_config))
+                        .with_dead_letter(
+                            dead_letter
+                                .clone()
+                                .map(|writer| writer as Arc<dyn DataWriter>),
+                        )
+                        .with_commit_every(opts.commit_every)
+                        .with_json_columns(if opts.raw_json {
+                            vec![RAW_JSON_COLUMN.to_string()]
+                        } else {
+                            Vec::new()
+                        })
+                        .with_column_types(opts.column_types.clone()),
                 );
 
                 // 2) Optional truncate hook that captures the *concrete* writer
@@ -62,6 +111,101 @@ impl MakeWriter for TargetConn {
 
                 Ok((writer, hook))
             }
+            #[cfg(feature = "snowflake")]
+            TargetConn::Snowflake { .. } if opts.swap => Err(postgres_only("swap", opts)),
+            #[cfg(feature = "snowflake")]
+            TargetConn::Snowflake { .. } if !opts.post_checks.is_empty() => {
+                Err(postgres_only("post_check", opts))
+            }
+            #[cfg(feature = "snowflake")]
+            TargetConn::Snowflake { client } => {
+                let sf = Arc::new(
+                    SnowflakeWriter::new(Arc::clone(client), opts.dest_table)
+                        .with_primary_key_single(opts.primary_key.clone())
+                        .with_batch_size(opts.batch_size)
+                        .with_sample_size(opts.sample_size)
+                        .auto_create(opts.auto_create)
+                        .auto_truncate(opts.auto_truncate),
+                );
+
+                let hook: Option<Hook> = if opts.truncate_first {
+                    let sf_for_hook = Arc::clone(&sf);
+                    Some(Box::new(move || {
+                        (async move { sf_for_hook.truncate().await }).boxed() as HookFuture
+                    }))
+                } else {
+                    None
+                };
+
+                let writer: Arc<dyn DataWriter> = sf;
+                Ok((writer, hook))
+            }
+            #[cfg(feature = "object_store")]
+            TargetConn::ObjectStore { .. } if opts.swap => Err(postgres_only("swap", opts)),
+            #[cfg(feature = "object_store")]
+            TargetConn::ObjectStore { .. } if !opts.post_checks.is_empty() => {
+                Err(postgres_only("post_check", opts))
+            }
+            #[cfg(feature = "object_store")]
+            TargetConn::ObjectStore {
+                store,
+                prefix,
+                partition,
+                parquet,
+            } => {
+                let os = Arc::new(
+                    ObjectStoreWriter::new(Arc::clone(store), prefix.clone(), opts.dest_table)
+                        .with_partition(partition.clone())
+                        .with_parquet(parquet.clone())
+                        .with_batch_size(opts.batch_size)
+                        .with_sample_size(opts.sample_size),
+                );
+
+                let hook: Option<Hook> = if opts.truncate_first {
+                    let os_for_hook = Arc::clone(&os);
+                    Some(Box::new(move || {
+                        (async move { os_for_hook.truncate().await }).boxed() as HookFuture
+                    }))
+                } else {
+                    None
+                };
+
+                let writer: Arc<dyn DataWriter> = os;
+                Ok((writer, hook))
+            }
+            TargetConn::Custom { sink, factory } => factory.make_writer(sink, opts),
+        }
+    }
+}
+
+impl TargetConn {
+    /// Runs `sql` on the target as written, for modules without a source.
+    ///
+    /// Postgres accepts several `;`-separated statements, which it runs in
+    /// one implicit transaction; Snowflake runs a single statement. Object stores can't
+    /// run SQL.
+    pub async fn execute_sql(&self, sql: &str) -> Result<()> {
+        match self {
+            #[cfg(feature = "postgres")]
+            TargetConn::Postgres { pool, .. } => {
+                sqlx::raw_sql(sql).execute(pool).await?;
+                Ok(())
+            }
+            #[cfg(feature = "snowflake")]
+            TargetConn::Snowflake { client } => client.execute(sql, &[]).await.map(|_| ()),
+            #[cfg(feature = "object_store")]
+            TargetConn::ObjectStore { .. } => Err(crate::errors::ApitapError::UnsupportedSink(
+                "object_store targets cannot run SQL-only modules".to_string(),
+            )),
+            TargetConn::Custom { sink, factory } => factory.execute_sql(sink, sql)?.await,
         }
     }
 }
+
+#[cfg(any(feature = "snowflake", feature = "object_store"))]
+fn postgres_only(option: &str, opts: &WriterOpts<'_>) -> crate::errors::ApitapError {
+    crate::errors::ApitapError::UnsupportedSink(format!(
+        "{option} is only supported by postgres targets (table '{}')",
+        opts.dest_table
+    ))
+}
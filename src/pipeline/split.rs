@@ -0,0 +1,132 @@
+//! Extra record arrays loaded from the same responses as the module's own.
+//!
+//! An endpoint that bundles related collections can feed several tables from
+//! one fetch. `data_path` still selects the records the module SQL reads;
+//! each entry of the source's `splits` names another array of every response
+//! and the table it lands in, with the module's sinks:
+//!
+//! ```yaml
+//! sources:
+//!   - name: directory
+//!     url: https://api.example.com/directory
+//!     data_path: /users
+//!     splits:
+//!       - data_path: /accounts
+//!         dest_table: accounts
+//!         primary_key: account_id
+//! ```
+//!
+//! Split records skip the module SQL and the source's record processing
+//! (`fields`, `transform`, ...); their schema is inferred on their own. They
+//! are written in the same run as the module's rows and commit or roll back
+//! with them.
+
+use std::sync::{Arc, Mutex};
+
+use futures::StreamExt;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use tokio::sync::mpsc::{unbounded_channel, UnboundedReceiver, UnboundedSender};
+
+use crate::errors::Result;
+use crate::http::fetcher::{DataFusionPageWriter, PageWriter, RequestTemplate};
+use crate::utils::execution::ExecutionOpts;
+use crate::writer::{DataWriter, WriteMode};
+
+/// One extra `(data_path, dest_table)` pair of a source.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SourceSplit {
+    /// JSON pointer to the array in each response.
+    pub data_path: String,
+    /// Table the records are written to, in every sink of the module.
+    pub dest_table: String,
+    /// Key used when the sink merges; without one, merges become appends.
+    #[serde(default)]
+    pub primary_key: Option<String>,
+}
+
+/// Where one split's records go during a run.
+pub struct SplitWriter {
+    /// JSON pointer to the array in each response.
+    pub data_path: String,
+    /// Table the records are written to.
+    pub dest_table: String,
+    pub writer: Arc<dyn DataWriter>,
+    pub write_mode: WriteMode,
+}
+
+/// Copies the split arrays of every parsed response to their writers.
+///
+/// Set on the [`RequestTemplate`] of each request of a run; the fetch paths
+/// call [`RequestTemplate::tee_splits`] once per response body.
+#[derive(Debug, Default)]
+pub struct SplitTee {
+    outputs: Vec<(String, Mutex<Option<UnboundedSender<Value>>>)>,
+}
+
+impl SplitTee {
+    /// A tee over `data_paths` and one receiver per path, in order.
+    pub fn new<'a>(
+        data_paths: impl IntoIterator<Item = &'a str>,
+    ) -> (Arc<SplitTee>, Vec<UnboundedReceiver<Value>>) {
+        let mut outputs = Vec::new();
+        let mut receivers = Vec::new();
+        for path in data_paths {
+            let (tx, rx) = unbounded_channel();
+            outputs.push((path.to_string(), Mutex::new(Some(tx))));
+            receivers.push(rx);
+        }
+        (Arc::new(SplitTee { outputs }), receivers)
+    }
+
+    /// Sends the records at each split's `data_path` of `body`, read like the
+    /// module's own records (`records_as`); missing paths send nothing.
+    pub fn send(&self, body: &Value, template: &RequestTemplate) {
+        for (path, tx) in &self.outputs {
+            let Some(target) = body.pointer(path) else {
+                continue;
+            };
+            let tx = tx.lock().unwrap_or_else(|e| e.into_inner());
+            let Some(tx) = tx.as_ref() else {
+                continue;
+            };
+            for record in template.records(target.clone()) {
+                // A receiver only goes away when its writer failed; the run
+                // reports that error
+                let _ = tx.send(record);
+            }
+        }
+    }
+
+    /// Ends every split stream; records sent afterwards are dropped.
+    pub fn close(&self) {
+        for (_, tx) in &self.outputs {
+            tx.lock().unwrap_or_else(|e| e.into_inner()).take();
+        }
+    }
+}
+
+/// Writes the records received for `split` until the tee closes.
+pub async fn write_split(
+    split: &SplitWriter,
+    records: UnboundedReceiver<Value>,
+    execution: &ExecutionOpts,
+) -> Result<()> {
+    let table = split
+        .dest_table
+        .rsplit_once('.')
+        .map_or(split.dest_table.as_str(), |(_, table)| table);
+    let page_writer = DataFusionPageWriter::new(
+        table,
+        format!("SELECT * FROM {table}"),
+        Arc::clone(&split.writer),
+    )
+    .with_execution(execution.clone());
+    let stream = futures::stream::unfold(records, |mut rx| async move {
+        rx.recv().await.map(|record| (Ok(record), rx))
+    })
+    .boxed();
+    page_writer
+        .write_page_stream(stream, split.write_mode.clone())
+        .await
+}
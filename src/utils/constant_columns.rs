@@ -0,0 +1,142 @@
+//! Fixed columns added to every written row, e.g. a tenant the payload
+//! doesn't carry.
+//!
+//! Values are text and support `${ENV}`, `${FILE:...}`, `${SECRET:...}` and
+//! `{{ current_date() }}`-style templates, resolved once per run. Like the
+//! metadata columns they are added after the module SQL runs:
+//!
+//! ```yaml
+//! sources:
+//!   - name: feed
+//!     url: https://api.example.com/feed
+//!     constant_columns:
+//!       tenant_id: "${TENANT}"
+//!       region: eu
+//! ```
+//!
+//! A name the query already returns is an error; the long form replaces it
+//! instead:
+//!
+//! ```yaml
+//!     constant_columns:
+//!       columns: { tenant_id: acme }
+//!       overwrite: true
+//! ```
+
+use std::collections::BTreeMap;
+use std::sync::Arc;
+
+use datafusion::arrow::datatypes::{DataType, Field, FieldRef, Schema, SchemaRef};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+use crate::errors::{ApitapError, Result};
+use crate::utils::template;
+
+/// `constant_columns` as written in the config: the columns alone, or with
+/// `overwrite`.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(untagged)]
+pub enum ConstantColumnsConfig {
+    Options(ConstantColumnsOptions),
+    Columns(BTreeMap<String, String>),
+}
+
+/// Long form of `constant_columns`.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct ConstantColumnsOptions {
+    pub columns: BTreeMap<String, String>,
+    /// Replace a column the query returns instead of failing the run.
+    #[serde(default)]
+    pub overwrite: bool,
+}
+
+impl ConstantColumnsConfig {
+    /// Resolves the templates of every value for one run.
+    pub fn resolve(&self) -> Result<ConstantColumns> {
+        let (columns, overwrite) = match self {
+            ConstantColumnsConfig::Options(o) => (&o.columns, o.overwrite),
+            ConstantColumnsConfig::Columns(columns) => (columns, false),
+        };
+        let values = columns
+            .iter()
+            .map(|(name, value)| {
+                let value = template::substitute_templates(&template::substitute_env_vars(value)?)?;
+                Ok((name.clone(), value))
+            })
+            .collect::<Result<Vec<_>>>()?;
+        Ok(ConstantColumns { values, overwrite })
+    }
+}
+
+/// Constant column values of one run.
+///
+/// # Example
+///
+/// ```
+/// use apitap::utils::constant_columns::ConstantColumnsConfig;
+/// use serde_json::json;
+///
+/// let config: ConstantColumnsConfig = serde_yaml::from_str("tenant_id: acme").unwrap();
+/// let constants = config.resolve().unwrap();
+///
+/// assert_eq!(
+///     constants.apply(json!({"id": 1})),
+///     json!({"id": 1, "tenant_id": "acme"})
+/// );
+/// ```
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ConstantColumns {
+    values: Vec<(String, String)>,
+    overwrite: bool,
+}
+
+impl ConstantColumns {
+    /// True when no column is configured.
+    pub fn is_empty(&self) -> bool {
+        self.values.is_empty()
+    }
+
+    /// `schema` with the constant columns appended as text.
+    ///
+    /// # Errors
+    ///
+    /// Returns a `ConfigError` when `schema` already has a column of the same
+    /// name and `overwrite` is not set.
+    pub fn schema(&self, schema: &Schema) -> Result<SchemaRef> {
+        let taken = |name: &str| schema.fields().iter().any(|f| f.name() == name);
+        if !self.overwrite {
+            if let Some((name, _)) = self.values.iter().find(|(name, _)| taken(name)) {
+                return Err(ApitapError::ConfigError(format!(
+                    "constant column '{name}' is already a column of the query result; rename it or set overwrite: true"
+                )));
+            }
+        }
+        let mut fields: Vec<FieldRef> = schema
+            .fields()
+            .iter()
+            .filter(|f| !self.values.iter().any(|(name, _)| name == f.name()))
+            .cloned()
+            .collect();
+        fields.extend(
+            self.values
+                .iter()
+                .map(|(name, _)| Arc::new(Field::new(name, DataType::Utf8, false))),
+        );
+        Ok(Arc::new(Schema::new_with_metadata(
+            fields,
+            schema.metadata().clone(),
+        )))
+    }
+
+    /// Adds the values to one row. Non-object rows are left alone.
+    pub fn apply(&self, mut row: Value) -> Value {
+        if let Value::Object(obj) = &mut row {
+            for (name, value) in &self.values {
+                obj.insert(name.clone(), Value::String(value.clone()));
+            }
+        }
+        row
+    }
+}
@@ -0,0 +1,57 @@
+// src/utils/csv.rs
+
+//! CSV/TSV → JSON rows for `format: csv` and `format: tsv` sources.
+//!
+//! Every record becomes an object keyed by the header row, or by
+//! `column_1`, `column_2`, ... when the body has no header. Fields stay strings
+//! (empty fields become `null`); cast them in the module SQL.
+
+use serde_json::{Map, Value};
+
+use crate::errors::Result;
+
+/// Parses a delimited body into a JSON array of row objects.
+///
+/// # Example
+///
+/// ```
+/// use apitap::utils::csv::csv_to_json;
+/// use serde_json::json;
+///
+/// let rows = csv_to_json(b"id,name\n1,Ada\n2,\n", b',', true).unwrap();
+/// assert_eq!(rows, json!([{"id": "1", "name": "Ada"}, {"id": "2", "name": null}]));
+/// ```
+pub fn csv_to_json(body: &[u8], delimiter: u8, has_header: bool) -> Result<Value> {
+    let mut reader = ::csv::ReaderBuilder::new()
+        .delimiter(delimiter)
+        .has_headers(has_header)
+        .flexible(true)
+        .from_reader(body);
+
+    let headers: Vec<String> = if has_header {
+        reader.headers()?.iter().map(str::to_owned).collect()
+    } else {
+        Vec::new()
+    };
+
+    let mut rows = Vec::new();
+    for record in reader.records() {
+        let record = record?;
+        let mut row = Map::with_capacity(record.len());
+        for (i, field) in record.iter().enumerate() {
+            let key = headers
+                .get(i)
+                .cloned()
+                .unwrap_or_else(|| format!("column_{}", i + 1));
+            let value = if field.is_empty() {
+                Value::Null
+            } else {
+                Value::String(field.to_owned())
+            };
+            row.insert(key, value);
+        }
+        rows.push(Value::Object(row));
+    }
+
+    Ok(Value::Array(rows))
+}
@@ -2,38 +2,54 @@
 
 use async_trait::async_trait;
 use datafusion::error::DataFusionError::ArrowError as DatafusionArrowError;
-use datafusion::execution::runtime_env::RuntimeEnvBuilder;
+use datafusion::execution::disk_manager::DiskManagerConfig;
+use datafusion::execution::runtime_env::{RuntimeEnv, RuntimeEnvBuilder};
+use datafusion::execution::SessionStateBuilder;
 use datafusion::{
-    arrow::{datatypes::FieldRef, error::ArrowError, record_batch::RecordBatch},
+    arrow::{
+        datatypes::{FieldRef, SchemaRef},
+        error::ArrowError,
+        record_batch::RecordBatch,
+    },
     dataframe::DataFrame,
     execution::{context::SessionConfig, memory_pool::GreedyMemoryPool},
+    logical_expr::ScalarUDF,
     prelude::*,
 };
 use futures::{stream, Stream, StreamExt};
 use serde_arrow::schema::{SchemaLike, TracingOptions};
-use std::{pin::Pin, sync::Arc};
+use std::collections::BTreeMap;
+use std::path::PathBuf;
+use std::{pin::Pin, sync::Arc, sync::Mutex};
 use tokio::sync::OnceCell;
 use tracing::error;
 
 use crate::errors::{ApitapError, Result};
+use crate::utils::dictionary::decode_batch;
+use crate::utils::execution::{ByteSize, ExecutionOpts, JsonStreamFactory};
+use crate::utils::schema::{infer_schema_from_values, STREAM_SAMPLE_SIZE};
+use crate::utils::table_provider::JsonStreamTableProvider;
+use crate::utils::udf::register_builtin_udfs;
 
 // =========================== Shared SessionContext ========================== //
 
 static SHARED_CTX: OnceCell<Arc<SessionContext>> = OnceCell::const_new();
 
+/// Memory limit, spill flag, and spill directory of a runtime.
+type RuntimeKey = (ByteSize, bool, Option<PathBuf>);
+
+/// Runtimes for non-default memory settings, shared by every query using the
+/// same settings so they draw from one memory pool.
+static RUNTIMES: Mutex<BTreeMap<RuntimeKey, Arc<RuntimeEnv>>> = Mutex::new(BTreeMap::new());
+
 /// Stream of JSON rows (`Result<Value>`) boxed + pinned for dynamic dispatch.
 pub type JsonStreamType = Pin<Box<dyn Stream<Item = Result<serde_json::Value>> + Send + 'static>>;
 
 pub async fn get_shared_context() -> Arc<SessionContext> {
     SHARED_CTX
         .get_or_init(|| async {
-            const MEM_LIMIT: usize = 256 * 1024 * 1024;
-            let setup_runtime_env = RuntimeEnvBuilder::new()
-                .with_memory_pool(Arc::new(GreedyMemoryPool::new(MEM_LIMIT)))
-                .build();
-
-            let runtime_env = match setup_runtime_env {
-                Ok(rt) => Arc::new(rt),
+            let runtime_env = match build_runtime(&ExecutionOpts::default()) {
+                Ok(rt) => rt,
                 Err(e) => {
                     error!(error = %e, "failed to build DataFusion RuntimeEnv; falling back to default");
                     Arc::new(RuntimeEnvBuilder::new().build().unwrap_or_default())
@@ -44,11 +60,87 @@ pub async fn get_shared_context() -> Arc<SessionContext> {
                 .with_target_partitions(1)
                 .with_batch_size(2048);
 
-            Arc::new(SessionContext::new_with_config_rt(session_config, runtime_env))
+            let ctx = SessionContext::new_with_config_rt(session_config, runtime_env);
+            register_builtin_udfs(&ctx);
+            Arc::new(ctx)
         })
         .await
         .clone()
 }
+
+/// Memory pool and spill setup for `opts`.
+fn build_runtime(opts: &ExecutionOpts) -> Result<Arc<RuntimeEnv>> {
+    let limit = usize::try_from(opts.memory_limit.0).unwrap_or(usize::MAX);
+    let disk = match (&opts.spill_dir, opts.spill) {
+        (_, false) => DiskManagerConfig::Disabled,
+        (Some(dir), true) => DiskManagerConfig::NewSpecified(vec![dir.clone()]),
+        (None, true) => DiskManagerConfig::NewOs,
+    };
+    Ok(Arc::new(
+        RuntimeEnvBuilder::new()
+            .with_memory_pool(Arc::new(GreedyMemoryPool::new(limit)))
+            .with_disk_manager(disk)
+            .build()?,
+    ))
+}
+
+/// The runtime for the memory settings of `opts`, built on first use.
+fn runtime_for(opts: &ExecutionOpts) -> Result<Arc<RuntimeEnv>> {
+    let key = (opts.memory_limit, opts.spill, opts.spill_dir.clone());
+    let mut runtimes = RUNTIMES.lock().unwrap_or_else(|e| e.into_inner());
+    if let Some(runtime) = runtimes.get(&key) {
+        return Ok(Arc::clone(runtime));
+    }
+    let runtime = build_runtime(opts)?;
+    runtimes.insert(key, Arc::clone(&runtime));
+    Ok(runtime)
+}
+
+/// Context for running module SQL with `opts`.
+///
+/// The shared context when `opts` keeps the default single partition and
+/// memory settings; otherwise a context with the same tables and functions
+/// (registrations on either are seen by both) whose queries use
+/// `opts.target_partitions` and a runtime with `opts.memory_limit` and
+/// spill settings.
+///
+/// # Errors
+///
+/// Returns an error if `opts.spill_dir` can't be used for spill files.
+pub async fn session_context(opts: &ExecutionOpts) -> Result<Arc<SessionContext>> {
+    let shared = get_shared_context().await;
+    let defaults = ExecutionOpts::default();
+    let default_memory = opts.memory_limit == defaults.memory_limit
+        && opts.spill == defaults.spill
+        && opts.spill_dir == defaults.spill_dir;
+    if opts.target_partitions <= 1 && default_memory {
+        return Ok(shared);
+    }
+    let mut config = shared
+        .copied_config()
+        .with_target_partitions(opts.target_partitions.max(1));
+    if !default_memory {
+        // Each sort holds this much back to merge its spill files; keep it
+        // within small limits so spilling sorts can start at all
+        let execution = &mut config.options_mut().execution;
+        let quarter = usize::try_from(opts.memory_limit.0 / 4).unwrap_or(usize::MAX);
+        execution.sort_spill_reservation_bytes =
+            execution.sort_spill_reservation_bytes.min(quarter);
+    }
+    let mut state = SessionStateBuilder::new_from_existing(shared.state()).with_config(config);
+    if !default_memory {
+        state = state.with_runtime_env(runtime_for(opts)?);
+    }
+    Ok(Arc::new(SessionContext::new_with_state(state.build())))
+}
+
+/// Registers a scalar function on the shared context so `dest_table` SQL can call it.
+///
+/// Call this before pipelines start; a function registered under an existing
+/// name replaces it. See [`crate::utils::udf`] for the functions shipped by default.
+pub async fn register_udf(udf: ScalarUDF) {
+    get_shared_context().await.register_udf(udf);
+}
 // ========================= RAII for temp table cleanup ====================== //
 
 pub struct SqlDataFrame {
@@ -75,7 +167,110 @@ impl Drop for SqlDataFrame {
 #[async_trait]
 pub trait JsonValueExt {
     async fn to_df(&self) -> Result<DataFrame>;
-    async fn to_sql(&self, table_name: &str, sql: &str) -> Result<SqlDataFrame>;
+    async fn to_sql(&self, table_name: &str, sql: &str) -> Result<SqlDataFrame> {
+        self.to_sql_in(get_shared_context().await, table_name, sql)
+            .await
+    }
+    /// Like [`Self::to_sql`], run in `ctx`.
+    async fn to_sql_in(
+        &self,
+        ctx: Arc<SessionContext>,
+        table_name: &str,
+        sql: &str,
+    ) -> Result<SqlDataFrame>;
+    /// Like [`Self::to_sql_in`], with the table built as `fields` instead of
+    /// inferred (see [`sample_fields`]).
+    async fn to_sql_with_fields(
+        &self,
+        ctx: Arc<SessionContext>,
+        table_name: &str,
+        sql: &str,
+        fields: &[FieldRef],
+    ) -> Result<SqlDataFrame>;
+}
+
+/// Fields [`JsonValueExt::to_sql_in`] infers from `values`.
+pub fn sample_fields(values: &[serde_json::Value]) -> Result<Vec<FieldRef>> {
+    Ok(Vec::<FieldRef>::from_samples(
+        values,
+        TracingOptions::default()
+            .allow_null_fields(true)
+            .coerce_numbers(true),
+    )?)
+}
+
+/// Runs `sql` over `records` registered as `table_name`, the way a module's
+/// SQL runs over a streamed fetch: the schema is inferred from the first
+/// [`STREAM_SAMPLE_SIZE`] records and the rows are scanned through the same
+/// table provider. Nothing is fetched or written, so module SQL can be tested
+/// against sample JSON.
+///
+/// `sql` is the rendered SQL, with the source's table name in place of
+/// `{{ use_source(...) }}`. The query runs in a context of its own with the
+/// built-in functions but no lookup tables.
+///
+/// # Example
+///
+/// ```
+/// use apitap::utils::datafusion_ext::transform_json_rows;
+/// use serde_json::json;
+///
+/// # #[tokio::main(flavor = "current_thread")]
+/// # async fn main() {
+/// let rows = transform_json_rows(
+///     "orders",
+///     "SELECT status, count(*) AS n FROM orders GROUP BY status ORDER BY status",
+///     &[json!({"id": 1, "status": "open"}), json!({"id": 2, "status": "open"}), json!({"id": 3, "status": "paid"})],
+/// )
+/// .await
+/// .unwrap();
+/// assert_eq!(rows, vec![json!({"status": "open", "n": 2}), json!({"status": "paid", "n": 1})]);
+/// # }
+/// ```
+///
+/// # Errors
+///
+/// Returns an error if `records` is empty, a record does not fit the
+/// inferred schema, or the SQL fails to plan or run.
+pub async fn transform_json(
+    table_name: &str,
+    sql: &str,
+    records: &[serde_json::Value],
+) -> Result<Vec<RecordBatch>> {
+    let schema = infer_schema_from_values(&records[..records.len().min(STREAM_SAMPLE_SIZE)])?;
+    let records = Arc::new(records.to_vec());
+    let factory: JsonStreamFactory = Arc::new(move || {
+        let records = Arc::clone(&records);
+        stream::iter((0..records.len()).map(move |i| Ok(records[i].clone()))).boxed()
+    });
+
+    let ctx = SessionContext::new_with_config(SessionConfig::new().with_target_partitions(1));
+    register_builtin_udfs(&ctx);
+    ctx.register_table(
+        table_name,
+        Arc::new(JsonStreamTableProvider::new(factory, schema)),
+    )?;
+    Ok(ctx.sql(sql).await?.collect().await?)
+}
+
+/// [`transform_json`] with the output as JSON rows, as the writers receive
+/// them.
+///
+/// # Errors
+///
+/// Returns the errors of [`transform_json`].
+pub async fn transform_json_rows(
+    table_name: &str,
+    sql: &str,
+    records: &[serde_json::Value],
+) -> Result<Vec<serde_json::Value>> {
+    let mut rows = Vec::new();
+    for batch in transform_json(table_name, sql, records).await? {
+        rows.extend(serde_arrow::from_record_batch::<Vec<serde_json::Value>>(
+            &decode_batch(batch)?,
+        )?);
+    }
+    Ok(rows)
 }
 
 #[async_trait]
@@ -108,9 +303,12 @@ impl JsonValueExt for serde_json::Value {
         Ok(ctx.read_batch(batch)?)
     }
 
-    async fn to_sql(&self, table_name: &str, sql: &str) -> Result<SqlDataFrame> {
-        let ctx = get_shared_context().await;
-
+    async fn to_sql_in(
+        &self,
+        ctx: Arc<SessionContext>,
+        table_name: &str,
+        sql: &str,
+    ) -> Result<SqlDataFrame> {
         let Self::Array(json_array) = self else {
             return Err(ApitapError::Datafusion(DatafusionArrowError(
                 ArrowError::JsonError("Expected JSON array".to_string()),
@@ -124,14 +322,25 @@ impl JsonValueExt for serde_json::Value {
             )));
         }
 
-        let fields: Vec<FieldRef> = Vec::<FieldRef>::from_samples(
-            json_array,
-            TracingOptions::default()
-                .allow_null_fields(true)
-                .coerce_numbers(true),
-        )?;
+        let fields = sample_fields(json_array)?;
+        self.to_sql_with_fields(ctx, table_name, sql, &fields).await
+    }
 
-        let batch: RecordBatch = serde_arrow::to_record_batch(&fields, json_array)?;
+    async fn to_sql_with_fields(
+        &self,
+        ctx: Arc<SessionContext>,
+        table_name: &str,
+        sql: &str,
+        fields: &[FieldRef],
+    ) -> Result<SqlDataFrame> {
+        let Self::Array(json_array) = self else {
+            return Err(ApitapError::Datafusion(DatafusionArrowError(
+                ArrowError::JsonError("Expected JSON array".to_string()),
+                None,
+            )));
+        };
+
+        let batch: RecordBatch = serde_arrow::to_record_batch(fields, json_array)?;
 
         // Best-effort cleanup of any existing table with the same name.
         let _ = ctx.deregister_table(table_name);
@@ -173,7 +382,7 @@ impl DataFrameExt for DataFrame {
 
         let mut out = Vec::<T>::new();
         while let Some(item) = rb_stream.next().await {
-            let batch = item.map_err(ApitapError::Datafusion)?;
+            let batch = item?;
             let vals: Vec<serde_json::Value> = serde_arrow::from_record_batch(&batch)?;
             let chunk: Vec<T> = serde_json::from_value(serde_json::Value::Array(vals))?;
             out.extend(chunk);
@@ -232,6 +441,8 @@ pub struct QueryResult {
 pub struct QueryResultStream {
     pub table_name: String,
     pub data: JsonStreamType,
+    /// Arrow schema of the rows in `data`, when the producer knows it.
+    pub schema: Option<SchemaRef>,
 }
 
 /// Query execution error
@@ -239,4 +450,6 @@ pub struct QueryResultStream {
 pub struct QueryError {
     pub table_name: String,
     pub error: String,
+    /// The row that failed, when the error is about a single row.
+    pub record: Option<serde_json::Value>,
 }
@@ -0,0 +1,180 @@
+//! Dictionary encoding of low-cardinality string columns.
+//!
+//! Columns such as `status` or `country` repeat a handful of values across
+//! millions of rows. With `dictionary` set on a source, those string columns
+//! are built as Arrow `Dictionary(Int32, Utf8)` arrays, which store each
+//! distinct value once per batch, so the module SQL runs over much smaller
+//! batches. Rows reach the writer decoded, as plain text.
+//!
+//! ```yaml
+//! sources:
+//!   - name: orders
+//!     url: https://api.example.com/orders
+//!     dictionary:
+//!       columns: [status, country]   # always encoded
+//!       auto: true                   # plus any low-cardinality string column
+//!       max_distinct: 100            # detection threshold (default)
+//! ```
+//!
+//! With `auto`, a string column is encoded when the records its schema is
+//! inferred from (a whole page, or the first 100 records of a stream) hold
+//! at most `max_distinct` distinct values and each value appears at least
+//! twice on average.
+
+use std::collections::HashSet;
+use std::sync::Arc;
+
+use datafusion::arrow::array::RecordBatch;
+use datafusion::arrow::compute::cast;
+use datafusion::arrow::datatypes::{DataType, Field, FieldRef, Schema, SchemaRef};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+use crate::errors::Result;
+
+/// Distinct values an auto-detected column may have in the sample.
+pub const DEFAULT_MAX_DISTINCT: usize = 100;
+
+/// A source's `dictionary` setting.
+///
+/// # Example
+///
+/// ```
+/// use apitap::utils::dictionary::DictionaryEncoding;
+/// use datafusion::arrow::datatypes::{DataType, Field, FieldRef};
+/// use serde_json::json;
+/// use std::sync::Arc;
+///
+/// let dictionary = DictionaryEncoding { auto: true, ..Default::default() };
+/// let samples: Vec<_> = (0..10)
+///     .map(|i| json!({"id": format!("o-{i}"), "status": if i % 2 == 0 { "open" } else { "paid" }}))
+///     .collect();
+/// let fields: Vec<FieldRef> = vec![
+///     Arc::new(Field::new("id", DataType::Utf8, true)),
+///     Arc::new(Field::new("status", DataType::Utf8, true)),
+/// ];
+///
+/// let encoded = dictionary.encode(&fields, &samples);
+/// assert_eq!(encoded[0].data_type(), &DataType::Utf8);
+/// assert_eq!(
+///     encoded[1].data_type(),
+///     &DataType::Dictionary(Box::new(DataType::Int32), Box::new(DataType::Utf8))
+/// );
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct DictionaryEncoding {
+    /// String columns that are always encoded.
+    #[serde(default)]
+    pub columns: Vec<String>,
+    /// Also encode string columns the sample shows to be low-cardinality.
+    #[serde(default)]
+    pub auto: bool,
+    /// Most distinct values an auto-detected column may have in the sample.
+    #[serde(default = "default_max_distinct")]
+    pub max_distinct: usize,
+}
+
+fn default_max_distinct() -> usize {
+    DEFAULT_MAX_DISTINCT
+}
+
+impl Default for DictionaryEncoding {
+    fn default() -> Self {
+        Self {
+            columns: Vec::new(),
+            auto: false,
+            max_distinct: DEFAULT_MAX_DISTINCT,
+        }
+    }
+}
+
+impl DictionaryEncoding {
+    /// `fields` with the string columns to encode turned into
+    /// `Dictionary(Int32, Utf8)`; `samples` are the records they were
+    /// inferred from.
+    pub fn encode(&self, fields: &[FieldRef], samples: &[Value]) -> Vec<FieldRef> {
+        fields
+            .iter()
+            .map(|field| {
+                let encode = matches!(field.data_type(), DataType::Utf8 | DataType::LargeUtf8)
+                    && (self.columns.iter().any(|c| c == field.name())
+                        || (self.auto && self.is_low_cardinality(field.name(), samples)));
+                if encode {
+                    let data_type =
+                        DataType::Dictionary(Box::new(DataType::Int32), Box::new(DataType::Utf8));
+                    Arc::new(field.as_ref().clone().with_data_type(data_type))
+                } else {
+                    Arc::clone(field)
+                }
+            })
+            .collect()
+    }
+
+    /// Whether `column` holds at most `max_distinct` distinct strings in
+    /// `samples`, each appearing at least twice on average.
+    fn is_low_cardinality(&self, column: &str, samples: &[Value]) -> bool {
+        let mut distinct = HashSet::new();
+        let mut count = 0;
+        for value in samples.iter().filter_map(|record| record.get(column)) {
+            let Value::String(s) = value else {
+                continue;
+            };
+            count += 1;
+            distinct.insert(s.as_str());
+            if distinct.len() > self.max_distinct {
+                return false;
+            }
+        }
+        !distinct.is_empty() && distinct.len() * 2 <= count
+    }
+}
+
+/// `schema` with dictionary columns replaced by their value type, as rows
+/// reach the writer.
+///
+/// ```
+/// use apitap::utils::dictionary::decoded_schema;
+/// use datafusion::arrow::datatypes::{DataType, Field, Schema};
+///
+/// let dict = DataType::Dictionary(Box::new(DataType::Int32), Box::new(DataType::Utf8));
+/// let schema = Schema::new(vec![Field::new("status", dict, true)]);
+/// assert_eq!(decoded_schema(&schema).field(0).data_type(), &DataType::Utf8);
+/// ```
+pub fn decoded_schema(schema: &Schema) -> SchemaRef {
+    let fields: Vec<Field> = schema
+        .fields()
+        .iter()
+        .map(|field| match field.data_type() {
+            DataType::Dictionary(_, value) => field
+                .as_ref()
+                .clone()
+                .with_data_type(value.as_ref().clone()),
+            _ => field.as_ref().clone(),
+        })
+        .collect();
+    Arc::new(Schema::new_with_metadata(fields, schema.metadata().clone()))
+}
+
+/// `batch` with dictionary columns cast back to their value type.
+///
+/// # Errors
+///
+/// Returns an error if a column cannot be cast.
+pub fn decode_batch(batch: RecordBatch) -> Result<RecordBatch> {
+    if !batch
+        .schema()
+        .fields()
+        .iter()
+        .any(|f| matches!(f.data_type(), DataType::Dictionary(_, _)))
+    {
+        return Ok(batch);
+    }
+    let schema = decoded_schema(&batch.schema());
+    let columns = batch
+        .columns()
+        .iter()
+        .zip(schema.fields())
+        .map(|(column, field)| cast(column, field.data_type()))
+        .collect::<std::result::Result<Vec<_>, _>>()?;
+    Ok(RecordBatch::try_new(schema, columns)?)
+}
@@ -0,0 +1,189 @@
+//! Detects objects that repeat a key in JSON responses.
+//!
+//! `serde_json` keeps the last value of a repeated key and drops the others
+//! without a word. Response bodies are parsed with a deserializer that
+//! notices repeats and, per the source's `duplicate_keys` setting, keeps the
+//! last value with a warning (the default), fails the page, or collects
+//! every value into an array:
+//!
+//! ```yaml
+//! sources:
+//!   - name: legacy
+//!     url: https://legacy.example.com/export
+//!     duplicate_keys: array   # last | error | array
+//! ```
+
+use std::cell::RefCell;
+use std::collections::BTreeSet;
+use std::fmt;
+
+use serde::de::{self, DeserializeSeed, MapAccess, SeqAccess, Visitor};
+use serde::{Deserialize, Serialize};
+use serde_json::{Map, Number, Value};
+use tracing::warn;
+
+use crate::errors::Result;
+
+/// What happens to a key that appears more than once in an object.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum DuplicateKeys {
+    /// Keep the last value and log a warning naming the keys.
+    #[default]
+    Last,
+    /// Fail the page.
+    Error,
+    /// Collect the values, in order, into an array.
+    Array,
+}
+
+impl DuplicateKeys {
+    /// Parses a JSON document, handling repeated keys as configured.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`crate::errors::ApitapError::SerdeJson`] if the body is not
+    /// JSON, or with [`DuplicateKeys::Error`], if an object repeats a key.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use apitap::utils::duplicate_keys::DuplicateKeys;
+    /// use serde_json::json;
+    ///
+    /// let body = br#"{"id": 1, "tag": "a", "tag": "b", "tag": "c"}"#;
+    ///
+    /// assert_eq!(DuplicateKeys::Last.parse(body).unwrap(), json!({"id": 1, "tag": "c"}));
+    /// assert_eq!(
+    ///     DuplicateKeys::Array.parse(body).unwrap(),
+    ///     json!({"id": 1, "tag": ["a", "b", "c"]})
+    /// );
+    /// assert!(DuplicateKeys::Error
+    ///     .parse(body)
+    ///     .unwrap_err()
+    ///     .to_string()
+    ///     .contains("duplicate key 'tag'"));
+    /// ```
+    pub fn parse(self, body: &[u8]) -> Result<Value> {
+        let repeated = RefCell::new(BTreeSet::new());
+        let mut de = serde_json::Deserializer::from_slice(body);
+        let value = ValueSeed {
+            policy: self,
+            repeated: &repeated,
+        }
+        .deserialize(&mut de)?;
+        de.end()?;
+
+        let repeated = repeated.into_inner();
+        if self == DuplicateKeys::Last && !repeated.is_empty() {
+            let keys: Vec<String> = repeated.into_iter().collect();
+            warn!(
+                keys = %keys.join(", "),
+                "⚠️ response has duplicate JSON keys; kept the last value of each (set duplicate_keys to error or array)"
+            );
+        }
+        Ok(value)
+    }
+}
+
+/// Builds a [`Value`] like `serde_json` does, except for repeated keys.
+#[derive(Clone, Copy)]
+struct ValueSeed<'a> {
+    policy: DuplicateKeys,
+    /// Keys seen more than once, for the warning.
+    repeated: &'a RefCell<BTreeSet<String>>,
+}
+
+impl<'de> DeserializeSeed<'de> for ValueSeed<'_> {
+    type Value = Value;
+
+    fn deserialize<D: de::Deserializer<'de>>(
+        self,
+        deserializer: D,
+    ) -> std::result::Result<Value, D::Error> {
+        deserializer.deserialize_any(self)
+    }
+}
+
+impl<'de> Visitor<'de> for ValueSeed<'_> {
+    type Value = Value;
+
+    fn expecting(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("any JSON value")
+    }
+
+    fn visit_bool<E>(self, v: bool) -> std::result::Result<Value, E> {
+        Ok(Value::Bool(v))
+    }
+
+    fn visit_i64<E>(self, v: i64) -> std::result::Result<Value, E> {
+        Ok(Value::Number(v.into()))
+    }
+
+    fn visit_u64<E>(self, v: u64) -> std::result::Result<Value, E> {
+        Ok(Value::Number(v.into()))
+    }
+
+    fn visit_f64<E>(self, v: f64) -> std::result::Result<Value, E> {
+        Ok(Number::from_f64(v).map_or(Value::Null, Value::Number))
+    }
+
+    fn visit_str<E>(self, v: &str) -> std::result::Result<Value, E> {
+        Ok(Value::String(v.to_string()))
+    }
+
+    fn visit_string<E>(self, v: String) -> std::result::Result<Value, E> {
+        Ok(Value::String(v))
+    }
+
+    fn visit_unit<E>(self) -> std::result::Result<Value, E> {
+        Ok(Value::Null)
+    }
+
+    fn visit_none<E>(self) -> std::result::Result<Value, E> {
+        Ok(Value::Null)
+    }
+
+    fn visit_some<D: de::Deserializer<'de>>(
+        self,
+        deserializer: D,
+    ) -> std::result::Result<Value, D::Error> {
+        self.deserialize(deserializer)
+    }
+
+    fn visit_seq<A: SeqAccess<'de>>(self, mut seq: A) -> std::result::Result<Value, A::Error> {
+        let mut items = Vec::with_capacity(seq.size_hint().unwrap_or(0));
+        while let Some(item) = seq.next_element_seed(self)? {
+            items.push(item);
+        }
+        Ok(Value::Array(items))
+    }
+
+    fn visit_map<A: MapAccess<'de>>(self, mut map: A) -> std::result::Result<Value, A::Error> {
+        let mut out = Map::new();
+        // Keys whose value is already the collected array
+        let mut collected = BTreeSet::new();
+        while let Some(key) = map.next_key::<String>()? {
+            let value = map.next_value_seed(self)?;
+            let Some(previous) = out.get_mut(&key) else {
+                out.insert(key, value);
+                continue;
+            };
+            match self.policy {
+                DuplicateKeys::Last => *previous = value,
+                DuplicateKeys::Error => {
+                    return Err(de::Error::custom(format!(
+                        "duplicate key '{key}' in JSON object (duplicate_keys: error)"
+                    )))
+                }
+                DuplicateKeys::Array => match previous {
+                    Value::Array(items) if collected.contains(&key) => items.push(value),
+                    _ => *previous = Value::Array(vec![previous.take(), value]),
+                },
+            }
+            collected.insert(key.clone());
+            self.repeated.borrow_mut().insert(key);
+        }
+        Ok(Value::Object(out))
+    }
+}
@@ -0,0 +1,123 @@
+//! Unwraps MongoDB extended JSON into plain values before schema inference.
+//!
+//! Exports from Mongo-backed APIs wrap typed values in `$`-objects such as
+//! `{"$oid": "..."}` or `{"$date": {"$numberLong": "..."}}`. Left alone,
+//! every such field lands as a nested object. With `ejson: true` on a source
+//! they are replaced, at any depth, by:
+//!
+//! | Wrapper | Becomes |
+//! |---|---|
+//! | `$oid`, `$symbol`, `$code`, `$uuid` | the string |
+//! | `$date` | an RFC 3339 timestamp string (UTC) |
+//! | `$numberInt`, `$numberLong` | an integer |
+//! | `$numberDouble` | a float; `NaN` and `±Infinity` become null |
+//! | `$numberDecimal` | the decimal as a string, so no precision is lost |
+//! | `$binary` | the base64 payload |
+//! | `$timestamp` | its seconds as an RFC 3339 timestamp string |
+//! | `$regularExpression`, `$regex` | the pattern |
+//! | `$minKey`, `$maxKey`, `$undefined` | null |
+//!
+//! Objects that merely contain `$` keys next to other keys are left alone.
+
+use chrono::{DateTime, SecondsFormat, Utc};
+use serde_json::{Map, Value};
+
+/// Replaces every extended JSON wrapper in `value` with its plain value.
+///
+/// # Example
+///
+/// ```
+/// use apitap::utils::ejson::unwrap_extended;
+/// use serde_json::json;
+///
+/// let record = json!({
+///     "_id": {"$oid": "65a1f0c2e4b0a1b2c3d4e5f6"},
+///     "created": {"$date": {"$numberLong": "1704067200000"}},
+///     "count": {"$numberLong": "42"},
+///     "tags": [{"$numberInt": "1"}],
+/// });
+/// assert_eq!(
+///     unwrap_extended(record),
+///     json!({
+///         "_id": "65a1f0c2e4b0a1b2c3d4e5f6",
+///         "created": "2024-01-01T00:00:00.000Z",
+///         "count": 42,
+///         "tags": [1],
+///     })
+/// );
+/// ```
+pub fn unwrap_extended(value: Value) -> Value {
+    match value {
+        Value::Array(items) => Value::Array(items.into_iter().map(unwrap_extended).collect()),
+        Value::Object(map) => match wrapped(&map) {
+            Some(plain) => plain,
+            None => Value::Object(
+                map.into_iter()
+                    .map(|(k, v)| (k, unwrap_extended(v)))
+                    .collect(),
+            ),
+        },
+        other => other,
+    }
+}
+
+/// The plain value of a wrapper object, or `None` if `map` is not one.
+fn wrapped(map: &Map<String, Value>) -> Option<Value> {
+    // Legacy forms carry a second key
+    if map.len() == 2 {
+        return match (map.get("$binary"), map.get("$type"), map.get("$regex")) {
+            (Some(Value::String(data)), Some(_), _) => Some(Value::String(data.clone())),
+            (_, _, Some(Value::String(pattern))) if map.contains_key("$options") => {
+                Some(Value::String(pattern.clone()))
+            }
+            _ => None,
+        };
+    }
+    let (key, inner) = map.iter().next().filter(|_| map.len() == 1)?;
+    match (key.as_str(), inner) {
+        ("$oid" | "$symbol" | "$code" | "$uuid", Value::String(s)) => {
+            Some(Value::String(s.clone()))
+        }
+        ("$date", inner) => date(inner),
+        ("$numberInt" | "$numberLong", Value::String(s)) => s.parse::<i64>().ok().map(Value::from),
+        ("$numberDouble", Value::String(s)) => s
+            .parse::<f64>()
+            .ok()
+            .map(|f| serde_json::Number::from_f64(f).map_or(Value::Null, Value::Number)),
+        ("$numberDecimal", Value::String(s)) => Some(Value::String(s.clone())),
+        ("$binary", Value::Object(b)) => b.get("base64").cloned(),
+        ("$timestamp", Value::Object(t)) => t
+            .get("t")
+            .and_then(Value::as_i64)
+            .and_then(|secs| timestamp(secs.checked_mul(1000)?)),
+        ("$regularExpression", Value::Object(r)) => r.get("pattern").cloned(),
+        ("$minKey" | "$maxKey" | "$undefined", _) => Some(Value::Null),
+        _ => None,
+    }
+}
+
+/// `$date` in its relaxed (ISO string), canonical (`$numberLong` millis),
+/// or legacy (number millis) form.
+fn date(inner: &Value) -> Option<Value> {
+    match inner {
+        Value::String(s) => Some(
+            DateTime::parse_from_rfc3339(s)
+                .map(|d| {
+                    d.with_timezone(&Utc)
+                        .to_rfc3339_opts(SecondsFormat::Millis, true)
+                })
+                .map_or_else(|_| Value::String(s.clone()), Value::String),
+        ),
+        Value::Number(n) => timestamp(n.as_i64()?),
+        Value::Object(m) => match m.get("$numberLong") {
+            Some(Value::String(ms)) => timestamp(ms.parse().ok()?),
+            _ => None,
+        },
+        _ => None,
+    }
+}
+
+fn timestamp(millis: i64) -> Option<Value> {
+    DateTime::<Utc>::from_timestamp_millis(millis)
+        .map(|d| Value::String(d.to_rfc3339_opts(SecondsFormat::Millis, true)))
+}
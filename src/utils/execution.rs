@@ -1,4 +1,4 @@
-use std::{any::Any, fmt, pin::Pin, sync::Arc};
+use std::{any::Any, fmt, path::PathBuf, pin::Pin, str::FromStr, sync::Arc};
 
 use datafusion::{
     arrow::datatypes::SchemaRef,
@@ -13,6 +13,7 @@ use datafusion::{
 use futures::stream::BoxStream;
 #[allow(unused_imports)]
 use futures::{Stream, StreamExt, TryFutureExt};
+use serde::{Deserialize, Serialize};
 use serde_json::Value;
 
 use crate::{
@@ -20,6 +21,152 @@ use crate::{
     utils::streaming::{self, StreamConfig},
 };
 
+/// How module SQL is executed, from the `execution` config block.
+///
+/// Larger batches mean fewer, bigger Arrow conversions at the cost of memory
+/// per in-flight batch. `target_partitions` above 1 lets DataFusion spread
+/// joins, aggregations, and sorts over that many threads: the API stream is
+/// still read as one partition and then repartitioned, so this helps
+/// CPU-heavy SQL, not fetching. Each partition buffers its own batches, and
+/// without an `ORDER BY` the output order is no longer the API order.
+///
+/// `memory_limit` caps what sorts, joins, and aggregations may hold across
+/// every module sharing these settings. With `spill` on (the default) they
+/// write to `spill_dir`, or the system temp directory, once it is reached;
+/// with `spill: false` the query fails with
+/// [`ApitapError::MemoryLimit`](crate::errors::ApitapError::MemoryLimit).
+///
+/// ```yaml
+/// execution:
+///   batch_size: 1024
+///   target_partitions: 4
+///   memory_limit: 2GB
+///   spill_dir: /scratch/apitap
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ExecutionOpts {
+    /// Records converted into each Arrow batch fed to the SQL.
+    pub batch_size: usize,
+    /// Records buffered between the fetch and the SQL before fetching
+    /// waits (backpressure).
+    pub max_buffered_items: usize,
+    /// Threads DataFusion may use for one query.
+    pub target_partitions: usize,
+    /// Memory the SQL operators may reserve.
+    pub memory_limit: ByteSize,
+    /// Let operators spill to disk instead of failing at `memory_limit`.
+    pub spill: bool,
+    /// Where spill files go; the system temp directory when unset.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub spill_dir: Option<PathBuf>,
+}
+
+impl Default for ExecutionOpts {
+    fn default() -> Self {
+        Self {
+            batch_size: StreamConfig::default().batch_size,
+            max_buffered_items: 8192,
+            target_partitions: 1,
+            memory_limit: ByteSize(256 * 1024 * 1024),
+            spill: true,
+            spill_dir: None,
+        }
+    }
+}
+
+/// A size in bytes, written as a number or with a binary `KB`, `MB`, or
+/// `GB` suffix.
+///
+/// ```
+/// use apitap::utils::execution::ByteSize;
+///
+/// assert_eq!("512MB".parse::<ByteSize>().unwrap(), ByteSize(512 * 1024 * 1024));
+/// assert_eq!("2 gb".parse::<ByteSize>().unwrap(), ByteSize(2 << 30));
+/// assert_eq!("4096".parse::<ByteSize>().unwrap(), ByteSize(4096));
+/// assert!("lots".parse::<ByteSize>().is_err());
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
+#[serde(try_from = "ByteSizeRepr", into = "u64")]
+pub struct ByteSize(pub u64);
+
+impl FromStr for ByteSize {
+    type Err = errors::ApitapError;
+
+    fn from_str(s: &str) -> errors::Result<Self> {
+        let text = s.trim().to_ascii_uppercase();
+        let (digits, unit) = match text.find(|c: char| !c.is_ascii_digit()) {
+            Some(at) => text.split_at(at),
+            None => (text.as_str(), ""),
+        };
+        let shift = match unit.trim() {
+            "" | "B" => 0,
+            "K" | "KB" | "KIB" => 10,
+            "M" | "MB" | "MIB" => 20,
+            "G" | "GB" | "GIB" => 30,
+            _ => u32::MAX,
+        };
+        digits
+            .parse::<u64>()
+            .ok()
+            .filter(|_| shift != u32::MAX)
+            .and_then(|n| n.checked_mul(1 << shift))
+            .map(ByteSize)
+            .ok_or_else(|| {
+                errors::ApitapError::ConfigError(format!(
+                    "unknown size '{s}' (expected bytes or a number with KB, MB, or GB)"
+                ))
+            })
+    }
+}
+
+impl From<ByteSize> for u64 {
+    fn from(size: ByteSize) -> u64 {
+        size.0
+    }
+}
+
+impl fmt::Display for ByteSize {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        const UNITS: [(u32, &str); 3] = [(30, "GB"), (20, "MB"), (10, "KB")];
+        for (shift, unit) in UNITS {
+            if self.0 >= 1 << shift && self.0 % (1 << shift) == 0 {
+                return write!(f, "{}{unit}", self.0 >> shift);
+            }
+        }
+        write!(f, "{} bytes", self.0)
+    }
+}
+
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum ByteSizeRepr {
+    Bytes(u64),
+    Text(String),
+}
+
+impl TryFrom<ByteSizeRepr> for ByteSize {
+    type Error = errors::ApitapError;
+
+    fn try_from(repr: ByteSizeRepr) -> errors::Result<Self> {
+        match repr {
+            ByteSizeRepr::Bytes(n) => Ok(ByteSize(n)),
+            ByteSizeRepr::Text(s) => s.parse(),
+        }
+    }
+}
+
+impl ExecutionOpts {
+    /// Streaming settings for the scan of the API records.
+    pub fn stream_config(&self) -> StreamConfig {
+        StreamConfig {
+            batch_size: self.batch_size,
+            max_buffered_items: self.max_buffered_items,
+            true_streaming: true,
+        }
+    }
+}
+
 /// Type alias for the factory function
 pub type JsonStreamFactory =
     Arc<dyn Fn() -> BoxStream<'static, errors::Result<Value>> + Send + Sync>;
@@ -30,6 +177,7 @@ pub struct Exec {
     stream_factory: JsonStreamFactory,
     pub projected_schema: SchemaRef,
     pub cache: PlanProperties,
+    stream_config: StreamConfig,
 }
 
 impl std::fmt::Debug for Exec {
@@ -60,9 +208,16 @@ impl Exec {
             stream_factory: Arc::new(stream_factory),
             projected_schema,
             cache,
+            stream_config: StreamConfig::default(),
         })
     }
 
+    /// Batching used when converting the records to Arrow.
+    pub fn with_stream_config(mut self, config: StreamConfig) -> Self {
+        self.stream_config = config;
+        self
+    }
+
     fn compute_properties(schema: SchemaRef) -> PlanProperties {
         let eq_properties = EquivalenceProperties::new(schema);
 
@@ -105,6 +260,7 @@ impl ExecutionPlan for Exec {
         let schema = self.projected_schema.clone();
         let stream_factory = self.stream_factory.clone();
         let schema_c = schema.clone();
+        let stream_config = self.stream_config.clone();
 
         // ✅ TRUE STREAMING: No intermediate buffering
         let record_batch_stream = async_stream::try_stream! {
@@ -114,11 +270,7 @@ impl ExecutionPlan for Exec {
             let batch_stream = streaming::stream_json_to_batches(
                 json_stream,
                 schema_c.clone(),
-                StreamConfig {
-                    batch_size: 256,
-                    max_buffered_items: 512,
-                    true_streaming: true,
-                },
+                stream_config,
             )
             .await
             .map_err(|e| datafusion::error::DataFusionError::External(e.into()))?;
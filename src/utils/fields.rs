@@ -0,0 +1,99 @@
+//! Per-record field renames and drops applied before schema inference.
+//!
+//! Names are either top-level keys (`usr_nm`) or JSON pointers
+//! (`/meta/created`). A pointer in `rename` lifts the nested value to a
+//! top-level column; a pointer in `drop` removes just that nested key.
+
+use std::collections::BTreeMap;
+
+use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value};
+
+/// A source's `rename` and `drop` settings.
+///
+/// Drops run first, so both lists refer to the field names the API sends.
+///
+/// # Example
+///
+/// ```
+/// use apitap::utils::fields::FieldMapping;
+/// use serde_json::json;
+///
+/// let mapping = FieldMapping {
+///     rename: [
+///         ("usr_nm".to_string(), "user_name".to_string()),
+///         ("/meta/created".to_string(), "created_at".to_string()),
+///     ]
+///     .into(),
+///     drop: vec!["internal".to_string()],
+/// };
+///
+/// let out = mapping.apply(json!({
+///     "usr_nm": "ada",
+///     "internal": true,
+///     "meta": {"created": "2024-01-01", "v": 2}
+/// }));
+/// assert_eq!(
+///     out,
+///     json!({"user_name": "ada", "created_at": "2024-01-01", "meta": {"v": 2}})
+/// );
+/// ```
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct FieldMapping {
+    /// Old name or pointer -> new top-level name.
+    #[serde(default)]
+    pub rename: BTreeMap<String, String>,
+    /// Names or pointers removed from every record.
+    #[serde(default)]
+    pub drop: Vec<String>,
+}
+
+impl FieldMapping {
+    /// True when applying the mapping would leave records unchanged.
+    pub fn is_empty(&self) -> bool {
+        self.rename.is_empty() && self.drop.is_empty()
+    }
+
+    /// Applies drops, then renames, to one record. Non-object records and
+    /// names missing from the record are left alone.
+    pub fn apply(&self, mut record: Value) -> Value {
+        let Value::Object(obj) = &mut record else {
+            return record;
+        };
+
+        for name in &self.drop {
+            take(obj, name);
+        }
+
+        // Take every value first so swaps (a -> b, b -> a) don't clobber each other
+        let moved: Vec<(&String, Value)> = self
+            .rename
+            .iter()
+            .filter_map(|(from, to)| take(obj, from).map(|v| (to, v)))
+            .collect();
+        for (to, value) in moved {
+            obj.insert(to.clone(), value);
+        }
+
+        record
+    }
+}
+
+/// Removes and returns the value at `name` (a key or a JSON pointer).
+fn take(obj: &mut Map<String, Value>, name: &str) -> Option<Value> {
+    let Some(pointer) = name.strip_prefix('/') else {
+        return obj.remove(name);
+    };
+
+    let mut tokens: Vec<String> = pointer
+        .split('/')
+        .map(|t| t.replace("~1", "/").replace("~0", "~"))
+        .collect();
+    let last = tokens.pop()?;
+
+    let mut parent = obj;
+    for token in &tokens {
+        parent = parent.get_mut(token)?.as_object_mut()?;
+    }
+    parent.remove(&last)
+}
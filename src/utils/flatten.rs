@@ -0,0 +1,137 @@
+//! Flattens nested objects into top-level columns before schema inference.
+//!
+//! BI tools often cannot query struct columns. With `flatten: true` on a
+//! source, `{"a": {"b": 1}}` lands as a column `a_b`. The block form sets the
+//! separator, how deep to go, and what happens to arrays:
+//!
+//! ```yaml
+//! sources:
+//!   - name: users
+//!     url: https://api.example.com/users
+//!     flatten:
+//!       separator: "__"   # default "_"
+//!       max_depth: 2      # deeper objects land as JSON text; default unlimited
+//!       arrays: index     # tags_0, tags_1, ...; default `json` (JSON text)
+//! ```
+//!
+//! Flattening runs after every other record step, so `rename`, `drop`, and
+//! `transform` still refer to the nested names the API sends.
+
+use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value};
+
+/// A source's `flatten` setting: `true`, `false`, or a [`Flatten`] block.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(untagged)]
+pub enum FlattenConfig {
+    Enabled(bool),
+    Options(Flatten),
+}
+
+impl FlattenConfig {
+    /// The options in effect, or `None` when flattening is off.
+    pub fn resolve(&self) -> Option<Flatten> {
+        match self {
+            FlattenConfig::Enabled(true) => Some(Flatten::default()),
+            FlattenConfig::Enabled(false) => None,
+            FlattenConfig::Options(flatten) => Some(flatten.clone()),
+        }
+    }
+}
+
+/// How arrays are landed by [`Flatten`].
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ArrayFlatten {
+    /// The whole array as JSON text in one column.
+    #[default]
+    Json,
+    /// One column per element, suffixed with its index.
+    Index,
+}
+
+/// Options of the `flatten` block.
+///
+/// # Example
+///
+/// ```
+/// use apitap::utils::flatten::{ArrayFlatten, Flatten};
+/// use serde_json::json;
+///
+/// let record = json!({"id": 1, "a": {"b": 2, "c": {"d": 3}}, "tags": ["x", "y"]});
+///
+/// assert_eq!(
+///     Flatten::default().apply(record.clone()),
+///     json!({"id": 1, "a_b": 2, "a_c_d": 3, "tags": "[\"x\",\"y\"]"})
+/// );
+///
+/// let shallow = Flatten {
+///     separator: ".".to_string(),
+///     max_depth: Some(1),
+///     arrays: ArrayFlatten::Index,
+/// };
+/// assert_eq!(
+///     shallow.apply(record),
+///     json!({"id": 1, "a.b": 2, "a.c": "{\"d\":3}", "tags.0": "x", "tags.1": "y"})
+/// );
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Flatten {
+    /// Joins parent and child names.
+    pub separator: String,
+    /// Levels of nesting flattened; anything deeper lands as JSON text.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub max_depth: Option<usize>,
+    pub arrays: ArrayFlatten,
+}
+
+impl Default for Flatten {
+    fn default() -> Self {
+        Self {
+            separator: "_".to_string(),
+            max_depth: None,
+            arrays: ArrayFlatten::default(),
+        }
+    }
+}
+
+impl Flatten {
+    /// Flattens one record. Non-object records are left alone; empty nested
+    /// objects and arrays produce no columns.
+    pub fn apply(&self, record: Value) -> Value {
+        let Value::Object(obj) = record else {
+            return record;
+        };
+        let mut out = Map::new();
+        for (key, value) in obj {
+            self.insert(&mut out, key, value, 0);
+        }
+        Value::Object(out)
+    }
+
+    /// Adds `value` under `name`, flattening it if `depth` allows.
+    fn insert(&self, out: &mut Map<String, Value>, name: String, value: Value, depth: usize) {
+        let flatten = self.max_depth.map_or(true, |max| depth < max);
+        let children: Vec<(String, Value)> = match value {
+            Value::Object(map) if flatten => map.into_iter().collect(),
+            Value::Array(items) if flatten && self.arrays == ArrayFlatten::Index => items
+                .into_iter()
+                .enumerate()
+                .map(|(i, v)| (i.to_string(), v))
+                .collect(),
+            Value::Object(_) | Value::Array(_) => {
+                out.insert(name, Value::String(value.to_string()));
+                return;
+            }
+            other => {
+                out.insert(name, other);
+                return;
+            }
+        };
+        for (key, child) in children {
+            let child_name = format!("{name}{}{key}", self.separator);
+            self.insert(out, child_name, child, depth + 1);
+        }
+    }
+}
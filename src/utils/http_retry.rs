@@ -1,12 +1,19 @@
+use crate::http::signing::{RequestSigner, SigningMiddleware};
 use http::Extensions;
+use rand::Rng;
 use reqwest::{Client, Request, Response};
 use reqwest_middleware::{
     ClientBuilder, ClientWithMiddleware, Middleware, Next, Result as MwResult,
 };
-use reqwest_retry::{policies::ExponentialBackoff, RetryTransientMiddleware};
-use std::time::{Duration, Instant};
+use reqwest_retry::{RetryDecision, RetryPolicy, RetryTransientMiddleware};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Arc;
+use std::time::{Duration, Instant, SystemTime};
 use tracing::warn;
 
+use crate::pipeline::RetryJitter;
+use crate::utils::redact::{redact_text, redact_url};
+
 #[derive(Debug, Default, Clone)]
 struct AttemptCount(pub u32);
 
@@ -32,7 +39,7 @@ impl Middleware for AttemptLogger {
         };
 
         let method = req.method().clone();
-        let url = req.url().clone();
+        let url = redact_url(req.url().as_str());
         let t0 = Instant::now();
         tracing::debug!("→ attempt #{attempt} {method} {url}");
 
@@ -44,19 +51,47 @@ impl Middleware for AttemptLogger {
                 tracing::debug!(
                     "← attempt #{attempt} {} {} in {:?}",
                     resp.status(),
-                    resp.url(),
+                    redact_url(resp.url().as_str()),
                     dt
                 );
             }
             Err(err) => {
                 let dt = t0.elapsed();
-                warn!("⇠ attempt #{attempt} error after {:?}: {err}", dt);
+                warn!(
+                    "⇠ attempt #{attempt} error after {:?}: {}",
+                    dt,
+                    redact_text(&err.to_string())
+                );
             }
         }
         res
     }
 }
 
+/// Request extension that counts every attempt sent for the request, retries
+/// included. Attach it with `RequestBuilder::with_extension`.
+#[derive(Debug, Default, Clone)]
+pub struct AttemptTally(pub Arc<AtomicUsize>);
+
+/// Bumps the request's [`AttemptTally`], if it has one; sits inside the retry
+/// layer so it sees each attempt.
+struct AttemptCounter;
+
+#[async_trait::async_trait]
+impl Middleware for AttemptCounter {
+    async fn handle(
+        &self,
+        req: Request,
+        extensions: &mut Extensions,
+        next: Next<'_>,
+    ) -> MwResult<Response> {
+        if let Some(tally) = extensions.get::<AttemptTally>() {
+            tally.0.fetch_add(1, Ordering::Relaxed);
+        }
+        next.run(req, extensions).await
+    }
+}
+
 struct SummaryLogger;
 
 #[async_trait::async_trait]
@@ -93,6 +128,101 @@ impl Middleware for SummaryLogger {
     }
 }
 
+/// Exponential backoff (`min_delay * 2^n`, capped at `max_delay`) with jitter.
+///
+/// Gives up after `max_retries` retries, or earlier when the next attempt
+/// would start more than `max_elapsed` after the first one.
+///
+/// # Example
+///
+/// ```
+/// use apitap::pipeline::{Retry, RetryJitter};
+/// use apitap::utils::http_retry::JitteredBackoff;
+/// use std::time::Duration;
+///
+/// let backoff = JitteredBackoff::from_config(&Retry {
+///     max_attempts: 5,
+///     min_delay_secs: 1,
+///     max_delay_secs: 30,
+///     jitter: RetryJitter::Equal,
+///     max_elapsed_secs: None,
+/// });
+/// assert_eq!(backoff.base_delay(3), Duration::from_secs(8));
+/// let d = backoff.delay(3);
+/// assert!(d >= Duration::from_secs(4) && d <= Duration::from_secs(8));
+/// ```
+#[derive(Debug, Clone)]
+pub struct JitteredBackoff {
+    pub min_delay: Duration,
+    pub max_delay: Duration,
+    pub max_retries: u32,
+    pub jitter: RetryJitter,
+    pub max_elapsed: Option<Duration>,
+}
+
+impl JitteredBackoff {
+    pub fn from_config(config: &crate::pipeline::Retry) -> Self {
+        Self {
+            min_delay: Duration::from_secs(config.min_delay_secs),
+            max_delay: Duration::from_secs(config.max_delay_secs),
+            max_retries: config.max_attempts,
+            jitter: config.jitter,
+            max_elapsed: config.max_elapsed_secs.map(Duration::from_secs),
+        }
+    }
+
+    /// Delay after `n_past_retries` failed retries, before jitter.
+    pub fn base_delay(&self, n_past_retries: u32) -> Duration {
+        let factor = 2u32.checked_pow(n_past_retries).unwrap_or(u32::MAX);
+        self.min_delay
+            .checked_mul(factor)
+            .unwrap_or(self.max_delay)
+            .min(self.max_delay)
+    }
+
+    /// Jittered delay after `n_past_retries` failed retries.
+    pub fn delay(&self, n_past_retries: u32) -> Duration {
+        let base = self.base_delay(n_past_retries);
+        let mut rng = rand::thread_rng();
+        match self.jitter {
+            RetryJitter::None => base,
+            RetryJitter::Full => base.mul_f64(rng.gen_range(0.0..=1.0)),
+            RetryJitter::Equal => base / 2 + (base / 2).mul_f64(rng.gen_range(0.0..=1.0)),
+        }
+    }
+}
+
+impl RetryPolicy for JitteredBackoff {
+    fn should_retry(&self, request_start_time: SystemTime, n_past_retries: u32) -> RetryDecision {
+        let now = SystemTime::now();
+        let elapsed = now.duration_since(request_start_time).unwrap_or_default();
+        let attempts = n_past_retries + 1;
+
+        if n_past_retries >= self.max_retries {
+            warn!(
+                "giving up after {attempts} attempts ({:?} elapsed): max_attempts reached",
+                elapsed
+            );
+            return RetryDecision::DoNotRetry;
+        }
+
+        let delay = self.delay(n_past_retries);
+        if let Some(max_elapsed) = self.max_elapsed {
+            if elapsed + delay > max_elapsed {
+                warn!(
+                    "giving up after {attempts} attempts ({:?} elapsed): next retry would exceed max_elapsed_secs ({:?})",
+                    elapsed, max_elapsed
+                );
+                return RetryDecision::DoNotRetry;
+            }
+        }
+
+        RetryDecision::Retry {
+            execute_after: now + delay,
+        }
+    }
+}
+
 /// Builds an HTTP client with automatic retry capabilities and logging middleware.
 ///
 /// This function wraps a reqwest Client with retry logic using exponential backoff
@@ -119,6 +249,8 @@ impl Middleware for SummaryLogger {
 ///     max_attempts: 3,
 ///     min_delay_secs: 1,
 ///     max_delay_secs: 10,
+///     jitter: Default::default(),
+///     max_elapsed_secs: Some(120),
 /// };
 ///
 /// let client = build_client_with_retry(base_client, &retry_config);
@@ -128,16 +260,26 @@ pub fn build_client_with_retry(
     reqwest_client: Client,
     config_retray: &crate::pipeline::Retry,
 ) -> ClientWithMiddleware {
-    let policy = ExponentialBackoff::builder()
-        .retry_bounds(
-            Duration::from_secs(config_retray.min_delay_secs),
-            Duration::from_secs(config_retray.max_delay_secs),
-        )
-        .build_with_max_retries(config_retray.max_attempts);
-
-    ClientBuilder::new(reqwest_client)
+    build_client_with_signer(reqwest_client, config_retray, None)
+}
+
+/// Like [`build_client_with_retry`], but runs `signer` on every attempt.
+///
+/// The signer sits inside the retry layer so each retry is signed afresh
+/// (timestamps and nonces stay valid).
+pub fn build_client_with_signer(
+    reqwest_client: Client,
+    config_retray: &crate::pipeline::Retry,
+    signer: Option<Arc<dyn RequestSigner>>,
+) -> ClientWithMiddleware {
+    let policy = JitteredBackoff::from_config(config_retray);
+
+    let mut builder = ClientBuilder::new(reqwest_client)
         .with(AttemptLogger)
         .with(RetryTransientMiddleware::new_with_policy(policy))
-        .with(SummaryLogger)
-        .build()
+        .with(AttemptCounter);
+    if let Some(signer) = signer {
+        builder = builder.with(SigningMiddleware::new(signer));
+    }
+    builder.with(SummaryLogger).build()
 }
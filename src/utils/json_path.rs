@@ -0,0 +1,81 @@
+//! Small path selector for pulling values out of JSON responses.
+//!
+//! Two syntaxes are accepted:
+//! - JSON pointer (`/data/items`), the same form used by `data_path`
+//! - Dotted paths (`data.*.edges.node`), where `*` matches every value of an
+//!   object or every element of an array
+//!
+//! In dotted paths, stepping into an array with a field name applies the
+//! field to each element, so `data.users.edges.node` walks every edge.
+
+use serde_json::Value;
+
+/// Returns every value matched by `path`.
+///
+/// # Example
+///
+/// ```
+/// use apitap::utils::json_path::select;
+/// use serde_json::json;
+///
+/// let v = json!({"data": {"users": {"edges": [{"node": {"id": 1}}, {"node": {"id": 2}}]}}});
+/// let nodes = select(&v, "data.*.edges.node");
+/// assert_eq!(nodes.len(), 2);
+/// assert_eq!(nodes[1]["id"], 2);
+/// ```
+pub fn select<'a>(value: &'a Value, path: &str) -> Vec<&'a Value> {
+    if path.starts_with('/') {
+        return value.pointer(path).into_iter().collect();
+    }
+
+    let mut current: Vec<&Value> = vec![value];
+    for segment in path.split('.').filter(|s| !s.is_empty()) {
+        let mut next = Vec::new();
+        for v in current {
+            step(v, segment, &mut next);
+        }
+        current = next;
+    }
+    current
+}
+
+/// Returns the first value matched by `path`, ignoring JSON nulls.
+///
+/// # Example
+///
+/// ```
+/// use apitap::utils::json_path::select_first;
+/// use serde_json::json;
+///
+/// let v = json!({"data": {"repo": {"pageInfo": {"endCursor": "abc"}}}});
+/// assert_eq!(select_first(&v, "data.*.pageInfo.endCursor"), Some(&json!("abc")));
+/// assert_eq!(select_first(&v, "data.missing"), None);
+/// ```
+pub fn select_first<'a>(value: &'a Value, path: &str) -> Option<&'a Value> {
+    select(value, path).into_iter().find(|v| !v.is_null())
+}
+
+fn step<'a>(value: &'a Value, segment: &str, out: &mut Vec<&'a Value>) {
+    match (value, segment) {
+        (Value::Object(map), "*") => out.extend(map.values()),
+        (Value::Array(items), "*") => out.extend(items.iter()),
+        (Value::Object(map), key) => {
+            if let Some(v) = map.get(key) {
+                out.push(v);
+            }
+        }
+        (Value::Array(items), key) => {
+            // Numeric segments index into the array, anything else maps over it.
+            if let Ok(idx) = key.parse::<usize>() {
+                if let Some(v) = items.get(idx) {
+                    out.push(v);
+                }
+            } else {
+                for item in items {
+                    step(item, key, out);
+                }
+            }
+        }
+        _ => {}
+    }
+}
@@ -0,0 +1,182 @@
+//! Reads JSON with `NaN`/`Infinity` tokens and numbers too large for `f64`.
+//!
+//! Some APIs (often scientific ones) send `NaN`, `Infinity`, and `-Infinity`
+//! as bare tokens, which standard JSON rejects, and numbers that lose digits
+//! when read as `i64`/`u64`/`f64`. With `lenient_json: true` on a source the
+//! response is rewritten into standard JSON before it is parsed:
+//!
+//! ```yaml
+//! sources:
+//!   - name: readings
+//!     url: https://api.example.com/readings
+//!     lenient_json:
+//!       non_finite: null     # or `string`: "NaN", "Infinity", "-Infinity"
+//!       big_numbers: string  # or `float`: read as a lossy f64
+//! ```
+//!
+//! Strict parsing stays the default.
+
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+use crate::errors::Result;
+
+/// A source's `lenient_json` setting: `true`, `false`, or a [`LenientJson`] block.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(untagged)]
+pub enum LenientJsonConfig {
+    Enabled(bool),
+    Options(LenientJson),
+}
+
+impl LenientJsonConfig {
+    /// The options in effect, or `None` for strict parsing.
+    pub fn resolve(&self) -> Option<LenientJson> {
+        match self {
+            LenientJsonConfig::Enabled(true) => Some(LenientJson::default()),
+            LenientJsonConfig::Enabled(false) => None,
+            LenientJsonConfig::Options(lenient) => Some(*lenient),
+        }
+    }
+}
+
+/// What `NaN`, `Infinity`, and `-Infinity` become.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum NonFinite {
+    #[default]
+    Null,
+    /// The token's text, e.g. `"NaN"`.
+    String,
+}
+
+/// What a number becomes when `i64`, `u64`, or `f64` can't hold it exactly.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum BigNumbers {
+    /// Its decimal text, so no digits are lost; cast it in SQL if needed.
+    #[default]
+    String,
+    /// The nearest `f64` (what strict parsing does).
+    Float,
+}
+
+/// Options of the `lenient_json` block.
+///
+/// # Example
+///
+/// ```
+/// use apitap::utils::lenient_json::{LenientJson, NonFinite};
+/// use serde_json::json;
+///
+/// let body = br#"{"a": NaN, "b": -Infinity, "c": 123456789012345678901234, "d": "NaN"}"#;
+///
+/// assert_eq!(
+///     LenientJson::default().parse(body).unwrap(),
+///     json!({"a": null, "b": null, "c": "123456789012345678901234", "d": "NaN"})
+/// );
+///
+/// let strings = LenientJson {
+///     non_finite: NonFinite::String,
+///     ..LenientJson::default()
+/// };
+/// assert_eq!(strings.parse(b"[NaN, Infinity, 1.5]").unwrap(), json!(["NaN", "Infinity", 1.5]));
+/// ```
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct LenientJson {
+    pub non_finite: NonFinite,
+    pub big_numbers: BigNumbers,
+}
+
+/// Bare tokens standard JSON has no number for.
+const NON_FINITE: [&str; 4] = ["NaN", "Infinity", "-Infinity", "+Infinity"];
+
+impl LenientJson {
+    /// Parses `body`, rewriting non-standard tokens first.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`crate::errors::ApitapError::SerdeJson`] if the body is not
+    /// JSON even after rewriting.
+    pub fn parse(&self, body: &[u8]) -> Result<Value> {
+        Ok(serde_json::from_slice(&self.rewrite(body))?)
+    }
+
+    /// Copies `body`, replacing non-finite tokens and (in `string` mode) big
+    /// numbers. String contents are copied unchanged.
+    pub fn rewrite(&self, body: &[u8]) -> Vec<u8> {
+        let mut out = Vec::with_capacity(body.len());
+        let mut i = 0;
+        while i < body.len() {
+            let b = body[i];
+            if b == b'"' {
+                let end = string_end(body, i);
+                out.extend_from_slice(&body[i..end]);
+                i = end;
+                continue;
+            }
+            if let Some(token) = NON_FINITE
+                .iter()
+                .find(|t| body[i..].starts_with(t.as_bytes()))
+            {
+                match self.non_finite {
+                    NonFinite::Null => out.extend_from_slice(b"null"),
+                    NonFinite::String => {
+                        let text = token.trim_start_matches('+');
+                        out.extend_from_slice(format!("\"{text}\"").as_bytes());
+                    }
+                }
+                i += token.len();
+                continue;
+            }
+            if b == b'-' || b.is_ascii_digit() {
+                let end = body[i..]
+                    .iter()
+                    .position(|c| !matches!(c, b'0'..=b'9' | b'-' | b'+' | b'.' | b'e' | b'E'))
+                    .map_or(body.len(), |n| i + n);
+                let literal = &body[i..end];
+                let quote = self.big_numbers == BigNumbers::String
+                    && std::str::from_utf8(literal).is_ok_and(is_big_number);
+                if quote {
+                    out.push(b'"');
+                    out.extend_from_slice(literal);
+                    out.push(b'"');
+                } else {
+                    out.extend_from_slice(literal);
+                }
+                i = end;
+                continue;
+            }
+            out.push(b);
+            i += 1;
+        }
+        out
+    }
+}
+
+/// Index just past the string starting at the quote at `start`.
+fn string_end(body: &[u8], start: usize) -> usize {
+    let mut i = start + 1;
+    while i < body.len() {
+        match body[i] {
+            b'\\' => i += 2,
+            b'"' => return i + 1,
+            _ => i += 1,
+        }
+    }
+    body.len()
+}
+
+/// True when parsing `literal` as `i64`/`u64` (integers) or `f64` would
+/// change it: integers out of range, and decimals with more than 17
+/// significant digits or beyond the `f64` range.
+fn is_big_number(literal: &str) -> bool {
+    if !literal.contains(['.', 'e', 'E']) {
+        return literal.parse::<i64>().is_err() && literal.parse::<u64>().is_err();
+    }
+    let mantissa = literal.split(['e', 'E']).next().unwrap_or_default();
+    let digits: String = mantissa.chars().filter(char::is_ascii_digit).collect();
+    let significant = digits.trim_start_matches('0').trim_end_matches('0');
+    significant.len() > 17 || literal.parse::<f64>().is_ok_and(|f| f.is_infinite())
+}
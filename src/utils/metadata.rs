@@ -0,0 +1,156 @@
+//! Lineage columns ApiTap adds to every written row.
+//!
+//! The columns are added after the module SQL runs, so they reach the
+//! destination whatever the query selects. Each one is opt-in and named by
+//! the user:
+//!
+//! ```yaml
+//! defaults:
+//!   metadata_columns:
+//!     ingested_at: _ingested_at
+//!     source: _source
+//! ```
+
+use std::sync::Arc;
+
+use chrono::{DateTime, SecondsFormat, Utc};
+use datafusion::arrow::datatypes::{DataType, Field, FieldRef, Schema, SchemaRef, TimeUnit};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+/// Which metadata columns to add, and what to call them. Unset columns are
+/// left out.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct MetadataColumns {
+    /// When the run started, as `TIMESTAMPTZ`; the same for every row of a run.
+    pub ingested_at: Option<String>,
+    /// Name of the source the row was fetched from.
+    pub source: Option<String>,
+    /// Name of the module that wrote the row.
+    pub module: Option<String>,
+    /// Page the row came from. Null for `limit_offset` and GraphQL sources,
+    /// which read all pages as one stream.
+    pub page: Option<String>,
+}
+
+impl MetadataColumns {
+    /// True when no column is configured.
+    pub fn is_empty(&self) -> bool {
+        self.ingested_at.is_none()
+            && self.source.is_none()
+            && self.module.is_none()
+            && self.page.is_none()
+    }
+
+    /// Fixes the values for one run of `module` reading `source`, stamped now.
+    pub fn stamp(&self, source: &str, module: &str) -> MetadataStamp {
+        self.stamp_at(source, module, Utc::now())
+    }
+
+    /// Like [`Self::stamp`] with an explicit ingestion time.
+    pub fn stamp_at(&self, source: &str, module: &str, at: DateTime<Utc>) -> MetadataStamp {
+        MetadataStamp {
+            columns: self.clone(),
+            ingested_at: at.to_rfc3339_opts(SecondsFormat::Micros, true),
+            source: source.to_string(),
+            module: module.to_string(),
+        }
+    }
+}
+
+/// Metadata values of one run, added to each output row.
+///
+/// # Example
+///
+/// ```
+/// use apitap::utils::metadata::MetadataColumns;
+/// use chrono::{TimeZone, Utc};
+/// use serde_json::json;
+///
+/// let columns = MetadataColumns {
+///     ingested_at: Some("_ingested_at".into()),
+///     source: Some("_source".into()),
+///     page: Some("_page".into()),
+///     ..MetadataColumns::default()
+/// };
+/// let at = Utc.with_ymd_and_hms(2024, 1, 2, 3, 4, 5).unwrap();
+/// let stamp = columns.stamp_at("orders_api", "orders", at);
+///
+/// assert_eq!(
+///     stamp.apply(json!({"id": 1}), Some(3)),
+///     json!({
+///         "id": 1,
+///         "_ingested_at": "2024-01-02T03:04:05.000000Z",
+///         "_source": "orders_api",
+///         "_page": 3
+///     })
+/// );
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct MetadataStamp {
+    columns: MetadataColumns,
+    ingested_at: String,
+    source: String,
+    module: String,
+}
+
+impl MetadataStamp {
+    /// True when the stamp adds no columns.
+    pub fn is_empty(&self) -> bool {
+        self.columns.is_empty()
+    }
+
+    /// `schema` with the metadata columns appended. A result column with the
+    /// same name is replaced.
+    pub fn schema(&self, schema: &Schema) -> SchemaRef {
+        let extra = self.fields();
+        let mut fields: Vec<FieldRef> = schema
+            .fields()
+            .iter()
+            .filter(|f| !extra.iter().any(|e| e.name() == f.name()))
+            .cloned()
+            .collect();
+        fields.extend(extra);
+        Arc::new(Schema::new_with_metadata(fields, schema.metadata().clone()))
+    }
+
+    /// Adds the metadata values to one row; `page` is `None` when unknown.
+    /// Non-object rows are left alone.
+    pub fn apply(&self, mut row: Value, page: Option<u64>) -> Value {
+        let Value::Object(obj) = &mut row else {
+            return row;
+        };
+        let c = &self.columns;
+        if let Some(name) = &c.ingested_at {
+            obj.insert(name.clone(), Value::String(self.ingested_at.clone()));
+        }
+        if let Some(name) = &c.source {
+            obj.insert(name.clone(), Value::String(self.source.clone()));
+        }
+        if let Some(name) = &c.module {
+            obj.insert(name.clone(), Value::String(self.module.clone()));
+        }
+        if let Some(name) = &c.page {
+            obj.insert(name.clone(), page.map_or(Value::Null, Value::from));
+        }
+        row
+    }
+
+    fn fields(&self) -> Vec<FieldRef> {
+        let c = &self.columns;
+        let timestamp = DataType::Timestamp(TimeUnit::Microsecond, Some("UTC".into()));
+        [
+            (&c.ingested_at, timestamp, false),
+            (&c.source, DataType::Utf8, false),
+            (&c.module, DataType::Utf8, false),
+            (&c.page, DataType::UInt64, true),
+        ]
+        .into_iter()
+        .filter_map(|(name, data_type, nullable)| {
+            name.as_ref()
+                .map(|name| Arc::new(Field::new(name, data_type, nullable)))
+        })
+        .collect()
+    }
+}
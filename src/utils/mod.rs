@@ -1,12 +1,34 @@
 //! Utility modules for ApiTap.
 //!
 //! This module contains helper utilities for DataFusion integration,
-//! SQL execution, HTTP retry logic, schema management, and streaming operations.
+//! SQL execution, custom SQL functions, HTTP retry logic, schema management,
+//! log redaction, secret providers, lenient JSON parsing, duplicate JSON keys, large-integer and
+//! null-like value handling, dictionary encoding, MongoDB extended JSON, record transforms and flattening, lineage metadata and constant columns, progress logging, schema contracts, and streaming
+//! operations.
 
+pub mod constant_columns;
+pub mod csv;
 pub mod datafusion_ext;
+pub mod dictionary;
+pub mod duplicate_keys;
+pub mod ejson;
 pub mod execution;
+pub mod fields;
+pub mod flatten;
 pub mod http_retry;
+pub mod json_path;
+pub mod lenient_json;
+pub mod metadata;
+pub mod nulls;
+pub mod numbers;
+pub mod progress;
+pub mod redact;
 pub mod schema;
+pub mod schema_contract;
+pub mod secrets;
 pub mod streaming;
 pub mod table_provider;
 pub mod template;
+pub mod transform;
+pub mod udf;
+pub mod xml;
@@ -0,0 +1,81 @@
+//! Coerces null-like strings to JSON `null` before schema inference.
+//!
+//! Some APIs send `""`, `"null"`, or `"N/A"` where they mean no value. Left
+//! alone, those land as text: a numeric column infers as `Utf8` and `IS NULL`
+//! filters miss them. `null_values` lists the strings to treat as null in
+//! every top-level field; `column_null_values` sets the list for individual
+//! columns instead. Matching is exact, so `"n/a"` does not match `"N/A"`.
+//!
+//! ```yaml
+//! sources:
+//!   - name: products
+//!     url: https://api.example.com/products
+//!     null_values: ["", "null"]
+//!     column_null_values:
+//!       price: ["", "N/A", "-"]
+//!       notes: []            # keep empty notes as ""
+//! ```
+
+use std::collections::BTreeMap;
+
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+/// A source's `null_values` and `column_null_values` settings.
+///
+/// Only top-level fields are checked, after `rename` and `drop`, so columns
+/// are named as they land.
+///
+/// # Example
+///
+/// ```
+/// use apitap::utils::nulls::NullHandling;
+/// use serde_json::json;
+///
+/// let nulls = NullHandling {
+///     null_values: vec!["".to_string(), "null".to_string()],
+///     column_null_values: [("price".to_string(), vec!["N/A".to_string()])].into(),
+/// };
+/// assert_eq!(
+///     nulls.apply(json!({"name": "", "note": "null", "price": "N/A", "sku": "a1"})),
+///     json!({"name": null, "note": null, "price": null, "sku": "a1"})
+/// );
+/// // `price` uses its own list only
+/// assert_eq!(nulls.apply(json!({"price": ""})), json!({"price": ""}));
+/// ```
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct NullHandling {
+    /// Strings read as null in every top-level field.
+    #[serde(default)]
+    pub null_values: Vec<String>,
+    /// Per-column lists, used instead of `null_values` for those columns.
+    #[serde(default)]
+    pub column_null_values: BTreeMap<String, Vec<String>>,
+}
+
+impl NullHandling {
+    /// True when records pass through unchanged.
+    pub fn is_empty(&self) -> bool {
+        self.null_values.is_empty() && self.column_null_values.values().all(Vec::is_empty)
+    }
+
+    /// Replaces null-like strings in one record with `null`. Non-object
+    /// records are left alone.
+    pub fn apply(&self, mut record: Value) -> Value {
+        if let Value::Object(obj) = &mut record {
+            for (column, value) in obj.iter_mut() {
+                let Value::String(s) = value else {
+                    continue;
+                };
+                let nulls = self
+                    .column_null_values
+                    .get(column)
+                    .unwrap_or(&self.null_values);
+                if nulls.iter().any(|n| n == s) {
+                    *value = Value::Null;
+                }
+            }
+        }
+        record
+    }
+}
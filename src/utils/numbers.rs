@@ -0,0 +1,158 @@
+//! Keeps integers that don't fit in `i64` from being silently corrupted.
+//!
+//! JSON integers above `i64::MAX` (e.g. snowflake IDs sent as numbers) infer
+//! as `UInt64`, or `Int64` when mixed with negatives, and Postgres stores
+//! them as `BIGINT`, so out-of-range values are lost on write. Listing a
+//! column in `string_columns` lands it as text; `large_integers` decides what
+//! happens to out-of-range integers in any other column.
+
+use std::collections::BTreeSet;
+
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+use crate::errors::{ApitapError, Result};
+
+/// What to do with an integer outside the `i64` range.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum LargeIntegers {
+    /// Leave the value alone (previous behavior).
+    #[default]
+    Keep,
+    /// Land every number in the affected column as text.
+    String,
+    /// Fail the run, naming the column.
+    Error,
+}
+
+/// A source's `large_integers` and `string_columns` settings.
+///
+/// Only top-level fields are checked.
+///
+/// # Example
+///
+/// ```
+/// use apitap::utils::numbers::{LargeIntegers, NumberHandling};
+/// use serde_json::json;
+///
+/// let numbers = NumberHandling {
+///     large_integers: LargeIntegers::String,
+///     string_columns: vec!["account".to_string()],
+/// };
+///
+/// let page = numbers
+///     .apply_page(vec![
+///         json!({"id": 1, "account": 7, "n": 1}),
+///         json!({"id": 18446744073709551615u64, "account": 8, "n": 2}),
+///     ])
+///     .unwrap();
+/// assert_eq!(page[0], json!({"id": "1", "account": "7", "n": 1}));
+/// assert_eq!(page[1]["id"], json!("18446744073709551615"));
+/// ```
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct NumberHandling {
+    #[serde(default)]
+    pub large_integers: LargeIntegers,
+    /// Columns whose numbers always land as text, whatever their size.
+    #[serde(default)]
+    pub string_columns: Vec<String>,
+}
+
+impl NumberHandling {
+    /// True when records pass through unchanged.
+    pub fn is_empty(&self) -> bool {
+        self.large_integers == LargeIntegers::Keep && self.string_columns.is_empty()
+    }
+
+    /// Columns whose numbers land as text for `records`: `string_columns`,
+    /// plus (in `string` mode) every column holding an out-of-range integer.
+    ///
+    /// # Errors
+    ///
+    /// In `error` mode, returns a data type error for the first out-of-range
+    /// integer outside `string_columns`.
+    pub fn text_columns(&self, records: &[Value]) -> Result<BTreeSet<String>> {
+        let mut columns: BTreeSet<String> = self.string_columns.iter().cloned().collect();
+        if self.large_integers == LargeIntegers::Keep {
+            return Ok(columns);
+        }
+
+        for record in records {
+            for (column, value) in overflowing(record) {
+                if columns.contains(column) {
+                    continue;
+                }
+                if self.large_integers == LargeIntegers::Error {
+                    return Err(overflow_error(column, value));
+                }
+                columns.insert(column.to_string());
+            }
+        }
+        Ok(columns)
+    }
+
+    /// Applies the settings to one page of records.
+    ///
+    /// In `string` mode a column is decided per page, so list columns that
+    /// must keep one type across pages in `string_columns`.
+    pub fn apply_page(&self, records: Vec<Value>) -> Result<Vec<Value>> {
+        if self.is_empty() {
+            return Ok(records);
+        }
+        let columns = self.text_columns(&records)?;
+        Ok(records
+            .into_iter()
+            .map(|r| stringify_columns(r, &columns))
+            .collect())
+    }
+
+    /// Applies the settings to a record streamed after the schema was fixed
+    /// from earlier samples, using the `columns` chosen for those samples.
+    ///
+    /// # Errors
+    ///
+    /// Returns a data type error if the record has an out-of-range integer in
+    /// a column that was not chosen as text (always, unless mode is `keep`).
+    pub fn apply_streamed(&self, record: Value, columns: &BTreeSet<String>) -> Result<Value> {
+        if self.large_integers != LargeIntegers::Keep {
+            if let Some((column, value)) = overflowing(&record).find(|(c, _)| !columns.contains(*c))
+            {
+                return Err(overflow_error(column, value));
+            }
+        }
+        Ok(stringify_columns(record, columns))
+    }
+}
+
+/// Top-level fields of `record` holding an integer outside `i64`.
+fn overflowing(record: &Value) -> impl Iterator<Item = (&str, &Value)> {
+    record
+        .as_object()
+        .into_iter()
+        .flatten()
+        .filter(|(_, v)| matches!(v, Value::Number(n) if n.is_u64() && !n.is_i64()))
+        .map(|(k, v)| (k.as_str(), v))
+}
+
+fn overflow_error(column: &str, value: &Value) -> ApitapError {
+    ApitapError::DataTypeError(format!(
+        "column '{column}' has integer {value} outside the Int64 range; \
+         add it to string_columns or set large_integers: string"
+    ))
+}
+
+/// Replaces numbers in `columns` with their decimal text.
+fn stringify_columns(mut record: Value, columns: &BTreeSet<String>) -> Value {
+    if columns.is_empty() {
+        return record;
+    }
+    if let Value::Object(obj) = &mut record {
+        for column in columns {
+            if let Some(value @ Value::Number(_)) = obj.get_mut(column) {
+                *value = Value::String(value.to_string());
+            }
+        }
+    }
+    record
+}
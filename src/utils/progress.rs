@@ -0,0 +1,165 @@
+//! Periodic progress logs for long loads.
+//!
+//! A [`WriteProgress`] counts rows handed to the destination writer and
+//! pages fetched, and logs both with the current throughput every few
+//! seconds (and optionally every N rows) until the run finishes. A stalled
+//! writer shows up as repeated lines with `rows_per_sec=0`.
+
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Mutex, OnceLock, Weak};
+use std::time::{Duration, Instant};
+
+use futures::StreamExt;
+use tokio::task::JoinHandle;
+use tracing::info;
+
+use crate::utils::datafusion_ext::JsonStreamType;
+
+/// Seconds between progress logs unless `--progress-interval` says otherwise.
+pub const DEFAULT_PROGRESS_INTERVAL_SECS: u64 = 5;
+
+/// When progress lines are logged.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ProgressOpts {
+    /// Log on this cadence; `None` turns the timer off.
+    pub interval: Option<Duration>,
+    /// Also log each time this many more rows have been written.
+    pub every_rows: Option<u64>,
+}
+
+impl Default for ProgressOpts {
+    fn default() -> Self {
+        Self {
+            interval: Some(Duration::from_secs(DEFAULT_PROGRESS_INTERVAL_SECS)),
+            every_rows: None,
+        }
+    }
+}
+
+/// Running row and page totals of one run, logged as they grow.
+///
+/// # Example
+///
+/// ```
+/// use apitap::utils::progress::{ProgressOpts, WriteProgress};
+///
+/// # #[tokio::main(flavor = "current_thread")]
+/// # async fn main() {
+/// let progress = WriteProgress::start("orders", &ProgressOpts::default());
+/// progress.add_page();
+/// progress.add_rows(250);
+/// assert_eq!((progress.rows(), progress.pages()), (250, 1));
+/// # }
+/// ```
+#[derive(Debug)]
+pub struct WriteProgress {
+    table: String,
+    every_rows: Option<u64>,
+    started: Instant,
+    rows: AtomicU64,
+    pages: AtomicU64,
+    /// Time and row count of the previous log line, for the current rate.
+    last: Mutex<(Instant, u64)>,
+    ticker: OnceLock<JoinHandle<()>>,
+}
+
+impl WriteProgress {
+    /// Starts tracking a run writing to `table`; the timer stops when the
+    /// last handle is dropped.
+    pub fn start(table: impl Into<String>, opts: &ProgressOpts) -> Arc<Self> {
+        let now = Instant::now();
+        let progress = Arc::new(Self {
+            table: table.into(),
+            every_rows: opts.every_rows.filter(|&n| n > 0),
+            started: now,
+            rows: AtomicU64::new(0),
+            pages: AtomicU64::new(0),
+            last: Mutex::new((now, 0)),
+            ticker: OnceLock::new(),
+        });
+
+        if let Some(period) = opts.interval.filter(|d| !d.is_zero()) {
+            let weak: Weak<Self> = Arc::downgrade(&progress);
+            let handle = tokio::spawn(async move {
+                let mut ticks = tokio::time::interval(period);
+                ticks.tick().await;
+                loop {
+                    ticks.tick().await;
+                    match weak.upgrade() {
+                        Some(progress) => progress.log(),
+                        None => break,
+                    }
+                }
+            });
+            let _ = progress.ticker.set(handle);
+        }
+        progress
+    }
+
+    /// Rows handed to the writer so far.
+    pub fn rows(&self) -> u64 {
+        self.rows.load(Ordering::Relaxed)
+    }
+
+    /// Pages fetched so far.
+    pub fn pages(&self) -> u64 {
+        self.pages.load(Ordering::Relaxed)
+    }
+
+    pub fn add_rows(&self, n: u64) {
+        let before = self.rows.fetch_add(n, Ordering::Relaxed);
+        if let Some(every) = self.every_rows {
+            if before / every != (before + n) / every {
+                self.log();
+            }
+        }
+    }
+
+    pub fn add_page(&self) {
+        self.pages.fetch_add(1, Ordering::Relaxed);
+    }
+
+    /// Counts every row of `stream` as the writer pulls it.
+    pub fn count_rows(self: &Arc<Self>, stream: JsonStreamType) -> JsonStreamType {
+        let progress = Arc::clone(self);
+        stream
+            .inspect(move |item| {
+                if item.is_ok() {
+                    progress.add_rows(1);
+                }
+            })
+            .boxed()
+    }
+
+    fn log(&self) {
+        let now = Instant::now();
+        let rows = self.rows();
+        let rate = {
+            let mut last = self.last.lock().unwrap_or_else(|e| e.into_inner());
+            let secs = now.duration_since(last.0).as_secs_f64();
+            let rate = if secs > 0.0 {
+                rows.saturating_sub(last.1) as f64 / secs
+            } else {
+                0.0
+            };
+            *last = (now, rows);
+            rate
+        };
+        info!(
+            table = %self.table,
+            rows,
+            pages = self.pages(),
+            rows_per_sec = rate.round() as u64,
+            elapsed_secs = self.started.elapsed().as_secs(),
+            "📈 write progress"
+        );
+    }
+}
+
+impl Drop for WriteProgress {
+    fn drop(&mut self) {
+        if let Some(handle) = self.ticker.get() {
+            handle.abort();
+        }
+    }
+}
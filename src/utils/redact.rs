@@ -0,0 +1,204 @@
+//! Masking of secrets before they reach the logs.
+//!
+//! Headers and URLs are built from `${ENV}` substitutions, so a debug line or
+//! an error message that echoes them can leak credentials. A [`Redactor`]
+//! replaces the values of sensitive headers and query parameters with `***`.
+//!
+//! The process-wide redactor starts with [`DEFAULT_HEADERS`] and
+//! [`DEFAULT_QUERY_PARAMS`]; the config's `redact` block adds to those lists
+//! (see [`install`]). Logging call sites use [`redact_url`] and
+//! [`redact_text`].
+
+use std::collections::BTreeSet;
+use std::sync::{Arc, OnceLock, RwLock};
+
+use regex::{Captures, Regex};
+use serde::{Deserialize, Serialize};
+
+/// Replacement for every masked value.
+pub const MASK: &str = "***";
+
+/// Header names masked by default (matched case-insensitively).
+pub const DEFAULT_HEADERS: &[&str] = &[
+    "authorization",
+    "proxy-authorization",
+    "api-key",
+    "x-api-key",
+    "cookie",
+    "set-cookie",
+];
+
+/// Query parameter names masked by default (matched case-insensitively).
+pub const DEFAULT_QUERY_PARAMS: &[&str] = &[
+    "token",
+    "access_token",
+    "refresh_token",
+    "key",
+    "api_key",
+    "apikey",
+    "password",
+    "secret",
+    "client_secret",
+    "signature",
+];
+
+/// Extra names to mask, from the config's top-level `redact` block.
+///
+/// ```yaml
+/// redact:
+///   headers: [X-Partner-Session]
+///   query_params: [sig]
+/// ```
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct RedactConfig {
+    pub headers: Vec<String>,
+    pub query_params: Vec<String>,
+}
+
+/// Masks sensitive header and query parameter values in log output.
+///
+/// # Example
+///
+/// ```
+/// use apitap::utils::redact::Redactor;
+///
+/// let r = Redactor::default();
+/// assert_eq!(
+///     r.redact_url("https://api.example.com/v1?token=abc&page=2"),
+///     "https://api.example.com/v1?token=***&page=2"
+/// );
+/// assert_eq!(r.redact_header("Authorization", "Bearer abc"), "***");
+/// assert_eq!(r.redact_header("Accept", "application/json"), "application/json");
+/// ```
+#[derive(Debug, Clone)]
+pub struct Redactor {
+    headers: BTreeSet<String>,
+    query_params: BTreeSet<String>,
+    param_re: Regex,
+    header_re: Regex,
+}
+
+impl Default for Redactor {
+    fn default() -> Self {
+        Self::from_config(&RedactConfig::default())
+    }
+}
+
+impl Redactor {
+    /// Builds a redactor from the defaults plus the names in `config`.
+    pub fn from_config(config: &RedactConfig) -> Self {
+        let headers: BTreeSet<String> = DEFAULT_HEADERS
+            .iter()
+            .map(|s| s.to_string())
+            .chain(config.headers.iter().map(|s| s.trim().to_ascii_lowercase()))
+            .filter(|s| !s.is_empty())
+            .collect();
+        let query_params: BTreeSet<String> = DEFAULT_QUERY_PARAMS
+            .iter()
+            .map(|s| s.to_string())
+            .chain(
+                config
+                    .query_params
+                    .iter()
+                    .map(|s| s.trim().to_ascii_lowercase()),
+            )
+            .filter(|s| !s.is_empty())
+            .collect();
+
+        // `name=value` after `?`, `&`, or `;`, with optional `[]` on the name
+        let param_re = Regex::new(&format!(
+            r"(?i)([?&;](?:{})(?:\[\])?=)[^&;#\s)'\x22]*",
+            alternation(&query_params)
+        ))
+        .expect("escaped alternation is a valid regex");
+        // `name: value` or `"name": "value"`, as in HeaderMap debug output
+        let header_re = Regex::new(&format!(
+            r#"(?i)(\b(?:{})"?\s*[:=]\s*)("[^"]*"|[^\r\n,;}}]*)"#,
+            alternation(&headers)
+        ))
+        .expect("escaped alternation is a valid regex");
+
+        Self {
+            headers,
+            query_params,
+            param_re,
+            header_re,
+        }
+    }
+
+    /// True if `name` is a header whose value must not be logged.
+    pub fn is_sensitive_header(&self, name: &str) -> bool {
+        self.headers.contains(&name.to_ascii_lowercase())
+    }
+
+    /// True if `name` is a query parameter whose value must not be logged.
+    pub fn is_sensitive_param(&self, name: &str) -> bool {
+        let name = name.strip_suffix("[]").unwrap_or(name);
+        self.query_params.contains(&name.to_ascii_lowercase())
+    }
+
+    /// Returns `value`, or [`MASK`] if `name` is a sensitive header.
+    pub fn redact_header<'a>(&self, name: &str, value: &'a str) -> &'a str {
+        if self.is_sensitive_header(name) {
+            MASK
+        } else {
+            value
+        }
+    }
+
+    /// Masks sensitive query parameter values in a URL.
+    pub fn redact_url(&self, url: &str) -> String {
+        self.param_re
+            .replace_all(url, |c: &Captures| format!("{}{MASK}", &c[1]))
+            .into_owned()
+    }
+
+    /// Masks sensitive query parameters and `header: value` pairs anywhere in
+    /// free text, such as error messages that embed a request URL.
+    pub fn redact_text(&self, text: &str) -> String {
+        let text = self.redact_url(text);
+        self.header_re
+            .replace_all(&text, |c: &Captures| {
+                if c[2].starts_with('"') {
+                    format!("{}\"{MASK}\"", &c[1])
+                } else {
+                    format!("{}{MASK}", &c[1])
+                }
+            })
+            .into_owned()
+    }
+}
+
+fn alternation(names: &BTreeSet<String>) -> String {
+    names
+        .iter()
+        .map(|n| regex::escape(n))
+        .collect::<Vec<_>>()
+        .join("|")
+}
+
+fn global() -> &'static RwLock<Arc<Redactor>> {
+    static GLOBAL: OnceLock<RwLock<Arc<Redactor>>> = OnceLock::new();
+    GLOBAL.get_or_init(|| RwLock::new(Arc::new(Redactor::default())))
+}
+
+/// Replaces the process-wide redactor. Called when a config is loaded.
+pub fn install(redactor: Redactor) {
+    *global().write().unwrap_or_else(|e| e.into_inner()) = Arc::new(redactor);
+}
+
+/// Returns the process-wide redactor.
+pub fn current() -> Arc<Redactor> {
+    global().read().unwrap_or_else(|e| e.into_inner()).clone()
+}
+
+/// [`Redactor::redact_url`] with the process-wide redactor.
+pub fn redact_url(url: &str) -> String {
+    current().redact_url(url)
+}
+
+/// [`Redactor::redact_text`] with the process-wide redactor.
+pub fn redact_text(text: &str) -> String {
+    current().redact_text(text)
+}
@@ -148,3 +148,72 @@ pub fn infer_schema_from_values(values: &[Value]) -> crate::errors::Result<Arc<S
 
     Ok(Arc::new(Schema::new(fields)))
 }
+
+/// Records a streamed fetch infers its schema from; later records must fit it.
+pub const STREAM_SAMPLE_SIZE: usize = 100;
+
+/// Column that holds the serialized record when a source sets `raw_json: true`.
+pub const RAW_JSON_COLUMN: &str = "data";
+
+/// One-column schema (`data: Utf8`) used instead of inference for raw JSON sources.
+pub fn raw_json_schema() -> Arc<Schema> {
+    Arc::new(Schema::new(vec![Field::new(
+        RAW_JSON_COLUMN,
+        DataType::Utf8,
+        false,
+    )]))
+}
+
+/// Serializes a record into the single-column row expected by [`raw_json_schema`].
+///
+/// # Example
+///
+/// ```
+/// use apitap::utils::schema::wrap_raw_json;
+/// use serde_json::json;
+///
+/// let row = wrap_raw_json(&json!({"id": 1})).unwrap();
+/// assert_eq!(row, json!({"data": "{\"id\":1}"}));
+/// ```
+pub fn wrap_raw_json(record: &Value) -> Result<Value> {
+    let mut row = serde_json::Map::with_capacity(1);
+    row.insert(
+        RAW_JSON_COLUMN.to_string(),
+        Value::String(serde_json::to_string(record)?),
+    );
+    Ok(Value::Object(row))
+}
+
+/// Renders a schema as an aligned `name  type  nullability` table.
+///
+/// # Example
+///
+/// ```
+/// use apitap::utils::schema::format_schema;
+/// use datafusion::arrow::datatypes::{DataType, Field, Schema};
+///
+/// let schema = Schema::new(vec![
+///     Field::new("id", DataType::Int64, false),
+///     Field::new("name", DataType::Utf8, true),
+/// ]);
+/// assert_eq!(
+///     format_schema(&schema),
+///     "id    Int64  NOT NULL\nname  Utf8   NULL\n"
+/// );
+/// ```
+pub fn format_schema(schema: &Schema) -> String {
+    let rows: Vec<(String, String, &str)> = schema
+        .fields()
+        .iter()
+        .map(|f| {
+            let nullable = if f.is_nullable() { "NULL" } else { "NOT NULL" };
+            (f.name().clone(), f.data_type().to_string(), nullable)
+        })
+        .collect();
+    let name_width = rows.iter().map(|r| r.0.len()).max().unwrap_or(0);
+    let type_width = rows.iter().map(|r| r.1.len()).max().unwrap_or(0);
+
+    rows.iter()
+        .map(|(name, ty, nullable)| format!("{name:<name_width$}  {ty:<type_width$}  {nullable}\n"))
+        .collect()
+}
@@ -0,0 +1,268 @@
+//! Pinned source schemas checked against what each run infers.
+//!
+//! A source's `schema_contract` lists the columns and Arrow types its
+//! records are expected to have, inline or in a YAML file. Every schema
+//! inferred from fetched records is compared with it before the module SQL
+//! runs, and drift is handled per `policy`:
+//!
+//! ```yaml
+//! sources:
+//!   - name: orders
+//!     url: https://api.example.com/orders
+//!     schema_contract:
+//!       policy: fail          # fail | warn | evolve
+//!       columns:
+//!         id: Int64
+//!         status: Utf8
+//!         total: Float64
+//! ```
+//!
+//! Types are written as `--infer-schema` prints them. A column inferred as
+//! `Null` (only nulls in the sample) matches any type, `Int64` matches an
+//! expected `Float64`, since a sample of whole numbers says nothing more, and
+//! `Utf8`, `LargeUtf8` and `Utf8View` are all text.
+
+use std::collections::{BTreeMap, BTreeSet};
+use std::fmt;
+use std::path::PathBuf;
+use std::sync::Mutex;
+
+use datafusion::arrow::datatypes::{DataType, Schema};
+use serde::{Deserialize, Serialize};
+use tracing::{info, warn};
+
+use crate::errors::{ApitapError, Result};
+
+/// What a run does when the inferred schema differs from the contract.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ContractPolicy {
+    /// Fail the run on any difference (the default).
+    #[default]
+    Fail,
+    /// Log the difference and carry on.
+    Warn,
+    /// Accept new columns with a log line; fail on removed or retyped ones.
+    Evolve,
+}
+
+/// `schema_contract` as written in the config.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct SchemaContractConfig {
+    /// Expected columns and their Arrow types, e.g. `id: Int64`.
+    #[serde(skip_serializing_if = "BTreeMap::is_empty")]
+    pub columns: BTreeMap<String, String>,
+    /// YAML (or JSON) file holding a `columns` map, used instead of `columns`.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub file: Option<PathBuf>,
+    pub policy: ContractPolicy,
+}
+
+#[derive(Deserialize)]
+struct ContractFile {
+    columns: BTreeMap<String, String>,
+}
+
+impl SchemaContractConfig {
+    /// Reads `file`, if set, and parses every type.
+    ///
+    /// # Errors
+    ///
+    /// Returns a `ConfigError` when both or neither of `columns` and `file`
+    /// are set, the file cannot be read, or a type is not an Arrow type.
+    pub fn load(&self) -> Result<SchemaContract> {
+        let columns = match (&self.file, self.columns.is_empty()) {
+            (Some(path), true) => {
+                let text = std::fs::read_to_string(path).map_err(|e| {
+                    ApitapError::ConfigError(format!(
+                        "schema_contract file '{}': {e}",
+                        path.display()
+                    ))
+                })?;
+                let file: ContractFile = serde_yaml::from_str(&text).map_err(|e| {
+                    ApitapError::ConfigError(format!(
+                        "schema_contract file '{}': {e}",
+                        path.display()
+                    ))
+                })?;
+                file.columns
+            }
+            (None, false) => self.columns.clone(),
+            (Some(_), false) => {
+                return Err(ApitapError::ConfigError(
+                    "schema_contract takes columns or file, not both".into(),
+                ))
+            }
+            (None, true) => {
+                return Err(ApitapError::ConfigError(
+                    "schema_contract needs columns or a file".into(),
+                ))
+            }
+        };
+        let columns = columns
+            .into_iter()
+            .map(|(name, ty)| {
+                let data_type = ty.trim().parse::<DataType>().map_err(|e| {
+                    ApitapError::ConfigError(format!(
+                        "schema_contract column '{name}': unknown type '{ty}': {e}"
+                    ))
+                })?;
+                Ok((name, data_type))
+            })
+            .collect::<Result<_>>()?;
+        Ok(SchemaContract {
+            columns,
+            policy: self.policy,
+            reported: Mutex::new(BTreeSet::new()),
+        })
+    }
+}
+
+/// Differences between a contract and an inferred schema.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct SchemaDiff {
+    /// Columns the contract doesn't list.
+    pub added: Vec<(String, DataType)>,
+    /// Contract columns the records no longer have.
+    pub removed: Vec<String>,
+    /// `(column, expected, inferred)` for columns whose type changed.
+    pub changed: Vec<(String, DataType, DataType)>,
+}
+
+impl SchemaDiff {
+    /// True when the schema matches the contract.
+    pub fn is_empty(&self) -> bool {
+        self.added.is_empty() && self.removed.is_empty() && self.changed.is_empty()
+    }
+
+    /// True when the only differences are new columns.
+    pub fn only_added(&self) -> bool {
+        self.removed.is_empty() && self.changed.is_empty()
+    }
+}
+
+impl fmt::Display for SchemaDiff {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let mut parts = Vec::new();
+        if !self.added.is_empty() {
+            let cols: Vec<String> = self
+                .added
+                .iter()
+                .map(|(name, ty)| format!("{name} ({ty})"))
+                .collect();
+            parts.push(format!("added {}", cols.join(", ")));
+        }
+        if !self.removed.is_empty() {
+            parts.push(format!("removed {}", self.removed.join(", ")));
+        }
+        if !self.changed.is_empty() {
+            let cols: Vec<String> = self
+                .changed
+                .iter()
+                .map(|(name, expected, found)| format!("{name} ({expected} -> {found})"))
+                .collect();
+            parts.push(format!("changed {}", cols.join(", ")));
+        }
+        write!(f, "{}", parts.join("; "))
+    }
+}
+
+/// A loaded contract, shared by every page of a run.
+///
+/// # Example
+///
+/// ```
+/// use apitap::utils::schema_contract::SchemaContractConfig;
+/// use datafusion::arrow::datatypes::{DataType, Field, Schema};
+///
+/// let config: SchemaContractConfig =
+///     serde_yaml::from_str("columns: {id: Int64, name: Utf8}").unwrap();
+/// let contract = config.load().unwrap();
+/// let inferred = Schema::new(vec![
+///     Field::new("id", DataType::Utf8, true),
+///     Field::new("email", DataType::Utf8, true),
+/// ]);
+///
+/// assert_eq!(
+///     contract.diff(&inferred).to_string(),
+///     "added email (Utf8); removed name; changed id (Int64 -> Utf8)"
+/// );
+/// assert!(contract.check("orders", &inferred).is_err());
+/// ```
+#[derive(Debug)]
+pub struct SchemaContract {
+    columns: BTreeMap<String, DataType>,
+    policy: ContractPolicy,
+    /// Differences already logged this run, so each is logged once.
+    reported: Mutex<BTreeSet<String>>,
+}
+
+impl SchemaContract {
+    /// Compares `schema` with the contract.
+    pub fn diff(&self, schema: &Schema) -> SchemaDiff {
+        let mut diff = SchemaDiff::default();
+        for field in schema.fields() {
+            match self.columns.get(field.name()) {
+                None => diff
+                    .added
+                    .push((field.name().clone(), field.data_type().clone())),
+                Some(expected) if !matches(expected, field.data_type()) => diff.changed.push((
+                    field.name().clone(),
+                    expected.clone(),
+                    field.data_type().clone(),
+                )),
+                Some(_) => {}
+            }
+        }
+        diff.removed = self
+            .columns
+            .keys()
+            .filter(|name| schema.field_with_name(name).is_err())
+            .cloned()
+            .collect();
+        diff
+    }
+
+    /// Applies the policy to the schema inferred for `table`.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`ApitapError::SchemaContract`] with the diff when the policy
+    /// is `fail`, or `evolve` and a column was removed or retyped.
+    pub fn check(&self, table: &str, schema: &Schema) -> Result<()> {
+        let diff = self.diff(schema);
+        if diff.is_empty() {
+            return Ok(());
+        }
+        let fails = match self.policy {
+            ContractPolicy::Fail => true,
+            ContractPolicy::Warn => false,
+            ContractPolicy::Evolve => !diff.only_added(),
+        };
+        if fails {
+            return Err(ApitapError::SchemaContract(format!("'{table}': {diff}")));
+        }
+        let first = self
+            .reported
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .insert(diff.to_string());
+        if first && self.policy == ContractPolicy::Warn {
+            warn!(table, %diff, "⚠️ schema drifted from schema_contract");
+        } else if first {
+            info!(table, %diff, "schema gained columns (schema_contract policy evolve)");
+        }
+        Ok(())
+    }
+}
+
+/// Whether an inferred type satisfies an expected one.
+fn matches(expected: &DataType, inferred: &DataType) -> bool {
+    let text =
+        |t: &DataType| matches!(t, DataType::Utf8 | DataType::LargeUtf8 | DataType::Utf8View);
+    expected == inferred
+        || *inferred == DataType::Null
+        || (*expected == DataType::Float64 && *inferred == DataType::Int64)
+        || (text(expected) && text(inferred))
+}
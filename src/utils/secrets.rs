@@ -0,0 +1,218 @@
+//! `${SECRET:key}` references resolved through a pluggable [`SecretProvider`].
+//!
+//! Config strings are substituted synchronously, so secrets are fetched
+//! ahead of time: [`resolve_config_secrets`] collects every `${SECRET:...}`
+//! reference in the config, asks the provider for each, and caches the
+//! values that [`substitute_env_vars`](crate::utils::template::substitute_env_vars)
+//! then reads. Each module run resolves again, so rotated secrets are picked
+//! up on the next tick.
+//!
+//! The default provider reads environment variables ([`EnvSecretProvider`]).
+//! Embedders swap in their own before starting the pipeline:
+//!
+//! ```no_run
+//! use std::sync::Arc;
+//!
+//! use apitap::errors::{ApitapError, Result};
+//! use apitap::utils::secrets::{set_secret_provider, SecretProvider};
+//!
+//! /// Reads `${SECRET:path#field}` from a HashiCorp Vault KV v2 mount.
+//! struct Vault {
+//!     http: reqwest::Client,
+//!     addr: String,
+//!     token: String,
+//! }
+//!
+//! #[async_trait::async_trait]
+//! impl SecretProvider for Vault {
+//!     async fn get(&self, key: &str) -> Result<String> {
+//!         let (path, field) = key.split_once('#').unwrap_or((key, "value"));
+//!         let body: serde_json::Value = self
+//!             .http
+//!             .get(format!("{}/v1/secret/data/{path}", self.addr))
+//!             .header("X-Vault-Token", &self.token)
+//!             .send()
+//!             .await?
+//!             .error_for_status()?
+//!             .json()
+//!             .await?;
+//!         body["data"]["data"][field]
+//!             .as_str()
+//!             .map(str::to_string)
+//!             .ok_or_else(|| ApitapError::ConfigError(format!("vault secret '{key}' not found")))
+//!     }
+//! }
+//!
+//! set_secret_provider(Arc::new(Vault {
+//!     http: reqwest::Client::new(),
+//!     addr: "https://vault.internal:8200".into(),
+//!     token: std::env::var("VAULT_TOKEN").unwrap(),
+//! }));
+//! ```
+
+use std::collections::BTreeMap;
+use std::sync::{Arc, RwLock};
+
+use async_trait::async_trait;
+use regex::Regex;
+use serde_json::Value;
+
+use crate::errors::{ApitapError, Result};
+use crate::pipeline::Config;
+
+/// Source of `${SECRET:key}` values.
+#[async_trait]
+pub trait SecretProvider: Send + Sync {
+    /// Returns the secret stored under `key`.
+    async fn get(&self, key: &str) -> Result<String>;
+}
+
+/// Reads secrets from environment variables: `db/password` becomes
+/// `DB_PASSWORD` (upper-cased, other characters replaced by `_`), after the
+/// optional prefix.
+///
+/// # Example
+///
+/// ```
+/// use apitap::utils::secrets::EnvSecretProvider;
+///
+/// let provider = EnvSecretProvider::with_prefix("APITAP_SECRET_");
+/// assert_eq!(provider.var_name("prod/db-password"), "APITAP_SECRET_PROD_DB_PASSWORD");
+/// ```
+#[derive(Debug, Clone, Default)]
+pub struct EnvSecretProvider {
+    prefix: String,
+}
+
+impl EnvSecretProvider {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn with_prefix(prefix: impl Into<String>) -> Self {
+        Self {
+            prefix: prefix.into(),
+        }
+    }
+
+    /// Environment variable holding `key`.
+    pub fn var_name(&self, key: &str) -> String {
+        let name: String = key
+            .chars()
+            .map(|c| {
+                if c.is_ascii_alphanumeric() {
+                    c.to_ascii_uppercase()
+                } else {
+                    '_'
+                }
+            })
+            .collect();
+        format!("{}{name}", self.prefix)
+    }
+}
+
+#[async_trait]
+impl SecretProvider for EnvSecretProvider {
+    async fn get(&self, key: &str) -> Result<String> {
+        let var = self.var_name(key);
+        std::env::var(&var).map_err(|_| {
+            ApitapError::ConfigError(format!(
+                "secret '{key}' not found (environment variable '{var}' is not set)"
+            ))
+        })
+    }
+}
+
+static PROVIDER: RwLock<Option<Arc<dyn SecretProvider>>> = RwLock::new(None);
+static RESOLVED: RwLock<BTreeMap<String, String>> = RwLock::new(BTreeMap::new());
+
+/// Replaces the process-wide provider (default: [`EnvSecretProvider`]).
+pub fn set_secret_provider(provider: Arc<dyn SecretProvider>) {
+    *PROVIDER.write().unwrap_or_else(|e| e.into_inner()) = Some(provider);
+}
+
+fn provider() -> Arc<dyn SecretProvider> {
+    PROVIDER
+        .read()
+        .unwrap_or_else(|e| e.into_inner())
+        .clone()
+        .unwrap_or_else(|| Arc::new(EnvSecretProvider::new()))
+}
+
+/// Keys of every `${SECRET:key}` reference in `text`.
+///
+/// # Example
+///
+/// ```
+/// use apitap::utils::secrets::secret_refs;
+///
+/// let refs = secret_refs("postgres://etl:${SECRET:db/password}@${SECRET:db/host}/app");
+/// assert_eq!(refs, vec!["db/password", "db/host"]);
+/// ```
+pub fn secret_refs(text: &str) -> Vec<String> {
+    let re = Regex::new(r"\$\{SECRET:([^}]+)\}").expect("valid secret regex");
+    re.captures_iter(text)
+        .map(|c| c[1].trim().to_string())
+        .collect()
+}
+
+/// Fetches every secret referenced in `texts` through the provider and
+/// caches it for substitution.
+///
+/// # Errors
+///
+/// Returns the provider's error for the first secret it cannot return.
+pub async fn resolve_secrets<'a>(texts: impl IntoIterator<Item = &'a str>) -> Result<()> {
+    let mut keys: Vec<String> = texts.into_iter().flat_map(secret_refs).collect();
+    keys.sort();
+    keys.dedup();
+    if keys.is_empty() {
+        return Ok(());
+    }
+
+    let provider = provider();
+    let mut values = BTreeMap::new();
+    for key in keys {
+        let value = provider.get(&key).await?;
+        values.insert(key, value);
+    }
+    RESOLVED
+        .write()
+        .unwrap_or_else(|e| e.into_inner())
+        .extend(values);
+    Ok(())
+}
+
+/// [`resolve_secrets`] over every string in `cfg`.
+pub async fn resolve_config_secrets(cfg: &Config) -> Result<()> {
+    let mut texts = Vec::new();
+    collect_strings(&serde_json::to_value(cfg)?, &mut texts);
+    resolve_secrets(texts.iter().map(String::as_str)).await
+}
+
+fn collect_strings(value: &Value, out: &mut Vec<String>) {
+    match value {
+        Value::String(s) if s.contains("${SECRET:") => out.push(s.clone()),
+        Value::Array(items) => items.iter().for_each(|v| collect_strings(v, out)),
+        Value::Object(obj) => obj.values().for_each(|v| collect_strings(v, out)),
+        _ => {}
+    }
+}
+
+/// The cached value of `key`.
+///
+/// # Errors
+///
+/// Returns a config error if `key` hasn't been resolved yet.
+pub fn cached_secret(key: &str) -> Result<String> {
+    RESOLVED
+        .read()
+        .unwrap_or_else(|e| e.into_inner())
+        .get(key)
+        .cloned()
+        .ok_or_else(|| {
+            ApitapError::ConfigError(format!(
+                "secret '{key}' has not been resolved; call resolve_config_secrets first"
+            ))
+        })
+}
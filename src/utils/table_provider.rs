@@ -1,18 +1,27 @@
 use datafusion::{
-    arrow::datatypes::SchemaRef,
+    arrow::datatypes::{FieldRef, SchemaRef},
     catalog::Session,
-    datasource::{TableProvider, TableType},
+    datasource::{MemTable, TableProvider, TableType},
     logical_expr::{Expr, TableProviderFilterPushDown},
     physical_plan::ExecutionPlan,
+    prelude::{CsvReadOptions, SessionContext},
 };
-use std::{any::Any, sync::Arc};
+use serde::{Deserialize, Serialize};
+use serde_arrow::schema::{SchemaLike, TracingOptions};
+use serde_json::Value;
+use std::{any::Any, path::Path, sync::Arc};
+use tracing::debug;
 
+use crate::errors::{ApitapError, Result};
 use crate::utils::execution::{Exec, JsonStreamFactory};
+use crate::utils::streaming::StreamConfig;
+use crate::utils::template::substitute_env_vars;
 
 /// Table provider for streaming JSON data
 pub struct JsonStreamTableProvider {
     stream_factory: JsonStreamFactory,
     schema: SchemaRef,
+    stream_config: StreamConfig,
 }
 
 impl JsonStreamTableProvider {
@@ -20,8 +29,15 @@ impl JsonStreamTableProvider {
         Self {
             stream_factory,
             schema,
+            stream_config: StreamConfig::default(),
         }
     }
+
+    /// Batching used when the scan converts records to Arrow.
+    pub fn with_stream_config(mut self, config: StreamConfig) -> Self {
+        self.stream_config = config;
+        self
+    }
 }
 
 impl std::fmt::Debug for JsonStreamTableProvider {
@@ -64,17 +80,125 @@ impl TableProvider for JsonStreamTableProvider {
         let exec = Exec::new(self.schema.clone(), projection, {
             let factory = self.stream_factory.clone();
             move || factory()
-        })?;
+        })?
+        .with_stream_config(self.stream_config.clone());
 
         Ok(Arc::new(exec))
     }
 
     fn supports_filters_pushdown(
         &self,
-        _filters: &[&Expr],
+        filters: &[&Expr],
     ) -> datafusion::error::Result<Vec<TableProviderFilterPushDown>> {
-        // Return empty to indicate no filters are pushed down
-        // Or return SupportedFilters to indicate which filters you support
-        Ok(vec![])
+        // One answer per filter; DataFusion applies them all after the scan
+        Ok(vec![
+            TableProviderFilterPushDown::Unsupported;
+            filters.len()
+        ])
+    }
+}
+
+// ============================== Lookup tables ============================== //
+
+/// File format of a [`Lookup`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum LookupFormat {
+    /// Comma-separated with a header row.
+    Csv,
+    /// A JSON array of objects, or one object per line (NDJSON).
+    Json,
+}
+
+/// Static table registered next to the API data so module SQL can join it.
+///
+/// ```yaml
+/// lookups:
+///   - name: country_codes
+///     path: ./lookups/country_codes.csv
+/// ```
+///
+/// `format` may be omitted when `path` ends in `.csv`, `.json`, or `.ndjson`.
+/// Relative paths resolve against the working directory.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Lookup {
+    pub name: String,
+    pub path: String,
+    #[serde(default)]
+    pub format: Option<LookupFormat>,
+}
+
+impl Lookup {
+    /// The configured format, or the one implied by the file extension.
+    pub fn resolved_format(&self) -> Result<LookupFormat> {
+        if let Some(format) = self.format {
+            return Ok(format);
+        }
+        let ext = Path::new(&self.path)
+            .extension()
+            .and_then(|e| e.to_str())
+            .map(str::to_ascii_lowercase);
+        match ext.as_deref() {
+            Some("csv") => Ok(LookupFormat::Csv),
+            Some("json") | Some("ndjson") | Some("jsonl") => Ok(LookupFormat::Json),
+            _ => Err(ApitapError::ConfigError(format!(
+                "lookup '{}': cannot infer format from '{}'; set `format: csv` or `format: json`",
+                self.name, self.path
+            ))),
+        }
+    }
+}
+
+/// Loads every lookup into memory and (re-)registers it on `ctx`.
+///
+/// Called at the start of each run, so edits to the files are picked up by
+/// the next scheduled execution without a restart.
+pub async fn register_lookups(ctx: &SessionContext, lookups: &[Lookup]) -> Result<()> {
+    for lookup in lookups {
+        let path = substitute_env_vars(&lookup.path)?;
+        let table = match lookup.resolved_format()? {
+            LookupFormat::Csv => load_csv(ctx, &path).await?,
+            LookupFormat::Json => load_json(&path)?,
+        };
+        let _ = ctx.deregister_table(&lookup.name);
+        ctx.register_table(lookup.name.as_str(), Arc::new(table))?;
+        debug!(lookup = %lookup.name, path = %path, "lookup table registered");
     }
+    Ok(())
+}
+
+async fn load_csv(ctx: &SessionContext, path: &str) -> Result<MemTable> {
+    // An empty extension filter accepts the file whatever it is named.
+    let df = ctx
+        .read_csv(path, CsvReadOptions::new().file_extension(""))
+        .await?;
+    let schema: SchemaRef = Arc::new(df.schema().as_arrow().clone());
+    let batches = df.collect().await?;
+    Ok(MemTable::try_new(schema, vec![batches])?)
+}
+
+fn load_json(path: &str) -> Result<MemTable> {
+    let text = std::fs::read_to_string(path)?;
+    let rows = match serde_json::from_str::<Value>(&text) {
+        Ok(Value::Array(rows)) => rows,
+        _ => text
+            .lines()
+            .filter(|l| !l.trim().is_empty())
+            .map(serde_json::from_str)
+            .collect::<std::result::Result<Vec<Value>, _>>()?,
+    };
+    if rows.is_empty() {
+        return Err(ApitapError::ConfigError(format!(
+            "lookup file '{path}' has no rows"
+        )));
+    }
+
+    let fields = Vec::<FieldRef>::from_samples(
+        &rows,
+        TracingOptions::default()
+            .allow_null_fields(true)
+            .coerce_numbers(true),
+    )?;
+    let batch = serde_arrow::to_record_batch(&fields, &rows)?;
+    Ok(MemTable::try_new(batch.schema(), vec![vec![batch]])?)
 }
@@ -1,7 +1,12 @@
+use crate::utils::secrets::cached_secret;
 use crate::{errors::Result, ApitapError};
-use chrono::{Duration, Local};
+use chrono::{Duration, Local, NaiveDate};
 use regex::Regex;
 use std::env;
+use std::sync::RwLock;
+
+/// The `--backfill` window being run, if any.
+static WINDOW: RwLock<Option<(NaiveDate, NaiveDate)>> = RwLock::new(None);
 
 #[macro_export]
 macro_rules! parse_function {
@@ -9,6 +14,10 @@ macro_rules! parse_function {
         let input = $func;
         if input == "current_date()" {
             Ok($crate::utils::template::current_date())
+        } else if input == "window_start()" {
+            Ok($crate::utils::template::window_start())
+        } else if input == "window_end()" {
+            Ok($crate::utils::template::window_end())
         } else if input.starts_with("few_date_ago(") && input.ends_with(")") {
             let arg_str = &input[13..input.len() - 1];
             let days: i64 = arg_str.parse().map_err(|_| {
@@ -145,12 +154,55 @@ pub fn few_date_ago(days: i64) -> Result<String> {
     Ok(final_date)
 }
 
+/// Sets the dates [`window_start`] and [`window_end`] return: `start`
+/// inclusive, `end` exclusive. `None` goes back to the last full day.
+pub fn set_window(window: Option<(NaiveDate, NaiveDate)>) {
+    *WINDOW.write().unwrap_or_else(|e| e.into_inner()) = window;
+}
+
+/// First day of the current `--backfill` window in YYYY-MM-DD format, or
+/// yesterday outside a backfill.
+///
+/// # Example
+///
+/// ```
+/// use apitap::utils::template::{set_window, substitute_templates};
+/// use chrono::NaiveDate;
+///
+/// let day = |d| NaiveDate::from_ymd_opt(2024, 1, d).unwrap();
+/// set_window(Some((day(1), day(8))));
+/// assert_eq!(
+///     substitute_templates("from={{ window_start() }}&to={{ window_end() }}").unwrap(),
+///     "from=2024-01-01&to=2024-01-08"
+/// );
+/// set_window(None);
+/// ```
+pub fn window_start() -> String {
+    match *WINDOW.read().unwrap_or_else(|e| e.into_inner()) {
+        Some((start, _)) => start.format("%Y-%m-%d").to_string(),
+        None => (Local::now().date_naive() - Duration::days(1))
+            .format("%Y-%m-%d")
+            .to_string(),
+    }
+}
+
+/// Day after the last day of the current `--backfill` window (an exclusive
+/// bound) in YYYY-MM-DD format, or today outside a backfill.
+pub fn window_end() -> String {
+    match *WINDOW.read().unwrap_or_else(|e| e.into_inner()) {
+        Some((_, end)) => end.format("%Y-%m-%d").to_string(),
+        None => current_date(),
+    }
+}
+
 /// Substitutes template variables in text with their actual values.
 /// Templates should be in the format {{ function_name() }}.
 ///
 /// Supported functions:
 /// - current_date(): Returns today's date in YYYY-MM-DD format
 /// - few_date_ago(n): Returns date n days ago in YYYY-MM-DD format
+/// - window_start() / window_end(): Bounds of the `--backfill` window being
+///   run, or yesterday / today outside a backfill
 ///
 /// # Example
 /// ```
@@ -191,6 +243,14 @@ pub fn substitute_templates(text: &str) -> Result<String> {
 /// Substitutes environment variables in text with their actual values.
 /// Environment variables should be in the format ${VAR_NAME}.
 ///
+/// `${FILE:/path/to/secret}` is replaced with the trimmed contents of that
+/// file, for secrets mounted as files (Kubernetes or Docker secrets).
+///
+/// `${SECRET:key}` is replaced with the value the configured
+/// [`SecretProvider`](crate::utils::secrets::SecretProvider) returned for
+/// `key`; it must have been fetched beforehand with
+/// [`resolve_config_secrets`](crate::utils::secrets::resolve_config_secrets).
+///
 /// Assumes that dotenv (or equivalent) has already been executed to load
 /// environment variables into the process.
 ///
@@ -205,7 +265,9 @@ pub fn substitute_templates(text: &str) -> Result<String> {
 ///
 /// # Errors
 ///
-/// Returns an error if any referenced environment variable is not set in the environment.
+/// Returns an error if any referenced environment variable is not set in the
+/// environment, a referenced secret file cannot be read, or a referenced
+/// provider secret has not been resolved.
 ///
 /// # Example
 /// ```no_run
@@ -218,26 +280,39 @@ pub fn substitute_templates(text: &str) -> Result<String> {
 /// let text = "Connect to ${BASE_URL} with key ${API_KEY}";
 /// let result = substitute_env_vars(text).expect("Failed to substitute env vars");
 /// assert_eq!(result, "Connect to https://api.example.com with key secret123");
+///
+/// // Secret mounted at /run/secrets/api_token
+/// let header = substitute_env_vars("Bearer ${FILE:/run/secrets/api_token}").unwrap();
 /// ```
 pub fn substitute_env_vars(text: &str) -> Result<String> {
-    let re = Regex::new(r"\$\{([a-zA-Z_][a-zA-Z0-9_]*)\}")?;
+    let re = Regex::new(r"\$\{(?:FILE:([^}]+)|SECRET:([^}]+)|([a-zA-Z_][a-zA-Z0-9_]*))\}")?;
 
     let mut result = String::with_capacity(text.len());
     let mut last_match = 0;
 
     for cap in re.captures_iter(text) {
         let full_match = cap.get(0).unwrap();
-        let var_name = cap.get(1).unwrap().as_str();
 
         // Add text before this match
         result.push_str(&text[last_match..full_match.start()]);
 
-        // Get the environment variable value
-        let env_value = env::var(var_name).map_err(|_| {
-            ApitapError::PipelineError(format!("Environment variable not found: {}", var_name))
-        })?;
+        let value = match (cap.get(1), cap.get(2), cap.get(3)) {
+            (Some(path), _, _) => read_secret_file(path.as_str().trim())?,
+            (None, Some(key), _) => cached_secret(key.as_str().trim())?,
+            (None, None, Some(var_name)) => {
+                // Get the environment variable value
+                let var_name = var_name.as_str();
+                env::var(var_name).map_err(|_| {
+                    ApitapError::PipelineError(format!(
+                        "Environment variable not found: {}",
+                        var_name
+                    ))
+                })?
+            }
+            _ => unreachable!("regex requires one of the three groups"),
+        };
 
-        result.push_str(&env_value);
+        result.push_str(&value);
 
         last_match = full_match.end();
     }
@@ -247,3 +322,48 @@ pub fn substitute_env_vars(text: &str) -> Result<String> {
 
     Ok(result)
 }
+
+/// Reads a file-mounted secret, trimming surrounding whitespace and newlines.
+fn read_secret_file(path: &str) -> Result<String> {
+    let contents = std::fs::read_to_string(path).map_err(|e| {
+        ApitapError::PipelineError(format!("Secret file '{}' could not be read: {}", path, e))
+    })?;
+    Ok(contents.trim().to_string())
+}
+
+/// Applies [`substitute_env_vars`] and then [`substitute_templates`] to every
+/// string inside a JSON value, leaving keys and non-string values untouched.
+///
+/// Used for structured config such as GraphQL variables and request bodies.
+///
+/// # Example
+/// ```
+/// use apitap::utils::template::substitute_json;
+/// use serde_json::json;
+///
+/// std::env::set_var("APITAP_DOC_ORG", "acme");
+/// let vars = json!({"org": "${APITAP_DOC_ORG}", "first": 50});
+/// let out = substitute_json(&vars).expect("substitution failed");
+/// assert_eq!(out, json!({"org": "acme", "first": 50}));
+/// ```
+pub fn substitute_json(value: &serde_json::Value) -> Result<serde_json::Value> {
+    use serde_json::Value;
+
+    Ok(match value {
+        Value::String(s) => Value::String(substitute_templates(&substitute_env_vars(s)?)?),
+        Value::Array(items) => Value::Array(
+            items
+                .iter()
+                .map(substitute_json)
+                .collect::<Result<Vec<_>>>()?,
+        ),
+        Value::Object(map) => {
+            let mut out = serde_json::Map::with_capacity(map.len());
+            for (k, v) in map {
+                out.insert(k.clone(), substitute_json(v)?);
+            }
+            Value::Object(out)
+        }
+        other => other.clone(),
+    })
+}
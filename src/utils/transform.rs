@@ -0,0 +1,451 @@
+//! Per-record `transform` statements: a small jq-like language for cleanups
+//! that are awkward in SQL.
+//!
+//! Each statement either assigns an expression to a path or deletes paths:
+//!
+//! ```text
+//! .city = .address.city            # lift a nested field
+//! .total = .amount // .price // 0  # first non-null value
+//! .id = tostring(.id)
+//! del(.address, .debug)
+//! ```
+//!
+//! Paths are dotted keys from the record root (`.a.b`); quote odd keys
+//! (`."first name"`) and use numbers to index arrays (`.items.0.sku`). `.`
+//! alone is the whole record. Expressions are paths, JSON literals, `a // b`,
+//! and the functions `coalesce`, `tostring`, `tonumber`, `lower`, and
+//! `upper`. Missing paths read as `null`; assignments create the objects they
+//! need. Statements run in order, each seeing the previous results.
+
+use serde_json::{Map, Value};
+
+use crate::errors::{ApitapError, Result};
+
+/// A source's parsed `transform` statements.
+///
+/// # Example
+///
+/// ```
+/// use apitap::utils::transform::RecordTransform;
+/// use serde_json::json;
+///
+/// let transform = RecordTransform::parse(&[
+///     ".city = .address.city".to_string(),
+///     ".total = .amount // .price // 0".to_string(),
+///     "del(.address)".to_string(),
+/// ])
+/// .unwrap();
+///
+/// let out = transform.apply(json!({"address": {"city": "Oslo"}, "price": 5}));
+/// assert_eq!(out, json!({"city": "Oslo", "price": 5, "total": 5}));
+/// ```
+#[derive(Debug, Clone, Default)]
+pub struct RecordTransform {
+    statements: Vec<Statement>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum Statement {
+    Assign(Vec<Segment>, Expr),
+    Delete(Vec<Vec<Segment>>),
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum Segment {
+    Key(String),
+    Index(usize),
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum Expr {
+    Path(Vec<Segment>),
+    Literal(Value),
+    Coalesce(Vec<Expr>),
+    Call(Function, Box<Expr>),
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Function {
+    ToString,
+    ToNumber,
+    Lower,
+    Upper,
+}
+
+impl RecordTransform {
+    /// Parses `statements`, one per entry.
+    ///
+    /// # Errors
+    ///
+    /// Returns a config error naming the first statement that doesn't parse.
+    pub fn parse(statements: &[String]) -> Result<Self> {
+        let statements = statements
+            .iter()
+            .map(|s| {
+                Parser::new(s)
+                    .statement()
+                    .map_err(|msg| ApitapError::ConfigError(format!("transform '{s}': {msg}")))
+            })
+            .collect::<Result<Vec<_>>>()?;
+        Ok(Self { statements })
+    }
+
+    /// True when records pass through unchanged.
+    pub fn is_empty(&self) -> bool {
+        self.statements.is_empty()
+    }
+
+    /// Runs every statement against one record. Non-object records are
+    /// left alone.
+    pub fn apply(&self, mut record: Value) -> Value {
+        if !record.is_object() {
+            return record;
+        }
+        for statement in &self.statements {
+            match statement {
+                Statement::Assign(path, expr) => {
+                    let value = expr.eval(&record);
+                    assign(&mut record, path, value);
+                }
+                Statement::Delete(paths) => {
+                    for path in paths {
+                        delete(&mut record, path);
+                    }
+                }
+            }
+        }
+        record
+    }
+}
+
+impl Expr {
+    fn eval(&self, record: &Value) -> Value {
+        match self {
+            Expr::Path(path) => lookup(record, path).cloned().unwrap_or(Value::Null),
+            Expr::Literal(v) => v.clone(),
+            Expr::Coalesce(exprs) => exprs
+                .iter()
+                .map(|e| e.eval(record))
+                .find(|v| !v.is_null())
+                .unwrap_or(Value::Null),
+            Expr::Call(f, arg) => f.call(arg.eval(record)),
+        }
+    }
+}
+
+impl Function {
+    fn from_name(name: &str) -> Option<Self> {
+        Some(match name {
+            "tostring" => Function::ToString,
+            "tonumber" => Function::ToNumber,
+            "lower" => Function::Lower,
+            "upper" => Function::Upper,
+            _ => return None,
+        })
+    }
+
+    fn call(self, arg: Value) -> Value {
+        match (self, arg) {
+            (_, Value::Null) => Value::Null,
+            (Function::ToString, Value::String(s)) => Value::String(s),
+            (Function::ToString, other) => Value::String(other.to_string()),
+            (Function::ToNumber, Value::Number(n)) => Value::Number(n),
+            (Function::ToNumber, Value::String(s)) => {
+                serde_json::from_str::<serde_json::Number>(s.trim())
+                    .map(Value::Number)
+                    .unwrap_or(Value::Null)
+            }
+            (Function::ToNumber, _) => Value::Null,
+            (Function::Lower, Value::String(s)) => Value::String(s.to_lowercase()),
+            (Function::Upper, Value::String(s)) => Value::String(s.to_uppercase()),
+            (Function::Lower | Function::Upper, other) => other,
+        }
+    }
+}
+
+fn lookup<'a>(mut value: &'a Value, path: &[Segment]) -> Option<&'a Value> {
+    for segment in path {
+        value = match (value, segment) {
+            (Value::Object(obj), Segment::Key(k)) => obj.get(k)?,
+            (Value::Object(obj), Segment::Index(i)) => obj.get(&i.to_string())?,
+            (Value::Array(items), Segment::Index(i)) => items.get(*i)?,
+            _ => return None,
+        };
+    }
+    Some(value)
+}
+
+fn assign(record: &mut Value, path: &[Segment], value: Value) {
+    let Some((last, parents)) = path.split_last() else {
+        *record = value;
+        return;
+    };
+
+    let mut target = record;
+    for segment in parents {
+        let Some(child) = child_mut(target, segment) else {
+            return;
+        };
+        target = child;
+    }
+    if let Some(slot) = child_mut(target, last) {
+        *slot = value;
+    }
+}
+
+/// The child at `segment`, created as `null` in objects. Scalars and nulls
+/// become empty objects; arrays only yield existing elements.
+fn child_mut<'a>(target: &'a mut Value, segment: &Segment) -> Option<&'a mut Value> {
+    if let Value::Array(items) = target {
+        return match segment {
+            Segment::Index(i) => items.get_mut(*i),
+            Segment::Key(_) => None,
+        };
+    }
+    if !target.is_object() {
+        *target = Value::Object(Map::new());
+    }
+    let obj = target.as_object_mut()?;
+    Some(obj.entry(segment.key()).or_insert(Value::Null))
+}
+
+fn delete(record: &mut Value, path: &[Segment]) {
+    let Some((last, parents)) = path.split_last() else {
+        return;
+    };
+    let mut target = record;
+    for segment in parents {
+        target = match (target, segment) {
+            (Value::Object(obj), segment) => match obj.get_mut(&segment.key()) {
+                Some(v) => v,
+                None => return,
+            },
+            (Value::Array(items), Segment::Index(i)) => match items.get_mut(*i) {
+                Some(v) => v,
+                None => return,
+            },
+            _ => return,
+        };
+    }
+    match (target, last) {
+        (Value::Object(obj), last) => {
+            obj.remove(&last.key());
+        }
+        (Value::Array(items), Segment::Index(i)) if *i < items.len() => {
+            items.remove(*i);
+        }
+        _ => {}
+    }
+}
+
+impl Segment {
+    fn key(&self) -> String {
+        match self {
+            Segment::Key(k) => k.clone(),
+            Segment::Index(i) => i.to_string(),
+        }
+    }
+}
+
+/// Recursive-descent parser over one statement.
+struct Parser<'a> {
+    src: &'a str,
+    pos: usize,
+}
+
+type ParseResult<T> = std::result::Result<T, String>;
+
+impl<'a> Parser<'a> {
+    fn new(src: &'a str) -> Self {
+        Self { src, pos: 0 }
+    }
+
+    fn rest(&self) -> &'a str {
+        &self.src[self.pos..]
+    }
+
+    fn skip_ws(&mut self) {
+        let trimmed = self.rest().trim_start();
+        self.pos = self.src.len() - trimmed.len();
+    }
+
+    fn eat(&mut self, token: &str) -> bool {
+        self.skip_ws();
+        if self.rest().starts_with(token) {
+            self.pos += token.len();
+            true
+        } else {
+            false
+        }
+    }
+
+    fn expect(&mut self, token: &str) -> ParseResult<()> {
+        if self.eat(token) {
+            Ok(())
+        } else {
+            Err(self.error(&format!("expected '{token}'")))
+        }
+    }
+
+    fn error(&self, msg: &str) -> String {
+        format!("{msg} at column {}", self.pos + 1)
+    }
+
+    fn ident(&mut self) -> Option<&'a str> {
+        self.skip_ws();
+        let rest = self.rest();
+        let len = rest
+            .find(|c: char| !(c.is_ascii_alphanumeric() || c == '_'))
+            .unwrap_or(rest.len());
+        if len == 0 {
+            return None;
+        }
+        self.pos += len;
+        Some(&rest[..len])
+    }
+
+    fn statement(&mut self) -> ParseResult<Statement> {
+        let start = self.pos;
+        let statement = if self.ident() == Some("del") && self.eat("(") {
+            let mut paths = vec![self.path()?];
+            while self.eat(",") {
+                paths.push(self.path()?);
+            }
+            self.expect(")")?;
+            Statement::Delete(paths)
+        } else {
+            self.pos = start;
+            let path = self.path()?;
+            self.expect("=")?;
+            Statement::Assign(path, self.expr()?)
+        };
+
+        self.skip_ws();
+        if !self.rest().is_empty() {
+            return Err(self.error("unexpected trailing input"));
+        }
+        Ok(statement)
+    }
+
+    fn path(&mut self) -> ParseResult<Vec<Segment>> {
+        self.expect(".")?;
+        let mut segments = Vec::new();
+        // `.` alone is the root
+        if !self
+            .rest()
+            .starts_with(|c: char| c == '"' || c.is_ascii_alphanumeric() || c == '_')
+        {
+            return Ok(segments);
+        }
+        loop {
+            segments.push(self.segment()?);
+            if !self.rest().starts_with('.') || self.rest().starts_with("..") {
+                break;
+            }
+            self.pos += 1;
+        }
+        Ok(segments)
+    }
+
+    fn segment(&mut self) -> ParseResult<Segment> {
+        if self.rest().starts_with('"') {
+            return Ok(Segment::Key(self.string()?));
+        }
+        let start = self.pos;
+        let rest = self.rest();
+        let len = rest
+            .find(|c: char| !(c.is_ascii_alphanumeric() || c == '_'))
+            .unwrap_or(rest.len());
+        if len == 0 {
+            return Err(self.error("expected a key after '.'"));
+        }
+        self.pos += len;
+        let name = &self.src[start..self.pos];
+        Ok(match name.parse::<usize>() {
+            Ok(i) => Segment::Index(i),
+            Err(_) => Segment::Key(name.to_string()),
+        })
+    }
+
+    fn string(&mut self) -> ParseResult<String> {
+        let rest = self.rest();
+        let mut escaped = false;
+        for (i, c) in rest.char_indices().skip(1) {
+            match c {
+                '\\' if !escaped => escaped = true,
+                '"' if !escaped => {
+                    let literal = &rest[..=i];
+                    self.pos += i + 1;
+                    return serde_json::from_str(literal).map_err(|e| self.error(&e.to_string()));
+                }
+                _ => escaped = false,
+            }
+        }
+        Err(self.error("unterminated string"))
+    }
+
+    fn expr(&mut self) -> ParseResult<Expr> {
+        let mut terms = vec![self.term()?];
+        while self.eat("//") {
+            terms.push(self.term()?);
+        }
+        Ok(if terms.len() == 1 {
+            terms.remove(0)
+        } else {
+            Expr::Coalesce(terms)
+        })
+    }
+
+    fn term(&mut self) -> ParseResult<Expr> {
+        self.skip_ws();
+        let rest = self.rest();
+        if rest.starts_with('.') {
+            return Ok(Expr::Path(self.path()?));
+        }
+        if rest.starts_with('"') {
+            return Ok(Expr::Literal(Value::String(self.string()?)));
+        }
+        if rest.starts_with(|c: char| c == '-' || c.is_ascii_digit()) {
+            let len = rest
+                .find(|c: char| !(c.is_ascii_digit() || matches!(c, '-' | '+' | '.' | 'e' | 'E')))
+                .unwrap_or(rest.len());
+            let number = serde_json::from_str::<serde_json::Number>(&rest[..len])
+                .map_err(|_| self.error(&format!("invalid number '{}'", &rest[..len])))?;
+            self.pos += len;
+            return Ok(Expr::Literal(Value::Number(number)));
+        }
+
+        let start = self.pos;
+        let Some(name) = self.ident() else {
+            return Err(self.error("expected an expression"));
+        };
+        match name {
+            "null" => return Ok(Expr::Literal(Value::Null)),
+            "true" => return Ok(Expr::Literal(Value::Bool(true))),
+            "false" => return Ok(Expr::Literal(Value::Bool(false))),
+            _ => {}
+        }
+        if !self.eat("(") {
+            self.pos = start;
+            return Err(self.error(&format!("unknown name '{name}'")));
+        }
+        let mut args = vec![self.expr()?];
+        while self.eat(",") {
+            args.push(self.expr()?);
+        }
+        self.expect(")")?;
+
+        if name == "coalesce" {
+            return Ok(Expr::Coalesce(args));
+        }
+        let Some(function) = Function::from_name(name) else {
+            self.pos = start;
+            return Err(self.error(&format!("unknown function '{name}'")));
+        };
+        if args.len() != 1 {
+            self.pos = start;
+            return Err(self.error(&format!("{name}() takes one argument")));
+        }
+        Ok(Expr::Call(function, Box::new(args.remove(0))))
+    }
+}
@@ -0,0 +1,234 @@
+// src/utils/udf.rs
+
+//! Custom scalar functions for `dest_table` SQL.
+//!
+//! Every function returned by [`builtin_udfs`] is registered on the shared
+//! [`SessionContext`](datafusion::prelude::SessionContext) when it is first
+//! built. Extra functions can be added at any time before a pipeline runs with
+//! [`register_udf`](crate::utils::datafusion_ext::register_udf):
+//!
+//! ```no_run
+//! use apitap::utils::datafusion_ext::register_udf;
+//! use apitap::utils::udf::create_coercing_udf;
+//! use datafusion::arrow::datatypes::DataType;
+//! use datafusion::logical_expr::ColumnarValue;
+//! use std::sync::Arc;
+//!
+//! # async fn example() {
+//! let identity = create_coercing_udf(
+//!     "identity",
+//!     vec![DataType::Utf8],
+//!     DataType::Utf8,
+//!     Arc::new(|args: &[ColumnarValue]| Ok(args[0].clone())),
+//! );
+//! register_udf(identity).await;
+//! # }
+//! ```
+//!
+//! Built-in functions:
+//!
+//! | Function | Returns |
+//! |----------|---------|
+//! | `url_host(url)` | Host part of a URL, or `NULL` if it does not parse |
+//! | `geohash(lat, lon, precision)` | Base-32 geohash of the point (precision 1–12) |
+
+use std::any::Any;
+use std::sync::Arc;
+
+use datafusion::arrow::array::{ArrayRef, StringArray};
+use datafusion::arrow::datatypes::DataType;
+use datafusion::common::cast::{as_float64_array, as_int64_array, as_string_array};
+use datafusion::error::{DataFusionError, Result as DfResult};
+use datafusion::logical_expr::{
+    ColumnarValue, ScalarFunctionArgs, ScalarFunctionImplementation, ScalarUDF, ScalarUDFImpl,
+    Signature, Volatility,
+};
+use datafusion::prelude::SessionContext;
+
+/// Functions registered on every shared session.
+pub fn builtin_udfs() -> Vec<ScalarUDF> {
+    vec![url_host_udf(), geohash_udf()]
+}
+
+/// Registers [`builtin_udfs`] on `ctx`.
+pub fn register_builtin_udfs(ctx: &SessionContext) {
+    for udf in builtin_udfs() {
+        ctx.register_udf(udf);
+    }
+}
+
+/// Like DataFusion's `create_udf`, but arguments are cast to `input_types`
+/// instead of having to match them exactly.
+///
+/// Columns inferred from JSON are often `UInt64` or `LargeUtf8`; with an
+/// exact signature `f(Int64)` would reject them at planning time.
+pub fn create_coercing_udf(
+    name: &str,
+    input_types: Vec<DataType>,
+    return_type: DataType,
+    fun: ScalarFunctionImplementation,
+) -> ScalarUDF {
+    ScalarUDF::new_from_impl(CoercingUdf {
+        name: name.to_string(),
+        signature: Signature::user_defined(Volatility::Immutable),
+        input_types,
+        return_type,
+        fun,
+    })
+}
+
+struct CoercingUdf {
+    name: String,
+    signature: Signature,
+    input_types: Vec<DataType>,
+    return_type: DataType,
+    fun: ScalarFunctionImplementation,
+}
+
+impl std::fmt::Debug for CoercingUdf {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("CoercingUdf")
+            .field("name", &self.name)
+            .field("input_types", &self.input_types)
+            .field("return_type", &self.return_type)
+            .finish()
+    }
+}
+
+impl ScalarUDFImpl for CoercingUdf {
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+
+    fn name(&self) -> &str {
+        &self.name
+    }
+
+    fn signature(&self) -> &Signature {
+        &self.signature
+    }
+
+    fn return_type(&self, _arg_types: &[DataType]) -> DfResult<DataType> {
+        Ok(self.return_type.clone())
+    }
+
+    fn coerce_types(&self, arg_types: &[DataType]) -> DfResult<Vec<DataType>> {
+        if arg_types.len() != self.input_types.len() {
+            return Err(DataFusionError::Plan(format!(
+                "{}() takes {} arguments, got {}",
+                self.name,
+                self.input_types.len(),
+                arg_types.len()
+            )));
+        }
+        Ok(self.input_types.clone())
+    }
+
+    fn invoke_with_args(&self, args: ScalarFunctionArgs) -> DfResult<ColumnarValue> {
+        (self.fun)(&args.args)
+    }
+}
+
+/// `url_host(url)`: the host of `url`, or `NULL` for relative or invalid URLs.
+pub fn url_host_udf() -> ScalarUDF {
+    create_coercing_udf(
+        "url_host",
+        vec![DataType::Utf8],
+        DataType::Utf8,
+        Arc::new(|args: &[ColumnarValue]| {
+            let arrays = ColumnarValue::values_to_arrays(args)?;
+            let urls = as_string_array(&arrays[0])?;
+            let hosts: StringArray = urls
+                .iter()
+                .map(|u| {
+                    u.and_then(|u| url::Url::parse(u).ok())
+                        .and_then(|u| u.host_str().map(str::to_owned))
+                })
+                .collect();
+            Ok(ColumnarValue::Array(Arc::new(hosts) as ArrayRef))
+        }),
+    )
+}
+
+/// `geohash(lat, lon, precision)`: geohash of the point, `NULL` if any input is.
+pub fn geohash_udf() -> ScalarUDF {
+    create_coercing_udf(
+        "geohash",
+        vec![DataType::Float64, DataType::Float64, DataType::Int64],
+        DataType::Utf8,
+        Arc::new(|args: &[ColumnarValue]| {
+            let arrays = ColumnarValue::values_to_arrays(args)?;
+            let lats = as_float64_array(&arrays[0])?;
+            let lons = as_float64_array(&arrays[1])?;
+            let precisions = as_int64_array(&arrays[2])?;
+
+            let hashes = lats
+                .iter()
+                .zip(lons.iter())
+                .zip(precisions.iter())
+                .map(|((lat, lon), precision)| match (lat, lon, precision) {
+                    (Some(lat), Some(lon), Some(precision)) => {
+                        geohash_encode(lat, lon, precision).map(Some)
+                    }
+                    _ => Ok(None),
+                })
+                .collect::<DfResult<StringArray>>()?;
+            Ok(ColumnarValue::Array(Arc::new(hashes) as ArrayRef))
+        }),
+    )
+}
+
+const GEOHASH_ALPHABET: &[u8; 32] = b"0123456789bcdefghjkmnpqrstuvwxyz";
+
+/// Encodes a latitude/longitude pair as a geohash of `precision` characters.
+///
+/// # Example
+///
+/// ```
+/// use apitap::utils::udf::geohash_encode;
+///
+/// assert_eq!(geohash_encode(57.64911, 10.40744, 11).unwrap(), "u4pruydqqvj");
+/// ```
+pub fn geohash_encode(lat: f64, lon: f64, precision: i64) -> DfResult<String> {
+    if !(1..=12).contains(&precision) {
+        return Err(DataFusionError::Execution(format!(
+            "geohash precision must be between 1 and 12, got {precision}"
+        )));
+    }
+    if !(-90.0..=90.0).contains(&lat) || !(-180.0..=180.0).contains(&lon) {
+        return Err(DataFusionError::Execution(format!(
+            "geohash coordinates out of range: ({lat}, {lon})"
+        )));
+    }
+
+    let (mut lat_range, mut lon_range) = ((-90.0, 90.0), (-180.0, 180.0));
+    let mut hash = String::with_capacity(precision as usize);
+    let mut even_bit = true;
+    let (mut bits, mut ch) = (0, 0usize);
+
+    while hash.len() < precision as usize {
+        let (range, value) = if even_bit {
+            (&mut lon_range, lon)
+        } else {
+            (&mut lat_range, lat)
+        };
+        let mid = (range.0 + range.1) / 2.0;
+        ch <<= 1;
+        if value >= mid {
+            ch |= 1;
+            range.0 = mid;
+        } else {
+            range.1 = mid;
+        }
+        even_bit = !even_bit;
+
+        bits += 1;
+        if bits == 5 {
+            hash.push(GEOHASH_ALPHABET[ch] as char);
+            bits = 0;
+            ch = 0;
+        }
+    }
+
+    Ok(hash)
+}
@@ -0,0 +1,165 @@
+// src/utils/xml.rs
+
+//! XML → JSON conversion for `format: xml` sources.
+//!
+//! The mapping is the usual "badgerfish-lite" shape:
+//!
+//! - the document becomes `{"<root>": ...}`;
+//! - an element with only text becomes a string (`null` when empty);
+//! - attributes become `@name` keys and mixed text becomes `#text`;
+//! - repeated child elements collapse into an array.
+//!
+//! All leaf values stay strings; cast them in the module SQL.
+
+use quick_xml::escape::unescape;
+use quick_xml::events::{BytesStart, Event};
+use quick_xml::Reader;
+use serde_json::{Map, Value};
+
+use crate::errors::{ApitapError, Result};
+
+struct Node {
+    name: String,
+    fields: Map<String, Value>,
+    text: String,
+}
+
+impl Node {
+    fn open(start: &BytesStart<'_>, reader: &Reader<&[u8]>) -> Result<Self> {
+        let name = reader
+            .decoder()
+            .decode(start.name().as_ref())
+            .map_err(quick_xml::Error::from)?
+            .into_owned();
+
+        let mut fields = Map::new();
+        for attr in start.attributes() {
+            let attr = attr.map_err(quick_xml::Error::from)?;
+            let key = reader
+                .decoder()
+                .decode(attr.key.as_ref())
+                .map_err(quick_xml::Error::from)?;
+            let value = attr.decode_and_unescape_value(reader.decoder())?;
+            fields.insert(format!("@{key}"), Value::String(value.into_owned()));
+        }
+
+        Ok(Self {
+            name,
+            fields,
+            text: String::new(),
+        })
+    }
+
+    fn close(self) -> Result<(String, Value)> {
+        let text = unescape(self.text.trim())
+            .map_err(quick_xml::Error::from)?
+            .into_owned();
+
+        let value = if self.fields.is_empty() {
+            if text.is_empty() {
+                Value::Null
+            } else {
+                Value::String(text)
+            }
+        } else {
+            let mut fields = self.fields;
+            if !text.is_empty() {
+                fields.insert("#text".to_string(), Value::String(text));
+            }
+            Value::Object(fields)
+        };
+        Ok((self.name, value))
+    }
+
+    fn push_child(&mut self, name: String, value: Value) {
+        match self.fields.get_mut(&name) {
+            Some(Value::Array(items)) => items.push(value),
+            Some(existing) => {
+                let first = existing.take();
+                *existing = Value::Array(vec![first, value]);
+            }
+            None => {
+                self.fields.insert(name, value);
+            }
+        }
+    }
+}
+
+/// Converts an XML document into a `serde_json::Value`.
+///
+/// # Example
+///
+/// ```
+/// use apitap::utils::xml::xml_to_json;
+/// use serde_json::json;
+///
+/// let xml = br#"<orders><order id="1"><sku>A</sku></order><order id="2"><sku>B</sku></order></orders>"#;
+/// let v = xml_to_json(xml).unwrap();
+/// assert_eq!(
+///     v.pointer("/orders/order").unwrap(),
+///     &json!([{"@id": "1", "sku": "A"}, {"@id": "2", "sku": "B"}])
+/// );
+/// ```
+pub fn xml_to_json(xml: &[u8]) -> Result<Value> {
+    let mut reader = Reader::from_reader(xml);
+    let mut stack: Vec<Node> = Vec::new();
+    let mut root: Option<(String, Value)> = None;
+
+    loop {
+        match reader.read_event()? {
+            Event::Start(start) => stack.push(Node::open(&start, &reader)?),
+            Event::Empty(start) => {
+                let (name, value) = Node::open(&start, &reader)?.close()?;
+                match stack.last_mut() {
+                    Some(parent) => parent.push_child(name, value),
+                    None => root = Some((name, value)),
+                }
+            }
+            Event::End(_) => {
+                let node = stack.pop().ok_or_else(|| {
+                    ApitapError::PipelineError("unbalanced XML end tag".to_string())
+                })?;
+                let (name, value) = node.close()?;
+                match stack.last_mut() {
+                    Some(parent) => parent.push_child(name, value),
+                    None => root = Some((name, value)),
+                }
+            }
+            Event::Text(text) => {
+                if let Some(node) = stack.last_mut() {
+                    node.text
+                        .push_str(&text.decode().map_err(quick_xml::Error::from)?);
+                }
+            }
+            Event::GeneralRef(entity) => {
+                // Re-escape so `close` resolves it together with the surrounding text
+                if let Some(node) = stack.last_mut() {
+                    let name = entity.decode().map_err(quick_xml::Error::from)?;
+                    node.text.push('&');
+                    node.text.push_str(&name);
+                    node.text.push(';');
+                }
+            }
+            Event::CData(cdata) => {
+                if let Some(node) = stack.last_mut() {
+                    // CDATA is literal; escape it so `close` round-trips it unchanged
+                    let raw = cdata.decode().map_err(quick_xml::Error::from)?;
+                    node.text.push_str(&quick_xml::escape::escape(raw.as_ref()));
+                }
+            }
+            Event::Eof => break,
+            Event::Comment(_) | Event::Decl(_) | Event::PI(_) | Event::DocType(_) => {}
+        }
+    }
+
+    match root {
+        Some((name, value)) if stack.is_empty() => {
+            let mut doc = Map::new();
+            doc.insert(name, value);
+            Ok(Value::Object(doc))
+        }
+        _ => Err(ApitapError::PipelineError(
+            "XML document has no complete root element".to_string(),
+        )),
+    }
+}
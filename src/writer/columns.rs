@@ -0,0 +1,197 @@
+//! Per-sink column selection.
+//!
+//! A module's SQL decides which columns exist; `write_columns` and
+//! `ignore_columns` on a `sink(...)` call then narrow what that one sink
+//! writes. Sinks sharing a module can so keep different column sets, e.g.
+//! a warehouse table without the raw payload an archive keeps:
+//!
+//! ```sql
+//! {{ sink(name="warehouse", ignore_columns=["raw_payload"]) }}
+//! {{ sink(name="archive") }}
+//! ```
+
+use std::sync::Arc;
+
+use async_trait::async_trait;
+use datafusion::arrow::datatypes::{Schema, SchemaRef};
+use futures::StreamExt;
+use serde_json::Value;
+
+use crate::errors::{ApitapError, Result};
+use crate::utils::datafusion_ext::{QueryError, QueryResult, QueryResultStream};
+use crate::writer::{DataWriter, WriteMode};
+
+/// Columns a sink writes: only `write_columns` when set, minus
+/// `ignore_columns`. The default writes every column.
+///
+/// # Example
+///
+/// ```
+/// use apitap::writer::columns::ColumnSelection;
+/// use datafusion::arrow::datatypes::{DataType, Field, Schema};
+/// use serde_json::json;
+///
+/// let selection = ColumnSelection {
+///     write_columns: Some(vec!["name".into(), "id".into()]),
+///     ignore_columns: Vec::new(),
+/// };
+/// let schema = Schema::new(vec![
+///     Field::new("id", DataType::Int64, false),
+///     Field::new("name", DataType::Utf8, true),
+///     Field::new("raw", DataType::Utf8, true),
+/// ]);
+///
+/// let projected = selection.project_schema(&schema).unwrap();
+/// let names: Vec<_> = projected.fields().iter().map(|f| f.name().as_str()).collect();
+/// assert_eq!(names, ["name", "id"]);
+/// assert_eq!(
+///     selection.project_row(json!({"id": 1, "name": "a", "raw": "{}"})),
+///     json!({"id": 1, "name": "a"})
+/// );
+/// ```
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ColumnSelection {
+    /// Columns to write, in this order; every one must be in the result.
+    pub write_columns: Option<Vec<String>>,
+    /// Columns to leave out; names absent from the result are ignored.
+    pub ignore_columns: Vec<String>,
+}
+
+impl ColumnSelection {
+    /// True when every column is written.
+    pub fn is_empty(&self) -> bool {
+        self.write_columns.is_none() && self.ignore_columns.is_empty()
+    }
+
+    fn keeps(&self, column: &str) -> bool {
+        let listed = self
+            .write_columns
+            .as_ref()
+            .map_or(true, |cols| cols.iter().any(|c| c == column));
+        listed && !self.ignore_columns.iter().any(|c| c == column)
+    }
+
+    /// `schema` narrowed to the selected columns. Fails if a
+    /// `write_columns` entry is not in `schema`.
+    pub fn project_schema(&self, schema: &Schema) -> Result<SchemaRef> {
+        let fields = match &self.write_columns {
+            Some(columns) => columns
+                .iter()
+                .filter(|c| self.keeps(c))
+                .map(|c| {
+                    schema
+                        .field_with_name(c)
+                        .map(|f| Arc::new(f.clone()))
+                        .map_err(|_| {
+                            ApitapError::WriterError(format!(
+                                "write_columns: column '{c}' is not in the result"
+                            ))
+                        })
+                })
+                .collect::<Result<Vec<_>>>()?,
+            None => schema
+                .fields()
+                .iter()
+                .filter(|f| self.keeps(f.name()))
+                .cloned()
+                .collect(),
+        };
+        Ok(Arc::new(Schema::new_with_metadata(
+            fields,
+            schema.metadata().clone(),
+        )))
+    }
+
+    /// Drops the unselected keys of one row. Non-object rows are left alone.
+    pub fn project_row(&self, mut row: Value) -> Value {
+        if let Value::Object(obj) = &mut row {
+            obj.retain(|k, _| self.keeps(k));
+        }
+        row
+    }
+}
+
+/// Writer that hands the inner writer only the selected columns.
+pub struct ColumnFilterWriter {
+    inner: Arc<dyn DataWriter>,
+    selection: Arc<ColumnSelection>,
+}
+
+impl ColumnFilterWriter {
+    pub fn new(inner: Arc<dyn DataWriter>, selection: ColumnSelection) -> Self {
+        Self {
+            inner,
+            selection: Arc::new(selection),
+        }
+    }
+
+    /// `inner` itself when `selection` keeps every column.
+    pub fn wrap(inner: Arc<dyn DataWriter>, selection: ColumnSelection) -> Arc<dyn DataWriter> {
+        if selection.is_empty() {
+            inner
+        } else {
+            Arc::new(Self::new(inner, selection))
+        }
+    }
+
+    fn project(&self, result: QueryResultStream) -> Result<QueryResultStream> {
+        let schema = result
+            .schema
+            .map(|s| self.selection.project_schema(&s))
+            .transpose()?;
+        let selection = Arc::clone(&self.selection);
+        Ok(QueryResultStream {
+            table_name: result.table_name,
+            data: result
+                .data
+                .map(move |row| row.map(|r| selection.project_row(r)))
+                .boxed(),
+            schema,
+        })
+    }
+}
+
+#[async_trait]
+impl DataWriter for ColumnFilterWriter {
+    async fn write(&self, result: QueryResult) -> Result<()> {
+        let data = match result.data {
+            Value::Array(rows) => Value::Array(
+                rows.into_iter()
+                    .map(|r| self.selection.project_row(r))
+                    .collect(),
+            ),
+            other => self.selection.project_row(other),
+        };
+        self.inner.write(QueryResult { data, ..result }).await
+    }
+
+    async fn write_stream(&self, result: QueryResultStream, write_mode: WriteMode) -> Result<()> {
+        let result = self.project(result)?;
+        self.inner.write_stream(result, write_mode).await
+    }
+
+    async fn merge(&self, result: QueryResultStream) -> Result<()> {
+        let result = self.project(result)?;
+        self.inner.merge(result).await
+    }
+
+    async fn on_error(&self, error: QueryError) -> Result<()> {
+        self.inner.on_error(error).await
+    }
+
+    async fn begin(&self) -> Result<()> {
+        self.inner.begin().await
+    }
+
+    async fn commit(&self) -> Result<()> {
+        self.inner.commit().await
+    }
+
+    async fn rollback(&self) -> Result<()> {
+        self.inner.rollback().await
+    }
+
+    async fn finish(&self) -> Result<()> {
+        self.inner.finish().await
+    }
+}
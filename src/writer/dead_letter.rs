@@ -0,0 +1,181 @@
+//! Dead-letter destinations for rows a writer could not store.
+//!
+//! With `dead_letter` set on a Postgres target, a batch that fails because of
+//! its data (a value the column type rejects, a constraint violation) is
+//! written again one row at a time. Rows that still fail are handed to the
+//! dead-letter writer through [`DataWriter::on_error`] with the database's
+//! reason, and the rest of the batch is kept:
+//!
+//! ```yaml
+//! dead_letter: { path: ./dead_letters.ndjson }   # NDJSON lines in a file
+//! dead_letter: { table: apitap_dead_letters }    # rows in the same database
+//! ```
+//!
+//! Dead letters are written outside the run's transaction, so they are kept
+//! even when the run is later rolled back.
+
+use std::path::PathBuf;
+
+use async_trait::async_trait;
+use chrono::{SecondsFormat, Utc};
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+use tokio::io::AsyncWriteExt;
+
+use crate::errors::{ApitapError, Result};
+use crate::utils::datafusion_ext::{QueryError, QueryResult};
+use crate::writer::DataWriter;
+
+/// Where a target sends rows it could not write.
+///
+/// # Example
+///
+/// ```
+/// use apitap::writer::dead_letter::DeadLetter;
+///
+/// let dl: DeadLetter = serde_yaml::from_str("{table: apitap_dead_letters}").unwrap();
+/// assert_eq!(dl, DeadLetter::Table { table: "apitap_dead_letters".into() });
+///
+/// let dl: DeadLetter = serde_yaml::from_str("{path: /var/lib/apitap/dlq.ndjson}").unwrap();
+/// assert!(matches!(dl, DeadLetter::File { .. }));
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(untagged, deny_unknown_fields)]
+pub enum DeadLetter {
+    /// Append one JSON line per row to the file at `path`.
+    File { path: PathBuf },
+    /// Insert one row per failed row into `table`, created if missing.
+    Table { table: String },
+}
+
+/// Records failed rows, each with the table it was meant for and the reason.
+#[derive(Debug)]
+pub enum DeadLetterWriter {
+    File {
+        path: PathBuf,
+        /// Keeps lines from concurrent modules whole.
+        lock: tokio::sync::Mutex<()>,
+    },
+    #[cfg(feature = "postgres")]
+    Table {
+        pool: sqlx::PgPool,
+        table: String,
+        ready: tokio::sync::OnceCell<()>,
+    },
+}
+
+impl DeadLetterWriter {
+    /// A writer appending NDJSON lines to `path`.
+    pub fn file(path: impl Into<PathBuf>) -> Self {
+        DeadLetterWriter::File {
+            path: path.into(),
+            lock: tokio::sync::Mutex::new(()),
+        }
+    }
+
+    /// A writer inserting into `table` through `pool`.
+    #[cfg(feature = "postgres")]
+    pub fn table(pool: sqlx::PgPool, table: impl Into<String>) -> Self {
+        DeadLetterWriter::Table {
+            pool,
+            table: table.into(),
+            ready: tokio::sync::OnceCell::new(),
+        }
+    }
+
+    /// The writer for `config`; a table is created in `pool`'s database.
+    #[cfg(feature = "postgres")]
+    pub fn for_postgres(config: &DeadLetter, pool: &sqlx::PgPool) -> Self {
+        match config {
+            DeadLetter::File { path } => Self::file(path.clone()),
+            DeadLetter::Table { table } => Self::table(pool.clone(), table.clone()),
+        }
+    }
+
+    /// The dead-letter line for `error`, as the file writer stores it.
+    ///
+    /// ```
+    /// use apitap::utils::datafusion_ext::QueryError;
+    /// use apitap::writer::dead_letter::DeadLetterWriter;
+    /// use serde_json::json;
+    ///
+    /// let entry = DeadLetterWriter::entry(&QueryError {
+    ///     table_name: "orders".into(),
+    ///     error: "invalid input syntax for type bigint".into(),
+    ///     record: Some(json!({"id": "abc"})),
+    /// });
+    /// assert_eq!(entry["table"], "orders");
+    /// assert_eq!(entry["record"], json!({"id": "abc"}));
+    /// assert!(entry["failed_at"].is_string());
+    /// ```
+    pub fn entry(error: &QueryError) -> Value {
+        json!({
+            "failed_at": Utc::now().to_rfc3339_opts(SecondsFormat::Millis, true),
+            "table": error.table_name,
+            "error": error.error,
+            "record": error.record,
+        })
+    }
+
+    async fn record(&self, error: &QueryError) -> Result<()> {
+        match self {
+            DeadLetterWriter::File { path, lock } => {
+                let mut line = serde_json::to_vec(&Self::entry(error))?;
+                line.push(b'\n');
+                let _guard = lock.lock().await;
+                if let Some(parent) = path.parent().filter(|p| !p.as_os_str().is_empty()) {
+                    tokio::fs::create_dir_all(parent).await?;
+                }
+                let mut file = tokio::fs::OpenOptions::new()
+                    .create(true)
+                    .append(true)
+                    .open(path)
+                    .await?;
+                file.write_all(&line).await?;
+                file.flush().await?;
+                Ok(())
+            }
+            #[cfg(feature = "postgres")]
+            DeadLetterWriter::Table { pool, table, ready } => {
+                use crate::writer::postgres::PostgresWriter;
+
+                let table_sql = PostgresWriter::quote_ident_path(table);
+                ready
+                    .get_or_try_init(|| async {
+                        let create = format!(
+                            "CREATE TABLE IF NOT EXISTS {table_sql} (\
+                             failed_at TIMESTAMPTZ NOT NULL DEFAULT now(), \
+                             table_name TEXT NOT NULL, \
+                             error TEXT NOT NULL, \
+                             record JSONB)"
+                        );
+                        sqlx::query(&create).execute(pool).await.map(|_| ())
+                    })
+                    .await?;
+                let insert = format!(
+                    "INSERT INTO {table_sql} (table_name, error, record) VALUES ($1, $2, $3)"
+                );
+                sqlx::query(&insert)
+                    .bind(&error.table_name)
+                    .bind(&error.error)
+                    .bind(error.record.clone().map(sqlx::types::Json))
+                    .execute(pool)
+                    .await?;
+                Ok(())
+            }
+        }
+    }
+}
+
+#[async_trait]
+impl DataWriter for DeadLetterWriter {
+    async fn write(&self, _result: QueryResult) -> Result<()> {
+        Err(ApitapError::WriterError(
+            "a dead-letter writer only takes failed rows through on_error".to_string(),
+        ))
+    }
+
+    async fn on_error(&self, error: QueryError) -> Result<()> {
+        self.record(&error).await
+    }
+}
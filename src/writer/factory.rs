@@ -0,0 +1,123 @@
+//! Writers for target types ApiTap doesn't ship, registered by embedders.
+//!
+//! A `targets` entry whose `type` is not built in (`postgres`, `snowflake`,
+//! `object_store`) is parsed as a [`CustomSink`] and written by the
+//! [`WriterFactory`] registered under that type. Register factories before
+//! loading the config, which rejects types nobody registered:
+//!
+//! ```no_run
+//! use std::sync::Arc;
+//!
+//! use apitap::errors::Result;
+//! use apitap::pipeline::sink::{Hook, WriterOpts};
+//! use apitap::pipeline::CustomSink;
+//! use apitap::utils::datafusion_ext::QueryResult;
+//! use apitap::writer::factory::{register_writer_factory, WriterFactory};
+//! use apitap::writer::DataWriter;
+//!
+//! #[derive(serde::Deserialize)]
+//! struct ClickHouseOptions {
+//!     url: String,
+//! }
+//!
+//! struct ClickHouseWriter {
+//!     url: String,
+//!     table: String,
+//! }
+//!
+//! #[async_trait::async_trait]
+//! impl DataWriter for ClickHouseWriter {
+//!     async fn write(&self, result: QueryResult) -> Result<()> {
+//!         println!("{} rows to {} at {}", result.row_count, self.table, self.url);
+//!         Ok(())
+//!     }
+//! }
+//!
+//! struct ClickHouse;
+//!
+//! impl WriterFactory for ClickHouse {
+//!     fn make_writer(
+//!         &self,
+//!         sink: &CustomSink,
+//!         opts: &WriterOpts<'_>,
+//!     ) -> Result<(Arc<dyn DataWriter>, Option<Hook>)> {
+//!         let options: ClickHouseOptions = sink.options()?;
+//!         let writer = ClickHouseWriter {
+//!             url: options.url,
+//!             table: opts.dest_table.to_string(),
+//!         };
+//!         Ok((Arc::new(writer), None))
+//!     }
+//! }
+//!
+//! register_writer_factory("clickhouse", Arc::new(ClickHouse));
+//! ```
+//!
+//! with a target like:
+//!
+//! ```yaml
+//! targets:
+//!   - type: clickhouse
+//!     name: events_db
+//!     url: http://clickhouse:8123
+//! ```
+
+use std::collections::BTreeMap;
+use std::sync::{Arc, RwLock};
+
+use crate::errors::{ApitapError, Result};
+use crate::pipeline::sink::{Hook, HookFuture, WriterOpts};
+use crate::pipeline::CustomSink;
+use crate::writer::DataWriter;
+
+/// Builds writers for one custom target type.
+pub trait WriterFactory: Send + Sync {
+    /// Builds the writer for one `sink(...)` of a module writing to `sink`.
+    ///
+    /// Called once per module run. `opts` carries the destination table and
+    /// the resolved table policy; return a hook when `opts.truncate_first`
+    /// is set to empty the table before the first row. Connections can be
+    /// opened here or lazily in [`DataWriter::begin`].
+    fn make_writer(
+        &self,
+        sink: &CustomSink,
+        opts: &WriterOpts<'_>,
+    ) -> Result<(Arc<dyn DataWriter>, Option<Hook>)>;
+
+    /// Runs the SQL of a module that has no source on the target `sink`.
+    ///
+    /// Targets that can't run SQL keep the default, which fails the module.
+    fn execute_sql(&self, sink: &CustomSink, _sql: &str) -> Result<HookFuture> {
+        Err(ApitapError::UnsupportedSink(format!(
+            "target '{}' of type '{}' cannot run SQL-only modules",
+            sink.name, sink.kind
+        )))
+    }
+}
+
+impl std::fmt::Debug for dyn WriterFactory {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str("WriterFactory")
+    }
+}
+
+static FACTORIES: RwLock<BTreeMap<String, Arc<dyn WriterFactory>>> = RwLock::new(BTreeMap::new());
+
+/// Registers `factory` for targets of type `kind`, replacing any earlier one.
+///
+/// Built-in types can't be overridden: their targets never reach a factory.
+pub fn register_writer_factory(kind: impl Into<String>, factory: Arc<dyn WriterFactory>) {
+    FACTORIES
+        .write()
+        .unwrap_or_else(|e| e.into_inner())
+        .insert(kind.into(), factory);
+}
+
+/// The factory registered for `kind`, if any.
+pub fn writer_factory(kind: &str) -> Option<Arc<dyn WriterFactory>> {
+    FACTORIES
+        .read()
+        .unwrap_or_else(|e| e.into_inner())
+        .get(kind)
+        .cloned()
+}
@@ -0,0 +1,280 @@
+//! Writes one module's output to several sinks.
+//!
+//! A module that calls `sink(...)` more than once fetches and transforms its
+//! source once; [`FanOutWriter`] then hands every row to each sink's own
+//! writer. A sink declared with `on_error="continue"` is best-effort: when it
+//! fails it is rolled back and dropped from the run while the other sinks
+//! carry on. A failure of any other sink fails the run, as with one sink.
+
+use std::collections::BTreeMap;
+use std::future::Future;
+use std::sync::{Arc, Mutex};
+
+use async_trait::async_trait;
+use futures::channel::mpsc;
+use futures::future::join_all;
+use futures::{SinkExt, StreamExt};
+use tracing::warn;
+
+use crate::errors::{ApitapError, Result};
+use crate::utils::datafusion_ext::{QueryError, QueryResult, QueryResultStream};
+use crate::writer::{DataWriter, WriteMode};
+
+/// Rows buffered per sink before the fastest sink waits for the slowest.
+const FANOUT_BUFFER: usize = 1024;
+
+/// What a failure of one sink does to the run, from `sink(on_error="...")`.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum SinkErrorPolicy {
+    /// Roll back every sink and fail the run.
+    #[default]
+    Fail,
+    /// Roll back this sink, report it, and keep writing to the others.
+    Continue,
+}
+
+impl std::str::FromStr for SinkErrorPolicy {
+    type Err = ApitapError;
+
+    fn from_str(s: &str) -> Result<Self> {
+        match s.trim().to_ascii_lowercase().as_str() {
+            "fail" => Ok(SinkErrorPolicy::Fail),
+            "continue" => Ok(SinkErrorPolicy::Continue),
+            other => Err(ApitapError::ConfigError(format!(
+                "unknown sink on_error '{other}' (expected fail or continue)"
+            ))),
+        }
+    }
+}
+
+/// One destination of a [`FanOutWriter`].
+pub struct SinkWriter {
+    pub name: String,
+    pub writer: Arc<dyn DataWriter>,
+    /// Mode this sink writes with, whatever mode the run passes in.
+    pub write_mode: WriteMode,
+    pub on_error: SinkErrorPolicy,
+}
+
+/// [`DataWriter`] that forwards every call to several sinks.
+///
+/// # Example
+///
+/// ```no_run
+/// use std::sync::Arc;
+/// use apitap::writer::fanout::{FanOutWriter, SinkErrorPolicy, SinkWriter};
+/// use apitap::writer::{DataWriter, WriteMode};
+///
+/// # fn demo(warehouse: Arc<dyn DataWriter>, archive: Arc<dyn DataWriter>) {
+/// let writer = FanOutWriter::new(vec![
+///     SinkWriter {
+///         name: "warehouse".into(),
+///         writer: warehouse,
+///         write_mode: WriteMode::Merge,
+///         on_error: SinkErrorPolicy::Fail,
+///     },
+///     SinkWriter {
+///         name: "archive".into(),
+///         writer: archive,
+///         write_mode: WriteMode::Append,
+///         on_error: SinkErrorPolicy::Continue,
+///     },
+/// ]);
+/// # }
+/// ```
+pub struct FanOutWriter {
+    sinks: Vec<SinkWriter>,
+    /// Sinks dropped under [`SinkErrorPolicy::Continue`], with their error.
+    failed: Mutex<BTreeMap<String, String>>,
+}
+
+impl FanOutWriter {
+    pub fn new(sinks: Vec<SinkWriter>) -> Self {
+        Self {
+            sinks,
+            failed: Mutex::new(BTreeMap::new()),
+        }
+    }
+
+    /// Best-effort sinks that failed during the run, with their error.
+    pub fn failures(&self) -> Vec<(String, String)> {
+        self.failed
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .iter()
+            .map(|(name, error)| (name.clone(), error.clone()))
+            .collect()
+    }
+
+    /// Sinks still taking part in the run.
+    fn live(&self) -> Vec<&SinkWriter> {
+        let failed = self.failed.lock().unwrap_or_else(|e| e.into_inner());
+        self.sinks
+            .iter()
+            .filter(|s| !failed.contains_key(&s.name))
+            .collect()
+    }
+
+    /// Applies the sink's policy to the outcome of one of its calls.
+    async fn settle(&self, sink: &SinkWriter, result: Result<()>) -> Result<()> {
+        let Err(e) = result else {
+            return Ok(());
+        };
+        match sink.on_error {
+            SinkErrorPolicy::Fail => Err(ApitapError::WriterError(format!(
+                "sink '{}': {e}",
+                sink.name
+            ))),
+            SinkErrorPolicy::Continue => {
+                warn!(sink = %sink.name, error = %e, "❌ sink failed; continuing with the others");
+                if let Err(rb) = sink.writer.rollback().await {
+                    warn!(sink = %sink.name, "rollback of failed sink also failed: {}", rb);
+                }
+                self.failed
+                    .lock()
+                    .unwrap_or_else(|e| e.into_inner())
+                    .insert(sink.name.clone(), e.to_string());
+                Ok(())
+            }
+        }
+    }
+
+    /// Runs `op` on each live sink in turn.
+    async fn each<'a, F, Fut>(&'a self, op: F) -> Result<()>
+    where
+        F: Fn(&'a SinkWriter) -> Fut,
+        Fut: Future<Output = Result<()>> + 'a,
+    {
+        for sink in self.live() {
+            let result = op(sink).await;
+            self.settle(sink, result).await?;
+        }
+        Ok(())
+    }
+
+    /// Copies `result` to every live sink through `write`, concurrently, so
+    /// the rows are read once.
+    async fn tee<'a, F, Fut>(&'a self, result: QueryResultStream, write: F) -> Result<()>
+    where
+        F: Fn(&'a SinkWriter, QueryResultStream) -> Fut,
+        Fut: Future<Output = Result<()>> + 'a,
+    {
+        let QueryResultStream {
+            table_name,
+            data: mut upstream,
+            schema,
+        } = result;
+
+        let live = self.live();
+        let mut senders = Vec::with_capacity(live.len());
+        let mut writes = Vec::with_capacity(live.len());
+        for sink in live {
+            let (tx, rx) = mpsc::channel(FANOUT_BUFFER);
+            senders.push(Some(tx));
+            let stream = QueryResultStream {
+                table_name: table_name.clone(),
+                data: rx.boxed(),
+                schema: schema.clone(),
+            };
+            let write = write(sink, stream);
+            writes.push(async move { (sink, write.await) });
+        }
+
+        let produce = async move {
+            while let Some(item) = upstream.next().await {
+                match item {
+                    Ok(row) => {
+                        for slot in senders.iter_mut() {
+                            // A sink that stopped reading has failed; its write reports why
+                            if let Some(tx) = slot {
+                                if tx.send(Ok(row.clone())).await.is_err() {
+                                    *slot = None;
+                                }
+                            }
+                        }
+                        if senders.iter().all(Option::is_none) {
+                            break;
+                        }
+                    }
+                    Err(e) => {
+                        let message = e.to_string();
+                        for tx in senders.iter_mut().flatten() {
+                            let _ = tx
+                                .send(Err(ApitapError::WriterError(message.clone())))
+                                .await;
+                        }
+                        return Err(e);
+                    }
+                }
+            }
+            Ok(())
+        };
+
+        let (produced, written) = futures::join!(produce, join_all(writes));
+        // An upstream failure isn't the sinks' fault: fail the whole run
+        produced?;
+        for (sink, result) in written {
+            self.settle(sink, result).await?;
+        }
+        Ok(())
+    }
+}
+
+#[async_trait]
+impl DataWriter for FanOutWriter {
+    async fn write(&self, result: QueryResult) -> Result<()> {
+        self.each(|sink| sink.writer.write(result.clone())).await
+    }
+
+    async fn write_stream(&self, result: QueryResultStream, _write_mode: WriteMode) -> Result<()> {
+        self.tee(result, |sink, stream| {
+            sink.writer.write_stream(stream, sink.write_mode.clone())
+        })
+        .await
+    }
+
+    async fn merge(&self, result: QueryResultStream) -> Result<()> {
+        self.tee(result, |sink, stream| sink.writer.merge(stream))
+            .await
+    }
+
+    async fn on_error(&self, error: QueryError) -> Result<()> {
+        self.each(|sink| sink.writer.on_error(error.clone())).await
+    }
+
+    async fn begin(&self) -> Result<()> {
+        self.each(|sink| sink.writer.begin()).await
+    }
+
+    async fn commit(&self) -> Result<()> {
+        // Required sinks first, so a failure there still rolls back the rest
+        let live = self.live();
+        let (required, best_effort): (Vec<_>, Vec<_>) = live
+            .into_iter()
+            .partition(|s| s.on_error == SinkErrorPolicy::Fail);
+        for sink in required.into_iter().chain(best_effort) {
+            let result = sink.writer.commit().await;
+            self.settle(sink, result).await?;
+        }
+        Ok(())
+    }
+
+    async fn rollback(&self) -> Result<()> {
+        let mut first_error = None;
+        for sink in self.live() {
+            if let Err(e) = sink.writer.rollback().await {
+                warn!(sink = %sink.name, "rollback failed: {}", e);
+                first_error.get_or_insert(e);
+            }
+        }
+        first_error.map_or(Ok(()), Err)
+    }
+
+    async fn finish(&self) -> Result<()> {
+        for sink in self.live() {
+            let result = sink.writer.finish().await;
+            self.settle(sink, result).await?;
+        }
+        Ok(())
+    }
+}
@@ -5,7 +5,17 @@ use crate::{
     utils::datafusion_ext::{QueryError, QueryResult, QueryResultStream},
 };
 
+pub mod columns;
+pub mod dead_letter;
+pub mod factory;
+pub mod fanout;
+#[cfg(feature = "object_store")]
+pub mod object_store;
+pub mod post_check;
+#[cfg(feature = "postgres")]
 pub mod postgres;
+#[cfg(feature = "snowflake")]
+pub mod snowflake;
 
 /// Defines how data should be written to the destination.
 ///
@@ -13,6 +23,7 @@ pub mod postgres;
 ///
 /// * `Merge` - Upsert data based on primary key (insert new, update existing)
 /// * `Append` - Always insert new rows without checking for duplicates
+/// * `Insert` - Insert new rows, silently skipping rows whose primary key already exists
 ///
 /// # Example
 ///
@@ -26,6 +37,10 @@ pub mod postgres;
 /// // For append-only logs or events
 /// let mode = WriteMode::Append;
 /// assert_eq!(mode, WriteMode::Append);
+///
+/// // For replay-safe event tables
+/// let mode: WriteMode = "insert".parse().unwrap();
+/// assert_eq!(mode, WriteMode::Insert);
 /// ```
 #[derive(Debug, Clone, PartialEq)]
 pub enum WriteMode {
@@ -33,12 +48,68 @@ pub enum WriteMode {
     Merge,
     /// Append mode: Always insert new records without checking for duplicates
     Append,
+    /// Insert mode: Insert new records, skip ones whose primary key already exists
+    Insert,
+}
+
+impl std::str::FromStr for WriteMode {
+    type Err = crate::errors::ApitapError;
+
+    fn from_str(s: &str) -> Result<Self> {
+        match s.trim().to_ascii_lowercase().as_str() {
+            "merge" => Ok(WriteMode::Merge),
+            "append" => Ok(WriteMode::Append),
+            "insert" => Ok(WriteMode::Insert),
+            other => Err(crate::errors::ApitapError::ConfigError(format!(
+                "unknown write mode '{other}' (expected merge, append, or insert)"
+            ))),
+        }
+    }
+}
+
+/// How a truncate-first run empties the destination table.
+///
+/// # Example
+///
+/// ```
+/// use apitap::writer::TruncateMode;
+///
+/// let mode: TruncateMode = serde_yaml::from_str("truncate_cascade").unwrap();
+/// assert_eq!(mode, TruncateMode::TruncateCascade);
+/// assert_eq!(TruncateMode::default(), TruncateMode::Truncate);
+/// ```
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum TruncateMode {
+    /// `TRUNCATE TABLE`: fastest, but fails on tables referenced by foreign keys.
+    #[default]
+    Truncate,
+    /// `TRUNCATE TABLE ... CASCADE`: also empties every table referencing this one.
+    TruncateCascade,
+    /// `DELETE FROM`: slower, but honours foreign keys and needs only `DELETE`.
+    Delete,
+}
+
+impl std::str::FromStr for TruncateMode {
+    type Err = crate::errors::ApitapError;
+
+    fn from_str(s: &str) -> Result<Self> {
+        match s.trim().to_ascii_lowercase().as_str() {
+            "truncate" => Ok(TruncateMode::Truncate),
+            "truncate_cascade" => Ok(TruncateMode::TruncateCascade),
+            "delete" => Ok(TruncateMode::Delete),
+            other => Err(crate::errors::ApitapError::ConfigError(format!(
+                "unknown truncate_mode '{other}' (expected truncate, truncate_cascade, or delete)"
+            ))),
+        }
+    }
 }
 
 /// Trait defining the interface for writing query results to various destinations.
 ///
 /// Implementations of this trait handle the specifics of writing data to different
-/// storage systems (PostgreSQL, ClickHouse, BigQuery, etc.).
+/// storage systems (PostgreSQL, ClickHouse, BigQuery, etc.). Register a
+/// [`factory::WriterFactory`] to write to a custom writer from a `targets` entry.
 ///
 /// # Key Methods
 ///
@@ -78,6 +149,7 @@ pub enum WriteMode {
 ///         match mode {
 ///             WriteMode::Merge => println!("Merging data..."),
 ///             WriteMode::Append => println!("Appending data..."),
+///             WriteMode::Insert => println!("Inserting new rows..."),
 ///         }
 ///         Ok(())
 ///     }
@@ -181,4 +253,13 @@ pub trait DataWriter: Send + Sync {
     async fn rollback(&self) -> Result<()> {
         Ok(())
     }
+
+    /// Called once after the run's last commit, when every write succeeded.
+    ///
+    /// Unlike [`Self::commit`], which may also run for intermediate commits,
+    /// this marks the end of the run; override it for work such as swapping
+    /// a staging table into place.
+    async fn finish(&self) -> Result<()> {
+        Ok(())
+    }
 }
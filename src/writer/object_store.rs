@@ -0,0 +1,315 @@
+// src/writer/object_store.rs
+
+//! Parquet writer for object stores (S3, GCS, Azure Blob, local files).
+//!
+//! Every `write`/`write_stream` call streams its rows into a new Parquet
+//! object, so a paginated fetch that writes page by page leaves one object per
+//! write under the same table prefix:
+//!
+//! ```text
+//! <prefix>/<table>/<partition>/part-<timestamp>-<id>.parquet
+//! ```
+//!
+//! The partition segment is optional and rendered with
+//! [`substitute_templates`], so `dt={{ current_date() }}` produces
+//! Hive-style date partitions. Uploads use multipart writes, so memory stays
+//! bounded by the batch size rather than the object size.
+//!
+//! Files are zstd-compressed unless the target sets another `compression`.
+//!
+//! Credentials are read from the environment by the `object_store` builders
+//! (`AWS_*`, `GOOGLE_*`, `AZURE_*`), falling back to instance metadata such as
+//! EC2 instance profiles.
+
+use crate::errors::{ApitapError, Result};
+use crate::pipeline::{ParquetCompression, ParquetOptions};
+use crate::utils::datafusion_ext::{QueryResult, QueryResultStream};
+use crate::utils::schema::infer_schema_from_values;
+use crate::utils::template::substitute_templates;
+use crate::writer::{DataWriter, WriteMode};
+use ::object_store::aws::AmazonS3Builder;
+use ::object_store::azure::MicrosoftAzureBuilder;
+use ::object_store::gcp::GoogleCloudStorageBuilder;
+use ::object_store::local::LocalFileSystem;
+use ::object_store::memory::InMemory;
+use ::object_store::path::Path;
+use ::object_store::ObjectStore;
+use async_trait::async_trait;
+use datafusion::arrow::datatypes::SchemaRef;
+use datafusion::arrow::record_batch::RecordBatch;
+use datafusion::parquet::arrow::async_writer::{AsyncArrowWriter, ParquetObjectWriter};
+use datafusion::parquet::basic::{Compression, GzipLevel, ZstdLevel};
+use datafusion::parquet::file::properties::WriterProperties;
+use futures::{StreamExt, TryStreamExt};
+use serde_json::Value;
+use std::sync::Arc;
+use tracing::{info, warn};
+use url::Url;
+
+/// Opens the object store addressed by `url` and returns it with the key prefix.
+///
+/// Supported schemes: `s3://`, `s3a://`, `gs://`, `az://`, `abfs://`,
+/// `abfss://`, `file://`, and `memory://` (in-process, for tests).
+///
+/// # Example
+///
+/// ```
+/// use apitap::writer::object_store::store_from_url;
+///
+/// let (_store, prefix) = store_from_url("memory:///lake/raw").unwrap();
+/// assert_eq!(prefix.as_ref(), "lake/raw");
+/// ```
+pub fn store_from_url(url: &str) -> Result<(Arc<dyn ObjectStore>, Path)> {
+    let parsed = Url::parse(url)?;
+    let prefix = Path::from_url_path(parsed.path())?;
+
+    let store: Arc<dyn ObjectStore> = match parsed.scheme() {
+        "s3" | "s3a" => Arc::new(AmazonS3Builder::from_env().with_url(url).build()?),
+        "gs" => Arc::new(
+            GoogleCloudStorageBuilder::from_env()
+                .with_url(url)
+                .build()?,
+        ),
+        "az" | "abfs" | "abfss" | "azure" => {
+            Arc::new(MicrosoftAzureBuilder::from_env().with_url(url).build()?)
+        }
+        "file" => {
+            // Keys are relative to the directory in the URL
+            let root = parsed
+                .to_file_path()
+                .map_err(|_| ApitapError::ConfigError(format!("invalid file URL '{url}'")))?;
+            std::fs::create_dir_all(&root)?;
+            return Ok((
+                Arc::new(LocalFileSystem::new_with_prefix(root)?),
+                Path::default(),
+            ));
+        }
+        "memory" => Arc::new(InMemory::new()),
+        other => {
+            return Err(ApitapError::ConfigError(format!(
+                "unsupported object store scheme '{other}' in '{url}'"
+            )))
+        }
+    };
+
+    Ok((store, prefix))
+}
+
+/// Maps a target's Parquet settings onto writer properties.
+///
+/// # Errors
+///
+/// Returns a config error if `compression_level` is out of range or set for a
+/// codec without levels.
+///
+/// # Example
+///
+/// ```
+/// use apitap::pipeline::{ParquetCompression, ParquetOptions};
+/// use apitap::writer::object_store::writer_properties;
+///
+/// let props = writer_properties(&ParquetOptions {
+///     compression: ParquetCompression::Gzip,
+///     compression_level: Some(9),
+///     row_group_size: Some(10_000),
+///     ..Default::default()
+/// })
+/// .unwrap();
+/// assert_eq!(props.max_row_group_size(), 10_000);
+/// ```
+pub fn writer_properties(opts: &ParquetOptions) -> Result<WriterProperties> {
+    let level_error = |e: datafusion::parquet::errors::ParquetError| {
+        ApitapError::ConfigError(format!("invalid compression_level: {e}"))
+    };
+    let compression = match (opts.compression, opts.compression_level) {
+        (ParquetCompression::None, None) => Compression::UNCOMPRESSED,
+        (ParquetCompression::Snappy, None) => Compression::SNAPPY,
+        (ParquetCompression::Lz4, None) => Compression::LZ4_RAW,
+        (ParquetCompression::Gzip, level) => Compression::GZIP(match level {
+            Some(l) => GzipLevel::try_new(l.try_into().unwrap_or(u32::MAX)).map_err(level_error)?,
+            None => GzipLevel::default(),
+        }),
+        (ParquetCompression::Zstd, level) => Compression::ZSTD(match level {
+            Some(l) => ZstdLevel::try_new(l).map_err(level_error)?,
+            None => ZstdLevel::default(),
+        }),
+        (codec, Some(_)) => {
+            return Err(ApitapError::ConfigError(format!(
+                "compression_level is not supported for {codec:?} compression"
+            )))
+        }
+    };
+
+    let mut builder = WriterProperties::builder().set_compression(compression);
+    if let Some(rows) = opts.row_group_size {
+        builder = builder.set_max_row_group_size(rows);
+    }
+    if let Some(bytes) = opts.data_page_size {
+        builder = builder.set_data_page_size_limit(bytes);
+    }
+    Ok(builder.build())
+}
+
+pub struct ObjectStoreWriter {
+    pub store: Arc<dyn ObjectStore>,
+    pub prefix: Path,
+    pub table_name: String,
+    pub partition: Option<String>,
+    pub batch_size: usize,
+    pub sample_size: usize,
+    pub parquet: ParquetOptions,
+}
+
+impl ObjectStoreWriter {
+    pub fn new(store: Arc<dyn ObjectStore>, prefix: Path, table_name: impl Into<String>) -> Self {
+        Self {
+            store,
+            prefix,
+            table_name: table_name.into(),
+            partition: None,
+            batch_size: 5000,
+            sample_size: 100,
+            parquet: ParquetOptions::default(),
+        }
+    }
+
+    /// Compression and row-group/page sizing of the written files.
+    pub fn with_parquet(mut self, options: ParquetOptions) -> Self {
+        self.parquet = options;
+        self
+    }
+
+    /// Partition template appended under the table, e.g. `dt={{ current_date() }}`.
+    pub fn with_partition(mut self, template: impl Into<Option<String>>) -> Self {
+        self.partition = template.into();
+        self
+    }
+
+    pub fn with_batch_size(mut self, size: usize) -> Self {
+        self.batch_size = size;
+        self
+    }
+
+    pub fn with_sample_size(mut self, size: usize) -> Self {
+        self.sample_size = size;
+        self
+    }
+
+    /// Directory that holds every object written for this table.
+    pub fn table_prefix(&self) -> Path {
+        self.table_name
+            .split('.')
+            .fold(self.prefix.clone(), |p, part| p.child(part))
+    }
+
+    /// Renders the key for a new object under the table (and partition).
+    pub fn object_path(&self) -> Result<Path> {
+        let mut path = self.table_prefix();
+        if let Some(template) = &self.partition {
+            for segment in substitute_templates(template)?.split('/') {
+                if !segment.is_empty() {
+                    path = path.child(segment);
+                }
+            }
+        }
+        let file = format!(
+            "part-{}-{}.parquet",
+            chrono::Utc::now().format("%Y%m%dT%H%M%S"),
+            nanoid::nanoid!(8)
+        );
+        Ok(path.child(file))
+    }
+
+    /// Deletes every object under the table prefix.
+    pub async fn truncate(&self) -> Result<()> {
+        let prefix = self.table_prefix();
+        info!(prefix = %prefix, "deleting objects under table prefix");
+        let locations = self
+            .store
+            .list(Some(&prefix))
+            .map_ok(|m| m.location)
+            .boxed();
+        self.store
+            .delete_stream(locations)
+            .try_collect::<Vec<_>>()
+            .await?;
+        Ok(())
+    }
+
+    fn to_batch(rows: &[Value], schema: &SchemaRef) -> Result<RecordBatch> {
+        Ok(serde_arrow::to_record_batch(schema.fields(), &rows)?)
+    }
+
+    async fn write_rows<S>(&self, mut rows: S) -> Result<usize>
+    where
+        S: futures::Stream<Item = Result<Value>> + Unpin,
+    {
+        let mut buf: Vec<Value> = Vec::with_capacity(self.batch_size);
+        let mut writer: Option<(AsyncArrowWriter<ParquetObjectWriter>, SchemaRef)> = None;
+        let mut total = 0usize;
+        let path = self.object_path()?;
+        let props = writer_properties(&self.parquet)?;
+
+        loop {
+            let item = rows.next().await;
+            let done = item.is_none();
+            if let Some(item) = item {
+                buf.push(item?);
+            }
+
+            if buf.len() >= self.batch_size || (done && !buf.is_empty()) {
+                if writer.is_none() {
+                    let sample = &buf[..buf.len().min(self.sample_size)];
+                    let schema = infer_schema_from_values(sample)?;
+                    let object = ParquetObjectWriter::new(Arc::clone(&self.store), path.clone());
+                    writer = Some((
+                        AsyncArrowWriter::try_new(object, schema.clone(), Some(props.clone()))?,
+                        schema,
+                    ));
+                }
+                let (w, schema) = writer.as_mut().expect("writer just set");
+                w.write(&Self::to_batch(&buf, schema)?).await?;
+                total += buf.len();
+                buf.clear();
+            }
+
+            if done {
+                break;
+            }
+        }
+
+        match writer {
+            Some((w, _)) => {
+                w.close().await?;
+                info!(path = %path, rows = total, "wrote parquet object");
+            }
+            None => info!(table = %self.table_name, "no rows; skipping parquet object"),
+        }
+        Ok(total)
+    }
+}
+
+#[async_trait]
+impl DataWriter for ObjectStoreWriter {
+    async fn write_stream(&self, result: QueryResultStream, write_mode: WriteMode) -> Result<()> {
+        if write_mode != WriteMode::Append {
+            warn!(table = %self.table_name, mode = ?write_mode, "object store sinks are append-only; writing a new object");
+        }
+        self.write_rows(result.data).await?;
+        Ok(())
+    }
+
+    async fn write(&self, result: QueryResult) -> Result<()> {
+        let rows = match result.data {
+            Value::Array(rows) => rows,
+            _ => {
+                return Err(ApitapError::PipelineError(
+                    "Expected JSON array".to_string(),
+                ))
+            }
+        };
+        self.write_rows(futures::stream::iter(rows.into_iter().map(Ok)))
+            .await?;
+        Ok(())
+    }
+}
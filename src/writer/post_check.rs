@@ -0,0 +1,212 @@
+//! Assertion queries run against the destination after a load.
+//!
+//! A check is a SQL query plus, optionally, a condition its single value
+//! must meet; without `expect` the query must return no rows. `{table}` in
+//! the query names the table the run loaded. Checks are declared on a
+//! Postgres target, for every table it loads, or per module:
+//!
+//! ```yaml
+//! targets:
+//!   - type: postgres
+//!     name: warehouse
+//!     post_checks:
+//!       - sql: SELECT count(*) FROM {table}
+//!         expect: "> 0"
+//! ```
+//!
+//! ```sql
+//! {{ post_check("SELECT id FROM {table} WHERE id IS NULL") }}
+//! ```
+//!
+//! Checks run once the run's transaction is committed and fail the module.
+//! With `swap`, they run on the staging table instead, before it is swapped
+//! in; a failing check drops it and leaves the live table as it was.
+
+use std::fmt;
+use std::str::FromStr;
+
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+use crate::errors::{ApitapError, Result};
+
+/// One assertion query.
+///
+/// # Example
+///
+/// ```
+/// use apitap::writer::post_check::PostCheck;
+/// use serde_json::json;
+///
+/// let check = PostCheck {
+///     sql: "SELECT count(*) FROM {table};".into(),
+///     expect: Some("> 0".parse().unwrap()),
+/// };
+/// assert_eq!(check.sql_for("public.users"), "SELECT count(*) FROM public.users");
+/// assert!(check.verify("public.users", Some(&json!({"count": 3}))).is_ok());
+/// assert!(check.verify("public.users", Some(&json!({"count": 0}))).is_err());
+/// ```
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct PostCheck {
+    /// The query; `{table}` is replaced by the loaded table.
+    pub sql: String,
+    /// Condition the query's single value must meet; `None` expects no rows.
+    #[serde(default)]
+    pub expect: Option<Expectation>,
+}
+
+impl PostCheck {
+    /// `sql` for `table`, without trailing `;` so it can be nested.
+    pub fn sql_for(&self, table: &str) -> String {
+        self.sql
+            .replace("{table}", table)
+            .trim_end()
+            .trim_end_matches(';')
+            .trim_end()
+            .to_string()
+    }
+
+    /// Judges the first row of the query, as a JSON object of its columns,
+    /// or `rows`, the number of rows it returned, when there is no `expect`.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`ApitapError::PostCheck`] saying what the query returned
+    /// when the check fails.
+    pub fn verify(&self, table: &str, first_row: Option<&Value>) -> Result<()> {
+        let fail = |got: String| {
+            ApitapError::PostCheck(format!("{table}: `{}` {got}", self.sql_for(table)))
+        };
+        let Some(expect) = &self.expect else {
+            return match first_row {
+                None => Ok(()),
+                Some(row) => Err(fail(format!("returned rows (expected none), first: {row}"))),
+            };
+        };
+        let value = match first_row {
+            None => return Err(fail(format!("returned no rows (expected {expect})"))),
+            Some(Value::Object(columns)) if columns.len() == 1 => {
+                columns.values().next().cloned().unwrap_or(Value::Null)
+            }
+            Some(row) => {
+                return Err(fail(format!(
+                    "must return a single column when expect is set, got {row}"
+                )))
+            }
+        };
+        let number = match &value {
+            Value::Number(n) => n.as_f64(),
+            Value::String(s) => s.trim().parse::<f64>().ok(),
+            Value::Bool(b) => Some(f64::from(u8::from(*b))),
+            _ => None,
+        };
+        match number {
+            Some(n) if expect.holds(n) => Ok(()),
+            _ => Err(fail(format!("returned {value} (expected {expect})"))),
+        }
+    }
+}
+
+/// How a check's value is compared with the expected one.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Comparison {
+    Gt,
+    Ge,
+    Lt,
+    Le,
+    Eq,
+    Ne,
+}
+
+impl Comparison {
+    fn symbol(self) -> &'static str {
+        match self {
+            Comparison::Gt => ">",
+            Comparison::Ge => ">=",
+            Comparison::Lt => "<",
+            Comparison::Le => "<=",
+            Comparison::Eq => "=",
+            Comparison::Ne => "!=",
+        }
+    }
+}
+
+/// A check's `expect`, such as `"> 0"` or `"= 0"`.
+///
+/// ```
+/// use apitap::writer::post_check::Expectation;
+///
+/// let expect: Expectation = ">= 100".parse().unwrap();
+/// assert!(expect.holds(100.0));
+/// assert!(!expect.holds(99.5));
+/// assert!("about 3".parse::<Expectation>().is_err());
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(try_from = "String", into = "String")]
+pub struct Expectation {
+    pub op: Comparison,
+    pub value: f64,
+}
+
+impl Expectation {
+    /// Whether `actual` meets the condition.
+    pub fn holds(&self, actual: f64) -> bool {
+        match self.op {
+            Comparison::Gt => actual > self.value,
+            Comparison::Ge => actual >= self.value,
+            Comparison::Lt => actual < self.value,
+            Comparison::Le => actual <= self.value,
+            Comparison::Eq => actual == self.value,
+            Comparison::Ne => actual != self.value,
+        }
+    }
+}
+
+impl FromStr for Expectation {
+    type Err = ApitapError;
+
+    fn from_str(s: &str) -> Result<Self> {
+        let trimmed = s.trim();
+        // Two-character operators first, so ">=" isn't read as ">"
+        let ops = [
+            (">=", Comparison::Ge),
+            ("<=", Comparison::Le),
+            ("!=", Comparison::Ne),
+            ("<>", Comparison::Ne),
+            ("==", Comparison::Eq),
+            (">", Comparison::Gt),
+            ("<", Comparison::Lt),
+            ("=", Comparison::Eq),
+        ];
+        ops.iter()
+            .find_map(|(symbol, op)| {
+                let value = trimmed.strip_prefix(symbol)?.trim().parse().ok()?;
+                Some(Expectation { op: *op, value })
+            })
+            .ok_or_else(|| {
+                ApitapError::ConfigError(format!(
+                    "unknown post_check expect '{s}' (expected a comparison with a number, e.g. '> 0')"
+                ))
+            })
+    }
+}
+
+impl TryFrom<String> for Expectation {
+    type Error = ApitapError;
+
+    fn try_from(s: String) -> Result<Self> {
+        s.parse()
+    }
+}
+
+impl From<Expectation> for String {
+    fn from(expect: Expectation) -> String {
+        expect.to_string()
+    }
+}
+
+impl fmt::Display for Expectation {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} {}", self.op.symbol(), self.value)
+    }
+}
@@ -1,15 +1,23 @@
 // src/utils/postgres_writer.rs
 
 use crate::errors::{ApitapError, Result};
-use crate::utils::datafusion_ext::{QueryResult, QueryResultStream};
-use crate::writer::{DataWriter, WriteMode};
+use crate::utils::datafusion_ext::{QueryError, QueryResult, QueryResultStream};
+use crate::utils::http_retry::JitteredBackoff;
+use crate::writer::post_check::PostCheck;
+use crate::writer::{DataWriter, TruncateMode, WriteMode};
 use async_trait::async_trait;
+use chrono::{DateTime, Duration, NaiveDate, SecondsFormat};
+use datafusion::arrow::datatypes::{DataType, Schema, TimeUnit};
 use serde_json::Value;
-use sqlx::{types::Json, PgPool};
-use std::borrow::Cow;
+use sqlx::pool::PoolConnection;
+use sqlx::postgres::{PgArguments, PgQueryResult};
+use sqlx::query::Query;
+use sqlx::{types::Json, PgPool, Postgres};
 use std::collections::BTreeMap;
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+use std::sync::Arc;
 use tokio_stream::StreamExt;
-use tracing::{debug, debug_span, info};
+use tracing::{debug, debug_span, info, warn};
 
 //=============== Type Definitions ============================================//
 
@@ -20,16 +28,83 @@ pub enum PgType {
     BigInt,
     Double,
     Jsonb,
+    Timestamptz,
+    Date,
+    Numeric { precision: u8, scale: i8 },
 }
 
 impl PgType {
-    pub fn as_sql(&self) -> &'static str {
+    pub fn as_sql(&self) -> String {
         match self {
-            PgType::Text => "TEXT",
-            PgType::Boolean => "BOOLEAN",
-            PgType::BigInt => "BIGINT",
-            PgType::Double => "DOUBLE PRECISION",
-            PgType::Jsonb => "JSONB",
+            PgType::Text => "TEXT".to_string(),
+            PgType::Boolean => "BOOLEAN".to_string(),
+            PgType::BigInt => "BIGINT".to_string(),
+            PgType::Double => "DOUBLE PRECISION".to_string(),
+            PgType::Jsonb => "JSONB".to_string(),
+            PgType::Timestamptz => "TIMESTAMPTZ".to_string(),
+            PgType::Date => "DATE".to_string(),
+            PgType::Numeric { precision, scale } => format!("NUMERIC({precision},{scale})"),
+        }
+    }
+
+    /// Cast appended to a bind placeholder for types that are bound as text.
+    pub fn placeholder_cast(&self) -> &'static str {
+        match self {
+            PgType::Timestamptz => "::TIMESTAMPTZ",
+            PgType::Date => "::DATE",
+            PgType::Numeric { .. } => "::NUMERIC",
+            _ => "",
+        }
+    }
+
+    /// Maps an Arrow column type to the Postgres type used by `auto_create`.
+    ///
+    /// Integers map to `BIGINT`, floats to `DOUBLE PRECISION`, timestamps to
+    /// `TIMESTAMPTZ` (zone-less timestamps are taken as UTC), decimals keep
+    /// their precision and scale, and nested types become `JSONB`. Anything
+    /// else falls back to `TEXT`.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use apitap::writer::postgres::PgType;
+    /// use datafusion::arrow::datatypes::DataType;
+    ///
+    /// assert_eq!(PgType::from_arrow(&DataType::Int64), PgType::BigInt);
+    /// assert_eq!(
+    ///     PgType::from_arrow(&DataType::Decimal128(12, 2)).as_sql(),
+    ///     "NUMERIC(12,2)"
+    /// );
+    /// ```
+    pub fn from_arrow(data_type: &DataType) -> Self {
+        match data_type {
+            DataType::Boolean => PgType::Boolean,
+            DataType::Int8
+            | DataType::Int16
+            | DataType::Int32
+            | DataType::Int64
+            | DataType::UInt8
+            | DataType::UInt16
+            | DataType::UInt32
+            | DataType::UInt64 => PgType::BigInt,
+            DataType::Float16 | DataType::Float32 | DataType::Float64 => PgType::Double,
+            DataType::Timestamp(_, _) => PgType::Timestamptz,
+            DataType::Date32 | DataType::Date64 => PgType::Date,
+            DataType::Decimal128(precision, scale) | DataType::Decimal256(precision, scale) => {
+                PgType::Numeric {
+                    precision: *precision,
+                    scale: *scale,
+                }
+            }
+            DataType::List(_)
+            | DataType::LargeList(_)
+            | DataType::FixedSizeList(_, _)
+            | DataType::ListView(_)
+            | DataType::LargeListView(_)
+            | DataType::Struct(_)
+            | DataType::Map(_, _) => PgType::Jsonb,
+            DataType::Dictionary(_, value) => Self::from_arrow(value),
+            _ => PgType::Text,
         }
     }
 
@@ -62,6 +137,125 @@ impl PgType {
     }
 }
 
+/// Renders an Arrow timestamp or date that reached JSON as an epoch number.
+///
+/// Record batches serialize `Timestamp` as an integer in the column's unit and
+/// `Date32` as days since the epoch; Postgres needs text it can cast. Returns
+/// `None` for non-temporal types or values that are not integers.
+///
+/// # Example
+///
+/// ```
+/// use apitap::writer::postgres::temporal_to_text;
+/// use datafusion::arrow::datatypes::{DataType, TimeUnit};
+/// use serde_json::json;
+///
+/// let ts = DataType::Timestamp(TimeUnit::Millisecond, None);
+/// assert_eq!(
+///     temporal_to_text(&json!(1704164645000_i64), &ts).as_deref(),
+///     Some("2024-01-02T03:04:05Z")
+/// );
+/// assert_eq!(
+///     temporal_to_text(&json!(19724), &DataType::Date32).as_deref(),
+///     Some("2024-01-02")
+/// );
+/// ```
+pub fn temporal_to_text(value: &Value, data_type: &DataType) -> Option<String> {
+    let v = value.as_i64()?;
+    match data_type {
+        DataType::Timestamp(unit, _) => {
+            let ts = match unit {
+                TimeUnit::Second => DateTime::from_timestamp(v, 0)?,
+                TimeUnit::Millisecond => DateTime::from_timestamp_millis(v)?,
+                TimeUnit::Microsecond => DateTime::from_timestamp_micros(v)?,
+                TimeUnit::Nanosecond => DateTime::from_timestamp_nanos(v),
+            };
+            Some(ts.to_rfc3339_opts(SecondsFormat::AutoSi, true))
+        }
+        DataType::Date32 => {
+            let epoch = NaiveDate::from_ymd_opt(1970, 1, 1)?;
+            Some(epoch.checked_add_signed(Duration::days(v))?.to_string())
+        }
+        DataType::Date64 => Some(DateTime::from_timestamp_millis(v)?.date_naive().to_string()),
+        _ => None,
+    }
+}
+
+/// How far a failed write got, which decides whether it can be retried.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum WriteFailure {
+    /// Retrying can't help, e.g. a constraint violation or a type error.
+    Permanent,
+    /// The statement lost a race (deadlock, serialization failure, lock
+    /// timeout); the connection is still usable.
+    Statement,
+    /// The connection broke, taking any open transaction with it.
+    Connection,
+}
+
+impl WriteFailure {
+    /// Classifies a Postgres SQLSTATE code.
+    ///
+    /// ```
+    /// use apitap::writer::postgres::WriteFailure;
+    ///
+    /// assert_eq!(WriteFailure::from_sqlstate("40P01"), WriteFailure::Statement);
+    /// assert_eq!(WriteFailure::from_sqlstate("08006"), WriteFailure::Connection);
+    /// assert_eq!(WriteFailure::from_sqlstate("23505"), WriteFailure::Permanent);
+    /// ```
+    pub fn from_sqlstate(code: &str) -> Self {
+        match code {
+            // serialization_failure, deadlock_detected, lock_not_available
+            "40001" | "40P01" | "55P03" => WriteFailure::Statement,
+            // connection_exception class, admin/crash shutdown, cannot_connect_now
+            c if c.starts_with("08") => WriteFailure::Connection,
+            "57P01" | "57P02" | "57P03" => WriteFailure::Connection,
+            _ => WriteFailure::Permanent,
+        }
+    }
+
+    /// Classifies an error returned by a write.
+    pub fn of(err: &ApitapError) -> Self {
+        let ApitapError::Sqlx(err) = err else {
+            return WriteFailure::Permanent;
+        };
+        match err {
+            sqlx::Error::Database(db) => db
+                .code()
+                .map_or(WriteFailure::Permanent, |c| Self::from_sqlstate(&c)),
+            sqlx::Error::Io(_) | sqlx::Error::PoolTimedOut => WriteFailure::Connection,
+            _ => WriteFailure::Permanent,
+        }
+    }
+}
+
+/// Whether a SQLSTATE code blames the data of a row: a data exception (class
+/// 22, e.g. a value the column type rejects) or an integrity constraint
+/// violation (class 23).
+///
+/// ```
+/// use apitap::writer::postgres::is_row_error_code;
+///
+/// assert!(is_row_error_code("22P02")); // invalid_text_representation
+/// assert!(is_row_error_code("23502")); // not_null_violation
+/// assert!(!is_row_error_code("42P01")); // undefined_table
+/// assert!(!is_row_error_code("40P01")); // deadlock_detected
+/// ```
+pub fn is_row_error_code(code: &str) -> bool {
+    code.starts_with("22") || code.starts_with("23")
+}
+
+/// Whether a write failed because of the data of some row, so writing the
+/// rows one at a time isolates it.
+pub fn is_row_error(err: &ApitapError) -> bool {
+    match err {
+        ApitapError::Sqlx(sqlx::Error::Database(db)) => {
+            db.code().is_some_and(|c| is_row_error_code(&c))
+        }
+        _ => false,
+    }
+}
+
 //=============== PostgreSQL Auto-Columns Writer ==============================//
 
 #[derive(Debug, Clone)]
@@ -81,6 +275,12 @@ impl PrimaryKey {
     }
 }
 
+/// Most bind parameters Postgres accepts in one statement.
+pub const MAX_BIND_PARAMS: usize = 65_535;
+
+/// Session-local table that chunks too large for one statement are merged from.
+const MERGE_STAGING_TABLE: &str = "apitap_merge_batch";
+
 pub struct PostgresWriter {
     pool: PgPool,
     pub table_name: String,
@@ -88,9 +288,38 @@ pub struct PostgresWriter {
     pub sample_size: usize,
     pub auto_create: bool,
     pub auto_truncate: bool,
+    /// Statement the truncate hook runs.
+    pub truncate_mode: TruncateMode,
     columns_cache: tokio::sync::RwLock<Option<BTreeMap<String, PgType>>>,
     pub primary_key: Option<String>,
     version_cache: tokio::sync::RwLock<Option<PostgresVersion>>,
+    /// Commit (and reopen) the transaction after this many rows; `None` keeps one transaction.
+    pub commit_every: Option<usize>,
+    /// Connection holding the open transaction between `begin` and `commit`/`rollback`.
+    tx_conn: tokio::sync::Mutex<Option<PoolConnection<Postgres>>>,
+    /// Held by each chunk write in a transaction, from its savepoint to its
+    /// release, so concurrent writes don't interleave savepoints on `tx_conn`.
+    write_lock: tokio::sync::Mutex<()>,
+    /// [`Self::truncate`] was called outside a transaction; the next `begin`
+    /// runs it first.
+    truncate_pending: AtomicBool,
+    rows_since_commit: AtomicUsize,
+    rows_committed: AtomicUsize,
+    /// Columns whose string values hold serialized JSON; stored as `JSONB`.
+    pub json_columns: Vec<String>,
+    /// Explicit Postgres types (e.g. `NUMERIC(12,2)`, `UUID`) that replace the inferred ones.
+    pub column_types: BTreeMap<String, String>,
+    /// Backoff for retrying batches that hit a transient error; `None` fails at once.
+    pub write_retry: Option<JitteredBackoff>,
+    /// With `swap`, the table that [`DataWriter::finish`] replaces; rows are
+    /// written to `table_name`, its staging table.
+    pub swap_into: Option<String>,
+    staging_ready: AtomicBool,
+    /// Receives, through [`DataWriter::on_error`], rows that fail on their
+    /// own data; `None` fails the batch instead.
+    pub dead_letter: Option<Arc<dyn DataWriter>>,
+    /// Assertion queries [`DataWriter::finish`] runs on the loaded table.
+    pub post_checks: Vec<PostCheck>,
 }
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
@@ -164,9 +393,23 @@ impl PostgresWriter {
             sample_size: 10,
             auto_create: true,
             auto_truncate: false,
+            truncate_mode: TruncateMode::default(),
             columns_cache: tokio::sync::RwLock::new(None),
             primary_key: None,
             version_cache: tokio::sync::RwLock::new(None),
+            commit_every: None,
+            tx_conn: tokio::sync::Mutex::new(None),
+            write_lock: tokio::sync::Mutex::new(()),
+            truncate_pending: AtomicBool::new(false),
+            rows_since_commit: AtomicUsize::new(0),
+            rows_committed: AtomicUsize::new(0),
+            json_columns: Vec::new(),
+            column_types: BTreeMap::new(),
+            write_retry: None,
+            swap_into: None,
+            staging_ready: AtomicBool::new(false),
+            dead_letter: None,
+            post_checks: Vec::new(),
         }
     }
 
@@ -195,17 +438,162 @@ impl PostgresWriter {
         self
     }
 
+    /// Retries batches that fail with a deadlock, serialization failure, or
+    /// lock timeout. Inside a transaction each attempt runs under a savepoint
+    /// that is rolled back before the next; a dropped connection is only
+    /// retried outside one, since the transaction is lost with it.
+    pub fn with_write_retry(mut self, backoff: Option<JitteredBackoff>) -> Self {
+        self.write_retry = backoff;
+        self
+    }
+
+    pub fn with_truncate_mode(mut self, mode: TruncateMode) -> Self {
+        self.truncate_mode = mode;
+        self
+    }
+
+    /// Writes to [`Self::staging_table`] instead, which replaces the table
+    /// when the run finishes, so readers never see a partial load.
+    pub fn with_swap(mut self, enabled: bool) -> Self {
+        if enabled && self.swap_into.is_none() {
+            let staging = Self::staging_table(&self.table_name);
+            self.swap_into = Some(std::mem::replace(&mut self.table_name, staging));
+        }
+        self
+    }
+
+    /// Runs `checks` once the run finishes; with `swap`, on the staging
+    /// table before it replaces the live one.
+    pub fn with_post_checks(mut self, checks: Vec<PostCheck>) -> Self {
+        self.post_checks = checks;
+        self
+    }
+
+    /// Runs every post check against `table`, stopping at the first failure.
+    async fn run_post_checks(&self, table: &str) -> Result<()> {
+        let quoted = Self::quote_ident_path(table);
+        for check in &self.post_checks {
+            let sql = format!(
+                "SELECT to_jsonb(apitap_check) FROM ({}) AS apitap_check LIMIT 1",
+                check.sql_for(&quoted)
+            );
+            let first_row: Option<Value> =
+                sqlx::query_scalar(&sql).fetch_optional(&self.pool).await?;
+            check.verify(table, first_row.as_ref())?;
+        }
+        if !self.post_checks.is_empty() {
+            info!(table = %table, checks = self.post_checks.len(), "✅ post checks passed");
+        }
+        Ok(())
+    }
+
+    /// Commits every `rows` written rows instead of once at the end.
+    ///
+    /// Trades all-or-nothing atomicity for shorter transactions: on failure,
+    /// rows from earlier checkpoints stay committed.
+    pub fn with_commit_every(mut self, rows: impl Into<Option<usize>>) -> Self {
+        self.commit_every = rows.into().filter(|n| *n > 0);
+        self
+    }
+
+    /// Sends rows that fail on their own data (see [`is_row_error`]) to
+    /// `writer` and keeps the rest of their batch.
+    pub fn with_dead_letter(mut self, writer: Option<Arc<dyn DataWriter>>) -> Self {
+        self.dead_letter = writer;
+        self
+    }
+
+    /// Treats string values in `columns` as serialized JSON and stores them as `JSONB`.
+    pub fn with_json_columns(mut self, columns: Vec<String>) -> Self {
+        self.json_columns = columns;
+        self
+    }
+
+    /// Overrides the inferred Postgres type of individual columns.
+    ///
+    /// Overridden columns are bound as text and cast to the given type, so any
+    /// type Postgres can parse from text works.
+    pub fn with_column_types(mut self, column_types: BTreeMap<String, String>) -> Self {
+        self.column_types = column_types;
+        self
+    }
+
+    /// Parses serialized JSON in [`Self::json_columns`]; unparsable strings are kept as-is.
+    pub fn decode_json_columns(&self, mut row: Value) -> Value {
+        if let Value::Object(obj) = &mut row {
+            for col in &self.json_columns {
+                if let Some(v) = obj.get_mut(col) {
+                    if let Some(parsed) = v.as_str().and_then(|s| serde_json::from_str(s).ok()) {
+                        *v = parsed;
+                    }
+                }
+            }
+        }
+        row
+    }
+
+    /// Rows made durable by intermediate commits so far.
+    pub fn rows_committed(&self) -> usize {
+        self.rows_committed.load(Ordering::Relaxed)
+    }
+
+    /// Runs `q` on the open transaction if there is one, otherwise on the pool.
+    async fn execute_query<'q>(
+        &self,
+        q: Query<'q, Postgres, PgArguments>,
+    ) -> Result<PgQueryResult> {
+        let mut tx = self.tx_conn.lock().await;
+        let res = match tx.as_mut() {
+            Some(conn) => q.execute(&mut **conn).await?,
+            None => q.execute(&self.pool).await?,
+        };
+        Ok(res)
+    }
+
+    /// Counts `rows` towards `commit_every` and commits once the threshold is reached.
+    async fn checkpoint(&self, rows: usize) -> Result<()> {
+        let Some(every) = self.commit_every else {
+            return Ok(());
+        };
+        let pending = self.rows_since_commit.fetch_add(rows, Ordering::Relaxed) + rows;
+        if pending < every {
+            return Ok(());
+        }
+
+        // Not while another chunk is inside its savepoint
+        let _turn = self.write_lock.lock().await;
+        let mut tx = self.tx_conn.lock().await;
+        if let Some(conn) = tx.as_mut() {
+            sqlx::query("COMMIT").execute(&mut **conn).await?;
+            sqlx::query("BEGIN").execute(&mut **conn).await?;
+            self.rows_since_commit.store(0, Ordering::Relaxed);
+            let total = self.rows_committed.fetch_add(pending, Ordering::Relaxed) + pending;
+            info!(table = %self.table_name, rows_committed = total, "intermediate commit");
+        }
+        Ok(())
+    }
+
     async fn table_exists(&self) -> Result<bool> {
-        let result: (bool,) = sqlx::query_as(
+        self.table_exists_named(&self.table_name).await
+    }
+
+    async fn table_exists_named(&self, name: &str) -> Result<bool> {
+        let (schema, table) = Self::split_table(name);
+        let q = sqlx::query_as(
             "SELECT EXISTS (
                 SELECT FROM information_schema.tables 
-                WHERE table_schema = 'public' 
-                AND table_name = $1
+                WHERE table_schema = COALESCE($1, current_schema()) 
+                AND table_name = $2
             )",
         )
-        .bind(&self.table_name)
-        .fetch_one(&self.pool)
-        .await?;
+        .bind(schema)
+        .bind(table);
+        // On the transaction's connection, which also sees tables it created
+        let mut tx = self.tx_conn.lock().await;
+        let result: (bool,) = match tx.as_mut() {
+            Some(conn) => q.fetch_one(&mut **conn).await?,
+            None => q.fetch_one(&self.pool).await?,
+        };
 
         Ok(result.0)
     }
@@ -235,12 +623,178 @@ impl PostgresWriter {
         Ok(final_types)
     }
 
+    /// Column types for an Arrow schema, via [`PgType::from_arrow`].
+    pub fn schema_from_arrow(schema: &Schema) -> BTreeMap<String, PgType> {
+        schema
+            .fields()
+            .iter()
+            .map(|f| (f.name().clone(), PgType::from_arrow(f.data_type())))
+            .collect()
+    }
+
     pub fn quote_ident(ident: &str) -> String {
         // "foo"  -> "\"foo\""
         // handle embedded quotes: foo"bar -> "foo""bar"
         format!(r#""{}""#, ident.replace('"', r#""""#))
     }
 
+    /// Splits `schema.table` into its schema and table; an unqualified name
+    /// has no schema and resolves through the `search_path` (`public` by default).
+    ///
+    /// ```
+    /// use apitap::writer::postgres::PostgresWriter;
+    ///
+    /// assert_eq!(PostgresWriter::split_table("analytics.users"), (Some("analytics"), "users"));
+    /// assert_eq!(PostgresWriter::split_table("users"), (None, "users"));
+    /// ```
+    pub fn split_table(name: &str) -> (Option<&str>, &str) {
+        match name.rsplit_once('.') {
+            Some((schema, table)) => (Some(schema), table),
+            None => (None, name),
+        }
+    }
+
+    /// Table a `swap` run loads before replacing `table`.
+    ///
+    /// ```
+    /// use apitap::writer::postgres::PostgresWriter;
+    ///
+    /// assert_eq!(PostgresWriter::staging_table("analytics.users"), "analytics.users__staging");
+    /// ```
+    pub fn staging_table(table: &str) -> String {
+        format!("{table}__staging")
+    }
+
+    /// Statements that replace `table` with `staging` (same schema), run in
+    /// one transaction.
+    ///
+    /// ```
+    /// use apitap::writer::postgres::PostgresWriter;
+    ///
+    /// assert_eq!(
+    ///     PostgresWriter::swap_sql("analytics.users__staging", "analytics.users"),
+    ///     [
+    ///         r#"DROP TABLE IF EXISTS "analytics"."users""#,
+    ///         r#"ALTER TABLE "analytics"."users__staging" RENAME TO "users""#,
+    ///     ]
+    /// );
+    /// ```
+    pub fn swap_sql(staging: &str, table: &str) -> [String; 2] {
+        let (_, bare) = Self::split_table(table);
+        [
+            format!("DROP TABLE IF EXISTS {}", Self::quote_ident_path(table)),
+            format!(
+                "ALTER TABLE {} RENAME TO {}",
+                Self::quote_ident_path(staging),
+                Self::quote_ident(bare)
+            ),
+        ]
+    }
+
+    /// `GRANT` that gives `grantee` (already quoted, or `PUBLIC`) `privilege`
+    /// on `table`.
+    ///
+    /// ```
+    /// use apitap::writer::postgres::PostgresWriter;
+    ///
+    /// assert_eq!(
+    ///     PostgresWriter::grant_sql("analytics.users__staging", "SELECT", "dashboards", true),
+    ///     r#"GRANT SELECT ON "analytics"."users__staging" TO dashboards WITH GRANT OPTION"#
+    /// );
+    /// ```
+    pub fn grant_sql(table: &str, privilege: &str, grantee: &str, grantable: bool) -> String {
+        let option = if grantable { " WITH GRANT OPTION" } else { "" };
+        format!(
+            "GRANT {privilege} ON {} TO {grantee}{option}",
+            Self::quote_ident_path(table)
+        )
+    }
+
+    /// Statements that give `staging` the owner and privileges of `table`,
+    /// run before the swap, and those that rename its indexes (and the
+    /// constraints behind them) to the names they have on `table`, run after
+    /// it. `LIKE ... INCLUDING ALL` copies neither.
+    async fn carry_over_sql(
+        conn: &mut sqlx::PgConnection,
+        staging: &str,
+        table: &str,
+    ) -> Result<(Vec<String>, Vec<String>)> {
+        let staging_sql = Self::quote_ident_path(staging);
+        let table_sql = Self::quote_ident_path(table);
+        let owner: Option<(String,)> = sqlx::query_as(
+            "SELECT relowner::regrole::text FROM pg_class WHERE oid = to_regclass($1)",
+        )
+        .bind(&table_sql)
+        .fetch_optional(&mut *conn)
+        .await?;
+        let Some((owner,)) = owner else {
+            return Ok((Vec::new(), Vec::new()));
+        };
+        let grants: Vec<(String, String, bool)> = sqlx::query_as(
+            "SELECT CASE WHEN a.grantee = 0 THEN 'PUBLIC' ELSE a.grantee::regrole::text END,
+                    a.privilege_type, a.is_grantable
+             FROM pg_class c, aclexplode(c.relacl) a
+             WHERE c.oid = to_regclass($1) AND a.grantee <> c.relowner",
+        )
+        .bind(&table_sql)
+        .fetch_all(&mut *conn)
+        .await?;
+        // Indexes are matched on their definition after the table name
+        let renames: Vec<(String, String)> = sqlx::query_as(
+            "SELECT DISTINCT ON (s.indexrelid) s.indexrelid::regclass::text, quote_ident(lc.relname)
+             FROM pg_index s
+             JOIN pg_index l ON l.indrelid = to_regclass($2)
+             JOIN pg_class lc ON lc.oid = l.indexrelid
+             WHERE s.indrelid = to_regclass($1)
+               AND s.indisunique = l.indisunique
+               AND regexp_replace(pg_get_indexdef(s.indexrelid), '^.*? USING ', '')
+                 = regexp_replace(pg_get_indexdef(l.indexrelid), '^.*? USING ', '')",
+        )
+        .bind(&staging_sql)
+        .bind(&table_sql)
+        .fetch_all(&mut *conn)
+        .await?;
+
+        let mut before = vec![format!("ALTER TABLE {staging_sql} OWNER TO {owner}")];
+        before.extend(grants.iter().map(|(grantee, privilege, grantable)| {
+            Self::grant_sql(staging, privilege, grantee, *grantable)
+        }));
+        let after = renames
+            .into_iter()
+            .filter(|(index, name)| index.rsplit('.').next() != Some(name.as_str()))
+            .map(|(index, name)| format!("ALTER INDEX {index} RENAME TO {name}"))
+            .collect();
+        Ok((before, after))
+    }
+
+    /// Drops a staging table left by an earlier run and, if the destination
+    /// exists, recreates it empty with the destination's columns, defaults,
+    /// constraints, and indexes. A missing destination is created from the
+    /// inferred schema on the first write, as without `swap`.
+    async fn prepare_staging(&self, table: &str) -> Result<()> {
+        if self.staging_ready.swap(true, Ordering::SeqCst) {
+            return Ok(());
+        }
+        let staging = Self::quote_ident_path(&self.table_name);
+        sqlx::query(&format!("DROP TABLE IF EXISTS {staging}"))
+            .execute(&self.pool)
+            .await?;
+        if self.table_exists_named(table).await? {
+            let like = format!(
+                "CREATE TABLE {staging} (LIKE {} INCLUDING ALL)",
+                Self::quote_ident_path(table)
+            );
+            sqlx::query(&like).execute(&self.pool).await?;
+        } else if !self.auto_create {
+            return Err(ApitapError::WriterError(format!(
+                "table '{table}' does not exist and auto_create is false; \
+                 create it (e.g. with a migration) or set auto_create: true"
+            )));
+        }
+        info!(table = %table, staging = %self.table_name, "loading into staging table");
+        Ok(())
+    }
+
     pub fn quote_ident_path(path: &str) -> String {
         // public.unplash -> "public"."unplash"
         path.split('.')
@@ -258,7 +812,13 @@ impl PostgresWriter {
 
         let column_defs: Vec<String> = schema
             .iter()
-            .map(|(name, pg_type)| format!(r#"{} {}"#, Self::quote_ident(name), pg_type.as_sql()))
+            .map(|(name, pg_type)| {
+                format!(
+                    r#"{} {}"#,
+                    Self::quote_ident(name),
+                    self.column_sql(name, pg_type)
+                )
+            })
             .collect();
 
         let pk_clause: Option<String> = match &self.primary_key {
@@ -291,20 +851,55 @@ impl PostgresWriter {
         // Execute CREATE TABLE and instrument with a debug span
         let span = debug_span!("sql.execute", statement = "create_table", table = %self.table_name);
         let _g = span.enter();
-        let res = sqlx::query(&query).execute(&self.pool).await?;
+        let res = self.execute_query(sqlx::query(&query)).await?;
         debug!(rows_affected = res.rows_affected(), "create_table executed");
 
         let column_names: Vec<String> = schema.keys().cloned().collect();
         tracing::info!(table = %self.table_name, columns = column_names.len(), cols = %column_names.join(", "), "created table");
         tracing::info!("column types:");
         for (name, pg_type) in schema {
-            tracing::info!(column = %name, typ = %pg_type.as_sql(), "column type");
+            tracing::info!(column = %name, typ = %self.column_sql(name, pg_type), "column type");
         }
 
         Ok(())
     }
 
-    async fn ensure_table(&self, sample_rows: &[Value]) -> Result<BTreeMap<String, PgType>> {
+    fn force_json_columns(&self, mut schema: BTreeMap<String, PgType>) -> BTreeMap<String, PgType> {
+        for col in &self.json_columns {
+            if let Some(ty) = schema.get_mut(col) {
+                *ty = PgType::Jsonb;
+            }
+        }
+        // Overridden columns are bound as text and cast by the placeholder
+        for col in self.column_types.keys() {
+            if let Some(ty) = schema.get_mut(col) {
+                *ty = PgType::Text;
+            }
+        }
+        schema
+    }
+
+    /// SQL type for `name` in `CREATE TABLE`: the override if set, else `pg_type`.
+    fn column_sql(&self, name: &str, pg_type: &PgType) -> String {
+        match self.column_types.get(name) {
+            Some(sql) => sql.clone(),
+            None => pg_type.as_sql(),
+        }
+    }
+
+    /// Bind placeholder `$n`, with a cast for overridden and text-bound columns.
+    fn placeholder(&self, n: usize, name: &str, pg_type: &PgType) -> String {
+        match self.column_types.get(name) {
+            Some(sql) => format!("${n}::{sql}"),
+            None => format!("${n}{}", pg_type.placeholder_cast()),
+        }
+    }
+
+    async fn ensure_table(
+        &self,
+        sample_rows: &[Value],
+        arrow_schema: Option<&Schema>,
+    ) -> Result<BTreeMap<String, PgType>> {
         if let Some(schema) = self.columns_cache.read().await.as_ref() {
             return Ok(schema.clone());
         }
@@ -316,21 +911,31 @@ impl PostgresWriter {
                         "Need sample data to create table".to_string(),
                     ));
                 }
-                let detected_schema = Self::analyze_schema(sample_rows, self.sample_size)?;
+                let detected_schema = self.force_json_columns(match arrow_schema {
+                    Some(arrow) => Self::schema_from_arrow(arrow),
+                    None => Self::analyze_schema(sample_rows, self.sample_size)?,
+                });
                 self.create_table_from_schema(&detected_schema).await?;
                 detected_schema
             } else {
-                return Err(ApitapError::PipelineError(format!(
-                    "Table '{}' does not exist",
+                return Err(ApitapError::WriterError(format!(
+                    "table '{}' does not exist and auto_create is false; \
+                     create it (e.g. with a migration) or set auto_create: true",
                     self.table_name
                 )));
             }
         } else {
-            if sample_rows.is_empty() {
-                return Err(ApitapError::PipelineError("Need sample data".to_string()));
+            match arrow_schema {
+                Some(arrow) => Self::schema_from_arrow(arrow),
+                None => {
+                    if sample_rows.is_empty() {
+                        return Err(ApitapError::PipelineError("Need sample data".to_string()));
+                    }
+                    Self::analyze_schema(sample_rows, self.sample_size)?
+                }
             }
-            Self::analyze_schema(sample_rows, self.sample_size)?
         };
+        let schema = self.force_json_columns(schema);
 
         *self.columns_cache.write().await = Some(schema.clone());
 
@@ -345,9 +950,14 @@ impl PostgresWriter {
         }
 
         // Fetch version from database
-        let version_row: (String,) = sqlx::query_as("SELECT version()")
-            .fetch_one(&self.pool)
-            .await?;
+        let q = sqlx::query_as("SELECT version()");
+        let version_row: (String,) = {
+            let mut tx = self.tx_conn.lock().await;
+            match tx.as_mut() {
+                Some(conn) => q.fetch_one(&mut **conn).await?,
+                None => q.fetch_one(&self.pool).await?,
+            }
+        };
 
         let version = PostgresVersion::parse(&version_row.0)?;
 
@@ -363,34 +973,155 @@ impl PostgresWriter {
         Ok(version)
     }
 
+    /// Statement that empties `table` under `mode`.
+    ///
+    /// ```
+    /// use apitap::writer::postgres::PostgresWriter;
+    /// use apitap::writer::TruncateMode;
+    ///
+    /// assert_eq!(
+    ///     PostgresWriter::truncate_sql("orders", TruncateMode::TruncateCascade),
+    ///     r#"TRUNCATE TABLE "orders" CASCADE"#
+    /// );
+    /// assert_eq!(
+    ///     PostgresWriter::truncate_sql("analytics.orders", TruncateMode::Delete),
+    ///     r#"DELETE FROM "analytics"."orders""#
+    /// );
+    /// ```
+    pub fn truncate_sql(table: &str, mode: TruncateMode) -> String {
+        let table_sql = Self::quote_ident_path(table);
+        match mode {
+            TruncateMode::Truncate => format!("TRUNCATE TABLE {table_sql}"),
+            TruncateMode::TruncateCascade => format!("TRUNCATE TABLE {table_sql} CASCADE"),
+            TruncateMode::Delete => format!("DELETE FROM {table_sql}"),
+        }
+    }
+
+    /// Empties the table inside the run's transaction, so a rollback restores
+    /// it. Called before [`DataWriter::begin`], it becomes the first statement
+    /// of the transaction `begin` opens.
     pub async fn truncate(&self) -> Result<()> {
-        let table_sql = Self::quote_ident(&self.table_name);
-        let sql = format!("TRUNCATE TABLE {}", table_sql);
+        if self.tx_conn.lock().await.is_none() {
+            self.truncate_pending.store(true, Ordering::SeqCst);
+            return Ok(());
+        }
+        // Checked first: a failed TRUNCATE would abort the transaction
+        if !self.table_exists().await? {
+            tracing::error!(table = %self.table_name, "table does not exist, skipping truncate");
+            return Ok(());
+        }
+        let sql = Self::truncate_sql(&self.table_name, self.truncate_mode);
 
         tracing::info!(table = %self.table_name, "truncating table");
         tracing::debug!(sql = %sql, "truncate sql");
 
-        let res = {
-            let span = debug_span!("sql.execute", statement = "truncate", table = %self.table_name);
-            let _g = span.enter();
-            sqlx::query(&sql).execute(&self.pool).await
-        };
-        match res {
-            Ok(res) => {
-                debug!(rows_affected = res.rows_affected(), "truncate executed");
-                Ok(())
-            }
-            Err(e) => {
-                // emulate IF EXISTS: swallow "undefined_table" (42P01)
-                if let Some(db_err) = e.as_database_error() {
-                    if db_err.code() == Some(Cow::Borrowed("42P01")) {
-                        tracing::error!(table = %self.table_name, "table does not exist, skipping TRUNCATE");
-                        return Ok(());
-                    }
-                }
-                Err(ApitapError::PipelineError(format!("TRUNCATE: {}", e)))
+        let span = debug_span!("sql.execute", statement = "truncate", table = %self.table_name);
+        let _g = span.enter();
+        let res = self
+            .execute_query(sqlx::query(&sql))
+            .await
+            .map_err(|e| ApitapError::PipelineError(format!("{sql}: {e}")))?;
+        debug!(rows_affected = res.rows_affected(), "truncate executed");
+        Ok(())
+    }
+
+    /// Rows of `columns` values each that fit in one statement, within
+    /// Postgres's limit of [`MAX_BIND_PARAMS`] bind parameters.
+    ///
+    /// ```
+    /// use apitap::writer::postgres::PostgresWriter;
+    ///
+    /// assert_eq!(PostgresWriter::rows_per_statement(10), 6553);
+    /// assert_eq!(PostgresWriter::rows_per_statement(100_000), 1);
+    /// ```
+    pub fn rows_per_statement(columns: usize) -> usize {
+        (MAX_BIND_PARAMS / columns.max(1)).max(1)
+    }
+
+    /// `MERGE` of `source` (aliased `s`) into `table` on `pk`, updating the
+    /// other `columns` of matched rows and inserting the rest.
+    ///
+    /// ```
+    /// use apitap::writer::postgres::PostgresWriter;
+    ///
+    /// let sql = PostgresWriter::merge_sql(r#""users""#, "id", &["id", "name"], "pg_temp.batch AS s");
+    /// assert!(sql.contains(r#"USING pg_temp.batch AS s"#));
+    /// assert!(sql.contains(r#"UPDATE SET "name" = s."name""#));
+    /// ```
+    pub fn merge_sql(table: &str, pk: &str, columns: &[&str], source: &str) -> String {
+        // Quoted names for target table (t."col") and source alias (s."col")
+        let cols_t: Vec<String> = columns.iter().map(|c| Self::quote_ident(c)).collect();
+        let cols_s: Vec<String> = cols_t.iter().map(|c| format!("s.{c}")).collect();
+        let non_pk: Vec<usize> = (0..columns.len()).filter(|&i| columns[i] != pk).collect();
+        let pk = Self::quote_ident(pk);
+
+        // Build the UPDATE clause with correct Postgres forms:
+        //  - 0 cols: no UPDATE
+        //  - 1 col:  UPDATE SET t."c" = s."c"
+        //  - >1:     UPDATE SET (t."c1", t."c2") = ROW(s."c1", s."c2")
+        let matched = match non_pk.as_slice() {
+            [] => String::new(),
+            [i] => format!(
+                "WHEN MATCHED THEN\n  UPDATE SET {} = {}\n",
+                cols_t[*i], cols_s[*i]
+            ),
+            _ => {
+                let left: Vec<&str> = non_pk.iter().map(|&i| cols_t[i].as_str()).collect();
+                let right: Vec<&str> = non_pk.iter().map(|&i| cols_s[i].as_str()).collect();
+                format!(
+                    "WHEN MATCHED THEN\n  UPDATE SET ({}) = ROW({})\n",
+                    left.join(", "),
+                    right.join(", ")
+                )
             }
-        }
+        };
+        format!(
+            r#"
+MERGE INTO {table} AS t
+USING {source}
+ON t.{pk} = s.{pk}
+{matched}WHEN NOT MATCHED THEN
+  INSERT ({cols})
+  VALUES ({cols_s});
+"#,
+            cols = cols_t.join(", "),
+            cols_s = cols_s.join(", "),
+        )
+    }
+
+    /// `INSERT ... ON CONFLICT` of `source` (a `VALUES` list or a `SELECT`)
+    /// into `table`, updating the other `columns` of rows whose `pk` exists.
+    ///
+    /// ```
+    /// use apitap::writer::postgres::PostgresWriter;
+    ///
+    /// assert_eq!(
+    ///     PostgresWriter::upsert_sql(r#""users""#, "id", &["id", "name"], "VALUES ($1, $2)"),
+    ///     r#"INSERT INTO "users" ("id", "name") VALUES ($1, $2) ON CONFLICT ("id") DO UPDATE SET "name" = EXCLUDED."name""#
+    /// );
+    /// ```
+    pub fn upsert_sql(table: &str, pk: &str, columns: &[&str], source: &str) -> String {
+        let cols: Vec<String> = columns.iter().map(|c| Self::quote_ident(c)).collect();
+        // "col" = EXCLUDED."col" for every non-PK column
+        let assignments: Vec<String> = columns
+            .iter()
+            .filter(|c| **c != pk)
+            .map(|c| {
+                let col = Self::quote_ident(c);
+                format!("{col} = EXCLUDED.{col}")
+            })
+            .collect();
+        // If only PK, do nothing on conflict (just ensures uniqueness)
+        let action = if assignments.is_empty() {
+            "DO NOTHING".to_string()
+        } else {
+            format!("DO UPDATE SET {}", assignments.join(", "))
+        };
+        format!(
+            "INSERT INTO {table} ({}) {source} ON CONFLICT ({}) {action}",
+            cols.join(", "),
+            Self::quote_ident(pk)
+        )
     }
 
     /// Upsert batch using INSERT ... ON CONFLICT DO UPDATE (PostgreSQL 9.5+)
@@ -416,68 +1147,28 @@ impl PostgresWriter {
         // Column lists (BTreeMap keeps stable order)
         let col_names_raw: Vec<&str> = schema.keys().map(|s| s.as_str()).collect();
         let values_per_row = col_names_raw.len();
-        let col_names_sql: Vec<String> =
-            col_names_raw.iter().map(|n| Self::quote_ident(n)).collect();
-
-        let columns_str = col_names_sql.join(", ");
 
         // Build placeholders for VALUES
         let mut placeholders = Vec::with_capacity(rows.len());
         for row_idx in 0..rows.len() {
             let row_ph: Vec<String> = (1..=values_per_row)
-                .map(|col_idx| format!("${}", row_idx * values_per_row + col_idx))
+                .map(|col_idx| {
+                    let name = col_names_raw[col_idx - 1];
+                    self.placeholder(row_idx * values_per_row + col_idx, name, &schema[name])
+                })
                 .collect();
             placeholders.push(format!("({})", row_ph.join(", ")));
         }
 
         let table_sql = Self::quote_ident_path(&self.table_name);
-        let pk_quoted = Self::quote_ident(&pk_name);
-
-        // Determine non-PK columns for UPDATE clause
-        let non_pk_cols: Vec<&str> = col_names_raw
-            .iter()
-            .filter(|c| **c != pk_name.as_str())
-            .copied()
-            .collect();
-
-        // Build UPDATE SET clause: "col" = EXCLUDED."col"
-        let update_set = if non_pk_cols.is_empty() {
-            // If only PK, do nothing on conflict (just ensures uniqueness)
-            "".to_string()
-        } else {
-            let assignments: Vec<String> = non_pk_cols
-                .iter()
-                .map(|col| {
-                    format!(
-                        "{} = EXCLUDED.{}",
-                        Self::quote_ident(col),
-                        Self::quote_ident(col)
-                    )
-                })
-                .collect();
-            format!("DO UPDATE SET {}", assignments.join(", "))
-        };
-
-        let conflict_clause = if non_pk_cols.is_empty() {
-            format!("ON CONFLICT ({}) DO NOTHING", pk_quoted)
-        } else {
-            format!("ON CONFLICT ({}) {}", pk_quoted, update_set)
-        };
-
-        let query = format!(
-            "INSERT INTO {} ({}) VALUES {} {}",
-            table_sql,
-            columns_str,
-            placeholders.join(", "),
-            conflict_clause
-        );
+        let source = format!("VALUES {}", placeholders.join(", "));
+        let query = Self::upsert_sql(&table_sql, &pk_name, &col_names_raw, &source);
 
         debug!(
             table = %table_sql,
             pk = %pk_name,
             rows = rows.len(),
             cols = values_per_row,
-            will_update_cols = non_pk_cols.len(),
             "UPSERT batch details"
         );
         debug!(%query, "UPSERT SQL");
@@ -503,7 +1194,7 @@ impl PostgresWriter {
         // Execute
         let span = debug_span!("sql.execute", statement = "upsert", table = %self.table_name, batch_rows = rows.len());
         let _g = span.enter();
-        let res = q.execute(&self.pool).await?;
+        let res = self.execute_query(q).await?;
         debug!(rows_affected = res.rows_affected(), "upsert executed");
 
         Ok(())
@@ -518,6 +1209,9 @@ impl PostgresWriter {
         // Choose implementation based on PostgreSQL version
         let version = self.get_postgres_version().await?;
 
+        if version.supports_upsert() && rows.len() > Self::rows_per_statement(schema.len()) {
+            return self.merge_staged(rows, schema, version).await;
+        }
         if version.supports_merge() {
             // Use MERGE for PostgreSQL 15+
             return self.merge_batch_pg15(rows, schema).await;
@@ -556,113 +1250,26 @@ impl PostgresWriter {
         let col_names_raw: Vec<&str> = schema.keys().map(|s| s.as_str()).collect();
         let values_per_row = col_names_raw.len();
 
-        // Quoted names for target table (t."col") and source alias (s."col")
-        let cols_t_quoted: Vec<String> =
-            col_names_raw.iter().map(|c| Self::quote_ident(c)).collect();
-        let cols_s_quoted: Vec<String> = col_names_raw
-            .iter()
-            .map(|c| format!("s.{}", Self::quote_ident(c)))
-            .collect();
-
-        // Helper strings
-        let columns_t_str = cols_t_quoted.join(", ");
-        let columns_s_str = cols_s_quoted.join(", ");
-        let using_cols_str = cols_t_quoted.join(", "); // names for s(...)
-
         // VALUES placeholders
         let mut placeholders = Vec::with_capacity(rows.len());
         for row_idx in 0..rows.len() {
             let row_ph: Vec<String> = (1..=values_per_row)
-                .map(|col_idx| format!("${}", row_idx * values_per_row + col_idx))
+                .map(|col_idx| {
+                    let name = col_names_raw[col_idx - 1];
+                    self.placeholder(row_idx * values_per_row + col_idx, name, &schema[name])
+                })
                 .collect();
             placeholders.push(format!("({})", row_ph.join(", ")));
         }
-        let values_block = placeholders.join(",\n        ");
+        let using_cols: Vec<String> = col_names_raw.iter().map(|c| Self::quote_ident(c)).collect();
+        let source = format!(
+            "(VALUES\n        {}\n) AS s({})",
+            placeholders.join(",\n        "),
+            using_cols.join(", ")
+        );
 
-        // Target table + PK refs
         let table_sql = Self::quote_ident_path(&self.table_name);
-        let pk_t = format!(r#"t.{}"#, Self::quote_ident(&pk_name));
-        let pk_s = format!(r#"s.{}"#, Self::quote_ident(&pk_name));
-
-        // Determine non-PK columns
-        let non_pk_idx: Vec<usize> = col_names_raw
-            .iter()
-            .enumerate()
-            .filter(|(_, c)| **c != pk_name.as_str())
-            .map(|(i, _)| i)
-            .collect();
-
-        // Build the UPDATE clause with correct Postgres forms:
-        //  - 0 cols: no UPDATE
-        //  - 1 col:  UPDATE SET t."c" = s."c"
-        //  - >1:     UPDATE SET (t."c1", t."c2") = ROW(s."c1", s."c2")
-        let set_clause = if non_pk_idx.is_empty() {
-            None
-        } else if non_pk_idx.len() == 1 {
-            let i = non_pk_idx[0];
-            let tcol = &cols_t_quoted[i];
-            let scol = &cols_s_quoted[i];
-            Some(format!(r#"UPDATE SET {tcol} = {scol}"#))
-        } else {
-            let left = non_pk_idx
-                .iter()
-                .map(|&i| &cols_t_quoted[i])
-                .cloned()
-                .collect::<Vec<_>>()
-                .join(", ");
-            let right = non_pk_idx
-                .iter()
-                .map(|&i| &cols_s_quoted[i])
-                .cloned()
-                .collect::<Vec<_>>()
-                .join(", ");
-            Some(format!(r#"UPDATE SET ({left}) = ROW({right})"#))
-        };
-
-        // Final SQL
-        let query = match set_clause {
-            Some(set) => format!(
-                r#"
-MERGE INTO {table} AS t
-USING (VALUES
-        {values}
-) AS s({using_cols})
-ON {pk_t} = {pk_s}
-WHEN MATCHED THEN
-  {set}
-WHEN NOT MATCHED THEN
-  INSERT ({cols})
-  VALUES ({cols_s});
-"#,
-                table = table_sql,
-                values = values_block,
-                using_cols = using_cols_str,
-                pk_t = pk_t,
-                pk_s = pk_s,
-                set = set,
-                cols = columns_t_str,
-                cols_s = columns_s_str,
-            ),
-            None => format!(
-                r#"
-MERGE INTO {table} AS t
-USING (VALUES
-        {values}
-) AS s({using_cols})
-ON {pk_t} = {pk_s}
-WHEN NOT MATCHED THEN
-  INSERT ({cols})
-  VALUES ({cols_s});
-"#,
-                table = table_sql,
-                values = values_block,
-                using_cols = using_cols_str,
-                pk_t = pk_t,
-                pk_s = pk_s,
-                cols = columns_t_str,
-                cols_s = columns_s_str,
-            ),
-        };
+        let query = Self::merge_sql(&table_sql, &pk_name, &col_names_raw, &source);
 
         // Log concise info at INFO, details at DEBUG
         debug!(
@@ -671,7 +1278,6 @@ WHEN NOT MATCHED THEN
             rows = rows.len(),
             cols = values_per_row,
             placeholders = rows.len() * values_per_row,
-            will_update_cols = non_pk_idx.len(),
             "MERGE batch details"
         );
         debug!(%query, "MERGE SQL");
@@ -698,7 +1304,7 @@ WHEN NOT MATCHED THEN
         // Instrument the MERGE execution and log rows_affected
         let span = debug_span!("sql.execute", statement = "merge", table = %self.table_name, batch_rows = rows.len());
         let _g = span.enter();
-        let res = q.execute(&self.pool).await?;
+        let res = self.execute_query(q).await?;
         debug!(rows_affected = res.rows_affected(), "merge executed");
 
         Ok(())
@@ -708,36 +1314,87 @@ WHEN NOT MATCHED THEN
         &self,
         rows: &[Value],
         schema: &BTreeMap<String, PgType>,
+    ) -> Result<()> {
+        self.insert_rows(rows, schema, None).await
+    }
+
+    /// Inserts rows, skipping any that collide with an existing key
+    /// (`ON CONFLICT DO NOTHING`). Replays of already-loaded rows are no-ops.
+    pub async fn insert_ignore_batch(
+        &self,
+        rows: &[Value],
+        schema: &BTreeMap<String, PgType>,
+    ) -> Result<()> {
+        let conflict = match &self.primary_key {
+            Some(pk) => format!("ON CONFLICT ({}) DO NOTHING", Self::quote_ident(pk)),
+            None => "ON CONFLICT DO NOTHING".to_string(),
+        };
+        self.insert_rows(rows, schema, Some(&conflict)).await
+    }
+
+    async fn insert_rows(
+        &self,
+        rows: &[Value],
+        schema: &BTreeMap<String, PgType>,
+        conflict_clause: Option<&str>,
     ) -> Result<()> {
         if rows.is_empty() {
             return Ok(());
         }
 
+        // Quote table name too
+        let table_sql = Self::quote_ident_path(&self.table_name);
+
+        // Chunks wider than one statement's bind parameters go in pieces
+        for piece in rows.chunks(Self::rows_per_statement(schema.len())) {
+            let (mut query, all_values) = self.insert_values(&table_sql, piece, schema);
+            if let Some(clause) = conflict_clause {
+                query.push(' ');
+                query.push_str(clause);
+            }
+            let q = self.bind_all(&query, &all_values, schema)?;
+
+            // Instrument the insert execution and log rows_affected
+            let span = debug_span!("sql.execute", statement = "insert", table = %self.table_name, batch_rows = piece.len());
+            let _g = span.enter();
+            let res = self.execute_query(q).await?;
+            debug!(rows_affected = res.rows_affected(), "insert executed");
+        }
+
+        Ok(())
+    }
+
+    /// `INSERT INTO table (...) VALUES ($1, ...), ...` for `rows`, and the
+    /// values to bind in placeholder order.
+    fn insert_values(
+        &self,
+        table_sql: &str,
+        rows: &[Value],
+        schema: &BTreeMap<String, PgType>,
+    ) -> (String, Vec<Value>) {
         // Raw column names (as present in JSON & schema)
         let col_names_raw: Vec<&str> = schema.keys().map(|s| s.as_str()).collect();
         // SQL-safe (quoted) column names for the statement
         let col_names_sql: Vec<String> =
             col_names_raw.iter().map(|n| Self::quote_ident(n)).collect();
-
-        let columns_str = col_names_sql.join(", ");
         let values_per_row = col_names_raw.len();
 
         // Build placeholders: ($1, $2, ...), ($n+1, ...)
         let mut placeholders = Vec::with_capacity(rows.len());
         for row_idx in 0..rows.len() {
             let row_placeholders: Vec<String> = (1..=values_per_row)
-                .map(|col_idx| format!("${}", row_idx * values_per_row + col_idx))
+                .map(|col_idx| {
+                    let name = col_names_raw[col_idx - 1];
+                    self.placeholder(row_idx * values_per_row + col_idx, name, &schema[name])
+                })
                 .collect();
             placeholders.push(format!("({})", row_placeholders.join(", ")));
         }
 
-        // Quote table name too
-        let table_sql = Self::quote_ident_path(&self.table_name);
-
         let query = format!(
             "INSERT INTO {} ({}) VALUES {}",
             table_sql,
-            columns_str,
+            col_names_sql.join(", "),
             placeholders.join(", ")
         );
 
@@ -745,26 +1402,287 @@ WHEN NOT MATCHED THEN
         let mut all_values = Vec::with_capacity(rows.len() * values_per_row);
         for row in rows {
             for col_name in &col_names_raw {
-                let value = row.get(*col_name).cloned().unwrap_or(Value::Null);
-                all_values.push(value);
+                all_values.push(row.get(*col_name).cloned().unwrap_or(Value::Null));
             }
         }
+        (query, all_values)
+    }
 
-        // Bind in the same order as placeholders
-        let mut q = sqlx::query(&query);
-        for (idx, value) in all_values.iter().enumerate() {
-            let col_idx = idx % values_per_row;
-            let col_name = col_names_raw[col_idx];
-            let expected_type = schema.get(col_name).expect("schema must contain column");
-
-            q = self.bind_value(q, value, expected_type)?;
+    /// Binds `values`, row after row in schema column order, to `query`.
+    fn bind_all<'q>(
+        &self,
+        query: &'q str,
+        values: &'q [Value],
+        schema: &BTreeMap<String, PgType>,
+    ) -> Result<Query<'q, Postgres, PgArguments>> {
+        let types: Vec<&PgType> = schema.values().collect();
+        let mut q = sqlx::query(query);
+        for (idx, value) in values.iter().enumerate() {
+            q = self.bind_value(q, value, types[idx % types.len()])?;
         }
+        Ok(q)
+    }
+
+    /// Merges a chunk too large for one statement: loads it into a temporary
+    /// table in statement-sized pieces, then merges the whole chunk from
+    /// there (upserts before PostgreSQL 15). Runs on the open transaction's
+    /// connection, or in a transaction of its own.
+    async fn merge_staged(
+        &self,
+        rows: &[Value],
+        schema: &BTreeMap<String, PgType>,
+        version: PostgresVersion,
+    ) -> Result<()> {
+        let pk_name = self.primary_key.clone().ok_or_else(|| {
+            ApitapError::MergeError("Postgres: primary key not configured".to_string())
+        })?;
+        let columns: Vec<&str> = schema.keys().map(|s| s.as_str()).collect();
+        let quoted: Vec<String> = columns.iter().map(|c| Self::quote_ident(c)).collect();
+        let table_sql = Self::quote_ident_path(&self.table_name);
+        let staging = format!("pg_temp.{}", Self::quote_ident(MERGE_STAGING_TABLE));
+
+        let merge = if version.supports_merge() {
+            Self::merge_sql(&table_sql, &pk_name, &columns, &format!("{staging} AS s"))
+        } else {
+            let select = format!("SELECT {} FROM {staging}", quoted.join(", "));
+            Self::upsert_sql(&table_sql, &pk_name, &columns, &select)
+        };
+        // Same column types as the destination, without its constraints
+        let create = format!(
+            "CREATE TEMP TABLE {} AS SELECT {} FROM {table_sql} WITH NO DATA",
+            Self::quote_ident(MERGE_STAGING_TABLE),
+            quoted.join(", ")
+        );
+        let drop = format!("DROP TABLE IF EXISTS {staging}");
+
+        // Temporary tables belong to one session, so every statement needs the same connection
+        let mut tx_conn = self.tx_conn.lock().await;
+        let mut own_tx: Option<sqlx::Transaction<'_, Postgres>> = None;
+        let conn: &mut sqlx::PgConnection = match tx_conn.as_mut() {
+            Some(conn) => conn,
+            None => own_tx.insert(self.pool.begin().await?),
+        };
 
-        // Instrument the insert execution and log rows_affected
-        let span = debug_span!("sql.execute", statement = "insert", table = %self.table_name, batch_rows = rows.len());
+        sqlx::query(&drop).execute(&mut *conn).await?;
+        sqlx::query(&create).execute(&mut *conn).await?;
+        for piece in rows.chunks(Self::rows_per_statement(columns.len())) {
+            let (query, values) = self.insert_values(&staging, piece, schema);
+            self.bind_all(&query, &values, schema)?
+                .execute(&mut *conn)
+                .await?;
+        }
+        let span = debug_span!("sql.execute", statement = "merge_staged", table = %self.table_name, batch_rows = rows.len());
         let _g = span.enter();
-        let res = q.execute(&self.pool).await?;
-        debug!(rows_affected = res.rows_affected(), "insert executed");
+        let res = sqlx::query(&merge).execute(&mut *conn).await?;
+        debug!(rows_affected = res.rows_affected(), "staged merge executed");
+        sqlx::query(&drop).execute(&mut *conn).await?;
+
+        if let Some(tx) = own_tx {
+            tx.commit().await?;
+        }
+        Ok(())
+    }
+
+    /// Writes one chunk with the chosen mode.
+    async fn write_chunk_once(
+        &self,
+        rows: &[Value],
+        schema: &BTreeMap<String, PgType>,
+        write_mode: &WriteMode,
+    ) -> Result<()> {
+        match write_mode {
+            WriteMode::Append => self.insert_batch(rows, schema).await,
+            WriteMode::Merge => self.merge_batch(rows, schema).await,
+            WriteMode::Insert => self.insert_ignore_batch(rows, schema).await,
+        }
+    }
+
+    /// Writes one chunk. With a dead-letter writer, a chunk failing on its
+    /// data is written again row by row and the failing rows are dead-lettered.
+    async fn write_chunk(
+        &self,
+        rows: &[Value],
+        schema: &BTreeMap<String, PgType>,
+        write_mode: &WriteMode,
+    ) -> Result<()> {
+        let in_tx = self.tx_conn.lock().await.is_some();
+        // Savepoints have fixed names on one connection, so chunks take turns
+        let _turn = if in_tx {
+            Some(self.write_lock.lock().await)
+        } else {
+            None
+        };
+        let Some(dead_letter) = &self.dead_letter else {
+            return self.write_chunk_retrying(rows, schema, write_mode).await;
+        };
+        if in_tx {
+            self.execute_query(sqlx::query("SAVEPOINT apitap_batch"))
+                .await?;
+        }
+        match self.write_chunk_retrying(rows, schema, write_mode).await {
+            Ok(()) => {}
+            Err(e) if is_row_error(&e) => {
+                if in_tx {
+                    self.execute_query(sqlx::query("ROLLBACK TO SAVEPOINT apitap_batch"))
+                        .await?;
+                }
+                warn!(table = %self.table_name, rows = rows.len(), error = %e, "batch failed on its data; writing rows one at a time");
+                let mut failed = 0;
+                for row in rows {
+                    if in_tx {
+                        self.execute_query(sqlx::query("SAVEPOINT apitap_row"))
+                            .await?;
+                    }
+                    match self
+                        .write_chunk_retrying(std::slice::from_ref(row), schema, write_mode)
+                        .await
+                    {
+                        Ok(()) => {}
+                        Err(e) if is_row_error(&e) => {
+                            if in_tx {
+                                self.execute_query(sqlx::query("ROLLBACK TO SAVEPOINT apitap_row"))
+                                    .await?;
+                            }
+                            failed += 1;
+                            dead_letter
+                                .on_error(QueryError {
+                                    table_name: self.table_name.clone(),
+                                    error: e.to_string(),
+                                    record: Some(row.clone()),
+                                })
+                                .await?;
+                        }
+                        Err(e) => return Err(e),
+                    }
+                    if in_tx {
+                        self.execute_query(sqlx::query("RELEASE SAVEPOINT apitap_row"))
+                            .await?;
+                    }
+                }
+                warn!(table = %self.table_name, failed, rows = rows.len(), "sent failed rows to the dead-letter writer");
+            }
+            Err(e) => return Err(e),
+        }
+        if in_tx {
+            self.execute_query(sqlx::query("RELEASE SAVEPOINT apitap_batch"))
+                .await?;
+        }
+        Ok(())
+    }
+
+    /// Writes one chunk, retrying transient failures per `write_retry`. In a
+    /// transaction the caller holds `write_lock`.
+    async fn write_chunk_retrying(
+        &self,
+        rows: &[Value],
+        schema: &BTreeMap<String, PgType>,
+        write_mode: &WriteMode,
+    ) -> Result<()> {
+        use reqwest_retry::{RetryDecision, RetryPolicy};
+
+        let Some(backoff) = &self.write_retry else {
+            return self.write_chunk_once(rows, schema, write_mode).await;
+        };
+        let in_tx = self.tx_conn.lock().await.is_some();
+        let started = std::time::SystemTime::now();
+        let mut retries = 0;
+        loop {
+            if in_tx {
+                self.execute_query(sqlx::query("SAVEPOINT apitap_write"))
+                    .await?;
+            }
+            let err = match self.write_chunk_once(rows, schema, write_mode).await {
+                Ok(()) => {
+                    if in_tx {
+                        self.execute_query(sqlx::query("RELEASE SAVEPOINT apitap_write"))
+                            .await?;
+                    }
+                    return Ok(());
+                }
+                Err(e) => e,
+            };
+
+            let retryable = match WriteFailure::of(&err) {
+                WriteFailure::Statement => true,
+                WriteFailure::Connection => !in_tx,
+                WriteFailure::Permanent => false,
+            };
+            if !retryable {
+                return Err(err);
+            }
+            let RetryDecision::Retry { execute_after } = backoff.should_retry(started, retries)
+            else {
+                return Err(err);
+            };
+            if in_tx {
+                self.execute_query(sqlx::query("ROLLBACK TO SAVEPOINT apitap_write"))
+                    .await?;
+            }
+            retries += 1;
+            warn!(table = %self.table_name, attempt = retries, rows = rows.len(), error = %err, "retrying failed batch");
+            let delay = execute_after
+                .duration_since(std::time::SystemTime::now())
+                .unwrap_or_default();
+            tokio::time::sleep(delay).await;
+        }
+    }
+
+    /// Streams rows into the table in `batch_size` chunks.
+    async fn write_stream_inner(
+        &self,
+        mut result: QueryResultStream,
+        write_mode: WriteMode,
+    ) -> Result<()> {
+        let mut buf: Vec<serde_json::Value> = Vec::with_capacity(self.batch_size);
+        let mut schema: Option<BTreeMap<String, PgType>> = None;
+        let arrow_schema = result.schema.clone();
+        let temporal: Vec<(String, DataType)> = arrow_schema
+            .iter()
+            .flat_map(|s| s.fields().iter())
+            .filter(|f| {
+                matches!(
+                    f.data_type(),
+                    DataType::Timestamp(_, _) | DataType::Date32 | DataType::Date64
+                )
+            })
+            .map(|f| (f.name().clone(), f.data_type().clone()))
+            .collect();
+
+        // Stream → buffer → write in batches
+        while let Some(item) = result.data.next().await {
+            let mut row = self.decode_json_columns(item?);
+            if let Value::Object(obj) = &mut row {
+                for (name, data_type) in &temporal {
+                    if let Some(v) = obj.get_mut(name) {
+                        if let Some(text) = temporal_to_text(v, data_type) {
+                            *v = Value::String(text);
+                        }
+                    }
+                }
+            }
+            buf.push(row);
+
+            if buf.len() >= self.batch_size {
+                // Lazily infer/create table schema from current batch
+                if schema.is_none() {
+                    schema = Some(self.ensure_table(&buf, arrow_schema.as_deref()).await?);
+                }
+                let schema_ref = schema.as_ref().expect("schema just set");
+                self.write_chunk(&buf, schema_ref, &write_mode).await?;
+                self.checkpoint(buf.len()).await?;
+                buf.clear();
+            }
+        }
+
+        // Flush remainder
+        if !buf.is_empty() {
+            if schema.is_none() {
+                schema = Some(self.ensure_table(&buf, arrow_schema.as_deref()).await?);
+            }
+            let schema_ref = schema.as_ref().expect("schema just set");
+            self.write_chunk(&buf, schema_ref, &write_mode).await?;
+            self.checkpoint(buf.len()).await?;
+        }
 
         Ok(())
     }
@@ -804,7 +1722,9 @@ WHEN NOT MATCHED THEN
                     query.bind::<Option<f64>>(None)
                 }
             }
-            (Value::Number(n), PgType::Text) => query.bind(n.to_string()),
+            (Value::Number(n), PgType::Text) | (Value::Number(n), PgType::Numeric { .. }) => {
+                query.bind(n.to_string())
+            }
             (Value::Number(_), PgType::Jsonb) => query.bind(Json(value)),
             (Value::Number(_), _) => query.bind::<Option<f64>>(None),
 
@@ -832,6 +1752,8 @@ WHEN NOT MATCHED THEN
                 let b = s.to_lowercase() == "true" || s == "1";
                 query.bind(b)
             }
+            // Cast server-side via `placeholder_cast`
+            (Value::String(s), _) => query.bind(s.as_str()),
 
             // Arrays / Objects
             (Value::Array(_), PgType::Jsonb) | (Value::Object(_), PgType::Jsonb) => {
@@ -851,49 +1773,19 @@ WHEN NOT MATCHED THEN
 
 #[async_trait]
 impl DataWriter for PostgresWriter {
-    async fn write_stream(
-        &self,
-        mut result: QueryResultStream,
-        write_mode: WriteMode,
-    ) -> Result<()> {
-        // Local macro: write one chunk with the chosen mode
-        macro_rules! write_chunk {
-            ($buf:expr, $schema:expr) => {
-                match write_mode {
-                    WriteMode::Append => self.insert_batch($buf, $schema).await,
-                    WriteMode::Merge => self.merge_batch($buf, $schema).await,
-                }
-            };
-        }
-
-        let mut buf: Vec<serde_json::Value> = Vec::with_capacity(self.batch_size);
-        let mut schema: Option<BTreeMap<String, PgType>> = None;
-
-        // Stream → buffer → write in batches
-        while let Some(item) = result.data.next().await {
-            buf.push(item?);
-
-            if buf.len() >= self.batch_size {
-                // Lazily infer/create table schema from current batch
-                if schema.is_none() {
-                    schema = Some(self.ensure_table(&buf).await?);
-                }
-                let schema_ref = schema.as_ref().expect("schema just set");
-                write_chunk!(&buf, schema_ref)?;
-                buf.clear();
-            }
-        }
-
-        // Flush remainder
-        if !buf.is_empty() {
-            if schema.is_none() {
-                schema = Some(self.ensure_table(&buf).await?);
+    async fn write_stream(&self, result: QueryResultStream, write_mode: WriteMode) -> Result<()> {
+        let res = self.write_stream_inner(result, write_mode).await;
+        match res {
+            Err(e) if self.commit_every.is_some() => {
+                let committed = self.rows_committed();
+                tracing::error!(table = %self.table_name, rows_committed = committed, "write failed after intermediate commits");
+                Err(ApitapError::WriterError(format!(
+                    "write to '{}' failed after committing {} rows: {}",
+                    self.table_name, committed, e
+                )))
             }
-            let schema_ref = schema.as_ref().expect("schema just set");
-            write_chunk!(&buf, schema_ref)?;
+            other => other,
         }
-
-        Ok(())
     }
 
     async fn write(&self, result: QueryResult) -> Result<()> {
@@ -906,27 +1798,91 @@ impl DataWriter for PostgresWriter {
             return Ok(());
         }
 
-        let schema = self.ensure_table(rows).await?;
+        let rows: Vec<Value> = rows
+            .iter()
+            .map(|r| self.decode_json_columns(r.clone()))
+            .collect();
+        let schema = self.ensure_table(&rows, None).await?;
 
         for chunk in rows.chunks(self.batch_size) {
-            self.insert_batch(chunk, &schema).await?;
+            self.write_chunk(chunk, &schema, &WriteMode::Append).await?;
         }
 
         Ok(())
     }
 
     async fn begin(&self) -> Result<()> {
-        sqlx::query("BEGIN").execute(&self.pool).await?;
+        if let Some(table) = &self.swap_into {
+            // Outside the transaction, so schema lookups on the pool see it
+            self.prepare_staging(table).await?;
+        }
+        // Pin one connection so every statement until commit shares the transaction
+        let mut conn = self.pool.acquire().await?;
+        sqlx::query("BEGIN").execute(&mut *conn).await?;
+        *self.tx_conn.lock().await = Some(conn);
+        self.rows_since_commit.store(0, Ordering::Relaxed);
+        self.rows_committed.store(0, Ordering::Relaxed);
+        if self.truncate_pending.swap(false, Ordering::SeqCst) {
+            self.truncate().await?;
+        }
         Ok(())
     }
 
     async fn commit(&self) -> Result<()> {
-        sqlx::query("COMMIT").execute(&self.pool).await?;
+        if let Some(mut conn) = self.tx_conn.lock().await.take() {
+            sqlx::query("COMMIT").execute(&mut *conn).await?;
+        }
         Ok(())
     }
 
     async fn rollback(&self) -> Result<()> {
-        sqlx::query("ROLLBACK").execute(&self.pool).await?;
+        if let Some(mut conn) = self.tx_conn.lock().await.take() {
+            sqlx::query("ROLLBACK").execute(&mut *conn).await?;
+            if self.commit_every.is_some() {
+                tracing::warn!(table = %self.table_name, rows_committed = self.rows_committed(), "rolled back rows since last intermediate commit");
+            }
+        }
+        if self.swap_into.is_some() {
+            let drop = format!(
+                "DROP TABLE IF EXISTS {}",
+                Self::quote_ident_path(&self.table_name)
+            );
+            sqlx::query(&drop).execute(&self.pool).await?;
+        }
+        Ok(())
+    }
+
+    async fn finish(&self) -> Result<()> {
+        let Some(table) = &self.swap_into else {
+            return self.run_post_checks(&self.table_name).await;
+        };
+        // Nothing was written and there was no table to copy
+        if !self.table_exists().await? {
+            debug!(table = %table, "no staging table to swap in");
+            return Ok(());
+        }
+        if let Err(e) = self.run_post_checks(&self.table_name).await {
+            // The live table stays as it was
+            let drop = format!(
+                "DROP TABLE IF EXISTS {}",
+                Self::quote_ident_path(&self.table_name)
+            );
+            if let Err(drop_err) = sqlx::query(&drop).execute(&self.pool).await {
+                warn!(staging = %self.table_name, error = %drop_err, "could not drop staging table after failed post check");
+            }
+            return Err(e);
+        }
+        let mut tx = self.pool.begin().await?;
+        let (before, after) = Self::carry_over_sql(&mut *tx, &self.table_name, table).await?;
+        for sql in before
+            .iter()
+            .chain(&Self::swap_sql(&self.table_name, table))
+            .chain(&after)
+        {
+            sqlx::query(sql).execute(&mut *tx).await?;
+        }
+        tx.commit().await?;
+        info!(table = %table, staging = %self.table_name, "swapped staging table into place");
         Ok(())
     }
 }
@@ -0,0 +1,644 @@
+// src/writer/snowflake.rs
+
+//! Snowflake writer backed by the Snowflake SQL REST API (`/api/v2/statements`).
+//!
+//! Each batch is loaded in two steps:
+//! 1. Rows are staged as `VARIANT` values into a transient staging table
+//!    created for that batch alone (`<table>__APITAP_STAGE_<id>`) next to the
+//!    destination table.
+//! 2. A single `INSERT ... SELECT` (append) or `MERGE` (merge, keyed on the
+//!    primary key) moves the batch from the staging table into the destination,
+//!    after which the staging table is dropped.
+//!
+//! The SQL API does not support `PUT`, so files cannot be uploaded to an
+//! internal stage from here; the staging table plays that role instead.
+//! Because every batch gets its own staging table, concurrent batches for the
+//! same destination never see each other's rows.
+//!
+//! Every API call runs in its own session, so `begin`/`commit`/`rollback` are
+//! no-ops. Staging takes several calls, but the move into the destination is a
+//! single statement, so a batch lands in the destination entirely or not at all.
+
+use crate::errors::{ApitapError, Result};
+use crate::utils::datafusion_ext::{QueryResult, QueryResultStream};
+use crate::writer::{DataWriter, WriteMode};
+use async_trait::async_trait;
+use serde_json::{json, Map, Value};
+use std::collections::BTreeMap;
+use std::sync::Arc;
+use std::time::Duration;
+use tokio_stream::StreamExt;
+use tracing::{debug, debug_span, info, Instrument};
+
+/// Suffix appended to the destination table name for the staging tables; a
+/// per-batch id follows it.
+pub const STAGE_SUFFIX: &str = "__APITAP_STAGE";
+
+/// Seconds a statement may run before Snowflake cancels it.
+const STATEMENT_TIMEOUT_SECS: u64 = 600;
+
+/// Delay between polls while an asynchronous statement is still running.
+const POLL_INTERVAL: Duration = Duration::from_millis(500);
+
+//=============== Type Definitions ============================================//
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SfType {
+    Varchar,
+    Boolean,
+    Number,
+    Float,
+    Variant,
+}
+
+impl SfType {
+    pub fn as_sql(&self) -> &'static str {
+        match self {
+            SfType::Varchar => "VARCHAR",
+            SfType::Boolean => "BOOLEAN",
+            SfType::Number => "NUMBER(38,0)",
+            SfType::Float => "FLOAT",
+            SfType::Variant => "VARIANT",
+        }
+    }
+
+    pub fn from_json_value(value: &Value) -> Self {
+        match value {
+            Value::Null => SfType::Varchar,
+            Value::Bool(_) => SfType::Boolean,
+            Value::Number(n) => {
+                if n.is_i64() || n.is_u64() {
+                    SfType::Number
+                } else {
+                    SfType::Float
+                }
+            }
+            Value::String(_) => SfType::Varchar,
+            Value::Array(_) | Value::Object(_) => SfType::Variant,
+        }
+    }
+
+    pub fn merge(&self, other: &Self) -> Self {
+        match (self, other) {
+            (a, b) if a == b => *a,
+            (SfType::Number, SfType::Float) | (SfType::Float, SfType::Number) => SfType::Float,
+            _ => SfType::Varchar,
+        }
+    }
+}
+
+//=============== SQL API Client ==============================================//
+
+/// Minimal client for the Snowflake SQL REST API.
+#[derive(Debug, Clone)]
+pub struct SnowflakeClient {
+    http: reqwest::Client,
+    endpoint: String,
+    token: String,
+    token_type: String,
+    warehouse: String,
+    database: String,
+    schema: String,
+    role: Option<String>,
+}
+
+impl SnowflakeClient {
+    /// Creates a client for `https://<account>.snowflakecomputing.com`.
+    ///
+    /// `token_type` is sent as `X-Snowflake-Authorization-Token-Type`
+    /// (`OAUTH`, `KEYPAIR_JWT`, or `PROGRAMMATIC_ACCESS_TOKEN`).
+    pub fn new(
+        account: &str,
+        token: impl Into<String>,
+        token_type: impl Into<String>,
+        warehouse: impl Into<String>,
+        database: impl Into<String>,
+        schema: impl Into<String>,
+    ) -> Self {
+        Self {
+            http: reqwest::Client::new(),
+            endpoint: format!("https://{account}.snowflakecomputing.com"),
+            token: token.into(),
+            token_type: token_type.into(),
+            warehouse: warehouse.into(),
+            database: database.into(),
+            schema: schema.into(),
+            role: None,
+        }
+    }
+
+    /// Overrides the base URL (private link or test servers).
+    pub fn with_endpoint(mut self, endpoint: impl Into<String>) -> Self {
+        self.endpoint = endpoint.into().trim_end_matches('/').to_string();
+        self
+    }
+
+    pub fn with_role(mut self, role: impl Into<Option<String>>) -> Self {
+        self.role = role.into();
+        self
+    }
+
+    /// Builds the JSON request body for one statement with positional bindings.
+    pub fn statement_body(&self, sql: &str, bindings: &[String]) -> Value {
+        let mut body = json!({
+            "statement": sql,
+            "timeout": STATEMENT_TIMEOUT_SECS,
+            "warehouse": self.warehouse,
+            "database": self.database,
+            "schema": self.schema,
+        });
+        if let Some(role) = &self.role {
+            body["role"] = json!(role);
+        }
+        if !bindings.is_empty() {
+            let binds: Map<String, Value> = bindings
+                .iter()
+                .enumerate()
+                .map(|(i, v)| ((i + 1).to_string(), json!({"type": "TEXT", "value": v})))
+                .collect();
+            body["bindings"] = Value::Object(binds);
+        }
+        body
+    }
+
+    /// Executes a single statement and waits for it to finish.
+    pub async fn execute(&self, sql: &str, bindings: &[String]) -> Result<Value> {
+        let url = format!("{}/api/v2/statements", self.endpoint);
+        let resp = self
+            .authorized(self.http.post(&url))
+            .json(&self.statement_body(sql, bindings))
+            .send()
+            .await?;
+
+        let mut status = resp.status();
+        let mut body: Value = resp.json().await.unwrap_or(Value::Null);
+
+        // 202: statement still running; poll its handle until it completes
+        while status == reqwest::StatusCode::ACCEPTED {
+            let handle = body
+                .get("statementHandle")
+                .and_then(Value::as_str)
+                .ok_or_else(|| {
+                    ApitapError::WriterError(
+                        "Snowflake: async response without statementHandle".to_string(),
+                    )
+                })?
+                .to_string();
+            tokio::time::sleep(POLL_INTERVAL).await;
+            let resp = self
+                .authorized(self.http.get(format!("{url}/{handle}")))
+                .send()
+                .await?;
+            status = resp.status();
+            body = resp.json().await.unwrap_or(Value::Null);
+        }
+
+        if !status.is_success() {
+            let message = body
+                .get("message")
+                .and_then(Value::as_str)
+                .unwrap_or("no error message");
+            return Err(ApitapError::WriterError(format!(
+                "Snowflake: statement failed ({status}): {message}"
+            )));
+        }
+
+        Ok(body)
+    }
+
+    fn authorized(&self, req: reqwest::RequestBuilder) -> reqwest::RequestBuilder {
+        req.bearer_auth(&self.token)
+            .header("X-Snowflake-Authorization-Token-Type", &self.token_type)
+            .header(reqwest::header::ACCEPT, "application/json")
+    }
+}
+
+//=============== Snowflake Writer ============================================//
+
+pub struct SnowflakeWriter {
+    pub client: Arc<SnowflakeClient>,
+    pub table_name: String,
+    pub primary_key: Option<String>,
+    pub batch_size: usize,
+    pub sample_size: usize,
+    pub auto_create: bool,
+    pub auto_truncate: bool,
+    columns_cache: tokio::sync::RwLock<Option<BTreeMap<String, SfType>>>,
+}
+
+impl SnowflakeWriter {
+    pub fn new(client: Arc<SnowflakeClient>, table_name: impl Into<String>) -> Self {
+        Self {
+            client,
+            table_name: table_name.into(),
+            batch_size: 5000,
+            sample_size: 10,
+            auto_create: true,
+            auto_truncate: false,
+            primary_key: None,
+            columns_cache: tokio::sync::RwLock::new(None),
+        }
+    }
+
+    pub fn with_primary_key_single(mut self, name: impl Into<Option<String>>) -> Self {
+        self.primary_key = name.into();
+        self
+    }
+
+    pub fn with_batch_size(mut self, size: usize) -> Self {
+        self.batch_size = size;
+        self
+    }
+
+    pub fn with_sample_size(mut self, size: usize) -> Self {
+        self.sample_size = size;
+        self
+    }
+
+    pub fn auto_create(mut self, enabled: bool) -> Self {
+        self.auto_create = enabled;
+        self
+    }
+
+    pub fn auto_truncate(mut self, enabled: bool) -> Self {
+        self.auto_truncate = enabled;
+        self
+    }
+
+    pub fn analyze_schema(rows: &[Value], sample_size: usize) -> Result<BTreeMap<String, SfType>> {
+        let mut column_types: BTreeMap<String, SfType> = BTreeMap::new();
+
+        for row in &rows[..rows.len().min(sample_size)] {
+            let obj = row
+                .as_object()
+                .ok_or_else(|| ApitapError::PipelineError("Expected JSON object".to_string()))?;
+
+            for (key, value) in obj {
+                let ty = SfType::from_json_value(value);
+                column_types
+                    .entry(key.clone())
+                    .and_modify(|t| *t = t.merge(&ty))
+                    .or_insert(ty);
+            }
+        }
+
+        Ok(column_types)
+    }
+
+    pub fn quote_ident(ident: &str) -> String {
+        format!(r#""{}""#, ident.replace('"', r#""""#))
+    }
+
+    pub fn quote_ident_path(path: &str) -> String {
+        path.split('.')
+            .map(Self::quote_ident)
+            .collect::<Vec<_>>()
+            .join(".")
+    }
+
+    /// Staging table path for one batch of `table`:
+    /// `db.schema.orders` → `db.schema.orders__APITAP_STAGE_<batch_id>`.
+    pub fn stage_table_name(table: &str, batch_id: &str) -> String {
+        format!("{table}{STAGE_SUFFIX}_{batch_id}")
+    }
+
+    pub fn create_table_sql(table: &str, schema: &BTreeMap<String, SfType>) -> String {
+        let column_defs: Vec<String> = schema
+            .iter()
+            .map(|(name, ty)| format!("{} {}", Self::quote_ident(name), ty.as_sql()))
+            .collect();
+        format!(
+            "CREATE TABLE IF NOT EXISTS {} ({})",
+            Self::quote_ident_path(table),
+            column_defs.join(", ")
+        )
+    }
+
+    /// Cheap query that fails if `table` doesn't exist.
+    pub fn table_probe_sql(table: &str) -> String {
+        format!("SELECT 1 FROM {} LIMIT 0", Self::quote_ident_path(table))
+    }
+
+    pub fn create_stage_sql(stage: &str) -> String {
+        format!(
+            "CREATE TRANSIENT TABLE {} (seq NUMBER AUTOINCREMENT, v VARIANT)",
+            Self::quote_ident_path(stage)
+        )
+    }
+
+    pub fn drop_stage_sql(stage: &str) -> String {
+        format!("DROP TABLE IF EXISTS {}", Self::quote_ident_path(stage))
+    }
+
+    /// `INSERT` that stages `rows` JSON documents, bound positionally.
+    pub fn stage_insert_sql(stage: &str, rows: usize) -> String {
+        let placeholders = vec!["(?)"; rows].join(", ");
+        format!(
+            "INSERT INTO {} (v) SELECT PARSE_JSON(column1) FROM VALUES {}",
+            Self::quote_ident_path(stage),
+            placeholders
+        )
+    }
+
+    fn projection(name: &str, ty: SfType) -> String {
+        let path = format!("v:{}", Self::quote_ident(name));
+        match ty {
+            SfType::Variant => path,
+            other => format!("{path}::{}", other.as_sql()),
+        }
+    }
+
+    pub fn append_sql(table: &str, stage: &str, schema: &BTreeMap<String, SfType>) -> String {
+        let cols: Vec<String> = schema.keys().map(|n| Self::quote_ident(n)).collect();
+        let projections: Vec<String> = schema
+            .iter()
+            .map(|(n, t)| Self::projection(n, *t))
+            .collect();
+        format!(
+            "INSERT INTO {} ({}) SELECT {} FROM {} ORDER BY seq",
+            Self::quote_ident_path(table),
+            cols.join(", "),
+            projections.join(", "),
+            Self::quote_ident_path(stage)
+        )
+    }
+
+    /// `MERGE` from the staging table, keeping the last staged row per key.
+    pub fn merge_sql(
+        table: &str,
+        stage: &str,
+        schema: &BTreeMap<String, SfType>,
+        pk: &str,
+    ) -> Result<String> {
+        Self::merge_sql_inner(table, stage, schema, pk, true)
+    }
+
+    /// Like [`merge_sql`](Self::merge_sql) but only inserts keys the table does not have yet.
+    pub fn insert_new_sql(
+        table: &str,
+        stage: &str,
+        schema: &BTreeMap<String, SfType>,
+        pk: &str,
+    ) -> Result<String> {
+        Self::merge_sql_inner(table, stage, schema, pk, false)
+    }
+
+    fn merge_sql_inner(
+        table: &str,
+        stage: &str,
+        schema: &BTreeMap<String, SfType>,
+        pk: &str,
+        update_matched: bool,
+    ) -> Result<String> {
+        let pk_ty = schema.get(pk).ok_or_else(|| {
+            ApitapError::MergeError(format!(
+                "Snowflake: primary key '{pk}' not found in result columns"
+            ))
+        })?;
+
+        let projections: Vec<String> = schema
+            .iter()
+            .map(|(n, t)| format!("{} AS {}", Self::projection(n, *t), Self::quote_ident(n)))
+            .collect();
+        let pk_q = Self::quote_ident(pk);
+
+        let source = format!(
+            "SELECT {} FROM {} QUALIFY ROW_NUMBER() OVER (PARTITION BY {} ORDER BY seq DESC) = 1",
+            projections.join(", "),
+            Self::quote_ident_path(stage),
+            Self::projection(pk, *pk_ty)
+        );
+
+        let updates: Vec<String> = schema
+            .keys()
+            .filter(|n| n.as_str() != pk)
+            .map(|n| {
+                let q = Self::quote_ident(n);
+                format!("tgt.{q} = src.{q}")
+            })
+            .collect();
+        let cols: Vec<String> = schema.keys().map(|n| Self::quote_ident(n)).collect();
+        let values: Vec<String> = cols.iter().map(|c| format!("src.{c}")).collect();
+
+        let mut sql = format!(
+            "MERGE INTO {} AS tgt USING ({}) AS src ON tgt.{pk_q} = src.{pk_q}",
+            Self::quote_ident_path(table),
+            source
+        );
+        if update_matched && !updates.is_empty() {
+            sql.push_str(&format!(
+                " WHEN MATCHED THEN UPDATE SET {}",
+                updates.join(", ")
+            ));
+        }
+        sql.push_str(&format!(
+            " WHEN NOT MATCHED THEN INSERT ({}) VALUES ({})",
+            cols.join(", "),
+            values.join(", ")
+        ));
+        Ok(sql)
+    }
+
+    async fn ensure_table(&self, sample_rows: &[Value]) -> Result<BTreeMap<String, SfType>> {
+        if let Some(schema) = self.columns_cache.read().await.as_ref() {
+            return Ok(schema.clone());
+        }
+
+        if sample_rows.is_empty() {
+            return Err(ApitapError::PipelineError("Need sample data".to_string()));
+        }
+        let schema = Self::analyze_schema(sample_rows, self.sample_size)?;
+        if schema.is_empty() {
+            return Err(ApitapError::PipelineError(
+                "No columns detected".to_string(),
+            ));
+        }
+
+        if self.auto_create {
+            let sql = Self::create_table_sql(&self.table_name, &schema);
+            debug!(sql = %sql, "create table sql");
+            self.client.execute(&sql, &[]).await?;
+        } else {
+            let sql = Self::table_probe_sql(&self.table_name);
+            if let Err(e) = self.client.execute(&sql, &[]).await {
+                return Err(ApitapError::WriterError(format!(
+                    "table '{}' is missing or not accessible and auto_create is false; \
+                     create it (e.g. with a migration) or set auto_create: true ({e})",
+                    self.table_name
+                )));
+            }
+        }
+        for (name, ty) in &schema {
+            tracing::info!(column = %name, typ = %ty.as_sql(), "column type");
+        }
+
+        *self.columns_cache.write().await = Some(schema.clone());
+        Ok(schema)
+    }
+
+    pub async fn truncate(&self) -> Result<()> {
+        let sql = format!(
+            "TRUNCATE TABLE IF EXISTS {}",
+            Self::quote_ident_path(&self.table_name)
+        );
+        tracing::info!(table = %self.table_name, "truncating table");
+        self.client.execute(&sql, &[]).await?;
+        Ok(())
+    }
+
+    /// Stages `rows` into a fresh staging table, runs `load_sql(stage)` to move
+    /// them into the destination, and drops the staging table again.
+    async fn load_batch(
+        &self,
+        rows: &[Value],
+        load_sql: impl FnOnce(&str) -> Result<String>,
+    ) -> Result<()> {
+        let alphabet: [char; 36] = [
+            '0', '1', '2', '3', '4', '5', '6', '7', '8', '9', 'A', 'B', 'C', 'D', 'E', 'F', 'G',
+            'H', 'I', 'J', 'K', 'L', 'M', 'N', 'O', 'P', 'Q', 'R', 'S', 'T', 'U', 'V', 'W', 'X',
+            'Y', 'Z',
+        ];
+        let stage = Self::stage_table_name(&self.table_name, &nanoid::nanoid!(10, &alphabet));
+        let sql = load_sql(&stage)?;
+
+        self.client
+            .execute(&Self::create_stage_sql(&stage), &[])
+            .await?;
+
+        let loaded = async {
+            let bindings = rows
+                .iter()
+                .map(serde_json::to_string)
+                .collect::<std::result::Result<Vec<_>, _>>()?;
+            self.client
+                .execute(&Self::stage_insert_sql(&stage, rows.len()), &bindings)
+                .await?;
+            debug!(sql = %sql, "load sql");
+            self.client.execute(&sql, &[]).await?;
+            Ok(())
+        }
+        .await;
+
+        // Best effort: a leftover transient staging table holds no destination data.
+        if let Err(e) = self
+            .client
+            .execute(&Self::drop_stage_sql(&stage), &[])
+            .await
+        {
+            tracing::warn!(stage = %stage, error = %e, "failed to drop staging table");
+        }
+        loaded
+    }
+
+    pub async fn insert_batch(
+        &self,
+        rows: &[Value],
+        schema: &BTreeMap<String, SfType>,
+    ) -> Result<()> {
+        if rows.is_empty() {
+            return Ok(());
+        }
+        let span = debug_span!("sql.execute", statement = "insert", table = %self.table_name, rows = rows.len());
+        self.load_batch(rows, |stage| {
+            Ok(Self::append_sql(&self.table_name, stage, schema))
+        })
+        .instrument(span)
+        .await
+    }
+
+    pub async fn merge_batch(
+        &self,
+        rows: &[Value],
+        schema: &BTreeMap<String, SfType>,
+    ) -> Result<()> {
+        self.merge_rows(rows, schema, true).await
+    }
+
+    /// Inserts staged rows whose key is not in the table yet; existing rows are left alone.
+    pub async fn insert_ignore_batch(
+        &self,
+        rows: &[Value],
+        schema: &BTreeMap<String, SfType>,
+    ) -> Result<()> {
+        self.merge_rows(rows, schema, false).await
+    }
+
+    async fn merge_rows(
+        &self,
+        rows: &[Value],
+        schema: &BTreeMap<String, SfType>,
+        update_matched: bool,
+    ) -> Result<()> {
+        if rows.is_empty() {
+            info!(table = %self.table_name, "merge_batch: no rows to merge; skipping");
+            return Ok(());
+        }
+        let pk = self.primary_key.as_deref().ok_or_else(|| {
+            ApitapError::MergeError("Snowflake: primary key not configured".to_string())
+        })?;
+        let span = debug_span!("sql.execute", statement = "merge", table = %self.table_name, rows = rows.len());
+        self.load_batch(rows, |stage| {
+            Self::merge_sql_inner(&self.table_name, stage, schema, pk, update_matched)
+        })
+        .instrument(span)
+        .await
+    }
+}
+
+#[async_trait]
+impl DataWriter for SnowflakeWriter {
+    async fn write_stream(
+        &self,
+        mut result: QueryResultStream,
+        write_mode: WriteMode,
+    ) -> Result<()> {
+        let mut buf: Vec<Value> = Vec::with_capacity(self.batch_size);
+        let mut schema: Option<BTreeMap<String, SfType>> = None;
+
+        loop {
+            let item = result.data.next().await;
+            let done = item.is_none();
+            if let Some(item) = item {
+                buf.push(item?);
+            }
+
+            if buf.len() >= self.batch_size || (done && !buf.is_empty()) {
+                if schema.is_none() {
+                    schema = Some(self.ensure_table(&buf).await?);
+                }
+                let schema_ref = schema.as_ref().expect("schema just set");
+                match write_mode {
+                    WriteMode::Append => self.insert_batch(&buf, schema_ref).await?,
+                    WriteMode::Merge => self.merge_batch(&buf, schema_ref).await?,
+                    WriteMode::Insert => self.insert_ignore_batch(&buf, schema_ref).await?,
+                }
+                buf.clear();
+            }
+
+            if done {
+                break;
+            }
+        }
+
+        Ok(())
+    }
+
+    async fn write(&self, result: QueryResult) -> Result<()> {
+        let rows = result
+            .data
+            .as_array()
+            .ok_or_else(|| ApitapError::PipelineError("Expected JSON array".to_string()))?;
+
+        if rows.is_empty() {
+            return Ok(());
+        }
+
+        let schema = self.ensure_table(rows).await?;
+
+        for chunk in rows.chunks(self.batch_size) {
+            self.insert_batch(chunk, &schema).await?;
+        }
+
+        Ok(())
+    }
+}
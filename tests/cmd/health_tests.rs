@@ -0,0 +1,89 @@
+use apitap::cmd::health::{spawn_health_server, HealthState};
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::net::TcpStream;
+
+/// Sends a bare GET and returns the status line and body.
+async fn get(addr: std::net::SocketAddr, path: &str) -> (String, serde_json::Value) {
+    let mut socket = TcpStream::connect(addr).await.unwrap();
+    socket
+        .write_all(format!("GET {path} HTTP/1.1\r\nhost: localhost\r\n\r\n").as_bytes())
+        .await
+        .unwrap();
+    let mut response = String::new();
+    socket.read_to_string(&mut response).await.unwrap();
+    let (head, body) = response.split_once("\r\n\r\n").unwrap();
+    (
+        head.lines().next().unwrap().to_string(),
+        serde_json::from_str(body).unwrap(),
+    )
+}
+
+#[tokio::test]
+async fn test_healthz_always_ok() {
+    let state = HealthState::new();
+    let addr = spawn_health_server("127.0.0.1:0".parse().unwrap(), state)
+        .await
+        .unwrap();
+
+    let (status, body) = get(addr, "/healthz").await;
+    assert_eq!(status, "HTTP/1.1 200 OK");
+    assert_eq!(body["status"], "ok");
+
+    let (status, _) = get(addr, "/nope").await;
+    assert_eq!(status, "HTTP/1.1 404 Not Found");
+}
+
+#[tokio::test]
+async fn test_readyz_tracks_startup_and_module_runs() {
+    let state = HealthState::new();
+    let addr = spawn_health_server("127.0.0.1:0".parse().unwrap(), state.clone())
+        .await
+        .unwrap();
+
+    let (status, body) = get(addr, "/readyz").await;
+    assert_eq!(status, "HTTP/1.1 503 Service Unavailable");
+    assert_eq!(body["ready"], false);
+
+    state.set_config_loaded(true);
+    state.register_module("orders.sql");
+    state.register_module("users.sql");
+    state.set_scheduler_started(true);
+    state.record_success("orders.sql");
+    state.record_failure("users.sql", "HTTP request failed: 500");
+
+    let (status, body) = get(addr, "/readyz").await;
+    assert_eq!(status, "HTTP/1.1 200 OK");
+    assert_eq!(body["ready"], true);
+    assert!(body["modules"]["orders.sql"]["last_success"].is_string());
+    assert!(body["modules"]["orders.sql"]["last_failure"].is_null());
+    assert!(body["modules"]["users.sql"]["last_success"].is_null());
+    assert_eq!(
+        body["modules"]["users.sql"]["last_error"],
+        "HTTP request failed: 500"
+    );
+}
+
+#[test]
+fn test_health_state_clones_share_state() {
+    let state = HealthState::new();
+    let job_view = state.clone();
+
+    job_view.record_success("orders.sql");
+    assert!(state.module("orders.sql").unwrap().last_success.is_some());
+    assert!(state.module("missing.sql").is_none());
+    assert!(!state.is_ready());
+}
+
+#[test]
+fn test_module_status_last_run_succeeded() {
+    let state = HealthState::new();
+    state.register_module("orders.sql");
+    assert!(!state.module("orders.sql").unwrap().last_run_succeeded());
+
+    state.record_success("orders.sql");
+    assert!(state.module("orders.sql").unwrap().last_run_succeeded());
+    state.record_failure("orders.sql", "boom");
+    assert!(!state.module("orders.sql").unwrap().last_run_succeeded());
+    state.record_success("orders.sql");
+    assert!(state.module("orders.sql").unwrap().last_run_succeeded());
+}
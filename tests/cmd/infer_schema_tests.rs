@@ -0,0 +1,60 @@
+use apitap::cmd::{infer_module_schema, RunOptions};
+use std::fs;
+use tempfile::TempDir;
+
+use crate::common::http_server::{Reply, TestServer};
+
+/// Answers per the request's `Accept`: JSON, CSV, or XML otherwise.
+async fn serve_negotiated() -> String {
+    let server = TestServer::start(|req| {
+        if req.contains("accept: application/json") {
+            Reply::json(r#"[{"id": 1, "name": "a"}]"#)
+        } else if req.contains("accept: text/csv") {
+            Reply::content("text/csv", "id,sku\n1,a-1\n")
+        } else {
+            Reply::content("application/xml", "<items><item><id>1</id></item></items>")
+        }
+    })
+    .await;
+    server.url
+}
+
+#[tokio::test]
+async fn test_accept_header_negotiates_response_format() {
+    let url = serve_negotiated().await;
+    let dir = TempDir::new().unwrap();
+    let root = dir.path().to_str().unwrap();
+    fs::write(
+        dir.path().join("items.sql"),
+        r#"{{ sink(name="pg") }}SELECT * FROM {{ use_source("items") }}"#,
+    )
+    .unwrap();
+    let cfg_path = dir.path().join("pipelines.yaml");
+    let config = |accept: &str| {
+        format!(
+            r#"
+sources:
+  - name: items
+    url: {url}/items
+    {accept}
+    retry:
+      max_attempts: 0
+      max_delay_secs: 1
+      min_delay_secs: 1
+targets: []
+"#
+        )
+    };
+    let opts = RunOptions::default();
+    let infer = || infer_module_schema(root, cfg_path.to_str().unwrap(), "items.sql", &opts);
+
+    // JSON is requested by default
+    fs::write(&cfg_path, config("")).unwrap();
+    let schema = infer().await.unwrap();
+    assert!(schema.field_with_name("name").is_ok(), "{schema:?}");
+
+    // `accept` alone also picks the format the body is read as
+    fs::write(&cfg_path, config("accept: text/csv")).unwrap();
+    let schema = infer().await.unwrap();
+    assert!(schema.field_with_name("sku").is_ok(), "{schema:?}");
+}
@@ -0,0 +1,5 @@
+mod health_tests;
+mod infer_schema_tests;
+mod print_sql_tests;
+mod run_once_tests;
+mod summary_tests;
@@ -0,0 +1,62 @@
+use apitap::cmd::render_modules_sql;
+use std::fs;
+use tempfile::TempDir;
+
+const CONFIG: &str = r#"
+sources:
+  - name: users
+    url: https://api.example.com/users
+    table_destination_name: analytics.dim_users
+targets: []
+"#;
+
+fn modules() -> TempDir {
+    let dir = TempDir::new().unwrap();
+    fs::write(
+        dir.path().join("users.sql"),
+        r#"{{ sink(name="pg") }}
+SELECT id, 'users' AS kind FROM {{ use_source("users") }}
+"#,
+    )
+    .unwrap();
+    fs::write(
+        dir.path().join("cleanup.sql"),
+        r#"{{ sink(name="pg") }}DELETE FROM audit WHERE ts < now() - interval '30 days'"#,
+    )
+    .unwrap();
+    fs::write(dir.path().join("pipelines.yaml"), CONFIG).unwrap();
+    dir
+}
+
+#[test]
+fn test_render_modules_sql_prints_each_module() {
+    let dir = modules();
+    let root = dir.path().to_str().unwrap();
+    let cfg = dir.path().join("pipelines.yaml");
+
+    let out = render_modules_sql(root, cfg.to_str().unwrap(), None).unwrap();
+    assert_eq!(
+        out,
+        "-- cleanup.sql: runs on pg\n\
+         DELETE FROM audit WHERE ts < now() - interval '30 days'\n\
+         \n\
+         -- users.sql: source 'users' → table analytics.dim_users\n\
+         \n\
+         SELECT id, 'users' AS kind FROM dim_users\n\
+         \n"
+    );
+}
+
+#[test]
+fn test_render_modules_sql_single_module() {
+    let dir = modules();
+    let root = dir.path().to_str().unwrap();
+    let cfg = dir.path().join("pipelines.yaml");
+
+    let out = render_modules_sql(root, cfg.to_str().unwrap(), Some("users.sql")).unwrap();
+    assert!(out.starts_with("-- users.sql:"));
+    assert!(!out.contains("cleanup.sql"));
+
+    let err = render_modules_sql(root, cfg.to_str().unwrap(), Some("missing.sql")).unwrap_err();
+    assert!(err.to_string().contains("module 'missing.sql' not found"));
+}
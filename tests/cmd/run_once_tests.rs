@@ -0,0 +1,497 @@
+use crate::common::http_server::{Reply, TestServer};
+use apitap::cmd::{run_modules_once, Cli, OnModuleError, RunOptions};
+use apitap::errors::Result;
+use apitap::pipeline::backfill::BackfillMode;
+use apitap::pipeline::sink::{Hook, WriterOpts};
+use apitap::pipeline::{Config, CustomSink};
+use apitap::utils::datafusion_ext::QueryResult;
+use apitap::writer::factory::{register_writer_factory, WriterFactory};
+use apitap::writer::DataWriter;
+use clap::Parser;
+use std::fs;
+use std::sync::Arc;
+use tempfile::TempDir;
+
+fn config() -> Config {
+    serde_yaml::from_str("sources: []\ntargets: []\n").unwrap()
+}
+
+#[tokio::test]
+async fn test_run_modules_once_reports_each_module() {
+    let dir = TempDir::new().unwrap();
+    let root = dir.path().to_str().unwrap();
+    fs::write(
+        dir.path().join("a_missing_source.sql"),
+        r#"{{ sink(name="pg") }}SELECT * FROM {{ use_source("nope") }}"#,
+    )
+    .unwrap();
+    fs::write(dir.path().join("b_no_sink.sql"), "SELECT 1").unwrap();
+
+    let results = run_modules_once(root, &[], &config(), &RunOptions::default())
+        .await
+        .unwrap();
+
+    let modules: Vec<&str> = results.iter().map(|r| r.module.as_str()).collect();
+    assert_eq!(modules, vec!["a_missing_source.sql", "b_no_sink.sql"]);
+    assert!(results.iter().all(|r| !r.is_success()));
+    let errors: Vec<String> = results
+        .iter()
+        .map(|r| r.result.as_ref().unwrap_err().to_string())
+        .collect();
+    assert!(
+        errors[0].contains("source not found in config: nope"),
+        "{}",
+        errors[0]
+    );
+    assert!(errors[1].contains("declares no sink"), "{}", errors[1]);
+
+    assert_eq!(results[0].source, "nope");
+    assert_eq!(results[0].sinks, vec!["pg"]);
+    let row = results[0].summary_row();
+    assert_eq!(row.rows, None);
+    assert!(row.error.unwrap().contains("source not found"));
+}
+
+#[tokio::test]
+async fn test_run_modules_once_selects_named_modules() {
+    let dir = TempDir::new().unwrap();
+    let root = dir.path().to_str().unwrap();
+    fs::write(dir.path().join("a.sql"), "SELECT 1").unwrap();
+    fs::write(dir.path().join("b.sql"), "SELECT 1").unwrap();
+
+    let prefixed = format!("{root}/b.sql");
+    let results = run_modules_once(root, &[prefixed], &config(), &RunOptions::default())
+        .await
+        .unwrap();
+    assert_eq!(results.len(), 1);
+    assert_eq!(results[0].module, "b.sql");
+
+    let err = run_modules_once(
+        root,
+        &["c.sql".to_string()],
+        &config(),
+        &RunOptions::default(),
+    )
+    .await
+    .unwrap_err();
+    assert!(err.to_string().contains("module 'c.sql' not found"));
+}
+
+#[tokio::test]
+async fn test_swap_and_auto_truncate_are_exclusive() {
+    let dir = TempDir::new().unwrap();
+    let root = dir.path().to_str().unwrap();
+    fs::write(
+        dir.path().join("users.sql"),
+        r#"{{ sink(name="pg", swap=true) }}SELECT * FROM {{ use_source("users") }}"#,
+    )
+    .unwrap();
+    let config: Config = serde_yaml::from_str(
+        r#"
+sources:
+  - name: users
+    url: http://127.0.0.1:9/users
+targets:
+  - type: postgres
+    name: pg
+    host: 127.0.0.1
+    database: app
+    auth: { username: u, password: p }
+    auto_truncate: true
+"#,
+    )
+    .unwrap();
+
+    let results = run_modules_once(root, &[], &config, &RunOptions::default())
+        .await
+        .unwrap();
+    let err = results[0].result.as_ref().unwrap_err().to_string();
+    assert!(
+        err.contains("sink 'pg': swap and auto_truncate cannot both be set"),
+        "{err}"
+    );
+}
+
+#[tokio::test]
+async fn test_resume_rejects_auto_truncate() {
+    let dir = TempDir::new().unwrap();
+    let root = dir.path().to_str().unwrap();
+    fs::write(
+        dir.path().join("events.sql"),
+        r#"{{ sink(name="pg", auto_truncate=true) }}SELECT * FROM {{ use_source("events") }}"#,
+    )
+    .unwrap();
+    let config: Config = serde_yaml::from_str(
+        r#"
+sources:
+  - name: events
+    url: http://127.0.0.1:9/events
+    pagination: { kind: page_number, page_param: page, per_page_param: per_page }
+    resume: {}
+targets:
+  - type: postgres
+    name: pg
+    host: 127.0.0.1
+    database: app
+    auth: { username: u, password: p }
+"#,
+    )
+    .unwrap();
+
+    let results = run_modules_once(root, &[], &config, &RunOptions::default())
+        .await
+        .unwrap();
+    let err = results[0].result.as_ref().unwrap_err().to_string();
+    assert!(
+        err.contains("source 'events' has resume, which cannot be combined with swap or auto_truncate"),
+        "{err}"
+    );
+}
+
+#[tokio::test]
+async fn test_run_modules_once_fail_fast_skips_later_modules() {
+    let dir = TempDir::new().unwrap();
+    let root = dir.path().to_str().unwrap();
+    fs::write(dir.path().join("a.sql"), "SELECT 1").unwrap();
+    fs::write(dir.path().join("b.sql"), "SELECT 1").unwrap();
+
+    let opts = RunOptions {
+        on_module_error: Some(OnModuleError::FailFast),
+        ..RunOptions::default()
+    };
+    let results = run_modules_once(root, &[], &config(), &opts).await.unwrap();
+    assert_eq!(results.len(), 1);
+    assert_eq!(results[0].module, "a.sql");
+    assert!(!results[0].is_success());
+}
+
+#[tokio::test]
+async fn test_run_modules_once_runs_dependencies_first() {
+    let dir = TempDir::new().unwrap();
+    let root = dir.path().to_str().unwrap();
+    fs::write(
+        dir.path().join("a_daily.sql"),
+        r#"{{ depends_on("b_raw") }}{{ sink(name="pg") }}SELECT 1"#,
+    )
+    .unwrap();
+    fs::write(dir.path().join("b_raw.sql"), "SELECT 1").unwrap();
+
+    let results = run_modules_once(root, &[], &config(), &RunOptions::default())
+        .await
+        .unwrap();
+    let modules: Vec<&str> = results.iter().map(|r| r.module.as_str()).collect();
+    assert_eq!(modules, vec!["b_raw.sql", "a_daily.sql"]);
+    // b_raw fails (no sink), so a_daily never runs
+    let err = results[1].result.as_ref().unwrap_err().to_string();
+    assert!(
+        err.contains("skipped: dependency 'b_raw.sql' did not succeed"),
+        "{err}"
+    );
+    assert_eq!(results[1].sinks, vec!["pg"]);
+}
+
+#[tokio::test]
+async fn test_run_modules_once_rejects_dependency_cycles() {
+    let dir = TempDir::new().unwrap();
+    let root = dir.path().to_str().unwrap();
+    fs::write(
+        dir.path().join("a.sql"),
+        r#"{{ depends_on("b.sql") }}SELECT 1"#,
+    )
+    .unwrap();
+    fs::write(
+        dir.path().join("b.sql"),
+        r#"{{ depends_on("a.sql") }}SELECT 1"#,
+    )
+    .unwrap();
+
+    let err = run_modules_once(root, &[], &config(), &RunOptions::default())
+        .await
+        .unwrap_err();
+    assert!(
+        err.to_string()
+            .contains("module dependency cycle: a.sql -> b.sql -> a.sql"),
+        "{err}"
+    );
+}
+
+struct Discard;
+
+#[async_trait::async_trait]
+impl DataWriter for Discard {
+    async fn write(&self, _result: QueryResult) -> Result<()> {
+        Ok(())
+    }
+}
+
+impl WriterFactory for Discard {
+    fn make_writer(
+        &self,
+        _sink: &CustomSink,
+        _opts: &WriterOpts<'_>,
+    ) -> Result<(Arc<dyn DataWriter>, Option<Hook>)> {
+        Ok((Arc::new(Discard), None))
+    }
+}
+
+#[tokio::test]
+async fn test_each_run_rereads_rotated_credentials() {
+    register_writer_factory("run_once_discard", Arc::new(Discard));
+
+    let server = TestServer::start(|req| {
+        if req.line().contains("page=1") {
+            Reply::json(r#"[{"id": 1}]"#)
+        } else {
+            Reply::json("[]")
+        }
+    })
+    .await;
+    let url = &server.url;
+
+    let dir = TempDir::new().unwrap();
+    let root = dir.path().join("modules");
+    fs::create_dir(&root).unwrap();
+    fs::write(
+        root.join("users.sql"),
+        r#"{{ sink(name="discard") }}SELECT id FROM {{ use_source("users") }}"#,
+    )
+    .unwrap();
+    let token = dir.path().join("token");
+    let config: Config = serde_yaml::from_str(&format!(
+        r#"
+sources:
+  - name: users
+    url: {url}/users
+    headers:
+      - key: Authorization
+        value: Bearer ${{FILE:{}}}
+    pagination:
+      kind: page_number
+      page_param: page
+      per_page_param: per_page
+targets:
+  - type: run_once_discard
+    name: discard
+"#,
+        token.display()
+    ))
+    .unwrap();
+
+    // The same loaded config, as the scheduler keeps it between ticks
+    for value in ["first", "rotated"] {
+        fs::write(&token, format!("{value}\n")).unwrap();
+        let results =
+            run_modules_once(root.to_str().unwrap(), &[], &config, &RunOptions::default())
+                .await
+                .unwrap();
+        assert!(results[0].is_success(), "{:?}", results[0].result);
+    }
+
+    // Every request's Authorization header, in order
+    let mut seen: Vec<_> = server
+        .requests()
+        .iter()
+        .map(|req| {
+            req.header("authorization")
+                .unwrap_or_default()
+                .to_lowercase()
+        })
+        .collect();
+    seen.dedup();
+    assert_eq!(seen, vec!["bearer first", "bearer rotated"]);
+}
+
+#[tokio::test]
+async fn test_modules_sharing_a_conditional_source_keep_their_own_validators() {
+    register_writer_factory("run_once_discard", Arc::new(Discard));
+
+    // Answers 304 to any request that sends the ETag back
+    let server = TestServer::start(|req| {
+        if req.header("if-none-match").is_some() {
+            Reply::status("304 Not Modified").header("etag", "\"v1\"")
+        } else {
+            Reply::json(r#"[{"id": 1}]"#).header("etag", "\"v1\"")
+        }
+    })
+    .await;
+    let url = &server.url;
+
+    let dir = TempDir::new().unwrap();
+    let root = dir.path().join("modules");
+    fs::create_dir(&root).unwrap();
+    fs::write(
+        root.join("a.sql"),
+        r#"{{ sink(name="discard") }}SELECT id FROM {{ use_source("orders") }}"#,
+    )
+    .unwrap();
+    fs::write(
+        root.join("b.sql"),
+        r#"{{ depends_on("a.sql") }}{{ sink(name="discard") }}SELECT id FROM {{ use_source("orders") }}"#,
+    )
+    .unwrap();
+    let config: Config = serde_yaml::from_str(&format!(
+        r#"
+state_dir: {}
+sources:
+  - name: orders
+    url: {url}/orders
+    conditional: true
+targets:
+  - type: run_once_discard
+    name: discard
+"#,
+        dir.path().join("state").display()
+    ))
+    .unwrap();
+
+    let results = run_modules_once(root.to_str().unwrap(), &[], &config, &RunOptions::default())
+        .await
+        .unwrap();
+    for result in &results {
+        let stats = result.result.as_ref().unwrap();
+        assert_eq!(stats.total_items, 1, "{}", result.module);
+        assert_eq!(stats.not_modified, 0, "{}", result.module);
+    }
+
+    // Each module's next run sends its own validators back
+    let results = run_modules_once(root.to_str().unwrap(), &[], &config, &RunOptions::default())
+        .await
+        .unwrap();
+    for result in &results {
+        let stats = result.result.as_ref().unwrap();
+        assert_eq!(stats.not_modified, 1, "{}", result.module);
+    }
+}
+
+#[tokio::test]
+async fn test_source_name_inside_other_identifiers_is_left_alone() {
+    register_writer_factory("run_once_discard", Arc::new(Discard));
+
+    let server = TestServer::start(|req| {
+        if req.line().contains("page=1") {
+            Reply::json(r#"[{"id": 1, "username": "ada", "user_id": 7}]"#)
+        } else {
+            Reply::json("[]")
+        }
+    })
+    .await;
+    let url = &server.url;
+
+    let dir = TempDir::new().unwrap();
+    // `user` is part of `username` and `user_id`, and a string literal
+    fs::write(
+        dir.path().join("people.sql"),
+        r#"{{ sink(name="discard") }}
+SELECT user.id, username, user_id, 'user' AS kind
+FROM {{ use_source("user") }}
+WHERE username <> 'user'"#,
+    )
+    .unwrap();
+    // The table name `user` is also part of those columns
+    fs::write(
+        dir.path().join("user.sql"),
+        r#"{{ sink(name="discard") }}SELECT username, user_id FROM {{ use_source("user_api") }}"#,
+    )
+    .unwrap();
+    let config: Config = serde_yaml::from_str(&format!(
+        r#"
+sources:
+  - name: user
+    url: {url}/users
+    table_destination_name: people
+    pagination: &pages
+      kind: page_number
+      page_param: page
+      per_page_param: per_page
+  - name: user_api
+    url: {url}/users
+    table_destination_name: user
+    pagination: *pages
+targets:
+  - type: run_once_discard
+    name: discard
+"#
+    ))
+    .unwrap();
+
+    let results = run_modules_once(
+        dir.path().to_str().unwrap(),
+        &[],
+        &config,
+        &RunOptions::default(),
+    )
+    .await
+    .unwrap();
+    assert_eq!(results.len(), 2);
+    for result in &results {
+        assert!(
+            result.is_success(),
+            "{}: {:?}",
+            result.module,
+            result.result
+        );
+    }
+}
+
+#[test]
+fn test_cli_module_error_flags() {
+    let policy = |args: &[&str]| {
+        let cli = Cli::try_parse_from(["apitap-run"].iter().chain(args)).unwrap();
+        RunOptions::from(&cli).on_module_error
+    };
+    assert_eq!(policy(&[]), None);
+    assert_eq!(
+        policy(&["--continue-on-error"]),
+        Some(OnModuleError::Continue)
+    );
+    assert_eq!(policy(&["--fail-fast"]), Some(OnModuleError::FailFast));
+    assert!(Cli::try_parse_from(["apitap-run", "--fail-fast", "--continue-on-error"]).is_err());
+}
+
+#[test]
+fn test_cli_once_flag() {
+    let cli = Cli::try_parse_from(["apitap-run", "--once"]).unwrap();
+    assert!(cli.once);
+    let cli = Cli::try_parse_from(["apitap-run"]).unwrap();
+    assert!(!cli.once);
+}
+
+#[test]
+fn test_cli_sample_flag() {
+    let cli = Cli::try_parse_from(["apitap-run", "--sample", "50"]).unwrap();
+    assert_eq!(cli.sample, Some(50));
+    assert_eq!(RunOptions::from(&cli).fetch_opts.max_records, Some(50));
+    let cli = Cli::try_parse_from(["apitap-run"]).unwrap();
+    assert_eq!(RunOptions::from(&cli).fetch_opts.max_records, None);
+    assert!(Cli::try_parse_from(["apitap-run", "--sample", "0"]).is_err());
+}
+
+#[test]
+fn test_cli_backfill_flags() {
+    let cli = Cli::try_parse_from([
+        "apitap-run",
+        "--backfill",
+        "--from",
+        "2024-01-01",
+        "--to",
+        "2024-01-31",
+        "--window",
+        "1w",
+        "--backfill-mode",
+        "replace",
+    ])
+    .unwrap();
+    let backfill = cli.backfill().unwrap();
+    assert_eq!(backfill.from.to_string(), "2024-01-01");
+    assert_eq!(backfill.to.to_string(), "2024-01-31");
+    assert_eq!(backfill.window_days, 7);
+    assert_eq!(backfill.mode, BackfillMode::Replace);
+
+    assert!(Cli::try_parse_from(["apitap-run"])
+        .unwrap()
+        .backfill()
+        .is_none());
+    // --backfill needs both ends of the range, and --from needs --backfill
+    assert!(Cli::try_parse_from(["apitap-run", "--backfill", "--from", "2024-01-01"]).is_err());
+    assert!(Cli::try_parse_from(["apitap-run", "--from", "2024-01-01"]).is_err());
+}
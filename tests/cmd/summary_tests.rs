@@ -0,0 +1,57 @@
+use apitap::cmd::summary::{render_table, RunSummary, SummaryRow};
+use std::time::Duration;
+
+fn row(module: &str, rows: Option<usize>, error: Option<&str>) -> SummaryRow {
+    SummaryRow {
+        module: module.into(),
+        source: "orders_api".into(),
+        sinks: vec!["warehouse".into(), "archive".into()],
+        rows,
+        duration: Duration::from_millis(125_000),
+        error: error.map(String::from),
+    }
+}
+
+#[test]
+fn test_render_table_aligns_columns_and_marks_failures() {
+    let mut failed = row("users.sql", None, Some("HTTP request failed: 500\nbody"));
+    failed.source = String::new();
+    failed.sinks = Vec::new();
+    failed.duration = Duration::from_millis(40);
+
+    let table = render_table(&[row("orders.sql", Some(12), None), failed]);
+    assert_eq!(
+        table,
+        "module      source      sink               rows  duration  status\n\
+         orders.sql  orders_api  warehouse,archive  12    2m05s     ✅ ok\n\
+         users.sql   -           -                  -     40ms      ❌ failed: HTTP request failed: 500...\n"
+    );
+}
+
+#[test]
+fn test_render_table_truncates_long_errors() {
+    let error = "x".repeat(200);
+    let table = render_table(&[row("a.sql", None, Some(&error))]);
+    let status = table
+        .lines()
+        .nth(1)
+        .unwrap()
+        .split("❌ failed: ")
+        .nth(1)
+        .unwrap();
+    assert_eq!(status, format!("{}...", "x".repeat(80)));
+}
+
+#[test]
+fn test_run_summary_keeps_latest_run_per_module() {
+    let summary = RunSummary::new();
+    summary.record(row("a.sql", None, Some("boom")));
+    summary.record(row("b.sql", Some(1), None));
+    summary.clone().record(row("a.sql", Some(5), None));
+
+    let rows = summary.rows();
+    assert_eq!(rows.len(), 2);
+    assert_eq!(rows[0].module, "a.sql");
+    assert_eq!(rows[0].rows, Some(5));
+    assert_eq!(rows[0].error, None);
+}
@@ -0,0 +1,300 @@
+//! A small HTTP/1.1 server for tests that need a real socket.
+//!
+//! Each request is read whole (head plus `content-length` body), recorded, and
+//! answered with the [`Reply`] the test's handler picks for it. Connections are
+//! served concurrently, so slow replies don't hold up other requests.
+
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::net::{TcpListener, TcpStream};
+
+/// A request as received.
+#[derive(Debug, Clone)]
+pub struct Request {
+    /// Position among every request the server received, from 0.
+    pub index: usize,
+    /// Request line and headers, as sent.
+    pub head: String,
+    pub body: Vec<u8>,
+}
+
+impl Request {
+    /// The request line, e.g. `GET /items?page=2 HTTP/1.1`.
+    pub fn line(&self) -> &str {
+        self.head.lines().next().unwrap_or_default()
+    }
+
+    /// Whether the head contains `needle`, ignoring ASCII case.
+    pub fn contains(&self, needle: &str) -> bool {
+        self.head
+            .to_ascii_lowercase()
+            .contains(&needle.to_ascii_lowercase())
+    }
+
+    /// The value of header `name`, matched case-insensitively.
+    pub fn header(&self, name: &str) -> Option<&str> {
+        self.head.lines().skip(1).find_map(|line| {
+            let (key, value) = line.split_once(':')?;
+            key.trim().eq_ignore_ascii_case(name).then(|| value.trim())
+        })
+    }
+
+    /// `http://host:port` the request was sent to.
+    pub fn base_url(&self) -> String {
+        format!("http://{}", self.header("host").unwrap_or_default())
+    }
+}
+
+enum Body {
+    Full(Vec<u8>),
+    /// Sent with chunked transfer encoding, pausing between chunks.
+    Chunked(Vec<String>, Duration),
+}
+
+/// The response to one request. `content-length` and `connection: close` are
+/// added unless the reply sets them or the server keeps connections alive.
+pub struct Reply {
+    status: String,
+    headers: Vec<(String, String)>,
+    body: Body,
+    delay: Duration,
+    hang: bool,
+}
+
+impl Reply {
+    /// `status` (e.g. `"503 Service Unavailable"`) with an empty body.
+    pub fn status(status: &str) -> Self {
+        Self {
+            status: status.to_string(),
+            headers: Vec::new(),
+            body: Body::Full(Vec::new()),
+            delay: Duration::ZERO,
+            hang: false,
+        }
+    }
+
+    /// `200 OK` with a JSON `body`.
+    pub fn json(body: impl Into<String>) -> Self {
+        Self::content("application/json", body)
+    }
+
+    /// `200 OK` with `body` as `content_type`.
+    pub fn content(content_type: &str, body: impl Into<String>) -> Self {
+        Self::status("200 OK")
+            .header("content-type", content_type)
+            .body(body)
+    }
+
+    /// Accepts the request but never answers it.
+    pub fn hang() -> Self {
+        Self {
+            hang: true,
+            ..Self::status("200 OK")
+        }
+    }
+
+    pub fn header(mut self, name: &str, value: &str) -> Self {
+        self.headers.push((name.to_string(), value.to_string()));
+        self
+    }
+
+    pub fn body(mut self, body: impl Into<String>) -> Self {
+        self.body = Body::Full(body.into().into_bytes());
+        self
+    }
+
+    /// Sends `chunks` one by one with chunked transfer encoding, `gap` apart.
+    pub fn chunked(mut self, chunks: &[&str], gap: Duration) -> Self {
+        self.body = Body::Chunked(chunks.iter().map(|c| c.to_string()).collect(), gap);
+        self
+    }
+
+    /// Waits `delay` before answering.
+    pub fn after(mut self, delay: Duration) -> Self {
+        self.delay = delay;
+        self
+    }
+
+    fn has_header(&self, name: &str) -> bool {
+        self.headers
+            .iter()
+            .any(|(n, _)| n.eq_ignore_ascii_case(name))
+    }
+
+    async fn write_to(&self, socket: &mut TcpStream, keep_alive: bool) -> std::io::Result<()> {
+        let mut head = format!("HTTP/1.1 {}\r\n", self.status);
+        for (name, value) in &self.headers {
+            head.push_str(&format!("{name}: {value}\r\n"));
+        }
+        match &self.body {
+            Body::Full(bytes) if !self.has_header("content-length") => {
+                head.push_str(&format!("content-length: {}\r\n", bytes.len()));
+            }
+            Body::Full(_) => {}
+            Body::Chunked(..) => head.push_str("transfer-encoding: chunked\r\n"),
+        }
+        if !keep_alive && !self.has_header("connection") {
+            head.push_str("connection: close\r\n");
+        }
+        head.push_str("\r\n");
+        socket.write_all(head.as_bytes()).await?;
+
+        match &self.body {
+            Body::Full(bytes) => socket.write_all(bytes).await?,
+            Body::Chunked(chunks, gap) => {
+                for chunk in chunks {
+                    let framed = format!("{:x}\r\n{chunk}\r\n", chunk.len());
+                    socket.write_all(framed.as_bytes()).await?;
+                    socket.flush().await?;
+                    tokio::time::sleep(*gap).await;
+                }
+                socket.write_all(b"0\r\n\r\n").await?;
+            }
+        }
+        socket.flush().await
+    }
+}
+
+type Handler = Box<dyn Fn(&Request) -> Reply + Send + Sync>;
+
+struct Shared {
+    respond: Handler,
+    keep_alive: bool,
+    requests: Mutex<Vec<Request>>,
+    connections: AtomicUsize,
+    in_flight: AtomicUsize,
+    peak_in_flight: AtomicUsize,
+}
+
+/// A server on `127.0.0.1` that runs until the test's runtime shuts down.
+pub struct TestServer {
+    /// `http://127.0.0.1:<port>`, without a trailing slash.
+    pub url: String,
+    shared: Arc<Shared>,
+}
+
+impl TestServer {
+    /// Answers every request with `respond`, closing the connection after
+    /// each response.
+    pub async fn start(respond: impl Fn(&Request) -> Reply + Send + Sync + 'static) -> Self {
+        Self::spawn(Box::new(respond), false).await
+    }
+
+    /// Like [`start`](Self::start), but keeps connections open between
+    /// requests.
+    pub async fn keep_alive(respond: impl Fn(&Request) -> Reply + Send + Sync + 'static) -> Self {
+        Self::spawn(Box::new(respond), true).await
+    }
+
+    async fn spawn(respond: Handler, keep_alive: bool) -> Self {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let url = format!("http://{}", listener.local_addr().unwrap());
+        let shared = Arc::new(Shared {
+            respond,
+            keep_alive,
+            requests: Mutex::default(),
+            connections: AtomicUsize::new(0),
+            in_flight: AtomicUsize::new(0),
+            peak_in_flight: AtomicUsize::new(0),
+        });
+        let server = Arc::clone(&shared);
+        tokio::spawn(async move {
+            while let Ok((socket, _)) = listener.accept().await {
+                server.connections.fetch_add(1, Ordering::SeqCst);
+                tokio::spawn(serve_connection(socket, Arc::clone(&server)));
+            }
+        });
+        Self { url, shared }
+    }
+
+    /// Every request received so far, in arrival order.
+    pub fn requests(&self) -> Vec<Request> {
+        self.shared.requests.lock().unwrap().clone()
+    }
+
+    /// Number of requests received so far.
+    pub fn hits(&self) -> usize {
+        self.shared.requests.lock().unwrap().len()
+    }
+
+    /// Number of connections accepted so far.
+    pub fn connections(&self) -> usize {
+        self.shared.connections.load(Ordering::SeqCst)
+    }
+
+    /// The most requests that were waiting for their reply at once.
+    pub fn peak_in_flight(&self) -> usize {
+        self.shared.peak_in_flight.load(Ordering::SeqCst)
+    }
+}
+
+async fn serve_connection(mut socket: TcpStream, server: Arc<Shared>) {
+    let mut buf = Vec::new();
+    while let Some((head, body)) = read_request(&mut socket, &mut buf).await {
+        let request = {
+            let mut requests = server.requests.lock().unwrap();
+            let request = Request {
+                index: requests.len(),
+                head,
+                body,
+            };
+            requests.push(request.clone());
+            request
+        };
+        let now = server.in_flight.fetch_add(1, Ordering::SeqCst) + 1;
+        server.peak_in_flight.fetch_max(now, Ordering::SeqCst);
+        let reply = (server.respond)(&request);
+        if reply.hang {
+            std::future::pending::<()>().await;
+        }
+        tokio::time::sleep(reply.delay).await;
+        // Done before writing, so a client that moves on at once is not counted twice
+        server.in_flight.fetch_sub(1, Ordering::SeqCst);
+        if reply
+            .write_to(&mut socket, server.keep_alive)
+            .await
+            .is_err()
+            || !server.keep_alive
+        {
+            break;
+        }
+    }
+}
+
+/// Reads the next request off `socket`, keeping bytes past it in `buf`;
+/// `None` once the peer closes before sending a whole head.
+async fn read_request(socket: &mut TcpStream, buf: &mut Vec<u8>) -> Option<(String, Vec<u8>)> {
+    let mut chunk = [0u8; 4096];
+    let head_end = loop {
+        if let Some(end) = buf.windows(4).position(|w| w == b"\r\n\r\n") {
+            break end;
+        }
+        match socket.read(&mut chunk).await {
+            Ok(0) | Err(_) => return None,
+            Ok(n) => buf.extend_from_slice(&chunk[..n]),
+        }
+    };
+    let head = String::from_utf8_lossy(&buf[..head_end]).into_owned();
+    let length: usize = head
+        .lines()
+        .find_map(|line| {
+            let (key, value) = line.split_once(':')?;
+            key.eq_ignore_ascii_case("content-length")
+                .then(|| value.trim().parse().ok())?
+        })
+        .unwrap_or(0);
+    let body_start = head_end + 4;
+    while buf.len() < body_start + length {
+        match socket.read(&mut chunk).await {
+            Ok(0) | Err(_) => break,
+            Ok(n) => buf.extend_from_slice(&chunk[..n]),
+        }
+    }
+    let body_end = buf.len().min(body_start + length);
+    let body = buf[body_start..body_end].to_vec();
+    buf.drain(..body_end);
+    Some((head, body))
+}
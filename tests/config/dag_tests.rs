@@ -0,0 +1,75 @@
+use apitap::config::dag::ModuleGraph;
+
+fn modules(deps: &[(&str, &[&str])]) -> Vec<(String, Vec<String>)> {
+    deps.iter()
+        .map(|(name, deps)| {
+            (
+                name.to_string(),
+                deps.iter().map(|d| d.to_string()).collect(),
+            )
+        })
+        .collect()
+}
+
+fn available(names: &[&str]) -> Vec<String> {
+    names.iter().map(|n| n.to_string()).collect()
+}
+
+#[test]
+fn test_order_keeps_listing_order_between_independent_modules() {
+    let graph = ModuleGraph::new(
+        &modules(&[
+            ("a.sql", &["c"]),
+            ("b.sql", &[]),
+            ("c.sql", &[]),
+            ("d.sql", &["a.sql", "b.sql"]),
+        ]),
+        &available(&["a.sql", "b.sql", "c.sql", "d.sql"]),
+    )
+    .unwrap();
+
+    assert_eq!(graph.order(), ["b.sql", "c.sql", "a.sql", "d.sql"]);
+    assert_eq!(graph.dependencies("d.sql"), ["a.sql", "b.sql"]);
+    assert!(graph.dependencies("b.sql").is_empty());
+}
+
+#[test]
+fn test_rejects_unknown_and_self_dependencies() {
+    let all = available(&["a.sql", "b.sql"]);
+
+    let err = ModuleGraph::new(&modules(&[("a.sql", &["missing"])]), &all).unwrap_err();
+    assert!(
+        err.to_string()
+            .contains("module 'a.sql' depends on unknown module 'missing'"),
+        "{err}"
+    );
+
+    let err = ModuleGraph::new(&modules(&[("a.sql", &["a"])]), &all).unwrap_err();
+    assert!(
+        err.to_string().contains("module 'a.sql' depends on itself"),
+        "{err}"
+    );
+}
+
+#[test]
+fn test_dependencies_outside_the_run_are_left_out() {
+    // Only c.sql runs; b.sql exists but is not part of this run
+    let graph = ModuleGraph::new(
+        &modules(&[("c.sql", &["b"])]),
+        &available(&["a.sql", "b.sql", "c.sql"]),
+    )
+    .unwrap();
+    assert_eq!(graph.order(), ["c.sql"]);
+    assert!(graph.dependencies("c.sql").is_empty());
+
+    let err = ModuleGraph::new(
+        &modules(&[("a.sql", &["b"]), ("b.sql", &["c"]), ("c.sql", &["b"])]),
+        &available(&["a.sql", "b.sql", "c.sql"]),
+    )
+    .unwrap_err();
+    assert!(
+        err.to_string()
+            .contains("module dependency cycle: b.sql -> c.sql -> b.sql"),
+        "{err}"
+    );
+}
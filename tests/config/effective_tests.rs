@@ -0,0 +1,111 @@
+use apitap::config::effective::effective_config;
+use apitap::pipeline::Config;
+
+fn config(yaml: &str) -> Config {
+    serde_yaml::from_str(yaml).unwrap()
+}
+
+#[test]
+fn test_effective_config_resolves_env_and_defaults() {
+    std::env::set_var("APITAP_EFFECTIVE_BASE", "https://api.example.com");
+    let cfg = config(
+        r#"
+sources:
+  - name: orders
+    url: ${APITAP_EFFECTIVE_BASE}/orders
+    retry: { max_attempts: 3, max_delay_secs: 60, min_delay_secs: 1 }
+targets: []
+"#,
+    );
+
+    let effective = effective_config(&cfg).unwrap();
+    let source = &effective["sources"][0];
+    assert_eq!(source["url"], "https://api.example.com/orders");
+    assert_eq!(source["method"], "GET");
+    assert_eq!(source["retry"]["jitter"], "full");
+}
+
+#[test]
+fn test_effective_config_masks_secrets() {
+    std::env::set_var("APITAP_EFFECTIVE_TOKEN", "s3cr3t");
+    let cfg = config(
+        r#"
+sources:
+  - name: orders
+    url: https://api.example.com/orders?api_key=abc&page=1
+    headers:
+      - key: Authorization
+        value: Bearer ${APITAP_EFFECTIVE_TOKEN}
+      - key: Accept
+        value: application/json
+    query_params:
+      - key: token
+        value: ${APITAP_EFFECTIVE_TOKEN}
+    signing:
+      kind: hmac
+      secret: ${APITAP_EFFECTIVE_TOKEN}
+    retry: { max_attempts: 3, max_delay_secs: 60, min_delay_secs: 1 }
+targets: []
+"#,
+    );
+
+    let effective = effective_config(&cfg).unwrap();
+    let rendered = effective.to_string();
+    assert!(!rendered.contains("s3cr3t"), "{rendered}");
+
+    let source = &effective["sources"][0];
+    assert_eq!(
+        source["url"],
+        "https://api.example.com/orders?api_key=***&page=1"
+    );
+    assert_eq!(source["headers"][0]["key"], "Authorization");
+    assert_eq!(source["headers"][0]["value"], "***");
+    assert_eq!(source["headers"][1]["value"], "application/json");
+    assert_eq!(source["query_params"][0]["value"], "***");
+    assert_eq!(source["signing"]["secret"], "***");
+}
+
+#[test]
+fn test_effective_config_keeps_unresolved_references() {
+    std::env::remove_var("APITAP_EFFECTIVE_MISSING");
+    let cfg = config(
+        r#"
+sources:
+  - name: orders
+    url: https://${APITAP_EFFECTIVE_MISSING}/orders
+    retry: { max_attempts: 3, max_delay_secs: 60, min_delay_secs: 1 }
+targets: []
+"#,
+    );
+
+    let effective = effective_config(&cfg).unwrap();
+    assert_eq!(
+        effective["sources"][0]["url"],
+        "https://${APITAP_EFFECTIVE_MISSING}/orders"
+    );
+}
+
+#[test]
+fn test_effective_config_masks_query_auth() {
+    let cfg = config(
+        r#"
+sources:
+  - name: legacy
+    url: https://legacy.example.com/items?acct_key=abc
+    auth: { type: query, param: acct_key, value: plain-key }
+    retry: { max_attempts: 3, max_delay_secs: 60, min_delay_secs: 1 }
+targets: []
+"#,
+    );
+
+    let effective = effective_config(&cfg).unwrap();
+    let source = &effective["sources"][0];
+    assert_eq!(source["auth"]["type"], "query");
+    assert_eq!(source["auth"]["param"], "acct_key");
+    assert_eq!(source["auth"]["value"], "***");
+    // The auth param is masked wherever it appears
+    assert_eq!(
+        source["url"],
+        "https://legacy.example.com/items?acct_key=***"
+    );
+}
@@ -1 +1,5 @@
+mod dag_tests;
+mod effective_tests;
+mod remote_tests;
+mod schedule_tests;
 mod templating_tests;
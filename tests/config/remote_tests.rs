@@ -0,0 +1,92 @@
+use apitap::config::remote::RemoteCache;
+
+use crate::common::http_server::{Reply, TestServer};
+
+/// Serves `status` and `body` and returns the base URL.
+async fn serve(status: &'static str, body: &'static str) -> String {
+    TestServer::start(move |_| Reply::status(status).body(body))
+        .await
+        .url
+}
+
+#[tokio::test]
+async fn test_remote_config_is_downloaded_into_the_cache() {
+    let base = serve("200 OK", "sources: []\ntargets: []\n").await;
+    let cache = RemoteCache::new().unwrap();
+
+    let local = cache
+        .config(&format!("{base}/prod/pipelines.yaml?X-Amz-Signature=abc"))
+        .await
+        .unwrap();
+    assert!(local.starts_with(cache.path().to_str().unwrap()));
+    assert!(local.ends_with("pipelines.yaml"));
+    assert_eq!(
+        std::fs::read_to_string(&local).unwrap(),
+        "sources: []\ntargets: []\n"
+    );
+    apitap::config::load_config_from_path(&local).unwrap();
+
+    // Local paths are used as they are
+    assert_eq!(
+        cache.config("pipelines.yaml").await.unwrap(),
+        "pipelines.yaml"
+    );
+    assert_eq!(cache.modules("pipelines").await.unwrap(), "pipelines");
+
+    let dir = cache.path().to_path_buf();
+    drop(cache);
+    assert!(!dir.exists());
+}
+
+#[tokio::test]
+async fn test_remote_config_error_status_fails() {
+    let base = serve("404 Not Found", "").await;
+    let cache = RemoteCache::new().unwrap();
+    assert!(cache
+        .config(&format!("{base}/pipelines.yaml"))
+        .await
+        .is_err());
+}
+
+#[tokio::test]
+async fn test_remote_modules_over_http_are_rejected() {
+    let cache = RemoteCache::new().unwrap();
+    let err = cache
+        .modules("https://config.example.com/modules")
+        .await
+        .unwrap_err();
+    assert!(err.to_string().contains("cannot be listed over HTTP"));
+}
+
+#[cfg(feature = "object_store")]
+#[tokio::test]
+async fn test_download_prefix_copies_modules() {
+    use apitap::config::remote::download_prefix;
+    use apitap::config::templating::list_sql_templates;
+    use object_store::{memory::InMemory, path::Path, ObjectStore, PutPayload};
+
+    let store = InMemory::new();
+    for (key, body) in [
+        ("prod/modules/orders.sql", "SELECT 1"),
+        ("prod/modules/raw/users.sql", "SELECT 2"),
+        ("prod/other/skip.sql", "SELECT 3"),
+    ] {
+        store
+            .put(&Path::from(key), PutPayload::from_static(body.as_bytes()))
+            .await
+            .unwrap();
+    }
+
+    let dest = tempfile::tempdir().unwrap();
+    let count = download_prefix(&store, &Path::from("prod/modules"), dest.path())
+        .await
+        .unwrap();
+    assert_eq!(count, 2);
+    let mut modules = list_sql_templates(dest.path()).unwrap();
+    modules.sort();
+    assert_eq!(modules, ["orders.sql", "raw/users.sql"]);
+    assert_eq!(
+        std::fs::read_to_string(dest.path().join("raw/users.sql")).unwrap(),
+        "SELECT 2"
+    );
+}
@@ -0,0 +1,126 @@
+use apitap::config::schedule::validate_cron;
+use apitap::errors::ApitapError;
+
+#[test]
+fn test_validate_cron_accepts_six_field_expressions() {
+    for expr in ["0 */3 * * * *", "0 0 12 * * MON-FRI", "*/10 * * * * *"] {
+        assert!(
+            validate_cron("m.sql", expr).is_ok(),
+            "{expr} should be valid"
+        );
+    }
+}
+
+#[test]
+fn test_validate_cron_rejects_invalid_expressions() {
+    for expr in ["daily_job", "61 * * * * *", "* * *"] {
+        match validate_cron("users.sql", expr) {
+            Err(ApitapError::ConfigError(msg)) => {
+                assert!(
+                    msg.contains("users.sql"),
+                    "message should name module: {msg}"
+                );
+                assert!(msg.contains(expr), "message should name expression: {msg}");
+            }
+            other => panic!("expected ConfigError for {expr}, got {other:?}"),
+        }
+    }
+}
+
+#[test]
+fn test_validate_cron_rejects_missing_schedule() {
+    let err = validate_cron("users.sql", "").unwrap_err();
+    assert!(err.to_string().contains("has no schedule"));
+}
+
+#[test]
+fn test_resolve_schedule_expands_aliases() {
+    use apitap::config::schedule::resolve_schedule;
+
+    let cases = [
+        ("@hourly", "0 0 * * * *"),
+        ("@daily", "0 0 0 * * *"),
+        ("@weekly", "0 0 0 * * SUN"),
+        ("@monthly", "0 0 0 1 * *"),
+        ("@yearly", "0 0 0 1 1 *"),
+        ("Every 5 Minutes", "0 */5 * * * *"),
+        ("every 30 seconds", "*/30 * * * * *"),
+        ("every 2 hours", "0 0 */2 * * *"),
+        ("0 */3 * * * *", "0 */3 * * * *"),
+    ];
+    for (alias, cron) in cases {
+        assert_eq!(resolve_schedule("m.sql", alias).unwrap(), cron, "{alias}");
+    }
+}
+
+#[test]
+fn test_resolve_schedule_rejects_unknown_alias() {
+    use apitap::config::schedule::resolve_schedule;
+
+    assert!(resolve_schedule("m.sql", "every 0 minutes").is_err());
+    assert!(resolve_schedule("m.sql", "every 5 fortnights").is_err());
+    assert!(resolve_schedule("m.sql", "@sometimes").is_err());
+}
+
+#[test]
+fn test_resolve_schedules_with_timezones() {
+    use apitap::config::schedule::resolve_schedules;
+    use apitap::config::templating::ScheduleCapture;
+
+    let capture = |expr: &str, tz: Option<&str>| ScheduleCapture {
+        expr: expr.into(),
+        tz: tz.map(str::to_string),
+    };
+    let schedules = resolve_schedules(
+        "m.sql",
+        &[
+            capture("0 0 9 * * MON-FRI", Some("America/New_York")),
+            capture("@daily", None),
+        ],
+    )
+    .unwrap();
+    assert_eq!(schedules[0].cron, "0 0 9 * * MON-FRI");
+    assert_eq!(schedules[0].tz, chrono_tz::America::New_York);
+    assert_eq!(schedules[1].cron, "0 0 0 * * *");
+    assert_eq!(schedules[1].tz, chrono_tz::UTC);
+
+    let err = resolve_schedules("m.sql", &[capture("@daily", Some("Mars/Olympus"))]).unwrap_err();
+    assert!(err.to_string().contains("Mars/Olympus"), "{err}");
+    let err = resolve_schedules("m.sql", &[]).unwrap_err();
+    assert!(err.to_string().contains("has no schedule"), "{err}");
+}
+
+#[tokio::test]
+async fn test_overlap_guard_skip_drops_concurrent_tick() {
+    use apitap::config::schedule::{OverlapGuard, OverlapPolicy};
+
+    let guard = OverlapGuard::new(OverlapPolicy::Skip);
+    let tick = guard.clone();
+
+    let running = guard.acquire().await.expect("first run proceeds");
+    assert!(
+        tick.acquire().await.is_none(),
+        "overlapping tick is skipped"
+    );
+
+    drop(running);
+    assert!(tick.acquire().await.is_some(), "next tick runs once free");
+}
+
+#[tokio::test]
+async fn test_overlap_guard_queue_waits_for_previous_run() {
+    use apitap::config::schedule::{OverlapGuard, OverlapPolicy};
+    use std::time::Duration;
+
+    let guard = OverlapGuard::new(OverlapPolicy::Queue);
+    let running = guard.acquire().await.unwrap();
+
+    let queued = guard.clone();
+    let waiter = tokio::spawn(async move { queued.acquire().await.is_some() });
+
+    tokio::time::sleep(Duration::from_millis(50)).await;
+    assert!(!waiter.is_finished(), "queued tick waits");
+
+    drop(running);
+    assert!(waiter.await.unwrap(), "queued tick runs after the first");
+}
@@ -1,5 +1,5 @@
 use apitap::config::templating::{
-    build_env_with_captures, list_sql_templates, render_one, RenderCapture,
+    build_env_with_captures, list_sql_templates, render_one, RenderCapture, ScheduleCapture,
 };
 use std::fs;
 use std::sync::{Arc, Mutex};
@@ -33,7 +33,7 @@ SELECT * FROM users;
 
     let result = render_one(&env, &shared_cap, "test.sql").unwrap();
 
-    assert_eq!(result.capture.sink, "postgres_target");
+    assert_eq!(result.capture.sinks[0].name, "postgres_target");
     assert!(result.sql.contains("SELECT * FROM users"));
 }
 
@@ -54,7 +54,7 @@ fn test_use_source_function_captures_name() {
     let result = render_one(&env, &shared_cap, "test.sql").unwrap();
 
     assert_eq!(result.capture.source, "api_users");
-    assert_eq!(result.capture.sink, "postgres_target");
+    assert_eq!(result.capture.sinks[0].name, "postgres_target");
 }
 
 #[test]
@@ -64,6 +64,7 @@ fn test_schedule_function_captures_name() {
 
     // Create a test SQL file with schedule() call
     let sql_content = r#"{{ schedule("daily_job") }}
+{{ schedule("0 0 9 * * MON-FRI", tz="Europe/Berlin") }}
 {{ sink(name="postgres_target") }}
 SELECT * FROM scheduled_data;
 "#;
@@ -74,8 +75,20 @@ SELECT * FROM scheduled_data;
 
     let result = render_one(&env, &shared_cap, "test.sql").unwrap();
 
-    assert_eq!(result.capture.source, "daily_job");
-    assert_eq!(result.capture.sink, "postgres_target");
+    assert_eq!(
+        result.capture.schedules,
+        vec![
+            ScheduleCapture {
+                expr: "daily_job".into(),
+                tz: None,
+            },
+            ScheduleCapture {
+                expr: "0 0 9 * * MON-FRI".into(),
+                tz: Some("Europe/Berlin".into()),
+            },
+        ]
+    );
+    assert_eq!(result.capture.sinks[0].name, "postgres_target");
     assert!(result.sql.contains("SELECT * FROM scheduled_data"));
 }
 
@@ -99,12 +112,12 @@ fn test_render_one_clears_previous_captures() {
 
     // Render first
     let result1 = render_one(&env, &shared_cap, "test1.sql").unwrap();
-    assert_eq!(result1.capture.sink, "sink1");
+    assert_eq!(result1.capture.sinks[0].name, "sink1");
     assert_eq!(result1.capture.source, "source1");
 
     // Render second - captures should be cleared
     let result2 = render_one(&env, &shared_cap, "test2.sql").unwrap();
-    assert_eq!(result2.capture.sink, "");
+    assert!(result2.capture.sinks.is_empty());
     assert_eq!(result2.capture.source, "");
 }
 
@@ -208,3 +221,337 @@ fn test_render_one_with_template_variables() {
 
     assert!(result.sql.contains("LIMIT 10"));
 }
+
+#[test]
+fn test_sink_function_captures_mode() {
+    use apitap::writer::WriteMode;
+
+    let temp_dir = TempDir::new().unwrap();
+    let root = temp_dir.path().to_str().unwrap();
+    fs::write(
+        temp_dir.path().join("events.sql"),
+        r#"{{ sink(name="pg", mode="insert") }}SELECT 1"#,
+    )
+    .unwrap();
+    fs::write(
+        temp_dir.path().join("plain.sql"),
+        r#"{{ sink(name="pg") }}SELECT 1"#,
+    )
+    .unwrap();
+    fs::write(
+        temp_dir.path().join("bad.sql"),
+        r#"{{ sink(name="pg", mode="replace") }}SELECT 1"#,
+    )
+    .unwrap();
+
+    let shared_cap = Arc::new(Mutex::new(RenderCapture::default()));
+    let env = build_env_with_captures(root, &shared_cap);
+
+    let events = render_one(&env, &shared_cap, "events.sql").unwrap();
+    assert_eq!(events.capture.sinks[0].mode, Some(WriteMode::Insert));
+
+    // Mode must not leak into the next template
+    let plain = render_one(&env, &shared_cap, "plain.sql").unwrap();
+    assert_eq!(plain.capture.sinks[0].mode, None);
+
+    let err = render_one(&env, &shared_cap, "bad.sql").unwrap_err();
+    assert!(err.to_string().contains("unknown write mode 'replace'"));
+}
+
+#[test]
+fn test_sink_function_captures_table_policy() {
+    use apitap::pipeline::TablePolicy;
+    use apitap::writer::TruncateMode;
+
+    let temp_dir = TempDir::new().unwrap();
+    let root = temp_dir.path().to_str().unwrap();
+    fs::write(
+        temp_dir.path().join("managed.sql"),
+        r#"{{ sink(name="pg", auto_create=false, auto_truncate=true, truncate_mode="delete") }}SELECT 1"#,
+    )
+    .unwrap();
+    fs::write(
+        temp_dir.path().join("plain.sql"),
+        r#"{{ sink(name="pg") }}SELECT 1"#,
+    )
+    .unwrap();
+    fs::write(
+        temp_dir.path().join("swapped.sql"),
+        r#"{{ sink(name="pg", swap=true) }}SELECT 1"#,
+    )
+    .unwrap();
+
+    let shared_cap = Arc::new(Mutex::new(RenderCapture::default()));
+    let env = build_env_with_captures(root, &shared_cap);
+
+    let managed = render_one(&env, &shared_cap, "managed.sql").unwrap();
+    assert_eq!(
+        managed.capture.sinks[0].tables,
+        TablePolicy {
+            auto_create: Some(false),
+            auto_truncate: Some(true),
+            truncate_mode: Some(TruncateMode::Delete),
+            swap: None,
+        }
+    );
+
+    let plain = render_one(&env, &shared_cap, "plain.sql").unwrap();
+    assert_eq!(plain.capture.sinks[0].tables, TablePolicy::default());
+
+    let swapped = render_one(&env, &shared_cap, "swapped.sql").unwrap();
+    assert!(swapped.capture.sinks[0].tables.swap());
+}
+
+#[test]
+fn test_sink_function_captures_columns() {
+    use apitap::writer::columns::ColumnSelection;
+
+    let temp_dir = TempDir::new().unwrap();
+    let root = temp_dir.path().to_str().unwrap();
+    fs::write(
+        temp_dir.path().join("orders.sql"),
+        r#"{{ sink(name="pg", write_columns=["id", "name"]) }}{{ sink(name="archive", ignore_columns=["raw"]) }}{{ sink(name="lake") }}SELECT 1"#,
+    )
+    .unwrap();
+
+    let shared_cap = Arc::new(Mutex::new(RenderCapture::default()));
+    let env = build_env_with_captures(root, &shared_cap);
+
+    let orders = render_one(&env, &shared_cap, "orders.sql").unwrap();
+    let sinks = &orders.capture.sinks;
+    assert_eq!(
+        sinks[0].columns.write_columns,
+        Some(vec!["id".to_string(), "name".to_string()])
+    );
+    assert_eq!(sinks[1].columns.ignore_columns, vec!["raw".to_string()]);
+    assert_eq!(sinks[2].columns, ColumnSelection::default());
+}
+
+#[test]
+fn test_sink_function_captures_schema() {
+    let temp_dir = TempDir::new().unwrap();
+    let root = temp_dir.path().to_str().unwrap();
+    fs::write(
+        temp_dir.path().join("users.sql"),
+        r#"{{ sink(name="pg", schema="analytics") }}{{ sink(name="lake") }}SELECT 1"#,
+    )
+    .unwrap();
+
+    let shared_cap = Arc::new(Mutex::new(RenderCapture::default()));
+    let env = build_env_with_captures(root, &shared_cap);
+
+    let users = render_one(&env, &shared_cap, "users.sql").unwrap();
+    let sinks = &users.capture.sinks;
+    assert_eq!(sinks[0].schema.as_deref(), Some("analytics"));
+    assert_eq!(sinks[0].table("users"), "analytics.users");
+    assert_eq!(sinks[1].schema, None);
+    assert_eq!(sinks[1].table("raw.users"), "raw.users");
+}
+
+#[test]
+fn test_sink_function_captures_multiple_sinks() {
+    use apitap::writer::fanout::SinkErrorPolicy;
+    use apitap::writer::WriteMode;
+
+    let temp_dir = TempDir::new().unwrap();
+    let root = temp_dir.path().to_str().unwrap();
+    fs::write(
+        temp_dir.path().join("orders.sql"),
+        r#"{{ sink(name="pg") }}{{ sink(name="archive", mode="append", on_error="continue") }}SELECT 1"#,
+    )
+    .unwrap();
+    fs::write(
+        temp_dir.path().join("twice.sql"),
+        r#"{{ sink(name="pg") }}{{ sink(name="pg") }}SELECT 1"#,
+    )
+    .unwrap();
+    fs::write(
+        temp_dir.path().join("bad.sql"),
+        r#"{{ sink(name="pg", on_error="ignore") }}SELECT 1"#,
+    )
+    .unwrap();
+
+    let shared_cap = Arc::new(Mutex::new(RenderCapture::default()));
+    let env = build_env_with_captures(root, &shared_cap);
+
+    let orders = render_one(&env, &shared_cap, "orders.sql").unwrap();
+    let sinks = &orders.capture.sinks;
+    assert_eq!(sinks.len(), 2);
+    assert_eq!(
+        (sinks[0].name.as_str(), sinks[0].on_error),
+        ("pg", SinkErrorPolicy::Fail)
+    );
+    assert_eq!(sinks[1].name, "archive");
+    assert_eq!(sinks[1].mode, Some(WriteMode::Append));
+    assert_eq!(sinks[1].on_error, SinkErrorPolicy::Continue);
+
+    let err = render_one(&env, &shared_cap, "twice.sql").unwrap_err();
+    assert!(err
+        .to_string()
+        .contains("sink 'pg' is declared more than once"));
+
+    let err = render_one(&env, &shared_cap, "bad.sql").unwrap_err();
+    assert!(err.to_string().contains("unknown sink on_error 'ignore'"));
+}
+
+#[test]
+fn test_on_empty_function_captures_policy() {
+    use apitap::pipeline::empty::OnEmpty;
+
+    let temp_dir = TempDir::new().unwrap();
+    let root = temp_dir.path().to_str().unwrap();
+    fs::write(
+        temp_dir.path().join("daily.sql"),
+        r#"{{ sink(name="pg") }}{{ on_empty("skip") }}SELECT 1"#,
+    )
+    .unwrap();
+    fs::write(
+        temp_dir.path().join("plain.sql"),
+        r#"{{ sink(name="pg") }}SELECT 1"#,
+    )
+    .unwrap();
+
+    let shared_cap = Arc::new(Mutex::new(RenderCapture::default()));
+    let env = build_env_with_captures(root, &shared_cap);
+
+    let daily = render_one(&env, &shared_cap, "daily.sql").unwrap();
+    assert_eq!(daily.capture.on_empty, OnEmpty::Skip);
+
+    // The policy must not leak into the next template
+    let plain = render_one(&env, &shared_cap, "plain.sql").unwrap();
+    assert_eq!(plain.capture.on_empty, OnEmpty::Proceed);
+}
+
+#[test]
+fn test_log_level_function_captures_level() {
+    use apitap::log::LogLevel;
+    use tracing::level_filters::LevelFilter;
+
+    let temp_dir = TempDir::new().unwrap();
+    let root = temp_dir.path().to_str().unwrap();
+    fs::write(
+        temp_dir.path().join("noisy.sql"),
+        r#"{{ sink(name="pg") }}{{ log_level("WARN") }}SELECT 1"#,
+    )
+    .unwrap();
+    fs::write(
+        temp_dir.path().join("plain.sql"),
+        r#"{{ sink(name="pg") }}SELECT 1"#,
+    )
+    .unwrap();
+    fs::write(
+        temp_dir.path().join("bad.sql"),
+        r#"{{ log_level("loud") }}SELECT 1"#,
+    )
+    .unwrap();
+
+    let shared_cap = Arc::new(Mutex::new(RenderCapture::default()));
+    let env = build_env_with_captures(root, &shared_cap);
+
+    let noisy = render_one(&env, &shared_cap, "noisy.sql").unwrap();
+    assert_eq!(noisy.capture.log_level, Some(LogLevel(LevelFilter::WARN)));
+    let plain = render_one(&env, &shared_cap, "plain.sql").unwrap();
+    assert_eq!(plain.capture.log_level, None);
+    let err = render_one(&env, &shared_cap, "bad.sql").unwrap_err();
+    assert!(
+        err.to_string().contains("unknown log level 'loud'"),
+        "{err}"
+    );
+}
+
+#[test]
+fn test_depends_on_function_captures_modules() {
+    let temp_dir = TempDir::new().unwrap();
+    let root = temp_dir.path().to_str().unwrap();
+    fs::write(
+        temp_dir.path().join("daily.sql"),
+        r#"{{ depends_on("raw/orders.sql", "raw/customers") }}{{ depends_on("raw/orders.sql") }}SELECT 1"#,
+    )
+    .unwrap();
+    fs::write(temp_dir.path().join("plain.sql"), "SELECT 1").unwrap();
+
+    let shared_cap = Arc::new(Mutex::new(RenderCapture::default()));
+    let env = build_env_with_captures(root, &shared_cap);
+
+    let daily = render_one(&env, &shared_cap, "daily.sql").unwrap();
+    assert_eq!(
+        daily.capture.depends_on,
+        vec!["raw/orders.sql", "raw/customers"]
+    );
+    let plain = render_one(&env, &shared_cap, "plain.sql").unwrap();
+    assert!(plain.capture.depends_on.is_empty());
+}
+
+#[test]
+fn test_post_check_function_captures_checks() {
+    let temp_dir = TempDir::new().unwrap();
+    let root = temp_dir.path().to_str().unwrap();
+    fs::write(
+        temp_dir.path().join("users.sql"),
+        r#"{{ post_check("SELECT count(*) FROM {table}", expect=">= 10") }}
+{{ post_check("SELECT id FROM {table} WHERE id IS NULL") }}SELECT 1"#,
+    )
+    .unwrap();
+    fs::write(
+        temp_dir.path().join("bad.sql"),
+        r#"{{ post_check("SELECT 1", expect="some") }}SELECT 1"#,
+    )
+    .unwrap();
+
+    let shared_cap = Arc::new(Mutex::new(RenderCapture::default()));
+    let env = build_env_with_captures(root, &shared_cap);
+
+    let users = render_one(&env, &shared_cap, "users.sql").unwrap();
+    let checks = &users.capture.post_checks;
+    assert_eq!(checks.len(), 2);
+    assert_eq!(checks[0].sql, "SELECT count(*) FROM {table}");
+    assert_eq!(checks[0].expect.unwrap().to_string(), ">= 10");
+    assert_eq!(checks[1].expect, None);
+
+    assert!(render_one(&env, &shared_cap, "bad.sql").is_err());
+    // Checks don't carry over to the next module
+    fs::write(temp_dir.path().join("plain.sql"), "SELECT 1").unwrap();
+    let plain = render_one(&env, &shared_cap, "plain.sql").unwrap();
+    assert!(plain.capture.post_checks.is_empty());
+}
+
+#[test]
+fn test_substitute_source_replaces_whole_identifiers_only() {
+    use apitap::config::templating::substitute_source;
+
+    let sql = "SELECT o.id, o.orders_total, orders.n\nFROM orders o\nJOIN raw.orders r ON r.id = o.id\nWHERE o.note <> 'it''s orders' AND preorders > 0";
+    assert_eq!(
+        substitute_source(sql, "orders", "fct_orders"),
+        "SELECT o.id, o.orders_total, fct_orders.n\nFROM fct_orders o\nJOIN raw.orders r ON r.id = o.id\nWHERE o.note <> 'it''s orders' AND preorders > 0"
+    );
+    // Substring of a keyword and of columns
+    assert_eq!(
+        substitute_source(
+            "SELECT userid, \"user\" FROM user WHERE user.x = 1",
+            "user",
+            "u2"
+        ),
+        "SELECT userid, \"u2\" FROM u2 WHERE u2.x = 1"
+    );
+    assert_eq!(
+        substitute_source("SELECT * FROM\tord\nWHERE ordinal = 1", "ord", "t"),
+        "SELECT * FROM\tt\nWHERE ordinal = 1"
+    );
+    assert_eq!(substitute_source("SELECT 1", "", "t"), "SELECT 1");
+}
+
+#[test]
+fn test_substitute_source_skips_comments() {
+    use apitap::config::templating::substitute_source;
+
+    // The apostrophe in the comment doesn't open a literal
+    let sql = "-- don't touch orders here\nSELECT * FROM orders /* orders' note */ JOIN x ON x.id = orders.id";
+    assert_eq!(
+        substitute_source(sql, "orders", "fct_orders"),
+        "-- don't touch orders here\nSELECT * FROM fct_orders /* orders' note */ JOIN x ON x.id = fct_orders.id"
+    );
+    assert_eq!(
+        substitute_source("SELECT 1 -- from orders", "orders", "t"),
+        "SELECT 1 -- from orders"
+    );
+}
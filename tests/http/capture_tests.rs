@@ -0,0 +1,158 @@
+use std::sync::Arc;
+
+use apitap::http::capture::Capture;
+use apitap::http::fetcher::{ndjson_stream_request, RequestTemplate};
+use apitap::pipeline::Retry;
+use futures::StreamExt;
+use serde_json::{json, Value};
+
+use crate::common::http_server::{Reply, TestServer};
+
+/// Answers with `status` and `body`, returning the base URL.
+async fn serve_status(status: &'static str, body: &'static str) -> String {
+    let server = TestServer::start(move |_| {
+        Reply::status(status)
+            .header("content-type", "application/json")
+            .header("set-cookie", "session=abc")
+            .body(body)
+    })
+    .await;
+    server.url
+}
+
+fn no_retry() -> Retry {
+    Retry {
+        max_attempts: 0,
+        ..Retry::default()
+    }
+}
+
+fn files(dir: &std::path::Path) -> Vec<String> {
+    let mut names: Vec<_> = std::fs::read_dir(dir)
+        .unwrap()
+        .map(|e| e.unwrap().file_name().to_string_lossy().into_owned())
+        .collect();
+    names.sort();
+    names
+}
+
+#[tokio::test]
+async fn test_capture_writes_masked_request_and_body() {
+    let body = r#"{"data": [{"id": 1}]}"#;
+    let url = serve_status("200 OK", body).await;
+    let dir = tempfile::tempdir().unwrap();
+    let capture = Arc::new(Capture::new(dir.path().join("tap"), 1024 * 1024));
+
+    let template = RequestTemplate {
+        capture: Some(capture.for_source(
+            "orders api",
+            vec![
+                ("Authorization".into(), "Bearer secret".into()),
+                ("Accept".into(), "application/json".into()),
+            ],
+        )),
+        ..RequestTemplate::default()
+    };
+    let records: Vec<_> = ndjson_stream_request(
+        &reqwest::Client::new(),
+        &format!("{url}/orders"),
+        &[("token".into(), "abc".into())],
+        &[("page".into(), 2.into())],
+        &template,
+        Some("/data"),
+        &no_retry(),
+    )
+    .await
+    .unwrap()
+    .collect()
+    .await;
+    assert_eq!(records.len(), 1);
+
+    let tap = dir.path().join("tap");
+    assert_eq!(
+        files(&tap),
+        [
+            "000001-orders_api.request.json",
+            "000001-orders_api.response.json"
+        ]
+    );
+    let request: Value = serde_json::from_str(
+        &std::fs::read_to_string(tap.join("000001-orders_api.request.json")).unwrap(),
+    )
+    .unwrap();
+    assert_eq!(request["method"], "GET");
+    assert_eq!(request["url"], format!("{url}/orders?token=***&page=2"));
+    assert_eq!(
+        request["headers"],
+        json!({"Authorization": "***", "Accept": "application/json"})
+    );
+    assert_eq!(request["response"]["status"], 200);
+    assert_eq!(request["response"]["headers"]["set-cookie"], "***");
+    assert_eq!(
+        std::fs::read_to_string(tap.join("000001-orders_api.response.json")).unwrap(),
+        body
+    );
+}
+
+#[tokio::test]
+async fn test_capture_keeps_error_response_body() {
+    let url = serve_status("422 Unprocessable Entity", r#"{"error": "bad filter"}"#).await;
+    let dir = tempfile::tempdir().unwrap();
+    let capture = Arc::new(Capture::new(dir.path(), 1024 * 1024));
+    let template = RequestTemplate {
+        capture: Some(capture.for_source("orders", Vec::new())),
+        ..RequestTemplate::default()
+    };
+
+    let result = ndjson_stream_request(
+        &reqwest::Client::new(),
+        &url,
+        &[],
+        &[],
+        &template,
+        None,
+        &no_retry(),
+    )
+    .await;
+    assert!(result.is_err());
+    assert_eq!(
+        std::fs::read_to_string(dir.path().join("000001-orders.response.json")).unwrap(),
+        r#"{"error": "bad filter"}"#
+    );
+}
+
+#[tokio::test]
+async fn test_capture_stops_at_size_limit() {
+    let url = serve_status("200 OK", r#"{"data": []}"#).await;
+    let dir = tempfile::tempdir().unwrap();
+    let capture = Arc::new(Capture::new(dir.path(), 16));
+    let template = RequestTemplate {
+        capture: Some(capture.for_source("orders", Vec::new())),
+        ..RequestTemplate::default()
+    };
+
+    let records: Vec<_> = ndjson_stream_request(
+        &reqwest::Client::new(),
+        &url,
+        &[],
+        &[],
+        &template,
+        None,
+        &no_retry(),
+    )
+    .await
+    .unwrap()
+    .collect()
+    .await;
+    assert_eq!(records.len(), 1);
+
+    // The request file is cut at the limit and no body is written
+    assert_eq!(files(dir.path()), ["000001-orders.request.json"]);
+    assert_eq!(capture.written(), 16);
+    assert_eq!(
+        std::fs::metadata(dir.path().join("000001-orders.request.json"))
+            .unwrap()
+            .len(),
+        16
+    );
+}
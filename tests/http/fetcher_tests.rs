@@ -1,4 +1,5 @@
-use apitap::http::fetcher::{FetchStats, Pagination};
+use crate::common::http_server::{Reply, TestServer};
+use apitap::http::fetcher::{CursorIn, FetchStats, Pagination};
 
 #[test]
 fn test_fetch_stats_new() {
@@ -7,6 +8,9 @@ fn test_fetch_stats_new() {
     assert_eq!(stats.success_count, 0);
     assert_eq!(stats.error_count, 0);
     assert_eq!(stats.total_items, 0);
+    assert_eq!(stats.total_bytes, 0);
+    assert_eq!(stats.request_count, 0);
+    assert_eq!(stats.page_count, 0);
 }
 
 #[test]
@@ -24,6 +28,8 @@ fn test_pagination_limit_offset_serialization() {
     let pagination = Pagination::LimitOffset {
         limit_param: "limit".to_string(),
         offset_param: "offset".to_string(),
+        start_offset: 0,
+        page_size: None,
     };
 
     let serialized = serde_json::to_string(&pagination).unwrap();
@@ -36,6 +42,7 @@ fn test_pagination_limit_offset_serialization() {
         Pagination::LimitOffset {
             limit_param,
             offset_param,
+            ..
         } => {
             assert_eq!(limit_param, "limit");
             assert_eq!(offset_param, "offset");
@@ -49,6 +56,8 @@ fn test_pagination_page_number_serialization() {
     let pagination = Pagination::PageNumber {
         page_param: "page".to_string(),
         per_page_param: "per_page".to_string(),
+        start_page: 1,
+        page_size: None,
     };
 
     let serialized = serde_json::to_string(&pagination).unwrap();
@@ -59,6 +68,7 @@ fn test_pagination_page_number_serialization() {
         Pagination::PageNumber {
             page_param,
             per_page_param,
+            ..
         } => {
             assert_eq!(page_param, "page");
             assert_eq!(per_page_param, "per_page");
@@ -90,6 +100,9 @@ fn test_pagination_cursor_serialization() {
     let pagination = Pagination::Cursor {
         cursor_param: "cursor".to_string(),
         page_size_param: Some("size".to_string()),
+        next_cursor_header: Some("X-Next-Cursor".to_string()),
+        cursor_in: CursorIn::Header,
+        page_size: None,
     };
 
     let serialized = serde_json::to_string(&pagination).unwrap();
@@ -100,9 +113,14 @@ fn test_pagination_cursor_serialization() {
         Pagination::Cursor {
             cursor_param,
             page_size_param,
+            next_cursor_header,
+            cursor_in,
+            ..
         } => {
             assert_eq!(cursor_param, "cursor");
             assert_eq!(page_size_param, Some("size".to_string()));
+            assert_eq!(next_cursor_header, Some("X-Next-Cursor".to_string()));
+            assert_eq!(cursor_in, CursorIn::Header);
         }
         _ => panic!("Expected Cursor pagination"),
     }
@@ -113,12 +131,16 @@ fn test_pagination_cursor_without_page_size() {
     let pagination = Pagination::Cursor {
         cursor_param: "next".to_string(),
         page_size_param: None,
+        next_cursor_header: None,
+        cursor_in: CursorIn::Param,
+        page_size: None,
     };
 
     match pagination {
         Pagination::Cursor {
             cursor_param,
             page_size_param,
+            ..
         } => {
             assert_eq!(cursor_param, "next");
             assert!(page_size_param.is_none());
@@ -143,6 +165,8 @@ fn test_pagination_debug_format() {
     let pagination = Pagination::LimitOffset {
         limit_param: "limit".to_string(),
         offset_param: "offset".to_string(),
+        start_offset: 0,
+        page_size: None,
     };
 
     let debug_str = format!("{:?}", pagination);
@@ -154,6 +178,8 @@ fn test_pagination_clone() {
     let pagination = Pagination::PageNumber {
         page_param: "page".to_string(),
         per_page_param: "per_page".to_string(),
+        start_page: 1,
+        page_size: None,
     };
 
     let cloned = pagination.clone();
@@ -163,10 +189,12 @@ fn test_pagination_clone() {
             Pagination::PageNumber {
                 page_param: p1,
                 per_page_param: pp1,
+                ..
             },
             Pagination::PageNumber {
                 page_param: p2,
                 per_page_param: pp2,
+                ..
             },
         ) => {
             assert_eq!(p1, p2);
@@ -178,26 +206,30 @@ fn test_pagination_clone() {
 
 #[test]
 fn test_fetch_stats_clone() {
-    let stats = FetchStats {
-        success_count: 5,
-        error_count: 2,
-        total_items: 100,
-    };
+    let mut stats = FetchStats::new();
+    stats.success_count = 5;
+    stats.error_count = 2;
+    stats.total_items = 100;
+    stats.total_bytes = 4096;
+    stats.request_count = 7;
+    stats.page_count = 5;
 
     let cloned = stats.clone();
 
     assert_eq!(cloned.success_count, 5);
     assert_eq!(cloned.error_count, 2);
     assert_eq!(cloned.total_items, 100);
+    assert_eq!(cloned.total_bytes, 4096);
+    assert_eq!(cloned.request_count, 7);
+    assert_eq!(cloned.page_count, 5);
 }
 
 #[test]
 fn test_fetch_stats_debug() {
-    let stats = FetchStats {
-        success_count: 3,
-        error_count: 1,
-        total_items: 50,
-    };
+    let mut stats = FetchStats::new();
+    stats.success_count = 3;
+    stats.error_count = 1;
+    stats.total_items = 50;
 
     let debug_str = format!("{:?}", stats);
     assert!(debug_str.contains("FetchStats"));
@@ -206,14 +238,18 @@ fn test_fetch_stats_debug() {
 #[test]
 fn test_pagination_variants() {
     // Test that all pagination variants can be created
-    let variants = vec![
+    let variants = [
         Pagination::LimitOffset {
             limit_param: "limit".to_string(),
             offset_param: "offset".to_string(),
+            start_offset: 0,
+            page_size: None,
         },
         Pagination::PageNumber {
             page_param: "page".to_string(),
             per_page_param: "size".to_string(),
+            start_page: 1,
+            page_size: None,
         },
         Pagination::PageOnly {
             page_param: "p".to_string(),
@@ -221,6 +257,9 @@ fn test_pagination_variants() {
         Pagination::Cursor {
             cursor_param: "cursor".to_string(),
             page_size_param: Some("limit".to_string()),
+            next_cursor_header: None,
+            cursor_in: Default::default(),
+            page_size: None,
         },
         Pagination::Default,
     ];
@@ -242,6 +281,7 @@ offset_param: skip
         Pagination::LimitOffset {
             limit_param,
             offset_param,
+            ..
         } => {
             assert_eq!(limit_param, "max");
             assert_eq!(offset_param, "skip");
@@ -264,6 +304,7 @@ per_page_param: pageSize
         Pagination::PageNumber {
             page_param,
             per_page_param,
+            ..
         } => {
             assert_eq!(page_param, "pageNum");
             assert_eq!(per_page_param, "pageSize");
@@ -278,6 +319,7 @@ fn test_pagination_cursor_yaml() {
 kind: cursor
 cursor_param: nextToken
 page_size_param: maxResults
+next_cursor_header: X-Next-Page-Token
 "#;
 
     let pagination: Pagination = serde_yaml::from_str(yaml).unwrap();
@@ -286,10 +328,1060 @@ page_size_param: maxResults
         Pagination::Cursor {
             cursor_param,
             page_size_param,
+            next_cursor_header,
+            cursor_in,
+            ..
         } => {
             assert_eq!(cursor_param, "nextToken");
             assert_eq!(page_size_param, Some("maxResults".to_string()));
+            assert_eq!(next_cursor_header, Some("X-Next-Page-Token".to_string()));
+            assert_eq!(cursor_in, CursorIn::Param);
         }
         _ => panic!("Expected Cursor"),
     }
 }
+
+#[test]
+fn test_request_template_query_placement() {
+    use apitap::http::fetcher::RequestTemplate;
+
+    let template = RequestTemplate::default();
+    let (query, body) = template
+        .build(
+            &[("q".to_string(), "rust".to_string())],
+            &[("page".to_string(), 2.into())],
+        )
+        .unwrap();
+
+    assert_eq!(
+        query,
+        vec![
+            ("q".to_string(), "rust".to_string()),
+            ("page".to_string(), "2".to_string())
+        ]
+    );
+    assert!(body.is_none());
+}
+
+#[test]
+fn test_request_template_body_placement() {
+    use apitap::http::fetcher::{HttpMethod, PaginationIn, RequestTemplate};
+    use serde_json::json;
+
+    let template = RequestTemplate {
+        method: HttpMethod::Post,
+        body: Some(json!({"filter": {"status": "open"}})),
+        pagination_in: PaginationIn::Body,
+        body_path: Some("page".to_string()),
+        ..RequestTemplate::default()
+    };
+    let (query, body) = template
+        .build(
+            &[("q".to_string(), "rust".to_string())],
+            &[
+                ("offset".to_string(), json!(100)),
+                ("cursor".to_string(), json!("abc")),
+            ],
+        )
+        .unwrap();
+
+    assert_eq!(query, vec![("q".to_string(), "rust".to_string())]);
+    assert_eq!(
+        body.unwrap(),
+        json!({"filter": {"status": "open"}, "page": {"offset": 100, "cursor": "abc"}})
+    );
+}
+
+#[test]
+fn test_merge_into_body_keeps_numeric_looking_cursor_a_string() {
+    use apitap::http::fetcher::merge_into_body;
+    use serde_json::json;
+
+    let merged = merge_into_body(
+        None,
+        None,
+        &[
+            ("limit".to_string(), json!(50)),
+            ("cursor".to_string(), json!("000123")),
+        ],
+    )
+    .unwrap();
+    assert_eq!(merged, json!({"limit": 50, "cursor": "000123"}));
+}
+
+#[test]
+fn test_merge_into_body_rejects_non_object_parent() {
+    use apitap::http::fetcher::merge_into_body;
+    use serde_json::json;
+
+    let body = json!({"page": 3});
+    let res = merge_into_body(
+        Some(&body),
+        Some("page.inner"),
+        &[("offset".to_string(), json!(0))],
+    );
+    assert!(res.is_err());
+}
+
+#[derive(Default)]
+struct CapturingWriter {
+    rows: tokio::sync::Mutex<Vec<serde_json::Value>>,
+}
+
+#[async_trait::async_trait]
+impl apitap::writer::DataWriter for CapturingWriter {
+    async fn write(
+        &self,
+        _result: apitap::utils::datafusion_ext::QueryResult,
+    ) -> apitap::errors::Result<()> {
+        Ok(())
+    }
+
+    async fn write_stream(
+        &self,
+        mut result: apitap::utils::datafusion_ext::QueryResultStream,
+        _write_mode: apitap::writer::WriteMode,
+    ) -> apitap::errors::Result<()> {
+        use futures::StreamExt;
+        while let Some(row) = result.data.next().await {
+            self.rows.lock().await.push(row?);
+        }
+        Ok(())
+    }
+}
+
+#[tokio::test]
+async fn test_page_writer_raw_json_single_column() {
+    use apitap::http::fetcher::{DataFusionPageWriter, PageWriter};
+    use futures::StreamExt;
+    use serde_json::json;
+    use std::sync::Arc;
+
+    let sink = Arc::new(CapturingWriter::default());
+    let writer = DataFusionPageWriter::new("raw_src", "SELECT data FROM raw_src", sink.clone())
+        .with_raw_json(true);
+
+    // Shapes that would not infer into one schema
+    let records = vec![
+        json!({"id": 1, "a": {"deep": [1, 2]}}),
+        json!({"id": "two", "b": true}),
+        json!([1, 2, 3]),
+    ];
+    let stream = futures::stream::iter(records.clone().into_iter().map(Ok)).boxed();
+    writer
+        .write_page_stream(stream, apitap::writer::WriteMode::Append)
+        .await
+        .unwrap();
+
+    let rows = sink.rows.lock().await;
+    assert_eq!(rows.len(), 3);
+    for (row, record) in rows.iter().zip(&records) {
+        let text = row["data"].as_str().expect("data is text");
+        assert_eq!(
+            &serde_json::from_str::<serde_json::Value>(text).unwrap(),
+            record
+        );
+    }
+}
+
+#[tokio::test]
+async fn test_page_writer_dictionary_columns_on_pages_and_streams() {
+    use apitap::http::fetcher::{DataFusionPageWriter, PageWriter};
+    use apitap::utils::dictionary::DictionaryEncoding;
+    use futures::StreamExt;
+    use serde_json::json;
+    use std::sync::Arc;
+
+    let records: Vec<_> = (0..6)
+        .map(|i| json!({"id": i, "status": if i % 3 == 0 { "paid" } else { "open" }}))
+        .collect();
+    let sink = Arc::new(CapturingWriter::default());
+    let writer = DataFusionPageWriter::new(
+        "dict_src",
+        "SELECT status, arrow_typeof(status) AS t FROM dict_src ORDER BY id",
+        sink.clone(),
+    )
+    .with_dictionary(Some(DictionaryEncoding {
+        auto: true,
+        ..Default::default()
+    }));
+
+    writer
+        .write_page(1, records.clone(), apitap::writer::WriteMode::Append)
+        .await
+        .unwrap();
+    let stream = futures::stream::iter(records.into_iter().map(Ok)).boxed();
+    writer
+        .write_page_stream(stream, apitap::writer::WriteMode::Append)
+        .await
+        .unwrap();
+
+    let rows = sink.rows.lock().await;
+    assert_eq!(rows.len(), 12);
+    for row in rows.iter() {
+        assert_eq!(row["t"], "Dictionary(Int32, Utf8)");
+    }
+    assert_eq!(rows[0]["status"], "paid");
+    assert_eq!(rows[7]["status"], "open");
+}
+
+#[test]
+fn test_request_template_parse_body_formats() {
+    use apitap::http::fetcher::{CsvOptions, RequestTemplate, ResponseFormat};
+    use serde_json::json;
+
+    let template = |format| RequestTemplate {
+        format,
+        ..RequestTemplate::default()
+    };
+
+    assert_eq!(
+        template(ResponseFormat::Json)
+            .parse_body(br#"{"a": 1}"#)
+            .unwrap(),
+        json!({"a": 1})
+    );
+    assert_eq!(
+        template(ResponseFormat::Xml)
+            .parse_body(b"<a><b>1</b></a>")
+            .unwrap(),
+        json!({"a": {"b": "1"}})
+    );
+    assert_eq!(
+        template(ResponseFormat::Tsv)
+            .parse_body(b"id\tname\n1\tAda\n")
+            .unwrap(),
+        json!([{"id": "1", "name": "Ada"}])
+    );
+    assert!(template(ResponseFormat::Json).parse_body(b"<a/>").is_err());
+
+    // Strict by default; `lenient_json` reads NaN as null
+    assert!(template(ResponseFormat::Json)
+        .parse_body(br#"{"a": NaN}"#)
+        .is_err());
+    let lenient = RequestTemplate {
+        lenient_json: Some(apitap::utils::lenient_json::LenientJson::default()),
+        ..RequestTemplate::default()
+    };
+    assert_eq!(
+        lenient.parse_body(br#"{"a": NaN}"#).unwrap(),
+        json!({"a": null})
+    );
+
+    let headerless = RequestTemplate {
+        format: ResponseFormat::Csv,
+        csv: CsvOptions {
+            delimiter: Some(';'),
+            has_header: false,
+        },
+        ..RequestTemplate::default()
+    };
+    assert_eq!(
+        headerless.parse_body(b"1;Ada\n2;Grace\n").unwrap(),
+        json!([
+            {"column_1": "1", "column_2": "Ada"},
+            {"column_1": "2", "column_2": "Grace"}
+        ])
+    );
+
+    // Multi-byte delimiters are rejected rather than truncated
+    let wide = RequestTemplate {
+        format: ResponseFormat::Csv,
+        csv: CsvOptions {
+            delimiter: Some('•'),
+            has_header: true,
+        },
+        ..RequestTemplate::default()
+    };
+    assert!(wide.parse_body(b"a").is_err());
+}
+
+#[test]
+fn test_pagination_start_defaults() {
+    let limit_offset: Pagination =
+        serde_yaml::from_str("kind: limit_offset\nlimit_param: limit\noffset_param: offset\n")
+            .unwrap();
+    assert!(matches!(
+        limit_offset,
+        Pagination::LimitOffset {
+            start_offset: 0,
+            ..
+        }
+    ));
+
+    let page_number: Pagination =
+        serde_yaml::from_str("kind: page_number\npage_param: page\nper_page_param: size\n")
+            .unwrap();
+    assert!(matches!(
+        page_number,
+        Pagination::PageNumber { start_page: 1, .. }
+    ));
+
+    let zero_based: Pagination = serde_yaml::from_str(
+        "kind: page_number\npage_param: page\nper_page_param: size\nstart_page: 0\n",
+    )
+    .unwrap();
+    assert!(matches!(
+        zero_based,
+        Pagination::PageNumber { start_page: 0, .. }
+    ));
+}
+
+#[tokio::test]
+async fn test_limit_offset_stream_honors_start_offset() {
+    use apitap::http::fetcher::PaginatedFetcher;
+    use apitap::pipeline::Retry;
+    use futures::StreamExt;
+
+    // Two records at offset 40, nothing after
+    let server = TestServer::start(|req| {
+        if req.line().contains("offset=40") {
+            Reply::json(r#"[{"id": 41}, {"id": 42}]"#)
+        } else {
+            Reply::json("[]")
+        }
+    })
+    .await;
+
+    let fetcher = PaginatedFetcher::new(reqwest::Client::new(), format!("{}/items", server.url), 1)
+        .with_limit_offset("limit", "offset")
+        .with_start_offset(40);
+    let retry = Retry {
+        max_attempts: 0,
+        max_delay_secs: 1,
+        min_delay_secs: 1,
+        jitter: Default::default(),
+        max_elapsed_secs: None,
+    };
+    let records: Vec<_> = fetcher
+        .limit_offset_stream(2, None, None, &retry)
+        .await
+        .unwrap()
+        .collect()
+        .await;
+
+    assert_eq!(records.len(), 2);
+    let seen = server.requests();
+    assert!(seen[0].line().contains("offset=40"), "{seen:?}");
+    assert!(seen[1].line().contains("offset=42"), "{seen:?}");
+}
+
+#[tokio::test]
+async fn test_record_budget_cuts_pagination_short() {
+    use apitap::http::fetcher::{PaginatedFetcher, RecordBudget, RequestTemplate};
+    use apitap::pipeline::Retry;
+    use futures::StreamExt;
+    use std::sync::Arc;
+
+    let retry = Retry {
+        max_attempts: 0,
+        max_delay_secs: 1,
+        min_delay_secs: 1,
+        jitter: Default::default(),
+        max_elapsed_secs: None,
+    };
+
+    // Every page is full, so only the cap ends pagination
+    let server = TestServer::start(|_| Reply::json(r#"[{"id": 1}, {"id": 2}]"#)).await;
+
+    let fetcher = PaginatedFetcher::new(reqwest::Client::new(), format!("{}/items", server.url), 1)
+        .with_limit_offset("limit", "offset")
+        .with_request(RequestTemplate {
+            record_budget: Some(Arc::new(RecordBudget::new(3))),
+            ..RequestTemplate::default()
+        });
+    let records: Vec<_> = fetcher
+        .limit_offset_stream(2, None, None, &retry)
+        .await
+        .unwrap()
+        .collect()
+        .await;
+    assert_eq!(records.len(), 3);
+    assert_eq!(server.hits(), 2);
+
+    // Cursor pagination stops before following the token
+    let (url, server) = serve_cursor_pages(&["t1", "t2"]).await;
+    let fetcher = PaginatedFetcher::new(reqwest::Client::new(), url, 1)
+        .with_cursor(
+            "page_token",
+            Some("page_size"),
+            "X-Next-Page-Token",
+            CursorIn::Param,
+        )
+        .with_request(RequestTemplate {
+            record_budget: Some(Arc::new(RecordBudget::new(1))),
+            ..RequestTemplate::default()
+        });
+    let records: Vec<_> = fetcher
+        .cursor_stream(10, None, None, &retry)
+        .await
+        .unwrap()
+        .collect()
+        .await;
+    assert_eq!(records.len(), 1);
+    assert_eq!(server.hits(), 1);
+}
+
+/// Serves pages carrying `tokens` in turn in `X-Next-Page-Token`, and no
+/// token after the last one.
+async fn serve_cursor_pages(tokens: &'static [&'static str]) -> (String, TestServer) {
+    let server = TestServer::start(move |req| {
+        let reply = Reply::json(format!(r#"[{{"page": {}}}]"#, req.index));
+        match tokens.get(req.index) {
+            Some(token) => reply.header("x-next-page-token", token),
+            None => reply,
+        }
+    })
+    .await;
+    (format!("{}/items", server.url), server)
+}
+
+#[tokio::test]
+async fn test_cursor_stream_follows_header_token() {
+    use apitap::http::fetcher::PaginatedFetcher;
+    use apitap::pipeline::Retry;
+    use futures::StreamExt;
+
+    let retry = Retry {
+        max_attempts: 0,
+        max_delay_secs: 1,
+        min_delay_secs: 1,
+        jitter: Default::default(),
+        max_elapsed_secs: None,
+    };
+
+    // Sent back as a query parameter; the last page has no token
+    let (url, server) = serve_cursor_pages(&["t1", "t2"]).await;
+    let fetcher = PaginatedFetcher::new(reqwest::Client::new(), url, 1).with_cursor(
+        "page_token",
+        Some("page_size"),
+        "X-Next-Page-Token",
+        CursorIn::Param,
+    );
+    let records: Vec<_> = fetcher
+        .cursor_stream(10, None, None, &retry)
+        .await
+        .unwrap()
+        .collect()
+        .await;
+    assert_eq!(records.len(), 3);
+    let seen = server.requests();
+    assert!(
+        seen[0].line().starts_with("GET /items?page_size=10 "),
+        "{seen:?}"
+    );
+    assert!(seen[1].line().contains("page_token=t1"), "{seen:?}");
+    assert!(seen[2].line().contains("page_token=t2"), "{seen:?}");
+
+    // Sent back as a header; an empty token ends pagination too
+    let (url, server) = serve_cursor_pages(&["t1", ""]).await;
+    let fetcher = PaginatedFetcher::new(reqwest::Client::new(), url, 1).with_cursor(
+        "X-Page-Token",
+        None,
+        "X-Next-Page-Token",
+        CursorIn::Header,
+    );
+    let records: Vec<_> = fetcher
+        .cursor_stream(10, None, None, &retry)
+        .await
+        .unwrap()
+        .collect()
+        .await;
+    assert_eq!(records.len(), 2);
+    let seen = server.requests();
+    assert_eq!(seen[0].header("x-page-token"), None, "{seen:?}");
+    assert_eq!(seen[1].header("x-page-token"), Some("t1"), "{seen:?}");
+}
+
+#[tokio::test]
+async fn test_cursor_stream_renders_body_template() {
+    use apitap::http::fetcher::{HttpMethod, PaginatedFetcher, PaginationIn, RequestTemplate};
+    use apitap::pipeline::Retry;
+    use futures::StreamExt;
+    use serde_json::json;
+
+    let retry = Retry {
+        max_attempts: 0,
+        max_delay_secs: 1,
+        min_delay_secs: 1,
+        jitter: Default::default(),
+        max_elapsed_secs: None,
+    };
+
+    let (url, server) = serve_cursor_pages(&["t1", ""]).await;
+    let fetcher = PaginatedFetcher::new(reqwest::Client::new(), url, 1)
+        .with_cursor("after", Some("first"), "X-Next-Page-Token", CursorIn::Param)
+        .with_request(RequestTemplate {
+            method: HttpMethod::Post,
+            body: Some(json!({"search": {"after": "{{ cursor }}", "first": "{{ page_size }}"}})),
+            pagination_in: PaginationIn::Template,
+            ..RequestTemplate::default()
+        });
+    let records: Vec<_> = fetcher
+        .cursor_stream(10, None, None, &retry)
+        .await
+        .unwrap()
+        .collect()
+        .await;
+    assert_eq!(records.len(), 2);
+    let seen = server.requests();
+    // Nothing is added outside the template
+    assert!(seen[0].line().starts_with("POST /items "), "{seen:?}");
+    assert_eq!(seen[0].body, br#"{"search":{"after":null,"first":10}}"#);
+    assert_eq!(seen[1].body, br#"{"search":{"after":"t1","first":10}}"#);
+}
+
+#[test]
+fn test_request_template_renders_page_vars() {
+    use apitap::http::fetcher::{PaginationIn, RequestTemplate};
+    use serde_json::json;
+
+    let template = RequestTemplate {
+        body: Some(json!({
+            "offset": "{{ offset }}",
+            "label": "page {{page}} of {{ unknown }}",
+            "fixed": 1
+        })),
+        ..RequestTemplate::default()
+    };
+    let page = template.for_page(&[("offset", json!(20)), ("page", json!(3))]);
+
+    // Query placement still appends the params; the body is rendered
+    let (query, body) = page
+        .build(&[], &[("offset".to_string(), json!(20))])
+        .unwrap();
+    assert_eq!(query, vec![("offset".to_string(), "20".to_string())]);
+    assert_eq!(
+        body.unwrap(),
+        json!({"offset": 20, "label": "page 3 of {{ unknown }}", "fixed": 1})
+    );
+
+    let templated = RequestTemplate {
+        pagination_in: PaginationIn::Template,
+        ..page
+    };
+    let (query, body) = templated
+        .build(&[], &[("offset".to_string(), json!(20))])
+        .unwrap();
+    assert!(query.is_empty());
+    assert_eq!(body.unwrap()["offset"], json!(20));
+}
+
+#[tokio::test]
+async fn test_page_writer_counts_written_rows_into_progress() {
+    use apitap::http::fetcher::{DataFusionPageWriter, PageWriter};
+    use apitap::utils::progress::{ProgressOpts, WriteProgress};
+    use serde_json::json;
+    use std::sync::Arc;
+
+    let sink = Arc::new(CapturingWriter::default());
+    let progress = WriteProgress::start("progress_src", &ProgressOpts::default());
+    let writer = DataFusionPageWriter::new(
+        "progress_src",
+        "SELECT id FROM progress_src WHERE id > 1",
+        sink.clone(),
+    )
+    .with_progress(progress.clone());
+
+    let records = vec![json!({"id": 1}), json!({"id": 2}), json!({"id": 3})];
+    writer
+        .write_page(1, records, apitap::writer::WriteMode::Append)
+        .await
+        .unwrap();
+
+    // Rows are counted after the module SQL, as the writer receives them
+    assert_eq!(sink.rows.lock().await.len(), 2);
+    assert_eq!(progress.rows(), 2);
+}
+
+#[test]
+fn test_request_template_records_as_object_values() {
+    use apitap::http::fetcher::{RecordsAs, RequestTemplate};
+    use serde_json::json;
+
+    let by_id = json!({
+        "123": {"name": "Ada"},
+        "456": {"name": "Grace"}
+    });
+
+    // By default an object is a single record
+    let array = RequestTemplate::default();
+    assert_eq!(array.records(by_id.clone()), vec![by_id.clone()]);
+    assert_eq!(array.records(json!([1, 2])), vec![json!(1), json!(2)]);
+    assert!(array.records(json!(null)).is_empty());
+
+    let values = RequestTemplate {
+        records_as: RecordsAs::ObjectValues,
+        ..RequestTemplate::default()
+    };
+    assert_eq!(
+        values.records(by_id.clone()),
+        vec![json!({"name": "Ada"}), json!({"name": "Grace"})]
+    );
+
+    let keyed = RequestTemplate {
+        records_as: RecordsAs::ObjectValues,
+        record_key_column: Some("_id".to_string()),
+        ..RequestTemplate::default()
+    };
+    assert_eq!(
+        keyed.records(by_id),
+        vec![
+            json!({"_id": "123", "name": "Ada"}),
+            json!({"_id": "456", "name": "Grace"})
+        ]
+    );
+    // Arrays are still read element by element
+    assert_eq!(keyed.records(json!([{"a": 1}])), vec![json!({"a": 1})]);
+}
+
+#[test]
+fn test_records_as_yaml() {
+    use apitap::http::fetcher::RecordsAs;
+
+    let records_as: RecordsAs = serde_yaml::from_str("object_values").unwrap();
+    assert_eq!(records_as, RecordsAs::ObjectValues);
+    assert_eq!(RecordsAs::default(), RecordsAs::Array);
+}
+
+#[derive(Default)]
+struct PageOrderWriter {
+    pages: std::sync::Mutex<Vec<(u64, Vec<serde_json::Value>)>>,
+}
+
+#[async_trait::async_trait]
+impl apitap::http::fetcher::PageWriter for PageOrderWriter {
+    async fn write_page(
+        &self,
+        page_number: u64,
+        data: Vec<serde_json::Value>,
+        _write_mode: apitap::writer::WriteMode,
+    ) -> apitap::errors::Result<()> {
+        self.pages.lock().unwrap().push((page_number, data));
+        Ok(())
+    }
+}
+
+#[tokio::test]
+async fn test_page_number_preserve_order_writes_in_page_order() {
+    use apitap::http::fetcher::{PaginatedFetcher, TotalHint};
+    use apitap::pipeline::Retry;
+    use serde_json::json;
+    use std::sync::Arc;
+    use std::time::Duration;
+
+    // Page 2 answers last, after pages 3 and 4
+    let server = TestServer::start(|req| {
+        let page: u64 = req
+            .line()
+            .split("page=")
+            .nth(1)
+            .and_then(|rest| rest.split(['&', ' ']).next())
+            .and_then(|p| p.parse().ok())
+            .unwrap();
+        let body = json!({"total_pages": 4, "data": [{"id": page * 10}, {"id": page * 10 + 1}]});
+        let reply = Reply::json(body.to_string());
+        if page == 2 {
+            reply.after(Duration::from_millis(300))
+        } else {
+            reply
+        }
+    })
+    .await;
+
+    let retry = Retry {
+        max_attempts: 0,
+        max_delay_secs: 1,
+        min_delay_secs: 1,
+        jitter: Default::default(),
+        max_elapsed_secs: None,
+    };
+    let writer = Arc::new(PageOrderWriter::default());
+    let stats = PaginatedFetcher::new(reqwest::Client::new(), format!("{}/items", server.url), 3)
+        .with_page_number("page", "per_page")
+        .with_batch_size(1)
+        .with_preserve_order(true)
+        .fetch_page_number(
+            2,
+            Some("/data"),
+            Some(TotalHint::Pages {
+                pointer: "/total_pages".to_string(),
+            }),
+            writer.clone(),
+            apitap::writer::WriteMode::Append,
+            &retry,
+        )
+        .await
+        .unwrap();
+
+    let pages = writer.pages.lock().unwrap();
+    let order: Vec<u64> = pages.iter().map(|(page, _)| *page).collect();
+    // The first page is written whole; later ones in batches of one row
+    assert_eq!(order, vec![1, 2, 2, 3, 3, 4, 4]);
+    assert_eq!(pages[1].1, vec![json!({"id": 20})]);
+    assert_eq!(stats.total_items, 8);
+}
+
+#[tokio::test]
+async fn test_gzip_body_survives_retry() {
+    use apitap::http::fetcher::{BodyEncoding, HttpMethod, PaginatedFetcher, RequestTemplate};
+    use apitap::pipeline::Retry;
+    use futures::StreamExt;
+    use serde_json::json;
+    use std::io::Read;
+
+    // The first request fails with 500
+    let server = TestServer::start(|req| match req.index {
+        0 => Reply::status("500 Internal Server Error")
+            .header("content-type", "application/json")
+            .body("{}"),
+        _ => Reply::json("[]"),
+    })
+    .await;
+
+    let retry = Retry {
+        max_attempts: 1,
+        max_delay_secs: 1,
+        min_delay_secs: 1,
+        jitter: Default::default(),
+        max_elapsed_secs: None,
+    };
+    let fetcher =
+        PaginatedFetcher::new(reqwest::Client::new(), format!("{}/search", server.url), 1)
+            .with_limit_offset("limit", "offset")
+            .with_request(RequestTemplate {
+                method: HttpMethod::Post,
+                body: Some(json!({"filter": {"status": "open"}})),
+                body_encoding: BodyEncoding::Gzip,
+                ..RequestTemplate::default()
+            });
+    let records: Vec<_> = fetcher
+        .limit_offset_stream(10, None, None, &retry)
+        .await
+        .unwrap()
+        .collect()
+        .await;
+    assert!(records.is_empty());
+
+    let seen = server.requests();
+    assert_eq!(seen.len(), 2, "the failed request is retried");
+    for req in &seen {
+        assert!(req.contains("content-encoding: gzip"), "{req:?}");
+        assert!(req.contains("content-type: application/json"), "{req:?}");
+        let mut json = String::new();
+        flate2::read::GzDecoder::new(req.body.as_slice())
+            .read_to_string(&mut json)
+            .unwrap();
+        assert_eq!(
+            serde_json::from_str::<serde_json::Value>(&json).unwrap(),
+            json!({"filter": {"status": "open"}})
+        );
+    }
+}
+
+/// Serves the bodies `pages` builds from the server's base URL, in turn,
+/// then 404s.
+async fn serve_bodies(pages: fn(&str) -> Vec<String>) -> (String, TestServer) {
+    let server = TestServer::start(move |req| match pages(&req.base_url()).get(req.index) {
+        Some(body) => Reply::json(body.as_str()),
+        None => Reply::status("404 Not Found"),
+    })
+    .await;
+    (format!("{}/items", server.url), server)
+}
+
+#[tokio::test]
+async fn test_next_url_stream_follows_body_links() {
+    use apitap::http::fetcher::PaginatedFetcher;
+    use apitap::pipeline::Retry;
+    use futures::StreamExt;
+    use reqwest::header::{HeaderMap, HeaderValue, AUTHORIZATION};
+
+    let retry = Retry {
+        max_attempts: 0,
+        max_delay_secs: 1,
+        min_delay_secs: 1,
+        jitter: Default::default(),
+        max_elapsed_secs: None,
+    };
+
+    // A relative link, an absolute one, then none
+    let (url, server) = serve_bodies(|base| {
+        vec![
+            r#"{"data": [{"id": 1}], "links": {"next": "/items?after=1"}}"#.to_string(),
+            format!(r#"{{"data": [{{"id": 2}}], "links": {{"next": "{base}/items?after=2"}}}}"#),
+            r#"{"data": [{"id": 3}], "links": {"next": null}}"#.to_string(),
+        ]
+    })
+    .await;
+    let mut headers = HeaderMap::new();
+    headers.insert(AUTHORIZATION, HeaderValue::from_static("Bearer t0ken"));
+    let client = reqwest::Client::builder()
+        .default_headers(headers)
+        .build()
+        .unwrap();
+    let fetcher =
+        PaginatedFetcher::new(client, url, 1).with_next_url("links.next", None, Some("limit"));
+    let extra = vec![("status".to_string(), "open".to_string())];
+    let records: Vec<_> = fetcher
+        .next_url_stream(10, Some("/data"), Some(&extra), &retry)
+        .await
+        .unwrap()
+        .map(|r| r.unwrap())
+        .collect()
+        .await;
+    assert_eq!(
+        records,
+        vec![
+            serde_json::json!({"id": 1}),
+            serde_json::json!({"id": 2}),
+            serde_json::json!({"id": 3})
+        ]
+    );
+
+    let seen = server.requests();
+    assert!(
+        seen[0]
+            .line()
+            .starts_with("GET /items?status=open&limit=10 "),
+        "{seen:?}"
+    );
+    // Later pages use the link as given
+    assert!(
+        seen[1].line().starts_with("GET /items?after=1 "),
+        "{seen:?}"
+    );
+    assert!(
+        seen[2].line().starts_with("GET /items?after=2 "),
+        "{seen:?}"
+    );
+    assert!(seen
+        .iter()
+        .all(|req| req.header("authorization") == Some("Bearer t0ken")));
+}
+
+#[tokio::test]
+async fn test_next_url_stream_stops_when_has_more_is_false() {
+    use apitap::http::fetcher::PaginatedFetcher;
+    use apitap::pipeline::Retry;
+    use futures::StreamExt;
+
+    let retry = Retry {
+        max_attempts: 0,
+        max_delay_secs: 1,
+        min_delay_secs: 1,
+        jitter: Default::default(),
+        max_elapsed_secs: None,
+    };
+
+    // Stripe-style: the link stays set on the last page
+    let (url, server) = serve_bodies(|_| {
+        vec![
+            r#"{"data": [{"id": 1}], "has_more": true, "next": "/items?page=2"}"#.to_string(),
+            r#"{"data": [{"id": 2}], "has_more": false, "next": "/items?page=3"}"#.to_string(),
+        ]
+    })
+    .await;
+    let fetcher = PaginatedFetcher::new(reqwest::Client::new(), url, 1).with_next_url(
+        "/next",
+        Some("has_more"),
+        None,
+    );
+    let records: Vec<_> = fetcher
+        .next_url_stream(10, Some("/data"), None, &retry)
+        .await
+        .unwrap()
+        .collect()
+        .await;
+    assert_eq!(records.len(), 2);
+    assert_eq!(server.hits(), 2);
+}
+
+/// Keeps every record written, whole pages or streamed.
+#[derive(Default)]
+struct CollectingWriter {
+    records: std::sync::Mutex<Vec<serde_json::Value>>,
+}
+
+#[async_trait::async_trait]
+impl apitap::http::fetcher::PageWriter for CollectingWriter {
+    async fn write_page(
+        &self,
+        _page_number: u64,
+        data: Vec<serde_json::Value>,
+        _write_mode: apitap::writer::WriteMode,
+    ) -> apitap::errors::Result<()> {
+        self.records.lock().unwrap().extend(data);
+        Ok(())
+    }
+
+    async fn write_page_stream(
+        &self,
+        mut stream_data: std::pin::Pin<
+            Box<dyn futures::Stream<Item = apitap::errors::Result<serde_json::Value>> + Send>,
+        >,
+        _write_mode: apitap::writer::WriteMode,
+    ) -> apitap::errors::Result<()> {
+        use futures::StreamExt;
+        while let Some(record) = stream_data.next().await {
+            self.records.lock().unwrap().push(record?);
+        }
+        Ok(())
+    }
+}
+
+fn no_retry() -> apitap::pipeline::Retry {
+    apitap::pipeline::Retry {
+        max_attempts: 0,
+        max_delay_secs: 1,
+        min_delay_secs: 1,
+        jitter: Default::default(),
+        max_elapsed_secs: None,
+    }
+}
+
+#[tokio::test]
+async fn test_stop_when_equals_ends_limit_offset() {
+    use apitap::http::fetcher::{PaginatedFetcher, RequestTemplate};
+    use futures::StreamExt;
+
+    // Both pages are full; only `has_more` says the second is the last
+    let (url, server) = serve_bodies(|_| {
+        vec![
+            r#"{"data": [{"id": 1}, {"id": 2}], "has_more": true}"#.to_string(),
+            r#"{"data": [{"id": 3}, {"id": 4}], "has_more": false}"#.to_string(),
+        ]
+    })
+    .await;
+    let fetcher = PaginatedFetcher::new(reqwest::Client::new(), url, 1)
+        .with_limit_offset("limit", "offset")
+        .with_request(RequestTemplate {
+            stop_when: Some(serde_yaml::from_str("{path: has_more, equals: false}").unwrap()),
+            ..RequestTemplate::default()
+        });
+    let records: Vec<_> = fetcher
+        .limit_offset_stream(2, Some("/data"), None, &no_retry())
+        .await
+        .unwrap()
+        .collect()
+        .await;
+    assert_eq!(records.len(), 4);
+    assert_eq!(server.hits(), 2);
+}
+
+#[tokio::test]
+async fn test_stop_when_null_ends_page_number() {
+    use apitap::http::fetcher::{PaginatedFetcher, RequestTemplate};
+    use std::sync::Arc;
+
+    // The second page has no `next`; a third request would be refused
+    let (url, server) = serve_bodies(|_| {
+        vec![
+            r#"{"data": [{"id": 1}, {"id": 2}], "next": 2}"#.to_string(),
+            r#"{"data": [{"id": 3}, {"id": 4}]}"#.to_string(),
+        ]
+    })
+    .await;
+    let writer = Arc::new(CollectingWriter::default());
+    let stats = PaginatedFetcher::new(reqwest::Client::new(), url, 1)
+        .with_page_number("page", "per_page")
+        .with_request(RequestTemplate {
+            stop_when: Some(serde_yaml::from_str("{path: next, equals: null}").unwrap()),
+            ..RequestTemplate::default()
+        })
+        .fetch_page_number(
+            2,
+            Some("/data"),
+            None,
+            writer.clone(),
+            apitap::writer::WriteMode::Append,
+            &no_retry(),
+        )
+        .await
+        .unwrap();
+
+    assert_eq!(writer.records.lock().unwrap().len(), 4);
+    assert_eq!(stats.request_count, 2);
+    let seen = server.requests();
+    assert!(seen[1].line().contains("page=2"), "{seen:?}");
+}
+
+#[tokio::test]
+async fn test_request_count_includes_retries() {
+    use apitap::http::fetcher::{PaginatedFetcher, RequestTemplate};
+    use apitap::pipeline::Retry;
+    use std::sync::Arc;
+
+    // The first attempt fails with a 503; the retry gets the only page
+    let server = TestServer::start(|req| match req.index {
+        0 => Reply::status("503 Service Unavailable"),
+        _ => Reply::json(r#"{"data": [{"id": 1}]}"#),
+    })
+    .await;
+
+    let retry = Retry {
+        max_attempts: 1,
+        ..no_retry()
+    };
+    let writer = Arc::new(CollectingWriter::default());
+    let stats = PaginatedFetcher::new(reqwest::Client::new(), format!("{}/items", server.url), 1)
+        .with_page_number("page", "per_page")
+        .with_request(RequestTemplate {
+            stop_when: Some(serde_yaml::from_str("{path: next, equals: null}").unwrap()),
+            ..RequestTemplate::default()
+        })
+        .fetch_page_number(
+            2,
+            Some("/data"),
+            None,
+            writer.clone(),
+            apitap::writer::WriteMode::Append,
+            &retry,
+        )
+        .await
+        .unwrap();
+
+    assert_eq!(writer.records.lock().unwrap().len(), 1);
+    assert_eq!(stats.page_count, 1);
+    assert_eq!(stats.request_count, 2);
+}
+
+#[tokio::test]
+async fn test_stop_when_short_page_ends_cursor() {
+    use apitap::http::fetcher::{PaginatedFetcher, RequestTemplate};
+    use futures::StreamExt;
+
+    // Two tokens are offered, but the second page is short
+    let (url, server) = serve_cursor_pages(&["t1", "t2"]).await;
+    let fetcher = PaginatedFetcher::new(reqwest::Client::new(), url, 1)
+        .with_cursor("after", None, "X-Next-Page-Token", CursorIn::Param)
+        .with_request(RequestTemplate {
+            stop_when: Some(serde_yaml::from_str("{short_page: true}").unwrap()),
+            ..RequestTemplate::default()
+        });
+    let records: Vec<_> = fetcher
+        .cursor_stream(1, None, None, &no_retry())
+        .await
+        .unwrap()
+        .collect()
+        .await;
+    // Every page of this server holds one record; a page size of 1 is never short
+    assert_eq!(records.len(), 3);
+    assert_eq!(server.hits(), 3);
+
+    let (url, server) = serve_cursor_pages(&["t1", "t2"]).await;
+    let fetcher = PaginatedFetcher::new(reqwest::Client::new(), url, 1)
+        .with_cursor("after", None, "X-Next-Page-Token", CursorIn::Param)
+        .with_request(RequestTemplate {
+            stop_when: Some(serde_yaml::from_str("{short_page: true}").unwrap()),
+            ..RequestTemplate::default()
+        });
+    let records: Vec<_> = fetcher
+        .cursor_stream(10, None, None, &no_retry())
+        .await
+        .unwrap()
+        .collect()
+        .await;
+    assert_eq!(records.len(), 1);
+    assert_eq!(server.hits(), 1);
+}
@@ -1,2 +1,5 @@
 mod arrow_type_tests;
+mod capture_tests;
 mod fetcher_tests;
+mod oauth2_tests;
+mod signing_tests;
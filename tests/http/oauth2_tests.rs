@@ -0,0 +1,121 @@
+use std::sync::Arc;
+
+use apitap::errors::ApitapError;
+use apitap::http::oauth2::{ClientCredentials, TokenCache};
+use apitap::http::signing::RequestSigner;
+use reqwest::Client;
+
+use crate::common::http_server::{Reply, TestServer};
+
+/// Token endpoint that answers every request with `t-<n>` and `expires_in`,
+/// returning its URL and the server, which counts the requests served.
+async fn serve_tokens(expires_in: u64) -> (String, TestServer) {
+    let server = TestServer::start(move |req| {
+        assert!(req.line().starts_with("POST /token"), "{req:?}");
+        let body = String::from_utf8_lossy(&req.body);
+        assert!(body.contains("grant_type=client_credentials"), "{body}");
+        Reply::json(format!(
+            r#"{{"access_token":"t-{}","expires_in":{expires_in}}}"#,
+            req.index + 1
+        ))
+    })
+    .await;
+    (format!("{}/token", server.url), server)
+}
+
+fn request() -> reqwest::Request {
+    Client::new()
+        .get("https://api.example.com/orders")
+        .build()
+        .unwrap()
+}
+
+#[tokio::test]
+async fn test_client_credentials_share_one_token_per_key() {
+    let (token_url, server) = serve_tokens(3600).await;
+    let cache = Arc::new(TokenCache::default());
+    let auth = |scope: &str| {
+        ClientCredentials::new(token_url.clone(), "etl", "s3cr3t")
+            .with_scope(scope.to_string())
+            .with_cache(cache.clone())
+    };
+
+    // Two modules with the same credentials, signing concurrently
+    let (first, second) = (auth("orders:read"), auth("orders:read"));
+    let tokens = futures::future::join_all((0..8).map(|i| {
+        let auth = if i % 2 == 0 { &first } else { &second };
+        async move {
+            let mut request = request();
+            auth.sign(&mut request).await.unwrap();
+            request.headers()["authorization"]
+                .to_str()
+                .unwrap()
+                .to_string()
+        }
+    }))
+    .await;
+    assert!(tokens.iter().all(|t| t == "Bearer t-1"), "{tokens:?}");
+    assert_eq!(server.hits(), 1);
+
+    // A different scope gets its own token
+    assert_eq!(auth("users:read").token().await.unwrap(), "t-2");
+    assert_eq!(server.hits(), 2);
+}
+
+#[tokio::test]
+async fn test_client_credentials_refresh_expiring_and_rejected_tokens() {
+    // Already expired, so never reused
+    let (token_url, server) = serve_tokens(0).await;
+    let auth = ClientCredentials::new(token_url, "etl", "s3cr3t")
+        .with_cache(Arc::new(TokenCache::default()));
+    assert_eq!(auth.token().await.unwrap(), "t-1");
+    assert_eq!(auth.token().await.unwrap(), "t-2");
+    assert_eq!(server.hits(), 2);
+
+    // Shorter than the expiry margin, but still reused for half its lifetime
+    let (token_url, server) = serve_tokens(10).await;
+    let auth = ClientCredentials::new(token_url, "etl", "s3cr3t")
+        .with_cache(Arc::new(TokenCache::default()));
+    assert_eq!(auth.token().await.unwrap(), "t-1");
+    assert_eq!(auth.token().await.unwrap(), "t-1");
+    assert_eq!(server.hits(), 1);
+
+    let (token_url, server) = serve_tokens(3600).await;
+    let auth = ClientCredentials::new(token_url, "etl", "s3cr3t")
+        .with_cache(Arc::new(TokenCache::default()));
+    assert_eq!(auth.token().await.unwrap(), "t-1");
+    let rejected = http::Response::builder().status(401).body("").unwrap();
+    auth.on_response(&reqwest::Response::from(rejected));
+    assert_eq!(auth.token().await.unwrap(), "t-2");
+    assert_eq!(server.hits(), 2);
+}
+
+#[tokio::test]
+async fn test_client_credentials_token_endpoint_error() {
+    let server = TestServer::start(|_| Reply::status("401 Unauthorized")).await;
+
+    let auth = ClientCredentials::new(format!("{}/token", server.url), "etl", "wrong")
+        .with_cache(Arc::new(TokenCache::default()));
+    let mut request = request();
+    let err = auth.sign(&mut request).await.unwrap_err();
+    assert!(matches!(err, ApitapError::TokenError(_)), "{err}");
+    assert!(err.to_string().contains("401"), "{err}");
+    assert!(request.headers().get("authorization").is_none());
+}
+
+#[tokio::test]
+async fn test_client_credentials_token_request_times_out() {
+    // Accepts the request but never answers
+    let server = TestServer::start(|_| Reply::hang()).await;
+
+    let client = Client::builder()
+        .timeout(std::time::Duration::from_millis(200))
+        .build()
+        .unwrap();
+    let auth = ClientCredentials::new(format!("{}/token", server.url), "etl", "s3cr3t")
+        .with_client(client)
+        .with_cache(Arc::new(TokenCache::default()));
+    let started = std::time::Instant::now();
+    assert!(auth.token().await.is_err());
+    assert!(started.elapsed() < std::time::Duration::from_secs(5));
+}
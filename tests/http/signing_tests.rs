@@ -0,0 +1,109 @@
+use apitap::http::signing::{HmacSigner, QueryParamAuth, RequestSigner, SignerChain};
+use reqwest::Client;
+use std::sync::Arc;
+
+#[test]
+fn test_hmac_signature_known_vector() {
+    let signer = HmacSigner::new("key");
+    assert_eq!(
+        signer.signature("GET", "/", "1700000000", b""),
+        "2b77cc7882c0848085ea090641e7bc32c69b43b80c2a5234a5d8e6474cf52da5"
+    );
+
+    let prefixed = HmacSigner::new("key").with_prefix("sha256=");
+    assert!(prefixed
+        .signature("GET", "/", "1700000000", b"")
+        .starts_with("sha256=2b77cc78"));
+}
+
+#[tokio::test]
+async fn test_hmac_signs_method_path_query_and_body() {
+    let mut request = Client::new()
+        .post("https://api.example.com/v1/orders?page=2")
+        .body(r#"{"a":1}"#)
+        .build()
+        .unwrap();
+
+    HmacSigner::new("s3cr3t")
+        .with_header("X-Sig")
+        .sign(&mut request)
+        .await
+        .unwrap();
+
+    assert_eq!(
+        request.headers()["x-sig"],
+        "5327474ab221faf2453339e1e0088f9bd44be942480acca526c93ee09ef927e8"
+    );
+}
+
+#[tokio::test]
+async fn test_hmac_timestamp_header_is_signed() {
+    let mut request = Client::new()
+        .get("https://api.example.com/items")
+        .build()
+        .unwrap();
+
+    let signer = HmacSigner::new("s3cr3t").with_timestamp_header("X-Timestamp".to_string());
+    signer.sign(&mut request).await.unwrap();
+
+    let ts = request.headers()["x-timestamp"]
+        .to_str()
+        .unwrap()
+        .to_string();
+    assert!(ts.parse::<u64>().is_ok());
+    assert_eq!(
+        request.headers()["x-signature"],
+        signer.signature("GET", "/items", &ts, b"").as_str()
+    );
+}
+
+#[test]
+fn test_hmac_signer_debug_hides_secret() {
+    let dbg = format!("{:?}", HmacSigner::new("super-secret"));
+    assert!(!dbg.contains("super-secret"));
+}
+
+#[tokio::test]
+async fn test_query_auth_replaces_existing_param() {
+    let mut request = Client::new()
+        .get("https://api.example.com/items?api_key=old&page=3")
+        .build()
+        .unwrap();
+
+    QueryParamAuth::new("api_key", "new key")
+        .sign(&mut request)
+        .await
+        .unwrap();
+
+    assert_eq!(request.url().query(), Some("page=3&api_key=new+key"));
+}
+
+#[tokio::test]
+async fn test_signer_chain_signs_the_query_auth_key() {
+    let mut request = Client::new()
+        .get("https://api.example.com/items?page=1")
+        .build()
+        .unwrap();
+
+    let hmac = HmacSigner::new("s3cr3t");
+    SignerChain(vec![
+        Arc::new(QueryParamAuth::new("api_key", "k1")),
+        Arc::new(hmac.clone()),
+    ])
+    .sign(&mut request)
+    .await
+    .unwrap();
+
+    assert_eq!(
+        request.headers()["x-signature"],
+        hmac.signature("GET", "/items?page=1&api_key=k1", "", b"")
+            .as_str()
+    );
+}
+
+#[test]
+fn test_query_auth_debug_hides_value() {
+    let debug = format!("{:?}", QueryParamAuth::new("apiKey", "k-123"));
+    assert!(debug.contains("apiKey"));
+    assert!(!debug.contains("k-123"));
+}
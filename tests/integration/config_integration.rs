@@ -132,6 +132,8 @@ fn test_retry_configuration_validation() {
         max_attempts: 5,
         max_delay_secs: 300,
         min_delay_secs: 1,
+        jitter: Default::default(),
+        max_elapsed_secs: None,
     };
 
     // Retry configuration should be valid
@@ -136,33 +136,52 @@ fn test_empty_vs_populated_records() {
     assert!(optional.is_nullable());
 }
 
-// Note: Full DataFusion integration tests would include:
-// 1. Creating DataFusion context
-// 2. Registering tables with inferred schemas
-// 3. Executing SQL queries
-// 4. Converting results to JSON
-// 5. Stream processing with DataFusion
-//
-// Example for future implementation:
-// #[tokio::test]
-// async fn test_datafusion_query_execution() {
-//     use datafusion::prelude::*;
-//
-//     let ctx = SessionContext::new();
-//
-//     // Create test data
-//     let values = vec![
-//         json!({"id": 1, "value": 100}),
-//         json!({"id": 2, "value": 200}),
-//     ];
-//
-//     // Infer schema and register table
-//     let schema = infer_schema_from_values(&values).unwrap();
-//     // ... register table with schema ...
-//
-//     // Execute query
-//     let df = ctx.sql("SELECT id, value FROM test_table WHERE value > 100").await.unwrap();
-//     let results = df.collect().await.unwrap();
-//
-//     assert_eq!(results.len(), 1);
-// }
+#[tokio::test]
+async fn test_transform_json_runs_sql_over_sample_records() {
+    use apitap::utils::datafusion_ext::{transform_json, transform_json_rows};
+
+    let records = vec![
+        json!({"id": 1, "value": 100, "url": "https://a.example.com/x"}),
+        json!({"id": 2, "value": 200, "url": "https://b.example.com/y"}),
+        json!({"id": 3, "value": 300, "url": null}),
+    ];
+    let sql = "SELECT id, url_host(url) AS host FROM test_table WHERE value > 100 ORDER BY id";
+
+    let batches = transform_json("test_table", sql, &records).await.unwrap();
+    assert_eq!(batches.iter().map(|b| b.num_rows()).sum::<usize>(), 2);
+    assert_eq!(
+        batches[0]
+            .schema()
+            .field_with_name("host")
+            .unwrap()
+            .data_type(),
+        &DataType::Utf8
+    );
+
+    let rows = transform_json_rows("test_table", sql, &records)
+        .await
+        .unwrap();
+    assert_eq!(
+        rows,
+        vec![
+            json!({"id": 2, "host": "b.example.com"}),
+            json!({"id": 3, "host": null}),
+        ]
+    );
+}
+
+#[tokio::test]
+async fn test_transform_json_uses_the_stream_sample_schema() {
+    use apitap::utils::datafusion_ext::transform_json;
+    use apitap::utils::schema::STREAM_SAMPLE_SIZE;
+
+    // A record past the sample that doesn't fit the inferred schema fails,
+    // as it would in a streamed fetch
+    let mut records: Vec<_> = (0..STREAM_SAMPLE_SIZE).map(|i| json!({"id": i})).collect();
+    records.push(json!({"id": "not-a-number"}));
+    assert!(transform_json("t", "SELECT * FROM t", &records)
+        .await
+        .is_err());
+
+    assert!(transform_json("t", "SELECT * FROM t", &[]).await.is_err());
+}
@@ -85,16 +85,23 @@ fn test_pagination_strategy_configuration() {
     let limit_offset = Pagination::LimitOffset {
         limit_param: "limit".to_string(),
         offset_param: "offset".to_string(),
+        start_offset: 0,
+        page_size: None,
     };
 
     let page_number = Pagination::PageNumber {
         page_param: "page".to_string(),
         per_page_param: "size".to_string(),
+        start_page: 1,
+        page_size: None,
     };
 
     let cursor = Pagination::Cursor {
         cursor_param: "next_cursor".to_string(),
         page_size_param: Some("page_size".to_string()),
+        next_cursor_header: Some("X-Next-Cursor".to_string()),
+        cursor_in: Default::default(),
+        page_size: None,
     };
 
     // All strategies should be configurable
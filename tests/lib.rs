@@ -1,16 +1,22 @@
 // Integration tests for apitap
 //
 // This test suite is organized into modules for better maintainability:
+// - cmd: Tests for CLI runtime helpers (health probes)
 // - config: Tests for configuration and templating
 // - errors: Tests for error handling and error types
+// - log: Tests for tracing setup (per-module log levels)
 // - utils: Tests for utility functions (schema inference, streaming)
 // - pipeline: Tests for pipeline configuration and management
 // - http: Tests for HTTP fetcher and pagination
 // - writer: Tests for data writer and write modes
+// - common: Shared helpers (a small HTTP server)
 
+mod cmd;
+mod common;
 mod config;
 mod errors;
 mod http;
+mod log;
 mod pipeline;
 mod utils;
 mod writer;
@@ -0,0 +1 @@
+mod module_level_tests;
@@ -0,0 +1,72 @@
+use std::sync::{Arc, Mutex};
+
+use apitap::log::{set_module_level, LogLevel, ModuleLevelFilter};
+use tracing::level_filters::LevelFilter;
+use tracing::{Event, Level, Subscriber};
+use tracing_subscriber::layer::{Context, Layer, SubscriberExt};
+use tracing_subscriber::{EnvFilter, Registry};
+
+/// Keeps the level and target of every event that gets through.
+#[derive(Clone, Default)]
+struct Seen(Arc<Mutex<Vec<(Level, String)>>>);
+
+impl<S: Subscriber> Layer<S> for Seen {
+    fn on_event(&self, event: &Event<'_>, _ctx: Context<'_, S>) {
+        let meta = event.metadata();
+        self.0
+            .lock()
+            .unwrap()
+            .push((*meta.level(), meta.target().to_string()));
+    }
+}
+
+#[test]
+fn test_module_level_overrides_apply_inside_module_span() {
+    set_module_level("log_test_quiet", Some(LogLevel(LevelFilter::WARN)));
+    set_module_level("log_test_verbose", Some("debug".parse().unwrap()));
+
+    let seen = Seen::default();
+    let subscriber = Registry::default()
+        .with(ModuleLevelFilter::new(EnvFilter::new("info")))
+        .with(seen.clone());
+    tracing::subscriber::with_default(subscriber, || {
+        tracing::info!(target: "apitap::test", "outside");
+        tracing::debug!(target: "apitap::test", "outside");
+        tracing::info_span!("module", idx = 1, module = "log_test_quiet").in_scope(|| {
+            tracing::info!(target: "apitap::test", "quiet");
+            tracing::warn!(target: "apitap::test", "quiet");
+        });
+        tracing::info_span!("module", idx = 2, module = "log_test_verbose").in_scope(|| {
+            tracing::info_span!("transform.load").in_scope(|| {
+                tracing::debug!(target: "apitap::test", "verbose");
+            });
+            tracing::trace!(target: "apitap::test", "verbose");
+            // Other crates keep the process-wide level
+            tracing::debug!(target: "hyper::proto", "verbose");
+        });
+    });
+
+    let seen = seen.0.lock().unwrap().clone();
+    assert_eq!(
+        seen,
+        vec![
+            (Level::INFO, "apitap::test".to_string()),
+            (Level::WARN, "apitap::test".to_string()),
+            (Level::DEBUG, "apitap::test".to_string()),
+        ]
+    );
+
+    set_module_level("log_test_quiet", None);
+    set_module_level("log_test_verbose", None);
+}
+
+#[test]
+fn test_log_level_parse_and_display() {
+    assert_eq!(
+        "Debug".parse::<LogLevel>().unwrap(),
+        LogLevel(LevelFilter::DEBUG)
+    );
+    assert_eq!(LogLevel(LevelFilter::OFF).to_string(), "off");
+    let err = "verbose".parse::<LogLevel>().unwrap_err();
+    assert!(err.to_string().contains("unknown log level 'verbose'"));
+}
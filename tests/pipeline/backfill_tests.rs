@@ -0,0 +1,87 @@
+use apitap::pipeline::backfill::{parse_window, Backfill, BackfillMode, Window};
+use chrono::NaiveDate;
+
+fn day(month: u32, d: u32) -> NaiveDate {
+    NaiveDate::from_ymd_opt(2024, month, d).unwrap()
+}
+
+fn backfill(from: NaiveDate, to: NaiveDate, window_days: u32) -> Backfill {
+    Backfill {
+        from,
+        to,
+        window_days,
+        mode: BackfillMode::Append,
+    }
+}
+
+#[test]
+fn test_daily_windows_cover_range_inclusive() {
+    let windows = backfill(day(1, 30), day(2, 2), 1).windows().unwrap();
+    let starts: Vec<String> = windows.iter().map(|w| w.start.to_string()).collect();
+    assert_eq!(
+        starts,
+        vec!["2024-01-30", "2024-01-31", "2024-02-01", "2024-02-02"]
+    );
+    assert!(windows.iter().all(|w| w.end == w.start.succ_opt().unwrap()));
+}
+
+#[test]
+fn test_last_window_is_clipped_to_range() {
+    let windows = backfill(day(1, 1), day(1, 10), 7).windows().unwrap();
+    assert_eq!(
+        windows,
+        vec![
+            Window {
+                start: day(1, 1),
+                end: day(1, 8)
+            },
+            Window {
+                start: day(1, 8),
+                end: day(1, 11)
+            },
+        ]
+    );
+}
+
+#[test]
+fn test_single_day_range_is_one_window() {
+    let windows = backfill(day(3, 5), day(3, 5), 30).windows().unwrap();
+    assert_eq!(
+        windows,
+        vec![Window {
+            start: day(3, 5),
+            end: day(3, 6)
+        }]
+    );
+}
+
+#[test]
+fn test_reversed_range_is_rejected() {
+    let err = backfill(day(2, 1), day(1, 1), 1).windows().unwrap_err();
+    assert!(err.to_string().contains("before --from"));
+}
+
+#[test]
+fn test_parse_window() {
+    assert_eq!(parse_window("1d").unwrap(), 1);
+    assert_eq!(parse_window("7D").unwrap(), 7);
+    assert_eq!(parse_window("2w").unwrap(), 14);
+    assert_eq!(parse_window("3").unwrap(), 3);
+    for bad in ["", "d", "0d", "-1d", "1m", "1.5d"] {
+        assert!(parse_window(bad).is_err(), "{bad:?} should be rejected");
+    }
+}
+
+#[test]
+fn test_backfill_mode_from_str() {
+    assert_eq!(
+        "append".parse::<BackfillMode>().unwrap(),
+        BackfillMode::Append
+    );
+    assert_eq!(
+        " Replace ".parse::<BackfillMode>().unwrap(),
+        BackfillMode::Replace
+    );
+    let err = "merge".parse::<BackfillMode>().unwrap_err();
+    assert!(err.to_string().contains("expected append or replace"));
+}
@@ -0,0 +1,171 @@
+use std::pin::Pin;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+use apitap::errors::Result;
+use apitap::http::fetcher::PageWriter;
+use apitap::pipeline::checkpoint::{CheckpointStore, CheckpointingPageWriter, Resume};
+use apitap::writer::{DataWriter, WriteMode};
+use futures::Stream;
+use serde_json::Value;
+
+const DAY: Duration = Duration::from_secs(86400);
+
+#[test]
+fn test_store_save_and_load() {
+    let dir = tempfile::tempdir().unwrap();
+    let store = CheckpointStore::new(dir.path());
+
+    assert!(store.load("events", "http://a/x", DAY).unwrap().is_none());
+
+    store.save("events", "http://a/x", 4, false).unwrap();
+    store.save("events", "http://a/y", 9, true).unwrap();
+
+    let x = store.load("events", "http://a/x", DAY).unwrap().unwrap();
+    assert_eq!((x.next_page, x.complete), (4, false));
+    let y = store.load("events", "http://a/y", DAY).unwrap().unwrap();
+    assert_eq!((y.next_page, y.complete), (9, true));
+    assert!(store.load("users", "http://a/x", DAY).unwrap().is_none());
+}
+
+#[test]
+fn test_store_ignores_checkpoints_outside_window() {
+    let dir = tempfile::tempdir().unwrap();
+    let store = CheckpointStore::new(dir.path());
+    store.save("events", "http://a/x", 4, false).unwrap();
+
+    std::thread::sleep(Duration::from_millis(20));
+    assert!(store
+        .load("events", "http://a/x", Duration::from_millis(1))
+        .unwrap()
+        .is_none());
+}
+
+#[test]
+fn test_store_clear() {
+    let dir = tempfile::tempdir().unwrap();
+    let store = CheckpointStore::new(dir.path());
+    store.save("events", "http://a/x", 4, false).unwrap();
+    store.save("users", "http://a/x", 2, false).unwrap();
+
+    store.clear("events").unwrap();
+    assert!(store.load("events", "http://a/x", DAY).unwrap().is_none());
+    assert!(store.load("users", "http://a/x", DAY).unwrap().is_some());
+
+    store.clear_all().unwrap();
+    assert!(store.load("users", "http://a/x", DAY).unwrap().is_none());
+
+    // Clearing a missing directory is fine
+    CheckpointStore::new(dir.path().join("missing"))
+        .clear_all()
+        .unwrap();
+}
+
+/// Records page writes and transaction hooks in call order.
+#[derive(Default)]
+struct Recorder {
+    calls: Mutex<Vec<String>>,
+}
+
+#[async_trait::async_trait]
+impl PageWriter for Recorder {
+    async fn write_page(
+        &self,
+        page_number: u64,
+        _data: Vec<Value>,
+        _mode: WriteMode,
+    ) -> Result<()> {
+        self.calls
+            .lock()
+            .unwrap()
+            .push(format!("page {page_number}"));
+        Ok(())
+    }
+
+    async fn write_page_stream(
+        &self,
+        _stream_data: Pin<Box<dyn Stream<Item = Result<Value>> + Send>>,
+        _mode: WriteMode,
+    ) -> Result<()> {
+        self.calls.lock().unwrap().push("stream".to_string());
+        Ok(())
+    }
+}
+
+#[async_trait::async_trait]
+impl DataWriter for Recorder {
+    async fn write(&self, _result: apitap::utils::datafusion_ext::QueryResult) -> Result<()> {
+        Ok(())
+    }
+
+    async fn begin(&self) -> Result<()> {
+        self.calls.lock().unwrap().push("begin".to_string());
+        Ok(())
+    }
+
+    async fn commit(&self) -> Result<()> {
+        self.calls.lock().unwrap().push("commit".to_string());
+        Ok(())
+    }
+}
+
+#[tokio::test]
+async fn test_checkpointing_writer_commits_each_page() {
+    let dir = tempfile::tempdir().unwrap();
+    let store = CheckpointStore::new(dir.path());
+    let recorder = Arc::new(Recorder::default());
+    let writer = CheckpointingPageWriter::new(
+        recorder.clone(),
+        recorder.clone(),
+        Resume {
+            store: store.clone(),
+            key: "events".to_string(),
+            window: DAY,
+        },
+        "http://a/x",
+        3,
+    );
+
+    // A page split over several writes is committed once, after its last one
+    writer
+        .write_page(3, vec![], WriteMode::Append)
+        .await
+        .unwrap();
+    writer
+        .write_page(3, vec![], WriteMode::Append)
+        .await
+        .unwrap();
+    writer.page_done(3).await.unwrap();
+    assert_eq!(writer.next_page(), 4);
+
+    // Page 5 finishing first does not move the checkpoint past page 4
+    writer
+        .write_page(5, vec![], WriteMode::Append)
+        .await
+        .unwrap();
+    writer.page_done(5).await.unwrap();
+    assert_eq!(writer.next_page(), 4);
+    let saved = store.load("events", "http://a/x", DAY).unwrap().unwrap();
+    assert_eq!((saved.next_page, saved.complete), (4, false));
+
+    writer
+        .write_numbered_page_stream(4, Box::pin(futures::stream::empty()), WriteMode::Append)
+        .await
+        .unwrap();
+    writer.page_done(4).await.unwrap();
+    assert_eq!(writer.next_page(), 6);
+    assert_eq!(
+        *recorder.calls.lock().unwrap(),
+        vec!["page 3", "page 3", "commit", "begin", "page 5", "stream", "commit", "begin"]
+    );
+
+    let saved = store.load("events", "http://a/x", DAY).unwrap().unwrap();
+    assert_eq!((saved.next_page, saved.complete), (6, false));
+
+    writer.on_page_error(6, "boom".to_string()).await.unwrap();
+    assert!(writer.page_failed());
+
+    writer.finish().unwrap();
+    let saved = store.load("events", "http://a/x", DAY).unwrap().unwrap();
+    assert_eq!((saved.next_page, saved.complete), (6, true));
+}
@@ -0,0 +1,38 @@
+use apitap::pipeline::conditional::{ConditionalRequest, ValidatorStore, Validators};
+
+#[test]
+fn test_validator_store_round_trip() {
+    let dir = tempfile::tempdir().unwrap();
+    let store = ValidatorStore::new(dir.path());
+    let validators = Validators {
+        etag: Some("\"abc\"".to_string()),
+        last_modified: Some("Wed, 21 Oct 2015 07:28:00 GMT".to_string()),
+    };
+
+    assert!(store
+        .load("catalog", "https://api/items")
+        .unwrap()
+        .is_none());
+    store
+        .save("catalog", "https://api/items", &validators)
+        .unwrap();
+    assert_eq!(
+        store.load("catalog", "https://api/items").unwrap(),
+        Some(validators)
+    );
+    assert!(store
+        .load("catalog", "https://api/other")
+        .unwrap()
+        .is_none());
+    assert!(store.load("users", "https://api/items").unwrap().is_none());
+}
+
+#[test]
+fn test_conditional_request_without_previous_validators() {
+    let conditional = ConditionalRequest::new(None);
+    let headers = conditional.first_request_headers().unwrap();
+    assert!(headers.is_empty());
+    assert!(conditional.first_request_headers().is_none());
+    assert!(!conditional.not_modified());
+    assert!(conditional.received().is_none());
+}
@@ -1,5 +1,5 @@
 use apitap::http::fetcher::Pagination;
-use apitap::pipeline::{Config, PostgresAuth, Retry, Target};
+use apitap::pipeline::{Config, PostgresAuth, Retry, RetryJitter, Target};
 
 #[test]
 fn test_config_source_indexing() {
@@ -71,6 +71,7 @@ targets:
             assert_eq!(pg.port, 5432);
             assert_eq!(pg.database, "testdb");
         }
+        other => panic!("expected postgres target, got {other:?}"),
     }
 }
 
@@ -173,6 +174,7 @@ targets:
         Target::Postgres(pg) => {
             assert_eq!(pg.port, 5432); // default port
         }
+        other => panic!("expected postgres target, got {other:?}"),
     }
 }
 
@@ -198,6 +200,7 @@ targets:
         Target::Postgres(pg) => {
             assert_eq!(pg.port, 5433);
         }
+        other => panic!("expected postgres target, got {other:?}"),
     }
 }
 
@@ -207,11 +210,41 @@ fn test_retry_configuration() {
         max_attempts: 5,
         max_delay_secs: 120,
         min_delay_secs: 2,
+        jitter: RetryJitter::Equal,
+        max_elapsed_secs: Some(300),
     };
 
     assert_eq!(retry.max_attempts, 5);
     assert_eq!(retry.max_delay_secs, 120);
     assert_eq!(retry.min_delay_secs, 2);
+    assert_eq!(retry.jitter, RetryJitter::Equal);
+}
+
+#[test]
+fn test_retry_jitter_defaults_to_full() {
+    let retry: Retry =
+        serde_yaml::from_str("max_attempts: 3\nmax_delay_secs: 60\nmin_delay_secs: 1\n").unwrap();
+    assert_eq!(retry.jitter, RetryJitter::Full);
+
+    let retry: Retry = serde_yaml::from_str(
+        "max_attempts: 3\nmax_delay_secs: 60\nmin_delay_secs: 1\njitter: none\n",
+    )
+    .unwrap();
+    assert_eq!(retry.jitter, RetryJitter::None);
+}
+
+#[test]
+fn test_retry_interval_and_elapsed_aliases() {
+    let retry: Retry =
+        serde_yaml::from_str("max_attempts: 3\nmax_delay_secs: 60\nmin_delay_secs: 1\n").unwrap();
+    assert_eq!(retry.max_elapsed_secs, None);
+
+    let retry: Retry = serde_yaml::from_str(
+        "max_attempts: 3\nmax_interval_secs: 30\nmin_delay_secs: 1\nmax_elapsed_time_secs: 600\n",
+    )
+    .unwrap();
+    assert_eq!(retry.max_delay_secs, 30);
+    assert_eq!(retry.max_elapsed_secs, Some(600));
 }
 
 #[test]
@@ -239,6 +272,7 @@ targets: []
         Pagination::LimitOffset {
             limit_param,
             offset_param,
+            ..
         } => {
             assert_eq!(limit_param, "limit");
             assert_eq!(offset_param, "offset");
@@ -247,6 +281,35 @@ targets: []
     }
 }
 
+#[test]
+fn test_source_path_params() {
+    let config_yaml = r#"
+sources:
+  - name: user_orders
+    url: https://api.example.com/users/{user_id}/orders
+    path_params:
+      name: user_id
+      values: [17, "abc"]
+      query: SELECT id FROM active_users
+    retry:
+      max_attempts: 3
+      max_delay_secs: 60
+      min_delay_secs: 1
+targets: []
+"#;
+
+    let config: Config = serde_yaml::from_str(config_yaml).unwrap();
+    let params = config
+        .source("user_orders")
+        .unwrap()
+        .path_params
+        .as_ref()
+        .unwrap();
+    assert_eq!(params.name, "user_id");
+    assert_eq!(params.values.len(), 2);
+    assert_eq!(params.query.as_deref(), Some("SELECT id FROM active_users"));
+}
+
 #[test]
 fn test_source_without_pagination() {
     let config_yaml = r#"
@@ -289,6 +352,65 @@ targets: []
     );
 }
 
+#[test]
+fn test_danger_accept_invalid_certs_defaults_off() {
+    let config_yaml = r#"
+sources:
+  - name: prod
+    url: https://api.example.com/data
+  - name: staging
+    url: https://staging.internal/data
+    danger_accept_invalid_certs: true
+targets: []
+"#;
+
+    let config: Config = serde_yaml::from_str(config_yaml).unwrap();
+    assert!(!config.source("prod").unwrap().danger_accept_invalid_certs);
+    assert!(
+        config
+            .source("staging")
+            .unwrap()
+            .danger_accept_invalid_certs
+    );
+}
+
+#[test]
+fn test_destination_table_defaults_to_module_file_name() {
+    let config_yaml = r#"
+defaults:
+  table_prefix: raw_
+sources:
+  - name: api1
+    url: https://api.example.com/data
+    table_destination_name: custom_table
+  - name: api2
+    url: https://api.example.com/other
+targets: []
+"#;
+
+    let config: Config = serde_yaml::from_str(config_yaml).unwrap();
+    let explicit = config.source("api1").unwrap();
+    let derived = config.source("api2").unwrap();
+
+    assert_eq!(
+        config.destination_table(explicit, "reports/users.sql"),
+        Some("custom_table".to_string())
+    );
+    assert_eq!(
+        config.destination_table(derived, "reports/users.sql"),
+        Some("raw_users".to_string())
+    );
+
+    let unprefixed: Config = serde_yaml::from_str(
+        "sources:\n  - name: api2\n    url: https://api.example.com/other\ntargets: []\n",
+    )
+    .unwrap();
+    assert_eq!(
+        unprefixed.destination_table(unprefixed.source("api2").unwrap(), "users.sql"),
+        Some("users".to_string())
+    );
+}
+
 #[test]
 fn test_pagination_page_number() {
     let config_yaml = r#"
@@ -313,6 +435,7 @@ targets: []
         Pagination::PageNumber {
             page_param,
             per_page_param,
+            ..
         } => {
             assert_eq!(page_param, "page");
             assert_eq!(per_page_param, "per_page");
@@ -345,6 +468,7 @@ targets: []
         Pagination::Cursor {
             cursor_param,
             page_size_param,
+            ..
         } => {
             assert_eq!(cursor_param, "cursor");
             assert_eq!(page_size_param, &Some("size".to_string()));
@@ -353,6 +477,41 @@ targets: []
     }
 }
 
+#[test]
+fn test_pagination_next_url() {
+    let config_yaml = r#"
+sources:
+  - name: api1
+    url: https://api.example.com/data
+    pagination:
+      kind: next_url
+      next_path: links.next
+      has_more_path: has_more
+    retry:
+      max_attempts: 3
+      max_delay_secs: 60
+      min_delay_secs: 1
+targets: []
+"#;
+
+    let config: Config = serde_yaml::from_str(config_yaml).unwrap();
+    let source = config.source("api1").unwrap();
+
+    match source.pagination.as_ref().unwrap() {
+        Pagination::NextUrl {
+            next_path,
+            has_more_path,
+            page_size_param,
+            ..
+        } => {
+            assert_eq!(next_path, "links.next");
+            assert_eq!(has_more_path.as_deref(), Some("has_more"));
+            assert_eq!(page_size_param, &None);
+        }
+        _ => panic!("Expected NextUrl pagination"),
+    }
+}
+
 #[test]
 fn test_config_serialization_deserialization() {
     let config_yaml = r#"
@@ -385,3 +544,1337 @@ targets:
     assert_eq!(config.sources.len(), config2.sources.len());
     assert_eq!(config.targets.len(), config2.targets.len());
 }
+
+#[test]
+fn test_graphql_source_defaults() {
+    use apitap::pipeline::SourceKind;
+
+    let config_yaml = r#"
+sources:
+  - name: gh
+    kind: graphql
+    url: https://api.github.com/graphql
+    table_destination_name: repos
+    graphql:
+      query: "query($after: String) { viewer { repositories(first: 50, after: $after) { edges { node { id } } pageInfo { endCursor hasNextPage } } } }"
+      variables:
+        first: 50
+      records_path: data.viewer.repositories.edges.node
+    retry:
+      max_attempts: 3
+      max_delay_secs: 60
+      min_delay_secs: 1
+targets: []
+"#;
+
+    let config: Config = serde_yaml::from_str(config_yaml).unwrap();
+    let source = config.source("gh").unwrap();
+    assert_eq!(source.kind, SourceKind::Graphql);
+
+    let gql = source.graphql.as_ref().unwrap();
+    assert_eq!(gql.records_path, "data.viewer.repositories.edges.node");
+    assert_eq!(gql.end_cursor_path, "data.*.pageInfo.endCursor");
+    assert_eq!(gql.cursor_variable, "after");
+}
+
+#[test]
+fn test_source_kind_defaults_to_http() {
+    use apitap::pipeline::SourceKind;
+
+    let config_yaml = r#"
+sources:
+  - name: api
+    url: https://api.example.com
+    retry:
+      max_attempts: 3
+      max_delay_secs: 60
+      min_delay_secs: 1
+targets: []
+"#;
+
+    let config: Config = serde_yaml::from_str(config_yaml).unwrap();
+    let source = config.source("api").unwrap();
+    assert_eq!(source.kind, SourceKind::Http);
+    assert!(source.graphql.is_none());
+}
+
+#[test]
+fn test_source_xml_format() {
+    use apitap::http::fetcher::ResponseFormat;
+
+    let config_yaml = r#"
+sources:
+  - name: legacy
+    url: https://legacy.example.com/feed
+    format: xml
+    data_path: /feed/entry
+    retry:
+      max_attempts: 3
+      max_delay_secs: 60
+      min_delay_secs: 1
+  - name: api
+    url: https://api.example.com
+    retry:
+      max_attempts: 3
+      max_delay_secs: 60
+      min_delay_secs: 1
+targets: []
+"#;
+
+    let config: Config = serde_yaml::from_str(config_yaml).unwrap();
+    assert_eq!(
+        config.source("legacy").unwrap().format,
+        Some(ResponseFormat::Xml)
+    );
+    assert_eq!(config.source("api").unwrap().format, None);
+    assert_eq!(
+        serde_yaml::from_str::<ResponseFormat>("ndjson_stream").unwrap(),
+        ResponseFormat::NdjsonStream
+    );
+}
+
+#[test]
+fn test_source_body_encoding() {
+    use apitap::http::fetcher::BodyEncoding;
+
+    let config_yaml = r#"
+sources:
+  - name: search
+    url: https://api.example.com/search
+    method: POST
+    body_encoding: gzip
+    body: { status: open }
+    retry:
+      max_attempts: 3
+      max_delay_secs: 60
+      min_delay_secs: 1
+  - name: api
+    url: https://api.example.com
+    retry:
+      max_attempts: 3
+      max_delay_secs: 60
+      min_delay_secs: 1
+targets: []
+"#;
+
+    let config: Config = serde_yaml::from_str(config_yaml).unwrap();
+    assert_eq!(
+        config.source("search").unwrap().body_encoding,
+        BodyEncoding::Gzip
+    );
+    assert_eq!(
+        config.source("api").unwrap().body_encoding,
+        BodyEncoding::Identity
+    );
+}
+
+#[test]
+fn test_source_csv_options() {
+    use apitap::http::fetcher::ResponseFormat;
+
+    let config_yaml = r#"
+sources:
+  - name: export
+    url: https://reports.example.com/export.csv
+    format: csv
+    csv:
+      delimiter: ";"
+    retry:
+      max_attempts: 3
+      max_delay_secs: 60
+      min_delay_secs: 1
+targets: []
+"#;
+
+    let config: Config = serde_yaml::from_str(config_yaml).unwrap();
+    let source = config.source("export").unwrap();
+    assert_eq!(source.format, Some(ResponseFormat::Csv));
+    let csv = source.csv.as_ref().unwrap();
+    assert_eq!(csv.delimiter, Some(';'));
+    assert!(csv.has_header);
+}
+
+#[test]
+fn test_source_fetch_overrides_win_over_defaults() {
+    use apitap::pipeline::run::FetchOpts;
+
+    let config_yaml = r#"
+sources:
+  - name: tuned
+    url: https://api.example.com
+    concurrency: 1
+    preserve_order: true
+    page_size: 500
+    timeout_secs: 300
+    retry:
+      max_attempts: 3
+      max_delay_secs: 60
+      min_delay_secs: 1
+targets: []
+"#;
+
+    let config: Config = serde_yaml::from_str(config_yaml).unwrap();
+    let defaults = FetchOpts {
+        concurrency: 5,
+        preserve_order: false,
+        default_page_size: 50,
+        fetch_batch_size: 256,
+        timeout: None,
+        progress: Default::default(),
+        execution: Default::default(),
+        capture: None,
+        limits: Default::default(),
+        max_records: None,
+    };
+
+    let opts = defaults.for_source(config.source("tuned").unwrap());
+    assert_eq!(opts.concurrency, 1);
+    assert!(opts.preserve_order);
+    assert_eq!(opts.default_page_size, 500);
+    assert_eq!(opts.fetch_batch_size, 256);
+    assert_eq!(opts.timeout, Some(std::time::Duration::from_secs(300)));
+}
+
+#[test]
+fn test_pagination_page_size_clamped_to_max_page_size() {
+    use apitap::pipeline::run::FetchOpts;
+
+    let config_yaml = r#"
+sources:
+  - name: bulk
+    url: https://api.example.com/bulk
+    page_size: 500
+    max_page_size: 1000
+    pagination:
+      kind: limit_offset
+      limit_param: limit
+      offset_param: offset
+      page_size: 5000
+    retry:
+      max_attempts: 3
+      max_delay_secs: 60
+      min_delay_secs: 1
+  - name: small
+    url: https://api.example.com/small
+    max_page_size: 100
+    retry:
+      max_attempts: 3
+      max_delay_secs: 60
+      min_delay_secs: 1
+targets: []
+"#;
+
+    let config: Config = serde_yaml::from_str(config_yaml).unwrap();
+    let bulk = config.source("bulk").unwrap();
+    assert_eq!(bulk.pagination.as_ref().unwrap().page_size(), Some(5000));
+    assert_eq!(bulk.max_page_size, Some(1000));
+
+    let defaults = FetchOpts {
+        concurrency: 5,
+        preserve_order: false,
+        default_page_size: 250,
+        fetch_batch_size: 256,
+        timeout: None,
+        progress: Default::default(),
+        execution: Default::default(),
+        capture: None,
+        limits: Default::default(),
+        max_records: None,
+    };
+    // The pagination block's size wins over the source's, then is clamped
+    assert_eq!(defaults.for_source(bulk).default_page_size, 1000);
+    // `--page-size` is clamped too
+    let small = config.source("small").unwrap();
+    assert_eq!(defaults.for_source(small).default_page_size, 100);
+
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("pipelines.yaml");
+    std::fs::write(
+        &path,
+        config_yaml.replace("page_size: 5000", "page_size: 0"),
+    )
+    .unwrap();
+    let err = apitap::config::load_config_from_path(&path).unwrap_err();
+    assert!(
+        err.to_string()
+            .contains("pagination.page_size must be greater than 0"),
+        "{err}"
+    );
+}
+
+#[test]
+fn test_snowflake_target_parsing() {
+    use apitap::pipeline::SnowflakeTokenType;
+
+    let config_yaml = r#"
+sources: []
+targets:
+  - type: snowflake
+    name: sf_sink
+    account: myorg-acct
+    warehouse: LOAD_WH
+    database: RAW
+    schema: API
+    auth:
+      token: ${SNOWFLAKE_TOKEN}
+"#;
+
+    let config: Config = serde_yaml::from_str(config_yaml).unwrap();
+    match config.target("sf_sink").unwrap() {
+        Target::Snowflake(sf) => {
+            assert_eq!(sf.account, "myorg-acct");
+            assert_eq!(sf.schema, "API");
+            assert!(sf.role.is_none());
+            assert_eq!(
+                sf.auth.token_type,
+                SnowflakeTokenType::ProgrammaticAccessToken
+            );
+            assert_eq!(sf.auth.token_type.as_header(), "PROGRAMMATIC_ACCESS_TOKEN");
+        }
+        other => panic!("expected snowflake target, got {other:?}"),
+    }
+}
+
+#[test]
+fn test_postgres_pool_settings() {
+    let config_yaml = r#"
+sources: []
+targets:
+  - type: postgres
+    name: pg_sink
+    host: localhost
+    database: testdb
+    max_connections: 4
+    acquire_timeout_secs: 5
+    auth:
+      username: testuser
+      password: testpass
+"#;
+
+    let config: Config = serde_yaml::from_str(config_yaml).unwrap();
+    match config.target("pg_sink").unwrap() {
+        Target::Postgres(pg) => {
+            assert_eq!(pg.max_connections, Some(4));
+            assert_eq!(pg.min_connections, None);
+            assert_eq!(pg.acquire_timeout_secs, Some(5));
+            assert_eq!(pg.idle_timeout_secs, None);
+        }
+        other => panic!("expected postgres target, got {other:?}"),
+    }
+}
+
+#[test]
+fn test_postgres_pool_min_above_max_rejected() {
+    let config_yaml = r#"
+sources: []
+targets:
+  - type: postgres
+    name: pg_sink
+    host: localhost
+    database: testdb
+    max_connections: 2
+    min_connections: 5
+    auth:
+      username: testuser
+      password: testpass
+"#;
+
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("pipelines.yaml");
+    std::fs::write(&path, config_yaml).unwrap();
+
+    let err = apitap::config::load_config_from_path(&path).unwrap_err();
+    assert!(err.to_string().contains("min_connections"));
+}
+
+#[test]
+fn test_source_hmac_signing() {
+    use apitap::pipeline::SigningConfig;
+
+    let config_yaml = r#"
+sources:
+  - name: partner
+    url: https://partner.example.com/v1/orders
+    signing:
+      kind: hmac
+      secret: ${PARTNER_SECRET}
+      timestamp_header: X-Timestamp
+    retry:
+      max_attempts: 3
+      max_delay_secs: 60
+      min_delay_secs: 1
+targets: []
+"#;
+
+    let config: Config = serde_yaml::from_str(config_yaml).unwrap();
+    match config.source("partner").unwrap().signing.as_ref().unwrap() {
+        SigningConfig::Hmac {
+            secret,
+            header,
+            timestamp_header,
+            prefix,
+        } => {
+            assert_eq!(secret, "${PARTNER_SECRET}");
+            assert_eq!(header, "X-Signature");
+            assert_eq!(timestamp_header.as_deref(), Some("X-Timestamp"));
+            assert!(prefix.is_empty());
+        }
+    }
+}
+
+#[test]
+fn test_config_lookups() {
+    use apitap::utils::table_provider::LookupFormat;
+
+    let config_yaml = r#"
+sources: []
+targets: []
+lookups:
+  - name: country_codes
+    path: ./lookups/country_codes.csv
+  - name: categories
+    path: ./lookups/categories.data
+    format: json
+"#;
+
+    let config: Config = serde_yaml::from_str(config_yaml).unwrap();
+    assert_eq!(config.lookups.len(), 2);
+    assert_eq!(config.lookups[0].name, "country_codes");
+    assert_eq!(config.lookups[0].format, None);
+    assert_eq!(config.lookups[1].format, Some(LookupFormat::Json));
+
+    let without: Config = serde_yaml::from_str("sources: []\ntargets: []\n").unwrap();
+    assert!(without.lookups.is_empty());
+}
+
+#[test]
+fn test_source_column_types() {
+    let config_yaml = r#"
+sources:
+  - name: orders
+    url: https://api.example.com/orders
+    column_types:
+      amount: NUMERIC(12,2)
+      external_id: UUID
+    retry:
+      max_attempts: 3
+      max_delay_secs: 60
+      min_delay_secs: 1
+targets: []
+"#;
+
+    let config: Config = serde_yaml::from_str(config_yaml).unwrap();
+    let source = config.source("orders").unwrap();
+    assert_eq!(source.column_types["amount"], "NUMERIC(12,2)");
+    assert_eq!(source.column_types["external_id"], "UUID");
+}
+
+#[test]
+fn test_query_param_lists_and_styles() {
+    use apitap::pipeline::{ArrayStyle, QueryValue};
+
+    let config_yaml = r#"
+sources:
+  - name: users
+    url: https://api.example.com/users
+    query_params:
+      - key: status
+        value: active
+      - key: id
+        value: ["1", "2"]
+      - key: tag
+        value: [a, b]
+        style: bracket
+      - key: fields
+        value: [id, name]
+        style: comma
+    retry:
+      max_attempts: 3
+      max_delay_secs: 60
+      min_delay_secs: 1
+targets: []
+"#;
+
+    let config: Config = serde_yaml::from_str(config_yaml).unwrap();
+    let params = config
+        .source("users")
+        .unwrap()
+        .query_params
+        .clone()
+        .unwrap();
+    assert_eq!(params[0].value, QueryValue::One("active".to_string()));
+    assert_eq!(params[1].style, ArrayStyle::Repeat);
+
+    let pairs: Vec<(String, String)> = params
+        .iter()
+        .flat_map(|p| p.to_pairs(|v| Ok(v.to_uppercase())).unwrap())
+        .collect();
+    let pairs: Vec<(&str, &str)> = pairs
+        .iter()
+        .map(|(k, v)| (k.as_str(), v.as_str()))
+        .collect();
+    assert_eq!(
+        pairs,
+        vec![
+            ("status", "ACTIVE"),
+            ("id", "1"),
+            ("id", "2"),
+            ("tag[]", "A"),
+            ("tag[]", "B"),
+            ("fields", "ID,NAME"),
+        ]
+    );
+}
+
+#[test]
+fn test_source_overlap_policy() {
+    use apitap::config::schedule::OverlapPolicy;
+
+    let config_yaml = r#"
+sources:
+  - name: slow
+    url: https://api.example.com/slow
+    overlap: queue
+    retry:
+      max_attempts: 3
+      max_delay_secs: 60
+      min_delay_secs: 1
+  - name: fast
+    url: https://api.example.com/fast
+    retry:
+      max_attempts: 3
+      max_delay_secs: 60
+      min_delay_secs: 1
+targets: []
+"#;
+
+    let config: Config = serde_yaml::from_str(config_yaml).unwrap();
+    assert_eq!(config.source("slow").unwrap().overlap, OverlapPolicy::Queue);
+    assert_eq!(config.source("fast").unwrap().overlap, OverlapPolicy::Skip);
+}
+
+#[test]
+fn test_config_redact_block() {
+    let config_yaml = r#"
+sources: []
+targets: []
+redact:
+  query_params: [sig]
+"#;
+
+    let config: Config = serde_yaml::from_str(config_yaml).unwrap();
+    assert_eq!(config.redact.query_params, vec!["sig".to_string()]);
+    assert!(config.redact.headers.is_empty());
+
+    let config: Config = serde_yaml::from_str("sources: []\ntargets: []\n").unwrap();
+    assert_eq!(config.redact, Default::default());
+}
+
+#[test]
+fn test_source_query_auth() {
+    use apitap::pipeline::SourceAuth;
+
+    let config_yaml = r#"
+sources:
+  - name: legacy
+    url: https://legacy.example.com/items
+    auth:
+      type: query
+      param: apiKey
+      value: ${LEGACY_API_KEY}
+    retry:
+      max_attempts: 3
+      max_delay_secs: 60
+      min_delay_secs: 1
+targets: []
+redact:
+  query_params: [sig]
+"#;
+
+    let config: Config = serde_yaml::from_str(config_yaml).unwrap();
+    let Some(SourceAuth::Query { param, value }) = &config.source("legacy").unwrap().auth else {
+        panic!("expected query auth");
+    };
+    assert_eq!(param, "apiKey");
+    assert_eq!(value, "${LEGACY_API_KEY}");
+    // The auth param joins the redacted query params
+    assert_eq!(
+        config.redact_config().query_params,
+        vec!["sig".to_string(), "apiKey".to_string()]
+    );
+}
+
+#[test]
+fn test_source_oauth2_auth() {
+    use apitap::pipeline::SourceAuth;
+
+    let config_yaml = r#"
+sources:
+  - name: orders
+    url: https://api.example.com/orders
+    auth:
+      type: oauth2
+      token_url: https://idp.example.com/oauth/token
+      client_id: etl
+      client_secret: ${ETL_CLIENT_SECRET}
+      scope: orders:read
+targets: []
+"#;
+
+    let config: Config = serde_yaml::from_str(config_yaml).unwrap();
+    let Some(SourceAuth::Oauth2 {
+        token_url,
+        client_id,
+        client_secret,
+        scope,
+    }) = &config.source("orders").unwrap().auth
+    else {
+        panic!("expected oauth2 auth");
+    };
+    assert_eq!(token_url, "https://idp.example.com/oauth/token");
+    assert_eq!(client_id, "etl");
+    assert_eq!(client_secret, "${ETL_CLIENT_SECRET}");
+    assert_eq!(scope.as_deref(), Some("orders:read"));
+}
+
+#[test]
+fn test_default_headers_merge_under_source_headers() {
+    let config_yaml = r#"
+defaults:
+  headers:
+    - key: User-Agent
+      value: apitap-fleet/1.0
+    - key: Accept
+      value: application/json
+sources:
+  - name: custom
+    url: https://api.example.com/a
+    headers:
+      - key: user-agent
+        value: partner-bot/2.0
+      - key: X-Tenant
+        value: acme
+    retry:
+      max_attempts: 3
+      max_delay_secs: 60
+      min_delay_secs: 1
+  - name: plain
+    url: https://api.example.com/b
+    retry:
+      max_attempts: 3
+      max_delay_secs: 60
+      min_delay_secs: 1
+targets: []
+"#;
+
+    let config: Config = serde_yaml::from_str(config_yaml).unwrap();
+    let pairs = |name: &str| -> Vec<(String, String)> {
+        config
+            .headers_for(config.source(name).unwrap())
+            .into_iter()
+            .map(|h| (h.key, h.value))
+            .collect()
+    };
+
+    assert_eq!(
+        pairs("custom"),
+        vec![
+            ("Accept".to_string(), "application/json".to_string()),
+            ("user-agent".to_string(), "partner-bot/2.0".to_string()),
+            ("X-Tenant".to_string(), "acme".to_string()),
+        ]
+    );
+    assert_eq!(
+        pairs("plain"),
+        vec![
+            ("User-Agent".to_string(), "apitap-fleet/1.0".to_string()),
+            ("Accept".to_string(), "application/json".to_string()),
+        ]
+    );
+}
+
+#[test]
+fn test_source_rename_and_drop() {
+    let config_yaml = r#"
+sources:
+  - name: users
+    url: https://api.example.com/users
+    rename:
+      usr_nm: user_name
+      /meta/created: created_at
+    drop: [internal_flags]
+    retry:
+      max_attempts: 3
+      max_delay_secs: 60
+      min_delay_secs: 1
+targets: []
+"#;
+
+    let config: Config = serde_yaml::from_str(config_yaml).unwrap();
+    let fields = &config.source("users").unwrap().fields;
+    assert_eq!(fields.rename["usr_nm"], "user_name");
+    assert_eq!(fields.rename["/meta/created"], "created_at");
+    assert_eq!(fields.drop, vec!["internal_flags".to_string()]);
+}
+
+#[test]
+fn test_source_resume_and_state_dir() {
+    let config_yaml = r#"
+state_dir: /var/lib/apitap
+sources:
+  - name: events
+    url: https://api.example.com/events
+    pagination:
+      kind: page_number
+      page_param: page
+      per_page_param: per_page
+    resume:
+      window_secs: 600
+    retry:
+      max_attempts: 3
+      max_delay_secs: 60
+      min_delay_secs: 1
+  - name: users
+    url: https://api.example.com/users
+    resume: {}
+    retry:
+      max_attempts: 3
+      max_delay_secs: 60
+      min_delay_secs: 1
+targets: []
+"#;
+
+    let config: Config = serde_yaml::from_str(config_yaml).unwrap();
+    assert_eq!(config.state_dir.as_deref(), Some("/var/lib/apitap"));
+    assert_eq!(
+        config
+            .source("events")
+            .unwrap()
+            .resume
+            .as_ref()
+            .unwrap()
+            .window_secs,
+        600
+    );
+    assert_eq!(
+        config
+            .source("users")
+            .unwrap()
+            .resume
+            .as_ref()
+            .unwrap()
+            .window_secs,
+        86400
+    );
+}
+
+#[test]
+fn test_target_table_policy() {
+    use apitap::pipeline::TablePolicy;
+
+    let config_yaml = r#"
+sources: []
+targets:
+  - type: postgres
+    name: managed
+    host: localhost
+    database: app
+    auth: { username: u, password: p }
+    auto_create: false
+  - type: postgres
+    name: scratch
+    host: localhost
+    database: app
+    auth: { username: u, password: p }
+"#;
+
+    let config: Config = serde_yaml::from_str(config_yaml).unwrap();
+    let managed = config.target("managed").unwrap().tables();
+    assert!(!managed.auto_create());
+    assert!(!managed.auto_truncate());
+
+    let scratch = config.target("scratch").unwrap().tables();
+    assert!(scratch.auto_create());
+
+    // A module's sink() kwargs win over the target
+    let module = TablePolicy {
+        auto_create: Some(true),
+        auto_truncate: Some(true),
+        truncate_mode: None,
+        swap: None,
+    };
+    let resolved = module.or(managed);
+    assert!(resolved.auto_create());
+    assert!(resolved.auto_truncate());
+    assert_eq!(TablePolicy::default().or(managed), managed);
+}
+
+#[test]
+fn test_target_truncate_mode() {
+    use apitap::pipeline::TablePolicy;
+    use apitap::writer::TruncateMode;
+
+    let config_yaml = r#"
+sources: []
+targets:
+  - type: postgres
+    name: warehouse
+    host: localhost
+    database: app
+    auth: { username: u, password: p }
+    auto_truncate: true
+    truncate_mode: truncate_cascade
+"#;
+
+    let config: Config = serde_yaml::from_str(config_yaml).unwrap();
+    let target = config.target("warehouse").unwrap().tables();
+    assert_eq!(target.truncate_mode(), TruncateMode::TruncateCascade);
+    assert_eq!(
+        TablePolicy::default().truncate_mode(),
+        TruncateMode::Truncate
+    );
+
+    let module = TablePolicy {
+        truncate_mode: Some(TruncateMode::Delete),
+        ..TablePolicy::default()
+    };
+    assert_eq!(module.or(target).truncate_mode(), TruncateMode::Delete);
+
+    assert_eq!(
+        " DELETE ".parse::<TruncateMode>().unwrap(),
+        TruncateMode::Delete
+    );
+    let err = "drop".parse::<TruncateMode>().unwrap_err();
+    assert!(err.to_string().contains("unknown truncate_mode 'drop'"));
+}
+
+#[test]
+fn test_record_key_column_requires_object_values() {
+    let config_yaml = r#"
+sources:
+  - name: vendor
+    url: https://vendor.example.com/accounts
+    data_path: /items
+    record_key_column: _id
+    retry:
+      max_attempts: 3
+      max_delay_secs: 60
+      min_delay_secs: 1
+targets: []
+"#;
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("pipelines.yaml");
+    std::fs::write(&path, config_yaml).unwrap();
+
+    let err = apitap::config::load_config_from_path(&path).unwrap_err();
+    assert!(err.to_string().contains("records_as: object_values"));
+
+    std::fs::write(
+        &path,
+        config_yaml.replace(
+            "    record_key_column",
+            "    records_as: object_values\n    record_key_column",
+        ),
+    )
+    .unwrap();
+    let config = apitap::config::load_config_from_path(&path).unwrap();
+    assert_eq!(
+        config
+            .source("vendor")
+            .unwrap()
+            .record_key_column
+            .as_deref(),
+        Some("_id")
+    );
+}
+
+#[test]
+fn test_postgres_target_write_retry() {
+    let config_yaml = r#"
+sources: []
+targets:
+  - type: postgres
+    name: warehouse
+    host: localhost
+    database: app
+    auth: { username: u, password: p }
+    write_retry:
+      max_attempts: 3
+      min_delay_secs: 1
+      max_delay_secs: 10
+  - type: postgres
+    name: scratch
+    host: localhost
+    database: app
+    auth: { username: u, password: p }
+"#;
+
+    let config: Config = serde_yaml::from_str(config_yaml).unwrap();
+    let Target::Postgres(warehouse) = config.target("warehouse").unwrap() else {
+        panic!("expected a postgres target");
+    };
+    let retry = warehouse.write_retry.as_ref().unwrap();
+    assert_eq!(retry.max_attempts, 3);
+    assert_eq!(retry.max_delay_secs, 10);
+
+    let Target::Postgres(scratch) = config.target("scratch").unwrap() else {
+        panic!("expected a postgres target");
+    };
+    assert!(scratch.write_retry.is_none());
+}
+
+#[test]
+fn test_source_http_pool() {
+    use apitap::http::HttpPool;
+
+    let config_yaml = r#"
+sources:
+  - name: events
+    url: https://api.example.com/events
+    table_destination_name: events
+    pool_max_idle_per_host: 32
+    pool_idle_timeout_secs: 30
+    tcp_keepalive_secs: 0
+  - name: plain
+    url: https://api.example.com/plain
+    table_destination_name: plain
+targets: []
+"#;
+
+    let config: Config = serde_yaml::from_str(config_yaml).unwrap();
+    assert_eq!(
+        config.source("events").unwrap().pool,
+        HttpPool {
+            pool_max_idle_per_host: Some(32),
+            pool_idle_timeout_secs: Some(30),
+            tcp_keepalive_secs: Some(0),
+        }
+    );
+    assert_eq!(config.source("plain").unwrap().pool, HttpPool::default());
+}
+
+#[test]
+fn test_postgres_target_post_checks() {
+    use apitap::writer::post_check::Comparison;
+
+    let config_yaml = r#"
+sources: []
+targets:
+  - type: postgres
+    name: warehouse
+    host: localhost
+    database: app
+    auth: { username: u, password: p }
+    post_checks:
+      - sql: SELECT count(*) FROM {table}
+        expect: "> 0"
+      - sql: SELECT id FROM {table} WHERE id IS NULL
+"#;
+
+    let config: Config = serde_yaml::from_str(config_yaml).unwrap();
+    let target = config.target("warehouse").unwrap();
+    let checks = target.post_checks();
+    assert_eq!(checks.len(), 2);
+    let expect = checks[0].expect.unwrap();
+    assert_eq!((expect.op, expect.value), (Comparison::Gt, 0.0));
+    assert_eq!(checks[1].expect, None);
+
+    let bad = config_yaml.replace("\"> 0\"", "\"lots\"");
+    let err = serde_yaml::from_str::<Config>(&bad).unwrap_err();
+    assert!(err.to_string().contains("unknown post_check expect 'lots'"));
+}
+
+#[test]
+fn test_postgres_target_dead_letter() {
+    use apitap::writer::dead_letter::DeadLetter;
+
+    let config_yaml = r#"
+sources: []
+targets:
+  - type: postgres
+    name: warehouse
+    host: localhost
+    database: app
+    auth: { username: u, password: p }
+    dead_letter: { table: analytics.dead_letters }
+  - type: postgres
+    name: scratch
+    host: localhost
+    database: app
+    auth: { username: u, password: p }
+    dead_letter: { path: /var/lib/apitap/dead_letters.ndjson }
+"#;
+
+    let config: Config = serde_yaml::from_str(config_yaml).unwrap();
+    let Target::Postgres(warehouse) = config.target("warehouse").unwrap() else {
+        panic!("expected a postgres target");
+    };
+    assert_eq!(
+        warehouse.dead_letter,
+        Some(DeadLetter::Table {
+            table: "analytics.dead_letters".into()
+        })
+    );
+    let Target::Postgres(scratch) = config.target("scratch").unwrap() else {
+        panic!("expected a postgres target");
+    };
+    assert_eq!(
+        scratch.dead_letter,
+        Some(DeadLetter::File {
+            path: "/var/lib/apitap/dead_letters.ndjson".into()
+        })
+    );
+
+    assert!(serde_yaml::from_str::<Config>(
+        &config_yaml.replace("{ table: analytics.dead_letters }", "{ topic: dlq }")
+    )
+    .is_err());
+}
+
+#[test]
+fn test_conditional_rejected_for_graphql() {
+    let config_yaml = r#"
+sources:
+  - name: gh
+    kind: graphql
+    url: https://api.github.com/graphql
+    conditional: true
+    graphql:
+      query: "query { viewer { login } }"
+      records_path: data.viewer
+    retry:
+      max_attempts: 3
+      max_delay_secs: 60
+      min_delay_secs: 1
+targets: []
+"#;
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("pipelines.yaml");
+    std::fs::write(&path, config_yaml).unwrap();
+
+    let err = apitap::config::load_config_from_path(&path).unwrap_err();
+    assert!(err
+        .to_string()
+        .contains("conditional is not supported for graphql sources"));
+}
+
+#[test]
+fn test_source_log_level() {
+    use apitap::log::LogLevel;
+    use tracing::level_filters::LevelFilter;
+
+    let yaml = "sources:\n  - name: feed\n    url: https://api.example.com/feed\n    log_level: debug\n    retry: {max_attempts: 1, max_delay_secs: 1, min_delay_secs: 1}\ntargets: []\n";
+    let config: Config = serde_yaml::from_str(yaml).unwrap();
+    assert_eq!(
+        config.source("feed").unwrap().log_level,
+        Some(LogLevel(LevelFilter::DEBUG))
+    );
+
+    let err = serde_yaml::from_str::<Config>(&yaml.replace("debug", "chatty")).unwrap_err();
+    assert!(
+        err.to_string().contains("unknown log level 'chatty'"),
+        "{err}"
+    );
+}
+
+#[test]
+fn test_source_splits() {
+    use apitap::pipeline::split::SourceSplit;
+
+    let base = r#"
+sources:
+  - name: directory
+    url: https://api.example.com/directory
+    data_path: /users
+    splits:
+      - data_path: /accounts
+        dest_table: accounts
+        primary_key: account_id
+      - data_path: /tags
+        dest_table: tags
+    retry:
+      max_attempts: 3
+      max_delay_secs: 60
+      min_delay_secs: 1
+targets: []
+"#;
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("pipelines.yaml");
+    std::fs::write(&path, base).unwrap();
+    let config = apitap::config::load_config_from_path(&path).unwrap();
+    assert_eq!(
+        config.source("directory").unwrap().splits,
+        vec![
+            SourceSplit {
+                data_path: "/accounts".into(),
+                dest_table: "accounts".into(),
+                primary_key: Some("account_id".into()),
+            },
+            SourceSplit {
+                data_path: "/tags".into(),
+                dest_table: "tags".into(),
+                primary_key: None,
+            },
+        ]
+    );
+
+    let invalid = [
+        (base.replace("- data_path: /tags", "- data_path: tags"), "each split needs a data_path"),
+        (
+            base.replace("    data_path: /users\n", "    kind: graphql\n    graphql:\n      query: \"{ users }\"\n      records_path: data.users\n"),
+            "splits are not supported for graphql sources",
+        ),
+    ];
+    for (yaml, message) in invalid {
+        std::fs::write(&path, yaml).unwrap();
+        let err = apitap::config::load_config_from_path(&path).unwrap_err();
+        assert!(err.to_string().contains(message), "{err}");
+    }
+}
+
+#[test]
+fn test_source_stop_when() {
+    use apitap::http::stop::StopWhen;
+
+    let base = r#"
+sources:
+  - name: events
+    url: https://api.example.com/events
+    pagination:
+      kind: limit_offset
+      limit_param: limit
+      offset_param: offset
+    stop_when: { path: meta.has_more, equals: false }
+    retry:
+      max_attempts: 3
+      max_delay_secs: 60
+      min_delay_secs: 1
+targets: []
+"#;
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("pipelines.yaml");
+    std::fs::write(&path, base).unwrap();
+    let config = apitap::config::load_config_from_path(&path).unwrap();
+    assert_eq!(
+        config.source("events").unwrap().stop_when,
+        Some(StopWhen::Equals {
+            path: "meta.has_more".into(),
+            equals: serde_json::Value::Bool(false),
+        })
+    );
+
+    let unpaginated = base.replace(
+        "    pagination:\n      kind: limit_offset\n      limit_param: limit\n      offset_param: offset\n",
+        "",
+    );
+    std::fs::write(&path, unpaginated).unwrap();
+    let err = apitap::config::load_config_from_path(&path).unwrap_err();
+    assert!(
+        err.to_string()
+            .contains("stop_when needs an http source with pagination"),
+        "{err}"
+    );
+}
+
+#[test]
+fn test_execution_block() {
+    use apitap::utils::execution::{ByteSize, ExecutionOpts};
+
+    let config: Config = serde_yaml::from_str(
+        "sources: []\ntargets: []\nexecution:\n  batch_size: 1024\n  target_partitions: 4\n",
+    )
+    .unwrap();
+    assert_eq!(
+        config.execution,
+        ExecutionOpts {
+            batch_size: 1024,
+            target_partitions: 4,
+            ..ExecutionOpts::default()
+        }
+    );
+    assert_eq!(ExecutionOpts::default().target_partitions, 1);
+
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("pipelines.yaml");
+    std::fs::write(
+        &path,
+        "sources: []\ntargets: []\nexecution:\n  target_partitions: 0\n",
+    )
+    .unwrap();
+    let err = apitap::config::load_config_from_path(&path).unwrap_err();
+    assert!(err
+        .to_string()
+        .contains("execution: target_partitions must be greater than 0"));
+
+    let config: Config = serde_yaml::from_str(
+        "sources: []\ntargets: []\nexecution:\n  memory_limit: 512MB\n  spill_dir: /scratch\n",
+    )
+    .unwrap();
+    assert_eq!(config.execution.memory_limit, ByteSize(512 << 20));
+    assert_eq!(config.execution.memory_limit.to_string(), "512MB");
+    assert!(config.execution.spill);
+    assert!(serde_yaml::from_str::<Config>(
+        "sources: []\ntargets: []\nexecution:\n  memory_limit: 12 parsecs\n"
+    )
+    .is_err());
+
+    std::fs::write(
+        &path,
+        "sources: []\ntargets: []\nexecution:\n  spill: false\n  spill_dir: /scratch\n",
+    )
+    .unwrap();
+    let err = apitap::config::load_config_from_path(&path).unwrap_err();
+    assert!(err
+        .to_string()
+        .contains("execution: spill_dir is set but spill is false"));
+}
+
+#[test]
+fn test_file_source_config() {
+    use apitap::pipeline::SourceKind;
+
+    let config_yaml = r#"
+sources:
+  - name: fixture
+    kind: file
+    path: ./fixtures/*.json
+    data_path: /data
+targets: []
+"#;
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("pipelines.yaml");
+    std::fs::write(&path, config_yaml).unwrap();
+
+    let config = apitap::config::load_config_from_path(&path).unwrap();
+    let source = config.source("fixture").unwrap();
+    assert_eq!(source.kind, SourceKind::File);
+    assert_eq!(source.path.as_deref(), Some("./fixtures/*.json"));
+    assert!(source.url.is_empty());
+    assert_eq!(source.retry.max_attempts, 3);
+
+    for (yaml, expected) in [
+        (
+            config_yaml.replace("    path: ./fixtures/*.json\n", ""),
+            "kind file requires a path",
+        ),
+        (
+            config_yaml.replace("    data_path", "    conditional: true\n    data_path"),
+            "conditional is not supported for file sources",
+        ),
+        (
+            config_yaml.replace("    kind: file\n", ""),
+            "url is required",
+        ),
+    ] {
+        std::fs::write(&path, yaml).unwrap();
+        let err = apitap::config::load_config_from_path(&path).unwrap_err();
+        assert!(err.to_string().contains(expected), "{err}");
+    }
+}
+
+#[test]
+fn test_limits_block() {
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("pipelines.yaml");
+    let yaml = |limits: &str| {
+        format!(
+            "sources: []\ntargets: []\nlimits:\n  max_concurrent_modules: {limits}\n  max_concurrent_requests: 16\n"
+        )
+    };
+
+    std::fs::write(&path, yaml("4")).unwrap();
+    let config = apitap::config::load_config_from_path(&path).unwrap();
+    assert_eq!(config.limits.max_concurrent_modules, Some(4));
+    assert_eq!(config.limits.max_concurrent_requests, Some(16));
+
+    std::fs::write(&path, yaml("0")).unwrap();
+    let err = apitap::config::load_config_from_path(&path).unwrap_err();
+    assert!(err
+        .to_string()
+        .contains("limits: max_concurrent_modules must be greater than 0"));
+}
+
+#[test]
+fn test_source_write_batch_size() {
+    let source: apitap::pipeline::Source = serde_yaml::from_str(
+        "name: events\nurl: https://api.example.com/events\nwrite_batch_size: 5000\n",
+    )
+    .unwrap();
+    assert_eq!(source.write_batch_size, Some(5000));
+
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("pipelines.yaml");
+    std::fs::write(
+        &path,
+        "sources:\n  - name: events\n    url: https://api.example.com/events\n    write_batch_size: 0\ntargets: []\n",
+    )
+    .unwrap();
+    let err = apitap::config::load_config_from_path(&path).unwrap_err();
+    assert!(err
+        .to_string()
+        .contains("source 'events': write_batch_size must be greater than 0"));
+}
+
+#[test]
+fn test_source_preflight() {
+    let base = r#"
+sources:
+  - name: export
+    url: https://reports.example.com/export
+    preflight: true
+    retry:
+      max_attempts: 3
+      max_delay_secs: 60
+      min_delay_secs: 1
+targets: []
+"#;
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("pipelines.yaml");
+    std::fs::write(&path, base).unwrap();
+    let config = apitap::config::load_config_from_path(&path).unwrap();
+    let source = config.source("export").unwrap();
+    assert!(source.preflight);
+    assert_eq!(source.format, None);
+
+    let file = base.replace(
+        "    url: https://reports.example.com/export\n",
+        "    kind: file\n    path: ./exports/*.csv\n",
+    );
+    std::fs::write(&path, file).unwrap();
+    let err = apitap::config::load_config_from_path(&path).unwrap_err();
+    assert!(
+        err.to_string().contains("preflight needs an http source"),
+        "{err}"
+    );
+}
+
+#[test]
+fn test_source_accept() {
+    let base = r#"
+sources:
+  - name: legacy
+    url: https://legacy.example.com/items
+    accept: application/xml
+    retry:
+      max_attempts: 3
+      max_delay_secs: 60
+      min_delay_secs: 1
+targets: []
+"#;
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("pipelines.yaml");
+    std::fs::write(&path, base).unwrap();
+    let config = apitap::config::load_config_from_path(&path).unwrap();
+    assert_eq!(
+        config.source("legacy").unwrap().accept.as_deref(),
+        Some("application/xml")
+    );
+
+    std::fs::write(&path, base.replace("application/xml", "\"\"")).unwrap();
+    let err = apitap::config::load_config_from_path(&path).unwrap_err();
+    assert!(
+        err.to_string()
+            .contains("accept is not a valid header value"),
+        "{err}"
+    );
+}
@@ -0,0 +1,112 @@
+use apitap::errors::Result;
+use apitap::pipeline::empty::{EmptyGuardWriter, OnEmpty};
+use apitap::pipeline::sink::{Hook, HookFuture};
+use apitap::utils::datafusion_ext::{QueryResult, QueryResultStream};
+use apitap::writer::{DataWriter, WriteMode};
+use futures::{FutureExt, StreamExt};
+use serde_json::json;
+use std::sync::{Arc, Mutex};
+
+#[derive(Default)]
+struct Recorder {
+    calls: Arc<Mutex<Vec<String>>>,
+}
+
+#[async_trait::async_trait]
+impl DataWriter for Recorder {
+    async fn write(&self, result: QueryResult) -> Result<()> {
+        self.calls
+            .lock()
+            .unwrap()
+            .push(format!("write {}", result.row_count));
+        Ok(())
+    }
+
+    async fn write_stream(&self, result: QueryResultStream, _mode: WriteMode) -> Result<()> {
+        let rows = result.data.count().await;
+        self.calls.lock().unwrap().push(format!("stream {rows}"));
+        Ok(())
+    }
+
+    async fn finish(&self) -> Result<()> {
+        self.calls.lock().unwrap().push("finish".to_string());
+        Ok(())
+    }
+}
+
+fn truncate_hook(calls: &Arc<Mutex<Vec<String>>>) -> Hook {
+    let calls = Arc::clone(calls);
+    Box::new(move || {
+        (async move {
+            calls.lock().unwrap().push("truncate".to_string());
+            Ok(())
+        })
+        .boxed() as HookFuture
+    })
+}
+
+fn stream(n: usize) -> QueryResultStream {
+    QueryResultStream {
+        table_name: "orders".into(),
+        data: futures::stream::iter((0..n).map(|i| Ok(json!({ "id": i })))).boxed(),
+        schema: None,
+    }
+}
+
+#[tokio::test]
+async fn test_guard_skips_empty_results_without_truncating() {
+    let recorder = Recorder::default();
+    let calls = Arc::clone(&recorder.calls);
+    let guard = EmptyGuardWriter::new(Arc::new(recorder), Some(truncate_hook(&calls)));
+
+    guard
+        .write_stream(stream(0), WriteMode::Merge)
+        .await
+        .unwrap();
+    guard
+        .write(QueryResult {
+            table_name: "orders".into(),
+            data: json!([]),
+            row_count: 0,
+        })
+        .await
+        .unwrap();
+
+    // Nor is a staging table swapped in
+    guard.finish().await.unwrap();
+
+    assert!(!guard.wrote_rows());
+    assert!(calls.lock().unwrap().is_empty());
+}
+
+#[tokio::test]
+async fn test_guard_truncates_once_before_first_rows() {
+    let recorder = Recorder::default();
+    let calls = Arc::clone(&recorder.calls);
+    let guard = EmptyGuardWriter::new(Arc::new(recorder), Some(truncate_hook(&calls)));
+
+    guard
+        .write_stream(stream(3), WriteMode::Merge)
+        .await
+        .unwrap();
+    guard
+        .write_stream(stream(2), WriteMode::Merge)
+        .await
+        .unwrap();
+    guard.finish().await.unwrap();
+
+    assert!(guard.wrote_rows());
+    assert_eq!(
+        *calls.lock().unwrap(),
+        vec!["truncate", "stream 3", "stream 2", "finish"]
+    );
+}
+
+#[test]
+fn test_on_empty_parse() {
+    assert_eq!("skip".parse::<OnEmpty>().unwrap(), OnEmpty::Skip);
+    assert_eq!("Fail".parse::<OnEmpty>().unwrap(), OnEmpty::Fail);
+    assert_eq!(OnEmpty::default(), OnEmpty::Proceed);
+    let err = "ignore".parse::<OnEmpty>().unwrap_err();
+    assert!(err.to_string().contains("unknown on_empty policy 'ignore'"));
+}
@@ -0,0 +1,89 @@
+use apitap::http::fetcher::{RecordsAs, RequestTemplate};
+use apitap::pipeline::file::{expand_glob, records_stream};
+use futures::StreamExt;
+use serde_json::json;
+use std::fs;
+
+#[test]
+fn test_expand_glob_single_file_and_wildcards() {
+    let dir = tempfile::tempdir().unwrap();
+    fs::write(dir.path().join("a1.json"), "[]").unwrap();
+    fs::write(dir.path().join("a2.json"), "[]").unwrap();
+    fs::write(dir.path().join("b10.json"), "[]").unwrap();
+    let base = dir.path().display().to_string();
+
+    let single = expand_glob(&format!("{base}/a1.json")).unwrap();
+    assert_eq!(single, vec![dir.path().join("a1.json")]);
+
+    let one_char = expand_glob(&format!("{base}/?1.json")).unwrap();
+    assert_eq!(one_char, vec![dir.path().join("a1.json")]);
+
+    let all = expand_glob(&format!("{base}/*.json")).unwrap();
+    assert_eq!(all.len(), 3);
+
+    let err = expand_glob(&format!("{base}/*.ndjson")).unwrap_err();
+    assert!(err.to_string().contains("no files match"));
+    let err = expand_glob(&format!("{base}/missing.json")).unwrap_err();
+    assert!(err.to_string().contains("is not a file"));
+}
+
+#[tokio::test]
+async fn test_records_stream_reads_json_and_ndjson_files() {
+    let dir = tempfile::tempdir().unwrap();
+    fs::write(
+        dir.path().join("a.json"),
+        r#"{"data": [{"id": 1}, {"id": 2}]}"#,
+    )
+    .unwrap();
+    fs::write(
+        dir.path().join("b.ndjson"),
+        "{\"data\": {\"id\": 3}}\n\n{\"id\": 4}\n",
+    )
+    .unwrap();
+
+    let paths = expand_glob(&format!("{}/*", dir.path().display())).unwrap();
+    let bytes = std::sync::Arc::default();
+    let records: Vec<_> = records_stream(
+        paths,
+        Some("/data".to_string()),
+        RequestTemplate::default(),
+        std::sync::Arc::clone(&bytes),
+    )
+    .map(|r| r.unwrap())
+    .collect()
+    .await;
+
+    assert_eq!(
+        records,
+        vec![
+            json!({"id": 1}),
+            json!({"id": 2}),
+            json!({"id": 3}),
+            json!({"id": 4})
+        ]
+    );
+    assert!(bytes.load(std::sync::atomic::Ordering::Relaxed) > 0);
+}
+
+#[tokio::test]
+async fn test_records_stream_honours_records_as() {
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("accounts.json");
+    fs::write(&path, r#"{"items": {"a": {"n": 1}}}"#).unwrap();
+
+    let template = RequestTemplate {
+        records_as: RecordsAs::ObjectValues,
+        record_key_column: Some("_id".into()),
+        ..RequestTemplate::default()
+    };
+    let records: Vec<_> = records_stream(
+        vec![path],
+        Some("/items".to_string()),
+        template,
+        Default::default(),
+    )
+    .map(|r| r.unwrap())
+    .collect()
+    .await;
+    assert_eq!(records, vec![json!({"_id": "a", "n": 1})]);
+}
@@ -0,0 +1,397 @@
+use apitap::cmd::{run_modules_once, RunOptions};
+use apitap::errors::Result;
+use apitap::pipeline::grpc::DynamicCodec;
+use apitap::pipeline::sink::{Hook, WriterOpts};
+use apitap::pipeline::{Config, CustomSink};
+use apitap::utils::datafusion_ext::{QueryResult, QueryResultStream};
+use apitap::writer::factory::{register_writer_factory, WriterFactory};
+use apitap::writer::{DataWriter, WriteMode};
+use futures::stream::BoxStream;
+use futures::StreamExt;
+use prost_reflect::prost::Message;
+use prost_reflect::prost_types::{
+    field_descriptor_proto::{Label, Type},
+    DescriptorProto, FieldDescriptorProto, FileDescriptorProto, FileDescriptorSet,
+    MethodDescriptorProto, ServiceDescriptorProto,
+};
+use prost_reflect::{DescriptorPool, DynamicMessage};
+use serde_json::{json, Value};
+use std::convert::Infallible;
+use std::fs;
+use std::sync::{Arc, Mutex};
+use tempfile::TempDir;
+use tonic::body::BoxBody;
+use tonic::codegen::{http, BoxFuture, Context, Poll, Service};
+use tonic::server::{Grpc, NamedService, ServerStreamingService, UnaryService};
+use tonic::Status;
+
+fn field(name: &str, number: i32, kind: Type, type_name: Option<&str>) -> FieldDescriptorProto {
+    FieldDescriptorProto {
+        name: Some(name.to_string()),
+        number: Some(number),
+        label: Some(Label::Optional as i32),
+        r#type: Some(kind as i32),
+        type_name: type_name.map(str::to_string),
+        ..Default::default()
+    }
+}
+
+fn message(name: &str, fields: Vec<FieldDescriptorProto>) -> DescriptorProto {
+    DescriptorProto {
+        name: Some(name.to_string()),
+        field: fields,
+        ..Default::default()
+    }
+}
+
+fn method(name: &str, input: &str, output: &str, streaming: bool) -> MethodDescriptorProto {
+    MethodDescriptorProto {
+        name: Some(name.to_string()),
+        input_type: Some(input.to_string()),
+        output_type: Some(output.to_string()),
+        server_streaming: Some(streaming),
+        ..Default::default()
+    }
+}
+
+/// `test.Users` with a streaming `List` and a paged unary `Page`.
+fn descriptor_set() -> FileDescriptorSet {
+    let mut users = field("users", 1, Type::Message, Some(".test.User"));
+    users.label = Some(Label::Repeated as i32);
+    FileDescriptorSet {
+        file: vec![FileDescriptorProto {
+            name: Some("test/users.proto".to_string()),
+            package: Some("test".to_string()),
+            syntax: Some("proto3".to_string()),
+            message_type: vec![
+                message(
+                    "ListRequest",
+                    vec![
+                        field("page_size", 1, Type::Int32, None),
+                        field("page_token", 2, Type::String, None),
+                    ],
+                ),
+                message(
+                    "User",
+                    vec![
+                        field("user_id", 1, Type::Int64, None),
+                        field("display_name", 2, Type::String, None),
+                    ],
+                ),
+                message(
+                    "ListResponse",
+                    vec![users, field("next_page_token", 2, Type::String, None)],
+                ),
+            ],
+            service: vec![ServiceDescriptorProto {
+                name: Some("Users".to_string()),
+                method: vec![
+                    method("List", ".test.ListRequest", ".test.User", true),
+                    method("Page", ".test.ListRequest", ".test.ListResponse", false),
+                ],
+                ..Default::default()
+            }],
+            ..Default::default()
+        }],
+    }
+}
+
+fn pool() -> DescriptorPool {
+    DescriptorPool::from_file_descriptor_set(descriptor_set()).unwrap()
+}
+
+fn dynamic(pool: &DescriptorPool, name: &str, value: Value) -> DynamicMessage {
+    DynamicMessage::deserialize(pool.get_message_by_name(name).unwrap(), value).unwrap()
+}
+
+/// Requests (as JSON) and `authorization` metadata the server received.
+type Seen = Arc<Mutex<Vec<(Value, Option<String>)>>>;
+
+#[derive(Clone)]
+struct Users {
+    pool: DescriptorPool,
+    seen: Seen,
+}
+
+impl Users {
+    fn record(&self, request: &tonic::Request<DynamicMessage>) -> Value {
+        let body = serde_json::to_value(request.get_ref()).unwrap();
+        let auth = request
+            .metadata()
+            .get("authorization")
+            .map(|v| v.to_str().unwrap().to_string());
+        self.seen.lock().unwrap().push((body.clone(), auth));
+        body
+    }
+}
+
+impl NamedService for Users {
+    const NAME: &'static str = "test.Users";
+}
+
+struct List(Users);
+
+impl ServerStreamingService<DynamicMessage> for List {
+    type Response = DynamicMessage;
+    type ResponseStream = BoxStream<'static, std::result::Result<DynamicMessage, Status>>;
+    type Future = BoxFuture<tonic::Response<Self::ResponseStream>, Status>;
+
+    fn call(&mut self, request: tonic::Request<DynamicMessage>) -> Self::Future {
+        self.0.record(&request);
+        let users: Vec<_> = (1..=3)
+            .map(|id| {
+                dynamic(
+                    &self.0.pool,
+                    "test.User",
+                    json!({"user_id": id, "display_name": format!("user {id}")}),
+                )
+            })
+            .collect();
+        let stream = futures::stream::iter(users).map(Ok).boxed();
+        Box::pin(async move { Ok(tonic::Response::new(stream)) })
+    }
+}
+
+struct Page(Users);
+
+impl UnaryService<DynamicMessage> for Page {
+    type Response = DynamicMessage;
+    type Future = BoxFuture<tonic::Response<DynamicMessage>, Status>;
+
+    fn call(&mut self, request: tonic::Request<DynamicMessage>) -> Self::Future {
+        let body = self.0.record(&request);
+        let page = match body.get("pageToken").and_then(Value::as_str) {
+            Some("p2") => json!({"users": [{"user_id": 3}], "next_page_token": ""}),
+            _ => json!({"users": [{"user_id": 1}, {"user_id": 2}], "next_page_token": "p2"}),
+        };
+        let response = dynamic(&self.0.pool, "test.ListResponse", page);
+        Box::pin(async move { Ok(tonic::Response::new(response)) })
+    }
+}
+
+impl Service<http::Request<BoxBody>> for Users {
+    type Response = http::Response<BoxBody>;
+    type Error = Infallible;
+    type Future = BoxFuture<Self::Response, Self::Error>;
+
+    fn poll_ready(&mut self, _cx: &mut Context<'_>) -> Poll<std::result::Result<(), Infallible>> {
+        Poll::Ready(Ok(()))
+    }
+
+    fn call(&mut self, req: http::Request<BoxBody>) -> Self::Future {
+        let input = self.pool.get_message_by_name("test.ListRequest").unwrap();
+        let mut grpc = Grpc::new(DynamicCodec::new(input));
+        let users = self.clone();
+        Box::pin(async move {
+            Ok(match req.uri().path() {
+                "/test.Users/List" => grpc.server_streaming(List(users), req).await,
+                _ => grpc.unary(Page(users), req).await,
+            })
+        })
+    }
+}
+
+/// Serves `test.Users` (and reflection when asked) on a free port.
+async fn serve(reflection: bool) -> (String, Seen) {
+    let seen = Seen::default();
+    let users = Users {
+        pool: pool(),
+        seen: Arc::clone(&seen),
+    };
+    let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let addr = listener.local_addr().unwrap();
+    let incoming = async_stream::stream! {
+        loop {
+            yield listener.accept().await.map(|(stream, _)| stream);
+        }
+    };
+    let reflection = reflection.then(|| {
+        tonic_reflection::server::Builder::configure()
+            .register_encoded_file_descriptor_set(&descriptor_set().encode_to_vec())
+            .build_v1()
+            .unwrap()
+    });
+    tokio::spawn(
+        tonic::transport::Server::builder()
+            .add_service(users)
+            .add_optional_service(reflection)
+            .serve_with_incoming(incoming),
+    );
+    (format!("http://{addr}"), seen)
+}
+
+#[derive(Default)]
+struct Memory {
+    rows: Mutex<Vec<Value>>,
+}
+
+#[async_trait::async_trait]
+impl DataWriter for Memory {
+    async fn write(&self, result: QueryResult) -> Result<()> {
+        self.rows.lock().unwrap().push(result.data);
+        Ok(())
+    }
+
+    async fn write_stream(&self, result: QueryResultStream, _mode: WriteMode) -> Result<()> {
+        let mut data = result.data;
+        while let Some(row) = data.next().await {
+            self.rows.lock().unwrap().push(row?);
+        }
+        Ok(())
+    }
+
+    async fn merge(&self, result: QueryResultStream) -> Result<()> {
+        self.write_stream(result, WriteMode::Merge).await
+    }
+}
+
+struct MemoryFactory(Arc<Memory>);
+
+impl WriterFactory for MemoryFactory {
+    fn make_writer(
+        &self,
+        _sink: &CustomSink,
+        _opts: &WriterOpts<'_>,
+    ) -> Result<(Arc<dyn DataWriter>, Option<Hook>)> {
+        Ok((self.0.clone(), None))
+    }
+}
+
+/// Runs one module reading `source_yaml` and returns the rows it wrote.
+async fn run_module(kind: &str, sql: &str, source_yaml: &str) -> Vec<Value> {
+    let memory = Arc::new(Memory::default());
+    register_writer_factory(kind, Arc::new(MemoryFactory(memory.clone())));
+
+    let dir = TempDir::new().unwrap();
+    fs::write(
+        dir.path().join("users.sql"),
+        format!(r#"{{{{ sink(name="mem") }}}}{sql}"#),
+    )
+    .unwrap();
+    let config: Config = serde_yaml::from_str(&format!(
+        "sources:\n{source_yaml}\ntargets:\n  - type: {kind}\n    name: mem\n"
+    ))
+    .unwrap();
+
+    let results = run_modules_once(
+        dir.path().to_str().unwrap(),
+        &[],
+        &config,
+        &RunOptions::default(),
+    )
+    .await
+    .unwrap();
+    assert!(results[0].is_success(), "{:?}", results[0].result);
+    let mut rows = memory.rows.lock().unwrap().clone();
+    rows.sort_by_key(|r| r["user_id"].as_i64());
+    rows
+}
+
+#[tokio::test]
+async fn test_grpc_streaming_method_via_descriptor_set() {
+    let (url, seen) = serve(false).await;
+    let dir = TempDir::new().unwrap();
+    let descriptors = dir.path().join("users.binpb");
+    fs::write(&descriptors, descriptor_set().encode_to_vec()).unwrap();
+
+    let rows = run_module(
+        "grpc_test_stream",
+        r#"SELECT user_id, display_name FROM {{ use_source("users") }}"#,
+        &format!(
+            r#"
+  - name: users
+    kind: grpc
+    url: {url}
+    headers:
+      - key: Authorization
+        value: Bearer t0k
+    grpc:
+      method: test.Users/List
+      descriptor_set: {}
+      request: {{ page_size: 50 }}
+"#,
+            descriptors.display()
+        ),
+    )
+    .await;
+
+    assert_eq!(
+        rows,
+        vec![
+            json!({"user_id": 1, "display_name": "user 1"}),
+            json!({"user_id": 2, "display_name": "user 2"}),
+            json!({"user_id": 3, "display_name": "user 3"}),
+        ]
+    );
+    let seen = seen.lock().unwrap();
+    assert_eq!(seen.len(), 1);
+    assert_eq!(seen[0].0, json!({"pageSize": 50}));
+    assert_eq!(seen[0].1.as_deref(), Some("Bearer t0k"));
+}
+
+#[tokio::test]
+async fn test_grpc_unary_pages_via_reflection() {
+    let (url, seen) = serve(true).await;
+
+    let rows = run_module(
+        "grpc_test_pages",
+        r#"SELECT user_id FROM {{ use_source("users") }}"#,
+        &format!(
+            r#"
+  - name: users
+    kind: grpc
+    url: {url}
+    data_path: /users
+    grpc:
+      method: test.Users/Page
+      page_token_field: page_token
+      next_page_token_field: next_page_token
+"#
+        ),
+    )
+    .await;
+
+    assert_eq!(
+        rows,
+        vec![
+            json!({"user_id": 1}),
+            json!({"user_id": 2}),
+            json!({"user_id": 3})
+        ]
+    );
+    let requests: Vec<Value> = seen
+        .lock()
+        .unwrap()
+        .iter()
+        .map(|(r, _)| r.clone())
+        .collect();
+    assert_eq!(requests, vec![json!({}), json!({"pageToken": "p2"})]);
+}
+
+#[test]
+fn test_grpc_source_validation() {
+    let load = |grpc: &str| {
+        let dir = TempDir::new().unwrap();
+        let path = dir.path().join("pipeline.yaml");
+        fs::write(
+            &path,
+            format!(
+                "sources:\n  - name: users\n    kind: grpc\n    url: http://localhost:50051\n{grpc}targets: []\n"
+            ),
+        )
+        .unwrap();
+        apitap::config::load_config_from_path(&path)
+            .unwrap_err()
+            .to_string()
+    };
+
+    assert!(load("").contains("kind grpc requires a grpc block"));
+    assert!(load("    grpc: { method: ListUsers }\n").contains("package.Service/Method"));
+    assert!(
+        load("    grpc: { method: a.B/C, page_token_field: t }\n").contains("must be set together")
+    );
+    assert!(load(
+        "    grpc: { method: a.B/C }\n    pagination: { kind: page_only, page_param: p }\n"
+    )
+    .contains("pagination is not supported for grpc sources"));
+}
@@ -0,0 +1,111 @@
+use std::time::Duration;
+
+use apitap::http::fetcher::{ndjson_stream_request, RequestTemplate};
+use apitap::pipeline::limits::{Limits, LimitsConfig};
+use apitap::pipeline::Retry;
+use futures::StreamExt;
+
+use crate::common::http_server::{Reply, TestServer};
+
+#[test]
+fn test_limits_or_prefers_own_values() {
+    let cli = LimitsConfig {
+        max_concurrent_modules: Some(2),
+        max_concurrent_requests: None,
+    };
+    let config = LimitsConfig {
+        max_concurrent_modules: Some(8),
+        max_concurrent_requests: Some(16),
+    };
+    assert_eq!(
+        cli.or(&config),
+        LimitsConfig {
+            max_concurrent_modules: Some(2),
+            max_concurrent_requests: Some(16),
+        }
+    );
+}
+
+#[tokio::test]
+async fn test_module_slot_waits_for_release() {
+    let limits = Limits::new(&LimitsConfig {
+        max_concurrent_modules: Some(1),
+        max_concurrent_requests: None,
+    });
+    let first = limits.acquire_module("a.sql").await.unwrap();
+
+    let waiting = tokio::spawn({
+        let limits = limits.clone();
+        async move { limits.acquire_module("b.sql").await.is_some() }
+    });
+    tokio::time::sleep(Duration::from_millis(50)).await;
+    assert!(!waiting.is_finished());
+
+    drop(first);
+    assert!(waiting.await.unwrap());
+}
+
+#[tokio::test]
+async fn test_unlimited_modules_need_no_permit() {
+    let limits = Limits::default();
+    assert!(limits.acquire_module("a.sql").await.is_none());
+    assert!(limits.requests().is_none());
+}
+
+/// Serves three one-record `data` pages, then empty ones, slowly; the
+/// server records the most requests handled at once.
+async fn serve_slowly() -> (String, TestServer) {
+    let server = TestServer::start(|req| {
+        let body = if req.index < 3 {
+            r#"{"data": [{"id": 1}]}"#
+        } else {
+            r#"{"data": []}"#
+        };
+        Reply::json(body).after(Duration::from_millis(30))
+    })
+    .await;
+    (server.url.clone(), server)
+}
+
+#[tokio::test]
+async fn test_request_limit_caps_requests_in_flight() {
+    for (limit, expected_peak) in [(1, 1), (2, 2)] {
+        let (url, server) = serve_slowly().await;
+        let limits = Limits::new(&LimitsConfig {
+            max_concurrent_modules: None,
+            max_concurrent_requests: Some(limit),
+        });
+        // Two modules fetching at once, sharing the process-wide limit
+        let template = RequestTemplate {
+            request_limit: limits.requests(),
+            ..RequestTemplate::default()
+        };
+        let retry = Retry {
+            max_attempts: 0,
+            ..Retry::default()
+        };
+        let client = reqwest::Client::new();
+        let fetch = |path: &'static str| {
+            let (client, url, template, retry) = (&client, &url, &template, &retry);
+            async move {
+                let records: Vec<_> = ndjson_stream_request(
+                    client,
+                    &format!("{url}/{path}"),
+                    &[],
+                    &[],
+                    template,
+                    Some("/data"),
+                    retry,
+                )
+                .await
+                .unwrap()
+                .collect()
+                .await;
+                records.len()
+            }
+        };
+        let (a, b, c) = tokio::join!(fetch("a"), fetch("b"), fetch("c"));
+        assert_eq!(a + b + c, 3);
+        assert_eq!(server.peak_in_flight(), expected_peak, "limit {limit}");
+    }
+}
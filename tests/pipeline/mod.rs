@@ -1 +1,10 @@
+mod backfill_tests;
+mod checkpoint_tests;
+mod conditional_tests;
 mod config_tests;
+mod empty_tests;
+mod file_tests;
+#[cfg(feature = "grpc")]
+mod grpc_tests;
+mod limits_tests;
+mod run_tests;
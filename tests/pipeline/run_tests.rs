@@ -0,0 +1,1329 @@
+use apitap::http::fetcher::{Pagination, RequestTemplate};
+use apitap::pipeline::run::{preview_schema, FetchOpts, FetchRequest};
+use apitap::pipeline::Retry;
+use datafusion::arrow::datatypes::DataType;
+
+use crate::common::http_server::{Reply, TestServer};
+
+/// Serves `body` to every request.
+async fn serve_body(body: &'static str) -> TestServer {
+    TestServer::start(move |_| Reply::json(body)).await
+}
+
+/// Serves `body` to the first request and an empty `data` page to the rest.
+async fn serve_pages(body: &'static str) -> TestServer {
+    TestServer::start(move |req| match req.index {
+        0 => Reply::json(body),
+        _ => Reply::json(r#"{"data": []}"#),
+    })
+    .await
+}
+
+fn request(url: &str, raw_json: bool) -> FetchRequest {
+    FetchRequest {
+        client: reqwest::Client::new(),
+        url: url::Url::parse(&format!("{url}/orders")).unwrap(),
+        data_path: Some("/data".to_string()),
+        extra_params: None,
+        pagination: Some(Pagination::LimitOffset {
+            limit_param: "limit".to_string(),
+            offset_param: "offset".to_string(),
+            start_offset: 0,
+            page_size: None,
+        }),
+        retry: Retry {
+            max_attempts: 0,
+            max_delay_secs: 1,
+            min_delay_secs: 1,
+            jitter: Default::default(),
+            max_elapsed_secs: None,
+        },
+        request_template: RequestTemplate::default(),
+        graphql: None,
+        grpc: None,
+        file: None,
+        raw_json,
+        ejson: false,
+        fields: Default::default(),
+        numbers: Default::default(),
+        nulls: Default::default(),
+        transform: Default::default(),
+        flatten: None,
+        dictionary: None,
+        resume: None,
+        metadata: None,
+        constant_columns: None,
+        schema_contract: None,
+        conditional: None,
+        preflight: None,
+    }
+}
+
+fn opts() -> FetchOpts {
+    FetchOpts {
+        concurrency: 1,
+        preserve_order: false,
+        default_page_size: 25,
+        fetch_batch_size: 16,
+        timeout: None,
+        progress: Default::default(),
+        execution: Default::default(),
+        capture: None,
+        limits: Default::default(),
+        max_records: None,
+    }
+}
+
+#[tokio::test]
+async fn test_preview_schema_fetches_first_page() {
+    let server = serve_body(
+        r#"{"data": [{"id": 1, "name": "a", "price": 1.5}, {"id": 2, "name": null, "price": 2}]}"#,
+    )
+    .await;
+
+    let schema = preview_schema(request(&server.url, false), &opts())
+        .await
+        .unwrap();
+
+    let requests = server.requests();
+    let request_line = requests[0].line();
+    assert!(request_line.starts_with("GET /orders?"));
+    assert!(request_line.contains("limit=25"));
+    assert!(request_line.contains("offset=0"));
+
+    let id = schema.field_with_name("id").unwrap();
+    assert_eq!(id.data_type(), &DataType::UInt64);
+    let name = schema.field_with_name("name").unwrap();
+    assert!(name.is_nullable());
+    assert_eq!(
+        schema.field_with_name("price").unwrap().data_type(),
+        &DataType::Float64
+    );
+}
+
+#[tokio::test]
+async fn test_preview_schema_sends_list_query_params() {
+    use apitap::pipeline::{ArrayStyle, QueryParam, QueryValue};
+
+    let server = serve_body(r#"{"data": [{"id": 1}]}"#).await;
+    let mut req = request(&server.url, false);
+    req.extra_params = Some(vec![QueryParam {
+        key: "id".to_string(),
+        value: QueryValue::Many(vec!["7".to_string(), "9".to_string()]),
+        style: ArrayStyle::Repeat,
+        raw: false,
+    }]);
+
+    preview_schema(req, &opts()).await.unwrap();
+    assert!(server.requests()[0].line().contains("id=7&id=9"));
+}
+
+#[tokio::test]
+async fn test_preview_schema_sends_raw_query_params_verbatim() {
+    use apitap::pipeline::{ArrayStyle, QueryParam, QueryValue};
+
+    let server = serve_body(r#"{"data": [{"id": 1}]}"#).await;
+    let mut req = request(&server.url, false);
+    let param = |key: &str, raw| QueryParam {
+        key: key.to_string(),
+        value: QueryValue::One("{{ status }}:${PRICE}".to_string()),
+        style: ArrayStyle::Repeat,
+        raw,
+    };
+    req.extra_params = Some(vec![param("filter", true)]);
+
+    preview_schema(req, &opts()).await.unwrap();
+    // `{`, `}` and `$` percent-encoded, otherwise untouched
+    assert!(server.requests()[0]
+        .line()
+        .contains("filter=%7B%7B+status+%7D%7D%3A%24%7BPRICE%7D"));
+}
+
+#[tokio::test]
+async fn test_preview_schema_applies_field_mapping() {
+    let url = serve_body(r#"{"data": [{"usr_nm": "a", "secret": 1, "id": 1}]}"#)
+        .await
+        .url;
+    let mut req = request(&url, false);
+    req.fields
+        .rename
+        .insert("usr_nm".to_string(), "user_name".to_string());
+    req.fields.drop.push("secret".to_string());
+
+    let schema = preview_schema(req, &opts()).await.unwrap();
+    let names: Vec<&str> = schema.fields().iter().map(|f| f.name().as_str()).collect();
+    assert!(names.contains(&"user_name"));
+    assert!(!names.contains(&"usr_nm"));
+    assert!(!names.contains(&"secret"));
+}
+
+#[tokio::test]
+async fn test_preview_schema_applies_transform() {
+    let url = serve_body(r#"{"data": [{"id": 1, "address": {"city": "Oslo"}}]}"#)
+        .await
+        .url;
+    let mut req = request(&url, false);
+    req.transform = apitap::utils::transform::RecordTransform::parse(&[
+        ".city = .address.city".to_string(),
+        "del(.address)".to_string(),
+    ])
+    .unwrap();
+
+    let schema = preview_schema(req, &opts()).await.unwrap();
+    let names: Vec<&str> = schema.fields().iter().map(|f| f.name().as_str()).collect();
+    assert_eq!(names.len(), 2);
+    assert!(names.contains(&"city"));
+    assert!(!names.contains(&"address"));
+}
+
+#[tokio::test]
+async fn test_preview_schema_raw_json() {
+    let url = serve_body(r#"{"data": [{"id": 1}]}"#).await.url;
+
+    let schema = preview_schema(request(&url, true), &opts()).await.unwrap();
+    assert_eq!(schema.fields().len(), 1);
+    assert_eq!(schema.field(0).name(), "data");
+}
+
+/// Records the transaction hooks the pipeline calls.
+#[derive(Default)]
+struct HookRecorder {
+    calls: std::sync::Mutex<Vec<&'static str>>,
+}
+
+#[async_trait::async_trait]
+impl apitap::writer::DataWriter for HookRecorder {
+    async fn write(
+        &self,
+        _result: apitap::utils::datafusion_ext::QueryResult,
+    ) -> apitap::errors::Result<()> {
+        Ok(())
+    }
+
+    async fn begin(&self) -> apitap::errors::Result<()> {
+        self.calls.lock().unwrap().push("begin");
+        Ok(())
+    }
+
+    async fn commit(&self) -> apitap::errors::Result<()> {
+        self.calls.lock().unwrap().push("commit");
+        Ok(())
+    }
+
+    async fn rollback(&self) -> apitap::errors::Result<()> {
+        self.calls.lock().unwrap().push("rollback");
+        Ok(())
+    }
+}
+
+#[tokio::test]
+async fn test_run_fetch_timeout_rolls_back() {
+    use apitap::errors::ApitapError;
+    use apitap::pipeline::run::{run_fetch, QueryConfig, WriteConfig};
+    use std::sync::Arc;
+    use std::time::Duration;
+
+    // Accepts the request but never answers
+    let url = TestServer::start(|_| Reply::hang()).await.url;
+
+    let writer = Arc::new(HookRecorder::default());
+    let opts = FetchOpts {
+        timeout: Some(Duration::from_millis(200)),
+        ..opts()
+    };
+    let err = run_fetch(
+        request(&url, false),
+        QueryConfig {
+            sql: "SELECT * FROM orders",
+            dest_table: "orders",
+        },
+        WriteConfig {
+            writer: writer.clone(),
+            write_mode: apitap::writer::WriteMode::Append,
+            splits: Vec::new(),
+        },
+        &opts,
+    )
+    .await
+    .unwrap_err();
+
+    assert!(matches!(err, ApitapError::Timeout(_)), "{err}");
+    assert_eq!(*writer.calls.lock().unwrap(), vec!["begin", "rollback"]);
+}
+
+#[test]
+fn test_expand_path_param_encodes_values() {
+    let mut req = request("http://localhost", false);
+    req.url = url::Url::parse("http://localhost/users/{user_id}/orders?x=1").unwrap();
+
+    let expanded = req
+        .expand_path_param("user_id", &["17".to_string(), "a b/c".to_string()])
+        .unwrap();
+    let urls: Vec<&str> = expanded.iter().map(|r| r.url.as_str()).collect();
+    assert_eq!(
+        urls,
+        vec![
+            "http://localhost/users/17/orders?x=1",
+            "http://localhost/users/a%20b%2Fc/orders?x=1",
+        ]
+    );
+}
+
+#[test]
+fn test_expand_path_param_requires_placeholder() {
+    let req = request("http://localhost", false);
+    let err = req
+        .expand_path_param("user_id", &["17".to_string()])
+        .unwrap_err();
+    assert!(err.to_string().contains("{user_id}"), "{err}");
+}
+
+#[tokio::test]
+async fn test_resolve_path_params_from_values_and_query() {
+    use apitap::pipeline::run::resolve_path_params;
+    use apitap::pipeline::PathParams;
+
+    let params = PathParams {
+        name: "id".to_string(),
+        values: vec![serde_json::json!(1), serde_json::json!("two")],
+        query: Some("SELECT * FROM (VALUES (3), (NULL), (4)) AS t(id)".to_string()),
+    };
+    assert_eq!(
+        resolve_path_params(&params).await.unwrap(),
+        vec!["1", "two", "3", "4"]
+    );
+}
+
+#[tokio::test]
+async fn test_run_fetch_all_unions_requests_in_one_transaction() {
+    use apitap::pipeline::run::{run_fetch_all, QueryConfig, WriteConfig};
+    use std::sync::Arc;
+
+    let first = serve_pages(r#"{"data": [{"id": 1}]}"#).await;
+    let second = serve_pages(r#"{"data": [{"id": 2}, {"id": 3}]}"#).await;
+
+    let writer = Arc::new(HookRecorder::default());
+    let stats = run_fetch_all(
+        vec![request(&first.url, false), request(&second.url, false)],
+        QueryConfig {
+            sql: "SELECT * FROM fan_out_orders",
+            dest_table: "fan_out_orders",
+        },
+        WriteConfig {
+            writer: writer.clone(),
+            write_mode: apitap::writer::WriteMode::Append,
+            splits: Vec::new(),
+        },
+        &opts(),
+    )
+    .await
+    .unwrap();
+
+    assert!(first.hits() >= 1);
+    assert!(second.hits() >= 1);
+    assert_eq!(stats.total_items, 3);
+    assert_eq!(*writer.calls.lock().unwrap(), vec!["begin", "commit"]);
+}
+
+#[derive(Default)]
+struct RowCollector {
+    rows: std::sync::Mutex<Vec<serde_json::Value>>,
+    schemas: std::sync::Mutex<Vec<datafusion::arrow::datatypes::SchemaRef>>,
+}
+
+#[async_trait::async_trait]
+impl apitap::writer::DataWriter for RowCollector {
+    async fn write(
+        &self,
+        _result: apitap::utils::datafusion_ext::QueryResult,
+    ) -> apitap::errors::Result<()> {
+        Ok(())
+    }
+
+    async fn write_stream(
+        &self,
+        result: apitap::utils::datafusion_ext::QueryResultStream,
+        _mode: apitap::writer::WriteMode,
+    ) -> apitap::errors::Result<()> {
+        use futures::StreamExt;
+
+        self.schemas.lock().unwrap().extend(result.schema);
+        let rows: Vec<_> = result.data.collect().await;
+        for row in rows {
+            self.rows.lock().unwrap().push(row?);
+        }
+        Ok(())
+    }
+}
+
+/// Like [`serve_pages`], but keeps connections open between requests.
+async fn serve_pages_keep_alive(body: &'static str) -> TestServer {
+    TestServer::keep_alive(move |req| match req.index {
+        0 => Reply::json(body),
+        _ => Reply::json(r#"{"data": []}"#),
+    })
+    .await
+}
+
+#[tokio::test]
+async fn test_run_fetch_reuses_pooled_connection_across_pages() {
+    use apitap::http::{Http, HttpPool};
+    use apitap::pipeline::run::{run_fetch, QueryConfig, WriteConfig};
+    use std::sync::Arc;
+
+    for (pool, expected) in [
+        (HttpPool::default(), 1),
+        (
+            HttpPool {
+                pool_max_idle_per_host: Some(0),
+                ..HttpPool::default()
+            },
+            2,
+        ),
+    ] {
+        let server = serve_pages_keep_alive(r#"{"data": [{"id": 1}]}"#).await;
+        let mut req = request(&server.url, false);
+        req.client = Http::new("").pool(pool).build_client();
+        req.pagination = Some(Pagination::PageNumber {
+            page_param: "page".to_string(),
+            per_page_param: "per_page".to_string(),
+            start_page: 1,
+            page_size: None,
+        });
+
+        let writer = Arc::new(RowCollector::default());
+        run_fetch(
+            req,
+            QueryConfig {
+                sql: "SELECT id FROM pooled_orders",
+                dest_table: "pooled_orders",
+            },
+            WriteConfig {
+                writer: writer.clone(),
+                write_mode: apitap::writer::WriteMode::Append,
+                splits: Vec::new(),
+            },
+            &opts(),
+        )
+        .await
+        .unwrap();
+
+        assert_eq!(writer.rows.lock().unwrap().len(), 1);
+        // Page 1 and the empty page 2 share a connection unless pooling is off
+        assert_eq!(server.connections(), expected, "{pool:?}");
+    }
+}
+
+#[tokio::test]
+async fn test_run_fetch_adds_metadata_columns_after_sql() {
+    use apitap::pipeline::run::{run_fetch, QueryConfig, WriteConfig};
+    use apitap::utils::metadata::MetadataColumns;
+    use std::sync::Arc;
+
+    let url = serve_pages(r#"{"data": [{"id": 1, "name": "a"}]}"#)
+        .await
+        .url;
+    let columns = MetadataColumns {
+        ingested_at: Some("_ingested_at".to_string()),
+        module: Some("_module".to_string()),
+        page: Some("_page".to_string()),
+        ..MetadataColumns::default()
+    };
+    let mut req = request(&url, false);
+    req.pagination = Some(Pagination::PageNumber {
+        page_param: "page".to_string(),
+        per_page_param: "per_page".to_string(),
+        start_page: 1,
+        page_size: None,
+    });
+    req.metadata = Some(columns.stamp("orders_api", "orders"));
+
+    let writer = Arc::new(RowCollector::default());
+    run_fetch(
+        req,
+        QueryConfig {
+            // The query doesn't select the metadata columns
+            sql: "SELECT id FROM metadata_orders",
+            dest_table: "metadata_orders",
+        },
+        WriteConfig {
+            writer: writer.clone(),
+            write_mode: apitap::writer::WriteMode::Append,
+            splits: Vec::new(),
+        },
+        &opts(),
+    )
+    .await
+    .unwrap();
+
+    let rows = writer.rows.lock().unwrap();
+    assert_eq!(rows.len(), 1);
+    assert_eq!(rows[0]["id"], 1);
+    assert_eq!(rows[0]["_module"], "orders");
+    assert_eq!(rows[0]["_page"], 1);
+    assert!(rows[0].get("_source").is_none());
+    assert!(rows[0]["_ingested_at"].as_str().unwrap().ends_with('Z'));
+
+    let schema = &writer.schemas.lock().unwrap()[0];
+    let names: Vec<&str> = schema.fields().iter().map(|f| f.name().as_str()).collect();
+    assert_eq!(names, vec!["id", "_ingested_at", "_module", "_page"]);
+    assert!(matches!(
+        schema.field_with_name("_ingested_at").unwrap().data_type(),
+        DataType::Timestamp(_, Some(_))
+    ));
+}
+
+#[tokio::test]
+async fn test_run_fetch_conditional_skips_on_not_modified() {
+    use apitap::pipeline::conditional::{Conditional, ValidatorStore};
+    use apitap::pipeline::run::{run_fetch, QueryConfig, WriteConfig};
+    use std::sync::Arc;
+
+    // First page carries an ETag; a request sending it back gets a 304
+    let url = TestServer::start(|req| {
+        if req.contains("if-none-match: \"v1\"") {
+            Reply::status("304 Not Modified").header("etag", "\"v1\"")
+        } else if req.line().contains("offset=0") {
+            Reply::json(r#"{"data": [{"id": 1}, {"id": 2}]}"#).header("etag", "\"v1\"")
+        } else {
+            Reply::json(r#"{"data": []}"#).header("etag", "\"v1\"")
+        }
+    })
+    .await
+    .url;
+
+    let state = tempfile::tempdir().unwrap();
+    let store = ValidatorStore::new(state.path());
+    let opts = opts();
+    let run = || async {
+        let mut req = request(&url, false);
+        req.conditional = Some(Conditional {
+            store: store.clone(),
+            key: "orders".to_string(),
+        });
+        let writer = Arc::new(RowCollector::default());
+        let stats = run_fetch(
+            req,
+            QueryConfig {
+                sql: "SELECT * FROM conditional_orders",
+                dest_table: "conditional_orders",
+            },
+            WriteConfig {
+                writer: writer.clone(),
+                write_mode: apitap::writer::WriteMode::Append,
+                splits: Vec::new(),
+            },
+            &opts,
+        )
+        .await
+        .unwrap();
+        (stats, writer)
+    };
+
+    let (first, writer) = run().await;
+    assert_eq!(first.total_items, 2);
+    assert_eq!(first.not_modified, 0);
+    assert_eq!(writer.rows.lock().unwrap().len(), 2);
+    let saved = store
+        .load("orders", &format!("{url}/orders"))
+        .unwrap()
+        .unwrap();
+    assert_eq!(saved.etag.as_deref(), Some("\"v1\""));
+
+    let (second, writer) = run().await;
+    assert_eq!(second.not_modified, 1);
+    assert_eq!(second.total_items, 0);
+    assert_eq!(second.request_count, 1);
+    assert!(writer.rows.lock().unwrap().is_empty());
+}
+
+/// Answers `offset=0` with two records carrying an ETag and later offsets
+/// with an empty page, on any path.
+async fn serve_tagged_pages() -> TestServer {
+    TestServer::start(|req| {
+        let body = if req.line().contains("offset=0") {
+            r#"{"data": [{"id": 1}, {"id": 2}]}"#
+        } else {
+            r#"{"data": []}"#
+        };
+        Reply::json(body).header("etag", "\"v1\"")
+    })
+    .await
+}
+
+#[tokio::test]
+async fn test_run_fetch_sampled_run_keeps_validators() {
+    use apitap::pipeline::conditional::{Conditional, ValidatorStore};
+    use apitap::pipeline::run::{run_fetch, QueryConfig, WriteConfig};
+    use std::sync::Arc;
+
+    let server = serve_tagged_pages().await;
+    let url = server.url.clone();
+    let state = tempfile::tempdir().unwrap();
+    let store = ValidatorStore::new(state.path());
+    let run = |opts: FetchOpts| {
+        let mut req = request(&url, false);
+        req.conditional = Some(Conditional {
+            store: store.clone(),
+            key: "orders".to_string(),
+        });
+        async move {
+            run_fetch(
+                req,
+                QueryConfig {
+                    sql: "SELECT * FROM sampled_orders",
+                    dest_table: "sampled_orders",
+                },
+                WriteConfig {
+                    writer: Arc::new(RowCollector::default()),
+                    write_mode: apitap::writer::WriteMode::Append,
+                    splits: Vec::new(),
+                },
+                &opts,
+            )
+            .await
+            .unwrap()
+        }
+    };
+
+    let sampled = run(FetchOpts {
+        max_records: Some(1),
+        ..opts()
+    })
+    .await;
+    assert_eq!(sampled.total_items, 1);
+    assert!(store
+        .load("orders", &format!("{url}/orders"))
+        .unwrap()
+        .is_none());
+
+    // The next full run fetches everything instead of getting a 304
+    let full = run(opts()).await;
+    assert_eq!(full.total_items, 2);
+    assert_eq!(full.not_modified, 0);
+    assert!(!server
+        .requests()
+        .iter()
+        .any(|req| req.contains("if-none-match")));
+}
+
+#[tokio::test]
+async fn test_run_fetch_all_shares_record_budget_across_requests() {
+    use apitap::pipeline::run::{run_fetch_all, QueryConfig, WriteConfig};
+    use std::sync::Arc;
+
+    // Two path values, each serving two records
+    let url = serve_tagged_pages().await.url;
+    let requests = ["a", "b"]
+        .iter()
+        .map(|region| {
+            let mut req = request(&url, false);
+            req.url = url::Url::parse(&format!("{url}/{region}/orders")).unwrap();
+            req
+        })
+        .collect();
+
+    let writer = Arc::new(RowCollector::default());
+    let stats = run_fetch_all(
+        requests,
+        QueryConfig {
+            sql: "SELECT * FROM budget_orders",
+            dest_table: "budget_orders",
+        },
+        WriteConfig {
+            writer: writer.clone(),
+            write_mode: apitap::writer::WriteMode::Append,
+            splits: Vec::new(),
+        },
+        &FetchOpts {
+            max_records: Some(3),
+            ..opts()
+        },
+    )
+    .await
+    .unwrap();
+
+    assert_eq!(stats.total_items, 3);
+    assert_eq!(writer.rows.lock().unwrap().len(), 3);
+}
+
+#[tokio::test]
+async fn test_run_fetch_with_multiple_target_partitions() {
+    use apitap::pipeline::run::{run_fetch, QueryConfig, WriteConfig};
+    use apitap::utils::execution::ExecutionOpts;
+    use std::sync::Arc;
+
+    let url = serve_pages(
+        r#"{"data": [{"id": 1, "kind": "a"}, {"id": 2, "kind": "b"}, {"id": 3, "kind": "a"}]}"#,
+    )
+    .await
+    .url;
+    let opts = FetchOpts {
+        execution: ExecutionOpts {
+            batch_size: 1,
+            target_partitions: 4,
+            ..ExecutionOpts::default()
+        },
+        ..opts()
+    };
+
+    let writer = Arc::new(RowCollector::default());
+    run_fetch(
+        request(&url, false),
+        QueryConfig {
+            sql: "SELECT kind, count(*) AS n FROM partitioned_orders GROUP BY kind ORDER BY kind",
+            dest_table: "partitioned_orders",
+        },
+        WriteConfig {
+            writer: writer.clone(),
+            write_mode: apitap::writer::WriteMode::Append,
+            splits: Vec::new(),
+        },
+        &opts,
+    )
+    .await
+    .unwrap();
+
+    let rows = writer.rows.lock().unwrap();
+    assert_eq!(rows.len(), 2);
+    assert_eq!((&rows[0]["kind"], &rows[0]["n"]), (&"a".into(), &2.into()));
+    assert_eq!((&rows[1]["kind"], &rows[1]["n"]), (&"b".into(), &1.into()));
+}
+
+#[tokio::test]
+async fn test_run_fetch_dictionary_columns_reach_writer_as_text() {
+    use apitap::pipeline::run::{run_fetch, QueryConfig, WriteConfig};
+    use apitap::utils::dictionary::DictionaryEncoding;
+    use std::sync::Arc;
+
+    let url = serve_pages(
+        r#"{"data": [{"id": 1, "kind": "a"}, {"id": 2, "kind": "b"}, {"id": 3, "kind": "a"}]}"#,
+    )
+    .await
+    .url;
+    let mut req = request(&url, false);
+    req.dictionary = Some(DictionaryEncoding {
+        columns: vec!["kind".into()],
+        ..Default::default()
+    });
+
+    let writer = Arc::new(RowCollector::default());
+    run_fetch(
+        req,
+        QueryConfig {
+            sql: "SELECT kind, arrow_typeof(kind) AS kind_type, count(*) AS n \
+                  FROM dictionary_orders GROUP BY kind ORDER BY kind",
+            dest_table: "dictionary_orders",
+        },
+        WriteConfig {
+            writer: writer.clone(),
+            write_mode: apitap::writer::WriteMode::Append,
+            splits: Vec::new(),
+        },
+        &opts(),
+    )
+    .await
+    .unwrap();
+
+    let rows = writer.rows.lock().unwrap();
+    assert_eq!(rows.len(), 2);
+    assert_eq!((&rows[0]["kind"], &rows[0]["n"]), (&"a".into(), &2.into()));
+    assert_eq!((&rows[1]["kind"], &rows[1]["n"]), (&"b".into(), &1.into()));
+    // The SQL sees the dictionary; the writer gets text
+    assert_eq!(rows[0]["kind_type"], "Dictionary(Int32, Utf8)");
+    let schema = &writer.schemas.lock().unwrap()[0];
+    assert_eq!(
+        schema.field_with_name("kind").unwrap().data_type(),
+        &DataType::Utf8
+    );
+}
+
+#[tokio::test]
+async fn test_run_fetch_memory_limit_spills_or_fails() {
+    use apitap::errors::ApitapError;
+    use apitap::pipeline::run::{run_fetch, QueryConfig, WriteConfig};
+    use apitap::utils::execution::{ByteSize, ExecutionOpts};
+    use std::sync::Arc;
+
+    // A sort over ~10 MB of rows against a 4 MB budget
+    let dir = tempfile::tempdir().unwrap();
+    let records: Vec<serde_json::Value> = (0..100_000)
+        .map(|i| serde_json::json!({"id": (i * 7919) % 100_000, "pad": "x".repeat(64)}))
+        .collect();
+    std::fs::write(
+        dir.path().join("big.json"),
+        serde_json::json!({ "data": records }).to_string(),
+    )
+    .unwrap();
+    let spill_dir = dir.path().join("spill");
+    std::fs::create_dir(&spill_dir).unwrap();
+
+    let run = |spill: bool| {
+        let mut req = request("http://127.0.0.1:9", false);
+        req.file = Some(format!("{}/big.json", dir.path().display()));
+        let opts = FetchOpts {
+            execution: ExecutionOpts {
+                batch_size: 1024,
+                memory_limit: ByteSize(4 << 20),
+                spill,
+                spill_dir: spill.then(|| spill_dir.clone()),
+                ..ExecutionOpts::default()
+            },
+            ..opts()
+        };
+        let writer = Arc::new(RowCollector::default());
+        async move {
+            let result = run_fetch(
+                req,
+                QueryConfig {
+                    sql: "SELECT id, pad FROM sorted_orders ORDER BY id",
+                    dest_table: "sorted_orders",
+                },
+                WriteConfig {
+                    writer: writer.clone(),
+                    write_mode: apitap::writer::WriteMode::Append,
+                    splits: Vec::new(),
+                },
+                &opts,
+            )
+            .await;
+            (result, writer)
+        }
+    };
+
+    let (result, _) = run(false).await;
+    let err = result.unwrap_err();
+    assert!(matches!(err, ApitapError::MemoryLimit(_)), "{err}");
+    assert!(err.to_string().contains("execution.memory_limit"), "{err}");
+
+    let (result, writer) = run(true).await;
+    result.unwrap();
+    let rows = writer.rows.lock().unwrap();
+    assert_eq!(rows.len(), 100_000);
+    assert_eq!(rows[0]["id"], 0);
+    assert_eq!(rows[99_999]["id"], 99_999);
+}
+
+#[tokio::test]
+async fn test_run_fetch_reads_file_source() {
+    use apitap::pipeline::run::{run_fetch, QueryConfig, WriteConfig};
+    use std::sync::Arc;
+
+    let dir = tempfile::tempdir().unwrap();
+    std::fs::write(
+        dir.path().join("page1.json"),
+        r#"{"data": [{"id": 1, "name": "a"}, {"id": 2, "name": "b"}]}"#,
+    )
+    .unwrap();
+    std::fs::write(
+        dir.path().join("page2.json"),
+        r#"{"data": [{"id": 3, "name": "c"}]}"#,
+    )
+    .unwrap();
+
+    // Never contacted
+    let mut req = request("http://127.0.0.1:9", false);
+    req.file = Some(format!("{}/page*.json", dir.path().display()));
+
+    let writer = Arc::new(RowCollector::default());
+    let stats = run_fetch(
+        req,
+        QueryConfig {
+            sql: "SELECT id, upper(name) AS name FROM file_orders ORDER BY id",
+            dest_table: "file_orders",
+        },
+        WriteConfig {
+            writer: writer.clone(),
+            write_mode: apitap::writer::WriteMode::Append,
+            splits: Vec::new(),
+        },
+        &opts(),
+    )
+    .await
+    .unwrap();
+
+    assert_eq!(stats.total_items, 3);
+    assert_eq!(stats.page_count, 2);
+    assert_eq!(stats.request_count, 0);
+    let rows = writer.rows.lock().unwrap();
+    let names: Vec<&str> = rows.iter().map(|r| r["name"].as_str().unwrap()).collect();
+    assert_eq!(names, vec!["A", "B", "C"]);
+}
+
+#[tokio::test]
+async fn test_run_fetch_captures_every_page() {
+    use apitap::http::capture::Capture;
+    use apitap::pipeline::run::{run_fetch, QueryConfig, WriteConfig};
+    use std::sync::Arc;
+
+    let url = serve_pages(r#"{"data": [{"id": 1}, {"id": 2}]}"#).await.url;
+    let dir = tempfile::tempdir().unwrap();
+    let capture = Arc::new(Capture::new(dir.path(), 1024 * 1024));
+    let mut req = request(&url, false);
+    req.request_template.capture = Some(capture.for_source("orders", Vec::new()));
+    let opts = FetchOpts {
+        concurrency: 1,
+        ..opts()
+    };
+
+    let writer = Arc::new(RowCollector::default());
+    run_fetch(
+        req,
+        QueryConfig {
+            sql: "SELECT * FROM orders",
+            dest_table: "orders",
+        },
+        WriteConfig {
+            writer,
+            write_mode: apitap::writer::WriteMode::Append,
+            splits: Vec::new(),
+        },
+        &opts,
+    )
+    .await
+    .unwrap();
+
+    let mut names: Vec<_> = std::fs::read_dir(dir.path())
+        .unwrap()
+        .map(|e| e.unwrap().file_name().to_string_lossy().into_owned())
+        .collect();
+    names.sort();
+    assert!(names.len() >= 4, "{names:?}");
+    assert_eq!(names[0], "000001-orders.request.json");
+    assert_eq!(names[1], "000001-orders.response.json");
+    let first = std::fs::read_to_string(dir.path().join("000001-orders.request.json")).unwrap();
+    assert!(first.contains("offset=0"), "{first}");
+}
+
+#[tokio::test]
+async fn test_run_fetch_coerces_null_like_strings() {
+    use apitap::pipeline::run::{run_fetch, QueryConfig, WriteConfig};
+    use std::sync::Arc;
+
+    let url = serve_pages(
+        r#"{"data": [{"id": 1, "price": 5, "note": ""}, {"id": 2, "price": "N/A", "note": "x"}, {"id": 3, "price": "", "note": "y"}]}"#,
+    ).await.url;
+    let mut req = request(&url, false);
+    req.nulls.null_values = vec!["".to_string(), "N/A".to_string()];
+    req.nulls.column_null_values = [("note".to_string(), Vec::new())].into();
+
+    let writer = Arc::new(RowCollector::default());
+    run_fetch(
+        req,
+        QueryConfig {
+            sql: "SELECT id, price, note, price IS NULL AS missing FROM orders ORDER BY id",
+            dest_table: "orders",
+        },
+        WriteConfig {
+            writer: writer.clone(),
+            write_mode: apitap::writer::WriteMode::Append,
+            splits: Vec::new(),
+        },
+        &opts(),
+    )
+    .await
+    .unwrap();
+
+    let schema = writer.schemas.lock().unwrap()[0].clone();
+    // Numeric, not Utf8
+    assert!(schema
+        .field_with_name("price")
+        .unwrap()
+        .data_type()
+        .is_integer());
+    let rows = writer.rows.lock().unwrap();
+    let missing: Vec<bool> = rows
+        .iter()
+        .map(|r| r["missing"].as_bool().unwrap())
+        .collect();
+    assert_eq!(missing, vec![false, true, true]);
+    assert_eq!(rows[0]["note"], "");
+}
+
+#[tokio::test]
+async fn test_run_fetch_unwraps_extended_json() {
+    use apitap::pipeline::run::{run_fetch, QueryConfig, WriteConfig};
+    use std::sync::Arc;
+
+    let url = serve_pages(
+        r#"{"data": [{"_id": {"$oid": "a1"}, "n": {"$numberLong": "41"}, "at": {"$date": {"$numberLong": "0"}}}]}"#,
+    ).await.url;
+    let mut req = request(&url, false);
+    req.ejson = true;
+
+    let writer = Arc::new(RowCollector::default());
+    run_fetch(
+        req,
+        QueryConfig {
+            sql: "SELECT \"_id\" AS id, n, at FROM orders",
+            dest_table: "orders",
+        },
+        WriteConfig {
+            writer: writer.clone(),
+            write_mode: apitap::writer::WriteMode::Append,
+            splits: Vec::new(),
+        },
+        &opts(),
+    )
+    .await
+    .unwrap();
+
+    let rows = writer.rows.lock().unwrap();
+    assert_eq!(
+        rows[0],
+        serde_json::json!({"id": "a1", "n": 41, "at": "1970-01-01T00:00:00.000Z"})
+    );
+}
+
+#[tokio::test]
+async fn test_run_fetch_flattens_nested_objects() {
+    use apitap::pipeline::run::{run_fetch, QueryConfig, WriteConfig};
+    use std::sync::Arc;
+
+    let url = serve_pages(
+        r#"{"data": [{"id": "a1", "address": {"city": "Oslo", "geo": {"zip": "0150"}}, "tags": [1]}]}"#,
+    ).await.url;
+    let mut req = request(&url, false);
+    req.flatten = Some(Default::default());
+
+    let writer = Arc::new(RowCollector::default());
+    run_fetch(
+        req,
+        QueryConfig {
+            sql: "SELECT id, address_city, address_geo_zip, tags FROM orders",
+            dest_table: "orders",
+        },
+        WriteConfig {
+            writer: writer.clone(),
+            write_mode: apitap::writer::WriteMode::Append,
+            splits: Vec::new(),
+        },
+        &opts(),
+    )
+    .await
+    .unwrap();
+
+    let rows = writer.rows.lock().unwrap();
+    assert_eq!(
+        rows[0],
+        serde_json::json!({"id": "a1", "address_city": "Oslo", "address_geo_zip": "0150", "tags": "[1]"})
+    );
+}
+
+#[tokio::test]
+async fn test_run_fetch_writes_splits_to_their_own_writers() {
+    use apitap::pipeline::run::{run_fetch, QueryConfig, WriteConfig};
+    use apitap::pipeline::split::SplitWriter;
+    use std::sync::Arc;
+
+    let url = serve_pages(
+        r#"{"data": [{"id": 1, "name": "a"}, {"id": 2, "name": "b"}],
+            "accounts": [{"account_id": 10, "owner": 1}],
+            "tags": [{"tag": "x"}, {"tag": "y"}, {"tag": "z"}]}"#,
+    )
+    .await
+    .url;
+    let writer = Arc::new(RowCollector::default());
+    let accounts = Arc::new(RowCollector::default());
+    let tags = Arc::new(RowCollector::default());
+    let split = |data_path: &str, dest_table: &str, writer: &Arc<RowCollector>| SplitWriter {
+        data_path: data_path.to_string(),
+        dest_table: dest_table.to_string(),
+        writer: writer.clone(),
+        write_mode: apitap::writer::WriteMode::Append,
+    };
+
+    let stats = run_fetch(
+        request(&url, false),
+        QueryConfig {
+            sql: "SELECT id FROM users",
+            dest_table: "users",
+        },
+        WriteConfig {
+            writer: writer.clone(),
+            write_mode: apitap::writer::WriteMode::Append,
+            splits: vec![
+                split("/accounts", "crm.accounts", &accounts),
+                split("/tags", "tags", &tags),
+            ],
+        },
+        &opts(),
+    )
+    .await
+    .unwrap();
+
+    assert_eq!(stats.total_items, 2);
+    assert_eq!(writer.rows.lock().unwrap().len(), 2);
+    let accounts = accounts.rows.lock().unwrap();
+    assert_eq!(accounts.len(), 1);
+    assert_eq!(accounts[0]["account_id"], 10);
+    assert_eq!(accounts[0]["owner"], 1);
+    let tags: Vec<_> = tags
+        .rows
+        .lock()
+        .unwrap()
+        .iter()
+        .map(|r| r["tag"].clone())
+        .collect();
+    assert_eq!(tags, vec!["x", "y", "z"]);
+}
+
+#[tokio::test]
+async fn test_run_fetch_splits_every_file() {
+    use apitap::pipeline::run::{run_fetch, QueryConfig, WriteConfig};
+    use apitap::pipeline::split::SplitWriter;
+    use std::sync::Arc;
+
+    let dir = tempfile::tempdir().unwrap();
+    for (name, body) in [
+        (
+            "page1.json",
+            r#"{"data": [{"id": 1}], "accounts": [{"account_id": 10}]}"#,
+        ),
+        ("page2.json", r#"{"data": [{"id": 2}]}"#),
+        (
+            "page3.json",
+            r#"{"data": [], "accounts": [{"account_id": 30}]}"#,
+        ),
+    ] {
+        std::fs::write(dir.path().join(name), body).unwrap();
+    }
+    let mut req = request("http://127.0.0.1:9", false);
+    req.file = Some(format!("{}/page*.json", dir.path().display()));
+
+    let writer = Arc::new(RowCollector::default());
+    let accounts = Arc::new(RowCollector::default());
+    run_fetch(
+        req,
+        QueryConfig {
+            sql: "SELECT id FROM users",
+            dest_table: "users",
+        },
+        WriteConfig {
+            writer: writer.clone(),
+            write_mode: apitap::writer::WriteMode::Append,
+            splits: vec![SplitWriter {
+                data_path: "/accounts".to_string(),
+                dest_table: "accounts".to_string(),
+                writer: accounts.clone(),
+                write_mode: apitap::writer::WriteMode::Append,
+            }],
+        },
+        &opts(),
+    )
+    .await
+    .unwrap();
+
+    assert_eq!(writer.rows.lock().unwrap().len(), 2);
+    let mut ids: Vec<_> = accounts
+        .rows
+        .lock()
+        .unwrap()
+        .iter()
+        .map(|r| r["account_id"].as_i64().unwrap())
+        .collect();
+    ids.sort();
+    assert_eq!(ids, vec![10, 30]);
+}
+
+#[tokio::test]
+async fn test_run_fetch_adds_constant_columns_after_sql() {
+    use apitap::pipeline::run::{run_fetch, QueryConfig, WriteConfig};
+    use apitap::utils::constant_columns::ConstantColumnsConfig;
+    use std::sync::Arc;
+
+    let run = |sql: &'static str, constants: &str| {
+        let config: ConstantColumnsConfig = serde_yaml::from_str(constants).unwrap();
+        let writer = Arc::new(RowCollector::default());
+        async move {
+            // A fresh server per run: it only answers the first request with rows
+            let url = serve_pages(r#"{"data": [{"id": 1, "tenant_id": "raw"}]}"#)
+                .await
+                .url;
+            let mut req = request(&url, false);
+            req.constant_columns = Some(config.resolve().unwrap());
+            run_fetch(
+                req,
+                QueryConfig {
+                    sql,
+                    dest_table: "constant_orders",
+                },
+                WriteConfig {
+                    writer: writer.clone(),
+                    write_mode: apitap::writer::WriteMode::Append,
+                    splits: Vec::new(),
+                },
+                &opts(),
+            )
+            .await
+            .map(|_| writer)
+        }
+    };
+
+    let writer = run("SELECT id FROM constant_orders", "tenant_id: acme")
+        .await
+        .unwrap();
+    assert_eq!(
+        writer.rows.lock().unwrap()[0],
+        serde_json::json!({"id": 1, "tenant_id": "acme"})
+    );
+
+    let err = run("SELECT * FROM constant_orders", "tenant_id: acme")
+        .await
+        .err()
+        .unwrap();
+    assert!(
+        err.to_string().contains("constant column 'tenant_id'"),
+        "{err}"
+    );
+}
+
+#[tokio::test]
+async fn test_run_fetch_reads_ndjson_stream_without_pagination() {
+    use apitap::http::fetcher::ResponseFormat;
+    use apitap::pipeline::run::{run_fetch, QueryConfig, WriteConfig};
+    use std::sync::Arc;
+
+    // Not an NDJSON content type, and a line split across chunks
+    let server = TestServer::start(|_| {
+        Reply::status("200 OK")
+            .header("content-type", "application/json")
+            .chunked(
+                &[
+                    "{\"id\": 1, \"name\": \"a\"}\n{\"id\": 2,",
+                    " \"name\": \"b\"}\n\n",
+                    "{\"id\": 3, \"name\": \"c\"}\n",
+                ],
+                std::time::Duration::from_millis(20),
+            )
+    })
+    .await;
+
+    let mut req = request(&server.url, false);
+    req.pagination = None;
+    req.data_path = None;
+    req.request_template.format = ResponseFormat::NdjsonStream;
+
+    let writer = Arc::new(RowCollector::default());
+    let stats = run_fetch(
+        req,
+        QueryConfig {
+            sql: "SELECT id, name FROM export_events ORDER BY id",
+            dest_table: "export_events",
+        },
+        WriteConfig {
+            writer: writer.clone(),
+            write_mode: apitap::writer::WriteMode::Append,
+            splits: Vec::new(),
+        },
+        &opts(),
+    )
+    .await
+    .unwrap();
+
+    assert_eq!(stats.request_count, 1);
+    assert_eq!(stats.total_items, 3);
+    let rows = writer.rows.lock().unwrap();
+    let names: Vec<&str> = rows.iter().map(|r| r["name"].as_str().unwrap()).collect();
+    assert_eq!(names, vec!["a", "b", "c"]);
+}
+
+#[tokio::test]
+async fn test_run_fetch_fails_on_schema_contract_drift() {
+    use apitap::pipeline::run::{run_fetch, QueryConfig, WriteConfig};
+    use apitap::utils::schema_contract::SchemaContractConfig;
+    use std::sync::Arc;
+
+    let url = serve_pages(r#"{"data": [{"id": "1", "name": "a"}]}"#)
+        .await
+        .url;
+    let mut req = request(&url, false);
+    let contract: SchemaContractConfig =
+        serde_yaml::from_str("columns: {id: Int64, name: Utf8}").unwrap();
+    req.schema_contract = Some(Arc::new(contract.load().unwrap()));
+
+    let writer = Arc::new(RowCollector::default());
+    let err = run_fetch(
+        req,
+        QueryConfig {
+            sql: "SELECT * FROM contract_orders",
+            dest_table: "contract_orders",
+        },
+        WriteConfig {
+            writer: writer.clone(),
+            write_mode: apitap::writer::WriteMode::Append,
+            splits: Vec::new(),
+        },
+        &opts(),
+    )
+    .await
+    .unwrap_err();
+
+    assert!(
+        matches!(err, apitap::errors::ApitapError::SchemaContract(_)),
+        "{err}"
+    );
+    assert!(
+        err.to_string().contains("changed id (Int64 -> LargeUtf8)"),
+        "{err}"
+    );
+    assert!(writer.rows.lock().unwrap().is_empty());
+}
+
+/// Answers `HEAD` with `head` and every other request with `body` as
+/// `text/plain`, so only the preflight names the format.
+async fn serve_with_head(head: fn() -> Reply, body: &'static str) -> String {
+    TestServer::start(move |req| {
+        if req.line().starts_with("HEAD ") {
+            head()
+        } else {
+            Reply::content("text/plain", body)
+        }
+    })
+    .await
+    .url
+}
+
+#[tokio::test]
+async fn test_preflight_picks_format_from_content_type() {
+    use apitap::http::preflight::Preflight;
+
+    let url = serve_with_head(
+        || {
+            Reply::status("200 OK")
+                .header("content-type", "text/csv")
+                .header("content-length", "19")
+        },
+        "id,name\n1,a\n2,b\n",
+    )
+    .await;
+    let mut req = request(&url, false);
+    req.data_path = None;
+    req.pagination = None;
+    req.preflight = Some(Preflight {
+        detect_format: true,
+    });
+
+    let schema = preview_schema(req, &opts()).await.unwrap();
+    assert!(schema.field_with_name("id").is_ok());
+    assert!(schema.field_with_name("name").is_ok());
+}
+
+#[tokio::test]
+async fn test_preflight_falls_back_when_head_is_rejected() {
+    use apitap::http::preflight::Preflight;
+
+    let url = serve_with_head(
+        || Reply::status("405 Method Not Allowed"),
+        r#"{"data": [{"id": 1}]}"#,
+    )
+    .await;
+    let mut req = request(&url, false);
+    req.pagination = None;
+    req.preflight = Some(Preflight {
+        detect_format: true,
+    });
+
+    let schema = preview_schema(req, &opts()).await.unwrap();
+    assert!(schema.field_with_name("id").is_ok());
+}
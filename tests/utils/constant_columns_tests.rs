@@ -0,0 +1,70 @@
+use apitap::utils::constant_columns::ConstantColumnsConfig;
+use datafusion::arrow::datatypes::{DataType, Field, Schema};
+use serde_json::json;
+
+#[test]
+fn test_constant_columns_short_and_long_form() {
+    let short: ConstantColumnsConfig =
+        serde_yaml::from_str("tenant_id: acme\nregion: eu\n").unwrap();
+    let long: ConstantColumnsConfig =
+        serde_yaml::from_str("columns: { tenant_id: acme }\noverwrite: true\n").unwrap();
+
+    assert_eq!(
+        short.resolve().unwrap().apply(json!({"id": 1})),
+        json!({"id": 1, "region": "eu", "tenant_id": "acme"})
+    );
+    assert!(matches!(long, ConstantColumnsConfig::Options(ref o) if o.overwrite));
+    assert_eq!(
+        long.resolve().unwrap().apply(json!({"tenant_id": "other"})),
+        json!({"tenant_id": "acme"})
+    );
+}
+
+#[test]
+fn test_constant_columns_resolve_env_and_templates() {
+    std::env::set_var("APITAP_TEST_TENANT", "globex");
+    let config: ConstantColumnsConfig = serde_yaml::from_str(
+        "tenant_id: \"${APITAP_TEST_TENANT}\"\nloaded_on: \"{{ current_date() }}\"\n",
+    )
+    .unwrap();
+
+    let row = config.resolve().unwrap().apply(json!({}));
+    assert_eq!(row["tenant_id"], "globex");
+    assert_eq!(row["loaded_on"], apitap::utils::template::current_date());
+
+    let missing: ConstantColumnsConfig =
+        serde_yaml::from_str("tenant_id: \"${APITAP_TEST_UNSET_TENANT}\"").unwrap();
+    assert!(missing.resolve().is_err());
+}
+
+#[test]
+fn test_constant_columns_schema_rejects_collisions_unless_overwrite() {
+    let schema = Schema::new(vec![
+        Field::new("id", DataType::Int64, false),
+        Field::new("tenant_id", DataType::Int64, true),
+    ]);
+
+    let strict: ConstantColumnsConfig = serde_yaml::from_str("tenant_id: acme").unwrap();
+    let err = strict.resolve().unwrap().schema(&schema).unwrap_err();
+    assert!(
+        err.to_string().contains("constant column 'tenant_id'"),
+        "{err}"
+    );
+
+    let overwrite: ConstantColumnsConfig =
+        serde_yaml::from_str("columns: { tenant_id: acme, region: eu }\noverwrite: true").unwrap();
+    let stamped = overwrite.resolve().unwrap().schema(&schema).unwrap();
+    let fields: Vec<(&str, &DataType)> = stamped
+        .fields()
+        .iter()
+        .map(|f| (f.name().as_str(), f.data_type()))
+        .collect();
+    assert_eq!(
+        fields,
+        vec![
+            ("id", &DataType::Int64),
+            ("region", &DataType::Utf8),
+            ("tenant_id", &DataType::Utf8),
+        ]
+    );
+}
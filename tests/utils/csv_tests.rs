@@ -0,0 +1,44 @@
+use apitap::utils::csv::csv_to_json;
+use serde_json::json;
+
+#[test]
+fn test_csv_header_keys_and_nulls() {
+    let body = b"id,name,email\n1,Ada,ada@example.com\n2,Grace,\n";
+    let rows = csv_to_json(body, b',', true).unwrap();
+
+    assert_eq!(
+        rows,
+        json!([
+            {"id": "1", "name": "Ada", "email": "ada@example.com"},
+            {"id": "2", "name": "Grace", "email": null}
+        ])
+    );
+}
+
+#[test]
+fn test_csv_quoted_fields() {
+    let body = b"id,comment\n1,\"hello, world\"\n2,\"she said \"\"hi\"\"\"\n";
+    let rows = csv_to_json(body, b',', true).unwrap();
+
+    assert_eq!(rows[0]["comment"], "hello, world");
+    assert_eq!(rows[1]["comment"], "she said \"hi\"");
+}
+
+#[test]
+fn test_csv_without_header_and_ragged_rows() {
+    let rows = csv_to_json(b"a|b\nc|d|e\n", b'|', false).unwrap();
+
+    assert_eq!(
+        rows,
+        json!([
+            {"column_1": "a", "column_2": "b"},
+            {"column_1": "c", "column_2": "d", "column_3": "e"}
+        ])
+    );
+}
+
+#[test]
+fn test_csv_header_only_is_empty() {
+    assert_eq!(csv_to_json(b"id,name\n", b',', true).unwrap(), json!([]));
+    assert_eq!(csv_to_json(b"", b',', true).unwrap(), json!([]));
+}
@@ -226,3 +226,33 @@ fn test_substitute_templates() -> Result<()> {
 
     Ok(())
 }
+
+#[test]
+fn test_substitute_env_vars_reads_secret_file() -> Result<()> {
+    let dir = tempfile::TempDir::new()?;
+    let path = dir.path().join("api_token");
+    std::fs::write(&path, "  tok-from-file\n")?;
+
+    unsafe {
+        std::env::set_var("TEST_FILE_SECRET_HOST", "api.example.com");
+    }
+    let text = format!(
+        "https://${{TEST_FILE_SECRET_HOST}}/?key=${{FILE:{}}}",
+        path.display()
+    );
+    let result = substitute_env_vars(&text)?;
+    assert_eq!(result, "https://api.example.com/?key=tok-from-file");
+    unsafe {
+        std::env::remove_var("TEST_FILE_SECRET_HOST");
+    }
+
+    Ok(())
+}
+
+#[test]
+fn test_substitute_env_vars_missing_secret_file() {
+    let err = substitute_env_vars("Bearer ${FILE:/nonexistent/apitap/secret}").unwrap_err();
+    assert!(err
+        .to_string()
+        .contains("Secret file '/nonexistent/apitap/secret' could not be read"));
+}
@@ -0,0 +1,94 @@
+use apitap::utils::dictionary::{decode_batch, DictionaryEncoding, DEFAULT_MAX_DISTINCT};
+use datafusion::arrow::array::{Array, AsArray, DictionaryArray, RecordBatch, StringArray};
+use datafusion::arrow::datatypes::{DataType, Field, FieldRef, Int32Type, Schema};
+use serde_json::json;
+use std::sync::Arc;
+
+fn dict_type() -> DataType {
+    DataType::Dictionary(Box::new(DataType::Int32), Box::new(DataType::Utf8))
+}
+
+fn fields(names: &[(&str, DataType)]) -> Vec<FieldRef> {
+    names
+        .iter()
+        .map(|(name, data_type)| Arc::new(Field::new(*name, data_type.clone(), true)))
+        .collect()
+}
+
+#[test]
+fn test_dictionary_config_defaults() {
+    let dictionary: DictionaryEncoding = serde_yaml::from_str("columns: [status]").unwrap();
+    assert_eq!(dictionary.columns, vec!["status".to_string()]);
+    assert!(!dictionary.auto);
+    assert_eq!(dictionary.max_distinct, DEFAULT_MAX_DISTINCT);
+}
+
+#[test]
+fn test_dictionary_encodes_named_string_columns_only() {
+    let dictionary = DictionaryEncoding {
+        columns: vec!["status".into(), "count".into()],
+        ..Default::default()
+    };
+    let encoded = dictionary.encode(
+        &fields(&[
+            ("status", DataType::Utf8),
+            ("count", DataType::Int64),
+            ("country", DataType::Utf8),
+        ]),
+        &[],
+    );
+    assert_eq!(encoded[0].data_type(), &dict_type());
+    // Not a string column
+    assert_eq!(encoded[1].data_type(), &DataType::Int64);
+    // Not named, and `auto` is off
+    assert_eq!(encoded[2].data_type(), &DataType::Utf8);
+}
+
+#[test]
+fn test_dictionary_auto_detection_threshold() {
+    let dictionary = DictionaryEncoding {
+        auto: true,
+        max_distinct: 3,
+        ..Default::default()
+    };
+    let samples: Vec<_> = (0..12)
+        .map(|i| {
+            let status = ["open", "paid", "void"][i % 3];
+            json!({
+                "id": format!("o-{i}"),
+                "status": status,
+                "region": format!("r{}", i % 4),
+                "note": null,
+            })
+        })
+        .collect();
+    let encoded = dictionary.encode(
+        &fields(&[
+            ("id", DataType::Utf8),
+            ("status", DataType::Utf8),
+            ("region", DataType::Utf8),
+            ("note", DataType::Utf8),
+        ]),
+        &samples,
+    );
+    // Every value distinct
+    assert_eq!(encoded[0].data_type(), &DataType::Utf8);
+    assert_eq!(encoded[1].data_type(), &dict_type());
+    // Four distinct values, over max_distinct
+    assert_eq!(encoded[2].data_type(), &DataType::Utf8);
+    // No values to judge by
+    assert_eq!(encoded[3].data_type(), &DataType::Utf8);
+}
+
+#[test]
+fn test_decode_batch_casts_dictionaries_to_text() {
+    let status: DictionaryArray<Int32Type> = vec!["open", "paid", "open"].into_iter().collect();
+    let schema = Arc::new(Schema::new(vec![Field::new("status", dict_type(), true)]));
+    let batch = RecordBatch::try_new(schema, vec![Arc::new(status)]).unwrap();
+
+    let decoded = decode_batch(batch).unwrap();
+    assert_eq!(decoded.schema().field(0).data_type(), &DataType::Utf8);
+    let values: &StringArray = decoded.column(0).as_string();
+    assert_eq!(values.len(), 3);
+    assert_eq!(values.value(2), "open");
+}
@@ -0,0 +1,78 @@
+use apitap::http::fetcher::RequestTemplate;
+use apitap::pipeline::Source;
+use apitap::utils::duplicate_keys::DuplicateKeys;
+use apitap::utils::lenient_json::LenientJson;
+use serde_json::{json, Value};
+
+#[test]
+fn test_last_matches_serde_json() {
+    let body =
+        br#"{"a": 1, "b": [1.5, -2, 18446744073709551615, null, true, "\u00e9"], "a": {"c": "x"}}"#;
+    let expected: Value = serde_json::from_slice(body).unwrap();
+    assert_eq!(DuplicateKeys::Last.parse(body).unwrap(), expected);
+    assert_eq!(DuplicateKeys::default(), DuplicateKeys::Last);
+}
+
+#[test]
+fn test_array_collects_nested_repeats() {
+    let body = br#"[
+        {"id": 1, "tags": ["a"], "tags": ["b"], "meta": {"k": 1, "k": 2, "k": 3}},
+        {"id": 2, "tags": ["c"]}
+    ]"#;
+    assert_eq!(
+        DuplicateKeys::Array.parse(body).unwrap(),
+        json!([
+            {"id": 1, "tags": [["a"], ["b"]], "meta": {"k": [1, 2, 3]}},
+            {"id": 2, "tags": ["c"]}
+        ])
+    );
+}
+
+#[test]
+fn test_error_names_the_nested_key() {
+    let err = DuplicateKeys::Error
+        .parse(br#"{"data": [{"id": 1, "id": 2}]}"#)
+        .unwrap_err();
+    assert!(err.to_string().contains("duplicate key 'id'"), "{err}");
+    assert!(DuplicateKeys::Error.parse(br#"{"id": 1}"#).is_ok());
+    assert!(DuplicateKeys::Last.parse(br#"{"id": 1} trailing"#).is_err());
+}
+
+#[test]
+fn test_duplicate_keys_from_source_yaml() {
+    let source: Source = serde_yaml::from_str(
+        "name: legacy\nurl: https://legacy.example.com/export\nduplicate_keys: array\n",
+    )
+    .unwrap();
+    assert_eq!(source.duplicate_keys, DuplicateKeys::Array);
+
+    let source: Source =
+        serde_yaml::from_str("name: legacy\nurl: https://legacy.example.com/export\n").unwrap();
+    assert_eq!(source.duplicate_keys, DuplicateKeys::Last);
+
+    assert!(serde_yaml::from_str::<Source>(
+        "name: legacy\nurl: https://legacy.example.com/export\nduplicate_keys: first\n",
+    )
+    .is_err());
+}
+
+#[test]
+fn test_request_template_combines_lenient_and_duplicate_handling() {
+    let template = RequestTemplate {
+        lenient_json: Some(LenientJson::default()),
+        duplicate_keys: DuplicateKeys::Array,
+        ..RequestTemplate::default()
+    };
+    assert_eq!(
+        template
+            .parse_body(br#"{"v": NaN, "v": 1.5, "big": 123456789012345678901234}"#)
+            .unwrap(),
+        json!({"v": [null, 1.5], "big": "123456789012345678901234"})
+    );
+
+    let strict = RequestTemplate {
+        duplicate_keys: DuplicateKeys::Error,
+        ..RequestTemplate::default()
+    };
+    assert!(strict.parse_body(br#"{"v": 1, "v": 2}"#).is_err());
+}
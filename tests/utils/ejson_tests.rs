@@ -0,0 +1,76 @@
+use apitap::utils::ejson::unwrap_extended;
+use serde_json::json;
+
+#[test]
+fn test_unwraps_canonical_wrappers() {
+    let record = json!({
+        "_id": {"$oid": "65a1f0c2e4b0a1b2c3d4e5f6"},
+        "n": {"$numberInt": "7"},
+        "big": {"$numberLong": "9007199254740993"},
+        "ratio": {"$numberDouble": "0.5"},
+        "bad": {"$numberDouble": "NaN"},
+        "price": {"$numberDecimal": "19.99"},
+        "blob": {"$binary": {"base64": "AQID", "subType": "00"}},
+        "seen": {"$timestamp": {"t": 1704067200, "i": 1}},
+        "re": {"$regularExpression": {"pattern": "^a", "options": "i"}},
+        "lo": {"$minKey": 1},
+    });
+    assert_eq!(
+        unwrap_extended(record),
+        json!({
+            "_id": "65a1f0c2e4b0a1b2c3d4e5f6",
+            "n": 7,
+            "big": 9007199254740993i64,
+            "ratio": 0.5,
+            "bad": null,
+            "price": "19.99",
+            "blob": "AQID",
+            "seen": "2024-01-01T00:00:00.000Z",
+            "re": "^a",
+            "lo": null,
+        })
+    );
+}
+
+#[test]
+fn test_unwraps_every_date_form() {
+    let expected = json!("2024-01-01T00:00:00.000Z");
+    for date in [
+        json!({"$date": "2024-01-01T00:00:00Z"}),
+        json!({"$date": "2024-01-01T01:00:00+01:00"}),
+        json!({"$date": {"$numberLong": "1704067200000"}}),
+        json!({"$date": 1704067200000i64}),
+    ] {
+        assert_eq!(unwrap_extended(date), expected);
+    }
+}
+
+#[test]
+fn test_unwraps_nested_and_legacy_forms() {
+    let record = json!({
+        "items": [{"sku": {"$oid": "a1"}, "qty": {"$numberLong": "2"}}],
+        "meta": {"owner": {"id": {"$oid": "b2"}}},
+        "legacy_bin": {"$binary": "AQID", "$type": "00"},
+        "legacy_re": {"$regex": "^x", "$options": ""},
+    });
+    assert_eq!(
+        unwrap_extended(record),
+        json!({
+            "items": [{"sku": "a1", "qty": 2}],
+            "meta": {"owner": {"id": "b2"}},
+            "legacy_bin": "AQID",
+            "legacy_re": "^x",
+        })
+    );
+}
+
+#[test]
+fn test_leaves_ordinary_objects_alone() {
+    let record = json!({
+        "filter": {"$oid": "a1", "note": "not a wrapper"},
+        "op": {"$set": {"a": 1}},
+        "plain": {"date": "2024-01-01"},
+        "odd": {"$numberLong": 5},
+    });
+    assert_eq!(unwrap_extended(record.clone()), record);
+}
@@ -0,0 +1,60 @@
+use apitap::utils::fields::FieldMapping;
+use serde_json::json;
+
+fn mapping(rename: &[(&str, &str)], drop: &[&str]) -> FieldMapping {
+    FieldMapping {
+        rename: rename
+            .iter()
+            .map(|(a, b)| (a.to_string(), b.to_string()))
+            .collect(),
+        drop: drop.iter().map(|s| s.to_string()).collect(),
+    }
+}
+
+#[test]
+fn test_rename_top_level_keys() {
+    let m = mapping(&[("usr_nm", "user_name"), ("order", "order_no")], &[]);
+    assert_eq!(
+        m.apply(json!({"usr_nm": "ada", "order": 7, "id": 1})),
+        json!({"user_name": "ada", "order_no": 7, "id": 1})
+    );
+}
+
+#[test]
+fn test_rename_swaps_without_clobbering() {
+    let m = mapping(&[("a", "b"), ("b", "a")], &[]);
+    assert_eq!(m.apply(json!({"a": 1, "b": 2})), json!({"a": 2, "b": 1}));
+}
+
+#[test]
+fn test_pointer_rename_lifts_nested_value() {
+    let m = mapping(&[("/meta/created~1at", "created_at")], &[]);
+    assert_eq!(
+        m.apply(json!({"id": 1, "meta": {"created/at": "2024-01-01", "v": 2}})),
+        json!({"id": 1, "created_at": "2024-01-01", "meta": {"v": 2}})
+    );
+}
+
+#[test]
+fn test_drop_keys_and_pointers() {
+    let m = mapping(&[], &["internal", "/meta/debug", "/missing/deep"]);
+    assert_eq!(
+        m.apply(json!({"id": 1, "internal": true, "meta": {"debug": "x", "v": 2}})),
+        json!({"id": 1, "meta": {"v": 2}})
+    );
+}
+
+#[test]
+fn test_drop_runs_before_rename() {
+    let m = mapping(&[("name", "label")], &["name"]);
+    assert_eq!(m.apply(json!({"name": "x", "id": 1})), json!({"id": 1}));
+}
+
+#[test]
+fn test_non_object_records_pass_through() {
+    let m = mapping(&[("a", "b")], &["c"]);
+    assert_eq!(m.apply(json!([1, 2])), json!([1, 2]));
+    assert_eq!(m.apply(json!("text")), json!("text"));
+    assert!(!m.is_empty());
+    assert!(FieldMapping::default().is_empty());
+}
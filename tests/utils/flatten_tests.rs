@@ -0,0 +1,99 @@
+use apitap::pipeline::Source;
+use apitap::utils::flatten::{ArrayFlatten, Flatten, FlattenConfig};
+use serde_json::json;
+
+#[test]
+fn test_flatten_joins_nested_names() {
+    let flat = Flatten::default().apply(json!({
+        "id": 7,
+        "address": {"city": "Oslo", "geo": {"lat": 59.9, "lng": 10.7}},
+        "tags": ["a", "b"],
+        "empty": {},
+    }));
+    assert_eq!(
+        flat,
+        json!({
+            "id": 7,
+            "address_city": "Oslo",
+            "address_geo_lat": 59.9,
+            "address_geo_lng": 10.7,
+            "tags": "[\"a\",\"b\"]",
+        })
+    );
+}
+
+#[test]
+fn test_flatten_max_depth_lands_deeper_objects_as_text() {
+    let flatten = Flatten {
+        max_depth: Some(1),
+        ..Flatten::default()
+    };
+    assert_eq!(
+        flatten.apply(json!({"a": {"b": {"c": 1}}, "d": {"e": null}})),
+        json!({"a_b": "{\"c\":1}", "d_e": null})
+    );
+
+    let flatten = Flatten {
+        max_depth: Some(0),
+        ..Flatten::default()
+    };
+    assert_eq!(
+        flatten.apply(json!({"a": {"b": 1}})),
+        json!({"a": "{\"b\":1}"})
+    );
+}
+
+#[test]
+fn test_flatten_indexes_arrays() {
+    let flatten = Flatten {
+        separator: "__".to_string(),
+        max_depth: None,
+        arrays: ArrayFlatten::Index,
+    };
+    assert_eq!(
+        flatten.apply(json!({"items": [{"sku": "x"}, {"sku": "y"}], "n": []})),
+        json!({"items__0__sku": "x", "items__1__sku": "y"})
+    );
+}
+
+#[test]
+fn test_flatten_leaves_non_objects_alone() {
+    assert_eq!(Flatten::default().apply(json!([1, 2])), json!([1, 2]));
+    assert_eq!(Flatten::default().apply(json!("x")), json!("x"));
+}
+
+#[test]
+fn test_flatten_from_source_yaml() {
+    let source: Source =
+        serde_yaml::from_str("name: users\nurl: https://api.example.com/users\nflatten: true\n")
+            .unwrap();
+    assert_eq!(
+        source.flatten.and_then(|f| f.resolve()),
+        Some(Flatten::default())
+    );
+
+    let source: Source = serde_yaml::from_str(
+        r#"
+name: users
+url: https://api.example.com/users
+flatten:
+  separator: "."
+  max_depth: 2
+  arrays: index
+"#,
+    )
+    .unwrap();
+    assert_eq!(
+        source.flatten.and_then(|f| f.resolve()),
+        Some(Flatten {
+            separator: ".".to_string(),
+            max_depth: Some(2),
+            arrays: ArrayFlatten::Index,
+        })
+    );
+
+    assert_eq!(FlattenConfig::Enabled(false).resolve(), None);
+    let source: Source =
+        serde_yaml::from_str("name: users\nurl: https://api.example.com/users\n").unwrap();
+    assert!(source.flatten.is_none());
+}
@@ -0,0 +1,106 @@
+use apitap::pipeline::{Retry, RetryJitter};
+use apitap::utils::http_retry::JitteredBackoff;
+use reqwest_retry::{RetryDecision, RetryPolicy};
+use std::time::{Duration, SystemTime};
+
+fn backoff(jitter: RetryJitter) -> JitteredBackoff {
+    JitteredBackoff::from_config(&Retry {
+        max_attempts: 4,
+        min_delay_secs: 1,
+        max_delay_secs: 10,
+        jitter,
+        max_elapsed_secs: None,
+    })
+}
+
+#[test]
+fn test_base_delay_doubles_and_caps() {
+    let b = backoff(RetryJitter::None);
+    assert_eq!(b.base_delay(0), Duration::from_secs(1));
+    assert_eq!(b.base_delay(1), Duration::from_secs(2));
+    assert_eq!(b.base_delay(3), Duration::from_secs(8));
+    assert_eq!(b.base_delay(4), Duration::from_secs(10));
+    assert_eq!(b.base_delay(40), Duration::from_secs(10));
+}
+
+#[test]
+fn test_no_jitter_is_exact() {
+    let b = backoff(RetryJitter::None);
+    for n in 0..6 {
+        assert_eq!(b.delay(n), b.base_delay(n));
+    }
+}
+
+#[test]
+fn test_full_jitter_within_bounds() {
+    let b = backoff(RetryJitter::Full);
+    for n in 0..6 {
+        let max = b.base_delay(n);
+        for _ in 0..200 {
+            assert!(b.delay(n) <= max);
+        }
+    }
+}
+
+#[test]
+fn test_equal_jitter_within_bounds() {
+    let b = backoff(RetryJitter::Equal);
+    for n in 0..6 {
+        let max = b.base_delay(n);
+        for _ in 0..200 {
+            let d = b.delay(n);
+            assert!(
+                d >= max / 2 && d <= max,
+                "{d:?} outside [{:?}, {max:?}]",
+                max / 2
+            );
+        }
+    }
+}
+
+#[test]
+fn test_jitter_spreads_delays() {
+    let b = backoff(RetryJitter::Full);
+    let delays: std::collections::HashSet<Duration> = (0..50).map(|_| b.delay(3)).collect();
+    assert!(delays.len() > 1, "full jitter should not retry in lockstep");
+}
+
+#[test]
+fn test_policy_stops_after_max_attempts() {
+    let b = backoff(RetryJitter::Full);
+    let start = SystemTime::now();
+    assert!(matches!(
+        b.should_retry(start, 3),
+        RetryDecision::Retry { .. }
+    ));
+    assert!(matches!(
+        b.should_retry(start, 4),
+        RetryDecision::DoNotRetry
+    ));
+}
+
+#[test]
+fn test_policy_stops_after_max_elapsed() {
+    let b = JitteredBackoff {
+        max_elapsed: Some(Duration::from_secs(30)),
+        ..backoff(RetryJitter::None)
+    };
+
+    let recent = SystemTime::now() - Duration::from_secs(5);
+    assert!(matches!(
+        b.should_retry(recent, 0),
+        RetryDecision::Retry { .. }
+    ));
+
+    // 29s in, the next 2s delay would land past the 30s budget
+    let old = SystemTime::now() - Duration::from_secs(29);
+    assert!(matches!(b.should_retry(old, 1), RetryDecision::DoNotRetry));
+}
+
+#[test]
+fn test_delay_never_exceeds_interval_cap() {
+    let b = backoff(RetryJitter::Equal);
+    for n in 0..64 {
+        assert!(b.delay(n) <= b.max_delay);
+    }
+}
@@ -0,0 +1,43 @@
+use apitap::utils::json_path::{select, select_first};
+use serde_json::json;
+
+#[test]
+fn test_select_json_pointer() {
+    let v = json!({"data": {"items": [1, 2, 3]}});
+    let out = select(&v, "/data/items");
+    assert_eq!(out, vec![&json!([1, 2, 3])]);
+}
+
+#[test]
+fn test_select_dotted_maps_over_arrays() {
+    let v = json!({
+        "data": {"viewer": {"edges": [
+            {"node": {"id": "a"}},
+            {"node": {"id": "b"}}
+        ]}}
+    });
+    let ids: Vec<_> = select(&v, "data.viewer.edges.node.id");
+    assert_eq!(ids, vec![&json!("a"), &json!("b")]);
+}
+
+#[test]
+fn test_select_wildcard_object_values() {
+    let v = json!({"data": {"repository": {"pageInfo": {"hasNextPage": true}}}});
+    assert_eq!(
+        select_first(&v, "data.*.pageInfo.hasNextPage"),
+        Some(&json!(true))
+    );
+}
+
+#[test]
+fn test_select_numeric_index() {
+    let v = json!({"items": [{"id": 1}, {"id": 2}]});
+    assert_eq!(select_first(&v, "items.1.id"), Some(&json!(2)));
+}
+
+#[test]
+fn test_select_first_skips_nulls_and_missing() {
+    let v = json!({"next": null});
+    assert_eq!(select_first(&v, "next"), None);
+    assert_eq!(select_first(&v, "missing.path"), None);
+}
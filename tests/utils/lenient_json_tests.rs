@@ -0,0 +1,112 @@
+use apitap::pipeline::Source;
+use apitap::utils::lenient_json::{BigNumbers, LenientJson, NonFinite};
+use serde_json::json;
+
+#[test]
+fn test_non_finite_tokens_become_null() {
+    let parsed = LenientJson::default()
+        .parse(br#"{"a": NaN, "b": Infinity, "c": -Infinity, "d": [1, NaN]}"#)
+        .unwrap();
+    assert_eq!(
+        parsed,
+        json!({"a": null, "b": null, "c": null, "d": [1, null]})
+    );
+}
+
+#[test]
+fn test_non_finite_tokens_as_strings() {
+    let lenient = LenientJson {
+        non_finite: NonFinite::String,
+        ..LenientJson::default()
+    };
+    assert_eq!(
+        lenient
+            .parse(b"[NaN, Infinity, -Infinity, +Infinity]")
+            .unwrap(),
+        json!(["NaN", "Infinity", "-Infinity", "Infinity"])
+    );
+}
+
+#[test]
+fn test_string_contents_are_untouched() {
+    let parsed = LenientJson::default()
+        .parse(br#"{"note": "NaN \"Infinity\" 123456789012345678901234", "n": 1}"#)
+        .unwrap();
+    assert_eq!(
+        parsed,
+        json!({"note": "NaN \"Infinity\" 123456789012345678901234", "n": 1})
+    );
+}
+
+#[test]
+fn test_big_numbers_keep_their_digits() {
+    let parsed = LenientJson::default()
+        .parse(
+            br#"[18446744073709551615, -9223372036854775808, 99999999999999999999,
+                 0.1, 1.5e3, 3.14159265358979323846, 1e400]"#,
+        )
+        .unwrap();
+    assert_eq!(
+        parsed,
+        json!([
+            18446744073709551615u64,
+            i64::MIN,
+            "99999999999999999999",
+            0.1,
+            1500.0,
+            "3.14159265358979323846",
+            "1e400"
+        ])
+    );
+}
+
+#[test]
+fn test_big_numbers_as_float() {
+    let lenient = LenientJson {
+        big_numbers: BigNumbers::Float,
+        ..LenientJson::default()
+    };
+    assert_eq!(
+        lenient.parse(b"[99999999999999999999]").unwrap(),
+        json!([1e20])
+    );
+}
+
+#[test]
+fn test_invalid_json_still_fails() {
+    assert!(LenientJson::default().parse(b"{\"a\": nope}").is_err());
+}
+
+#[test]
+fn test_lenient_json_from_source_yaml() {
+    let source: Source = serde_yaml::from_str(
+        "name: readings\nurl: https://api.example.com/readings\nlenient_json: true\n",
+    )
+    .unwrap();
+    assert_eq!(
+        source.lenient_json.and_then(|l| l.resolve()),
+        Some(LenientJson::default())
+    );
+
+    let source: Source = serde_yaml::from_str(
+        r#"
+name: readings
+url: https://api.example.com/readings
+lenient_json:
+  non_finite: string
+  big_numbers: float
+"#,
+    )
+    .unwrap();
+    assert_eq!(
+        source.lenient_json.and_then(|l| l.resolve()),
+        Some(LenientJson {
+            non_finite: NonFinite::String,
+            big_numbers: BigNumbers::Float,
+        })
+    );
+
+    let source: Source =
+        serde_yaml::from_str("name: readings\nurl: https://api.example.com/readings\n").unwrap();
+    assert!(source.lenient_json.is_none());
+}
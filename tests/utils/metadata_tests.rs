@@ -0,0 +1,76 @@
+use apitap::pipeline::Config;
+use apitap::utils::metadata::MetadataColumns;
+use datafusion::arrow::datatypes::{DataType, Field, Schema};
+use serde_json::json;
+
+#[test]
+fn test_metadata_schema_replaces_same_named_columns() {
+    let columns = MetadataColumns {
+        source: Some("_source".to_string()),
+        page: Some("_page".to_string()),
+        ..MetadataColumns::default()
+    };
+    let stamp = columns.stamp("orders_api", "orders");
+    let schema = Schema::new(vec![
+        Field::new("_source", DataType::Int64, true),
+        Field::new("id", DataType::Int64, false),
+    ]);
+
+    let stamped = stamp.schema(&schema);
+    let fields: Vec<(&str, &DataType)> = stamped
+        .fields()
+        .iter()
+        .map(|f| (f.name().as_str(), f.data_type()))
+        .collect();
+    assert_eq!(
+        fields,
+        vec![
+            ("id", &DataType::Int64),
+            ("_source", &DataType::Utf8),
+            ("_page", &DataType::UInt64),
+        ]
+    );
+
+    // The API's own `_source` is overwritten; an unknown page is null
+    assert_eq!(
+        stamp.apply(json!({"id": 1, "_source": 9}), None),
+        json!({"id": 1, "_source": "orders_api", "_page": null})
+    );
+    assert_eq!(stamp.apply(json!([1, 2]), Some(1)), json!([1, 2]));
+}
+
+#[test]
+fn test_metadata_columns_from_defaults_and_source() {
+    let config_yaml = r#"
+defaults:
+  metadata_columns:
+    ingested_at: _ingested_at
+    source: _source
+sources:
+  - name: orders
+    url: https://api.example.com/orders
+    retry: { max_attempts: 1, max_delay_secs: 1, min_delay_secs: 1 }
+  - name: users
+    url: https://api.example.com/users
+    metadata_columns:
+      module: _module
+    retry: { max_attempts: 1, max_delay_secs: 1, min_delay_secs: 1 }
+targets: []
+"#;
+    let config: Config = serde_yaml::from_str(config_yaml).unwrap();
+
+    let orders = config.metadata_columns_for(config.source("orders").unwrap());
+    assert_eq!(orders.ingested_at.as_deref(), Some("_ingested_at"));
+    assert_eq!(orders.source.as_deref(), Some("_source"));
+
+    // A source's own block replaces the defaults
+    let users = config.metadata_columns_for(config.source("users").unwrap());
+    assert_eq!(
+        users,
+        &MetadataColumns {
+            module: Some("_module".to_string()),
+            ..MetadataColumns::default()
+        }
+    );
+    assert!(MetadataColumns::default().is_empty());
+}
@@ -1,3 +1,24 @@
+mod constant_columns_tests;
+mod csv_tests;
 mod custom_macro_tests;
+mod dictionary_tests;
+mod duplicate_keys_tests;
+mod ejson_tests;
+mod fields_tests;
+mod flatten_tests;
+mod http_retry_tests;
+mod json_path_tests;
+mod lenient_json_tests;
+mod metadata_tests;
+mod nulls_tests;
+mod numbers_tests;
+mod progress_tests;
+mod redact_tests;
+mod schema_contract_tests;
 mod schema_tests;
+mod secrets_tests;
 mod streaming_tests;
+mod table_provider_tests;
+mod transform_tests;
+mod udf_tests;
+mod xml_tests;
@@ -0,0 +1,63 @@
+use apitap::pipeline::Source;
+use apitap::utils::nulls::NullHandling;
+use serde_json::json;
+
+fn handling(values: &[&str]) -> NullHandling {
+    NullHandling {
+        null_values: values.iter().map(|s| s.to_string()).collect(),
+        ..NullHandling::default()
+    }
+}
+
+#[test]
+fn test_null_values_coerce_matching_strings() {
+    let nulls = handling(&["", "null", "N/A"]);
+    assert_eq!(
+        nulls.apply(json!({"a": "", "b": "null", "c": "N/A", "d": "n/a", "e": 0, "f": [""]})),
+        json!({"a": null, "b": null, "c": null, "d": "n/a", "e": 0, "f": [""]})
+    );
+}
+
+#[test]
+fn test_empty_handling_keeps_empty_strings() {
+    let nulls = NullHandling::default();
+    assert!(nulls.is_empty());
+    assert_eq!(
+        nulls.apply(json!({"note": "", "flag": "null"})),
+        json!({"note": "", "flag": "null"})
+    );
+}
+
+#[test]
+fn test_column_null_values_override_global_list() {
+    let nulls = NullHandling {
+        null_values: vec!["".to_string()],
+        column_null_values: [
+            ("price".to_string(), vec!["-".to_string()]),
+            ("notes".to_string(), Vec::new()),
+        ]
+        .into(),
+    };
+    assert_eq!(
+        nulls.apply(json!({"price": "-", "notes": "", "name": ""})),
+        json!({"price": null, "notes": "", "name": null})
+    );
+    assert_eq!(nulls.apply(json!({"price": ""})), json!({"price": ""}));
+}
+
+#[test]
+fn test_null_handling_from_source_yaml() {
+    let source: Source = serde_yaml::from_str(
+        r#"
+name: products
+url: https://api.example.com/products
+null_values: ["", "null"]
+column_null_values:
+  price: ["N/A"]
+"#,
+    )
+    .unwrap();
+    assert_eq!(source.nulls.null_values, vec!["", "null"]);
+    assert_eq!(source.nulls.column_null_values["price"], vec!["N/A"]);
+    assert!(!source.nulls.is_empty());
+}
@@ -0,0 +1,78 @@
+use apitap::utils::numbers::{LargeIntegers, NumberHandling};
+use serde_json::json;
+use std::collections::BTreeSet;
+
+const BIG: u64 = 18_446_744_073_709_551_615;
+
+fn handling(mode: LargeIntegers, string_columns: &[&str]) -> NumberHandling {
+    NumberHandling {
+        large_integers: mode,
+        string_columns: string_columns.iter().map(|s| s.to_string()).collect(),
+    }
+}
+
+#[test]
+fn test_keep_leaves_records_alone() {
+    let records = vec![json!({"id": BIG}), json!({"id": -1})];
+    let out = NumberHandling::default()
+        .apply_page(records.clone())
+        .unwrap();
+    assert_eq!(out, records);
+}
+
+#[test]
+fn test_string_mode_converts_whole_column_on_page() {
+    let out = handling(LargeIntegers::String, &[])
+        .apply_page(vec![
+            json!({"id": -1, "n": 5}),
+            json!({"id": BIG, "n": 6}),
+            json!({"id": null, "n": 7}),
+        ])
+        .unwrap();
+    assert_eq!(out[0], json!({"id": "-1", "n": 5}));
+    assert_eq!(out[1], json!({"id": BIG.to_string(), "n": 6}));
+    assert_eq!(out[2], json!({"id": null, "n": 7}));
+}
+
+#[test]
+fn test_string_columns_always_text() {
+    let out = handling(LargeIntegers::Keep, &["account"])
+        .apply_page(vec![json!({"account": 42, "amount": 1.5})])
+        .unwrap();
+    assert_eq!(out[0], json!({"account": "42", "amount": 1.5}));
+}
+
+#[test]
+fn test_error_mode_names_column() {
+    let err = handling(LargeIntegers::Error, &[])
+        .apply_page(vec![json!({"id": 1}), json!({"tweet_id": BIG})])
+        .unwrap_err();
+    assert!(err.to_string().contains("'tweet_id'"), "{err}");
+
+    // Listed columns are exempt
+    let out = handling(LargeIntegers::Error, &["tweet_id"])
+        .apply_page(vec![json!({"tweet_id": BIG})])
+        .unwrap();
+    assert_eq!(out[0]["tweet_id"], json!(BIG.to_string()));
+}
+
+#[test]
+fn test_streamed_overflow_after_sampling_fails() {
+    let numbers = handling(LargeIntegers::String, &[]);
+    let columns: BTreeSet<String> = ["id".to_string()].into();
+
+    assert_eq!(
+        numbers.apply_streamed(json!({"id": 1}), &columns).unwrap(),
+        json!({"id": "1"})
+    );
+    assert!(numbers
+        .apply_streamed(json!({"other": BIG}), &columns)
+        .is_err());
+}
+
+#[test]
+fn test_deserializes_flat_keys() {
+    let numbers: NumberHandling =
+        serde_yaml::from_str("large_integers: string\nstring_columns: [id]\n").unwrap();
+    assert_eq!(numbers, handling(LargeIntegers::String, &["id"]));
+}
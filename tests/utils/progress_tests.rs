@@ -0,0 +1,47 @@
+use apitap::errors::ApitapError;
+use apitap::utils::progress::{ProgressOpts, WriteProgress};
+use futures::StreamExt;
+use serde_json::json;
+use std::time::Duration;
+
+#[tokio::test]
+async fn test_counts_rows_and_pages() {
+    let progress = WriteProgress::start(
+        "orders",
+        &ProgressOpts {
+            interval: None,
+            every_rows: Some(2),
+        },
+    );
+    progress.add_page();
+    progress.add_page();
+    progress.add_rows(3);
+    progress.add_rows(4);
+    assert_eq!(progress.pages(), 2);
+    assert_eq!(progress.rows(), 7);
+}
+
+#[tokio::test]
+async fn test_count_rows_skips_errors() {
+    let progress = WriteProgress::start(
+        "orders",
+        &ProgressOpts {
+            interval: Some(Duration::from_millis(10)),
+            every_rows: None,
+        },
+    );
+    let stream = futures::stream::iter(vec![
+        Ok(json!({"id": 1})),
+        Err(ApitapError::PipelineError("bad row".to_string())),
+        Ok(json!({"id": 2})),
+    ])
+    .boxed();
+
+    let items: Vec<_> = progress.count_rows(stream).collect().await;
+    assert_eq!(items.len(), 3);
+    assert_eq!(progress.rows(), 2);
+
+    // The ticker keeps running without panicking while rows are idle
+    tokio::time::sleep(Duration::from_millis(30)).await;
+    assert_eq!(progress.rows(), 2);
+}
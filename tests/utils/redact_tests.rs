@@ -0,0 +1,85 @@
+use apitap::utils::redact::{RedactConfig, Redactor, MASK};
+
+#[test]
+fn test_redact_url_masks_default_params() {
+    let r = Redactor::default();
+    assert_eq!(
+        r.redact_url("https://api.example.com/v1/items?API_KEY=abc123&page=2&password=p%40ss"),
+        format!("https://api.example.com/v1/items?API_KEY={MASK}&page=2&password={MASK}")
+    );
+    // Names that merely contain a sensitive word are left alone
+    assert_eq!(
+        r.redact_url("https://api.example.com/?monkey=1&tokens=2"),
+        "https://api.example.com/?monkey=1&tokens=2"
+    );
+}
+
+#[test]
+fn test_redact_url_handles_bracket_params() {
+    let r = Redactor::default();
+    assert_eq!(
+        r.redact_url("/v1?key[]=a&key[]=b&id=1"),
+        format!("/v1?key[]={MASK}&key[]={MASK}&id=1")
+    );
+}
+
+#[test]
+fn test_redact_header_is_case_insensitive() {
+    let r = Redactor::default();
+    assert_eq!(r.redact_header("X-Api-Key", "abc"), MASK);
+    assert_eq!(r.redact_header("COOKIE", "session=1"), MASK);
+    assert_eq!(r.redact_header("Content-Type", "text/csv"), "text/csv");
+}
+
+#[test]
+fn test_redact_text_masks_embedded_urls_and_headers() {
+    let r = Redactor::default();
+    let msg = "error sending request for url (https://h.example.com/x?token=s3cr3t&page=1)";
+    assert_eq!(
+        r.redact_text(msg),
+        format!("error sending request for url (https://h.example.com/x?token={MASK}&page=1)")
+    );
+
+    let debug_headers = r#"{"authorization": "Bearer s3cr3t", "accept": "*/*"}"#;
+    assert_eq!(
+        r.redact_text(debug_headers),
+        format!(r#"{{"authorization": "{MASK}", "accept": "*/*"}}"#)
+    );
+
+    let plain = "Authorization: Basic dXNlcjpwYXNz\nAccept: */*";
+    assert_eq!(
+        r.redact_text(plain),
+        format!("Authorization: {MASK}\nAccept: */*")
+    );
+}
+
+#[test]
+fn test_config_extends_denylist() {
+    let cfg: RedactConfig = serde_yaml::from_str(
+        r#"
+headers: [X-Partner-Session]
+query_params: [sig]
+"#,
+    )
+    .unwrap();
+    let r = Redactor::from_config(&cfg);
+
+    assert!(r.is_sensitive_header("x-partner-session"));
+    assert!(r.is_sensitive_header("authorization"));
+    assert!(r.is_sensitive_param("SIG"));
+    assert!(r.is_sensitive_param("token"));
+    assert_eq!(r.redact_url("/a?sig=1&b=2"), format!("/a?sig={MASK}&b=2"));
+}
+
+#[tokio::test]
+async fn test_reqwest_error_display_is_redacted() {
+    // Nothing listens on port 1, so the request fails with the URL in the message
+    let err = reqwest::get("http://127.0.0.1:1/items?token=s3cr3t")
+        .await
+        .unwrap_err();
+    assert!(err.to_string().contains("s3cr3t"));
+
+    let msg = apitap::errors::ApitapError::from(err).to_string();
+    assert!(!msg.contains("s3cr3t"), "{msg}");
+    assert!(msg.contains(&format!("token={MASK}")), "{msg}");
+}
@@ -0,0 +1,102 @@
+use apitap::utils::schema_contract::{ContractPolicy, SchemaContractConfig};
+use datafusion::arrow::datatypes::{DataType, Field, Schema};
+
+fn contract(policy: &str) -> SchemaContractConfig {
+    serde_yaml::from_str(&format!(
+        "policy: {policy}\ncolumns: {{id: Int64, total: Float64, note: Utf8}}"
+    ))
+    .unwrap()
+}
+
+fn schema(fields: &[(&str, DataType)]) -> Schema {
+    Schema::new(
+        fields
+            .iter()
+            .map(|(name, ty)| Field::new(*name, ty.clone(), true))
+            .collect::<Vec<_>>(),
+    )
+}
+
+#[test]
+fn test_contract_matches_nulls_whole_numbers_and_text() {
+    let contract = contract("fail").load().unwrap();
+    let inferred = schema(&[
+        ("id", DataType::Null),
+        ("total", DataType::Int64),
+        ("note", DataType::LargeUtf8),
+    ]);
+
+    assert!(contract.diff(&inferred).is_empty());
+    contract.check("orders", &inferred).unwrap();
+}
+
+#[test]
+fn test_contract_policies() {
+    let added = schema(&[
+        ("id", DataType::Int64),
+        ("total", DataType::Float64),
+        ("note", DataType::Utf8),
+        ("coupon", DataType::Utf8),
+    ]);
+    let retyped = schema(&[("id", DataType::Utf8), ("total", DataType::Float64)]);
+
+    let fail = contract("fail").load().unwrap();
+    let err = fail.check("orders", &added).unwrap_err();
+    assert_eq!(
+        err.to_string(),
+        "schema contract violated for 'orders': added coupon (Utf8)"
+    );
+
+    let warn = contract("warn").load().unwrap();
+    warn.check("orders", &added).unwrap();
+    warn.check("orders", &retyped).unwrap();
+
+    let evolve = contract("evolve").load().unwrap();
+    evolve.check("orders", &added).unwrap();
+    let err = evolve.check("orders", &retyped).unwrap_err();
+    assert!(
+        err.to_string()
+            .ends_with("removed note; changed id (Int64 -> Utf8)"),
+        "{err}"
+    );
+}
+
+#[test]
+fn test_contract_file_and_invalid_configs() {
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("orders.yaml");
+    std::fs::write(&path, "columns:\n  id: Int64\n  tags: List(Utf8)\n").unwrap();
+    let config = SchemaContractConfig {
+        file: Some(path),
+        policy: ContractPolicy::Evolve,
+        ..Default::default()
+    };
+    let contract = config.load().unwrap();
+    assert!(contract
+        .diff(&schema(&[
+            ("id", DataType::Int64),
+            (
+                "tags",
+                DataType::List(Field::new("item", DataType::Utf8, true).into())
+            ),
+        ]))
+        .removed
+        .is_empty());
+
+    let both = SchemaContractConfig {
+        columns: [("id".to_string(), "Int64".to_string())].into(),
+        ..config.clone()
+    };
+    assert!(both.load().unwrap_err().to_string().contains("not both"));
+    assert!(SchemaContractConfig::default()
+        .load()
+        .unwrap_err()
+        .to_string()
+        .contains("needs columns or a file"));
+    let bad: SchemaContractConfig = serde_yaml::from_str("columns: {id: BigInt}").unwrap();
+    assert!(bad
+        .load()
+        .unwrap_err()
+        .to_string()
+        .contains("schema_contract column 'id': unknown type 'BigInt'"));
+}
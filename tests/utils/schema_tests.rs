@@ -1,4 +1,7 @@
-use apitap::utils::schema::{infer_schema_from_values, infer_schema_streaming};
+use apitap::utils::schema::{
+    infer_schema_from_values, infer_schema_streaming, raw_json_schema, wrap_raw_json,
+    RAW_JSON_COLUMN,
+};
 use datafusion::arrow::datatypes::DataType;
 use futures::stream;
 use serde_json::{json, Value};
@@ -256,3 +259,22 @@ async fn test_infer_schema_streaming_arrays_as_strings() {
     let tags_field = schema.field_with_name("tags").unwrap();
     assert!(matches!(tags_field.data_type(), DataType::Utf8));
 }
+
+#[test]
+fn test_raw_json_schema_single_text_column() {
+    let schema = raw_json_schema();
+
+    assert_eq!(schema.fields().len(), 1);
+    assert_eq!(schema.field(0).name(), RAW_JSON_COLUMN);
+    assert_eq!(schema.field(0).data_type(), &DataType::Utf8);
+}
+
+#[test]
+fn test_wrap_raw_json_round_trips() {
+    let record = json!({"id": 7, "nested": {"a": [1, 2]}, "irregular": null});
+    let row = wrap_raw_json(&record).unwrap();
+
+    let text = row[RAW_JSON_COLUMN].as_str().unwrap();
+    let parsed: Value = serde_json::from_str(text).unwrap();
+    assert_eq!(parsed, record);
+}
@@ -0,0 +1,95 @@
+use apitap::errors::{ApitapError, Result};
+use apitap::pipeline::Config;
+use apitap::utils::secrets::{
+    cached_secret, resolve_config_secrets, resolve_secrets, secret_refs, set_secret_provider,
+    EnvSecretProvider, SecretProvider,
+};
+use apitap::utils::template::substitute_env_vars;
+use std::sync::Arc;
+
+/// Answers `vault/<name>` keys with `s3cr3t-<name>`.
+struct FakeVault;
+
+#[async_trait::async_trait]
+impl SecretProvider for FakeVault {
+    async fn get(&self, key: &str) -> Result<String> {
+        key.strip_prefix("vault/")
+            .map(|name| format!("s3cr3t-{name}"))
+            .ok_or_else(|| ApitapError::ConfigError(format!("no secret '{key}'")))
+    }
+}
+
+#[test]
+fn test_env_provider_maps_keys_to_variables() {
+    assert_eq!(
+        EnvSecretProvider::new().var_name("db/password"),
+        "DB_PASSWORD"
+    );
+    assert_eq!(
+        EnvSecretProvider::with_prefix("APP_").var_name("api.token-v2"),
+        "APP_API_TOKEN_V2"
+    );
+}
+
+#[tokio::test]
+async fn test_env_provider_reads_variable() {
+    std::env::set_var("APITAP_TEST_SECRETS_PARTNER_TOKEN", "tok-123");
+    let provider = EnvSecretProvider::with_prefix("APITAP_TEST_SECRETS_");
+    assert_eq!(provider.get("partner/token").await.unwrap(), "tok-123");
+
+    let err = provider.get("partner/missing").await.unwrap_err();
+    assert!(err
+        .to_string()
+        .contains("APITAP_TEST_SECRETS_PARTNER_MISSING"));
+}
+
+#[test]
+fn test_secret_refs_lists_keys() {
+    assert_eq!(
+        secret_refs("${SECRET:a/b} and ${ENV_VAR} and ${SECRET: c }"),
+        vec!["a/b", "c"]
+    );
+    assert!(secret_refs("${FILE:/run/secrets/x}").is_empty());
+}
+
+#[tokio::test]
+async fn test_resolved_secrets_are_substituted() {
+    set_secret_provider(Arc::new(FakeVault));
+
+    let cfg: Config = serde_yaml::from_str(
+        r#"
+sources: []
+targets:
+  - type: postgres
+    name: pg
+    host: db.internal
+    database: app
+    auth: { username: etl, password: "${SECRET:vault/pg_password}" }
+"#,
+    )
+    .unwrap();
+    resolve_config_secrets(&cfg).await.unwrap();
+    assert_eq!(
+        cached_secret("vault/pg_password").unwrap(),
+        "s3cr3t-pg_password"
+    );
+
+    resolve_secrets(["Bearer ${SECRET:vault/api_token}"])
+        .await
+        .unwrap();
+    assert_eq!(
+        substitute_env_vars("Bearer ${SECRET:vault/api_token}").unwrap(),
+        "Bearer s3cr3t-api_token"
+    );
+
+    let err = resolve_secrets(["${SECRET:elsewhere/token}"])
+        .await
+        .unwrap_err();
+    assert!(err.to_string().contains("elsewhere/token"));
+}
+
+#[test]
+fn test_unresolved_secret_fails_substitution() {
+    let err = substitute_env_vars("${SECRET:never/resolved}").unwrap_err();
+    assert!(err.to_string().contains("never/resolved"));
+}
@@ -0,0 +1,91 @@
+use apitap::utils::datafusion_ext::DataFrameExt;
+use apitap::utils::table_provider::{register_lookups, Lookup, LookupFormat};
+use datafusion::prelude::SessionContext;
+use serde_json::json;
+
+fn lookup(name: &str, path: &std::path::Path, format: Option<LookupFormat>) -> Lookup {
+    Lookup {
+        name: name.to_string(),
+        path: path.to_string_lossy().into_owned(),
+        format,
+    }
+}
+
+#[test]
+fn test_lookup_format_from_extension() {
+    let csv = lookup("a", "codes.CSV".as_ref(), None);
+    assert_eq!(csv.resolved_format().unwrap(), LookupFormat::Csv);
+
+    let ndjson = lookup("b", "codes.ndjson".as_ref(), None);
+    assert_eq!(ndjson.resolved_format().unwrap(), LookupFormat::Json);
+
+    let explicit = lookup("c", "codes.txt".as_ref(), Some(LookupFormat::Csv));
+    assert_eq!(explicit.resolved_format().unwrap(), LookupFormat::Csv);
+
+    let unknown = lookup("d", "codes.txt".as_ref(), None);
+    assert!(unknown.resolved_format().is_err());
+}
+
+#[tokio::test]
+async fn test_register_lookups_joins_csv_and_json() {
+    let dir = tempfile::tempdir().unwrap();
+    let countries = dir.path().join("countries.csv");
+    std::fs::write(&countries, "code,country\nFR,France\nDE,Germany\n").unwrap();
+    let categories = dir.path().join("categories.json");
+    std::fs::write(
+        &categories,
+        r#"[{"category_id": 1, "category": "books"}, {"category_id": 2, "category": "music"}]"#,
+    )
+    .unwrap();
+
+    let ctx = SessionContext::new();
+    ctx.sql(
+        "CREATE VIEW orders AS SELECT * FROM \
+         (VALUES (1, 'FR', 2), (2, 'DE', 1)) AS t(id, code, category_id)",
+    )
+    .await
+    .unwrap();
+
+    register_lookups(
+        &ctx,
+        &[
+            lookup("country_codes", &countries, None),
+            lookup("categories", &categories, None),
+        ],
+    )
+    .await
+    .unwrap();
+
+    let df = ctx
+        .sql(
+            "SELECT id, country, category FROM orders \
+             JOIN country_codes USING (code) \
+             JOIN categories USING (category_id) ORDER BY id",
+        )
+        .await
+        .unwrap();
+    assert_eq!(
+        df.to_json().await.unwrap(),
+        json!([
+            {"id": 1, "country": "France", "category": "music"},
+            {"id": 2, "country": "Germany", "category": "books"}
+        ])
+    );
+}
+
+#[tokio::test]
+async fn test_register_lookups_reloads_changed_file() {
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("codes.ndjson");
+    std::fs::write(&path, "{\"code\": \"FR\"}\n").unwrap();
+
+    let ctx = SessionContext::new();
+    let lookups = [lookup("codes", &path, None)];
+    register_lookups(&ctx, &lookups).await.unwrap();
+
+    std::fs::write(&path, "{\"code\": \"FR\"}\n{\"code\": \"DE\"}\n").unwrap();
+    register_lookups(&ctx, &lookups).await.unwrap();
+
+    let df = ctx.sql("SELECT count(*) AS n FROM codes").await.unwrap();
+    assert_eq!(df.to_json().await.unwrap(), json!([{"n": 2}]));
+}
@@ -0,0 +1,124 @@
+use apitap::errors::ApitapError;
+use apitap::utils::transform::RecordTransform;
+use serde_json::json;
+
+fn transform(statements: &[&str]) -> RecordTransform {
+    let statements: Vec<String> = statements.iter().map(|s| s.to_string()).collect();
+    RecordTransform::parse(&statements).unwrap()
+}
+
+#[test]
+fn test_empty_transform_is_noop() {
+    let t = transform(&[]);
+    assert!(t.is_empty());
+    assert_eq!(t.apply(json!({"a": 1})), json!({"a": 1}));
+}
+
+#[test]
+fn test_lift_nested_field_and_delete() {
+    let t = transform(&[".city = .address.city", "del(.address, .debug)"]);
+    let out = t.apply(json!({"id": 1, "address": {"city": "Oslo"}, "debug": true}));
+    assert_eq!(out, json!({"id": 1, "city": "Oslo"}));
+}
+
+#[test]
+fn test_coalesce_operator_and_function() {
+    let t = transform(&[
+        ".total = .amount // .price // 0",
+        ".name = coalesce(.nick, .full_name, \"unknown\")",
+    ]);
+    assert_eq!(
+        t.apply(json!({"amount": null, "price": 7})),
+        json!({"amount": null, "price": 7, "total": 7, "name": "unknown"})
+    );
+    assert_eq!(t.apply(json!({"full_name": "Ada"}))["total"], json!(0));
+    assert_eq!(t.apply(json!({"full_name": "Ada"}))["name"], json!("Ada"));
+}
+
+#[test]
+fn test_functions() {
+    let t = transform(&[
+        ".id = tostring(.id)",
+        ".qty = tonumber(.qty)",
+        ".bad = tonumber(.bad)",
+        ".code = upper(.code)",
+        ".email = lower(.email)",
+        ".meta = tostring(.meta)",
+    ]);
+    let out = t.apply(json!({
+        "id": 12345678901234567890u64,
+        "qty": " 42 ",
+        "bad": "n/a",
+        "code": "ab",
+        "email": "Ada@Example.COM",
+        "meta": {"k": 1}
+    }));
+    assert_eq!(
+        out,
+        json!({
+            "id": "12345678901234567890",
+            "qty": 42,
+            "bad": null,
+            "code": "AB",
+            "email": "ada@example.com",
+            "meta": "{\"k\":1}"
+        })
+    );
+}
+
+#[test]
+fn test_quoted_keys_array_indexes_and_nested_assign() {
+    let t = transform(&[
+        ".first_sku = .items.0.sku",
+        ".\"full name\" = .names.\"first name\"",
+        ".meta.source = \"api\"",
+        "del(.items.1)",
+    ]);
+    let out = t.apply(json!({
+        "items": [{"sku": "a"}, {"sku": "b"}],
+        "names": {"first name": "Ada"}
+    }));
+    assert_eq!(
+        out,
+        json!({
+            "items": [{"sku": "a"}],
+            "names": {"first name": "Ada"},
+            "first_sku": "a",
+            "full name": "Ada",
+            "meta": {"source": "api"}
+        })
+    );
+}
+
+#[test]
+fn test_statements_see_earlier_results() {
+    let t = transform(&[".a = 1", ".b = .a", ".a = true"]);
+    assert_eq!(t.apply(json!({})), json!({"a": true, "b": 1}));
+}
+
+#[test]
+fn test_non_object_records_pass_through() {
+    let t = transform(&[".a = 1"]);
+    assert_eq!(t.apply(json!([1, 2])), json!([1, 2]));
+    assert_eq!(t.apply(json!("x")), json!("x"));
+}
+
+#[test]
+fn test_parse_errors_name_the_statement() {
+    for bad in [
+        "a = 1",
+        ".a = ",
+        ".a = frobnicate(.b)",
+        ".a = tostring(.b, .c)",
+        ".a = \"unterminated",
+        ".a = 1 2",
+        "del(.a",
+        ".a = bogus",
+    ] {
+        let err = RecordTransform::parse(&[bad.to_string()]).unwrap_err();
+        assert!(
+            matches!(&err, ApitapError::ConfigError(msg) if msg.contains(bad)),
+            "{bad}: {err}"
+        );
+    }
+}
@@ -0,0 +1,78 @@
+use apitap::utils::datafusion_ext::{get_shared_context, register_udf, DataFrameExt, JsonValueExt};
+use apitap::utils::udf::{create_coercing_udf, geohash_encode};
+use datafusion::arrow::datatypes::DataType;
+use datafusion::execution::FunctionRegistry;
+use datafusion::logical_expr::ColumnarValue;
+use serde_json::json;
+use std::sync::Arc;
+
+#[test]
+fn test_geohash_encode_known_points() {
+    assert_eq!(
+        geohash_encode(57.64911, 10.40744, 11).unwrap(),
+        "u4pruydqqvj"
+    );
+    assert_eq!(geohash_encode(48.8584, 2.2945, 7).unwrap(), "u09tunq");
+    assert_eq!(geohash_encode(0.0, 0.0, 1).unwrap(), "s");
+}
+
+#[test]
+fn test_geohash_encode_rejects_bad_input() {
+    assert!(geohash_encode(0.0, 0.0, 0).is_err());
+    assert!(geohash_encode(0.0, 0.0, 13).is_err());
+    assert!(geohash_encode(91.0, 0.0, 5).is_err());
+}
+
+#[tokio::test]
+async fn test_builtin_udfs_available_in_sql() {
+    let rows = json!([
+        {"id": 1, "url": "https://api.example.com/v1/users", "lat": 48.8584, "lon": 2.2945},
+        {"id": 2, "url": "not a url", "lat": 57.64911, "lon": 10.40744}
+    ]);
+
+    let sdf = rows
+        .to_sql(
+            "udf_builtin_src",
+            "SELECT id, url_host(url) AS host, geohash(lat, lon, 5) AS cell \
+             FROM udf_builtin_src ORDER BY id",
+        )
+        .await
+        .unwrap();
+    let out = sdf.inner().to_json().await.unwrap();
+
+    assert_eq!(out[0]["host"], "api.example.com");
+    assert_eq!(out[0]["cell"], "u09tu");
+    assert!(out[1].get("host").is_none_or(|h| h.is_null()));
+    assert_eq!(out[1]["cell"], "u4pru");
+}
+
+#[tokio::test]
+async fn test_register_custom_udf() {
+    // JSON integers are inferred as UInt64; the signature casts them to Int64
+    let double_it = create_coercing_udf(
+        "double_it",
+        vec![DataType::Int64],
+        DataType::Int64,
+        Arc::new(|args: &[ColumnarValue]| {
+            let arrays = ColumnarValue::values_to_arrays(args)?;
+            let ints = datafusion::common::cast::as_int64_array(&arrays[0])?;
+            let doubled: datafusion::arrow::array::Int64Array =
+                ints.iter().map(|v| v.map(|v| v * 2)).collect();
+            Ok(ColumnarValue::Array(Arc::new(doubled)))
+        }),
+    );
+    register_udf(double_it).await;
+
+    let ctx = get_shared_context().await;
+    assert!(ctx.udf("double_it").is_ok());
+
+    let sdf = json!([{"n": 21}])
+        .to_sql(
+            "udf_custom_src",
+            "SELECT double_it(n) AS n FROM udf_custom_src",
+        )
+        .await
+        .unwrap();
+    let out = sdf.inner().to_json().await.unwrap();
+    assert_eq!(out[0]["n"], 42);
+}
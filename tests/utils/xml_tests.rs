@@ -0,0 +1,47 @@
+use apitap::utils::xml::xml_to_json;
+use serde_json::json;
+
+#[test]
+fn test_xml_text_only_elements_become_strings() {
+    let v = xml_to_json(b"<user><id>42</id><name>Ada</name><nick/></user>").unwrap();
+    assert_eq!(
+        v,
+        json!({"user": {"id": "42", "name": "Ada", "nick": null}})
+    );
+}
+
+#[test]
+fn test_xml_repeated_elements_become_arrays() {
+    let xml = br#"<?xml version="1.0"?>
+<feed>
+  <entry><id>1</id></entry>
+  <entry><id>2</id></entry>
+  <entry><id>3</id></entry>
+</feed>"#;
+    let v = xml_to_json(xml).unwrap();
+    assert_eq!(
+        v.pointer("/feed/entry").unwrap(),
+        &json!([{"id": "1"}, {"id": "2"}, {"id": "3"}])
+    );
+}
+
+#[test]
+fn test_xml_attributes_and_mixed_text() {
+    let v = xml_to_json(br#"<price currency="EUR">9.99</price>"#).unwrap();
+    assert_eq!(v, json!({"price": {"@currency": "EUR", "#text": "9.99"}}));
+}
+
+#[test]
+fn test_xml_entities_and_cdata() {
+    let xml = b"<note><title>Tom &amp; Jerry &#x263A;</title><body><![CDATA[<b>raw</b> & more]]></body></note>";
+    let v = xml_to_json(xml).unwrap();
+    assert_eq!(v["note"]["title"], "Tom & Jerry \u{263A}");
+    assert_eq!(v["note"]["body"], "<b>raw</b> & more");
+}
+
+#[test]
+fn test_xml_malformed_is_error() {
+    assert!(xml_to_json(b"<a><b></a>").is_err());
+    assert!(xml_to_json(b"<a>").is_err());
+    assert!(xml_to_json(b"").is_err());
+}
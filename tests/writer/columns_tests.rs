@@ -0,0 +1,140 @@
+use apitap::errors::Result;
+use apitap::utils::datafusion_ext::{QueryResult, QueryResultStream};
+use apitap::writer::columns::{ColumnFilterWriter, ColumnSelection};
+use apitap::writer::{DataWriter, WriteMode};
+use datafusion::arrow::datatypes::{DataType, Field, Schema, SchemaRef};
+use futures::StreamExt;
+use serde_json::{json, Value};
+use std::sync::{Arc, Mutex};
+
+#[derive(Default)]
+struct Memory {
+    rows: Mutex<Vec<Value>>,
+    schema: Mutex<Option<SchemaRef>>,
+}
+
+#[async_trait::async_trait]
+impl DataWriter for Memory {
+    async fn write(&self, result: QueryResult) -> Result<()> {
+        self.rows.lock().unwrap().push(result.data);
+        Ok(())
+    }
+
+    async fn write_stream(&self, result: QueryResultStream, _mode: WriteMode) -> Result<()> {
+        *self.schema.lock().unwrap() = result.schema;
+        let mut data = result.data;
+        while let Some(row) = data.next().await {
+            self.rows.lock().unwrap().push(row?);
+        }
+        Ok(())
+    }
+}
+
+fn stream() -> QueryResultStream {
+    QueryResultStream {
+        table_name: "orders".into(),
+        data: futures::stream::iter(vec![
+            Ok(json!({"id": 1, "name": "a", "raw": "{}"})),
+            Ok(json!({"id": 2, "name": "b", "raw": "{}"})),
+        ])
+        .boxed(),
+        schema: Some(Arc::new(Schema::new(vec![
+            Field::new("id", DataType::Int64, false),
+            Field::new("name", DataType::Utf8, true),
+            Field::new("raw", DataType::Utf8, true),
+        ]))),
+    }
+}
+
+fn field_names(schema: &Schema) -> Vec<&str> {
+    schema.fields().iter().map(|f| f.name().as_str()).collect()
+}
+
+#[tokio::test]
+async fn test_ignore_columns_drops_rows_and_schema_fields() {
+    let memory = Arc::new(Memory::default());
+    let writer = ColumnFilterWriter::new(
+        memory.clone(),
+        ColumnSelection {
+            write_columns: None,
+            ignore_columns: vec!["raw".into(), "not_there".into()],
+        },
+    );
+
+    writer
+        .write_stream(stream(), WriteMode::Append)
+        .await
+        .unwrap();
+
+    let schema = memory.schema.lock().unwrap().clone().unwrap();
+    assert_eq!(field_names(&schema), ["id", "name"]);
+    assert_eq!(
+        *memory.rows.lock().unwrap(),
+        vec![json!({"id": 1, "name": "a"}), json!({"id": 2, "name": "b"})]
+    );
+}
+
+#[tokio::test]
+async fn test_write_columns_projects_in_listed_order() {
+    let memory = Arc::new(Memory::default());
+    let writer = ColumnFilterWriter::new(
+        memory.clone(),
+        ColumnSelection {
+            write_columns: Some(vec!["name".into(), "id".into()]),
+            ignore_columns: vec!["id".into()],
+        },
+    );
+
+    writer
+        .write_stream(stream(), WriteMode::Append)
+        .await
+        .unwrap();
+
+    let schema = memory.schema.lock().unwrap().clone().unwrap();
+    assert_eq!(field_names(&schema), ["name"]);
+    assert_eq!(memory.rows.lock().unwrap()[0], json!({"name": "a"}));
+}
+
+#[tokio::test]
+async fn test_write_columns_missing_from_result_fails() {
+    let memory = Arc::new(Memory::default());
+    let writer = ColumnFilterWriter::new(
+        memory.clone(),
+        ColumnSelection {
+            write_columns: Some(vec!["id".into(), "email".into()]),
+            ignore_columns: Vec::new(),
+        },
+    );
+
+    let err = writer
+        .write_stream(stream(), WriteMode::Append)
+        .await
+        .unwrap_err();
+    assert!(err
+        .to_string()
+        .contains("write_columns: column 'email' is not in the result"));
+    assert!(memory.rows.lock().unwrap().is_empty());
+}
+
+#[tokio::test]
+async fn test_column_filter_projects_in_memory_results() {
+    let memory = Arc::new(Memory::default());
+    let writer = ColumnFilterWriter::wrap(
+        memory.clone(),
+        ColumnSelection {
+            write_columns: None,
+            ignore_columns: vec!["raw".into()],
+        },
+    );
+
+    writer
+        .write(QueryResult {
+            table_name: "orders".into(),
+            data: json!([{"id": 1, "raw": "{}"}]),
+            row_count: 1,
+        })
+        .await
+        .unwrap();
+
+    assert_eq!(memory.rows.lock().unwrap()[0], json!([{"id": 1}]));
+}
@@ -0,0 +1,52 @@
+use apitap::utils::datafusion_ext::{QueryError, QueryResult};
+use apitap::writer::dead_letter::DeadLetterWriter;
+use apitap::writer::DataWriter;
+use serde_json::{json, Value};
+
+#[tokio::test]
+async fn test_file_dead_letter_appends_ndjson_lines() {
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("dlq").join("dead_letters.ndjson");
+    let writer = DeadLetterWriter::file(&path);
+
+    for (id, reason) in [
+        (2, "violates check constraint"),
+        (3, "violates not-null constraint"),
+    ] {
+        writer
+            .on_error(QueryError {
+                table_name: "orders".into(),
+                error: reason.into(),
+                record: Some(json!({"id": id})),
+            })
+            .await
+            .unwrap();
+    }
+
+    let lines: Vec<Value> = std::fs::read_to_string(&path)
+        .unwrap()
+        .lines()
+        .map(|line| serde_json::from_str(line).unwrap())
+        .collect();
+    assert_eq!(lines.len(), 2);
+    assert_eq!(lines[0]["table"], "orders");
+    assert_eq!(lines[0]["error"], "violates check constraint");
+    assert_eq!(lines[0]["record"], json!({"id": 2}));
+    assert_eq!(lines[1]["record"], json!({"id": 3}));
+}
+
+#[tokio::test]
+async fn test_dead_letter_writer_rejects_plain_writes() {
+    let dir = tempfile::tempdir().unwrap();
+    let writer = DeadLetterWriter::file(dir.path().join("dead_letters.ndjson"));
+
+    let err = writer
+        .write(QueryResult {
+            table_name: "orders".into(),
+            data: json!([{"id": 1}]),
+            row_count: 1,
+        })
+        .await
+        .unwrap_err();
+    assert!(err.to_string().contains("on_error"));
+}
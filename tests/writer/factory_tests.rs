@@ -0,0 +1,307 @@
+use apitap::cmd::{run_modules_once, RunOptions};
+use apitap::errors::{ApitapError, Result};
+use apitap::pipeline::sink::{Hook, HookFuture, WriterOpts};
+use apitap::pipeline::{Config, CustomSink, Target};
+use apitap::utils::datafusion_ext::{QueryResult, QueryResultStream};
+use apitap::writer::factory::{register_writer_factory, writer_factory, WriterFactory};
+use apitap::writer::{DataWriter, WriteMode};
+use futures::StreamExt;
+use serde::Deserialize;
+use serde_json::{json, Value};
+use std::fs;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use tempfile::TempDir;
+
+#[derive(Default)]
+struct Memory {
+    rows: Mutex<Vec<Value>>,
+}
+
+#[async_trait::async_trait]
+impl DataWriter for Memory {
+    async fn write(&self, result: QueryResult) -> Result<()> {
+        self.rows.lock().unwrap().push(result.data);
+        Ok(())
+    }
+
+    async fn write_stream(&self, result: QueryResultStream, _mode: WriteMode) -> Result<()> {
+        let mut data = result.data;
+        while let Some(row) = data.next().await {
+            self.rows.lock().unwrap().push(row?);
+        }
+        Ok(())
+    }
+
+    async fn merge(&self, result: QueryResultStream) -> Result<()> {
+        self.write_stream(result, WriteMode::Merge).await
+    }
+}
+
+#[derive(Deserialize)]
+struct MemoryOptions {
+    bucket: String,
+}
+
+/// Hands out one shared writer and records what it was built for.
+#[derive(Default)]
+struct MemoryFactory {
+    writer: Arc<Memory>,
+    built: Mutex<Vec<(String, String, bool)>>,
+}
+
+impl WriterFactory for MemoryFactory {
+    fn make_writer(
+        &self,
+        sink: &CustomSink,
+        opts: &WriterOpts<'_>,
+    ) -> Result<(Arc<dyn DataWriter>, Option<Hook>)> {
+        let options: MemoryOptions = sink.options()?;
+        self.built.lock().unwrap().push((
+            options.bucket,
+            opts.dest_table.to_string(),
+            opts.auto_create,
+        ));
+        Ok((self.writer.clone(), None))
+    }
+}
+
+/// Records the SQL of modules without a source instead of writing rows.
+#[derive(Default)]
+struct SqlFactory {
+    statements: Arc<Mutex<Vec<(String, String)>>>,
+}
+
+impl WriterFactory for SqlFactory {
+    fn make_writer(
+        &self,
+        _sink: &CustomSink,
+        _opts: &WriterOpts<'_>,
+    ) -> Result<(Arc<dyn DataWriter>, Option<Hook>)> {
+        Ok((Arc::new(Memory::default()), None))
+    }
+
+    fn execute_sql(&self, sink: &CustomSink, sql: &str) -> Result<HookFuture> {
+        let statements = self.statements.clone();
+        let entry = (sink.name.clone(), sql.trim().to_string());
+        Ok(Box::pin(async move {
+            statements.lock().unwrap().push(entry);
+            Ok(())
+        }))
+    }
+}
+
+/// Runs SQL that never finishes.
+struct HangingSqlFactory;
+
+impl WriterFactory for HangingSqlFactory {
+    fn make_writer(
+        &self,
+        _sink: &CustomSink,
+        _opts: &WriterOpts<'_>,
+    ) -> Result<(Arc<dyn DataWriter>, Option<Hook>)> {
+        Ok((Arc::new(Memory::default()), None))
+    }
+
+    fn execute_sql(&self, _sink: &CustomSink, _sql: &str) -> Result<HookFuture> {
+        Ok(Box::pin(std::future::pending()))
+    }
+}
+
+#[test]
+fn test_custom_target_keeps_unknown_keys_as_options() {
+    register_writer_factory("factory_test_parse", Arc::new(MemoryFactory::default()));
+    let config: Config = serde_yaml::from_str(
+        r#"
+sources: []
+targets:
+  - type: factory_test_parse
+    name: mem
+    bucket: b1
+    auto_create: false
+"#,
+    )
+    .unwrap();
+
+    let Target::Custom(sink) = config.target("mem").unwrap() else {
+        panic!("expected a custom target");
+    };
+    assert_eq!(sink.kind, "factory_test_parse");
+    assert_eq!(sink.tables.auto_create, Some(false));
+    assert_eq!(sink.options.get("bucket"), Some(&json!("b1")));
+    assert!(!sink.options.contains_key("auto_create"));
+
+    let round_trip = serde_json::to_value(config.target("mem").unwrap()).unwrap();
+    assert_eq!(
+        round_trip,
+        json!({"type": "factory_test_parse", "name": "mem", "bucket": "b1", "auto_create": false})
+    );
+}
+
+#[test]
+fn test_builtin_target_errors_are_not_treated_as_custom() {
+    let err =
+        serde_yaml::from_str::<Config>("sources: []\ntargets:\n  - type: postgres\n    name: pg\n")
+            .unwrap_err();
+    assert!(err.to_string().contains("missing field"), "{err}");
+}
+
+#[test]
+fn test_unregistered_custom_type_fails_to_load() {
+    let dir = TempDir::new().unwrap();
+    let path = dir.path().join("pipeline.yaml");
+    fs::write(
+        &path,
+        "sources: []\ntargets:\n  - type: factory_test_missing\n    name: nowhere\n",
+    )
+    .unwrap();
+
+    assert!(writer_factory("factory_test_missing").is_none());
+    let err = apitap::config::load_config_from_path(&path).unwrap_err();
+    assert!(
+        err.to_string()
+            .contains("target 'nowhere' has unknown type 'factory_test_missing'"),
+        "{err}"
+    );
+}
+
+#[tokio::test]
+async fn test_module_writes_through_registered_factory() {
+    let factory = Arc::new(MemoryFactory::default());
+    register_writer_factory("factory_test_run", factory.clone());
+
+    let dir = TempDir::new().unwrap();
+    let root = dir.path().join("modules");
+    fs::create_dir(&root).unwrap();
+    let records = dir.path().join("users.json");
+    fs::write(&records, r#"[{"id": 1}, {"id": 2}]"#).unwrap();
+    fs::write(
+        root.join("users.sql"),
+        r#"{{ sink(name="mem", auto_create=false) }}SELECT id FROM {{ use_source("users") }}"#,
+    )
+    .unwrap();
+    let config: Config = serde_yaml::from_str(&format!(
+        r#"
+sources:
+  - name: users
+    kind: file
+    path: {}
+targets:
+  - type: factory_test_run
+    name: mem
+    bucket: raw
+"#,
+        records.display()
+    ))
+    .unwrap();
+
+    let results = run_modules_once(root.to_str().unwrap(), &[], &config, &RunOptions::default())
+        .await
+        .unwrap();
+    assert!(results[0].is_success(), "{:?}", results[0].result);
+
+    assert_eq!(
+        *factory.built.lock().unwrap(),
+        vec![("raw".to_string(), "users".to_string(), false)]
+    );
+    let mut ids: Vec<Value> = factory
+        .writer
+        .rows
+        .lock()
+        .unwrap()
+        .iter()
+        .map(|row| row["id"].clone())
+        .collect();
+    ids.sort_by_key(|id| id.as_i64());
+    assert_eq!(ids, vec![json!(1), json!(2)]);
+}
+
+#[tokio::test]
+async fn test_module_without_source_runs_sql_on_target() {
+    let factory = Arc::new(SqlFactory::default());
+    register_writer_factory("factory_test_sql", factory.clone());
+    register_writer_factory("factory_test_no_sql", Arc::new(MemoryFactory::default()));
+
+    let dir = TempDir::new().unwrap();
+    let root = dir.path().join("modules");
+    fs::create_dir(&root).unwrap();
+    fs::write(
+        root.join("summary.sql"),
+        r#"{{ sink(name="rows_only", on_error="continue") }}{{ sink(name="warehouse") }}
+INSERT INTO summary SELECT day, count(*) FROM fact GROUP BY day"#,
+    )
+    .unwrap();
+    let config: Config = serde_yaml::from_str(
+        r#"
+sources: []
+targets:
+  - type: factory_test_no_sql
+    name: rows_only
+    bucket: raw
+  - type: factory_test_sql
+    name: warehouse
+"#,
+    )
+    .unwrap();
+
+    let results = run_modules_once(root.to_str().unwrap(), &[], &config, &RunOptions::default())
+        .await
+        .unwrap();
+    assert!(results[0].is_success(), "{:?}", results[0].result);
+    assert_eq!(results[0].source, "");
+    assert_eq!(
+        *factory.statements.lock().unwrap(),
+        vec![(
+            "warehouse".to_string(),
+            "INSERT INTO summary SELECT day, count(*) FROM fact GROUP BY day".to_string()
+        )]
+    );
+
+    // A target that can't run SQL fails the module when it is the only sink
+    fs::write(
+        root.join("summary.sql"),
+        r#"{{ sink(name="rows_only") }}DELETE FROM fact"#,
+    )
+    .unwrap();
+    let results = run_modules_once(root.to_str().unwrap(), &[], &config, &RunOptions::default())
+        .await
+        .unwrap();
+    let err = results[0].result.as_ref().unwrap_err();
+    assert!(
+        err.to_string().contains("cannot run SQL-only modules"),
+        "{err}"
+    );
+}
+
+#[tokio::test]
+async fn test_module_without_source_times_out() {
+    register_writer_factory("factory_test_hanging_sql", Arc::new(HangingSqlFactory));
+
+    let dir = TempDir::new().unwrap();
+    let root = dir.path().join("modules");
+    fs::create_dir(&root).unwrap();
+    fs::write(
+        root.join("summary.sql"),
+        r#"{{ sink(name="warehouse") }}DELETE FROM fact"#,
+    )
+    .unwrap();
+    let config: Config = serde_yaml::from_str(
+        r#"
+sources: []
+targets:
+  - type: factory_test_hanging_sql
+    name: warehouse
+"#,
+    )
+    .unwrap();
+
+    let mut opts = RunOptions::default();
+    opts.fetch_opts.timeout = Some(Duration::from_secs(1));
+    let results = run_modules_once(root.to_str().unwrap(), &[], &config, &opts)
+        .await
+        .unwrap();
+    let err = results[0].result.as_ref().unwrap_err();
+    assert!(matches!(err, ApitapError::Timeout(_)), "{err}");
+    assert!(err.to_string().contains("'summary.sql' exceeded 1s"), "{err}");
+    assert!(!err.to_string().contains("rolled back"), "{err}");
+}
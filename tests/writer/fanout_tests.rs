@@ -0,0 +1,196 @@
+use apitap::errors::{ApitapError, Result};
+use apitap::utils::datafusion_ext::{QueryResult, QueryResultStream};
+use apitap::writer::fanout::{FanOutWriter, SinkErrorPolicy, SinkWriter};
+use apitap::writer::{DataWriter, WriteMode};
+use futures::StreamExt;
+use serde_json::{json, Value};
+use std::sync::{Arc, Mutex};
+
+#[derive(Default)]
+struct Memory {
+    rows: Mutex<Vec<Value>>,
+    calls: Mutex<Vec<String>>,
+    /// Fail after reading this many rows.
+    fail_after: Option<usize>,
+}
+
+impl Memory {
+    fn failing_after(n: usize) -> Self {
+        Self {
+            fail_after: Some(n),
+            ..Self::default()
+        }
+    }
+
+    fn calls(&self) -> Vec<String> {
+        self.calls.lock().unwrap().clone()
+    }
+}
+
+#[async_trait::async_trait]
+impl DataWriter for Memory {
+    async fn write(&self, result: QueryResult) -> Result<()> {
+        self.rows.lock().unwrap().push(result.data);
+        Ok(())
+    }
+
+    async fn write_stream(&self, result: QueryResultStream, mode: WriteMode) -> Result<()> {
+        self.calls.lock().unwrap().push(format!("{mode:?}"));
+        let mut data = result.data;
+        while let Some(row) = data.next().await {
+            if self.fail_after == Some(self.rows.lock().unwrap().len()) {
+                return Err(ApitapError::WriterError("disk full".into()));
+            }
+            self.rows.lock().unwrap().push(row?);
+        }
+        Ok(())
+    }
+
+    async fn begin(&self) -> Result<()> {
+        self.calls.lock().unwrap().push("begin".into());
+        Ok(())
+    }
+
+    async fn commit(&self) -> Result<()> {
+        self.calls.lock().unwrap().push("commit".into());
+        Ok(())
+    }
+
+    async fn rollback(&self) -> Result<()> {
+        self.calls.lock().unwrap().push("rollback".into());
+        Ok(())
+    }
+}
+
+fn sink(
+    name: &str,
+    writer: &Arc<Memory>,
+    write_mode: WriteMode,
+    on_error: SinkErrorPolicy,
+) -> SinkWriter {
+    SinkWriter {
+        name: name.into(),
+        writer: writer.clone(),
+        write_mode,
+        on_error,
+    }
+}
+
+fn rows(n: usize) -> QueryResultStream {
+    QueryResultStream {
+        table_name: "orders".into(),
+        data: futures::stream::iter((0..n).map(|i| Ok(json!({ "id": i })))).boxed(),
+        schema: None,
+    }
+}
+
+#[tokio::test]
+async fn test_fanout_copies_rows_to_every_sink() {
+    let warehouse = Arc::new(Memory::default());
+    let archive = Arc::new(Memory::default());
+    let writer = FanOutWriter::new(vec![
+        sink(
+            "warehouse",
+            &warehouse,
+            WriteMode::Merge,
+            SinkErrorPolicy::Fail,
+        ),
+        sink(
+            "archive",
+            &archive,
+            WriteMode::Append,
+            SinkErrorPolicy::Fail,
+        ),
+    ]);
+
+    writer.begin().await.unwrap();
+    writer
+        .write_stream(rows(3000), WriteMode::Merge)
+        .await
+        .unwrap();
+    writer.commit().await.unwrap();
+
+    assert_eq!(warehouse.rows.lock().unwrap().len(), 3000);
+    assert_eq!(archive.rows.lock().unwrap().len(), 3000);
+    // Each sink writes with its own mode
+    assert_eq!(warehouse.calls(), vec!["begin", "Merge", "commit"]);
+    assert_eq!(archive.calls(), vec!["begin", "Append", "commit"]);
+    assert!(writer.failures().is_empty());
+}
+
+#[tokio::test]
+async fn test_fanout_continues_past_best_effort_sink() {
+    let warehouse = Arc::new(Memory::default());
+    let archive = Arc::new(Memory::failing_after(10));
+    let writer = FanOutWriter::new(vec![
+        sink(
+            "warehouse",
+            &warehouse,
+            WriteMode::Merge,
+            SinkErrorPolicy::Fail,
+        ),
+        sink(
+            "archive",
+            &archive,
+            WriteMode::Append,
+            SinkErrorPolicy::Continue,
+        ),
+    ]);
+
+    writer.begin().await.unwrap();
+    writer
+        .write_stream(rows(100), WriteMode::Merge)
+        .await
+        .unwrap();
+    writer.commit().await.unwrap();
+
+    assert_eq!(warehouse.rows.lock().unwrap().len(), 100);
+    assert_eq!(archive.calls(), vec!["begin", "Append", "rollback"]);
+    let failures = writer.failures();
+    assert_eq!(failures.len(), 1);
+    assert_eq!(failures[0].0, "archive");
+    assert!(failures[0].1.contains("disk full"));
+}
+
+#[tokio::test]
+async fn test_fanout_fails_on_required_sink() {
+    let warehouse = Arc::new(Memory::failing_after(10));
+    let archive = Arc::new(Memory::default());
+    let writer = FanOutWriter::new(vec![
+        sink(
+            "warehouse",
+            &warehouse,
+            WriteMode::Merge,
+            SinkErrorPolicy::Fail,
+        ),
+        sink(
+            "archive",
+            &archive,
+            WriteMode::Append,
+            SinkErrorPolicy::Continue,
+        ),
+    ]);
+
+    let err = writer
+        .write_stream(rows(100), WriteMode::Merge)
+        .await
+        .unwrap_err();
+    assert!(err.to_string().contains("sink 'warehouse'"));
+
+    writer.rollback().await.unwrap();
+    assert_eq!(warehouse.calls(), vec!["Merge", "rollback"]);
+    assert_eq!(archive.calls(), vec!["Append", "rollback"]);
+}
+
+#[test]
+fn test_sink_error_policy_parse() {
+    assert_eq!(
+        "continue".parse::<SinkErrorPolicy>().unwrap(),
+        SinkErrorPolicy::Continue
+    );
+    assert_eq!(
+        " FAIL ".parse::<SinkErrorPolicy>().unwrap(),
+        SinkErrorPolicy::Fail
+    );
+    assert!("ignore".parse::<SinkErrorPolicy>().is_err());
+}
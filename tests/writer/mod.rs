@@ -1,2 +1,12 @@
+mod columns_tests;
+mod dead_letter_tests;
+mod factory_tests;
+mod fanout_tests;
+#[cfg(feature = "object_store")]
+mod object_store_tests;
+mod post_check_tests;
+#[cfg(feature = "postgres")]
 mod postgres_tests;
+#[cfg(feature = "snowflake")]
+mod snowflake_tests;
 mod writer_tests;
@@ -0,0 +1,191 @@
+// Tests for the object store Parquet writer
+//
+// These tests cover:
+// - URL parsing into store + prefix
+// - Object key layout with partition templates
+// - Streaming rows into a Parquet object and reading it back
+// - Truncate deleting objects under the table prefix
+// - Parquet compression and row-group settings
+
+use apitap::pipeline::{ParquetCompression, ParquetOptions};
+use apitap::utils::datafusion_ext::QueryResultStream;
+use apitap::utils::template::current_date;
+use apitap::writer::object_store::{store_from_url, writer_properties, ObjectStoreWriter};
+use apitap::writer::{DataWriter, WriteMode};
+use datafusion::parquet::arrow::arrow_reader::ParquetRecordBatchReaderBuilder;
+use datafusion::parquet::file::metadata::ParquetMetaData;
+use futures::{StreamExt, TryStreamExt};
+use serde_json::json;
+
+fn rows_stream(rows: Vec<serde_json::Value>) -> QueryResultStream {
+    QueryResultStream {
+        table_name: "events".to_string(),
+        data: futures::stream::iter(rows.into_iter().map(Ok)).boxed(),
+        schema: None,
+    }
+}
+
+#[test]
+fn test_store_from_url_rejects_unknown_scheme() {
+    assert!(store_from_url("ftp://host/path").is_err());
+}
+
+#[test]
+fn test_object_path_with_date_partition() {
+    let (store, prefix) = store_from_url("memory:///lake/raw").unwrap();
+    let writer = ObjectStoreWriter::new(store, prefix, "events")
+        .with_partition(Some("dt={{ current_date() }}".to_string()));
+
+    let path = writer.object_path().unwrap().to_string();
+    let expected = format!("lake/raw/events/dt={}/part-", current_date());
+    assert!(path.starts_with(&expected), "{path}");
+    assert!(path.ends_with(".parquet"));
+}
+
+#[tokio::test]
+async fn test_write_stream_round_trips_parquet() {
+    let (store, prefix) = store_from_url("memory:///lake").unwrap();
+    let writer = ObjectStoreWriter::new(store.clone(), prefix, "events").with_batch_size(2);
+
+    let rows = vec![
+        json!({"id": 1, "name": "a"}),
+        json!({"id": 2, "name": "b"}),
+        json!({"id": 3, "name": "c"}),
+    ];
+    writer
+        .write_stream(rows_stream(rows), WriteMode::Append)
+        .await
+        .unwrap();
+
+    let objects: Vec<_> = store.list(None).try_collect().await.unwrap();
+    assert_eq!(objects.len(), 1);
+
+    let bytes = store
+        .get(&objects[0].location)
+        .await
+        .unwrap()
+        .bytes()
+        .await
+        .unwrap();
+    let reader = ParquetRecordBatchReaderBuilder::try_new(bytes)
+        .unwrap()
+        .build()
+        .unwrap();
+    let total: usize = reader.map(|b| b.unwrap().num_rows()).sum();
+    assert_eq!(total, 3);
+}
+
+#[tokio::test]
+async fn test_empty_stream_writes_nothing_and_truncate_clears_prefix() {
+    let (store, prefix) = store_from_url("memory:///lake").unwrap();
+    let writer = ObjectStoreWriter::new(store.clone(), prefix, "events");
+
+    writer
+        .write_stream(rows_stream(vec![]), WriteMode::Append)
+        .await
+        .unwrap();
+    assert_eq!(store.list(None).count().await, 0);
+
+    writer
+        .write_stream(rows_stream(vec![json!({"id": 1})]), WriteMode::Append)
+        .await
+        .unwrap();
+    assert_eq!(store.list(None).count().await, 1);
+
+    writer.truncate().await.unwrap();
+    assert_eq!(store.list(None).count().await, 0);
+}
+
+/// Writes `rows` with `parquet` settings to a single object and reads back
+/// its footer and row count.
+async fn write_one_object(
+    parquet: ParquetOptions,
+    rows: Vec<serde_json::Value>,
+) -> (ParquetMetaData, usize) {
+    let (store, prefix) = store_from_url("memory:///lake").unwrap();
+    let writer = ObjectStoreWriter::new(store.clone(), prefix, "events").with_parquet(parquet);
+    writer
+        .write_stream(rows_stream(rows), WriteMode::Append)
+        .await
+        .unwrap();
+
+    let objects: Vec<_> = store.list(None).try_collect().await.unwrap();
+    assert_eq!(objects.len(), 1);
+    let bytes = store
+        .get(&objects[0].location)
+        .await
+        .unwrap()
+        .bytes()
+        .await
+        .unwrap();
+    let builder = ParquetRecordBatchReaderBuilder::try_new(bytes).unwrap();
+    let metadata = builder.metadata().as_ref().clone();
+    let rows = builder
+        .build()
+        .unwrap()
+        .map(|b| b.unwrap().num_rows())
+        .sum();
+    (metadata, rows)
+}
+
+#[tokio::test]
+async fn test_parquet_defaults_to_zstd() {
+    use datafusion::parquet::basic::Compression;
+
+    let (metadata, rows) =
+        write_one_object(ParquetOptions::default(), vec![json!({"id": 1})]).await;
+    assert_eq!(rows, 1);
+    let codec = metadata.row_group(0).column(0).compression();
+    assert!(matches!(codec, Compression::ZSTD(_)), "{codec:?}");
+}
+
+#[tokio::test]
+async fn test_parquet_compression_and_row_groups_applied() {
+    use datafusion::parquet::basic::Compression;
+
+    let rows = (0..5).map(|i| json!({"id": i, "name": "x"})).collect();
+    let (metadata, total) = write_one_object(
+        ParquetOptions {
+            compression: ParquetCompression::Gzip,
+            compression_level: Some(9),
+            row_group_size: Some(2),
+            data_page_size: Some(1024),
+        },
+        rows,
+    )
+    .await;
+
+    assert_eq!(metadata.num_row_groups(), 3);
+    for group in metadata.row_groups() {
+        for column in group.columns() {
+            assert!(
+                matches!(column.compression(), Compression::GZIP(_)),
+                "{:?}",
+                column.compression()
+            );
+        }
+    }
+    assert_eq!(total, 5);
+}
+
+#[test]
+fn test_writer_properties_rejects_bad_levels() {
+    let zstd = ParquetOptions {
+        compression_level: Some(40),
+        ..Default::default()
+    };
+    assert!(writer_properties(&zstd).is_err());
+
+    let snappy = ParquetOptions {
+        compression: ParquetCompression::Snappy,
+        compression_level: Some(1),
+        ..Default::default()
+    };
+    assert!(writer_properties(&snappy).is_err());
+
+    let none = ParquetOptions {
+        compression: ParquetCompression::None,
+        ..Default::default()
+    };
+    assert!(writer_properties(&none).is_ok());
+}
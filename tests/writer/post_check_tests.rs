@@ -0,0 +1,79 @@
+use apitap::errors::ApitapError;
+use apitap::writer::post_check::{Comparison, Expectation, PostCheck};
+use serde_json::json;
+
+fn check(sql: &str, expect: Option<&str>) -> PostCheck {
+    PostCheck {
+        sql: sql.to_string(),
+        expect: expect.map(|e| e.parse().unwrap()),
+    }
+}
+
+#[test]
+fn test_expectation_parses_each_operator() {
+    let cases = [
+        ("> 0", Comparison::Gt, 0.0),
+        (">=100", Comparison::Ge, 100.0),
+        ("< 2.5", Comparison::Lt, 2.5),
+        ("<= -1", Comparison::Le, -1.0),
+        ("= 0", Comparison::Eq, 0.0),
+        ("== 0", Comparison::Eq, 0.0),
+        ("!= 3", Comparison::Ne, 3.0),
+        ("<> 3", Comparison::Ne, 3.0),
+    ];
+    for (text, op, value) in cases {
+        let expect: Expectation = text.parse().unwrap();
+        assert_eq!((expect.op, expect.value), (op, value), "{text}");
+    }
+    for bad in ["", "> ", "0", "> zero", "~ 1"] {
+        assert!(
+            bad.parse::<Expectation>().is_err(),
+            "{bad:?} should not parse"
+        );
+    }
+}
+
+#[test]
+fn test_post_check_without_expect_requires_no_rows() {
+    let nulls = check("SELECT id FROM {table} WHERE id IS NULL", None);
+    assert!(nulls.verify("users", None).is_ok());
+
+    let err = nulls
+        .verify("users", Some(&json!({"id": null})))
+        .unwrap_err();
+    assert!(matches!(err, ApitapError::PostCheck(_)));
+    let message = err.to_string();
+    assert!(message.contains("post_check failed on users"), "{message}");
+    assert!(
+        message.contains("SELECT id FROM users WHERE id IS NULL"),
+        "{message}"
+    );
+    assert!(message.contains("expected none"), "{message}");
+}
+
+#[test]
+fn test_post_check_compares_single_value() {
+    let count = check("SELECT count(*) FROM {table}", Some("> 0"));
+    assert!(count.verify("users", Some(&json!({"count": 12}))).is_ok());
+    // numeric columns may arrive as strings, booleans count as 0/1
+    assert!(count
+        .verify("users", Some(&json!({"count": "3.5"})))
+        .is_ok());
+    assert!(check("SELECT bool_and(ok) FROM {table}", Some("= 1"))
+        .verify("users", Some(&json!({"bool_and": true})))
+        .is_ok());
+
+    let message = count
+        .verify("users", Some(&json!({"count": 0})))
+        .unwrap_err()
+        .to_string();
+    assert!(message.contains("returned 0 (expected > 0)"), "{message}");
+
+    for row in [
+        None,
+        Some(json!({"count": null})),
+        Some(json!({"a": 1, "b": 2})),
+    ] {
+        assert!(count.verify("users", row.as_ref()).is_err(), "{row:?}");
+    }
+}
@@ -0,0 +1,127 @@
+// Tests for Snowflake Writer
+//
+// These tests cover:
+// - SfType type inference and merging
+// - Schema analysis from JSON values
+// - Generated staging, append, and MERGE statements
+// - SQL API request bodies
+
+use apitap::writer::snowflake::{SfType, SnowflakeClient, SnowflakeWriter};
+use serde_json::json;
+use std::collections::BTreeMap;
+
+fn schema() -> BTreeMap<String, SfType> {
+    SnowflakeWriter::analyze_schema(
+        &[json!({"id": 1, "name": "a", "tags": ["x"], "score": 1.5})],
+        10,
+    )
+    .unwrap()
+}
+
+#[test]
+fn test_sftype_from_json() {
+    assert_eq!(SfType::from_json_value(&json!(null)), SfType::Varchar);
+    assert_eq!(SfType::from_json_value(&json!(true)), SfType::Boolean);
+    assert_eq!(SfType::from_json_value(&json!(42)), SfType::Number);
+    assert_eq!(SfType::from_json_value(&json!(4.2)), SfType::Float);
+    assert_eq!(SfType::from_json_value(&json!({"a": 1})), SfType::Variant);
+}
+
+#[test]
+fn test_sftype_merge() {
+    assert_eq!(SfType::Number.merge(&SfType::Float), SfType::Float);
+    assert_eq!(SfType::Number.merge(&SfType::Boolean), SfType::Varchar);
+    assert_eq!(SfType::Variant.merge(&SfType::Variant), SfType::Variant);
+}
+
+#[test]
+fn test_analyze_schema_merges_samples() {
+    let rows = vec![json!({"id": 1, "v": 1}), json!({"id": 2, "v": 2.5})];
+    let schema = SnowflakeWriter::analyze_schema(&rows, 10).unwrap();
+    assert_eq!(schema["id"], SfType::Number);
+    assert_eq!(schema["v"], SfType::Float);
+}
+
+#[test]
+fn test_create_and_stage_sql() {
+    let sql = SnowflakeWriter::create_table_sql("RAW.API.orders", &schema());
+    assert_eq!(
+        sql,
+        r#"CREATE TABLE IF NOT EXISTS "RAW"."API"."orders" ("id" NUMBER(38,0), "name" VARCHAR, "score" FLOAT, "tags" VARIANT)"#
+    );
+
+    assert_eq!(
+        SnowflakeWriter::stage_insert_sql("orders__APITAP_STAGE_B1", 2),
+        r#"INSERT INTO "orders__APITAP_STAGE_B1" (v) SELECT PARSE_JSON(column1) FROM VALUES (?), (?)"#
+    );
+
+    let stage = SnowflakeWriter::stage_table_name("RAW.API.orders", "B1");
+    assert_eq!(stage, "RAW.API.orders__APITAP_STAGE_B1");
+    assert_eq!(
+        SnowflakeWriter::create_stage_sql(&stage),
+        r#"CREATE TRANSIENT TABLE "RAW"."API"."orders__APITAP_STAGE_B1" (seq NUMBER AUTOINCREMENT, v VARIANT)"#
+    );
+    assert_eq!(
+        SnowflakeWriter::drop_stage_sql(&stage),
+        r#"DROP TABLE IF EXISTS "RAW"."API"."orders__APITAP_STAGE_B1""#
+    );
+
+    assert_eq!(
+        SnowflakeWriter::table_probe_sql("RAW.API.orders"),
+        r#"SELECT 1 FROM "RAW"."API"."orders" LIMIT 0"#
+    );
+}
+
+#[test]
+fn test_append_sql_casts_from_variant() {
+    let sql = SnowflakeWriter::append_sql("orders", "orders__APITAP_STAGE_B1", &schema());
+    assert_eq!(
+        sql,
+        r#"INSERT INTO "orders" ("id", "name", "score", "tags") SELECT v:"id"::NUMBER(38,0), v:"name"::VARCHAR, v:"score"::FLOAT, v:"tags" FROM "orders__APITAP_STAGE_B1" ORDER BY seq"#
+    );
+}
+
+#[test]
+fn test_merge_sql_keys_on_primary_key() {
+    let sql =
+        SnowflakeWriter::merge_sql("orders", "orders__APITAP_STAGE_B1", &schema(), "id").unwrap();
+    assert!(sql.starts_with(r#"MERGE INTO "orders" AS tgt USING (SELECT "#));
+    assert!(sql.contains(r#"PARTITION BY v:"id"::NUMBER(38,0) ORDER BY seq DESC"#));
+    assert!(sql.contains(r#"ON tgt."id" = src."id""#));
+    assert!(sql.contains(r#"UPDATE SET tgt."name" = src."name", tgt."score" = src."score""#));
+    assert!(!sql.contains(r#"tgt."id" = src."id","#));
+    assert!(sql.ends_with(
+        r#"WHEN NOT MATCHED THEN INSERT ("id", "name", "score", "tags") VALUES (src."id", src."name", src."score", src."tags")"#
+    ));
+}
+
+#[test]
+fn test_insert_new_sql_skips_matched_rows() {
+    let sql = SnowflakeWriter::insert_new_sql("orders", "orders__APITAP_STAGE_B1", &schema(), "id")
+        .unwrap();
+    assert!(sql.contains(r#"ON tgt."id" = src."id""#));
+    assert!(!sql.contains("WHEN MATCHED"));
+    assert!(sql.contains("WHEN NOT MATCHED THEN INSERT"));
+}
+
+#[test]
+fn test_merge_sql_requires_known_primary_key() {
+    assert!(
+        SnowflakeWriter::merge_sql("orders", "orders__APITAP_STAGE_B1", &schema(), "missing")
+            .is_err()
+    );
+}
+
+#[test]
+fn test_statement_body_includes_context_and_bindings() {
+    let client = SnowflakeClient::new("acct", "tok", "OAUTH", "WH", "DB", "SCH")
+        .with_role(Some("LOADER".to_string()));
+    let body = client.statement_body("SELECT ?", &["{\"a\":1}".to_string()]);
+
+    assert_eq!(body["warehouse"], "WH");
+    assert_eq!(body["database"], "DB");
+    assert_eq!(body["schema"], "SCH");
+    assert_eq!(body["role"], "LOADER");
+    assert_eq!(body["bindings"]["1"]["type"], "TEXT");
+    assert_eq!(body["bindings"]["1"]["value"], "{\"a\":1}");
+}
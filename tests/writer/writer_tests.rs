@@ -57,11 +57,20 @@ fn test_write_mode_match_patterns() {
     let result = match mode {
         WriteMode::Merge => "merge_operation",
         WriteMode::Append => "append_operation",
+        WriteMode::Insert => "insert_operation",
     };
 
     assert_eq!(result, "merge_operation");
 }
 
+#[test]
+fn test_write_mode_from_str() {
+    assert_eq!("merge".parse::<WriteMode>().unwrap(), WriteMode::Merge);
+    assert_eq!("Append".parse::<WriteMode>().unwrap(), WriteMode::Append);
+    assert_eq!(" insert ".parse::<WriteMode>().unwrap(), WriteMode::Insert);
+    assert!("upsert".parse::<WriteMode>().is_err());
+}
+
 #[test]
 fn test_write_mode_in_vec() {
     let modes = [WriteMode::Merge, WriteMode::Append, WriteMode::Merge];
@@ -93,6 +102,7 @@ fn test_write_mode_as_function_parameter() {
         match mode {
             WriteMode::Merge => "merging",
             WriteMode::Append => "appending",
+            WriteMode::Insert => "inserting",
         }
     }
 